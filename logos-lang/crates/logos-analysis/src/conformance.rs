@@ -0,0 +1,104 @@
+//! Snapshot cases for what the engine answers through [`AnalysisHost`]
+//!
+//! Each [`Case`] is a document with the outline and diagnostics it must
+//! produce, written down in a plain form any embedding can render its
+//! results to. [`run`] answers a case through the host, and the tests
+//! compare that with [`Case::expected`]; the `logos-ffi` tests answer the
+//! same cases through the C ABI. There is no WASM build yet to run
+//! [`CASES`] through its own bindings.
+
+use logos_core::Diagnostic;
+use logos_index::IndexedSymbol;
+
+use crate::AnalysisHost;
+
+/// A document and what analyzing it must give
+pub struct Case {
+    pub name: &'static str,
+    pub uri: &'static str,
+    pub language_id: &'static str,
+    pub text: &'static str,
+    /// Outline in document order, parents before their children, as
+    /// rendered by [`symbol_line`]
+    pub symbols: &'static [&'static str],
+    /// Diagnostics in order, as rendered by [`diagnostic_line`]
+    pub diagnostics: &'static [&'static str],
+}
+
+/// Results of one case, in the rendered form
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Outcome {
+    pub symbols: Vec<String>,
+    pub diagnostics: Vec<String>,
+}
+
+impl Case {
+    pub fn expected(&self) -> Outcome {
+        Outcome {
+            symbols: self.symbols.iter().map(|s| s.to_string()).collect(),
+            diagnostics: self.diagnostics.iter().map(|d| d.to_string()).collect(),
+        }
+    }
+}
+
+pub const CASES: &[Case] = &[
+    Case {
+        name: "python outline",
+        uri: "file:///conformance/greeter.py",
+        language_id: "python",
+        text: "class Greeter:\n    def greet(self):\n        pass\n\ndef main():\n    pass\n",
+        symbols: &["Class Greeter 0:6", "Method greet 1:8", "Function main 4:4"],
+        diagnostics: &[],
+    },
+    Case {
+        name: "rust outline",
+        uri: "file:///conformance/point.rs",
+        language_id: "rust",
+        text: "struct Point { x: i32 }\nfn main() {}\n",
+        symbols: &["Struct Point 0:7", "Field x 0:15", "Function main 1:3"],
+        diagnostics: &[],
+    },
+    Case {
+        name: "json trailing comma",
+        uri: "file:///conformance/config.json",
+        language_id: "json",
+        text: "{\"a\": 1,}",
+        symbols: &["Number a 0:1"],
+        diagnostics: &["0:7 Trailing comma"],
+    },
+];
+
+/// `<kind> <name> <line>:<column>`, at the start of the symbol's name
+pub fn symbol_line(symbol: &IndexedSymbol) -> String {
+    let start = symbol.selection_range.start;
+    format!("{:?} {} {}:{}", symbol.kind, symbol.name, start.line, start.column)
+}
+
+/// `<line>:<column> <message>`, at the start of the diagnostic
+pub fn diagnostic_line(diagnostic: &Diagnostic) -> String {
+    let start = diagnostic.range.start;
+    format!("{}:{} {}", start.line, start.column, diagnostic.message)
+}
+
+/// Answer `case` through a fresh [`AnalysisHost`], as the daemon does
+pub fn run(case: &Case) -> Outcome {
+    let mut host = AnalysisHost::new();
+    host.open_document(case.uri, case.language_id, case.text);
+    let analysis = host.snapshot();
+    Outcome {
+        symbols: analysis.document_symbols(case.uri).iter().map(symbol_line).collect(),
+        diagnostics: analysis.diagnostics(case.uri).iter().map(diagnostic_line).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_host_conforms() {
+        for case in CASES {
+            assert_eq!(run(case), case.expected(), "{}", case.name);
+        }
+    }
+}
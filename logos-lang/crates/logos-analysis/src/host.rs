@@ -0,0 +1,472 @@
+//! Mutable side of the analysis engine
+
+use std::collections::{HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
+
+use logos_core::{Document, Range, Symbol, TextChange};
+use logos_index::{SymbolIndex, TodoIndex, TrigramIndex};
+use logos_parser::conditional::{self, CfgConfig};
+use logos_parser::{lite, pool, LanguageId};
+use tree_sitter::Tree;
+
+use crate::snapshot::AnalysisSnapshot;
+
+/// Open documents and the analysis derived from them
+pub struct AnalysisHost {
+    documents: HashMap<String, Document>,
+    /// Latest parse tree of each open document (absent for oversize files)
+    trees: HashMap<String, Tree>,
+    symbol_index: SymbolIndex,
+    todo_index: TodoIndex,
+    /// Text of every document and workspace file, for text search
+    text_index: TrigramIndex,
+    /// Active defines and features
+    cfg: CfgConfig,
+    /// Regions of each open document that `cfg` compiles out
+    inactive_regions: HashMap<String, Vec<Range>>,
+    /// Languages that are skipped, with the reason, keyed by language id
+    failed_languages: HashMap<String, String>,
+    /// Failures recorded since the last `take_new_failures`
+    new_failures: Vec<(String, String)>,
+    /// Files indexed without being opened, e.g. by a workspace scan
+    workspace_files: HashSet<String>,
+}
+
+impl AnalysisHost {
+    pub fn new() -> Self {
+        Self {
+            documents: HashMap::new(),
+            trees: HashMap::new(),
+            symbol_index: SymbolIndex::new(),
+            todo_index: TodoIndex::new(),
+            text_index: TrigramIndex::new(),
+            cfg: CfgConfig::default(),
+            inactive_regions: HashMap::new(),
+            failed_languages: HashMap::new(),
+            new_failures: Vec::new(),
+            workspace_files: HashSet::new(),
+        }
+    }
+
+    /// Read-only view of the current analysis
+    pub fn snapshot(&self) -> AnalysisSnapshot<'_> {
+        AnalysisSnapshot::new(
+            &self.documents,
+            &self.trees,
+            &self.symbol_index,
+            &self.todo_index,
+            &self.text_index,
+            &self.inactive_regions,
+        )
+    }
+
+    /// Change the active defines and features, re-analyzing open documents
+    /// when they differ. Symbols in regions they compile out are dropped.
+    pub fn set_cfg(&mut self, cfg: CfgConfig) {
+        if self.cfg == cfg {
+            return;
+        }
+        self.cfg = cfg;
+        let uris: Vec<String> = self.documents.keys().cloned().collect();
+        for uri in uris {
+            self.analyze(&uri, None);
+        }
+    }
+
+    pub fn cfg(&self) -> &CfgConfig {
+        &self.cfg
+    }
+
+    /// Open a document, or replace one with the same URI
+    /// A language id the parser doesn't know (`plaintext` for a `Makefile`
+    /// or a shebanged script) is replaced by the one detected from the file
+    /// name and content, when there is one.
+    pub fn open_document(&mut self, uri: impl Into<String>, language_id: impl Into<String>, text: impl Into<String>) {
+        let uri = uri.into();
+        let text = text.into();
+        let mut language_id = language_id.into();
+        if LanguageId::from_str(&language_id).is_none() {
+            let file_name = uri.rsplit('/').next().unwrap_or_default();
+            if let Some(lang) = LanguageId::detect(file_name, &text) {
+                language_id = lang.as_str().to_string();
+            }
+        }
+        let doc = Document::new(uri.clone(), language_id, text);
+        self.documents.insert(uri.clone(), doc);
+        self.analyze(&uri, None);
+    }
+
+    /// Replace the full text of an open document. Unknown URIs are ignored.
+    pub fn change_document(&mut self, uri: &str, text: impl Into<String>) {
+        let Some(doc) = self.documents.get_mut(uri) else {
+            return;
+        };
+        doc.set_content(text.into());
+        self.analyze(uri, None);
+    }
+
+    /// Apply edits to an open document, in order. The previous parse tree
+    /// is edited along and reused, so only the changed region is reparsed.
+    /// Unknown URIs are ignored.
+    pub fn edit_document(&mut self, uri: &str, changes: &[TextChange]) {
+        let Some(doc) = self.documents.get_mut(uri) else {
+            return;
+        };
+        let mut tree = self.trees.remove(uri);
+        logos_parser::apply_changes(doc, &mut tree, changes);
+        self.analyze(uri, tree);
+    }
+
+    /// Close a document and drop its analysis. A file of the workspace
+    /// keeps its symbols and TODOs as of its last content, until it is
+    /// indexed again.
+    pub fn close_document(&mut self, uri: &str) {
+        self.documents.remove(uri);
+        self.trees.remove(uri);
+        self.inactive_regions.remove(uri);
+        if !self.workspace_files.contains(uri) {
+            self.symbol_index.remove_document(uri);
+            self.todo_index.remove_document(uri);
+            self.text_index.remove_document(uri);
+        }
+    }
+
+    /// Index the symbols, TODOs and text of a file that isn't open, such as one
+    /// found by a workspace scan, so workspace queries cover it. The
+    /// language is detected from the file name and content. An open
+    /// document keeps the analysis of its editor content.
+    pub fn index_file(&mut self, uri: impl Into<String>, text: &str) {
+        let uri = uri.into();
+        if self.documents.contains_key(&uri) {
+            self.workspace_files.insert(uri);
+            return;
+        }
+        self.todo_index.index_document(&uri, text);
+        self.text_index.index_document(&uri, text);
+        let file_name = uri.rsplit('/').next().unwrap_or_default();
+        let lang = LanguageId::detect(file_name, text).filter(|lang| !self.failed_languages.contains_key(lang.as_str()));
+        if let Some(lang) = lang {
+            match self.extract_symbols(lang, text, None) {
+                Ok((mut symbols, tree)) => {
+                    if let Some(tree) = tree {
+                        let regions = conditional::inactive_regions(lang, &tree, text, &self.cfg);
+                        conditional::remove_inactive_symbols(&mut symbols, &regions);
+                    }
+                    self.symbol_index.index_document(&uri, &symbols);
+                    if logos_index::is_generated(&uri, text) {
+                        self.symbol_index.mark_generated(&uri);
+                    }
+                }
+                Err(reason) => {
+                    self.symbol_index.remove_document(&uri);
+                    self.mark_language_failed(lang.as_str(), reason);
+                }
+            }
+        }
+        self.workspace_files.insert(uri);
+    }
+
+    /// Whether `uri` was indexed with [`index_file`](Self::index_file)
+    pub fn is_workspace_file(&self, uri: &str) -> bool {
+        self.workspace_files.contains(uri)
+    }
+
+    /// URIs of the files indexed with [`index_file`](Self::index_file)
+    pub fn workspace_files(&self) -> impl Iterator<Item = &str> {
+        self.workspace_files.iter().map(String::as_str)
+    }
+
+    /// Drop a file indexed with [`index_file`](Self::index_file), e.g.
+    /// because it was deleted. Open documents are left alone.
+    pub fn forget_file(&mut self, uri: &str) {
+        self.workspace_files.remove(uri);
+        if !self.documents.contains_key(uri) {
+            self.symbol_index.remove_document(uri);
+            self.todo_index.remove_document(uri);
+            self.text_index.remove_document(uri);
+        }
+    }
+
+    /// Re-key everything stored for a renamed file or directory. Returns the
+    /// number of indexed documents and of documents with TODOs that moved.
+    pub fn rename_files(&mut self, from: &str, to: &str) -> (usize, usize) {
+        let moved = self.symbol_index.rename_documents(from, to);
+        let todos = self.todo_index.rename_documents(from, to);
+        self.text_index.rename_documents(from, to);
+
+        let indexed: Vec<(String, String)> = self
+            .workspace_files
+            .iter()
+            .filter_map(|uri| logos_index::rebase_uri(uri, from, to).map(|new| (uri.clone(), new)))
+            .collect();
+        for (old, new) in indexed {
+            self.workspace_files.remove(&old);
+            self.workspace_files.insert(new);
+        }
+
+        let open: Vec<(String, String)> = self
+            .documents
+            .keys()
+            .filter_map(|uri| logos_index::rebase_uri(uri, from, to).map(|new| (uri.clone(), new)))
+            .collect();
+        for (old, new) in open {
+            if let Some(mut doc) = self.documents.remove(&old) {
+                doc.uri = new.clone();
+                self.documents.insert(new.clone(), doc);
+            }
+            if let Some(tree) = self.trees.remove(&old) {
+                self.trees.insert(new.clone(), tree);
+            }
+            if let Some(regions) = self.inactive_regions.remove(&old) {
+                self.inactive_regions.insert(new, regions);
+            }
+        }
+        (moved.len(), todos)
+    }
+
+    /// Stop analyzing a language, e.g. because another component found its
+    /// grammar broken. Its documents keep their text but get no symbols.
+    pub fn mark_language_failed(&mut self, language: &str, reason: String) {
+        if self.failed_languages.contains_key(language) {
+            return;
+        }
+        self.failed_languages.insert(language.to_string(), reason.clone());
+        self.new_failures.push((language.to_string(), reason));
+    }
+
+    /// Reason a language is skipped, if it is
+    pub fn language_failure(&self, language: &str) -> Option<&str> {
+        self.failed_languages.get(language).map(String::as_str)
+    }
+
+    /// Languages that failed since the last call, with the reason, so an
+    /// embedder can report each one once
+    pub fn take_new_failures(&mut self) -> Vec<(String, String)> {
+        std::mem::take(&mut self.new_failures)
+    }
+
+    /// Refresh the TODOs, symbols and tree of an open document. `old_tree`
+    /// is the previous tree, already edited to match the current content.
+    fn analyze(&mut self, uri: &str, old_tree: Option<Tree>) {
+        let Some(doc) = self.documents.get(uri) else {
+            return;
+        };
+        let content = doc.content().to_string();
+        let lang = LanguageId::from_str(&doc.language_id);
+        self.todo_index.index_document(uri, &content);
+        self.text_index.index_document(uri, &content);
+
+        self.trees.remove(uri);
+        self.inactive_regions.remove(uri);
+        let Some(lang) = lang else {
+            return;
+        };
+        if self.failed_languages.contains_key(lang.as_str()) {
+            return;
+        }
+
+        match self.extract_symbols(lang, &content, old_tree.as_ref()) {
+            Ok((mut symbols, tree)) => {
+                if let Some(tree) = tree {
+                    let regions = conditional::inactive_regions(lang, &tree, &content, &self.cfg);
+                    conditional::remove_inactive_symbols(&mut symbols, &regions);
+                    if !regions.is_empty() {
+                        self.inactive_regions.insert(uri.to_string(), regions);
+                    }
+                    self.trees.insert(uri.to_string(), tree);
+                }
+                self.symbol_index.index_document(uri, &symbols);
+                if logos_index::is_generated(uri, &content) {
+                    self.symbol_index.mark_generated(uri);
+                }
+            }
+            Err(reason) => {
+                self.symbol_index.remove_document(uri);
+                self.mark_language_failed(lang.as_str(), reason);
+            }
+        }
+    }
+
+    fn extract_symbols(
+        &mut self,
+        lang: LanguageId,
+        content: &str,
+        old_tree: Option<&Tree>,
+    ) -> Result<(Vec<Symbol>, Option<Tree>), String> {
+        if lite::exceeds_parse_limit(content) {
+            return Ok((lite::guess_symbols(lang, content), None));
+        }
+        let parsed = pool::shared()
+            .checkout(lang)
+            .map_err(|e| format!("Grammar failed to load: {}", e))?
+            .parse(content, old_tree);
+        let tree = match parsed {
+            Ok(tree) => tree,
+            Err(e) => {
+                // A single unparseable document doesn't disable the language
+                log::warn!("Failed to parse {} document: {}", lang.as_str(), e);
+                return Ok((Vec::new(), None));
+            }
+        };
+        let mut symbols = panic::catch_unwind(AssertUnwindSafe(|| logos_parser::extract_symbols(lang, &tree, content)))
+            .map_err(|_| "Symbol extraction crashed".to_string())?;
+        self.extract_injected_symbols(lang, &tree, content, &mut symbols);
+        Ok((symbols, Some(tree)))
+    }
+
+    /// Add the symbols of embedded regions (e.g. `<script>` in HTML, a
+    /// `language=sql` string) that are written in a supported language
+    fn extract_injected_symbols(&mut self, lang: LanguageId, tree: &Tree, content: &str, symbols: &mut Vec<Symbol>) {
+        // Code blocks in Markdown are examples, not declarations of the
+        // document; they'd crowd the heading outline and workspace search
+        if lang == LanguageId::Markdown {
+            return;
+        }
+        for injection in logos_parser::injections(lang, tree, content) {
+            let Some(embedded) = injection.language_id() else {
+                continue;
+            };
+            if self.failed_languages.contains_key(embedded.as_str()) {
+                continue;
+            }
+            match self.extract_symbols(embedded, &injection.content(content), None) {
+                Ok((mut injected, _)) => {
+                    logos_parser::offset_symbols(&mut injected, injection.range.start);
+                    symbols.extend(injected);
+                }
+                Err(reason) => self.mark_language_failed(embedded.as_str(), reason),
+            }
+        }
+    }
+}
+
+impl Default for AnalysisHost {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logos_core::Position;
+    use logos_index::{NameMatch, ReplaceOptions};
+
+    #[test]
+    fn test_edits_are_reflected_in_new_snapshots() {
+        let mut host = AnalysisHost::new();
+        host.open_document("file:///a.py", "python", "def first():\n    pass\n");
+        host.open_document("file:///b.py", "python", "# TODO: split\ndef second():\n    first()\n");
+
+        let analysis = host.snapshot();
+        assert_eq!(analysis.workspace_symbols("first", NameMatch::Normalized).len(), 1);
+        assert_eq!(analysis.todos("file:///b.py").len(), 1);
+        assert!(analysis.tree("file:///a.py").is_some());
+
+        host.change_document("file:///a.py", "def renamed():\n    pass\n");
+        host.close_document("file:///b.py");
+
+        let analysis = host.snapshot();
+        assert!(analysis.workspace_symbols("first", NameMatch::Normalized).is_empty());
+        let symbol = analysis.symbol_at("file:///a.py", Position::new(0, 5)).unwrap();
+        assert_eq!(symbol.name, "renamed");
+        assert!(analysis.document("file:///b.py").is_none());
+        assert!(analysis.todos("file:///b.py").is_empty());
+    }
+
+    #[test]
+    fn test_edits_reuse_the_tree() {
+        let mut host = AnalysisHost::new();
+        host.open_document("file:///a.py", "python", "def first():\n    pass\n");
+        host.edit_document(
+            "file:///a.py",
+            &[
+                TextChange { range: Some(Range::from_coords(0, 4, 0, 9)), text: "renamed".to_string() },
+                TextChange { range: Some(Range::from_coords(2, 0, 2, 0)), text: "def second():\n    pass\n".to_string() },
+            ],
+        );
+
+        let analysis = host.snapshot();
+        assert_eq!(analysis.document("file:///a.py").unwrap().content(), "def renamed():\n    pass\ndef second():\n    pass\n");
+        let names: Vec<_> = analysis.document_symbols("file:///a.py").iter().map(|s| s.name.clone()).collect();
+        assert_eq!(names, vec!["renamed", "second"]);
+        assert!(!analysis.tree("file:///a.py").unwrap().root_node().has_error());
+    }
+
+    #[test]
+    fn test_workspace_files_outlive_their_documents() {
+        let mut host = AnalysisHost::new();
+        host.index_file("file:///src/a.py", "# TODO: test\ndef first():\n    pass\n");
+        host.index_file("file:///src/notes.txt", "TODO: nothing to outline\n");
+
+        let analysis = host.snapshot();
+        assert!(analysis.document("file:///src/a.py").is_none());
+        assert_eq!(analysis.workspace_symbols("first", NameMatch::Normalized).len(), 1);
+        assert_eq!(analysis.todos("file:///src/a.py").len(), 1);
+        assert_eq!(analysis.todos("file:///src/notes.txt").len(), 1);
+
+        // The editor content wins while the file is open, and the file
+        // stays indexed after closing
+        host.open_document("file:///src/a.py", "python", "def second():\n    pass\n");
+        host.index_file("file:///src/a.py", "def first():\n    pass\n");
+        assert_eq!(host.snapshot().workspace_symbols("second", NameMatch::Normalized).len(), 1);
+        host.close_document("file:///src/a.py");
+        assert_eq!(host.snapshot().workspace_symbols("second", NameMatch::Normalized).len(), 1);
+
+        host.forget_file("file:///src/a.py");
+        assert!(host.snapshot().document_symbols("file:///src/a.py").is_empty());
+    }
+
+    #[test]
+    fn test_text_search_follows_documents_and_files() {
+        let mut host = AnalysisHost::new();
+        let options = ReplaceOptions::default();
+        host.index_file("file:///src/a.py", "RETRIES = 3\n");
+        host.index_file("file:///src/notes.txt", "raise RETRIES before release\n");
+        assert_eq!(host.snapshot().text_search("RETRIES", &options).unwrap().files.len(), 2);
+
+        // The editor content wins while the file is open
+        host.open_document("file:///src/a.py", "python", "ATTEMPTS = 3\n");
+        let result = host.snapshot().text_search("RETRIES", &options).unwrap();
+        assert_eq!(result.files[0].uri, "file:///src/notes.txt");
+        assert_eq!(result.files.len(), 1);
+
+        host.forget_file("file:///src/notes.txt");
+        assert!(host.snapshot().text_search("RETRIES", &options).unwrap().files.is_empty());
+        assert_eq!(host.snapshot().text_search("ATTEMPTS", &options).unwrap().match_count(), 1);
+    }
+
+    #[test]
+    fn test_failed_language_is_skipped() {
+        let mut host = AnalysisHost::new();
+        host.mark_language_failed("python", "grammar missing".to_string());
+        host.open_document("file:///a.py", "python", "def first():\n    pass\n");
+
+        assert!(host.snapshot().document_symbols("file:///a.py").is_empty());
+        assert_eq!(host.language_failure("python"), Some("grammar missing"));
+        assert_eq!(host.take_new_failures(), vec![("python".to_string(), "grammar missing".to_string())]);
+        assert!(host.take_new_failures().is_empty());
+    }
+
+    #[test]
+    fn test_cfg_drops_inactive_symbols() {
+        let mut host = AnalysisHost::new();
+        host.open_document("file:///a.c", "c", "#ifdef FAST
+int fast(void);
+#else
+int slow(void);
+#endif
+");
+        // With no defines known, both branches stay live
+        assert_eq!(host.snapshot().document_symbols("file:///a.c").len(), 2);
+
+        host.set_cfg(CfgConfig { defines: Some(HashMap::from([("FAST".to_string(), None)])), features: None });
+        let analysis = host.snapshot();
+        let names: Vec<_> = analysis.document_symbols("file:///a.c").iter().map(|s| s.name.clone()).collect();
+        assert_eq!(names, vec!["fast"]);
+        assert_eq!(analysis.inactive_regions("file:///a.c").len(), 1);
+        let diagnostics = analysis.diagnostics("file:///a.c");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].tags, vec![logos_core::DiagnosticTag::Unnecessary]);
+    }
+}
@@ -0,0 +1,28 @@
+//! Logos Analysis - Embeddable analysis engine
+//!
+//! [`AnalysisHost`] owns the open documents and everything derived from
+//! them: parse trees and the symbol and TODO indexes. Edits go through the
+//! host; queries go through an [`AnalysisSnapshot`], a read-only view that
+//! stays consistent for as long as it is held. The language service daemon
+//! is built on this API, and other Rust tools can use it directly without
+//! speaking JSON-RPC.
+//!
+//! ```
+//! use logos_analysis::AnalysisHost;
+//!
+//! let mut host = AnalysisHost::new();
+//! host.open_document("file:///main.py", "python", "def main():\n    pass\n");
+//!
+//! let analysis = host.snapshot();
+//! let names: Vec<_> = analysis.document_symbols("file:///main.py").iter().map(|s| &s.name).collect();
+//! assert_eq!(names, ["main"]);
+//! ```
+
+pub mod conformance;
+pub mod host;
+pub mod outline;
+pub mod snapshot;
+
+pub use host::AnalysisHost;
+pub use outline::{Outline, OutlineEntry, OutlineOptions, Section};
+pub use snapshot::AnalysisSnapshot;
@@ -0,0 +1,275 @@
+//! Summarized outlines of very large documents
+//!
+//! The symbols of a document are indexed flat, parents before their
+//! children. [`Outline`] nests them again by range and hands out at most a
+//! set number of them, breadth first, so the top of the tree is always
+//! there. The children left out of a list are replaced by one
+//! [`OutlineEntry::More`] placeholder naming the [`Section`] they make up,
+//! which [`Outline::page`] expands later. Leaf fields, variables and
+//! constants can be collapsed into a placeholder of their own, as they are
+//! rarely what a large outline is browsed for.
+
+use std::collections::VecDeque;
+
+use logos_core::{Range, SymbolKind};
+use logos_index::IndexedSymbol;
+
+/// How much of an outline to hand out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutlineOptions {
+    /// Symbols per response, placeholders not counted
+    pub max_symbols: usize,
+    /// Put the leaf fields, variables and constants of each list behind
+    /// one placeholder
+    pub collapse_trivial: bool,
+}
+
+/// Symbols of one list left out of a response: the children of `parent`
+/// (a position in the flat symbol list, `None` for the top level) from
+/// the `start`-th on, either the trivial ones or all the others
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Section {
+    pub parent: Option<usize>,
+    pub start: usize,
+    pub trivial: bool,
+}
+
+/// A node of a summarized outline
+#[derive(Debug, Clone)]
+pub enum OutlineEntry<'a> {
+    Symbol {
+        symbol: &'a IndexedSymbol,
+        children: Vec<OutlineEntry<'a>>,
+    },
+    /// `count` symbols left out, spanning `range`
+    More {
+        section: Section,
+        count: usize,
+        range: Range,
+        selection_range: Range,
+    },
+}
+
+impl OutlineEntry<'_> {
+    fn range(&self) -> Range {
+        match self {
+            Self::Symbol { symbol, .. } => symbol.range,
+            Self::More { range, .. } => *range,
+        }
+    }
+}
+
+/// The symbols of a document as a tree
+pub struct Outline<'a> {
+    symbols: &'a [IndexedSymbol],
+    roots: Vec<usize>,
+    children: Vec<Vec<usize>>,
+    options: OutlineOptions,
+}
+
+impl<'a> Outline<'a> {
+    /// Nest `symbols`, given in document order with parents before their
+    /// children, by range
+    pub fn new(symbols: &'a [IndexedSymbol], options: OutlineOptions) -> Self {
+        let mut roots = Vec::new();
+        let mut children = vec![Vec::new(); symbols.len()];
+        let mut open: Vec<usize> = Vec::new();
+        for (index, symbol) in symbols.iter().enumerate() {
+            while open.last().is_some_and(|&parent| !encloses(symbols[parent].range, symbol.range)) {
+                open.pop();
+            }
+            match open.last() {
+                Some(&parent) => children[parent].push(index),
+                None => roots.push(index),
+            }
+            open.push(index);
+        }
+        Self { symbols, roots, children, options }
+    }
+
+    /// Whether the document has more symbols than a response may carry
+    pub fn is_over_budget(&self) -> bool {
+        self.options.max_symbols > 0 && self.symbols.len() > self.options.max_symbols
+    }
+
+    /// The top of the outline, within the budget
+    pub fn summary(&self) -> Vec<OutlineEntry<'a>> {
+        let included = self.budget(&self.list(None, false));
+        self.render_children(None, &included)
+    }
+
+    /// The symbols of a section left out earlier, within the budget;
+    /// `None` when the section doesn't exist in this outline
+    pub fn page(&self, section: Section) -> Option<Vec<OutlineEntry<'a>>> {
+        let list = self.list(section.parent, section.trivial);
+        let first = list.get(section.start..).filter(|first| !first.is_empty())?;
+        let included = self.budget(first);
+        Some(self.render(section, &included))
+    }
+
+    /// Children of `parent`, the trivial ones or the others
+    fn list(&self, parent: Option<usize>, trivial: bool) -> Vec<usize> {
+        let all = match parent {
+            Some(parent) => match self.children.get(parent) {
+                Some(children) => children,
+                None => return Vec::new(),
+            },
+            None => &self.roots,
+        };
+        let trivial_ones: Vec<usize> = if self.options.collapse_trivial {
+            all.iter().copied().filter(|&index| self.is_trivial(index)).collect()
+        } else {
+            Vec::new()
+        };
+        // A single trivial symbol stays where it is
+        let collapsed = trivial_ones.len() > 1;
+        if trivial {
+            return if collapsed { trivial_ones } else { Vec::new() };
+        }
+        all.iter().copied().filter(|&index| !collapsed || !self.is_trivial(index)).collect()
+    }
+
+    fn is_trivial(&self, index: usize) -> bool {
+        self.children[index].is_empty()
+            && matches!(
+                self.symbols[index].kind,
+                SymbolKind::Field
+                    | SymbolKind::Property
+                    | SymbolKind::Variable
+                    | SymbolKind::Constant
+                    | SymbolKind::EnumMember
+            )
+    }
+
+    /// Symbols handed out, breadth first from `first`, up to the budget.
+    /// Collapsed trivial symbols are only handed out as a page of their own.
+    fn budget(&self, first: &[usize]) -> Vec<bool> {
+        let mut included = vec![false; self.symbols.len()];
+        let mut left = if self.options.max_symbols == 0 { usize::MAX } else { self.options.max_symbols };
+        let mut queue: VecDeque<usize> = first.iter().copied().collect();
+        while let Some(index) = queue.pop_front() {
+            if left == 0 {
+                break;
+            }
+            included[index] = true;
+            left -= 1;
+            queue.extend(self.list(Some(index), false));
+        }
+        included
+    }
+
+    fn render(&self, section: Section, included: &[bool]) -> Vec<OutlineEntry<'a>> {
+        let list = self.list(section.parent, section.trivial);
+        let list = &list[section.start..];
+        // Breadth first, the included symbols of a list are a prefix of it
+        let shown = list.iter().take_while(|&&index| included[index]).count();
+
+        let mut entries: Vec<OutlineEntry<'a>> = list[..shown]
+            .iter()
+            .map(|&index| OutlineEntry::Symbol {
+                symbol: &self.symbols[index],
+                children: self.render_children(Some(index), included),
+            })
+            .collect();
+        if shown < list.len() {
+            entries.push(self.more(Section { start: section.start + shown, ..section }, &list[shown..]));
+        }
+        entries.sort_by_key(OutlineEntry::range);
+        entries
+    }
+
+    /// Children of `parent`, with placeholders for those left out and for
+    /// the collapsed trivial ones
+    fn render_children(&self, parent: Option<usize>, included: &[bool]) -> Vec<OutlineEntry<'a>> {
+        let mut entries = self.render(Section { parent, start: 0, trivial: false }, included);
+        entries.extend(self.render(Section { parent, start: 0, trivial: true }, included));
+        entries.sort_by_key(OutlineEntry::range);
+        entries
+    }
+
+    fn more(&self, section: Section, hidden: &[usize]) -> OutlineEntry<'a> {
+        let first = &self.symbols[hidden[0]];
+        let last = &self.symbols[hidden[hidden.len() - 1]];
+        OutlineEntry::More {
+            section,
+            count: hidden.len(),
+            range: Range::new(first.range.start, last.range.end),
+            selection_range: first.selection_range,
+        }
+    }
+}
+
+/// Whether `outer` contains `inner`; equal ranges nest too, so a
+/// declaration and its single member don't end up side by side
+fn encloses(outer: Range, inner: Range) -> bool {
+    outer.start <= inner.start && inner.end <= outer.end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AnalysisHost;
+
+    const URI: &str = "file:///big.py";
+
+    fn names(entries: &[OutlineEntry]) -> Vec<String> {
+        entries
+            .iter()
+            .map(|entry| match entry {
+                OutlineEntry::Symbol { symbol, children } if children.is_empty() => symbol.name.clone(),
+                OutlineEntry::Symbol { symbol, children } => format!("{}({})", symbol.name, names(children).join(" ")),
+                OutlineEntry::More { count, section, .. } => {
+                    format!("+{}{}", count, if section.trivial { "t" } else { "" })
+                }
+            })
+            .collect()
+    }
+
+    fn host(text: &str) -> AnalysisHost {
+        let mut host = AnalysisHost::new();
+        host.open_document(URI, "python", text);
+        host
+    }
+
+    #[test]
+    fn test_budget_keeps_the_top_of_the_tree() {
+        let host = host("class A:\n    def a1(self):\n        pass\n    def a2(self):\n        pass\n\nclass B:\n    def b1(self):\n        pass\n\ndef f():\n    pass\n");
+        let analysis = host.snapshot();
+        let options = OutlineOptions { max_symbols: 4, collapse_trivial: false };
+        let outline = Outline::new(analysis.document_symbols(URI), options);
+
+        assert!(outline.is_over_budget());
+        assert_eq!(names(&outline.summary()), vec!["A(a1 +1)", "B(+1)", "f"]);
+    }
+
+    #[test]
+    fn test_page_expands_a_section() {
+        let host = host("def f1():\n    pass\n\ndef f2():\n    pass\n\ndef f3():\n    pass\n");
+        let analysis = host.snapshot();
+        let options = OutlineOptions { max_symbols: 1, collapse_trivial: false };
+        let outline = Outline::new(analysis.document_symbols(URI), options);
+
+        let summary = outline.summary();
+        assert_eq!(names(&summary), vec!["f1", "+2"]);
+        let OutlineEntry::More { section, .. } = summary[1] else {
+            panic!("expected a placeholder");
+        };
+        assert_eq!(names(&outline.page(section).unwrap()), vec!["f2", "+1"]);
+        assert!(outline.page(Section { parent: None, start: 3, trivial: false }).is_none());
+    }
+
+    #[test]
+    fn test_trivial_symbols_are_collapsed() {
+        let host = host("X = 1\nY = 2\n\ndef f():\n    pass\n");
+        let analysis = host.snapshot();
+        let options = OutlineOptions { max_symbols: 10, collapse_trivial: true };
+        let outline = Outline::new(analysis.document_symbols(URI), options);
+
+        let summary = outline.summary();
+        assert_eq!(names(&summary), vec!["+2t", "f"]);
+        let OutlineEntry::More { section, .. } = summary[0] else {
+            panic!("expected a placeholder");
+        };
+        assert_eq!(names(&outline.page(section).unwrap()), vec!["X", "Y"]);
+    }
+}
@@ -0,0 +1,404 @@
+//! Read-only side of the analysis engine
+
+use std::collections::HashMap;
+
+use logos_core::{Diagnostic, DiagnosticTag, Document, Location, Position, Range, SymbolKind};
+use logos_index::{
+    IndexedSymbol, MergedSymbol, NameMatch, ReplaceOptions, SymbolIndex, TextSearchResult, TodoIndex, TodoItem,
+    TrigramIndex,
+};
+use logos_parser::css::{self, ColorInformation};
+use logos_parser::folding::{self, FoldingRange};
+use logos_parser::suppression::Suppressions;
+use logos_parser::{pool, LanguageId, Utf16Columns};
+use logos_refactor::{RefactorAction, RefactorContext, RefactorEngine, RefactorError, RefactorResult};
+use tree_sitter::Tree;
+
+use crate::outline::{Outline, OutlineOptions};
+
+/// Embedded languages whose syntax errors are reported
+const CHECKED_INJECTIONS: &[LanguageId] = &[LanguageId::Json, LanguageId::Yaml, LanguageId::Toml, LanguageId::Sql];
+
+/// A consistent view of the analysis at one point in time.
+///
+/// Snapshots borrow from their [`AnalysisHost`](crate::AnalysisHost), so the
+/// host can't be edited while one is alive; take a new snapshot after each
+/// batch of edits.
+#[derive(Clone, Copy)]
+pub struct AnalysisSnapshot<'a> {
+    documents: &'a HashMap<String, Document>,
+    trees: &'a HashMap<String, Tree>,
+    symbol_index: &'a SymbolIndex,
+    todo_index: &'a TodoIndex,
+    text_index: &'a TrigramIndex,
+    inactive_regions: &'a HashMap<String, Vec<Range>>,
+}
+
+impl<'a> AnalysisSnapshot<'a> {
+    pub(crate) fn new(
+        documents: &'a HashMap<String, Document>,
+        trees: &'a HashMap<String, Tree>,
+        symbol_index: &'a SymbolIndex,
+        todo_index: &'a TodoIndex,
+        text_index: &'a TrigramIndex,
+        inactive_regions: &'a HashMap<String, Vec<Range>>,
+    ) -> Self {
+        Self { documents, trees, symbol_index, todo_index, text_index, inactive_regions }
+    }
+
+    /// An open document
+    pub fn document(&self, uri: &str) -> Option<&'a Document> {
+        self.documents.get(uri)
+    }
+
+    /// All open documents, by URI
+    pub fn documents(&self) -> impl Iterator<Item = (&'a String, &'a Document)> {
+        self.documents.iter()
+    }
+
+    /// Latest parse tree of an open document
+    pub fn tree(&self, uri: &str) -> Option<&'a Tree> {
+        self.trees.get(uri)
+    }
+
+    /// Language of an open document, when it is a supported one
+    pub fn language(&self, uri: &str) -> Option<LanguageId> {
+        LanguageId::from_str(&self.document(uri)?.language_id)
+    }
+
+    /// Language at `position` of an open document: that of the embedded
+    /// region around it (a `language=sql` string, a `<script>` block) when
+    /// this build supports it, the document's otherwise
+    pub fn language_at(&self, uri: &str, position: Position) -> Option<LanguageId> {
+        let lang = self.language(uri)?;
+        let (Some(doc), Some(tree)) = (self.document(uri), self.tree(uri)) else {
+            return Some(lang);
+        };
+        // Injection ranges are in tree-sitter columns; the end of a region
+        // is where completion happens, so it counts
+        let point = Utf16Columns::new(doc.content()).point(position);
+        let position = Position::new(point.row as u32, point.column as u32);
+        logos_parser::injections(lang, tree, doc.content())
+            .into_iter()
+            .filter(|injection| injection.range.start <= position && position <= injection.range.end)
+            .find_map(|injection| injection.language_id())
+            .or(Some(lang))
+    }
+
+    /// Symbols of all open documents
+    pub fn symbol_index(&self) -> &'a SymbolIndex {
+        self.symbol_index
+    }
+
+    /// TODO comments of all open documents
+    pub fn todo_index(&self) -> &'a TodoIndex {
+        self.todo_index
+    }
+
+    /// Symbols declared in a document, in source order
+    pub fn document_symbols(&self, uri: &str) -> &'a [IndexedSymbol] {
+        self.symbol_index.get_document_symbols(uri)
+    }
+
+    /// Symbols of a document as a tree, to hand out a summary of
+    pub fn outline(&self, uri: &str, options: OutlineOptions) -> Outline<'a> {
+        Outline::new(self.document_symbols(uri), options)
+    }
+
+    /// Symbols matching a query across open documents, with declarations
+    /// of the same symbol merged
+    pub fn workspace_symbols(&self, query: &str, matching: NameMatch) -> Vec<MergedSymbol<'a>> {
+        self.symbol_index.search_merged(query, matching)
+    }
+
+    /// Matches of a literal or regex query in the text of open documents
+    /// and workspace files, found through a trigram index
+    pub fn text_search(&self, query: &str, options: &ReplaceOptions) -> Result<TextSearchResult, String> {
+        self.text_index.search(query, options)
+    }
+
+    /// The innermost symbol declared at a position
+    pub fn symbol_at(&self, uri: &str, position: Position) -> Option<&'a IndexedSymbol> {
+        self.symbol_index.find_at_position(uri, position)
+    }
+
+    /// The symbol whose name is at a position; `None` inside a body, away
+    /// from any name
+    pub fn declaration_at(&self, uri: &str, position: Position) -> Option<&'a IndexedSymbol> {
+        self.symbol_index.find_declared_at(uri, position)
+    }
+
+    /// Every declaration sharing the name of the symbol at a position, one
+    /// per location, in location order
+    pub fn references(&self, uri: &str, position: Position) -> Vec<Location> {
+        let Some(symbol) = self.symbol_at(uri, position) else {
+            return Vec::new();
+        };
+        logos_index::dedup_locations(
+            self.symbol_index
+                .find_named(&symbol.name)
+                .iter()
+                .map(|s| Location::new(s.uri.clone(), s.selection_range))
+                .collect(),
+        )
+    }
+
+    /// Class name at `position`: one of the classes of an HTML `class`
+    /// attribute, or a class selector of a CSS rule
+    pub fn class_at(&self, uri: &str, position: Position) -> Option<String> {
+        let doc = self.document(uri)?;
+        // HTML classes carry their tag name as detail; CSS rules have none
+        self.document_symbols(uri)
+            .iter()
+            .filter(|symbol| symbol.kind == SymbolKind::Class && symbol.selection_range.contains(position))
+            .find_map(|symbol| {
+                if symbol.detail.is_some() {
+                    return symbol.name.strip_prefix('.').map(str::to_string);
+                }
+                let selector = doc.text_in_range(symbol.selection_range)?;
+                let offset = doc.offset_at(position)? - doc.offset_at(symbol.selection_range.start)?;
+                css::selector_classes(selector)
+                    .into_iter()
+                    .find(|(start, name)| *start <= offset + 1 && offset <= start + name.len())
+                    .map(|(_, name)| name.to_string())
+            })
+    }
+
+    /// CSS rules using a class in their selectors, across open stylesheets
+    /// and style blocks, in location order
+    pub fn class_rules(&self, class: &str) -> Vec<Location> {
+        let rules = self
+            .symbol_index
+            .documents()
+            .flat_map(|uri| self.document_symbols(uri))
+            .filter(|symbol| symbol.kind == SymbolKind::Class && symbol.detail.is_none())
+            .filter(|symbol| css::selector_classes(&symbol.name).iter().any(|(_, name)| *name == class))
+            .map(|symbol| Location::new(symbol.uri.clone(), symbol.selection_range))
+            .collect();
+        logos_index::dedup_locations(rules)
+    }
+
+    /// Every use of a class: the elements carrying it in open HTML
+    /// documents and the CSS rules styling it, in location order
+    pub fn class_references(&self, class: &str) -> Vec<Location> {
+        let mut locations: Vec<Location> = self
+            .symbol_index
+            .documents()
+            .flat_map(|uri| self.document_symbols(uri))
+            .filter(|symbol| symbol.kind == SymbolKind::Class && symbol.detail.is_some())
+            .filter(|symbol| symbol.name.strip_prefix('.') == Some(class))
+            .map(|symbol| Location::new(symbol.uri.clone(), symbol.selection_range))
+            .collect();
+        locations.extend(self.class_rules(class));
+        logos_index::dedup_locations(locations)
+    }
+
+    /// Folding ranges of an open document
+    pub fn folding_ranges(&self, uri: &str) -> Vec<FoldingRange> {
+        let (Some(lang), Some(doc), Some(tree)) = (self.language(uri), self.document(uri), self.tree(uri)) else {
+            return Vec::new();
+        };
+        folding::folding_ranges(lang, tree, doc.content())
+    }
+
+    /// Colors written in a stylesheet, or in the style regions of another
+    /// document (`<style>` blocks, `css` template literals)
+    pub fn document_colors(&self, uri: &str) -> Vec<ColorInformation> {
+        let (Some(lang), Some(doc), Some(tree)) = (self.language(uri), self.document(uri), self.tree(uri)) else {
+            return Vec::new();
+        };
+        let is_style = |lang: LanguageId| matches!(lang, LanguageId::Css | LanguageId::Scss);
+        let mut colors = if is_style(lang) { css::colors(tree, doc.content()) } else { Vec::new() };
+        for injection in logos_parser::injections(lang, tree, doc.content()) {
+            let Some(embedded) = injection.language_id().filter(|lang| is_style(*lang)) else {
+                continue;
+            };
+            let content = injection.content(doc.content());
+            let Some(injected) = pool::shared().checkout(embedded).ok().and_then(|mut p| p.parse(&content, None).ok()) else {
+                continue;
+            };
+            for mut color in css::colors(&injected, &content) {
+                logos_parser::offset_range(&mut color.range, injection.range.start);
+                colors.push(color);
+            }
+        }
+        colors
+    }
+
+    /// Regions of a document compiled out by the active defines and
+    /// features, in source order
+    pub fn inactive_regions(&self, uri: &str) -> &'a [Range] {
+        self.inactive_regions.get(uri).map_or(&[], Vec::as_slice)
+    }
+
+    /// TODO comments in a document
+    pub fn todos(&self, uri: &str) -> &'a [TodoItem] {
+        self.todo_index.get_document_todos(uri)
+    }
+
+    /// Syntax errors and inactive regions of a document. Syntax errors are
+    /// only reported for data files (JSON, YAML and TOML), SQL scripts, and
+    /// data and SQL embedded in other documents, which have no other checks;
+    /// in code, a half-typed line is normal. Dockerfiles get their
+    /// instruction checks instead (see [`logos_parser::dockerfile::diagnostics`]).
+    /// Inactive regions are hints tagged [`DiagnosticTag::Unnecessary`] so
+    /// editors fade them. Diagnostics silenced by suppression comments are
+    /// left out.
+    pub fn diagnostics(&self, uri: &str) -> Vec<Diagnostic> {
+        let (Some(doc), Some(tree)) = (self.document(uri), self.tree(uri)) else {
+            return Vec::new();
+        };
+        let mut diagnostics = Vec::new();
+        if matches!(self.language(uri), Some(LanguageId::Json | LanguageId::Yaml | LanguageId::Toml | LanguageId::Sql)) {
+            diagnostics = logos_parser::extract_parse_errors(tree, doc.content());
+        }
+        if self.language(uri) == Some(LanguageId::Dockerfile) {
+            diagnostics = logos_parser::dockerfile::diagnostics(doc.content());
+        }
+        // Code blocks in Markdown are examples
+        if let Some(lang) = self.language(uri).filter(|lang| *lang != LanguageId::Markdown) {
+            diagnostics.extend(logos_parser::injected_parse_errors(lang, tree, doc.content(), CHECKED_INJECTIONS));
+        }
+        diagnostics.extend(self.inactive_regions(uri).iter().map(|range| {
+            Diagnostic::hint(*range, "Inactive code: excluded by the current configuration".to_string())
+                .with_code("inactive-code".to_string())
+                .with_source("logos".to_string())
+                .with_tag(DiagnosticTag::Unnecessary)
+        }));
+        let suppressions = Suppressions::scan(doc.content());
+        diagnostics.retain(|d| !suppressions.is_suppressed(d.range.start.line, d.code.as_deref()));
+        diagnostics
+    }
+
+    /// Refactoring context for a selection in an open document
+    pub fn refactor_context(&self, uri: &'a str, selection: Range) -> Option<RefactorContext<'a>> {
+        let doc = self.document(uri)?;
+        let language = self.language(uri)?;
+        Some(RefactorContext::new(doc.content(), uri, selection, language))
+    }
+
+    /// Refactorings offered for a selection
+    pub fn refactor_actions(&self, uri: &'a str, selection: Range) -> Vec<RefactorAction> {
+        self.refactor_context(uri, selection)
+            .map(|ctx| RefactorEngine::get_actions(&ctx))
+            .unwrap_or_default()
+    }
+
+    /// Compute the edits of a refactoring; nothing is applied
+    pub fn refactor(
+        &self,
+        uri: &'a str,
+        selection: Range,
+        action_id: &str,
+        new_name: Option<&str>,
+    ) -> Result<RefactorResult, RefactorError> {
+        let ctx = self
+            .refactor_context(uri, selection)
+            .ok_or_else(|| RefactorError::InvalidSelection("Document not found or language unsupported".to_string()))?;
+        RefactorEngine::execute(&ctx, action_id, new_name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::AnalysisHost;
+    use logos_core::{Position, Range};
+    use logos_parser::LanguageId;
+
+    #[test]
+    fn test_references_and_diagnostics() {
+        let mut host = AnalysisHost::new();
+        host.open_document("file:///b.py", "python", "def helper():\n    pass\n");
+        host.open_document("file:///a.py", "python", "def helper():\n    return 1\n");
+        host.open_document("file:///c.json", "json", "{\"a\": 1,}");
+        host.open_document("file:///d.py", "python", "# language=json\nCONFIG = '{\"a\": }'\n");
+
+        let analysis = host.snapshot();
+        let refs = analysis.references("file:///b.py", Position::new(0, 5));
+        let uris: Vec<_> = refs.iter().map(|l| l.uri.as_str()).collect();
+        assert_eq!(uris, ["file:///a.py", "file:///b.py"]);
+
+        assert!(analysis.diagnostics("file:///a.py").is_empty());
+        let errors = analysis.diagnostics("file:///c.json");
+        assert_eq!(errors[0].message, "Trailing comma");
+        // Syntax errors of embedded JSON, in document coordinates
+        let errors = analysis.diagnostics("file:///d.py");
+        assert!(!errors.is_empty());
+        assert!(errors.iter().all(|d| d.range.start.line == 1 && d.range.start.column >= 10));
+    }
+
+    #[test]
+    fn test_suppressed_diagnostics() {
+        let mut host = AnalysisHost::new();
+        host.open_document("file:///a.toml", "toml", "[a]\nx = \n# logos-ignore-next-line\ny = 1,\n");
+
+        let lines: Vec<_> = host.snapshot().diagnostics("file:///a.toml").iter().map(|d| d.range.start.line).collect();
+        assert_eq!(lines, vec![1]);
+    }
+
+    #[test]
+    fn test_sql_scripts_and_strings() {
+        let mut host = AnalysisHost::new();
+        host.open_document("file:///a.sql", "sql", "SELECT * FROM users WHERE;\n");
+        host.open_document("file:///b.py", "python", "# language=sql\nQUERY = \"SELECT id FROM users\"\n");
+
+        let analysis = host.snapshot();
+        assert!(!analysis.diagnostics("file:///a.sql").is_empty());
+        assert_eq!(analysis.language_at("file:///b.py", Position::new(1, 20)), Some(LanguageId::Sql));
+        assert_eq!(analysis.language_at("file:///b.py", Position::new(1, 2)), Some(LanguageId::Python));
+        let outline: Vec<_> = analysis.document_symbols("file:///b.py").iter().map(|s| s.name.as_str()).collect();
+        assert!(outline.contains(&"SELECT"));
+    }
+
+    #[test]
+    fn test_dockerfile() {
+        let mut host = AnalysisHost::new();
+        let source = "FROM node:20 AS build\n# TODO: pin the digest\nRUN npm ci\nMAINTAINER me\n";
+        host.open_document("file:///ws/Dockerfile", "plaintext", source);
+
+        let analysis = host.snapshot();
+        let stages: Vec<_> = analysis.document_symbols("file:///ws/Dockerfile").iter().map(|s| s.name.as_str()).collect();
+        assert!(stages.contains(&"build"));
+        assert_eq!(analysis.todos("file:///ws/Dockerfile").len(), 1);
+        let codes: Vec<_> = analysis.diagnostics("file:///ws/Dockerfile").into_iter().filter_map(|d| d.code).collect();
+        assert_eq!(codes, vec!["deprecated-instruction"]);
+    }
+
+    #[test]
+    fn test_html_and_css() {
+        let mut host = AnalysisHost::new();
+        host.open_document("file:///index.html", "html", "<div class=\"card wide\">\n  <style>.wide { color: #fff; }</style>\n</div>\n");
+        host.open_document("file:///site.css", "css", ".card,\n.card:hover { color: red; }\n");
+
+        let analysis = host.snapshot();
+        assert_eq!(analysis.class_at("file:///index.html", Position::new(0, 13)).as_deref(), Some("card"));
+        assert_eq!(analysis.class_at("file:///site.css", Position::new(1, 2)).as_deref(), Some("card"));
+        let rules: Vec<_> = analysis.class_rules("card").into_iter().map(|l| (l.uri, l.range.start.line)).collect();
+        assert_eq!(rules, vec![("file:///site.css".to_string(), 0)]);
+        // The element and the rule of its `<style>` block
+        let lines: Vec<_> = analysis.class_references("wide").iter().map(|l| l.range.start.line).collect();
+        assert_eq!(lines, vec![0, 1]);
+
+        let colors = analysis.document_colors("file:///index.html");
+        assert_eq!(colors.len(), 1);
+        assert_eq!(colors[0].range.start, Position::new(1, 24));
+        assert_eq!(analysis.document_colors("file:///site.css").len(), 1);
+        assert_eq!(analysis.folding_ranges("file:///index.html")[0].start_line, 0);
+    }
+
+    #[test]
+    fn test_refactor_without_applying() {
+        let mut host = AnalysisHost::new();
+        let source = "def f():\n    total = 1 + 2\n    return total\n";
+        host.open_document("file:///a.py", "python", source);
+
+        let analysis = host.snapshot();
+        let selection = Range::from_coords(1, 12, 1, 17);
+        assert!(analysis.refactor_actions("file:///a.py", selection).iter().any(|a| a.id == "extract-variable"));
+        let result = analysis.refactor("file:///a.py", selection, "extract-variable", Some("sum")).unwrap();
+        assert!(!result.edits.is_empty());
+        assert_eq!(analysis.document("file:///a.py").unwrap().content(), source);
+
+        assert!(analysis.refactor("file:///missing.py", selection, "extract-variable", None).is_err());
+    }
+}
@@ -0,0 +1,228 @@
+//! Completion insertion behaviour shared by all service frontends
+
+use crate::symbol::SymbolKind;
+use serde::{Deserialize, Serialize};
+
+/// How a completion for a callable symbol is inserted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CompletionInsertMode {
+    /// `name(${1:arg})` with tab stops; needs client snippet support
+    #[default]
+    Snippet,
+    /// Just the identifier
+    PlainText,
+    /// `name()` as plain text
+    CallWithParens,
+}
+
+impl CompletionInsertMode {
+    /// Downgrade snippet insertion for clients that cannot render snippets
+    pub fn effective(self, client_supports_snippets: bool) -> Self {
+        match self {
+            CompletionInsertMode::Snippet if !client_supports_snippets => {
+                CompletionInsertMode::CallWithParens
+            }
+            mode => mode,
+        }
+    }
+}
+
+/// LSP `InsertTextFormat`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InsertTextFormat {
+    PlainText = 1,
+    Snippet = 2,
+}
+
+/// Text to insert for a completion item
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InsertText {
+    pub text: String,
+    pub format: InsertTextFormat,
+}
+
+fn is_callable(kind: SymbolKind) -> bool {
+    matches!(kind, SymbolKind::Function | SymbolKind::Method | SymbolKind::Constructor)
+}
+
+/// Receiver parameters that callers never pass explicitly
+fn is_receiver(param: &str) -> bool {
+    matches!(param, "self" | "&self" | "&mut self" | "mut self" | "cls" | "this")
+}
+
+/// Split a parameter list like `(a, b: Dict[str, int] = {})` on top-level commas
+fn split_params(detail: &str) -> Vec<String> {
+    let inner = detail.trim();
+    let inner = inner.strip_prefix('(').unwrap_or(inner);
+    let inner = inner.strip_suffix(')').unwrap_or(inner);
+
+    let mut params = Vec::new();
+    let mut depth = 0i32;
+    let mut current = String::new();
+    for c in inner.chars() {
+        match c {
+            '(' | '[' | '{' | '<' => depth += 1,
+            ')' | ']' | '}' | '>' => depth -= 1,
+            ',' if depth == 0 => {
+                params.push(std::mem::take(&mut current));
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    params.push(current);
+
+    params
+        .into_iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty() && !is_receiver(p))
+        .collect()
+}
+
+/// The parameter list of `name` in a signature like
+/// `fn parse(source: &str) -> Tree`; a detail that is already a bare list
+/// like `(a, b)` is returned as is
+fn parameter_list<'a>(detail: &'a str, name: &str) -> &'a str {
+    let detail = detail.trim();
+    if detail.starts_with('(') || name.is_empty() {
+        return detail;
+    }
+    for (start, _) in detail.match_indices(name) {
+        let mut rest = detail[start + name.len()..].trim_start();
+        // Generic parameters between the name and the list
+        if rest.starts_with('<') {
+            let Some(end) = balanced_end(rest, '<', '>') else {
+                continue;
+            };
+            rest = rest[end..].trim_start();
+        }
+        if rest.starts_with('(') {
+            if let Some(end) = balanced_end(rest, '(', ')') {
+                return &rest[..end];
+            }
+        }
+    }
+    ""
+}
+
+/// Byte offset just past the delimiter closing the one `text` starts with
+fn balanced_end(text: &str, open: char, close: char) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        if c == open {
+            depth += 1;
+        } else if c == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(i + c.len_utf8());
+            }
+        }
+    }
+    None
+}
+
+/// Escape characters with special meaning in snippet placeholders
+fn escape_placeholder(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '$' | '}' | '\\') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Build the insert text for a symbol completion.
+///
+/// `detail` is the symbol's signature or parameter list when known (e.g.
+/// `def greet(self, name)` or `(a, b)`).
+/// Non-callable symbols always insert their plain name.
+pub fn insert_text(name: &str, kind: SymbolKind, detail: Option<&str>, mode: CompletionInsertMode) -> InsertText {
+    let plain = |text: String| InsertText { text, format: InsertTextFormat::PlainText };
+
+    if !is_callable(kind) {
+        return plain(name.to_string());
+    }
+
+    match mode {
+        CompletionInsertMode::PlainText => plain(name.to_string()),
+        CompletionInsertMode::CallWithParens => plain(format!("{}()", name)),
+        CompletionInsertMode::Snippet => {
+            let params = detail.map(|detail| split_params(parameter_list(detail, name))).unwrap_or_default();
+            let body = if params.is_empty() {
+                "$0".to_string()
+            } else {
+                params
+                    .iter()
+                    .enumerate()
+                    .map(|(i, p)| format!("${{{}:{}}}", i + 1, escape_placeholder(p)))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            };
+            InsertText {
+                text: format!("{}({})", escape_placeholder(name), body),
+                format: InsertTextFormat::Snippet,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snippet_from_signature() {
+        let snippet = |name, signature| insert_text(name, SymbolKind::Method, Some(signature), CompletionInsertMode::Snippet).text;
+        assert_eq!(
+            snippet("Run", "func (r *Runner) Run(ctx context.Context, n int) error"),
+            "Run(${1:ctx context.Context}, ${2:n int})"
+        );
+        assert_eq!(snippet("parse", "pub fn parse<T: Into<String>>(source: T) -> Tree"), "parse(${1:source: T})");
+        assert_eq!(snippet("greet", "def greet(self, name)"), "greet(${1:name})");
+    }
+
+    #[test]
+    fn test_snippet_with_params() {
+        let text = insert_text("greet", SymbolKind::Method, Some("(self, name, times=1)"), CompletionInsertMode::Snippet);
+        assert_eq!(text.text, "greet(${1:name}, ${2:times=1})");
+        assert_eq!(text.format, InsertTextFormat::Snippet);
+    }
+
+    #[test]
+    fn test_snippet_nested_commas() {
+        let text = insert_text("f", SymbolKind::Function, Some("(m: Map<K, V>, cb)"), CompletionInsertMode::Snippet);
+        assert_eq!(text.text, "f(${1:m: Map<K, V>}, ${2:cb})");
+    }
+
+    #[test]
+    fn test_snippet_without_params() {
+        let text = insert_text("run", SymbolKind::Function, None, CompletionInsertMode::Snippet);
+        assert_eq!(text.text, "run($0)");
+    }
+
+    #[test]
+    fn test_plain_and_parens() {
+        let plain = insert_text("run", SymbolKind::Function, Some("(a)"), CompletionInsertMode::PlainText);
+        assert_eq!(plain.text, "run");
+        let parens = insert_text("run", SymbolKind::Function, Some("(a)"), CompletionInsertMode::CallWithParens);
+        assert_eq!(parens, InsertText { text: "run()".to_string(), format: InsertTextFormat::PlainText });
+    }
+
+    #[test]
+    fn test_non_callable_is_plain() {
+        let text = insert_text("Config", SymbolKind::Class, None, CompletionInsertMode::Snippet);
+        assert_eq!(text.format, InsertTextFormat::PlainText);
+        assert_eq!(text.text, "Config");
+    }
+
+    #[test]
+    fn test_effective_mode() {
+        assert_eq!(CompletionInsertMode::Snippet.effective(false), CompletionInsertMode::CallWithParens);
+        assert_eq!(CompletionInsertMode::Snippet.effective(true), CompletionInsertMode::Snippet);
+        assert_eq!(CompletionInsertMode::PlainText.effective(false), CompletionInsertMode::PlainText);
+    }
+}
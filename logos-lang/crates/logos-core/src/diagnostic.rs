@@ -25,6 +25,16 @@ impl DiagnosticSeverity {
     }
 }
 
+/// How a diagnostic's range should be rendered, on top of its severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DiagnosticTag {
+    /// Unused or inactive code; editors fade it out
+    Unnecessary = 1,
+    /// Use of a deprecated symbol; editors strike it through
+    Deprecated = 2,
+}
+
 /// A diagnostic message
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Diagnostic {
@@ -43,6 +53,9 @@ pub struct Diagnostic {
     /// Related information
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub related_information: Vec<DiagnosticRelatedInformation>,
+    /// Rendering hints
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<DiagnosticTag>,
 }
 
 impl Diagnostic {
@@ -54,6 +67,7 @@ impl Diagnostic {
             source: None,
             message,
             related_information: Vec::new(),
+            tags: Vec::new(),
         }
     }
 
@@ -65,6 +79,7 @@ impl Diagnostic {
             source: None,
             message,
             related_information: Vec::new(),
+            tags: Vec::new(),
         }
     }
 
@@ -76,6 +91,7 @@ impl Diagnostic {
             source: None,
             message,
             related_information: Vec::new(),
+            tags: Vec::new(),
         }
     }
 
@@ -87,6 +103,7 @@ impl Diagnostic {
             source: None,
             message,
             related_information: Vec::new(),
+            tags: Vec::new(),
         }
     }
 
@@ -104,6 +121,11 @@ impl Diagnostic {
         self.related_information = related;
         self
     }
+
+    pub fn with_tag(mut self, tag: DiagnosticTag) -> Self {
+        self.tags.push(tag);
+        self
+    }
 }
 
 /// Related information for a diagnostic
@@ -3,6 +3,14 @@
 use crate::position::{Position, Range};
 use serde::{Deserialize, Serialize};
 
+/// An edit to a document: `range` replaced by `text`, or the whole content
+/// when `range` is `None`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextChange {
+    pub range: Option<Range>,
+    pub text: String,
+}
+
 /// A text document managed by the language service
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Document {
@@ -63,7 +71,9 @@ impl Document {
         Some(&self.content[start..end])
     }
 
-    /// Convert a position to a byte offset
+    /// Convert a position to a byte offset. A column inside a surrogate
+    /// pair (the second unit of an astral character such as `🦀`) resolves
+    /// to the start of the character.
     pub fn offset_at(&self, position: Position) -> Option<usize> {
         let line_idx = position.line as usize;
         if line_idx >= self.line_offsets.len() {
@@ -83,7 +93,7 @@ impl Document {
         let mut byte_offset = 0;
 
         for ch in line_content.chars() {
-            if col >= position.column {
+            if col + ch.len_utf16() as u32 > position.column {
                 break;
             }
             col += ch.len_utf16() as u32;
@@ -93,9 +103,13 @@ impl Document {
         Some(line_start + byte_offset)
     }
 
-    /// Convert a byte offset to a position
+    /// Convert a byte offset to a position. An offset inside a multi-byte
+    /// character resolves to the start of the character.
     pub fn position_at(&self, offset: usize) -> Position {
-        let offset = offset.min(self.content.len());
+        let mut offset = offset.min(self.content.len());
+        while !self.content.is_char_boundary(offset) {
+            offset -= 1;
+        }
 
         // Binary search for the line
         let line = match self.line_offsets.binary_search(&offset) {
@@ -136,6 +150,14 @@ impl Document {
         self.version += 1;
     }
 
+    /// Apply a change of either kind
+    pub fn apply(&mut self, change: &TextChange) {
+        match change.range {
+            Some(range) => self.apply_change(range, &change.text),
+            None => self.set_content(change.text.clone()),
+        }
+    }
+
     /// Get text in a range
     pub fn text_in_range(&self, range: Range) -> Option<&str> {
         let start = self.offset_at(range.start)?;
@@ -187,6 +209,25 @@ mod tests {
         assert_eq!(pos, pos2);
     }
 
+    #[test]
+    fn test_astral_and_cjk_columns() {
+        let doc = Document::new(
+            "test.js".to_string(),
+            "javascript".to_string(),
+            "const 名前 = 1;
+let 🦀x = 名前;
+".to_string(),
+        );
+
+        // `名前` is two units; the crab is a surrogate pair
+        assert_eq!(doc.offset_at(Position::new(0, 8)), Some(12));
+        assert_eq!(doc.position_at(27), Position::new(1, 7));
+        assert_eq!(doc.text_in_range(Range::from_coords(1, 4, 1, 7)), Some("🦀x"));
+        // Inside the pair and inside a UTF-8 sequence: start of the character
+        assert_eq!(doc.offset_at(Position::new(1, 5)), doc.offset_at(Position::new(1, 4)));
+        assert_eq!(doc.position_at(23), Position::new(1, 4));
+    }
+
     #[test]
     fn test_apply_change() {
         let mut doc = Document::new(
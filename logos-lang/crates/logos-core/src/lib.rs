@@ -1,11 +1,14 @@
 //! Logos Core - Core types and interfaces for the language service
 
+pub mod completion;
 pub mod diagnostic;
 pub mod document;
 pub mod position;
 pub mod symbol;
+pub mod uri;
 
-pub use diagnostic::{Diagnostic, DiagnosticSeverity};
-pub use document::Document;
+pub use completion::{CompletionInsertMode, InsertText, InsertTextFormat};
+pub use diagnostic::{Diagnostic, DiagnosticSeverity, DiagnosticTag};
+pub use document::{Document, TextChange};
 pub use position::{Location, Position, Range};
 pub use symbol::{Symbol, SymbolKind};
\ No newline at end of file
@@ -42,7 +42,7 @@ impl Ord for Position {
     }
 }
 
-/// A range in a text document
+/// A range in a text document. Ranges order by start, then end.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[derive(Default)]
 pub struct Range {
@@ -88,9 +88,22 @@ impl Range {
     }
 }
 
+impl PartialOrd for Range {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
 
-/// A location in a document (URI + Range)
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+impl Ord for Range {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.start.cmp(&other.start).then(self.end.cmp(&other.end))
+    }
+}
+
+
+/// A location in a document (URI + Range). Locations order by URI, then
+/// range; list responses use this order.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Location {
     pub uri: String,
     pub range: Range,
@@ -117,6 +130,16 @@ mod tests {
         assert!(p1 < p3);
     }
 
+    #[test]
+    fn test_location_ordering() {
+        let a = Location::new("file:///a".to_string(), Range::from_coords(3, 0, 3, 4));
+        let b = Location::new("file:///a".to_string(), Range::from_coords(3, 0, 5, 0));
+        let c = Location::new("file:///b".to_string(), Range::from_coords(0, 0, 0, 1));
+
+        assert!(a < b);
+        assert!(b < c);
+    }
+
     #[test]
     fn test_range_contains() {
         let range = Range::from_coords(1, 0, 1, 10);
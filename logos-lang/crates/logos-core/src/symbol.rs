@@ -67,6 +67,39 @@ impl SymbolKind {
             SymbolKind::TypeParameter => 26,
         }
     }
+
+    /// The kind a Monaco editor symbol kind stands for
+    pub fn from_monaco_kind(kind: u32) -> Option<Self> {
+        Some(match kind {
+            1 => SymbolKind::File,
+            2 => SymbolKind::Module,
+            3 => SymbolKind::Namespace,
+            4 => SymbolKind::Package,
+            5 => SymbolKind::Class,
+            6 => SymbolKind::Method,
+            7 => SymbolKind::Property,
+            8 => SymbolKind::Field,
+            9 => SymbolKind::Constructor,
+            10 => SymbolKind::Enum,
+            11 => SymbolKind::Interface,
+            12 => SymbolKind::Function,
+            13 => SymbolKind::Variable,
+            14 => SymbolKind::Constant,
+            15 => SymbolKind::String,
+            16 => SymbolKind::Number,
+            17 => SymbolKind::Boolean,
+            18 => SymbolKind::Array,
+            19 => SymbolKind::Object,
+            20 => SymbolKind::Key,
+            21 => SymbolKind::Null,
+            22 => SymbolKind::EnumMember,
+            23 => SymbolKind::Struct,
+            24 => SymbolKind::Event,
+            25 => SymbolKind::Operator,
+            26 => SymbolKind::TypeParameter,
+            _ => return None,
+        })
+    }
 }
 
 /// A symbol in a document
@@ -83,6 +116,9 @@ pub struct Symbol {
     /// Detail information (e.g., type signature)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub detail: Option<String>,
+    /// Doc comment or docstring of the declaration, as markdown
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub documentation: Option<String>,
     /// Children symbols (for hierarchical structure)
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub children: Vec<Symbol>,
@@ -96,6 +132,7 @@ impl Symbol {
             range,
             selection_range,
             detail: None,
+            documentation: None,
             children: Vec::new(),
         }
     }
@@ -0,0 +1,317 @@
+//! Canonical `file://` URIs
+//!
+//! Clients spell the same file in many ways: `file:///C:/src`,
+//! `file:///c%3A/src`, `file://localhost/src/a%20b.py`, or through a
+//! symlinked directory. Every URI entering the service is normalized here so
+//! that the state, the indexes and the adapters agree on one key per file.
+//!
+//! The canonical form is `file://<host>/<path>` with a lowercase host (empty
+//! for local files), forward slashes, a lowercase drive letter followed by a
+//! literal `:`, and percent-encoding of everything outside the unreserved set.
+//! Non-`file` URIs are returned unchanged.
+//!
+//! Canonical URIs are keys: a [`UriMap`] remembers how the client spelled
+//! each one, so answers name files the way the client knows them.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const SCHEME: &str = "file:";
+
+/// Normalize a URI to its canonical form without touching the filesystem
+pub fn normalize_uri(uri: &str) -> String {
+    match split_file_uri(uri) {
+        Some((authority, path)) => build_uri(&authority, &path),
+        None => uri.to_string(),
+    }
+}
+
+/// Normalize a URI and resolve symlinks. A file that doesn't exist yet is
+/// resolved through its nearest existing directory, so it keeps the key it
+/// has once created.
+pub fn canonicalize_uri(uri: &str) -> String {
+    let normalized = normalize_uri(uri);
+    match uri_to_path(&normalized).and_then(|path| canonicalize_existing(&path)) {
+        Some(real) => path_to_uri(&real),
+        None => normalized,
+    }
+}
+
+/// Resolve symlinks in the part of `path` that exists, then append the rest
+fn canonicalize_existing(path: &Path) -> Option<PathBuf> {
+    let mut missing = Vec::new();
+    let mut existing = path;
+    loop {
+        if let Ok(real) = fs::canonicalize(existing) {
+            return Some(missing.iter().rev().fold(real, |path, name| path.join(name)));
+        }
+        missing.push(existing.file_name()?);
+        existing = existing.parent()?;
+    }
+}
+
+/// The URIs a client sends and the canonical keys they stand for. Each
+/// spelling is canonicalized once, so resolving symlinks doesn't cost a
+/// filesystem call per message.
+#[derive(Debug, Default)]
+pub struct UriMap {
+    /// Client spelling -> canonical key
+    keys: HashMap<String, String>,
+    /// Canonical key -> client spelling, where the two differ
+    spellings: HashMap<String, String>,
+}
+
+impl UriMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Canonical key of a URI sent by the client
+    pub fn key(&mut self, uri: &str) -> String {
+        if let Some(key) = self.keys.get(uri) {
+            return key.clone();
+        }
+        let key = canonicalize_uri(uri);
+        // The spelling the client used last names the file in answers
+        if key == uri {
+            self.spellings.remove(&key);
+        } else {
+            self.spellings.insert(key.clone(), uri.to_string());
+        }
+        self.keys.insert(uri.to_string(), key.clone());
+        key
+    }
+
+    /// How the client spells `key`, when not as the key itself
+    pub fn spelling(&self, key: &str) -> Option<&str> {
+        self.spellings.get(key).map(String::as_str)
+    }
+
+    /// Whether the client spells every key as the key itself
+    pub fn is_identity(&self) -> bool {
+        self.spellings.is_empty()
+    }
+}
+
+/// Convert a `file://` URI to a filesystem path
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let (authority, path) = split_file_uri(uri)?;
+    let path = if !authority.is_empty() {
+        // UNC: file://server/share/x -> //server/share/x
+        format!("//{}{}", authority, path)
+    } else if has_drive_letter(&path) {
+        let mut path = path[1..].to_string();
+        path[..1].make_ascii_lowercase();
+        path
+    } else {
+        path
+    };
+    Some(PathBuf::from(path))
+}
+
+/// Convert a filesystem path to a canonical `file://` URI
+pub fn path_to_uri(path: &Path) -> String {
+    let mut path = path.to_string_lossy().replace('\\', "/");
+
+    // Strip Windows verbatim prefixes produced by `fs::canonicalize`
+    if let Some(rest) = path.strip_prefix("//?/UNC/") {
+        path = format!("//{}", rest);
+    } else if let Some(rest) = path.strip_prefix("//?/") {
+        path = rest.to_string();
+    }
+
+    if let Some(unc) = path.strip_prefix("//") {
+        let (host, rest) = match unc.find('/') {
+            Some(i) => (&unc[..i], &unc[i..]),
+            None => (unc, "/"),
+        };
+        return build_uri(host, rest);
+    }
+    if !path.starts_with('/') {
+        path.insert(0, '/');
+    }
+    build_uri("", &path)
+}
+
+/// Split a `file` URI into lowercase authority and decoded, slash-normalized path
+fn split_file_uri(uri: &str) -> Option<(String, String)> {
+    let scheme = uri.get(..SCHEME.len())?;
+    if !scheme.eq_ignore_ascii_case(SCHEME) {
+        return None;
+    }
+    let rest = &uri[SCHEME.len()..];
+    let rest = rest.split(['?', '#']).next().unwrap_or(rest);
+
+    let (authority, path) = match rest.strip_prefix("//") {
+        Some(rest) => match rest.find('/') {
+            Some(i) => (&rest[..i], &rest[i..]),
+            None => (rest, "/"),
+        },
+        None => ("", rest),
+    };
+
+    let mut authority = percent_decode(authority).to_lowercase();
+    if authority == "localhost" {
+        authority.clear();
+    }
+    let mut path = percent_decode(path).replace('\\', "/");
+    if !path.starts_with('/') {
+        path.insert(0, '/');
+    }
+    Some((authority, path))
+}
+
+fn build_uri(authority: &str, path: &str) -> String {
+    let mut path = path.to_string();
+    if has_drive_letter(&path) {
+        path[1..2].make_ascii_lowercase();
+    }
+    format!("file://{}{}", authority.to_lowercase(), percent_encode(&path))
+}
+
+/// Whether a path looks like `/c:` or `/c:/...`
+fn has_drive_letter(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    bytes.len() >= 3
+        && bytes[0] == b'/'
+        && bytes[1].is_ascii_alphabetic()
+        && bytes[2] == b':'
+        && (bytes.len() == 3 || bytes[3] == b'/')
+}
+
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            if let (Some(hi), Some(lo)) = (hex_value(bytes[i + 1]), hex_value(bytes[i + 2])) {
+                out.push(hi << 4 | lo);
+                i += 3;
+                continue;
+            }
+        }
+        out.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|d| d as u8)
+}
+
+fn percent_encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'.' | b'_' | b'~' | b'/' | b':') {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_percent_encoding() {
+        assert_eq!(normalize_uri("file:///src/a%20b.py"), "file:///src/a%20b.py");
+        assert_eq!(normalize_uri("file:///src/a b.py"), "file:///src/a%20b.py");
+        assert_eq!(normalize_uri("file:///src/%61.py"), "file:///src/a.py");
+        assert_eq!(normalize_uri("file:///src/caf%c3%a9.py"), "file:///src/caf%C3%A9.py");
+        assert_eq!(normalize_uri("file:///src/café.py"), "file:///src/caf%C3%A9.py");
+        // Stray percent signs are encoded, not decoded
+        assert_eq!(normalize_uri("file:///src/100%.py"), "file:///src/100%25.py");
+        assert_eq!(normalize_uri("file:///src/%é"), "file:///src/%25%C3%A9");
+    }
+
+    #[test]
+    fn test_normalize_drive_letters() {
+        assert_eq!(normalize_uri("file:///C:/src/a.py"), "file:///c:/src/a.py");
+        assert_eq!(normalize_uri("file:///c%3A/src/a.py"), "file:///c:/src/a.py");
+        assert_eq!(normalize_uri("FILE:///C:\\src\\a.py"), "file:///c:/src/a.py");
+        // Only the drive letter is folded
+        assert_eq!(normalize_uri("file:///C:/Src/A.py"), "file:///c:/Src/A.py");
+    }
+
+    #[test]
+    fn test_normalize_authority() {
+        assert_eq!(normalize_uri("file://localhost/src/a.py"), "file:///src/a.py");
+        assert_eq!(normalize_uri("file://Server/Share/a.py"), "file://server/Share/a.py");
+        assert_eq!(normalize_uri("untitled:Untitled-1"), "untitled:Untitled-1");
+    }
+
+    #[test]
+    fn test_path_round_trip() {
+        assert_eq!(path_to_uri(Path::new("/src/a b.py")), "file:///src/a%20b.py");
+        assert_eq!(uri_to_path("file:///src/a%20b.py"), Some(PathBuf::from("/src/a b.py")));
+
+        assert_eq!(path_to_uri(Path::new("C:\\src\\a.py")), "file:///c:/src/a.py");
+        assert_eq!(uri_to_path("file:///C:/src/a.py"), Some(PathBuf::from("c:/src/a.py")));
+
+        assert_eq!(path_to_uri(Path::new("\\\\server\\share\\a.py")), "file://server/share/a.py");
+        assert_eq!(path_to_uri(Path::new("\\\\?\\UNC\\server\\share\\a.py")), "file://server/share/a.py");
+        assert_eq!(path_to_uri(Path::new("\\\\?\\C:\\src\\a.py")), "file:///c:/src/a.py");
+        assert_eq!(uri_to_path("file://server/share/a.py"), Some(PathBuf::from("//server/share/a.py")));
+
+        assert_eq!(uri_to_path("untitled:Untitled-1"), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_canonicalize_resolves_symlinks() {
+        let root = std::env::temp_dir().join(format!("logos-uri-{}", std::process::id()));
+        let real = root.join("real");
+        fs::create_dir_all(&real).unwrap();
+        fs::write(real.join("a.py"), "").unwrap();
+        let link = root.join("link");
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let via_link = canonicalize_uri(&path_to_uri(&link.join("a.py")));
+        let direct = canonicalize_uri(&path_to_uri(&real.join("a.py")));
+        assert_eq!(via_link, direct);
+        // Missing files are only normalized
+        assert_eq!(canonicalize_uri("file:///no/such/File.py"), "file:///no/such/File.py");
+        // ... past their existing directories, so creating one keeps its key
+        let missing = canonicalize_uri(&path_to_uri(&link.join("new/b.py")));
+        assert_eq!(missing, canonicalize_uri(&path_to_uri(&real.join("new/b.py"))));
+        fs::create_dir_all(real.join("new")).unwrap();
+        fs::write(real.join("new/b.py"), "").unwrap();
+        assert_eq!(canonicalize_uri(&path_to_uri(&link.join("new/b.py"))), missing);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_uri_map_remembers_client_spellings() {
+        let root = std::env::temp_dir().join(format!("logos-uri-map-{}", std::process::id()));
+        let real = root.join("real");
+        fs::create_dir_all(&real).unwrap();
+        fs::write(real.join("a.py"), "").unwrap();
+        let link = root.join("link");
+        let _ = fs::remove_file(&link);
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let mut uris = UriMap::new();
+        let spelled = path_to_uri(&link.join("a.py"));
+        let key = uris.key(&spelled);
+        assert_eq!(key, canonicalize_uri(&path_to_uri(&real.join("a.py"))));
+        assert_eq!(uris.spelling(&key), Some(spelled.as_str()));
+        assert!(!uris.is_identity());
+
+        // Resolved once: the link is gone, the key stays
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(uris.key(&spelled), key);
+
+        let mut uris = UriMap::new();
+        assert_eq!(uris.key("file:///no/such/a.py"), "file:///no/such/a.py");
+        assert!(uris.is_identity());
+    }
+}
@@ -0,0 +1,201 @@
+//! Diagnostics baseline
+//!
+//! A baseline records the diagnostics a workspace already has, so that
+//! only new ones are reported. Entries are matched on file, source, code,
+//! message and the trimmed text of the diagnostic's line rather than on
+//! line numbers, so edits elsewhere in a file don't resurface them. Each
+//! entry absorbs one diagnostic: a second copy of a baselined problem is
+//! still reported.
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Version of the baseline file format
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BaselineEntry {
+    /// Path relative to the workspace root, `/`-separated
+    pub path: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+    pub message: String,
+    /// Trimmed text of the line the diagnostic starts on
+    pub line: String,
+}
+
+impl BaselineEntry {
+    /// Fingerprint of an LSP diagnostic in `content`
+    pub fn new(path: &str, content: &str, diagnostic: &Value) -> Option<Self> {
+        let line = diagnostic.pointer("/range/start/line").and_then(Value::as_u64)?;
+        let code = match diagnostic.get("code") {
+            Some(Value::String(code)) => Some(code.clone()),
+            Some(Value::Number(code)) => Some(code.to_string()),
+            _ => None,
+        };
+        Some(Self {
+            path: path.to_string(),
+            source: diagnostic.get("source").and_then(Value::as_str).map(str::to_string),
+            code,
+            message: diagnostic.get("message").and_then(Value::as_str).unwrap_or_default().to_string(),
+            line: content.lines().nth(line as usize).unwrap_or_default().trim().to_string(),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct BaselineFile {
+    version: u32,
+    diagnostics: Vec<BaselineEntry>,
+}
+
+/// Accepted diagnostics, counted per fingerprint
+#[derive(Debug, Clone, Default)]
+pub struct Baseline {
+    counts: HashMap<BaselineEntry, usize>,
+}
+
+impl Baseline {
+    pub fn from_entries(entries: impl IntoIterator<Item = BaselineEntry>) -> Self {
+        let mut counts = HashMap::new();
+        for entry in entries {
+            *counts.entry(entry).or_insert(0) += 1;
+        }
+        Self { counts }
+    }
+
+    /// Read a baseline file; `Ok(None)` when there is none
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        let json = match std::fs::read_to_string(path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.to_string()),
+        };
+        let file: BaselineFile = serde_json::from_str(&json).map_err(|e| e.to_string())?;
+        if file.version != FORMAT_VERSION {
+            return Err(format!("unsupported baseline version {}", file.version));
+        }
+        Ok(Some(Self::from_entries(file.diagnostics)))
+    }
+
+    /// Write the baseline, sorted so that the file diffs well
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let mut diagnostics: Vec<BaselineEntry> = self
+            .counts
+            .iter()
+            .flat_map(|(entry, count)| std::iter::repeat_n(entry.clone(), *count))
+            .collect();
+        diagnostics.sort_by(|a, b| {
+            (&a.path, &a.line, &a.code, &a.message).cmp(&(&b.path, &b.line, &b.code, &b.message))
+        });
+        let json = serde_json::to_string_pretty(&BaselineFile { version: FORMAT_VERSION, diagnostics })
+            .map_err(|e| e.to_string())?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(path, json + "\n").map_err(|e| e.to_string())
+    }
+
+    pub fn len(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Drop the diagnostics of `path` that the baseline accepts
+    pub fn filter(&self, path: &str, content: &str, diagnostics: Vec<Value>) -> Vec<Value> {
+        let mut remaining: HashMap<&BaselineEntry, usize> = HashMap::new();
+        diagnostics
+            .into_iter()
+            .filter(|diagnostic| {
+                let Some(entry) = BaselineEntry::new(path, content, diagnostic) else {
+                    return true;
+                };
+                let Some((key, total)) = self.counts.get_key_value(&entry) else {
+                    return true;
+                };
+                let left = remaining.entry(key).or_insert(*total);
+                if *left == 0 {
+                    return true;
+                }
+                *left -= 1;
+                false
+            })
+            .collect()
+    }
+}
+
+/// Path of `uri` relative to `root`, `/`-separated; `None` outside it
+pub fn relative_path(root: &Path, uri: &str) -> Option<String> {
+    let path = logos_core::uri::uri_to_path(uri)?;
+    let relative = path.strip_prefix(root).ok()?;
+    Some(relative.components().map(|c| c.as_os_str().to_string_lossy()).collect::<Vec<_>>().join("/"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn diagnostic(line: u64, code: Value, message: &str) -> Value {
+        json!({
+            "range": { "start": { "line": line, "character": 0 }, "end": { "line": line, "character": 1 } },
+            "source": "logos",
+            "code": code,
+            "message": message,
+        })
+    }
+
+    #[test]
+    fn test_fingerprint_ignores_line_numbers() {
+        let before = "import os\nx = undefined\n";
+        let after = "import os\n\n\n    x = undefined   \n";
+        let entry = BaselineEntry::new("a.py", before, &diagnostic(1, json!("E1"), "undefined name")).unwrap();
+        assert_eq!(entry.line, "x = undefined");
+
+        // Moved and re-indented, the same problem
+        assert_eq!(BaselineEntry::new("a.py", after, &diagnostic(3, json!("E1"), "undefined name")), Some(entry.clone()));
+        // Another file, code, message or line text is another problem
+        for other in [
+            BaselineEntry::new("b.py", before, &diagnostic(1, json!("E1"), "undefined name")),
+            BaselineEntry::new("a.py", before, &diagnostic(1, json!("E2"), "undefined name")),
+            BaselineEntry::new("a.py", before, &diagnostic(1, json!("E1"), "unused name")),
+            BaselineEntry::new("a.py", before, &diagnostic(0, json!("E1"), "undefined name")),
+        ] {
+            assert_ne!(other, Some(entry.clone()));
+        }
+
+        // Numeric codes compare as text; no range, no fingerprint
+        let numeric = BaselineEntry::new("a.py", before, &diagnostic(1, json!(401), "m")).unwrap();
+        assert_eq!(numeric.code.as_deref(), Some("401"));
+        assert_eq!(BaselineEntry::new("a.py", before, &json!({ "message": "m" })), None);
+    }
+
+    #[test]
+    fn test_filter_absorbs_one_diagnostic_per_entry() {
+        let content = "x = undefined\nx = undefined\ny = 1\n";
+        let accepted = diagnostic(0, json!("E1"), "undefined name");
+        let baseline = Baseline::from_entries(BaselineEntry::new("a.py", content, &accepted));
+        assert_eq!(baseline.len(), 1);
+
+        // Two copies of the accepted problem: the second is still reported
+        let reported = baseline.filter(
+            "a.py",
+            content,
+            vec![
+                accepted.clone(),
+                diagnostic(1, json!("E1"), "undefined name"),
+                diagnostic(2, json!("E1"), "undefined name"),
+            ],
+        );
+        assert_eq!(reported.iter().map(|d| d["range"]["start"]["line"].clone()).collect::<Vec<_>>(), vec![json!(1), json!(2)]);
+
+        // Each call starts from the full counts; other files are untouched
+        assert!(baseline.filter("a.py", content, vec![accepted.clone()]).is_empty());
+        assert_eq!(baseline.filter("b.py", content, vec![accepted]).len(), 1);
+    }
+}
@@ -0,0 +1,301 @@
+//! `logos-daemon check`: the editor's diagnostics as a pre-commit gate
+//!
+//! Checks the files of a workspace, or with `--staged` only those changed
+//! in the git staging area as they are staged, with the settings the IDE
+//! keeps in `.logos/settings.json`: analysis diagnostics and plugin lint
+//! rules under the configured severities, suppressions and baseline, then
+//! the configured format checks. Problems are printed one per line as
+//! `path:line:column: severity [code] message`; the exit code is 1 when one
+//! reaches the `--fail-on` severity (errors by default) or a format check
+//! fails.
+
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde_json::Value;
+use logos_core::uri::path_to_uri;
+use logos_index::PathGlob;
+use logos_parser::LanguageId;
+
+use crate::baseline::relative_path;
+use crate::config::{FormatCheck, Settings, WorkspaceTrust};
+use crate::git;
+use crate::handlers::diagnostics::{apply_severities, collect};
+use crate::process::ToolCommand;
+use crate::scan::workspace_files;
+use crate::state::State;
+
+const USAGE: &str = "usage: logos-daemon check [--staged] [--fail-on error|warning|information|hint] [<dir>]";
+
+/// The IDE's project settings, relative to the workspace root
+const SETTINGS_FILE: &str = ".logos/settings.json";
+
+/// Time a format check gets per file unless configured
+const FORMAT_TIMEOUT: Duration = Duration::from_secs(30);
+
+const SEVERITY_NAMES: &[&str] = &["error", "warning", "information", "hint"];
+
+#[derive(Debug, PartialEq)]
+struct Options {
+    dir: PathBuf,
+    staged: bool,
+    /// Lowest LSP severity (1 = error … 4 = hint) that fails the check
+    fail_on: u8,
+}
+
+/// A diagnostic as reported
+#[derive(Debug)]
+struct Problem {
+    path: String,
+    /// 0-based, as in LSP
+    line: u64,
+    character: u64,
+    severity: u8,
+    code: Option<String>,
+    message: String,
+}
+
+/// Run the check; the exit code on success
+pub fn run(args: &[String]) -> Result<i32, String> {
+    let Some(options) = parse_args(args)? else {
+        println!("{}", USAGE);
+        return Ok(0);
+    };
+    let root = options
+        .dir
+        .canonicalize()
+        .map_err(|e| format!("{}: {}", options.dir.display(), e))?;
+
+    let mut state = State::new();
+    state.root_path = Some(root.to_string_lossy().into_owned());
+    // The check runs at the user's request, on a workspace they picked;
+    // the project settings may still restrict it
+    state.trust = WorkspaceTrust::Trusted;
+    state.apply_settings(load_settings(&root)?);
+    state.load_plugins();
+
+    let files = if options.staged {
+        let mut files = Vec::new();
+        for path in git::staged_files(&root)? {
+            let content = git::staged_content(&root, &path)?;
+            files.push((path, content));
+        }
+        files
+    } else {
+        workspace_files(state.vfs.as_ref(), &root, &state.settings.index.exclude)
+            .iter()
+            .filter_map(|file| {
+                let path = relative_path(&root, &path_to_uri(file))?;
+                Some((path, state.vfs.read_to_string(file).ok()?))
+            })
+            .collect()
+    };
+
+    let mut problems = Vec::new();
+    let mut format_failures = Vec::new();
+    for (path, content) in &files {
+        if let Some(language) = LanguageId::detect(path, content) {
+            let uri = path_to_uri(&root.join(path));
+            state.open_document(uri.clone(), language.as_str().to_string(), content.clone());
+            let items = collect(state.analysis(), &state.plugins, &uri, language.as_str(), content);
+            let items = apply_severities(&state.settings.diagnostics, path, items);
+            let items = match &state.baseline {
+                Some(baseline) => baseline.filter(path, content, items),
+                None => items,
+            };
+            state.close_document(&uri);
+            problems.extend(items.iter().map(|item| Problem::new(path, item)));
+        }
+
+        let checks = state.settings.check.format.iter().filter(|check| matches_file(check, path));
+        for check in checks {
+            if let Err(output) = run_format_check(check, &root, path, content) {
+                format_failures.push((path.clone(), check.command.clone(), output));
+            }
+        }
+    }
+
+    problems.sort_by(|a, b| (&a.path, a.line, a.character).cmp(&(&b.path, b.line, b.character)));
+    for problem in &problems {
+        println!("{}", problem);
+    }
+    for (path, command, output) in &format_failures {
+        println!("{}: not formatted ({})", path, command);
+        for line in output.lines().filter(|l| !l.trim().is_empty()) {
+            println!("    {}", line);
+        }
+    }
+
+    let failing = problems.iter().filter(|p| p.severity <= options.fail_on).count();
+    println!("{}", summary(&problems, format_failures.len(), files.len()));
+    Ok(if failing > 0 || !format_failures.is_empty() { 1 } else { 0 })
+}
+
+/// `None` when help was asked for
+fn parse_args(args: &[String]) -> Result<Option<Options>, String> {
+    let mut options = Options { dir: PathBuf::from("."), staged: false, fail_on: 1 };
+    let mut dir = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--staged" => options.staged = true,
+            "--fail-on" => {
+                let name = args.next().ok_or(USAGE)?;
+                let index = SEVERITY_NAMES
+                    .iter()
+                    .position(|s| s == name || (*s == "information" && name == "info"))
+                    .ok_or_else(|| format!("unknown severity `{}`\n{}", name, USAGE))?;
+                options.fail_on = index as u8 + 1;
+            }
+            "-h" | "--help" => return Ok(None),
+            _ if dir.is_none() && !arg.starts_with('-') => dir = Some(PathBuf::from(arg)),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    if let Some(dir) = dir {
+        options.dir = dir;
+    }
+    Ok(Some(options))
+}
+
+/// Settings from the IDE's project settings file, the defaults without one
+fn load_settings(root: &Path) -> Result<Settings, String> {
+    let path = root.join(SETTINGS_FILE);
+    match std::fs::read_to_string(&path) {
+        Ok(json) => {
+            let value: Value = serde_json::from_str(&json).map_err(|e| format!("{}: {}", path.display(), e))?;
+            Ok(Settings::from_value(&value))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Settings::default()),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+fn matches_file(check: &FormatCheck, path: &str) -> bool {
+    PathGlob::new(&check.files).is_some_and(|glob| glob.is_match(path))
+}
+
+/// Run `check` on `content`; its output when it fails
+fn run_format_check(check: &FormatCheck, root: &Path, path: &str, content: &str) -> Result<(), String> {
+    let output = ToolCommand::new(&check.command)
+        .current_dir(root)
+        .args(format_args(check, path))
+        .stdin(content)
+        .timeout(check.timeout_ms.map(Duration::from_millis).unwrap_or(FORMAT_TIMEOUT))
+        .run()
+        .map_err(|e| format!("failed to run {}: {}", check.command, e))?;
+    if output.status.success() {
+        return Ok(());
+    }
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Err(text)
+}
+
+fn format_args(check: &FormatCheck, path: &str) -> Vec<String> {
+    check.args.iter().map(|arg| arg.replace("{file}", path)).collect()
+}
+
+/// `3 errors, 1 warning, 1 unformatted file in 2 of 10 files checked`
+fn summary(problems: &[Problem], format_failures: usize, files: usize) -> String {
+    let count = |severity: u8| problems.iter().filter(|p| p.severity == severity).count();
+    let plural = |n: usize, word: &str| format!("{} {}{}", n, word, if n == 1 { "" } else { "s" });
+
+    let mut parts = vec![plural(count(1), "error"), plural(count(2), "warning")];
+    let notes = count(3) + count(4);
+    if notes > 0 {
+        parts.push(plural(notes, "note"));
+    }
+    if format_failures > 0 {
+        parts.push(plural(format_failures, "unformatted file"));
+    }
+    let mut affected: Vec<&str> = problems.iter().map(|p| p.path.as_str()).collect();
+    affected.sort_unstable();
+    affected.dedup();
+    format!("{} in {} of {} checked", parts.join(", "), affected.len(), plural(files, "file"))
+}
+
+impl Problem {
+    fn new(path: &str, diagnostic: &Value) -> Self {
+        let start = &diagnostic["range"]["start"];
+        let code = match &diagnostic["code"] {
+            Value::String(code) => Some(code.clone()),
+            Value::Number(code) => Some(code.to_string()),
+            _ => None,
+        };
+        Self {
+            path: path.to_string(),
+            line: start["line"].as_u64().unwrap_or(0),
+            character: start["character"].as_u64().unwrap_or(0),
+            // LSP leaves a missing severity to the client; editors show errors
+            severity: diagnostic["severity"].as_u64().map_or(1, |s| s.clamp(1, 4) as u8),
+            code,
+            message: diagnostic["message"].as_str().unwrap_or_default().to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let severity = SEVERITY_NAMES[self.severity as usize - 1];
+        write!(f, "{}:{}:{}: {}", self.path, self.line + 1, self.character + 1, severity)?;
+        if let Some(code) = &self.code {
+            write!(f, " [{}]", code)?;
+        }
+        write!(f, " {}", self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let options = parse_args(&args(&["--staged", "--fail-on", "warning", "repo"])).unwrap().unwrap();
+        assert_eq!(options, Options { dir: PathBuf::from("repo"), staged: true, fail_on: 2 });
+
+        let options = parse_args(&[]).unwrap().unwrap();
+        assert_eq!(options, Options { dir: PathBuf::from("."), staged: false, fail_on: 1 });
+
+        assert_eq!(parse_args(&args(&["--fail-on", "info"])).unwrap().unwrap().fail_on, 3);
+        assert!(parse_args(&args(&["--fail-on", "fatal"])).is_err());
+        assert!(parse_args(&args(&["a", "b"])).is_err());
+        assert!(parse_args(&args(&["--help"])).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_report() {
+        let diagnostic = json!({
+            "range": { "start": { "line": 2, "character": 4 }, "end": { "line": 2, "character": 9 } },
+            "severity": 2,
+            "code": "unused-import",
+            "message": "`os` is imported but never used"
+        });
+        let problem = Problem::new("src/app.py", &diagnostic);
+        assert_eq!(problem.to_string(), "src/app.py:3:5: warning [unused-import] `os` is imported but never used");
+
+        let error = Problem::new("src/app.py", &json!({ "message": "syntax error" }));
+        assert_eq!(error.to_string(), "src/app.py:1:1: error syntax error");
+
+        assert_eq!(summary(&[problem, error], 1, 4), "1 error, 1 warning, 1 unformatted file in 1 of 4 files checked");
+    }
+
+    #[test]
+    fn test_format_check_args() {
+        let check: FormatCheck = serde_json::from_value(json!({
+            "files": "**/*.rs",
+            "command": "rustfmt",
+            "args": ["--check", "--stdin-filepath", "{file}"]
+        }))
+        .unwrap();
+        assert!(matches_file(&check, "src/main.rs"));
+        assert!(!matches_file(&check, "src/main.py"));
+        assert_eq!(format_args(&check, "src/main.rs"), vec!["--check", "--stdin-filepath", "src/main.rs"]);
+    }
+}
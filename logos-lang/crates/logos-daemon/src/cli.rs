@@ -0,0 +1,79 @@
+//! Command-line subcommands that run once and exit instead of serving
+//!
+//! `logos-daemon tags <dir> [-o <file>]` indexes `<dir>` and writes its
+//! symbols as a universal-ctags compatible tags file (default `tags`, `-`
+//! for stdout).
+//!
+//! `logos-daemon check [--staged] [<dir>]` reports the diagnostics and
+//! format problems of a workspace, or of its staged changes, and fails
+//! when there are any; see [`crate::check`].
+
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use logos_index::ProjectIndexer;
+
+const USAGE: &str = "usage: logos-daemon tags <dir> [-o <file>]";
+
+/// Run the subcommand named by `args` (without the program name). `None`
+/// when there is none and the daemon should serve; otherwise the exit code.
+pub fn run(args: &[String]) -> Option<i32> {
+    let (command, rest) = args.split_first()?;
+    let result = match command.as_str() {
+        "tags" => tags(rest).map(|()| 0),
+        "check" => crate::check::run(rest),
+        _ => return None,
+    };
+    Some(match result {
+        Ok(code) => code,
+        Err(message) => {
+            eprintln!("logos-daemon: {}", message);
+            1
+        }
+    })
+}
+
+fn tags(args: &[String]) -> Result<(), String> {
+    let mut dir = None;
+    let mut output = PathBuf::from("tags");
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-o" | "--output" => output = args.next().ok_or(USAGE)?.into(),
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return Ok(());
+            }
+            _ if dir.is_none() => dir = Some(PathBuf::from(arg)),
+            _ => return Err(USAGE.to_string()),
+        }
+    }
+    let dir = dir.ok_or(USAGE)?;
+    let dir = dir
+        .canonicalize()
+        .map_err(|e| format!("{}: {}", dir.display(), e))?;
+
+    let indexer = ProjectIndexer::new();
+    let stats = indexer.index_directory(&dir)?;
+    let symbols = &indexer.get_index().symbols;
+
+    let count = if output.as_os_str() == "-" {
+        let mut out = io::stdout().lock();
+        logos_index::write_tags(symbols, &dir, &mut out).and_then(|count| out.flush().map(|_| count))
+    } else {
+        let file = File::create(&output).map_err(|e| format!("{}: {}", output.display(), e))?;
+        let mut out = BufWriter::new(file);
+        logos_index::write_tags(symbols, &tags_base(&output), &mut out).and_then(|count| out.flush().map(|_| count))
+    }
+    .map_err(|e| format!("{}: {}", output.display(), e))?;
+
+    eprintln!("Wrote {} tags from {} files", count, stats.files_indexed);
+    Ok(())
+}
+
+/// The directory tag paths are relative to: where the tags file is
+fn tags_base(output: &Path) -> PathBuf {
+    let parent = output.parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    parent.canonicalize().unwrap_or_else(|_| parent.to_path_buf())
+}
@@ -0,0 +1,489 @@
+//! Server settings and client capabilities
+//!
+//! Settings arrive through `initializationOptions` and
+//! `workspace/didChangeConfiguration`, either bare or nested under `logos`.
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use serde::Deserialize;
+use serde_json::Value;
+use logos_analysis::OutlineOptions;
+use logos_core::{CompletionInsertMode, DiagnosticSeverity};
+use logos_index::NameMatch;
+use logos_parser::conditional::CfgConfig;
+use logos_parser::grammar::GrammarSpec;
+
+use crate::hooks::HookEvent;
+
+/// User-configurable settings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    pub completion: CompletionSettings,
+    pub plugins: PluginSettings,
+    pub hooks: HookSettings,
+    pub index: IndexSettings,
+    pub cpp: CppSettings,
+    pub cfg: CfgSettings,
+    pub inlay_hints: InlayHintSettings,
+    pub diagnostics: DiagnosticsSettings,
+    pub coverage: CoverageSettings,
+    pub search: SearchSettings,
+    pub outline: OutlineSettings,
+    pub refactor: RefactorSettings,
+    pub check: CheckSettings,
+    /// Tree-sitter grammars loaded from shared libraries; relative paths
+    /// are resolved against the workspace root
+    pub grammars: Vec<GrammarSpec>,
+    /// Whether the workspace may run code; the client decides. Settings
+    /// without it keep the trust granted before.
+    pub trust: Option<WorkspaceTrust>,
+}
+
+/// Trust in the workspace, as granted by the client. A workspace is
+/// restricted until the client trusts it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WorkspaceTrust {
+    Trusted,
+    /// Analysis only: nothing the workspace configures is executed or loaded
+    #[default]
+    Restricted,
+}
+
+impl WorkspaceTrust {
+    /// Features turned off in restricted mode, as named in the `initialize`
+    /// response: hook commands, plugin libraries, grammar libraries, and git
+    /// (whose repository config can name commands to run)
+    pub const RESTRICTED_FEATURES: &'static [&'static str] = &["hooks", "plugins", "grammars", "gitHistory"];
+
+    pub fn is_trusted(self) -> bool {
+        self == Self::Trusted
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CompletionSettings {
+    /// `snippet`, `plainText` or `callWithParens`
+    pub insert_mode: CompletionInsertMode,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PluginSettings {
+    /// Directory scanned for plugin libraries at startup; defaults to
+    /// `$LOGOS_PLUGINS_DIR`, then `~/.logos/plugins`
+    pub directory: Option<String>,
+}
+
+impl PluginSettings {
+    pub fn resolved_directory(&self) -> Option<PathBuf> {
+        if let Some(ref dir) = self.directory {
+            return Some(PathBuf::from(dir));
+        }
+        if let Some(dir) = std::env::var_os("LOGOS_PLUGINS_DIR") {
+            return Some(PathBuf::from(dir));
+        }
+        let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+        Some(PathBuf::from(home).join(".logos").join("plugins"))
+    }
+}
+
+/// Project index persistence
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IndexSettings {
+    /// Save the Smart mode index and restore it on the next start
+    pub persist: bool,
+    /// Directory for saved indexes; defaults to `~/.logos/index`
+    pub directory: Option<String>,
+    /// Analyze every file of the workspace after startup, so workspace
+    /// symbols and TODOs aren't limited to open documents
+    pub scan: bool,
+    /// Files and directories left out of indexing and the scan, as
+    /// `.gitignore` patterns relative to the workspace root (`vendor/`,
+    /// `**/*.generated.ts`), on top of the `.gitignore` and `.logosignore`
+    /// files. Hidden entries and `node_modules`, `target`, `dist`, `build`
+    /// and `__pycache__` are always left out.
+    pub exclude: Vec<String>,
+    /// Megabytes the documentation and types of indexed symbols may take.
+    /// Past it, those of the least recently used files are dropped and the
+    /// files analyzed again when needed. Unlimited by default.
+    pub memory_budget_mb: Option<u64>,
+}
+
+impl Default for IndexSettings {
+    fn default() -> Self {
+        Self { persist: true, directory: None, scan: true, exclude: Vec::new(), memory_budget_mb: None }
+    }
+}
+
+impl IndexSettings {
+    /// The memory budget for symbol details, in bytes
+    pub fn memory_budget(&self) -> Option<usize> {
+        self.memory_budget_mb.map(|mb| (mb as usize).saturating_mul(1024 * 1024))
+    }
+
+    /// File the index of the workspace at `root` is saved to. The name
+    /// carries a hash of the full path so that workspaces sharing a
+    /// directory name don't collide.
+    pub fn cache_file(&self, root: &Path) -> Option<PathBuf> {
+        if !self.persist {
+            return None;
+        }
+        let dir = match self.directory {
+            Some(ref dir) => PathBuf::from(dir),
+            None => {
+                let home = std::env::var_os("HOME").or_else(|| std::env::var_os("USERPROFILE"))?;
+                PathBuf::from(home).join(".logos").join("index")
+            }
+        };
+        // FNV-1a, stable across releases unlike `DefaultHasher`
+        let hash = root.to_string_lossy().bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        });
+        let name = root.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+        Some(dir.join(format!("{}-{:016x}.json", name, hash)))
+    }
+}
+
+/// C and C++ build information
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CppSettings {
+    /// `compile_commands.json` whose include directories and defines
+    /// resolve `#include`s; relative to the workspace root
+    pub compile_commands: Option<String>,
+    /// Directories `#include`s are looked up in after those of the
+    /// compilation database; relative to the workspace root
+    pub include_paths: Vec<String>,
+}
+
+impl CppSettings {
+    pub fn compile_commands_path(&self, root: &Path) -> Option<PathBuf> {
+        self.compile_commands.as_deref().map(|path| root.join(path))
+    }
+
+    pub fn include_paths(&self, root: &Path) -> Vec<PathBuf> {
+        self.include_paths.iter().map(|path| root.join(path)).collect()
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct DiagnosticsSettings {
+    /// Baseline of accepted diagnostics, relative to the workspace root;
+    /// defaults to `.logos/diagnostics-baseline.json`
+    pub baseline: Option<String>,
+    /// Severity per diagnostic code, e.g. `{ "unused-import": "off" }`
+    pub severity: HashMap<String, RuleSeverity>,
+    /// Severities for files matching a glob; later entries win
+    pub overrides: Vec<SeverityOverride>,
+}
+
+/// Severity a diagnostic code is reported with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum RuleSeverity {
+    Error,
+    Warning,
+    #[serde(alias = "info")]
+    Information,
+    Hint,
+    /// Not reported at all
+    Off,
+}
+
+impl RuleSeverity {
+    /// LSP severity, `None` for `off`
+    pub fn to_lsp(self) -> Option<u8> {
+        match self {
+            Self::Error => Some(DiagnosticSeverity::Error as u8),
+            Self::Warning => Some(DiagnosticSeverity::Warning as u8),
+            Self::Information => Some(DiagnosticSeverity::Information as u8),
+            Self::Hint => Some(DiagnosticSeverity::Hint as u8),
+            Self::Off => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SeverityOverride {
+    /// Path glob, relative to the workspace root unless it starts with `/`;
+    /// see [`logos_index::PathGlob`]
+    pub files: String,
+    pub severity: HashMap<String, RuleSeverity>,
+}
+
+impl DiagnosticsSettings {
+    pub fn baseline_path(&self, root: &Path) -> PathBuf {
+        root.join(self.baseline.as_deref().unwrap_or(".logos/diagnostics-baseline.json"))
+    }
+}
+
+/// `logos-daemon check` settings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CheckSettings {
+    /// Format checks, each run on the checked files its glob matches
+    pub format: Vec<FormatCheck>,
+}
+
+/// A formatter run in check mode, e.g. `rustfmt --check`
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FormatCheck {
+    /// Path glob, relative to the workspace root unless it starts with `/`;
+    /// see [`logos_index::PathGlob`]
+    pub files: String,
+    /// Run in the workspace root with the file's content on stdin; a
+    /// non-zero exit fails the file
+    pub command: String,
+    /// `{file}` in an argument stands for the file's relative path
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+/// Test coverage reports shown in code lenses
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CoverageSettings {
+    /// lcov, coverage.py JSON, JaCoCo XML or Go cover reports, relative to
+    /// the workspace root; defaults to the usual output locations
+    pub reports: Option<Vec<String>>,
+    /// Show a coverage lens above each function
+    pub code_lens: bool,
+}
+
+impl Default for CoverageSettings {
+    fn default() -> Self {
+        Self { reports: None, code_lens: true }
+    }
+}
+
+impl CoverageSettings {
+    pub fn report_paths(&self, root: &Path) -> Vec<PathBuf> {
+        const DEFAULT_REPORTS: [&str; 6] = [
+            "coverage/lcov.info",
+            "lcov.info",
+            "coverage.json",
+            "coverage.out",
+            "target/site/jacoco/jacoco.xml",
+            "build/reports/jacoco/test/jacocoTestReport.xml",
+        ];
+        match &self.reports {
+            Some(reports) => reports.iter().map(|report| root.join(report)).collect(),
+            None => DEFAULT_REPORTS.iter().map(|report| root.join(report)).collect(),
+        }
+    }
+}
+
+/// Active preprocessor defines and Rust features. Code they compile out is
+/// greyed and its symbols are dropped; a list left unset keeps every branch
+/// that depends on it.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CfgSettings {
+    /// `NAME` or `NAME=value` entries, as given to `-D`
+    pub defines: Option<Vec<String>>,
+    /// Enabled Cargo features
+    pub features: Option<Vec<String>>,
+}
+
+impl CfgSettings {
+    pub fn to_config(&self) -> CfgConfig {
+        let defines = self.defines.as_ref().map(|defines| {
+            defines
+                .iter()
+                .map(|define| match define.split_once('=') {
+                    Some((name, value)) => (name.to_string(), Some(value.to_string())),
+                    None => (define.clone(), None),
+                })
+                .collect::<HashMap<_, _>>()
+        });
+        let features = self.features.as_ref().map(|f| f.iter().cloned().collect::<HashSet<_>>());
+        CfgConfig { defines, features }
+    }
+}
+
+/// Matching of workspace symbol queries
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct SearchSettings {
+    /// Ignore diacritics and spell out letters such as `ß` and `æ`, so
+    /// `cafe` finds `café`
+    pub ignore_diacritics: bool,
+}
+
+impl SearchSettings {
+    pub fn name_match(&self) -> NameMatch {
+        if self.ignore_diacritics {
+            NameMatch::Folded
+        } else {
+            NameMatch::Normalized
+        }
+    }
+}
+
+/// Document outlines of very large files
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct OutlineSettings {
+    /// Symbols in a `documentSymbol` response; documents with more get a
+    /// summarized outline whose left-out parts are fetched with
+    /// `logos/getOutlinePage`. 0 turns summarizing off.
+    pub max_symbols: usize,
+    /// In a summarized outline, put the leaf fields, variables and
+    /// constants of each list behind one placeholder
+    pub collapse_trivial: bool,
+}
+
+impl Default for OutlineSettings {
+    fn default() -> Self {
+        Self { max_symbols: 2000, collapse_trivial: true }
+    }
+}
+
+impl OutlineSettings {
+    pub fn options(&self) -> OutlineOptions {
+        OutlineOptions { max_symbols: self.max_symbols, collapse_trivial: self.collapse_trivial }
+    }
+}
+
+/// Renames and other refactorings
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RefactorSettings {
+    /// Edit declarations in generated files (protobuf output, minified or
+    /// `@generated` code) too; by default only the one under the cursor is
+    pub include_generated: bool,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct InlayHintSettings {
+    pub complexity: ComplexityHintSettings,
+}
+
+/// Badges at the end of function signatures whose complexity or length
+/// passes a threshold
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ComplexityHintSettings {
+    pub enabled: bool,
+    /// Thresholds of languages without an entry in `languages`
+    #[serde(flatten)]
+    pub thresholds: ComplexityThresholds,
+    /// Per-language overrides, keyed by language id
+    pub languages: HashMap<String, ComplexityOverride>,
+}
+
+impl ComplexityHintSettings {
+    /// Thresholds for a language, or `None` if its badges are off
+    pub fn thresholds_for(&self, language: &str) -> Option<ComplexityThresholds> {
+        let Some(language) = self.languages.get(language) else {
+            return self.enabled.then_some(self.thresholds);
+        };
+        language.enabled.unwrap_or(self.enabled).then(|| ComplexityThresholds {
+            max_complexity: language.max_complexity.unwrap_or(self.thresholds.max_complexity),
+            max_lines: language.max_lines.unwrap_or(self.thresholds.max_lines),
+        })
+    }
+}
+
+/// A badge is shown once either value is exceeded
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ComplexityThresholds {
+    pub max_complexity: u32,
+    pub max_lines: u32,
+}
+
+impl Default for ComplexityThresholds {
+    fn default() -> Self {
+        Self { max_complexity: 10, max_lines: 60 }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ComplexityOverride {
+    pub enabled: Option<bool>,
+    pub max_complexity: Option<u32>,
+    pub max_lines: Option<u32>,
+}
+
+/// External commands run on server events
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HookSettings {
+    pub will_save: Vec<HookCommand>,
+    pub did_save: Vec<HookCommand>,
+    pub index_complete: Vec<HookCommand>,
+    pub diagnostics_published: Vec<HookCommand>,
+}
+
+impl HookSettings {
+    pub fn commands_for(&self, event: HookEvent) -> &[HookCommand] {
+        match event {
+            HookEvent::WillSave => &self.will_save,
+            HookEvent::DidSave => &self.did_save,
+            HookEvent::IndexComplete => &self.index_complete,
+            HookEvent::DiagnosticsPublished => &self.diagnostics_published,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookCommand {
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Working directory; defaults to the workspace root
+    #[serde(default)]
+    pub cwd: Option<String>,
+    /// Kill the command after this long; defaults to 10 seconds
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+impl Settings {
+    /// Parse settings, accepting both `{ "logos": { .. } }` and `{ .. }`.
+    /// Invalid input falls back to defaults.
+    pub fn from_value(value: &Value) -> Self {
+        let value = value.get("logos").unwrap_or(value);
+        serde_json::from_value(value.clone()).unwrap_or_else(|e| {
+            log::warn!("Ignoring invalid settings: {}", e);
+            Self::default()
+        })
+    }
+}
+
+/// Client features the server adapts to
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClientCapabilities {
+    pub snippet_support: bool,
+    /// Whether the client shows progress the server starts with
+    /// `window/workDoneProgress/create`
+    pub work_done_progress: bool,
+}
+
+impl ClientCapabilities {
+    pub fn from_value(capabilities: &Value) -> Self {
+        Self {
+            snippet_support: capabilities
+                .pointer("/textDocument/completion/completionItem/snippetSupport")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+            work_done_progress: capabilities
+                .pointer("/window/workDoneProgress")
+                .and_then(Value::as_bool)
+                .unwrap_or(false),
+        }
+    }
+}
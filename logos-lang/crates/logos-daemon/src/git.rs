@@ -0,0 +1,117 @@
+//! Git history queries
+//!
+//! Runs the `git` executable found on `PATH`; a workspace without git or
+//! outside a repository gets an error, not an empty answer.
+
+use std::path::Path;
+use std::time::Duration;
+
+use crate::process::ToolCommand;
+
+/// Commits read for churn; older history rarely changes the picture
+const MAX_COMMITS: usize = 500;
+/// Time git gets before the request fails
+const GIT_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// How many commits touched each line of the committed version of `path`.
+///
+/// Walks the file's zero-context patches newest first, following each
+/// current line back through the hunks of every commit until the commit
+/// that added it, so edits above a line don't count against it. A line
+/// replaced by a hunk is matched to the line at the same offset on the
+/// hunk's old side.
+pub fn line_churn(path: &Path, line_count: usize) -> Result<Vec<u32>, String> {
+    let dir = path.parent().ok_or_else(|| format!("{} has no parent directory", path.display()))?;
+    let output = ToolCommand::new("git")
+        .current_dir(dir)
+        .args(["log", "--follow", "--no-merges", "--no-color", "--unified=0", "--format=%x1e"])
+        .arg(format!("--max-count={}", MAX_COMMITS))
+        .arg("--")
+        .arg(path.to_string_lossy())
+        // Never wait on a credential prompt
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .timeout(GIT_TIMEOUT)
+        .run()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let log = String::from_utf8_lossy(&output.stdout);
+
+    let mut churn = vec![0; line_count];
+    // Line of each current line (1-based) in the new side of the commit
+    // being read; `None` once the line's origin is passed
+    let mut mapping: Vec<Option<u32>> = (1..=line_count as u32).map(Some).collect();
+    for commit in log.split('\x1e').filter(|c| !c.trim().is_empty()) {
+        let hunks: Vec<Hunk> = commit.lines().filter_map(parse_hunk_header).collect();
+        for (current, line) in mapping.iter_mut().enumerate() {
+            let Some(at) = *line else {
+                continue;
+            };
+            if let Some(h) = hunks.iter().find(|h| (h.new_start..h.new_start + h.new_len).contains(&at)) {
+                churn[current] += 1;
+                let offset = at - h.new_start;
+                *line = (offset < h.old_len).then_some(h.old_start + offset);
+                continue;
+            }
+            let shift: i64 = hunks
+                .iter()
+                .filter(|h| h.new_start + h.new_len.max(1) <= at)
+                .map(|h| h.old_len as i64 - h.new_len as i64)
+                .sum();
+            *line = u32::try_from(at as i64 + shift).ok();
+        }
+    }
+    Ok(churn)
+}
+
+/// Files added, copied, modified or renamed in the staging area of the
+/// repository `dir` is in, relative to `dir`
+pub fn staged_files(dir: &Path) -> Result<Vec<String>, String> {
+    let output = git(dir, &["diff", "--cached", "--name-only", "--diff-filter=ACMR", "--relative", "-z"])?;
+    Ok(output.split('\0').filter(|path| !path.is_empty()).map(String::from).collect())
+}
+
+/// Staged content of `path`, relative to `dir`: what a commit would
+/// record, which may differ from the working tree
+pub fn staged_content(dir: &Path, path: &str) -> Result<String, String> {
+    git(dir, &["show", &format!(":./{}", path)])
+}
+
+/// Run git in `dir`, returning its stdout
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = ToolCommand::new("git")
+        .current_dir(dir)
+        .args(args.iter().copied())
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .timeout(GIT_TIMEOUT)
+        .run()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+struct Hunk {
+    old_start: u32,
+    old_len: u32,
+    new_start: u32,
+    new_len: u32,
+}
+
+/// `@@ -a[,b] +c[,d] @@`
+fn parse_hunk_header(line: &str) -> Option<Hunk> {
+    let ranges = line.strip_prefix("@@ -")?;
+    let (old, rest) = ranges.split_once(" +")?;
+    let (new, _) = rest.split_once(" @@")?;
+    let span = |range: &str| -> Option<(u32, u32)> {
+        match range.split_once(',') {
+            Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+            None => Some((range.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_len) = span(old)?;
+    let (new_start, new_len) = span(new)?;
+    Some(Hunk { old_start, old_len, new_start, new_len })
+}
@@ -1,9 +1,12 @@
-//! Analysis handlers: TODO items, unused symbols
+//! Analysis handlers: TODO items, unused symbols, inactive regions, AST dumps,
+//! bracket pairs
 
 use serde_json::{json, Value};
 use logos_index::TodoKind;
+use logos_parser::{brackets, dump, LanguageId};
+use tree_sitter::Point;
 
-use crate::protocol::{DocumentSymbolParams, RequestId, Response};
+use crate::protocol::{DocumentSymbolParams, DumpAstParams, RequestId, Response, TodoFilterParams};
 use crate::state::State;
 
 /// Handle logos/getTodoItems
@@ -20,7 +23,7 @@ pub fn get_todo_items(state: &State, params: &Value, id: Option<RequestId>) -> R
     };
 
     let uri = &params.text_document.uri;
-    let todos = state.todo_index.get_document_todos(uri);
+    let todos = state.analysis().todos(uri);
 
     let items: Vec<_> = todos.iter().map(|todo| {
         json!({
@@ -45,9 +48,24 @@ pub fn get_todo_items(state: &State, params: &Value, id: Option<RequestId>) -> R
     Response::success(id, json!(items))
 }
 
-/// Handle logos/getAllTodoItems
-pub fn get_all_todo_items(state: &State, id: Option<RequestId>) -> Response {
-    let todos = state.todo_index.get_all_todos();
+/// Handle logos/getAllTodoItems. Params may narrow the items by author,
+/// kinds, minimum priority and path glob.
+pub fn get_all_todo_items(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let filter = if params.is_null() {
+        TodoFilterParams::default()
+    } else {
+        match serde_json::from_value::<TodoFilterParams>(params.clone()) {
+            Ok(p) => p,
+            Err(e) => {
+                return Response::error(
+                    id,
+                    crate::protocol::error_codes::INVALID_PARAMS,
+                    format!("Invalid getAllTodoItems params: {}", e),
+                );
+            }
+        }
+    };
+    let todos = state.analysis().todo_index().query(&filter.into_filter());
 
     let items: Vec<_> = todos.iter().map(|(uri, todo)| {
         json!({
@@ -75,10 +93,11 @@ pub fn get_all_todo_items(state: &State, id: Option<RequestId>) -> Response {
 
 /// Handle logos/getTodoStats
 pub fn get_todo_stats(state: &State, id: Option<RequestId>) -> Response {
-    let count_by_kind = state.todo_index.count_by_kind();
+    let count_by_kind = state.analysis().todo_index().count_by_kind();
+    let count_by_author = state.analysis().todo_index().count_by_author();
 
     let stats = json!({
-        "total": state.todo_index.todo_count(),
+        "total": state.analysis().todo_index().todo_count(),
         "byKind": {
             "todo": count_by_kind.get(&TodoKind::Todo).unwrap_or(&0),
             "fixme": count_by_kind.get(&TodoKind::Fixme).unwrap_or(&0),
@@ -87,7 +106,8 @@ pub fn get_todo_stats(state: &State, id: Option<RequestId>) -> Response {
             "note": count_by_kind.get(&TodoKind::Note).unwrap_or(&0),
             "bug": count_by_kind.get(&TodoKind::Bug).unwrap_or(&0),
             "optimize": count_by_kind.get(&TodoKind::Optimize).unwrap_or(&0)
-        }
+        },
+        "byAuthor": count_by_author
     });
 
     Response::success(id, stats)
@@ -113,7 +133,7 @@ pub fn get_unused_symbols(state: &State, params: &Value, id: Option<RequestId>)
         None => return Response::success(id, json!([])),
     };
 
-    let symbols: Vec<_> = state.symbol_index.get_document_symbols(uri)
+    let symbols: Vec<_> = state.analysis().document_symbols(uri)
         .iter()
         .map(|s| logos_core::Symbol {
             name: s.name.clone(),
@@ -121,6 +141,7 @@ pub fn get_unused_symbols(state: &State, params: &Value, id: Option<RequestId>)
             range: s.range,
             selection_range: s.selection_range,
             detail: None,
+            documentation: None,
             children: Vec::new(),
         })
         .collect();
@@ -150,6 +171,83 @@ pub fn get_unused_symbols(state: &State, params: &Value, id: Option<RequestId>)
     Response::success(id, json!(items))
 }
 
+/// Handle logos/getInactiveRegions: ranges of a document compiled out by
+/// the configured defines and features, for the client to dim
+pub fn get_inactive_regions(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: DocumentSymbolParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid getInactiveRegions params: {}", e),
+            );
+        }
+    };
+
+    let uri = &params.text_document.uri;
+    let items: Vec<_> = state.analysis().inactive_regions(uri).iter().map(|range| {
+        json!({
+            "start": {
+                "line": range.start.line,
+                "character": range.start.column
+            },
+            "end": {
+                "line": range.end.line,
+                "character": range.end.column
+            }
+        })
+    }).collect();
+
+    Response::success(id, json!(items))
+}
+
+/// Handle logos/dumpAst: the parse tree of a document, or of the node
+/// covering `range`, as an S-expression string or a JSON node tree
+pub fn dump_ast(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: DumpAstParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid dumpAst params: {}", e),
+            );
+        }
+    };
+
+    let uri = &params.text_document.uri;
+    let analysis = state.analysis();
+    let (Some(doc), Some(tree)) = (analysis.document(uri), analysis.tree(uri)) else {
+        return Response::null_result(id);
+    };
+    let node = match &params.range {
+        Some(range) => {
+            let start = Point::new(range.start.line as usize, range.start.character as usize);
+            let end = Point::new(range.end.line as usize, range.end.character as usize);
+            tree.root_node().descendant_for_point_range(start, end).unwrap_or(tree.root_node())
+        }
+        None => tree.root_node(),
+    };
+
+    let format = params.format.as_deref().unwrap_or("sexp");
+    let ast = match format {
+        "sexp" => json!(dump::to_sexp(node, params.include_anonymous)),
+        "json" => json!(dump::to_ast(node, doc.content(), params.include_anonymous)),
+        other => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Unknown AST format: {}", other),
+            );
+        }
+    };
+    Response::success(id, json!({
+        "format": format,
+        "ast": ast
+    }))
+}
+
 fn todo_kind_to_string(kind: TodoKind) -> &'static str {
     match kind {
         TodoKind::Todo => "todo",
@@ -162,3 +260,46 @@ fn todo_kind_to_string(kind: TodoKind) -> &'static str {
         TodoKind::Custom => "custom",
     }
 }
+
+/// Handle logos/getBracketPairs: the bracket pairs of a document with
+/// their nesting depth, for rainbow brackets, and the indentation level of
+/// each line, for smart indent, both read from the parse tree
+pub fn get_bracket_pairs(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: DocumentSymbolParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid getBracketPairs params: {}", e),
+            );
+        }
+    };
+
+    let uri = &params.text_document.uri;
+    let analysis = state.analysis();
+    let (Some(doc), Some(tree)) = (analysis.document(uri), analysis.tree(uri)) else {
+        return Response::null_result(id);
+    };
+    let Some(lang) = LanguageId::from_str(&doc.language_id) else {
+        return Response::null_result(id);
+    };
+
+    let range = |range: &logos_core::Range| json!({
+        "start": { "line": range.start.line, "character": range.start.column },
+        "end": { "line": range.end.line, "character": range.end.column }
+    });
+    let pairs: Vec<_> = brackets::bracket_pairs(tree, doc.content())
+        .iter()
+        .map(|pair| json!({
+            "open": range(&pair.open),
+            "close": range(&pair.close),
+            "depth": pair.depth
+        }))
+        .collect();
+
+    Response::success(id, json!({
+        "pairs": pairs,
+        "indentLevels": brackets::indent_levels(lang, tree, doc.content())
+    }))
+}
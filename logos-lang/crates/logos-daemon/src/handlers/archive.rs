@@ -0,0 +1,130 @@
+//! Dependency archive handlers
+//!
+//! Sources packaged in `.jar`/`.whl`/`.crate` files are mounted in place and
+//! served to the client as `archive:` virtual documents.
+
+use serde_json::{json, Value};
+use logos_core::uri::uri_to_path;
+use logos_index::{is_archive, parse_archive_uri};
+use logos_parser::LanguageId;
+
+use crate::protocol::{ArchiveParams, RequestId, Response};
+use crate::state::State;
+
+/// Handle logos/indexArchive
+///
+/// Mounts the archive and, in Smart mode, indexes its sources so that
+/// definitions inside it can be navigated to.
+pub fn index_archive(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: ArchiveParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid indexArchive params: {}", e),
+            );
+        }
+    };
+
+    let path = match uri_to_path(&params.uri) {
+        Some(path) if is_archive(&path) => path,
+        _ => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Not a .jar, .whl or .crate file: {}", params.uri),
+            );
+        }
+    };
+
+    let entries = match state.archives.mount(&path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::REQUEST_FAILED,
+                format!("Failed to open archive {}: {}", params.uri, e),
+            );
+        }
+    };
+
+    let files_indexed = match state.get_indexer() {
+        Some(indexer) => match indexer.index_directory(&path) {
+            Ok(stats) => stats.files_indexed,
+            Err(e) => {
+                log::warn!("Failed to index archive {}: {}", params.uri, e);
+                0
+            }
+        },
+        None => 0,
+    };
+    log::info!("Mounted {} ({} entries, {} indexed)", params.uri, entries, files_indexed);
+
+    Response::success(
+        id,
+        json!({
+            "uri": params.uri,
+            "entries": entries,
+            "filesIndexed": files_indexed,
+        }),
+    )
+}
+
+/// Handle logos/archiveContent
+///
+/// Returns the text of an `archive:` document, mounting its archive on first use.
+pub fn archive_content(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: ArchiveParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid archiveContent params: {}", e),
+            );
+        }
+    };
+
+    let Some((archive, entry)) = parse_archive_uri(&params.uri) else {
+        return Response::error(
+            id,
+            crate::protocol::error_codes::INVALID_PARAMS,
+            format!("Not an archive URI: {}", params.uri),
+        );
+    };
+
+    if !state.archives.is_mounted(&archive) {
+        if let Err(e) = state.archives.mount(&archive) {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::REQUEST_FAILED,
+                format!("Failed to open archive {}: {}", archive.display(), e),
+            );
+        }
+    }
+
+    let path = archive.join(&entry);
+    match state.vfs.read_to_string(&path) {
+        Ok(content) => {
+            let language_id = entry
+                .extension()
+                .and_then(|e| e.to_str())
+                .and_then(LanguageId::from_extension)
+                .map(|lang| lang.as_str());
+            Response::success(
+                id,
+                json!({
+                    "uri": params.uri,
+                    "languageId": language_id,
+                    "content": content,
+                }),
+            )
+        }
+        Err(e) => Response::error(
+            id,
+            crate::protocol::error_codes::REQUEST_FAILED,
+            format!("Failed to read {}: {}", params.uri, e),
+        ),
+    }
+}
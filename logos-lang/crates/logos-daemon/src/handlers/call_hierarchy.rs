@@ -192,7 +192,7 @@ pub fn handle_outgoing_calls(state: &State, params: &Value, id: Option<RequestId
     Response::success(id, json!(outgoing_calls))
 }
 
-fn range_to_serializable(range: &logos_core::Range) -> SerializableRange {
+pub(crate) fn range_to_serializable(range: &logos_core::Range) -> SerializableRange {
     SerializableRange {
         start: SerializablePosition {
             line: range.start.line,
@@ -205,7 +205,7 @@ fn range_to_serializable(range: &logos_core::Range) -> SerializableRange {
     }
 }
 
-fn symbol_kind_to_lsp(kind: logos_core::SymbolKind) -> i32 {
+pub(crate) fn symbol_kind_to_lsp(kind: logos_core::SymbolKind) -> i32 {
     use logos_core::SymbolKind;
     match kind {
         SymbolKind::File => 1,
@@ -0,0 +1,79 @@
+//! Code actions handler
+//!
+//! Offers one quick fix per diagnostic in the request context: a
+//! `logos-ignore-next-line` comment above the diagnostic's line, naming its
+//! rule when it has one.
+
+use std::collections::HashSet;
+
+use serde_json::{json, Value};
+use logos_core::DiagnosticTag;
+use logos_parser::suppression::suppression_edit;
+use logos_parser::LanguageId;
+
+use crate::protocol::{CodeActionParams, RequestId, Response};
+use crate::state::State;
+
+/// Handle textDocument/codeAction
+pub fn handle(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: CodeActionParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid codeAction params: {}", e),
+            );
+        }
+    };
+
+    let uri = &params.text_document.uri;
+    let Some(doc) = state.get_document(uri) else {
+        return Response::success(id, json!([]));
+    };
+    let Some(language) = LanguageId::from_str(&doc.language_id) else {
+        return Response::success(id, json!([]));
+    };
+
+    let mut seen = HashSet::new();
+    let mut actions = Vec::new();
+    for diagnostic in &params.context.diagnostics {
+        // Faded hints such as inactive code are not problems to silence
+        let tags = diagnostic.get("tags").and_then(Value::as_array);
+        if tags.is_some_and(|tags| tags.iter().any(|t| t.as_u64() == Some(DiagnosticTag::Unnecessary as u64))) {
+            continue;
+        }
+        let Some(line) = diagnostic.pointer("/range/start/line").and_then(Value::as_u64) else {
+            continue;
+        };
+        let code = match diagnostic.get("code") {
+            Some(Value::String(code)) => Some(code.clone()),
+            Some(Value::Number(code)) => Some(code.to_string()),
+            _ => None,
+        };
+        if !seen.insert((line, code.clone())) {
+            continue;
+        }
+        let Some((position, text)) = suppression_edit(language, doc.content(), line as u32, code.as_deref()) else {
+            continue;
+        };
+
+        let title = match &code {
+            Some(code) => format!("Suppress {} on this line", code),
+            None => "Suppress all diagnostics on this line".to_string(),
+        };
+        let at = json!({ "line": position.line, "character": position.column });
+        actions.push(json!({
+            "title": title,
+            "kind": "quickfix",
+            "diagnostics": [diagnostic],
+            "edit": {
+                "changes": {
+                    uri.as_str(): [{ "range": { "start": at, "end": at }, "newText": text }]
+                }
+            }
+        }));
+    }
+
+    Response::success(id, json!(actions))
+}
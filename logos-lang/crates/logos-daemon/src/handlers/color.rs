@@ -0,0 +1,73 @@
+//! Document color handlers
+//!
+//! Colors come from stylesheets and from the style regions of other
+//! documents; presentations offer the hex, `rgb()` and `hsl()` spellings.
+
+use serde_json::{json, Value};
+use logos_parser::css::{self, Color};
+
+use crate::protocol::{ColorPresentationParams, DocumentSymbolParams, RequestId, Response};
+use crate::state::State;
+
+/// Handle textDocument/documentColor
+pub fn document_colors(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: DocumentSymbolParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid documentColor params: {}", e),
+            );
+        }
+    };
+
+    let colors: Vec<_> = state
+        .analysis()
+        .document_colors(&params.text_document.uri)
+        .iter()
+        .map(|info| {
+            json!({
+                "range": {
+                    "start": { "line": info.range.start.line, "character": info.range.start.column },
+                    "end": { "line": info.range.end.line, "character": info.range.end.column }
+                },
+                "color": {
+                    "red": info.color.red,
+                    "green": info.color.green,
+                    "blue": info.color.blue,
+                    "alpha": info.color.alpha
+                }
+            })
+        })
+        .collect();
+
+    Response::success(id, json!(colors))
+}
+
+/// Handle textDocument/colorPresentation
+pub fn color_presentations(params: &Value, id: Option<RequestId>) -> Response {
+    let params: ColorPresentationParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid colorPresentation params: {}", e),
+            );
+        }
+    };
+
+    let color = Color {
+        red: params.color.red,
+        green: params.color.green,
+        blue: params.color.blue,
+        alpha: params.color.alpha,
+    };
+    let presentations: Vec<_> = css::color_presentations(color)
+        .into_iter()
+        .map(|label| json!({ "label": label }))
+        .collect();
+
+    Response::success(id, json!(presentations))
+}
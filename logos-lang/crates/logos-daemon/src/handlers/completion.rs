@@ -1,7 +1,10 @@
 //! Completion handler
 
+use std::collections::HashSet;
 use serde_json::{json, Value};
-use logos_core::SymbolKind;
+use logos_core::completion::insert_text;
+use logos_core::{Position, SymbolKind};
+use logos_parser::LanguageId;
 
 use crate::protocol::{TextDocumentPositionParams, RequestId, Response};
 use crate::state::State;
@@ -29,21 +32,23 @@ pub fn handle(state: &State, params: &Value, id: Option<RequestId>) -> Response
     };
 
     let mut completions = Vec::new();
+    // Labels already offered; the same symbol may be indexed more than once
+    let mut seen = HashSet::new();
 
-    // Add keyword completions based on language
-    let keywords = match doc.language_id.as_str() {
-        "python" => logos_parser::python::get_keywords(),
-        "go" => logos_parser::go::get_keywords(),
-        "rust" => logos_parser::rust_lang::get_keywords(),
-        "c" => logos_parser::c::get_keywords(),
-        "cpp" => logos_parser::cpp::get_keywords(),
-        "java" => logos_parser::java::get_keywords(),
-        "javascript" => logos_parser::javascript::get_keywords(),
-        "typescript" => logos_parser::typescript::get_keywords(),
-        _ => &[],
-    };
+    // Add keyword completions based on language; inside an embedded
+    // region (a `language=sql` string) those of its language
+    let position = Position::new(params.position.line, params.position.character);
+    let keywords = state
+        .analysis()
+        .language_at(uri, position)
+        .or_else(|| LanguageId::from_str(&doc.language_id))
+        .map(logos_parser::keywords)
+        .unwrap_or(&[]);
 
     for kw in keywords {
+        if !seen.insert(kw.to_string()) {
+            continue;
+        }
         completions.push(json!({
             "label": kw,
             "kind": 14, // Keyword
@@ -51,13 +56,31 @@ pub fn handle(state: &State, params: &Value, id: Option<RequestId>) -> Response
         }));
     }
 
+    // In Smart mode, hide members the cursor has no access to
+    let hidden = inaccessible_symbols(state, uri, position);
+
     // Add symbols from index
-    for symbol in state.symbol_index.get_document_symbols(uri) {
-        completions.push(json!({
+    let insert_mode = state.completion_insert_mode();
+    for symbol in state.analysis().document_symbols(uri) {
+        if hidden.contains(&(symbol.name.clone(), symbol.selection_range.start)) {
+            continue;
+        }
+        if !seen.insert(symbol.name.clone()) {
+            continue;
+        }
+        let insert = insert_text(&symbol.name, symbol.kind, symbol.detail.as_deref(), insert_mode);
+        let mut item = json!({
             "label": symbol.name,
             "kind": symbol_kind_to_completion_kind(symbol.kind),
-            "detail": format!("{:?}", symbol.kind)
-        }));
+            "detail": format!("{:?}", symbol.kind),
+            "insertText": insert.text,
+            "insertTextFormat": insert.format as u8
+        });
+        // Symbols of generated files sort after every label
+        if symbol.generated {
+            item["sortText"] = json!(format!("~{}", symbol.name));
+        }
+        completions.push(item);
     }
 
     Response::success(id, json!({
@@ -66,6 +89,22 @@ pub fn handle(state: &State, params: &Value, id: Option<RequestId>) -> Response
     }))
 }
 
+/// Name and selection start of the document's symbols that the Smart index
+/// marks as private or protected to another scope
+fn inaccessible_symbols(state: &State, uri: &str, position: Position) -> HashSet<(String, Position)> {
+    let Some(indexer) = state.get_indexer() else {
+        return HashSet::new();
+    };
+    let index = indexer.get_index();
+    index
+        .symbols
+        .get_file_symbols(uri)
+        .into_iter()
+        .filter(|s| !index.is_accessible(s, uri, position))
+        .map(|s| (s.name, s.location.selection_range.start))
+        .collect()
+}
+
 fn symbol_kind_to_completion_kind(kind: SymbolKind) -> u32 {
     match kind {
         SymbolKind::Function | SymbolKind::Method => 3,  // Function
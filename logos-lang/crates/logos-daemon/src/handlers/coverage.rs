@@ -0,0 +1,118 @@
+//! Coverage handlers: per-line hit counts and per-function code lenses
+
+use serde_json::{json, Value};
+use logos_core::uri::uri_to_path;
+use logos_core::SymbolKind;
+use logos_index::{CoverageSummary, FileCoverage};
+
+use crate::protocol::{DocumentSymbolParams, RequestId, Response};
+use crate::state::State;
+
+/// Handle logos/getCoverage: hit counts of the executable lines of a
+/// document and the coverage of each of its functions. Null when no
+/// report covers the document.
+pub fn get_coverage(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: DocumentSymbolParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid getCoverage params: {}", e),
+            );
+        }
+    };
+
+    let uri = &params.text_document.uri;
+    let Some(file) = file_coverage(state, uri) else {
+        return Response::null_result(id);
+    };
+    let lines: Vec<_> = file.lines.iter().map(|(line, hits)| json!({ "line": line, "hits": hits })).collect();
+    let symbols: Vec<_> = function_summaries(state, uri, file)
+        .into_iter()
+        .map(|(symbol, summary)| {
+            json!({
+                "name": symbol.name,
+                "range": {
+                    "start": { "line": symbol.range.start.line, "character": symbol.range.start.column },
+                    "end": { "line": symbol.range.end.line, "character": symbol.range.end.column }
+                },
+                "covered": summary.covered,
+                "total": summary.total
+            })
+        })
+        .collect();
+    let total = file.total();
+    Response::success(id, json!({
+        "lines": lines,
+        "symbols": symbols,
+        "covered": total.covered,
+        "total": total.total
+    }))
+}
+
+/// Handle logos/reloadCoverage: re-read the reports after a test run
+pub fn reload_coverage(state: &mut State, id: Option<RequestId>) -> Response {
+    state.load_coverage();
+    let files = state.coverage.as_ref().map_or(0, |coverage| coverage.len());
+    Response::success(id, json!({ "files": files }))
+}
+
+/// Handle textDocument/codeLens: `covered/total lines (percent)` above each
+/// function with executable lines
+pub fn code_lens(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: DocumentSymbolParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid codeLens params: {}", e),
+            );
+        }
+    };
+
+    let uri = &params.text_document.uri;
+    let file = match file_coverage(state, uri) {
+        Some(file) if state.settings.coverage.code_lens => file,
+        _ => return Response::success(id, json!([])),
+    };
+    let lenses: Vec<_> = function_summaries(state, uri, file)
+        .into_iter()
+        .filter_map(|(symbol, summary)| {
+            let percent = summary.percent()?;
+            let line = symbol.selection_range.start.line;
+            Some(json!({
+                "range": {
+                    "start": { "line": line, "character": 0 },
+                    "end": { "line": line, "character": 0 }
+                },
+                "command": {
+                    "title": format!("{}/{} lines covered ({:.0}%)", summary.covered, summary.total, percent),
+                    "command": "logos.showCoverage",
+                    "arguments": [uri, line]
+                }
+            }))
+        })
+        .collect();
+    Response::success(id, json!(lenses))
+}
+
+fn file_coverage<'a>(state: &'a State, uri: &str) -> Option<&'a FileCoverage> {
+    state.coverage.as_ref()?.file(&uri_to_path(uri)?)
+}
+
+/// Functions and methods of a document with their coverage
+fn function_summaries<'a>(
+    state: &'a State,
+    uri: &str,
+    file: &FileCoverage,
+) -> Vec<(&'a logos_index::IndexedSymbol, CoverageSummary)> {
+    state
+        .analysis()
+        .document_symbols(uri)
+        .iter()
+        .filter(|s| matches!(s.kind, SymbolKind::Function | SymbolKind::Method | SymbolKind::Constructor))
+        .map(|s| (s, file.summary(s.range)))
+        .collect()
+}
@@ -1,7 +1,10 @@
 //! Definition handler
 
 use serde_json::{json, Value};
-use logos_core::Position;
+use logos_core::uri::uri_to_path;
+use logos_core::{Position, Range, SymbolKind};
+use logos_index::{IndexedSymbol, LanguageAdapter, TypeScriptAdapter};
+use logos_parser::{LanguageId, Utf16Columns};
 
 use crate::protocol::{TextDocumentPositionParams, RequestId, Response};
 use crate::state::State;
@@ -22,22 +25,200 @@ pub fn handle(state: &State, params: &Value, id: Option<RequestId>) -> Response
     let uri = &params.text_document.uri;
     let position = Position::new(params.position.line, params.position.character);
 
-    if let Some(symbol) = state.symbol_index.find_at_position(uri, position) {
-        let definition = json!({
-            "uri": symbol.uri,
-            "range": {
-                "start": {
-                    "line": symbol.range.start.line,
-                    "character": symbol.range.start.column
-                },
-                "end": {
-                    "line": symbol.range.end.line,
-                    "character": symbol.range.end.column
-                }
-            }
+    // A class, on an HTML element or in a CSS selector, goes to the first
+    // rule styling it
+    let analysis = state.analysis();
+    if let Some(rule) = analysis.class_at(uri, position).and_then(|class| analysis.class_rules(&class).into_iter().next()) {
+        return Response::success(id, location(&rule.uri, rule.range));
+    }
+
+    // A module specifier goes to the file the import loads, a package's
+    // declaration file for a bare one
+    if let Some(file) = imported_file(state, uri, position) {
+        return Response::success(id, location(&file, Range::default()));
+    }
+
+    match resolve(state, uri, position) {
+        Some(target) => Response::success(id, location(&target.uri, target.range)),
+        None => Response::null_result(id),
+    }
+}
+
+/// Declaration an identifier resolves to
+pub(crate) struct Target {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub uri: String,
+    pub range: Range,
+    /// Signature of a callable, when the extractor gives one
+    pub detail: Option<String>,
+    /// Doc comment of the declaration
+    pub documentation: Option<String>,
+    /// Declared in a generated file
+    pub generated: bool,
+}
+
+/// Resolve the identifier at `position` to its declaration.
+///
+/// A name bound across a language boundary (an `extern` function, a
+/// ctypes or cffi binding, a C prototype, a `.d.ts` declaration) goes to
+/// the other side when an analyzed file has it. On a declaration itself,
+/// the declaration is the target. Other
+/// identifiers are looked up by name: this document first, then the
+/// project index ordered by the import graph (which includes indexed
+/// archives), then other open documents. A name found only in generated
+/// code, or not at all, goes to the schema declaration (a `.proto` message,
+/// service or RPC) it was generated from when there is one. An unresolved
+/// identifier, such as a builtin, has no target; off any identifier, the
+/// enclosing symbol is the target.
+pub(crate) fn resolve(state: &State, uri: &str, position: Position) -> Option<Target> {
+    if let Some(target) = ffi_counterpart(state, uri, position) {
+        return Some(target);
+    }
+
+    let name = identifier_at(state, uri, position);
+    let enclosing = state.analysis().symbol_at(uri, position).map(Target::from);
+
+    if let Some(symbol) = &enclosing {
+        if name.as_deref().is_none_or(|name| name == symbol.name) {
+            return enclosing;
+        }
+    }
+
+    let name = name?;
+    let target = find_by_name(state, uri, &name);
+    if target.as_ref().is_none_or(|target| target.generated) {
+        return schema_definition(state, &name).or(target);
+    }
+    target
+}
+
+impl From<&IndexedSymbol> for Target {
+    fn from(symbol: &IndexedSymbol) -> Self {
+        Self {
+            name: symbol.name.clone(),
+            kind: symbol.kind,
+            uri: symbol.uri.clone(),
+            range: symbol.range,
+            detail: symbol.detail.clone(),
+            documentation: symbol.documentation.clone(),
+            generated: symbol.generated,
+        }
+    }
+}
+
+/// The other side of the FFI binding at `position`, the best ranked
+/// among the symbols of the analyzed files
+fn ffi_counterpart(state: &State, uri: &str, position: Position) -> Option<Target> {
+    let doc = state.get_document(uri)?;
+    let language = LanguageId::from_str(&doc.language_id)?;
+    let bindings = logos_index::find_bindings(language, uri, state.get_tree(uri)?, doc.content());
+    let binding = logos_index::binding_at(&bindings, position)?;
+
+    let analysis = state.analysis();
+    analysis
+        .symbol_index()
+        .documents()
+        .flat_map(|doc| analysis.document_symbols(doc))
+        .filter_map(|symbol| Some((binding.rank(uri, symbol)?, symbol)))
+        .min_by(|(a_rank, a), (b_rank, b)| a_rank.cmp(b_rank).then_with(|| a.uri.cmp(&b.uri)))
+        .map(|(_, symbol)| Target::from(symbol))
+}
+
+/// The schema declaration code named `name` was generated from
+fn schema_definition(state: &State, name: &str) -> Option<Target> {
+    let analysis = state.analysis();
+    analysis.symbol_index().find_schema_definitions(name).into_iter().next().map(Target::from)
+}
+
+/// URI of the file loaded by the import, export or `require` whose module
+/// string is at `position` of a JavaScript or TypeScript document
+fn imported_file(state: &State, uri: &str, position: Position) -> Option<String> {
+    let doc = state.get_document(uri)?;
+    let language = LanguageId::from_str(&doc.language_id)?;
+    if !matches!(
+        language,
+        LanguageId::TypeScript | LanguageId::TypeScriptReact | LanguageId::JavaScript | LanguageId::JavaScriptReact
+    ) {
+        return None;
+    }
+    let point = Utf16Columns::new(doc.content()).point(position);
+    let mut node = state.get_tree(uri)?.root_node().descendant_for_point_range(point, point)?;
+    if node.kind() == "string_fragment" {
+        node = node.parent()?;
+    }
+    if node.kind() != "string" {
+        return None;
+    }
+    let parent = node.parent()?;
+    let is_module = match parent.kind() {
+        "import_statement" | "export_statement" | "import_require_clause" => true,
+        "arguments" => parent
+            .parent()
+            .and_then(|call| call.child_by_field_name("function"))
+            .is_some_and(|function| matches!(function.utf8_text(doc.content().as_bytes()), Ok("require" | "import"))),
+        _ => false,
+    };
+    if !is_module {
+        return None;
+    }
+
+    let module = node.utf8_text(doc.content().as_bytes()).ok()?.trim_matches(['"', '\'', '`']);
+    let resolved = TypeScriptAdapter.resolve_import(state.vfs.as_ref(), &uri_to_path(uri)?, module)?;
+    Some(state.vfs.uri(&resolved))
+}
+
+/// Text of the identifier under the cursor
+fn identifier_at(state: &State, uri: &str, position: Position) -> Option<String> {
+    let doc = state.get_document(uri)?;
+    let range = logos_parser::identifier_range_at(state.get_tree(uri)?, doc.content(), position)?;
+    doc.text_in_range(range).map(str::to_string)
+}
+
+fn find_by_name(state: &State, uri: &str, name: &str) -> Option<Target> {
+    let local = state.analysis().document_symbols(uri).iter().find(|symbol| symbol.name == name);
+    if let Some(symbol) = local {
+        return Some(symbol.into());
+    }
+
+    let indexed = state
+        .get_indexer()
+        .and_then(|indexer| indexer.get_index().resolve_name(name, uri).into_iter().next());
+    if let Some(symbol) = indexed {
+        let generated = symbol.is_generated();
+        return Some(Target {
+            name: symbol.name,
+            kind: symbol.kind,
+            uri: symbol.location.uri,
+            range: symbol.location.range,
+            detail: None,
+            documentation: symbol.documentation,
+            generated,
         });
-        return Response::success(id, definition);
     }
 
-    Response::null_result(id)
+    let analysis = state.analysis();
+    analysis
+        .symbol_index()
+        .documents()
+        .filter(|doc| *doc != uri)
+        .flat_map(|doc| analysis.document_symbols(doc))
+        .find(|symbol| symbol.name == name)
+        .map(Target::from)
+}
+
+fn location(uri: &str, range: Range) -> Value {
+    json!({
+        "uri": uri,
+        "range": {
+            "start": {
+                "line": range.start.line,
+                "character": range.start.column
+            },
+            "end": {
+                "line": range.end.line,
+                "character": range.end.column
+            }
+        }
+    })
 }
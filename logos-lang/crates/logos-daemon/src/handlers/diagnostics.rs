@@ -1,8 +1,25 @@
 //! Diagnostics handler
+//!
+//! Diagnostics pass through one pipeline before they are reported:
+//! collection with suppression comments applied, severity remapping from
+//! the settings, then the baseline.
+
+use std::path::{Path, PathBuf};
 
 use serde_json::{json, Value};
+use logos_analysis::{AnalysisHost, AnalysisSnapshot};
+use logos_core::uri::path_to_uri;
+use logos_core::Diagnostic;
+use logos_index::PathGlob;
+use logos_parser::suppression::Suppressions;
+use logos_parser::LanguageId;
 
+use crate::baseline::{relative_path, Baseline, BaselineEntry};
+use crate::config::DiagnosticsSettings;
+use crate::hooks::HookEvent;
+use crate::plugins::PluginHost;
 use crate::protocol::{DocumentSymbolParams, RequestId, Response};
+use crate::scan::workspace_files;
 use crate::state::State;
 
 /// Handle textDocument/diagnostic
@@ -18,12 +35,172 @@ pub fn handle(state: &State, params: &Value, id: Option<RequestId>) -> Response
         }
     };
 
-    let _uri = &params.text_document.uri;
+    let uri = &params.text_document.uri;
+
+    let mut items = Vec::new();
+    if let Some(doc) = state.get_document(uri) {
+        items = collect(state.analysis(), &state.plugins, uri, &doc.language_id, doc.content());
+        let root = state.root_path.as_deref().map(Path::new);
+        let path = root.and_then(|root| relative_path(root, uri));
+        items = apply_severities(&state.settings.diagnostics, path.as_deref().unwrap_or(uri), items);
+        if let (Some(baseline), Some(path)) = (&state.baseline, path) {
+            items = baseline.filter(&path, doc.content(), items);
+        }
+    }
+
+    state.fire_document_hook(HookEvent::DiagnosticsPublished, uri, json!({ "uri": uri, "diagnostics": items }));
 
-    // For now, return empty diagnostics
-    // Future: integrate with parser errors and semantic analysis
     Response::success(id, json!({
         "kind": "full",
-        "items": []
+        "items": items
     }))
 }
+
+/// Handle logos/createDiagnosticsBaseline: record the diagnostics of every
+/// workspace file as accepted, replacing any previous baseline. Open
+/// documents count with their unsaved text.
+pub fn create_baseline(state: &mut State, id: Option<RequestId>) -> Response {
+    let Some(root) = state.root_path.clone().map(PathBuf::from) else {
+        return Response::error(
+            id,
+            crate::protocol::error_codes::REQUEST_FAILED,
+            "A diagnostics baseline needs a workspace root".to_string(),
+        );
+    };
+
+    let files = workspace_files(state.vfs.as_ref(), &root, &state.settings.index.exclude);
+
+    // Closed files are analyzed one at a time in a scratch host
+    let mut scratch = AnalysisHost::new();
+    scratch.set_cfg(state.settings.cfg.to_config());
+    let mut entries = Vec::new();
+    for file in &files {
+        let uri = path_to_uri(file);
+        let Some(path) = relative_path(&root, &uri) else {
+            continue;
+        };
+        let diagnostics = if let Some(doc) = state.get_document(&uri) {
+            let items = collect(state.analysis(), &state.plugins, &uri, &doc.language_id, doc.content());
+            let items = apply_severities(&state.settings.diagnostics, &path, items);
+            items.iter().filter_map(|d| BaselineEntry::new(&path, doc.content(), d)).collect::<Vec<_>>()
+        } else {
+            let Ok(content) = state.vfs.read_to_string(file) else {
+                continue;
+            };
+            let name = file.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+            let Some(language) = LanguageId::detect(name, &content) else {
+                continue;
+            };
+            scratch.open_document(uri.clone(), language.as_str(), content.clone());
+            let items = collect(scratch.snapshot(), &state.plugins, &uri, language.as_str(), &content);
+            let items = apply_severities(&state.settings.diagnostics, &path, items);
+            scratch.close_document(&uri);
+            items.iter().filter_map(|d| BaselineEntry::new(&path, &content, d)).collect()
+        };
+        entries.extend(diagnostics);
+    }
+
+    let baseline = Baseline::from_entries(entries);
+    let baseline_path = state.settings.diagnostics.baseline_path(&root);
+    if let Err(e) = baseline.save(&baseline_path) {
+        return Response::error(
+            id,
+            crate::protocol::error_codes::REQUEST_FAILED,
+            format!("Failed to write {}: {}", baseline_path.display(), e),
+        );
+    }
+    let result = json!({
+        "path": baseline_path.to_string_lossy(),
+        "files": files.len(),
+        "diagnostics": baseline.len()
+    });
+    state.baseline = Some(baseline);
+    Response::success(id, result)
+}
+
+/// Diagnostics of a document from every pass: plugin lint rules, inactive
+/// cfg/ifdef regions, plus syntax errors for data files, which have no
+/// other checks. Suppression comments apply to all of them.
+// Future: integrate with semantic analysis
+pub(crate) fn collect(analysis: AnalysisSnapshot, plugins: &PluginHost, uri: &str, language_id: &str, content: &str) -> Vec<Value> {
+    let mut items: Vec<Value> = analysis.diagnostics(uri).iter().map(to_lsp).collect();
+    let suppressions = Suppressions::scan(content);
+    items.extend(
+        plugins
+            .lint(uri, language_id, content)
+            .into_iter()
+            .filter(|lint| !is_suppressed(&suppressions, lint)),
+    );
+    items
+}
+
+/// Remap severities by code as configured, dropping codes turned `off`.
+/// `path` is relative to the workspace root, or the document URI outside
+/// one.
+pub(crate) fn apply_severities(settings: &DiagnosticsSettings, path: &str, items: Vec<Value>) -> Vec<Value> {
+    if settings.severity.is_empty() && settings.overrides.is_empty() {
+        return items;
+    }
+    let path = path.strip_prefix("file://").unwrap_or(path);
+    let overrides: Vec<_> = settings
+        .overrides
+        .iter()
+        .filter(|o| PathGlob::new(&o.files).is_some_and(|glob| glob.is_match(path)))
+        .collect();
+    items
+        .into_iter()
+        .filter_map(|mut item| {
+            let Some(code) = diagnostic_code(&item) else {
+                return Some(item);
+            };
+            let configured = overrides
+                .iter()
+                .rev()
+                .find_map(|o| o.severity.get(&code))
+                .or_else(|| settings.severity.get(&code));
+            let Some(configured) = configured else {
+                return Some(item);
+            };
+            item["severity"] = json!(configured.to_lsp()?);
+            Some(item)
+        })
+        .collect()
+}
+
+/// Code of an LSP diagnostic, a string or a number
+fn diagnostic_code(diagnostic: &Value) -> Option<String> {
+    match diagnostic.get("code") {
+        Some(Value::String(code)) => Some(code.clone()),
+        Some(Value::Number(code)) => Some(code.to_string()),
+        _ => None,
+    }
+}
+
+/// Whether a plugin lint is silenced; the rule id is its `code`, a string
+/// or a number
+fn is_suppressed(suppressions: &Suppressions, lint: &Value) -> bool {
+    let Some(line) = lint.pointer("/range/start/line").and_then(Value::as_u64) else {
+        return false;
+    };
+    suppressions.is_suppressed(line as u32, diagnostic_code(lint).as_deref())
+}
+
+fn to_lsp(diagnostic: &Diagnostic) -> Value {
+    json!({
+        "range": {
+            "start": {
+                "line": diagnostic.range.start.line,
+                "character": diagnostic.range.start.column
+            },
+            "end": {
+                "line": diagnostic.range.end.line,
+                "character": diagnostic.range.end.column
+            }
+        },
+        "severity": diagnostic.severity as u8,
+        "code": diagnostic.code,
+        "source": diagnostic.source,
+        "message": diagnostic.message,
+        "tags": diagnostic.tags.iter().map(|tag| *tag as u8).collect::<Vec<_>>()
+    })
+}
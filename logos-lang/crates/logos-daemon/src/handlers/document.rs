@@ -1,9 +1,14 @@
 //! Document synchronization handlers
 
 use log::debug;
-use serde_json::Value;
+use serde_json::{json, Value};
+use logos_core::TextChange;
 
-use crate::protocol::{DidOpenTextDocumentParams, DidChangeTextDocumentParams, DidCloseTextDocumentParams};
+use crate::hooks::HookEvent;
+use crate::protocol::{
+    DidOpenTextDocumentParams, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidSaveTextDocumentParams, WillSaveTextDocumentParams,
+};
 use crate::state::State;
 
 /// Handle textDocument/didOpen
@@ -34,10 +39,19 @@ pub fn did_change(state: &mut State, params: &Value) {
 
     debug!("Changing document: {}", params.text_document.uri);
 
-    // We use full sync, so just take the last change
-    if let Some(change) = params.content_changes.last() {
-        state.update_document(&params.text_document.uri, change.text.clone());
-    }
+    // Incremental sync: ranged edits apply in order; a change without a
+    // range replaces the whole document
+    let changes: Vec<TextChange> = params
+        .content_changes
+        .into_iter()
+        .map(|change| TextChange {
+            range: change.range.map(|r| {
+                logos_core::Range::from_coords(r.start.line, r.start.character, r.end.line, r.end.character)
+            }),
+            text: change.text,
+        })
+        .collect();
+    state.edit_document(&params.text_document.uri, &changes);
 }
 
 /// Handle textDocument/didClose
@@ -54,3 +68,39 @@ pub fn did_close(state: &mut State, params: &Value) {
 
     state.close_document(&params.text_document.uri);
 }
+
+/// Handle textDocument/willSave
+pub fn will_save(state: &State, params: &Value) {
+    let params: WillSaveTextDocumentParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            log::warn!("Invalid willSave params: {}", e);
+            return;
+        }
+    };
+
+    let uri = params.text_document.uri;
+    let language_id = state.get_document(&uri).map(|doc| doc.language_id.clone());
+    state.fire_hook(
+        HookEvent::WillSave,
+        json!({ "uri": uri, "languageId": language_id, "reason": params.reason }),
+    );
+}
+
+/// Handle textDocument/didSave
+pub fn did_save(state: &mut State, params: &Value) {
+    let params: DidSaveTextDocumentParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            log::warn!("Invalid didSave params: {}", e);
+            return;
+        }
+    };
+
+    debug!("Saved document: {}", params.text_document.uri);
+
+    let uri = params.text_document.uri;
+    state.reindex_project_file(&uri);
+    let language_id = state.get_document(&uri).map(|doc| doc.language_id.clone());
+    state.fire_hook(HookEvent::DidSave, json!({ "uri": uri, "languageId": language_id }));
+}
@@ -0,0 +1,38 @@
+//! Folding range handler
+
+use serde_json::{json, Value};
+
+use crate::protocol::{DocumentSymbolParams, RequestId, Response};
+use crate::state::State;
+
+/// Handle textDocument/foldingRange
+pub fn handle(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: DocumentSymbolParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid foldingRange params: {}", e),
+            );
+        }
+    };
+
+    let ranges: Vec<_> = state
+        .analysis()
+        .folding_ranges(&params.text_document.uri)
+        .iter()
+        .map(|range| {
+            let mut folding = json!({
+                "startLine": range.start_line,
+                "endLine": range.end_line
+            });
+            if let Some(kind) = range.kind {
+                folding["kind"] = json!(kind.as_str());
+            }
+            folding
+        })
+        .collect();
+
+    Response::success(id, json!(ranges))
+}
@@ -0,0 +1,125 @@
+//! Heatmap handler: per-line scores for minimap and gutter overlays
+
+use serde_json::{json, Value};
+use logos_core::uri::uri_to_path;
+use logos_core::DiagnosticTag;
+
+use crate::protocol::{HeatmapParams, RequestId, Response};
+use crate::state::State;
+
+/// Handle logos/getHeatmap. `kind` picks the metric:
+/// - `complexity`: complexity of the innermost function around the line
+/// - `churn`: commits that changed the line, from git history
+/// - `diagnostics`: diagnostics covering the line, plugin lints included
+///
+/// The result has one raw score per line plus the maximum, for the client
+/// to normalize.
+pub fn get_heatmap(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: HeatmapParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid getHeatmap params: {}", e),
+            );
+        }
+    };
+
+    let uri = &params.text_document.uri;
+    let Some(doc) = state.get_document(uri) else {
+        return Response::null_result(id);
+    };
+    let line_count = doc.line_count() as usize;
+
+    let scores = match params.kind.as_str() {
+        "complexity" => complexity_scores(state, uri, line_count),
+        "diagnostics" => diagnostic_scores(state, uri, line_count),
+        "churn" => {
+            if !state.trust.is_trusted() {
+                return Response::error(
+                    id,
+                    crate::protocol::error_codes::REQUEST_FAILED,
+                    "Git history is unavailable in restricted workspaces".to_string(),
+                );
+            }
+            let Some(path) = uri_to_path(uri) else {
+                return Response::error(
+                    id,
+                    crate::protocol::error_codes::REQUEST_FAILED,
+                    format!("Churn needs a file on disk: {}", uri),
+                );
+            };
+            match crate::git::line_churn(&path, line_count) {
+                Ok(scores) => scores,
+                Err(e) => {
+                    return Response::error(
+                        id,
+                        crate::protocol::error_codes::REQUEST_FAILED,
+                        format!("Failed to read git history: {}", e),
+                    );
+                }
+            }
+        }
+        other => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Unknown heatmap kind: {}", other),
+            );
+        }
+    };
+
+    let max = scores.iter().copied().max().unwrap_or(0);
+    Response::success(id, json!({
+        "kind": params.kind,
+        "scores": scores,
+        "max": max
+    }))
+}
+
+fn complexity_scores(state: &State, uri: &str, line_count: usize) -> Vec<u32> {
+    let mut scores = vec![0; line_count];
+    let analysis = state.analysis();
+    let (Some(doc), Some(tree)) = (analysis.document(uri), analysis.tree(uri)) else {
+        return scores;
+    };
+    let symbols: Vec<_> = analysis.document_symbols(uri)
+        .iter()
+        .map(|s| logos_core::Symbol::new(s.name.clone(), s.kind, s.range, s.selection_range))
+        .collect();
+    let mut metrics = logos_semantic::function_metrics(tree, doc.content(), &symbols);
+    // Outer functions first so that nested ones overwrite their lines
+    metrics.sort_by_key(|m| std::cmp::Reverse(m.lines));
+    for m in metrics {
+        let end = (m.range.end.line as usize + 1).min(line_count);
+        for score in scores.iter_mut().take(end).skip(m.range.start.line as usize) {
+            *score = m.complexity;
+        }
+    }
+    scores
+}
+
+fn diagnostic_scores(state: &State, uri: &str, line_count: usize) -> Vec<u32> {
+    let mut scores = vec![0; line_count];
+    let mut add = |start: u64, end: u64| {
+        let end = (end as usize + 1).min(line_count);
+        for score in scores.iter_mut().take(end).skip(start as usize) {
+            *score += 1;
+        }
+    };
+    // Inactive-region hints mark configuration, not problems
+    let diagnostics = state.analysis().diagnostics(uri);
+    for diagnostic in diagnostics.iter().filter(|d| !d.tags.contains(&DiagnosticTag::Unnecessary)) {
+        add(diagnostic.range.start.line as u64, diagnostic.range.end.line as u64);
+    }
+    if let Some(doc) = state.get_document(uri) {
+        for lint in state.plugins.lint(uri, &doc.language_id, doc.content()) {
+            let line = |pointer: &str| lint.pointer(pointer).and_then(Value::as_u64);
+            if let (Some(start), Some(end)) = (line("/range/start/line"), line("/range/end/line")) {
+                add(start, end);
+            }
+        }
+    }
+    scores
+}
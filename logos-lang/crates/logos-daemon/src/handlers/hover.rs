@@ -1,8 +1,9 @@
 //! Hover handler
 
 use serde_json::{json, Value};
-use logos_core::Position;
+use logos_core::{Position, SymbolKind};
 
+use crate::handlers::definition::resolve;
 use crate::protocol::{TextDocumentPositionParams, RequestId, Response};
 use crate::state::State;
 
@@ -22,20 +23,37 @@ pub fn handle(state: &State, params: &Value, id: Option<RequestId>) -> Response
     let uri = &params.text_document.uri;
     let position = Position::new(params.position.line, params.position.character);
 
-    if let Some(symbol) = state.symbol_index.find_at_position(uri, position) {
+    // References describe the declaration they resolve to
+    if let Some(symbol) = resolve(state, uri, position) {
+        // Highlight only the hovered token, not the whole declaration
+        let range = state
+            .get_tree(uri)
+            .zip(state.get_document(uri))
+            .and_then(|(tree, doc)| logos_parser::identifier_range_at(tree, doc.content(), position))
+            .or_else(|| state.analysis().symbol_at(uri, position).map(|s| s.selection_range))
+            .unwrap_or(symbol.range);
+        let mut value = format!("**{}** ({:?})", symbol.name, symbol.kind);
+        if let Some(signature) = symbol.detail.as_deref().filter(|_| is_callable(symbol.kind)) {
+            let language = state.get_document(uri).map(|doc| doc.language_id.clone()).unwrap_or_default();
+            value.push_str(&format!("\n\n```{}\n{}\n```", language, signature));
+        }
+        if let Some(documentation) = &symbol.documentation {
+            value.push_str("\n\n---\n\n");
+            value.push_str(documentation);
+        }
         let hover = json!({
             "contents": {
                 "kind": "markdown",
-                "value": format!("**{}** ({})", symbol.name, format!("{:?}", symbol.kind))
+                "value": value
             },
             "range": {
                 "start": {
-                    "line": symbol.selection_range.start.line,
-                    "character": symbol.selection_range.start.column
+                    "line": range.start.line,
+                    "character": range.start.column
                 },
                 "end": {
-                    "line": symbol.selection_range.end.line,
-                    "character": symbol.selection_range.end.column
+                    "line": range.end.line,
+                    "character": range.end.column
                 }
             }
         });
@@ -44,3 +62,7 @@ pub fn handle(state: &State, params: &Value, id: Option<RequestId>) -> Response
 
     Response::null_result(id)
 }
+
+fn is_callable(kind: SymbolKind) -> bool {
+    matches!(kind, SymbolKind::Function | SymbolKind::Method | SymbolKind::Constructor)
+}
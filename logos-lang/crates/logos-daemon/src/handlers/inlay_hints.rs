@@ -0,0 +1,62 @@
+//! Inlay hints handler
+//!
+//! The only category so far is the complexity badge: functions whose
+//! cyclomatic complexity or length passes the configured thresholds get a
+//! short note at the end of their signature line.
+
+use serde_json::{json, Value};
+
+use crate::protocol::{InlayHintParams, RequestId, Response};
+use crate::state::State;
+
+/// Handle textDocument/inlayHint
+pub fn handle(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: InlayHintParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid inlayHint params: {}", e),
+            );
+        }
+    };
+
+    let uri = &params.text_document.uri;
+    let analysis = state.analysis();
+    let (Some(doc), Some(tree)) = (analysis.document(uri), analysis.tree(uri)) else {
+        return Response::success(id, json!([]));
+    };
+    let Some(thresholds) = state.settings.inlay_hints.complexity.thresholds_for(&doc.language_id) else {
+        return Response::success(id, json!([]));
+    };
+
+    let lines: Vec<&str> = doc.content().lines().collect();
+    // The index keeps nested symbols flattened, so children stay empty
+    let symbols: Vec<_> = analysis.document_symbols(uri)
+        .iter()
+        .map(|s| logos_core::Symbol::new(s.name.clone(), s.kind, s.range, s.selection_range))
+        .collect();
+    let hints: Vec<Value> = logos_semantic::function_metrics(tree, doc.content(), &symbols)
+        .into_iter()
+        .filter(|m| m.complexity > thresholds.max_complexity || m.lines > thresholds.max_lines)
+        .filter_map(|m| {
+            let line = m.selection_range.start.line;
+            if line < params.range.start.line || line > params.range.end.line {
+                return None;
+            }
+            let character: usize = lines.get(line as usize)?.trim_end().chars().map(char::len_utf16).sum();
+            Some(json!({
+                "position": { "line": line, "character": character },
+                "label": format!("complexity {} · {} lines", m.complexity, m.lines),
+                "paddingLeft": true,
+                "tooltip": format!(
+                    "{} exceeds the complexity ({}) or length ({} lines) threshold; consider splitting it",
+                    m.name, thresholds.max_complexity, thresholds.max_lines
+                )
+            }))
+        })
+        .collect();
+
+    Response::success(id, json!(hints))
+}
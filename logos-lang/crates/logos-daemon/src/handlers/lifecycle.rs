@@ -2,7 +2,10 @@
 
 use log::info;
 use serde_json::{json, Value};
+use logos_core::uri::uri_to_path;
+use logos_parser::highlight;
 
+use crate::config::{ClientCapabilities, Settings, WorkspaceTrust};
 use crate::protocol::{InitializeParams, RequestId, Response};
 use crate::state::State;
 
@@ -24,15 +27,27 @@ pub fn initialize(state: &mut State, params: &Value, id: Option<RequestId>) -> R
     info!("  Root path: {:?}", params.root_path);
     info!("  Root URI: {:?}", params.root_uri);
 
-    state.root_path = params.root_path.or(params.root_uri);
+    state.root_path = params.root_path.or_else(|| {
+        let path = uri_to_path(params.root_uri.as_deref()?)?;
+        Some(path.to_string_lossy().into_owned())
+    });
+    state.client_capabilities = ClientCapabilities::from_value(&params.capabilities);
+    let settings = params.initialization_options.as_ref().map(Settings::from_value).unwrap_or_default();
+    state.check_grammars();
+    state.apply_settings(settings);
+    state.load_plugins();
     state.initialized = true;
 
+    let trusted = state.trust.is_trusted();
+    let restricted_features = if trusted { &[][..] } else { WorkspaceTrust::RESTRICTED_FEATURES };
+
     // Return server capabilities
     let capabilities = json!({
         "capabilities": {
             "textDocumentSync": {
                 "openClose": true,
-                "change": 1,  // Full sync
+                "change": 2,  // Incremental sync
+                "willSave": true,
                 "save": {
                     "includeText": false
                 }
@@ -43,15 +58,46 @@ pub fn initialize(state: &mut State, params: &Value, id: Option<RequestId>) -> R
             },
             "hoverProvider": true,
             "definitionProvider": true,
+            "implementationProvider": true,
+            "typeHierarchyProvider": true,
             "referencesProvider": true,
             "documentSymbolProvider": true,
             "workspaceSymbolProvider": true,
             "renameProvider": {
                 "prepareProvider": true
             },
+            "semanticTokensProvider": {
+                "legend": {
+                    "tokenTypes": highlight::TOKEN_TYPES,
+                    "tokenModifiers": highlight::TOKEN_MODIFIERS
+                },
+                "full": true
+            },
+            "inlayHintProvider": true,
+            "foldingRangeProvider": true,
+            "colorProvider": true,
+            "codeLensProvider": {
+                "resolveProvider": false
+            },
+            "codeActionProvider": {
+                "codeActionKinds": ["quickfix"]
+            },
             "diagnosticProvider": {
                 "interFileDependencies": false,
                 "workspaceDiagnostics": false
+            },
+            "workspace": {
+                "fileOperations": {
+                    "didRename": {
+                        "filters": [{ "scheme": "file", "pattern": { "glob": "**/*" } }]
+                    }
+                }
+            },
+            "experimental": {
+                "workspaceTrust": {
+                    "trusted": trusted,
+                    "disabledFeatures": restricted_features
+                }
             }
         },
         "serverInfo": {
@@ -67,4 +113,5 @@ pub fn initialize(state: &mut State, params: &Value, id: Option<RequestId>) -> R
 pub fn initialized(state: &mut State) {
     info!("Client initialized, server is ready");
     state.initialized = true;
+    state.start_workspace_scan();
 }
@@ -9,7 +9,23 @@ pub mod hover;
 pub mod symbols;
 pub mod rename;
 pub mod diagnostics;
+pub mod semantic_tokens;
+pub mod inlay_hints;
+pub mod folding;
+pub mod color;
+pub mod code_action;
 pub mod refactor;
 pub mod analysis;
 pub mod call_hierarchy;
+pub mod type_hierarchy;
 pub mod mode;
+pub mod workspace;
+pub mod archive;
+pub mod plugins;
+pub mod stats;
+pub mod heatmap;
+pub mod coverage;
+pub mod permalink;
+pub mod related;
+pub mod replace;
+pub mod stack_trace;
@@ -72,7 +72,7 @@ pub fn handle_get_index_stats(state: &State, _params: &Value, id: Option<Request
         Response::success(
             id,
             json!({
-                "symbolCount": state.symbol_index.symbol_count(),
+                "symbolCount": state.analysis().symbol_index().symbol_count(),
                 "callSiteCount": 0,
                 "fileCount": 0,
             }),
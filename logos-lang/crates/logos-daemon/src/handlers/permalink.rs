@@ -0,0 +1,93 @@
+//! Permalinks to selections, for sharing and deep links
+
+use std::path::{Path, PathBuf};
+
+use logos_core::uri::{path_to_uri, uri_to_path};
+use logos_core::Range;
+use logos_index::Permalink;
+use serde_json::{json, Value};
+
+use crate::baseline::relative_path;
+use crate::protocol::{DescribeSelectionParams, RequestId, Response};
+use crate::state::State;
+
+fn range_json(range: &Range) -> Value {
+    json!({
+        "start": { "line": range.start.line, "character": range.start.column },
+        "end": { "line": range.end.line, "character": range.end.column }
+    })
+}
+
+/// Handle logos/describeSelection.
+///
+/// Given a document and a range, returns the permalink of the selection
+/// (`src/user.ts::User.greet#L10-L14`) with its parts. Given a permalink,
+/// resolves it back to a location: the range of the named symbol when the
+/// document is open and still declares it, the linked lines otherwise.
+pub fn describe_selection(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: DescribeSelectionParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid describeSelection params: {}", e),
+            );
+        }
+    };
+
+    if let Some(text) = params.permalink {
+        return resolve_permalink(state, &text, id);
+    }
+    let (Some(document), Some(range)) = (params.text_document, params.range) else {
+        return Response::error(
+            id,
+            crate::protocol::error_codes::INVALID_PARAMS,
+            "Invalid describeSelection params: expected textDocument and range, or permalink".to_string(),
+        );
+    };
+
+    let uri = &document.uri;
+    let range = Range::from_coords(range.start.line, range.start.character, range.end.line, range.end.character);
+    // Outside the workspace the link can only hold the full path
+    let path = state
+        .root_path
+        .as_deref()
+        .and_then(|root| relative_path(Path::new(root), uri))
+        .or_else(|| uri_to_path(uri).map(|path| path.to_string_lossy().replace('\\', "/")))
+        .unwrap_or_else(|| uri.clone());
+    let link = Permalink::new(path, state.analysis().document_symbols(uri), range);
+
+    Response::success(id, json!({
+        "permalink": link.to_string(),
+        "path": link.path,
+        "symbol": link.symbol,
+        "startLine": link.start_line,
+        "endLine": link.end_line
+    }))
+}
+
+fn resolve_permalink(state: &State, text: &str, id: Option<RequestId>) -> Response {
+    let Some(link) = Permalink::parse(text) else {
+        return Response::error(
+            id,
+            crate::protocol::error_codes::INVALID_PARAMS,
+            format!("Not a permalink: {}", text),
+        );
+    };
+
+    let path = match &state.root_path {
+        Some(root) if !Path::new(&link.path).is_absolute() => PathBuf::from(root).join(&link.path),
+        _ => PathBuf::from(&link.path),
+    };
+    let uri = path_to_uri(&path);
+    let symbol = link.find_symbol(state.analysis().document_symbols(&uri));
+    let range = symbol.map_or_else(|| link.line_range(), |symbol| symbol.range);
+
+    Response::success(id, json!({
+        "uri": uri,
+        "range": range_json(&range),
+        "symbol": link.symbol,
+        "symbolFound": symbol.is_some()
+    }))
+}
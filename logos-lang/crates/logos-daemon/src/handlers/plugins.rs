@@ -0,0 +1,26 @@
+//! Requests served by plugins
+
+use serde_json::Value;
+
+use crate::protocol::{RequestId, Response};
+use crate::state::State;
+
+/// Forward a plugin-registered method to its plugin
+pub fn handle(state: &State, method: &str, params: &Value, id: Option<RequestId>) -> Response {
+    let Some(plugin) = state.plugins.handler_for(method) else {
+        return Response::error(
+            id,
+            crate::protocol::error_codes::METHOD_NOT_FOUND,
+            format!("Method not found: {}", method),
+        );
+    };
+
+    match plugin.call(method, params) {
+        Ok(result) => Response::success(id, result),
+        Err(e) => Response::error(
+            id,
+            crate::protocol::error_codes::REQUEST_FAILED,
+            format!("Plugin {} failed: {}", plugin.manifest.name, e),
+        ),
+    }
+}
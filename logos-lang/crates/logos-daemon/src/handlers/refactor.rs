@@ -1,8 +1,16 @@
 //! Refactoring handlers
 
-use serde_json::{json, Value};
+use std::path::PathBuf;
 
-use crate::protocol::{RefactorParams, ExtractVariableParams, ExtractMethodParams, RequestId, Response};
+use serde_json::{json, Value};
+use logos_core::uri::{path_to_uri, uri_to_path};
+use logos_core::{Position, Symbol, SymbolKind};
+use logos_refactor::generate_test::{self, SkeletonContext};
+
+use crate::jobs::{Job, JobResult, FILES_PER_STEP};
+use crate::protocol::{
+    RefactorParams, ExtractVariableParams, ExtractMethodParams, RequestId, Response, TextDocumentPositionParams,
+};
 use crate::state::State;
 
 /// Handle logos/getRefactorActions
@@ -269,45 +277,69 @@ pub fn can_safe_delete(state: &State, params: &Value, id: Option<RequestId>) ->
     }
 }
 
-/// Handle logos/safeDelete
-pub fn safe_delete(state: &State, params: &Value, id: Option<RequestId>) -> Response {
-    let params: RefactorParams = match serde_json::from_value(params.clone()) {
+/// Handle logos/safeDelete. The symbol must be unused in its own document;
+/// the other files of the workspace are then searched for usages in the
+/// background, and the deletion is generated once none is found. `None`
+/// means the response comes when the search is done.
+pub fn safe_delete(state: &mut State, params: &Value, id: Option<RequestId>) -> Option<Response> {
+    let request: RefactorParams = match serde_json::from_value(params.clone()) {
         Ok(p) => p,
         Err(e) => {
-            return Response::error(
+            return Some(Response::error(
                 id,
                 crate::protocol::error_codes::INVALID_PARAMS,
                 format!("Invalid safeDelete params: {}", e),
-            );
+            ));
         }
     };
 
-    let uri = &params.text_document.uri;
+    let uri = &request.text_document.uri;
 
     let doc = match state.get_document(uri) {
         Some(d) => d,
         None => {
-            return Response::success(id, json!({"success": false, "error": "Document not found"}));
+            return Some(Response::success(id, json!({"success": false, "error": "Document not found"})));
         }
     };
 
     let language = match logos_parser::LanguageId::from_str(&doc.language_id) {
         Some(l) => l,
         None => {
-            return Response::success(id, json!({"success": false, "error": "Unsupported language"}));
+            return Some(Response::success(id, json!({"success": false, "error": "Unsupported language"})));
         }
     };
 
     let selection = logos_core::Range::from_coords(
-        params.range.start.line,
-        params.range.start.character,
-        params.range.end.line,
-        params.range.end.character,
+        request.range.start.line,
+        request.range.start.character,
+        request.range.end.line,
+        request.range.end.character,
     );
 
     let ctx = logos_refactor::RefactorContext::new(doc.content(), uri, selection, language);
 
-    match logos_refactor::safe_delete::delete(&ctx) {
+    let analysis = match logos_refactor::safe_delete::analyze(&ctx) {
+        Ok(analysis) if analysis.can_delete => analysis,
+        Ok(analysis) => {
+            let in_use = logos_refactor::RefactorError::SymbolInUse(analysis.usages);
+            return Some(Response::success(id, safe_delete_result(Err(in_use))));
+        }
+        Err(e) => return Some(Response::success(id, safe_delete_result(Err(e)))),
+    };
+
+    let files: Vec<PathBuf> =
+        state.workspace_files().into_iter().filter(|path| state.vfs.uri(path) != *uri).collect();
+    let job = SafeDeleteJob::new(uri.clone(), doc.version, language, selection, analysis.symbol_name, files);
+    match id {
+        Some(id) => state.start_job(id, params, Box::new(job)),
+        None => log::debug!("Ignoring safe delete sent as a notification"),
+    }
+    None
+}
+
+/// Response of logos/safeDelete for the deletion or the reason it was refused
+fn safe_delete_result(result: Result<logos_refactor::RefactorResult, logos_refactor::RefactorError>) -> Value {
+    match result {
         Ok(result) => {
             let edits: Vec<_> = result.edits.iter().map(|edit| {
                 json!({
@@ -325,11 +357,11 @@ pub fn safe_delete(state: &State, params: &Value, id: Option<RequestId>) -> Resp
                 })
             }).collect();
 
-            Response::success(id, json!({
+            json!({
                 "success": true,
                 "edits": edits,
                 "description": result.description
-            }))
+            })
         }
         Err(e) => {
             let error_msg = match &e {
@@ -342,10 +374,174 @@ pub fn safe_delete(state: &State, params: &Value, id: Option<RequestId>) -> Resp
                 _ => e.to_string()
             };
 
-            Response::success(id, json!({
+            json!({
                 "success": false,
                 "error": error_msg
-            }))
+            })
+        }
+    }
+}
+
+/// Search of the workspace for usages of a symbol about to be deleted,
+/// a few files at a time. It stops at the first file using the symbol.
+struct SafeDeleteJob {
+    uri: String,
+    /// Version of the document the deletion was asked for; the edits are
+    /// only generated if it is still current
+    version: u32,
+    language: logos_parser::LanguageId,
+    selection: logos_core::Range,
+    symbol_name: String,
+    /// Files left to search, in reverse order so the next one is last
+    pending: Vec<PathBuf>,
+    total: usize,
+}
+
+impl SafeDeleteJob {
+    fn new(
+        uri: String,
+        version: u32,
+        language: logos_parser::LanguageId,
+        selection: logos_core::Range,
+        symbol_name: String,
+        mut files: Vec<PathBuf>,
+    ) -> Self {
+        files.reverse();
+        Self { uri, version, language, selection, symbol_name, total: files.len(), pending: files }
+    }
+}
+
+impl Job for SafeDeleteJob {
+    fn title(&self) -> String {
+        format!("Looking for usages of {}", self.symbol_name)
+    }
+
+    fn total(&self) -> usize {
+        self.total
+    }
+
+    fn done(&self) -> usize {
+        self.total - self.pending.len()
+    }
+
+    fn step(&mut self, state: &State) -> Option<JobResult> {
+        let start = self.pending.len().saturating_sub(FILES_PER_STEP);
+        let mut usages = Vec::new();
+        for path in self.pending.split_off(start).into_iter().rev() {
+            let uri = state.vfs.uri(&path);
+            let text = match state.get_document(&uri) {
+                Some(doc) => doc.content().to_string(),
+                None => match state.vfs.read_to_string(&path) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        log::debug!("Skipping {}: {}", path.display(), e);
+                        continue;
+                    }
+                },
+            };
+            usages.extend(logos_refactor::safe_delete::find_usages_in(&uri, &text, &self.symbol_name));
+        }
+        if !usages.is_empty() {
+            return Some(Ok(safe_delete_result(Err(logos_refactor::RefactorError::SymbolInUse(usages)))));
         }
+        if !self.pending.is_empty() {
+            return None;
+        }
+
+        let doc = match state.get_document(&self.uri) {
+            Some(doc) if doc.version == self.version => doc,
+            _ => return Some(Ok(json!({"success": false, "error": "Document changed while looking for usages"}))),
+        };
+        let ctx = logos_refactor::RefactorContext::new(doc.content(), &self.uri, self.selection, self.language);
+        Some(Ok(safe_delete_result(logos_refactor::safe_delete::delete(&ctx))))
     }
 }
+
+/// Handle logos/generateTestSkeleton: stub tests for the function or class
+/// at the position, added to the conventional test file of the document.
+/// `created` tells the client to create the file before applying `edits`.
+pub fn generate_test_skeleton(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: TextDocumentPositionParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid generateTestSkeleton params: {}", e),
+            );
+        }
+    };
+
+    let uri = &params.text_document.uri;
+    let analysis = state.analysis();
+    let (Some(doc), Some(tree), Some(language)) = (analysis.document(uri), analysis.tree(uri), analysis.language(uri)) else {
+        return Response::success(id, json!({"success": false, "error": "Document not found"}));
+    };
+    let Some(source_path) = uri_to_path(uri) else {
+        return Response::success(id, json!({"success": false, "error": "Document is not a file on disk"}));
+    };
+    let Some(test_path) = logos_index::test_file_path(state.vfs.as_ref(), &source_path) else {
+        return Response::success(id, json!({"success": false, "error": "No test file convention for this file"}));
+    };
+
+    let symbols = logos_parser::extract_symbols(language, tree, doc.content());
+    let position = Position::new(params.position.line, params.position.character);
+    let Some(target) = testable_symbol_at(&symbols, position) else {
+        return Response::success(id, json!({"success": false, "error": "No function or class at the position"}));
+    };
+    let ctx = SkeletonContext {
+        language,
+        source: doc.content(),
+        tree,
+        symbols: &symbols,
+        target,
+        source_path: &source_path,
+        test_path: &test_path,
+    };
+    let skeleton = match generate_test::skeleton(&ctx) {
+        Ok(skeleton) => skeleton,
+        Err(e) => return Response::success(id, json!({"success": false, "error": e.to_string()})),
+    };
+
+    let test_uri = path_to_uri(&test_path);
+    let existing = match analysis.document(&test_uri) {
+        Some(test_doc) => Some(test_doc.content().to_string()),
+        None => state.vfs.read_to_string(&test_path).ok(),
+    };
+    let edits: Vec<_> = skeleton.edits(existing.as_deref()).iter().map(|edit| {
+        json!({
+            "range": {
+                "start": {
+                    "line": edit.range.start.line,
+                    "character": edit.range.start.column
+                },
+                "end": {
+                    "line": edit.range.end.line,
+                    "character": edit.range.end.column
+                }
+            },
+            "newText": edit.new_text
+        })
+    }).collect();
+
+    Response::success(id, json!({
+        "success": true,
+        "uri": test_uri,
+        "created": existing.is_none(),
+        "edits": edits,
+        "description": format!("Generate tests for {}", target.name)
+    }))
+}
+
+/// Innermost function, method or type whose range contains `position`
+fn testable_symbol_at(symbols: &[Symbol], position: Position) -> Option<&Symbol> {
+    symbols.iter().filter(|s| s.range.contains(position)).find_map(|symbol| {
+        testable_symbol_at(&symbol.children, position).or_else(|| {
+            matches!(
+                symbol.kind,
+                SymbolKind::Function | SymbolKind::Method | SymbolKind::Class | SymbolKind::Struct | SymbolKind::Interface
+            )
+            .then_some(symbol)
+        })
+    })
+}
@@ -22,28 +22,27 @@ pub fn handle(state: &State, params: &Value, id: Option<RequestId>) -> Response
     let uri = &params.text_document.uri;
     let position = Position::new(params.position.line, params.position.character);
 
-    // Find the symbol at the given position
-    let symbol = match state.symbol_index.find_at_position(uri, position) {
-        Some(s) => s,
-        None => return Response::success(id, json!([])),
+    // All occurrences of the symbol at the given position, one entry per
+    // location. Classes link HTML elements and the CSS rules styling them.
+    let analysis = state.analysis();
+    let locations = match analysis.class_at(uri, position) {
+        Some(class) => analysis.class_references(&class),
+        None => analysis.references(uri, position),
     };
 
-    let symbol_name = symbol.name.clone();
-
-    // Search for all occurrences of this symbol name
-    let references: Vec<_> = state.symbol_index.search(&symbol_name)
+    let references: Vec<_> = locations
         .iter()
-        .map(|s| {
+        .map(|loc| {
             json!({
-                "uri": s.uri,
+                "uri": loc.uri,
                 "range": {
                     "start": {
-                        "line": s.selection_range.start.line,
-                        "character": s.selection_range.start.column
+                        "line": loc.range.start.line,
+                        "character": loc.range.start.column
                     },
                     "end": {
-                        "line": s.selection_range.end.line,
-                        "character": s.selection_range.end.column
+                        "line": loc.range.end.line,
+                        "character": loc.range.end.column
                     }
                 }
             })
@@ -0,0 +1,36 @@
+//! Related files handler: the "alternate file" switch of the editor
+
+use serde_json::{json, Value};
+use logos_core::uri::uri_to_path;
+use logos_index::related_files;
+
+use crate::protocol::{RelatedFilesParams, RequestId, Response};
+use crate::state::State;
+
+/// Handle logos/getRelatedFiles: header/source, test/implementation and
+/// component/style counterparts of a document, each with its `kind`
+pub fn get_related_files(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: RelatedFilesParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid getRelatedFiles params: {}", e),
+            );
+        }
+    };
+
+    // Untitled buffers have no neighbours
+    let Some(path) = uri_to_path(&params.text_document.uri) else {
+        return Response::success(id, json!([]));
+    };
+    let related: Vec<Value> = related_files(state.vfs.as_ref(), &path)
+        .into_iter()
+        .map(|file| json!({
+            "uri": state.vfs.uri(&file.path),
+            "kind": file.kind,
+        }))
+        .collect();
+    Response::success(id, json!(related))
+}
@@ -1,9 +1,10 @@
 //! Rename handler
 
 use serde_json::{json, Value};
-use std::collections::HashMap;
-use logos_core::Position;
+use std::collections::BTreeMap;
+use logos_core::{Position, Range};
 
+use crate::jobs::{Job, JobResult, FILES_PER_STEP};
 use crate::protocol::{TextDocumentPositionParams, RenameParams, RequestId, Response};
 use crate::state::State;
 
@@ -23,7 +24,7 @@ pub fn prepare(state: &State, params: &Value, id: Option<RequestId>) -> Response
     let uri = &params.text_document.uri;
     let position = Position::new(params.position.line, params.position.character);
 
-    if let Some(symbol) = state.symbol_index.find_at_position(uri, position) {
+    if let Some(symbol) = state.analysis().declaration_at(uri, position) {
         let result = json!({
             "range": {
                 "start": {
@@ -43,57 +44,137 @@ pub fn prepare(state: &State, params: &Value, id: Option<RequestId>) -> Response
     Response::null_result(id)
 }
 
-/// Handle textDocument/rename
-pub fn handle(state: &State, params: &Value, id: Option<RequestId>) -> Response {
-    let params: RenameParams = match serde_json::from_value(params.clone()) {
+/// Handle textDocument/rename. The edits are generated file by file in
+/// the background; `None` means the response comes when they are done.
+pub fn handle(state: &mut State, params: &Value, id: Option<RequestId>) -> Option<Response> {
+    let request: RenameParams = match serde_json::from_value(params.clone()) {
         Ok(p) => p,
         Err(e) => {
-            return Response::error(
+            return Some(Response::error(
                 id,
                 crate::protocol::error_codes::INVALID_PARAMS,
                 format!("Invalid rename params: {}", e),
-            );
+            ));
         }
     };
 
-    let uri = &params.text_document.uri;
-    let position = Position::new(params.position.line, params.position.character);
-    let new_name = &params.new_name;
+    let uri = &request.text_document.uri;
+    let position = Position::new(request.position.line, request.position.character);
+    // Written composed, like the names it is compared with
+    let new_name = logos_index::compose_name(&request.new_name);
 
-    // Find the symbol at the given position
-    let symbol = match state.symbol_index.find_at_position(uri, position) {
+    // Find the symbol named at the given position; a position inside a
+    // body renames nothing rather than the body's owner
+    let symbol = match state.analysis().declaration_at(uri, position) {
         Some(s) => s,
-        None => return Response::null_result(id),
+        None => return Some(Response::null_result(id)),
     };
 
     let old_name = symbol.name.clone();
 
-    // Find all references to this symbol
-    let references = state.symbol_index.search(&old_name);
-
-    // Group edits by document URI
-    let mut changes: HashMap<String, Vec<Value>> = HashMap::new();
+    // Declarations with the same name; spellings that differ only in
+    // composition are the same identifier. Generated files are left alone
+    // unless the rename starts in one or the settings include them.
+    let include_generated = state.settings.refactor.include_generated;
+    let references = state
+        .analysis()
+        .symbol_index()
+        .find_named(&old_name)
+        .into_iter()
+        .filter(|s| include_generated || !s.generated || s.uri == *uri);
 
+    // Group edits by document URI; `find_named` yields them in location order
+    let mut files: BTreeMap<String, Vec<Range>> = BTreeMap::new();
     for s in references {
-        let edit = json!({
-            "range": {
-                "start": {
-                    "line": s.selection_range.start.line,
-                    "character": s.selection_range.start.column
-                },
-                "end": {
-                    "line": s.selection_range.end.line,
-                    "character": s.selection_range.end.column
-                }
-            },
-            "newText": new_name
-        });
-        changes.entry(s.uri.clone()).or_default().push(edit);
+        files.entry(s.uri.clone()).or_default().push(s.selection_range);
     }
 
-    let workspace_edit = json!({
-        "changes": changes
-    });
+    let versions = files.keys().map(|uri| (uri.clone(), state.get_document(uri).map(|doc| doc.version))).collect();
+    let job = RenameJob::new(old_name, new_name, files, versions);
+    match id {
+        Some(id) => state.start_job(id, params, Box::new(job)),
+        None => log::debug!("Ignoring rename sent as a notification"),
+    }
+    None
+}
+
+/// Edits of a rename, generated a few files at a time
+struct RenameJob {
+    old_name: String,
+    new_name: String,
+    /// Declarations left to rename, grouped by file, in reverse URI order
+    /// so the next file is last
+    pending: Vec<(String, Vec<Range>)>,
+    total: usize,
+    changes: BTreeMap<String, Vec<Value>>,
+    /// Versions of the files renamed in when the rename was asked, `None`
+    /// for files not open; the edits are only sent if they are all still
+    /// current
+    versions: Vec<(String, Option<u32>)>,
+}
 
-    Response::success(id, workspace_edit)
+impl RenameJob {
+    fn new(
+        old_name: String,
+        new_name: String,
+        files: BTreeMap<String, Vec<Range>>,
+        versions: Vec<(String, Option<u32>)>,
+    ) -> Self {
+        let pending: Vec<_> = files.into_iter().rev().collect();
+        Self { old_name, new_name, total: pending.len(), pending, changes: BTreeMap::new(), versions }
+    }
+
+    /// Whether a file changed since the rename was asked
+    fn is_outdated(&self, state: &State) -> bool {
+        self.versions.iter().any(|(uri, version)| state.get_document(uri).map(|doc| doc.version) != *version)
+    }
+}
+
+impl Job for RenameJob {
+    fn title(&self) -> String {
+        format!("Renaming {} to {}", self.old_name, self.new_name)
+    }
+
+    fn total(&self) -> usize {
+        self.total
+    }
+
+    fn done(&self) -> usize {
+        self.total - self.pending.len()
+    }
+
+    fn step(&mut self, state: &State) -> Option<JobResult> {
+        if self.is_outdated(state) {
+            let message = "Documents changed during the rename".to_string();
+            return Some(Err((crate::protocol::error_codes::CONTENT_MODIFIED, message)));
+        }
+        let start = self.pending.len().saturating_sub(FILES_PER_STEP);
+        for (uri, ranges) in self.pending.split_off(start).into_iter().rev() {
+            let edits = ranges
+                .iter()
+                .map(|range| {
+                    json!({
+                        "range": {
+                            "start": {
+                                "line": range.start.line,
+                                "character": range.start.column
+                            },
+                            "end": {
+                                "line": range.end.line,
+                                "character": range.end.column
+                            }
+                        },
+                        "newText": self.new_name
+                    })
+                })
+                .collect();
+            self.changes.insert(uri, edits);
+        }
+        if !self.pending.is_empty() {
+            return None;
+        }
+        Some(Ok(json!({
+            "changes": std::mem::take(&mut self.changes)
+        })))
+    }
 }
@@ -0,0 +1,142 @@
+//! Plain-text find and replace across the workspace
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use logos_core::uri::uri_to_path;
+use logos_core::Range;
+use logos_index::{OverlayFs, Vfs};
+use serde_json::{json, Map, Value};
+
+use crate::protocol::{RequestId, Response, TextSearchParams, WorkspaceReplaceParams};
+use crate::state::State;
+
+fn range_json(range: &Range) -> Value {
+    json!({
+        "start": { "line": range.start.line, "character": range.start.column },
+        "end": { "line": range.end.line, "character": range.end.column }
+    })
+}
+
+/// Handle logos/workspaceReplace: the edits replacing every match of a
+/// literal or regex query, without applying them.
+///
+/// `edit` is a workspace edit with one text document edit per file, each
+/// under a change annotation that needs confirmation, so the client shows
+/// a preview before applying. `files` lists the same edits per file with
+/// the text they replace. Open documents are searched as edited.
+pub fn workspace_replace(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: WorkspaceReplaceParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid workspaceReplace params: {}", e),
+            );
+        }
+    };
+
+    let vfs = OverlayFs::new(Arc::clone(&state.vfs));
+    let mut versions = HashMap::new();
+    for (uri, document) in state.analysis().documents() {
+        if let Some(path) = uri_to_path(uri) {
+            vfs.overlay().insert(path.clone(), document.content());
+            versions.insert(path, document.version);
+        }
+    }
+
+    let roots: Vec<PathBuf> = state.root_path.iter().map(PathBuf::from).collect();
+    let options = params.options.into_options();
+    let result = match logos_index::workspace_replace(&vfs, &roots, &params.query, &params.replacement, &options) {
+        Ok(result) => result,
+        Err(e) => {
+            return Response::error(id, crate::protocol::error_codes::INVALID_PARAMS, e);
+        }
+    };
+
+    let mut document_changes = Vec::new();
+    let mut annotations = Map::new();
+    let mut files = Vec::new();
+    for file in &result.files {
+        let uri = vfs.uri(&file.path);
+        let count = file.edits.len();
+        annotations.insert(uri.clone(), json!({
+            "label": format!("{} {}", count, if count == 1 { "replacement" } else { "replacements" }),
+            "description": file.path.display().to_string(),
+            "needsConfirmation": true
+        }));
+        document_changes.push(json!({
+            "textDocument": { "uri": uri, "version": versions.get(&file.path) },
+            "edits": file.edits.iter().map(|edit| json!({
+                "range": range_json(&edit.range),
+                "newText": edit.new_text,
+                "annotationId": uri
+            })).collect::<Vec<_>>()
+        }));
+        files.push(json!({
+            "uri": uri,
+            "edits": file.edits.iter().map(|edit| json!({
+                "range": range_json(&edit.range),
+                "oldText": edit.old_text,
+                "newText": edit.new_text
+            })).collect::<Vec<_>>()
+        }));
+    }
+
+    Response::success(id, json!({
+        "edit": {
+            "documentChanges": document_changes,
+            "changeAnnotations": annotations
+        },
+        "files": files,
+        "matchCount": result.match_count(),
+        "truncated": result.truncated
+    }))
+}
+
+/// Handle logos/textSearch: the matches of a literal or regex query in the
+/// open documents and the files of the workspace scan, with the range and
+/// line text of each, per file. The trigram index of the analysis narrows
+/// the files searched, so nothing is read from disk.
+pub fn text_search(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: TextSearchParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid textSearch params: {}", e),
+            );
+        }
+    };
+
+    let options = params.options.into_options();
+    let result = match state.analysis().text_search(&params.query, &options) {
+        Ok(result) => result,
+        Err(e) => {
+            return Response::error(id, crate::protocol::error_codes::INVALID_PARAMS, e);
+        }
+    };
+
+    let files: Vec<Value> = result
+        .files
+        .iter()
+        .map(|file| {
+            json!({
+                "uri": file.uri,
+                "matches": file.matches.iter().map(|found| json!({
+                    "range": range_json(&found.range),
+                    "lineText": found.line_text
+                })).collect::<Vec<_>>()
+            })
+        })
+        .collect();
+
+    Response::success(id, json!({
+        "files": files,
+        "matchCount": result.match_count(),
+        "truncated": result.truncated
+    }))
+}
@@ -0,0 +1,76 @@
+//! Semantic tokens handler
+//!
+//! Tokens come from the grammar's highlight query over the cached parse
+//! tree. Documents above the parse-size limit, and languages whose grammar
+//! ships no highlight query, fall back to the lite tokenizer.
+
+use serde_json::{json, Value};
+use logos_parser::{highlight, lite, LanguageId};
+
+use crate::protocol::{DocumentSymbolParams, RequestId, Response};
+use crate::state::State;
+
+/// Handle textDocument/semanticTokens/full
+pub fn handle_full(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: DocumentSymbolParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid semanticTokens params: {}", e),
+            );
+        }
+    };
+
+    let doc = match state.get_document(&params.text_document.uri) {
+        Some(d) => d,
+        None => return Response::null_result(id),
+    };
+    let lang = match LanguageId::from_str(&doc.language_id) {
+        Some(l) => l,
+        None => return Response::null_result(id),
+    };
+    // (line, start, length, token type, modifiers) in document order
+    let mut tokens: Vec<[u32; 5]> = Vec::new();
+    let tree = state.get_tree(&params.text_document.uri).filter(|_| !lite::exceeds_parse_limit(doc.content()));
+    let highlighted = tree.and_then(|tree| {
+        let mut engine = state.queries.lock().unwrap_or_else(|e| e.into_inner());
+        match highlight::highlight(&mut engine, lang, tree, doc.content()) {
+            Ok(found) => found,
+            Err(e) => {
+                log::warn!("Highlighting {} failed: {}", lang.as_str(), e);
+                None
+            }
+        }
+    });
+    match highlighted {
+        Some(found) => {
+            for token in found {
+                if let Some((token_type, modifiers)) = token.semantic_type() {
+                    let start = token.range.start;
+                    let length = token.range.end.column - start.column;
+                    tokens.push([start.line, start.column, length, token_type, modifiers]);
+                }
+            }
+        }
+        None => {
+            for token in lite::tokenize(lang, doc.content()) {
+                tokens.push([token.line, token.start, token.length, token.kind.legend_index(), 0]);
+            }
+        }
+    }
+
+    // LSP relative encoding: deltaLine, deltaStart, length, tokenType, tokenModifiers
+    let mut data = Vec::with_capacity(tokens.len() * 5);
+    let (mut prev_line, mut prev_start) = (0, 0);
+    for [line, start, length, token_type, modifiers] in tokens {
+        let delta_line = line - prev_line;
+        let delta_start = if delta_line == 0 { start - prev_start } else { start };
+        data.extend_from_slice(&[delta_line, delta_start, length, token_type, modifiers]);
+        prev_line = line;
+        prev_start = start;
+    }
+
+    Response::success(id, json!({ "data": data }))
+}
@@ -0,0 +1,97 @@
+//! Runtime output handlers: stack trace frames and log lines, back to the
+//! source that produced them
+
+use std::path::PathBuf;
+
+use serde_json::{json, Value};
+use logos_index::{find_log_statements, parse_stack_trace, FrameResolver};
+
+use crate::protocol::{FindLogStatementParams, ResolveStackTraceParams, RequestId, Response};
+use crate::state::State;
+
+/// Handle logos/resolveStackTrace: the frames of the traces in a piece of
+/// output, each with the range of its location in the text and the
+/// workspace location it points to (null when the file isn't found)
+pub fn resolve_stack_trace(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: ResolveStackTraceParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid resolveStackTrace params: {}", e),
+            );
+        }
+    };
+
+    let roots: Vec<PathBuf> = state.root_path.iter().map(PathBuf::from).collect();
+    let resolver = FrameResolver::new(state.vfs.as_ref(), roots, state.workspace_files());
+
+    let lines: Vec<&str> = params.text.lines().collect();
+    let frames: Vec<Value> = parse_stack_trace(&params.text)
+        .into_iter()
+        .map(|frame| {
+            let text = lines.get(frame.trace_line as usize).copied().unwrap_or_default();
+            // The client counts UTF-16 code units
+            let character = |byte: usize| text.get(..byte).map_or(0, |prefix| prefix.encode_utf16().count());
+            let location = resolver.resolve(&frame).map(|path| {
+                let column = frame.column.unwrap_or(0);
+                json!({
+                    "uri": state.vfs.uri(&path),
+                    "range": {
+                        "start": { "line": frame.line, "character": column },
+                        "end": { "line": frame.line, "character": column }
+                    },
+                    "inWorkspace": resolver.in_workspace(&path)
+                })
+            });
+            json!({
+                "format": frame.format,
+                "function": frame.function,
+                "file": frame.file,
+                "traceRange": {
+                    "start": { "line": frame.trace_line, "character": character(frame.span.start) },
+                    "end": { "line": frame.trace_line, "character": character(frame.span.end) }
+                },
+                "location": location
+            })
+        })
+        .collect();
+    Response::success(id, json!(frames))
+}
+
+/// Matches returned when the request sets no limit
+const DEFAULT_LOG_MATCHES: usize = 10;
+
+/// Handle logos/findLogStatement: source lines whose string literals could
+/// have printed a line of output, best match first
+pub fn find_log_statement(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: FindLogStatementParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid findLogStatement params: {}", e),
+            );
+        }
+    };
+
+    let limit = params.limit.unwrap_or(DEFAULT_LOG_MATCHES);
+    let files = state.workspace_files();
+    let matches: Vec<Value> = find_log_statements(state.vfs.as_ref(), &files, params.text.trim(), limit)
+        .into_iter()
+        .map(|found| {
+            json!({
+                "uri": state.vfs.uri(&found.path),
+                "range": {
+                    "start": { "line": found.range.start.line, "character": found.range.start.column },
+                    "end": { "line": found.range.end.line, "character": found.range.end.column }
+                },
+                "literal": found.literal,
+                "score": found.score
+            })
+        })
+        .collect();
+    Response::success(id, json!(matches))
+}
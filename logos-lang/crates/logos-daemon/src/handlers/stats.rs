@@ -0,0 +1,233 @@
+//! Workspace statistics handler
+//!
+//! Backs the IDE status/dashboard view and helps diagnose slow indexing.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde_json::{json, Value};
+use logos_core::SymbolKind;
+use logos_parser::LanguageId;
+
+use crate::protocol::{RequestId, Response};
+use crate::state::State;
+
+/// Handle logos/getWorkspaceStats
+pub fn handle_get_workspace_stats(state: &State, _params: &Value, id: Option<RequestId>) -> Response {
+    // Files per language: open documents plus everything in the project index
+    let mut files_by_language: BTreeMap<String, usize> = BTreeMap::new();
+    let mut seen = HashSet::new();
+    for (uri, doc) in state.analysis().documents() {
+        seen.insert(uri.clone());
+        *files_by_language.entry(doc.language_id.clone()).or_insert(0) += 1;
+    }
+
+    let (symbol_counts, index_memory, evicted_files) = match state.get_indexer() {
+        Some(indexer) => {
+            let index = indexer.get_index();
+            for uri in index.symbols.files() {
+                if seen.insert(uri.clone()) {
+                    *files_by_language.entry(language_for_uri(&uri)).or_insert(0) += 1;
+                }
+            }
+            (
+                index.symbols.count_by_kind(),
+                index.symbols.estimated_memory_bytes(),
+                index.symbols.evicted_files(),
+            )
+        }
+        None => (
+            state.analysis().symbol_index().count_by_kind(),
+            state.analysis().symbol_index().estimated_memory_bytes(),
+            0,
+        ),
+    };
+
+    let todo_counts: BTreeMap<&str, usize> = state
+        .analysis()
+        .todo_index()
+        .count_by_kind()
+        .into_iter()
+        .map(|(kind, count)| (kind.as_str(), count))
+        .collect();
+
+    let last_index = state.last_index_stats.as_ref().map(|stats| {
+        json!({
+            "filesIndexed": stats.files_indexed,
+            "filesByLanguage": stats.files_by_language.iter().collect::<BTreeMap<_, _>>(),
+            "symbolsFound": stats.symbols_found,
+            "errorCount": stats.errors.len(),
+            "durationMs": stats.duration.as_millis() as u64,
+        })
+    });
+
+    Response::success(
+        id,
+        json!({
+            "mode": if state.is_smart_mode() { "smart" } else { "basic" },
+            "filesByLanguage": files_by_language,
+            "symbolsByKind": symbols_by_kind(&symbol_counts),
+            "todos": {
+                "total": state.analysis().todo_index().todo_count(),
+                "byKind": todo_counts,
+            },
+            "memory": {
+                "symbolIndexBytes": index_memory,
+                "evictedFiles": evicted_files,
+                "todoIndexBytes": state.analysis().todo_index().estimated_memory_bytes(),
+                "documentBytes": state.analysis().documents().map(|(_, d)| d.content().len()).sum::<usize>(),
+            },
+            "lastIndex": last_index,
+        }),
+    )
+}
+
+/// Handle logos/indexStats: how far indexing got, so that a client can
+/// tell "no results" from "not indexed yet". `status` is `indexing` while
+/// the workspace scan has files left, `ready` once the Smart mode index or
+/// the scan is complete, and `openDocuments` when only the open documents
+/// are analyzed.
+pub fn handle_index_stats(state: &State, _params: &Value, id: Option<RequestId>) -> Response {
+    let mut symbols_by_language: BTreeMap<String, usize> = BTreeMap::new();
+    let (file_count, index_bytes, detail_bytes, evicted_files) = match state.get_indexer() {
+        Some(indexer) => {
+            let index = indexer.get_index();
+            for (uri, count) in index.symbols.count_by_file() {
+                *symbols_by_language.entry(language_for_uri(&uri)).or_insert(0) += count;
+            }
+            (
+                index.files.len(),
+                index.symbols.estimated_memory_bytes(),
+                index.symbols.detail_bytes(),
+                index.symbols.evicted_files(),
+            )
+        }
+        None => {
+            let analysis = state.analysis();
+            let symbols = analysis.symbol_index();
+            for uri in symbols.documents() {
+                *symbols_by_language.entry(language_for_uri(uri)).or_insert(0) += symbols.get_document_symbols(uri).len();
+            }
+            (symbols.documents().count(), symbols.estimated_memory_bytes(), 0, 0)
+        }
+    };
+
+    let status = if state.pending_scan_files() > 0 {
+        "indexing"
+    } else if (state.is_smart_mode() && state.index_synced_at().is_some()) || state.last_scan_at.is_some() {
+        "ready"
+    } else {
+        "openDocuments"
+    };
+    let millis = |time: Option<SystemTime>| {
+        time.map(|time| time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64)
+    };
+    let last_run = state.last_index_stats.as_ref().map(|stats| {
+        json!({
+            "filesIndexed": stats.files_indexed,
+            "filesUnchanged": stats.files_unchanged,
+            "errorCount": stats.errors.len(),
+            "durationMs": stats.duration.as_millis() as u64,
+        })
+    });
+
+    Response::success(
+        id,
+        json!({
+            "mode": if state.is_smart_mode() { "smart" } else { "basic" },
+            "status": status,
+            "fileCount": file_count,
+            "symbolCount": symbols_by_language.values().sum::<usize>(),
+            "symbolsByLanguage": symbols_by_language,
+            "memory": {
+                "symbolIndexBytes": index_bytes,
+                "symbolDetailBytes": detail_bytes,
+                "evictedFiles": evicted_files,
+            },
+            "pending": {
+                "scanFiles": state.pending_scan_files(),
+                "jobs": state.pending_jobs(),
+            },
+            "lastIndexedAt": millis(state.index_synced_at()),
+            "lastScannedAt": millis(state.last_scan_at),
+            "lastReindexedAt": millis(state.last_reindex_at),
+            "lastIndexRun": last_run,
+        }),
+    )
+}
+
+/// Handle logos/getServerStats
+pub fn handle_get_server_stats(state: &State, _params: &Value, id: Option<RequestId>) -> Response {
+    let mut degraded: Vec<_> = state.language_failures.values().collect();
+    degraded.sort_by(|a, b| a.language.cmp(&b.language));
+    let degraded: Vec<_> = degraded
+        .into_iter()
+        .map(|f| {
+            json!({
+                "language": f.language,
+                "reason": f.reason,
+                "sinceMs": f.since.elapsed().as_millis() as u64,
+            })
+        })
+        .collect();
+
+    let plugins: Vec<_> = state
+        .plugins
+        .plugins()
+        .iter()
+        .map(|p| {
+            json!({
+                "name": p.manifest.name,
+                "version": p.manifest.version,
+                "path": p.path.display().to_string(),
+            })
+        })
+        .chain(state.plugins.errors.iter().map(|(path, error)| {
+            json!({
+                "path": path.display().to_string(),
+                "error": error,
+            })
+        }))
+        .collect();
+
+    let grammars: Vec<_> = logos_parser::compat::all()
+        .into_iter()
+        .map(|g| {
+            json!({
+                "language": g.language.as_str(),
+                "crate": g.crate_name,
+                "version": g.version,
+                "abiVersion": g.abi_version,
+                "nodeKindHash": format!("{:016x}", g.node_kind_hash),
+            })
+        })
+        .collect();
+
+    Response::success(
+        id,
+        json!({
+            "version": env!("CARGO_PKG_VERSION"),
+            "uptimeMs": state.started_at.elapsed().as_millis() as u64,
+            "mode": if state.is_smart_mode() { "smart" } else { "basic" },
+            "initialized": state.initialized,
+            "openDocuments": state.analysis().documents().count(),
+            "degradedLanguages": degraded,
+            "pluginAbiVersion": crate::plugins::PLUGIN_ABI_VERSION,
+            "plugins": plugins,
+            "grammars": grammars,
+        }),
+    )
+}
+
+fn symbols_by_kind(counts: &HashMap<SymbolKind, usize>) -> BTreeMap<String, usize> {
+    counts
+        .iter()
+        .map(|(kind, count)| (format!("{:?}", kind), *count))
+        .collect()
+}
+
+fn language_for_uri(uri: &str) -> String {
+    uri.rsplit_once('.')
+        .and_then(|(_, ext)| LanguageId::from_extension(ext))
+        .map(|lang| lang.as_str().to_string())
+        .unwrap_or_else(|| "other".to_string())
+}
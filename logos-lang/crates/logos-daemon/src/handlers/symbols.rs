@@ -1,11 +1,15 @@
 //! Symbol handlers
 
 use serde_json::{json, Value};
+use logos_analysis::{OutlineEntry, Section};
+use logos_core::{Range, SymbolKind};
+use logos_index::IndexedSymbol;
 
-use crate::protocol::{DocumentSymbolParams, WorkspaceSymbolParams, RequestId, Response};
+use crate::protocol::{DocumentSymbolParams, OutlinePageParams, WorkspaceSymbolParams, RequestId, Response};
 use crate::state::State;
 
-/// Handle textDocument/documentSymbol
+/// Handle textDocument/documentSymbol. Documents with more symbols than
+/// the configured cap get a summarized outline instead of the flat list.
 pub fn document_symbols(state: &State, params: &Value, id: Option<RequestId>) -> Response {
     let params: DocumentSymbolParams = match serde_json::from_value(params.clone()) {
         Ok(p) => p,
@@ -20,37 +24,119 @@ pub fn document_symbols(state: &State, params: &Value, id: Option<RequestId>) ->
 
     let uri = &params.text_document.uri;
 
-    let symbols: Vec<_> = state.symbol_index.get_document_symbols(uri)
+    let outline = state.analysis().outline(uri, state.settings.outline.options());
+    if outline.is_over_budget() {
+        let version = state.get_document(uri).map(|doc| doc.version);
+        return Response::success(id, outline_json(&outline.summary(), version));
+    }
+
+    let symbols: Vec<_> = state.analysis().document_symbols(uri)
         .iter()
-        .map(|s| {
-            json!({
-                "name": s.name,
-                "kind": s.kind.to_monaco_kind(),
-                "range": {
-                    "start": {
-                        "line": s.range.start.line,
-                        "character": s.range.start.column
-                    },
-                    "end": {
-                        "line": s.range.end.line,
-                        "character": s.range.end.column
-                    }
-                },
-                "selectionRange": {
-                    "start": {
-                        "line": s.selection_range.start.line,
-                        "character": s.selection_range.start.column
+        .map(symbol_json)
+        .collect();
+
+    Response::success(id, json!(symbols))
+}
+
+/// Handle logos/getOutlinePage: the symbols behind a placeholder of a
+/// summarized outline
+pub fn outline_page(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: OutlinePageParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid getOutlinePage params: {}", e),
+            );
+        }
+    };
+
+    let uri = &params.text_document.uri;
+    let version = state.get_document(uri).map(|doc| doc.version);
+    if params.version.is_some() && params.version != version {
+        return Response::error(
+            id,
+            crate::protocol::error_codes::CONTENT_MODIFIED,
+            "The document changed; request its symbols again".to_string(),
+        );
+    }
+
+    let section = Section {
+        parent: params.section.parent,
+        start: params.section.start,
+        trivial: params.section.trivial,
+    };
+    match state.analysis().outline(uri, state.settings.outline.options()).page(section) {
+        Some(entries) => Response::success(id, outline_json(&entries, version)),
+        None => Response::error(
+            id,
+            crate::protocol::error_codes::INVALID_PARAMS,
+            format!("No outline section {:?} in {}", section, uri),
+        ),
+    }
+}
+
+fn symbol_json(s: &IndexedSymbol) -> Value {
+    json!({
+        "name": s.name,
+        "kind": s.kind.to_monaco_kind(),
+        "range": range_json(&s.range),
+        "selectionRange": range_json(&s.selection_range)
+    })
+}
+
+/// Nested document symbols; a placeholder carries the section it stands
+/// for and the document version in `data`, for `logos/getOutlinePage`
+fn outline_json(entries: &[OutlineEntry], version: Option<u32>) -> Value {
+    let symbols: Vec<_> = entries
+        .iter()
+        .map(|entry| match entry {
+            OutlineEntry::Symbol { symbol, children } => {
+                let mut value = symbol_json(symbol);
+                value["children"] = outline_json(children, version);
+                value
+            }
+            OutlineEntry::More { section, count, range, selection_range } => json!({
+                "name": format!("{} more…", count),
+                "detail": placeholder_detail(section),
+                "kind": SymbolKind::Null.to_monaco_kind(),
+                "range": range_json(range),
+                "selectionRange": range_json(selection_range),
+                "children": [],
+                "data": {
+                    "section": {
+                        "parent": section.parent,
+                        "start": section.start,
+                        "trivial": section.trivial
                     },
-                    "end": {
-                        "line": s.selection_range.end.line,
-                        "character": s.selection_range.end.column
-                    }
+                    "version": version
                 }
-            })
+            }),
         })
         .collect();
+    json!(symbols)
+}
 
-    Response::success(id, json!(symbols))
+fn placeholder_detail(section: &Section) -> &'static str {
+    if section.trivial {
+        "fields, variables and constants"
+    } else {
+        "not loaded"
+    }
+}
+
+fn range_json(range: &Range) -> Value {
+    json!({
+        "start": {
+            "line": range.start.line,
+            "character": range.start.column
+        },
+        "end": {
+            "line": range.end.line,
+            "character": range.end.column
+        }
+    })
 }
 
 /// Handle workspace/symbol
@@ -66,12 +152,32 @@ pub fn workspace_symbols(state: &State, params: &Value, id: Option<RequestId>) -
         }
     };
 
-    let results: Vec<_> = state.symbol_index.search(&params.query)
+    let results: Vec<_> = state.analysis().workspace_symbols(&params.query, state.settings.search.name_match())
         .iter()
-        .map(|s| {
+        .map(|m| {
+            let s = m.symbol;
+            let alternates: Vec<_> = m.alternate_locations
+                .iter()
+                .map(|loc| {
+                    json!({
+                        "uri": loc.uri,
+                        "range": {
+                            "start": {
+                                "line": loc.range.start.line,
+                                "character": loc.range.start.column
+                            },
+                            "end": {
+                                "line": loc.range.end.line,
+                                "character": loc.range.end.column
+                            }
+                        }
+                    })
+                })
+                .collect();
             json!({
                 "name": s.name,
                 "kind": s.kind.to_monaco_kind(),
+                "containerName": s.container,
                 "location": {
                     "uri": s.uri,
                     "range": {
@@ -84,7 +190,8 @@ pub fn workspace_symbols(state: &State, params: &Value, id: Option<RequestId>) -
                             "character": s.range.end.column
                         }
                     }
-                }
+                },
+                "alternateLocations": alternates
             })
         })
         .collect();
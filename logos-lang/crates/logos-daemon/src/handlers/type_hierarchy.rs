@@ -0,0 +1,167 @@
+//! Type Hierarchy handler (LSP 3.17) and go to implementation
+//!
+//! Supertypes and subtypes come from the type hierarchy of the project
+//! index, across the whole workspace, in Smart mode.
+
+use serde_json::{json, Value};
+use logos_core::{Position, SymbolKind};
+use logos_index::{is_type_kind, ProjectIndex, SmartSymbol, SymbolId};
+
+use crate::handlers::call_hierarchy::{range_to_serializable, symbol_kind_to_lsp};
+use crate::handlers::definition;
+use crate::protocol::{RequestId, Response, TextDocumentPositionParams, TypeHierarchyItem, TypeHierarchyItemParams};
+use crate::state::State;
+
+/// Handle textDocument/prepareTypeHierarchy
+pub fn handle_prepare(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: TextDocumentPositionParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid prepareTypeHierarchy params: {}", e),
+            );
+        }
+    };
+
+    let Some(indexer) = state.get_indexer().filter(|_| state.is_smart_mode()) else {
+        return Response::success(id, json!(null));
+    };
+
+    let uri = &params.text_document.uri;
+    let position = Position::new(params.position.line, params.position.character);
+    match type_at(state, &indexer.get_index(), uri, position) {
+        Some(symbol) => Response::success(id, json!([item(&symbol)])),
+        None => Response::success(id, json!(null)),
+    }
+}
+
+/// Handle typeHierarchy/supertypes
+pub fn handle_supertypes(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    related(state, params, id, "supertypes", ProjectIndex::supertypes)
+}
+
+/// Handle typeHierarchy/subtypes
+pub fn handle_subtypes(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    related(state, params, id, "subtypes", ProjectIndex::subtypes)
+}
+
+/// Handle textDocument/implementation: the types extending or
+/// implementing the type at the cursor, directly or not. On a method of a
+/// type, the methods of the same name in those types.
+pub fn handle_implementation(state: &State, params: &Value, id: Option<RequestId>) -> Response {
+    let params: TextDocumentPositionParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid implementation params: {}", e),
+            );
+        }
+    };
+
+    let Some(indexer) = state.get_indexer().filter(|_| state.is_smart_mode()) else {
+        return Response::success(id, json!(null));
+    };
+
+    let uri = &params.text_document.uri;
+    let position = Position::new(params.position.line, params.position.character);
+    let index = indexer.get_index();
+
+    let method = index
+        .symbols
+        .find_at_position(uri, position)
+        .filter(|s| matches!(s.kind, SymbolKind::Method | SymbolKind::Function | SymbolKind::Property));
+    let owner = method.as_ref().and_then(|m| m.parent).and_then(|parent| index.symbols.get(parent));
+    let implementations: Vec<SmartSymbol> = match (method, owner) {
+        (Some(method), Some(owner)) if is_type_kind(owner.kind) => index
+            .implementations(owner.id)
+            .iter()
+            .flat_map(|ty| {
+                let (parent, name) = (ty.id, &method.name);
+                index
+                    .symbols
+                    .get_file_symbols(&ty.location.uri)
+                    .into_iter()
+                    .filter(move |member| member.parent == Some(parent) && member.name == *name)
+            })
+            .collect(),
+        _ => match type_at(state, &index, uri, position) {
+            Some(ty) => index.implementations(ty.id),
+            None => Vec::new(),
+        },
+    };
+
+    let locations: Vec<Value> = implementations
+        .iter()
+        .map(|s| json!({ "uri": s.location.uri, "range": range_to_serializable(&s.location.range) }))
+        .collect();
+    Response::success(id, json!(locations))
+}
+
+/// Answer a supertypes or subtypes request with `find`
+fn related(
+    state: &State,
+    params: &Value,
+    id: Option<RequestId>,
+    method: &str,
+    find: fn(&ProjectIndex, SymbolId) -> Vec<SmartSymbol>,
+) -> Response {
+    let params: TypeHierarchyItemParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            return Response::error(
+                id,
+                crate::protocol::error_codes::INVALID_PARAMS,
+                format!("Invalid {} params: {}", method, e),
+            );
+        }
+    };
+
+    let Some(indexer) = state.get_indexer().filter(|_| state.is_smart_mode()) else {
+        return Response::success(id, json!([]));
+    };
+
+    // Get symbol ID from item data
+    let symbol_id = params
+        .item
+        .data
+        .as_ref()
+        .and_then(|d| d.get("symbolId"))
+        .and_then(|v| v.as_u64())
+        .map(SymbolId);
+    let Some(symbol_id) = symbol_id else {
+        return Response::success(id, json!([]));
+    };
+
+    let items: Vec<TypeHierarchyItem> = find(&indexer.get_index(), symbol_id).iter().map(item).collect();
+    Response::success(id, json!(items))
+}
+
+/// The type declared at `position`, or the one the identifier there
+/// resolves to
+fn type_at(state: &State, index: &ProjectIndex, uri: &str, position: Position) -> Option<SmartSymbol> {
+    let declared = index.symbols.find_at_position(uri, position).filter(|s| is_type_kind(s.kind));
+    declared.or_else(|| {
+        let target = definition::resolve(state, uri, position)?;
+        index
+            .symbols
+            .get_file_symbols(&target.uri)
+            .into_iter()
+            .find(|s| s.name == target.name && is_type_kind(s.kind))
+    })
+}
+
+fn item(symbol: &SmartSymbol) -> TypeHierarchyItem {
+    TypeHierarchyItem {
+        name: symbol.name.clone(),
+        kind: symbol_kind_to_lsp(symbol.kind),
+        detail: Some(symbol.qualified_name.clone()),
+        uri: symbol.location.uri.clone(),
+        range: range_to_serializable(&symbol.location.range),
+        selection_range: range_to_serializable(&symbol.location.selection_range),
+        data: Some(json!({ "symbolId": symbol.id.0 })),
+    }
+}
@@ -0,0 +1,36 @@
+//! Workspace notification handlers
+
+use serde_json::Value;
+
+use crate::config::Settings;
+use crate::protocol::{DidChangeConfigurationParams, RenameFilesParams};
+use crate::state::State;
+
+/// Handle workspace/didChangeConfiguration
+pub fn did_change_configuration(state: &mut State, params: &Value) {
+    let params: DidChangeConfigurationParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            log::warn!("Invalid didChangeConfiguration params: {}", e);
+            return;
+        }
+    };
+
+    state.apply_settings(Settings::from_value(&params.settings));
+    log::info!("Settings updated: {:?}", state.settings);
+}
+
+/// Handle workspace/didRenameFiles
+pub fn did_rename_files(state: &mut State, params: &Value) {
+    let params: RenameFilesParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => {
+            log::warn!("Invalid didRenameFiles params: {}", e);
+            return;
+        }
+    };
+
+    for file in params.files {
+        state.rename_files(&file.old_uri, &file.new_uri);
+    }
+}
@@ -0,0 +1,203 @@
+//! User hooks
+//!
+//! Settings can attach external commands to server events. Each command is
+//! started with `{"event": .., "payload": ..}` as JSON on stdin and the event
+//! name in `$LOGOS_HOOK_EVENT`. Commands run in the background, so a slow
+//! hook never stalls requests; one still running at its timeout is killed.
+//! Plugins that list an event under `hooks` in their manifest receive it as
+//! a `logos/hook` call with the same JSON.
+//!
+//! Events that come with every request on a document, such as
+//! `diagnosticsPublished` on each diagnostics pull, fire through a
+//! [`HookRuns`] claim instead: while the commands started for a document
+//! are still running, the event is skipped for that document.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+
+use crate::config::{HookCommand, HookSettings};
+use crate::plugins::PluginHost;
+use crate::process::{CancelToken, ToolCommand};
+
+/// Events hooks can attach to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum HookEvent {
+    /// `textDocument/willSave`, before the editor writes the file
+    WillSave,
+    /// `textDocument/didSave`
+    DidSave,
+    /// Workspace indexing finished
+    IndexComplete,
+    /// Diagnostics computed for a document
+    DiagnosticsPublished,
+}
+
+impl HookEvent {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::WillSave => "willSave",
+            Self::DidSave => "didSave",
+            Self::IndexComplete => "indexComplete",
+            Self::DiagnosticsPublished => "diagnosticsPublished",
+        }
+    }
+}
+
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Events whose commands are running, by the document they fired for
+#[derive(Debug, Clone, Default)]
+pub struct HookRuns(Arc<Mutex<HashSet<(HookEvent, String)>>>);
+
+impl HookRuns {
+    /// Claim `event` for `uri`; `None` while the commands of an earlier
+    /// claim are still running
+    pub fn claim(&self, event: HookEvent, uri: &str) -> Option<Arc<HookClaim>> {
+        let key = (event, uri.to_string());
+        let claimed = self.0.lock().unwrap_or_else(PoisonError::into_inner).insert(key.clone());
+        claimed.then(|| Arc::new(HookClaim { runs: self.clone(), key }))
+    }
+}
+
+/// Holds an event for a document until the last command given it is done
+#[derive(Debug)]
+pub struct HookClaim {
+    runs: HookRuns,
+    key: (HookEvent, String),
+}
+
+impl Drop for HookClaim {
+    fn drop(&mut self) {
+        self.runs.0.lock().unwrap_or_else(PoisonError::into_inner).remove(&self.key);
+    }
+}
+
+/// Fire `event` to the configured commands and subscribed plugins.
+/// Commands run in `cwd` (the workspace root) when given and are killed
+/// when `cancel` is; each keeps `claim` until it exits.
+pub fn fire(
+    settings: &HookSettings,
+    plugins: &PluginHost,
+    cwd: Option<&Path>,
+    cancel: &CancelToken,
+    event: HookEvent,
+    payload: Value,
+    claim: Option<Arc<HookClaim>>,
+) {
+    let message = json!({ "event": event.as_str(), "payload": payload });
+
+    for hook in settings.commands_for(event) {
+        spawn(hook, cwd, cancel, event, message.to_string(), claim.clone());
+    }
+    plugins.notify(event.as_str(), &message);
+}
+
+fn spawn(
+    hook: &HookCommand,
+    cwd: Option<&Path>,
+    cancel: &CancelToken,
+    event: HookEvent,
+    input: String,
+    claim: Option<Arc<HookClaim>>,
+) {
+    let mut command = ToolCommand::new(&hook.command)
+        .args(&hook.args)
+        .env("LOGOS_HOOK_EVENT", event.as_str())
+        .stdin(input)
+        .timeout(hook.timeout_ms.map(Duration::from_millis).unwrap_or(DEFAULT_TIMEOUT))
+        .cancel_token(cancel.clone());
+    if let Some(dir) = hook.cwd.as_deref().map(Path::new).or(cwd) {
+        command = command.current_dir(dir);
+    }
+
+    let name = hook.command.clone();
+    command.spawn(move |result| {
+        match result {
+            Ok(output) if !output.status.success() => {
+                let stderr = String::from_utf8_lossy(&output.stderr);
+                log::warn!("{} hook {} exited with {}: {}", event.as_str(), name, output.status, stderr.trim());
+            }
+            Ok(_) => {}
+            Err(e) => log::warn!("{} hook {} failed: {}", event.as_str(), name, e),
+        }
+        drop(claim);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::time::Instant;
+
+    fn hook(script: &str) -> HookCommand {
+        serde_json::from_value(json!({ "command": "sh", "args": ["-c", script] })).unwrap()
+    }
+
+    /// Wait for a hook to get somewhere, failing after a few seconds
+    fn wait_for(mut done: impl FnMut() -> bool) {
+        let started = Instant::now();
+        while !done() {
+            assert!(started.elapsed() < Duration::from_secs(5), "hook did not finish");
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_commands_for() {
+        let settings: HookSettings = serde_json::from_value(json!({
+            "didSave": [{ "command": "fmt" }],
+            "diagnosticsPublished": [{ "command": "notify", "args": ["-q"] }, { "command": "log" }],
+        }))
+        .unwrap();
+        let commands = |event| settings.commands_for(event).iter().map(|h| h.command.as_str()).collect::<Vec<_>>();
+        assert_eq!(commands(HookEvent::DidSave), vec!["fmt"]);
+        assert_eq!(commands(HookEvent::DiagnosticsPublished), vec!["notify", "log"]);
+        assert!(commands(HookEvent::WillSave).is_empty());
+        assert!(commands(HookEvent::IndexComplete).is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_command_gets_event_and_payload() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = HookSettings {
+            did_save: vec![hook("cat > input.json && printf %s \"$LOGOS_HOOK_EVENT\" > event.tmp && mv event.tmp event")],
+            ..HookSettings::default()
+        };
+        let payload = json!({ "uri": "file:///a.py", "languageId": "python" });
+        fire(&settings, &PluginHost::default(), Some(dir.path()), &CancelToken::new(), HookEvent::DidSave, payload.clone(), None);
+
+        wait_for(|| dir.path().join("event").exists());
+        assert_eq!(fs::read_to_string(dir.path().join("event")).unwrap(), "didSave");
+        let input: Value = serde_json::from_slice(&fs::read(dir.path().join("input.json")).unwrap()).unwrap();
+        assert_eq!(input, json!({ "event": "didSave", "payload": payload }));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_claim_held_until_commands_exit() {
+        let dir = tempfile::tempdir().unwrap();
+        let settings = HookSettings {
+            diagnostics_published: vec![hook("cat > /dev/null; while [ ! -e go ]; do sleep 0.01; done")],
+            ..HookSettings::default()
+        };
+        let runs = HookRuns::default();
+        let event = HookEvent::DiagnosticsPublished;
+        let claim = runs.claim(event, "file:///a.py");
+        assert!(claim.is_some());
+        fire(&settings, &PluginHost::default(), Some(dir.path()), &CancelToken::new(), event, json!({}), claim);
+
+        // Skipped for the same document while the command runs
+        assert!(runs.claim(event, "file:///a.py").is_none());
+        assert!(runs.claim(event, "file:///b.py").is_some());
+        assert!(runs.claim(HookEvent::DidSave, "file:///a.py").is_some());
+
+        fs::write(dir.path().join("go"), "").unwrap();
+        wait_for(|| runs.claim(event, "file:///a.py").is_some());
+    }
+}
@@ -0,0 +1,172 @@
+//! Requests served in steps while the server is idle
+//!
+//! Rename and safe delete can go through every file of a large workspace.
+//! Instead of being answered right away they are queued as a [`Job`], and
+//! the server works on them a few files at a time between messages: a
+//! `$/cancelRequest` arriving meanwhile stops the job before its edits are
+//! complete, and the client sees per-file work-done progress. The response
+//! is sent when the job finishes, or a `RequestCancelled` error when it is
+//! cancelled.
+//!
+//! Progress goes under the `workDoneToken` of the request when the client
+//! gave one. Otherwise, if the client supports it, the server asks for a
+//! token with `window/workDoneProgress/create` and reports nothing until
+//! the client has answered. Cancelling the progress in the client
+//! (`window/workDoneProgress/cancel`) cancels the job too.
+
+use serde_json::{json, Value};
+
+use crate::protocol::{error_codes, Notification, Outgoing, RequestId, Response, ServerRequest};
+use crate::state::State;
+
+/// Files a job goes through between two checks for incoming messages
+pub const FILES_PER_STEP: usize = 16;
+
+/// Answer of a finished job: its result, or the code and message of the
+/// error it fails with
+pub type JobResult = Result<Value, (i32, String)>;
+
+/// A request answered once all its files are gone through
+pub trait Job {
+    /// Title of the progress, e.g. "Renaming foo"
+    fn title(&self) -> String;
+
+    /// Files to go through in total
+    fn total(&self) -> usize;
+
+    /// Files gone through so far
+    fn done(&self) -> usize;
+
+    /// Go through the next files; returns the answer once all are done,
+    /// or as soon as the job fails
+    fn step(&mut self, state: &State) -> Option<JobResult>;
+}
+
+/// A queued job and where its progress and result go
+pub struct RunningJob {
+    pub id: RequestId,
+    job: Box<dyn Job>,
+    progress: WorkDone,
+}
+
+impl RunningJob {
+    pub fn new(id: RequestId, job: Box<dyn Job>, progress: WorkDone) -> Self {
+        Self { id, job, progress }
+    }
+
+    /// Take the next step; returns the messages to send and whether the job
+    /// is complete
+    pub fn step(&mut self, state: &State) -> (Vec<Outgoing>, bool) {
+        let mut messages = Vec::new();
+        let result = self.job.step(state);
+        match result {
+            Some(Ok(result)) => {
+                messages.extend(self.progress.end("Done").map(Outgoing::from));
+                messages.push(Response::success(Some(self.id.clone()), result).into());
+                (messages, true)
+            }
+            Some(Err((code, message))) => {
+                messages.extend(self.progress.end("Failed").map(Outgoing::from));
+                messages.push(Response::error(Some(self.id.clone()), code, message).into());
+                (messages, true)
+            }
+            None => {
+                let (done, total) = (self.job.done(), self.job.total());
+                messages.extend(self.progress.report(&self.job.title(), done, total).map(Outgoing::from));
+                (messages, false)
+            }
+        }
+    }
+
+    /// Stop the job; returns the messages to send
+    pub fn cancel(mut self) -> Vec<Outgoing> {
+        log::info!("{}: cancelled after {}/{} files", self.job.title(), self.job.done(), self.job.total());
+        let mut messages: Vec<Outgoing> = self.progress.end("Cancelled").into_iter().map(Outgoing::from).collect();
+        messages.push(
+            Response::error(Some(self.id), error_codes::REQUEST_CANCELLED, "Request cancelled".to_string()).into(),
+        );
+        messages
+    }
+
+    /// Whether progress is reported under `token`
+    pub fn reports_to(&self, token: &Value) -> bool {
+        self.progress.token.as_ref() == Some(token)
+    }
+
+    /// Record the client's answer to the request creating the progress
+    /// token; returns false when `id` is not that request
+    pub fn acknowledge(&mut self, id: &RequestId, accepted: bool) -> bool {
+        self.progress.acknowledge(id, accepted)
+    }
+}
+
+/// Work-done progress of a job
+pub struct WorkDone {
+    /// Token to report under; none when the client can't show progress
+    token: Option<Value>,
+    /// The `window/workDoneProgress/create` request not answered yet
+    creating: Option<RequestId>,
+    begun: bool,
+}
+
+impl WorkDone {
+    /// No progress reported
+    pub fn none() -> Self {
+        Self { token: None, creating: None, begun: false }
+    }
+
+    /// Progress under a token the client sent with its request
+    pub fn client(token: Value) -> Self {
+        Self { token: Some(token), creating: None, begun: false }
+    }
+
+    /// Progress under a token the server creates: returns the progress and
+    /// the request asking the client to create the token
+    pub fn create(request_id: RequestId, token: Value) -> (Self, ServerRequest) {
+        let request = ServerRequest::new(
+            request_id.clone(),
+            "window/workDoneProgress/create",
+            json!({ "token": token }),
+        );
+        (Self { token: Some(token), creating: Some(request_id), begun: false }, request)
+    }
+
+    fn acknowledge(&mut self, id: &RequestId, accepted: bool) -> bool {
+        if self.creating.as_ref() != Some(id) {
+            return false;
+        }
+        self.creating = None;
+        if !accepted {
+            self.token = None;
+        }
+        true
+    }
+
+    /// `begin` the first time, `report` after that
+    fn report(&mut self, title: &str, done: usize, total: usize) -> Option<Notification> {
+        if self.creating.is_some() {
+            return None;
+        }
+        let message = format!("{}/{} files", done, total);
+        let percentage = done * 100 / total.max(1);
+        let value = if self.begun {
+            json!({ "kind": "report", "message": message, "percentage": percentage })
+        } else {
+            self.begun = true;
+            json!({ "kind": "begin", "title": title, "message": message, "percentage": percentage, "cancellable": true })
+        };
+        self.progress(value)
+    }
+
+    fn end(&mut self, message: &str) -> Option<Notification> {
+        if !self.begun {
+            return None;
+        }
+        self.progress(json!({ "kind": "end", "message": message }))
+    }
+
+    fn progress(&self, value: Value) -> Option<Notification> {
+        let token = self.token.clone()?;
+        Some(Notification::new("$/progress", json!({ "token": token, "value": value })))
+    }
+}
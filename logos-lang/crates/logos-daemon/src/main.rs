@@ -1,37 +1,145 @@
 //! logos-daemon - Language service daemon for Logos IDE
 //!
 //! Communicates via stdio using JSON-RPC 2.0 protocol with LSP-style headers.
-
+//! One-shot subcommands such as `tags` and `check` are handled in [`cli`].
+
+mod baseline;
+mod check;
+mod cli;
+mod config;
+mod git;
+mod hooks;
+mod jobs;
+mod plugins;
+mod process;
 mod protocol;
+mod scan;
+mod scheduler;
 mod server;
 mod state;
 mod handlers;
 
-use std::io::{self, BufRead, BufReader, Read, Write};
+use std::io::{self, BufRead, BufReader, Write};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
 use log::{info, error, debug};
 
+use scheduler::Scheduler;
+
 fn main() {
     // Initialize logger
     env_logger::Builder::from_env(
         env_logger::Env::default().default_filter_or("info")
     ).init();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
     info!("logos-daemon starting...");
 
-    let stdin = io::stdin();
     let stdout = io::stdout();
-    let mut reader = BufReader::new(stdin.lock());
     let mut stdout = stdout.lock();
 
+    // Read messages on their own thread so queued work can be prioritized
+    let (tx, rx) = mpsc::channel::<String>();
+    let reader_thread = thread::spawn(move || {
+        let stdin = io::stdin();
+        let mut reader = BufReader::new(stdin.lock());
+        while let Some(body) = read_message(&mut reader) {
+            if tx.send(body).is_err() {
+                break;
+            }
+        }
+    });
+
     let mut server = server::Server::new();
+    let mut queue = Scheduler::new();
+    let mut input_closed = false;
 
+    loop {
+        // Block only when there is nothing left to do
+        if queue.is_empty() {
+            if input_closed {
+                break;
+            }
+            if !server.has_background_work() {
+                match rx.recv() {
+                    Ok(body) => enqueue(&mut queue, body),
+                    Err(_) => {
+                        input_closed = true;
+                        continue;
+                    }
+                }
+            }
+        }
+        input_closed |= drain_pending(&rx, &mut queue);
+
+        let body = match queue.pop() {
+            Some(body) => body,
+            None => {
+                // Idle: take the next step of background work
+                server.run_background_work();
+                let messages = server.take_outgoing();
+                if let Err(e) = messages.iter().try_for_each(|m| write_message(&mut stdout, m)) {
+                    error!("Error writing message: {}", e);
+                    break;
+                }
+                continue;
+            }
+        };
+
+        // Handle message
+        let response = server.handle_message(&body);
+        let messages = server.take_outgoing();
+        let outgoing = response.iter().chain(&messages);
+        if let Err(e) = outgoing.into_iter().try_for_each(|m| write_message(&mut stdout, m)) {
+            error!("Error writing message: {}", e);
+            break;
+        }
+
+        // Check if we should exit
+        if server.should_exit() {
+            info!("Exit requested, shutting down");
+            break;
+        }
+    }
+
+    // The reader thread only finishes on EOF; don't wait for it after `exit`
+    if input_closed {
+        let _ = reader_thread.join();
+    }
+    info!("logos-daemon stopped");
+}
+
+fn enqueue(queue: &mut Scheduler<String>, body: String) {
+    let lane = scheduler::lane_for_message(&body);
+    queue.push(lane, body);
+    debug!("Queued on {:?} lane, {} pending", lane, queue.len());
+}
+
+/// Move everything already received into the queue; returns true once the
+/// reader has hung up
+fn drain_pending(rx: &Receiver<String>, queue: &mut Scheduler<String>) -> bool {
+    loop {
+        match rx.try_recv() {
+            Ok(body) => enqueue(queue, body),
+            Err(TryRecvError::Empty) => return false,
+            Err(TryRecvError::Disconnected) => return true,
+        }
+    }
+}
+
+/// Read one framed message; `None` on EOF
+fn read_message<R: BufRead>(reader: &mut R) -> Option<String> {
     loop {
         // Read header
-        let content_length = match read_header(&mut reader) {
+        let content_length = match read_header(reader) {
             Ok(Some(len)) => len,
             Ok(None) => {
                 info!("EOF reached, shutting down");
-                break;
+                return None;
             }
             Err(e) => {
                 error!("Error reading header: {}", e);
@@ -46,45 +154,27 @@ fn main() {
             continue;
         }
 
-        let body = match String::from_utf8(body) {
-            Ok(s) => s,
+        match String::from_utf8(body) {
+            Ok(s) => {
+                debug!("Received: {}", s);
+                return Some(s);
+            }
             Err(e) => {
                 error!("Invalid UTF-8 in body: {}", e);
                 continue;
             }
-        };
-
-        debug!("Received: {}", body);
-
-        // Handle message
-        if let Some(response) = server.handle_message(&body) {
-            let response_bytes = response.as_bytes();
-            let header = format!("Content-Length: {}\r\n\r\n", response_bytes.len());
-
-            if let Err(e) = stdout.write_all(header.as_bytes()) {
-                error!("Error writing header: {}", e);
-                break;
-            }
-            if let Err(e) = stdout.write_all(response_bytes) {
-                error!("Error writing body: {}", e);
-                break;
-            }
-            if let Err(e) = stdout.flush() {
-                error!("Error flushing stdout: {}", e);
-                break;
-            }
-
-            debug!("Sent: {}", response);
-        }
-
-        // Check if we should exit
-        if server.should_exit() {
-            info!("Exit requested, shutting down");
-            break;
         }
     }
+}
 
-    info!("logos-daemon stopped");
+/// Write a message with an LSP-style header
+fn write_message<W: Write>(out: &mut W, message: &str) -> io::Result<()> {
+    let bytes = message.as_bytes();
+    write!(out, "Content-Length: {}\r\n\r\n", bytes.len())?;
+    out.write_all(bytes)?;
+    out.flush()?;
+    debug!("Sent: {}", message);
+    Ok(())
 }
 
 /// Read LSP-style header and return content length
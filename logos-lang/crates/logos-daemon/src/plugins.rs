@@ -0,0 +1,426 @@
+//! Native plugins
+//!
+//! A plugin is a dynamic library (`.so`/`.dylib`/`.dll`) in the plugins
+//! directory. It exports a small C ABI through which every value travels as
+//! a NUL-terminated JSON string, so plugins can be written in any language
+//! that builds a C-compatible shared library:
+//!
+//! ```c
+//! uint32_t logos_plugin_abi_version(void);          // must equal PLUGIN_ABI_VERSION
+//! char*    logos_plugin_manifest(void);             // PluginManifest as JSON
+//! char*    logos_plugin_call(const char* method,    // {"result": ..} or {"error": ".."}
+//!                            const char* params);
+//! void     logos_plugin_free(char* ptr);            // frees strings returned above
+//! ```
+//!
+//! `logos_plugin_call` receives the custom request methods listed in the
+//! manifest, plus `logos/lint` for plugins declaring lint rules,
+//! `logos/analyze` for plugins declaring languages and `logos/hook` for
+//! subscribed hook events.
+
+use std::collections::HashSet;
+use std::ffi::{c_char, CStr, CString};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, OnceLock, PoisonError};
+
+use libloading::Library;
+use logos_core::Symbol;
+use logos_index::{make_location, AnalysisResult, LanguageAdapter, SmartSymbol, SymbolBuilder, SymbolId};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+/// Version of the plugin ABI; bumped on any incompatible change
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type ManifestFn = unsafe extern "C" fn() -> *mut c_char;
+type CallFn = unsafe extern "C" fn(*const c_char, *const c_char) -> *mut c_char;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+/// What a plugin contributes
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginManifest {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    /// Custom request methods, e.g. `acme/listRoutes`
+    #[serde(default)]
+    pub methods: Vec<String>,
+    /// Lint rule ids run on `textDocument/diagnostic`
+    #[serde(default)]
+    pub lint_rules: Vec<String>,
+    /// Languages indexed in Smart mode
+    #[serde(default)]
+    pub languages: Vec<PluginLanguage>,
+    /// Hook events delivered as `logos/hook` calls, e.g. `didSave`
+    #[serde(default)]
+    pub hooks: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginLanguage {
+    pub id: String,
+    pub extensions: Vec<String>,
+}
+
+/// A loaded plugin library
+pub struct Plugin {
+    pub manifest: PluginManifest,
+    pub path: PathBuf,
+    /// File extensions of each of `manifest.languages`, interned
+    extensions: Vec<Vec<&'static str>>,
+    call: CallFn,
+    free: FreeFn,
+    // Keeps `call` and `free` valid; declared last so it is dropped last
+    _library: Library,
+}
+
+impl Plugin {
+    /// Load a plugin library and check its ABI version
+    pub fn load(path: &Path) -> Result<Self, String> {
+        // SAFETY: loading runs the library's initializers; plugins are trusted
+        // code installed by the user, and the exports are checked by name and
+        // ABI version before any other call
+        unsafe {
+            let library = Library::new(path).map_err(|e| e.to_string())?;
+            let abi_version: AbiVersionFn = *library
+                .get::<AbiVersionFn>(b"logos_plugin_abi_version\0")
+                .map_err(|e| e.to_string())?;
+            let version = abi_version();
+            if version != PLUGIN_ABI_VERSION {
+                return Err(format!(
+                    "plugin ABI version {} is not supported (expected {})",
+                    version, PLUGIN_ABI_VERSION
+                ));
+            }
+
+            let manifest: ManifestFn = *library.get::<ManifestFn>(b"logos_plugin_manifest\0").map_err(|e| e.to_string())?;
+            let call: CallFn = *library.get::<CallFn>(b"logos_plugin_call\0").map_err(|e| e.to_string())?;
+            let free: FreeFn = *library.get::<FreeFn>(b"logos_plugin_free\0").map_err(|e| e.to_string())?;
+
+            let manifest = take_string(manifest(), free).ok_or("plugin returned no manifest")?;
+            let manifest: PluginManifest =
+                serde_json::from_str(&manifest).map_err(|e| format!("invalid plugin manifest: {}", e))?;
+
+            let extensions = manifest
+                .languages
+                .iter()
+                .map(|language| language.extensions.iter().map(|e| intern(e)).collect())
+                .collect();
+            Ok(Self { manifest, path: path.to_path_buf(), extensions, call, free, _library: library })
+        }
+    }
+
+    /// Invoke the plugin's `logos_plugin_call` entry point
+    pub fn call(&self, method: &str, params: &Value) -> Result<Value, String> {
+        let method = CString::new(method).map_err(|e| e.to_string())?;
+        let params = CString::new(params.to_string()).map_err(|e| e.to_string())?;
+
+        // SAFETY: both arguments are valid NUL-terminated strings that outlive
+        // the call, and the returned string is released with the plugin's own
+        // allocator through `free`
+        let reply = unsafe { take_string((self.call)(method.as_ptr(), params.as_ptr()), self.free) }
+            .ok_or_else(|| format!("plugin {} returned nothing for {}", self.manifest.name, method.to_string_lossy()))?;
+
+        let mut reply: Value = serde_json::from_str(&reply)
+            .map_err(|e| format!("plugin {} returned invalid JSON: {}", self.manifest.name, e))?;
+        if let Some(error) = reply.get("error") {
+            return Err(error.as_str().map(str::to_string).unwrap_or_else(|| error.to_string()));
+        }
+        Ok(reply.get_mut("result").map(Value::take).unwrap_or(Value::Null))
+    }
+}
+
+/// A `'static` copy of `text`, shared by every plugin declaring it.
+/// Adapters hand out `&str` extensions for the whole process, and plugins
+/// may be loaded again, so each distinct extension is leaked only once.
+fn intern(text: &str) -> &'static str {
+    static INTERNED: OnceLock<Mutex<HashSet<&'static str>>> = OnceLock::new();
+    let mut interned = INTERNED.get_or_init(Default::default).lock().unwrap_or_else(PoisonError::into_inner);
+    match interned.get(text) {
+        Some(existing) => existing,
+        None => {
+            let leaked: &'static str = Box::leak(text.into());
+            interned.insert(leaked);
+            leaked
+        }
+    }
+}
+
+/// Copy a plugin-owned string and hand it back to the plugin
+///
+/// # Safety
+/// `ptr` must be null or a NUL-terminated string that `free` accepts.
+unsafe fn take_string(ptr: *mut c_char, free: FreeFn) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    let text = CStr::from_ptr(ptr).to_string_lossy().into_owned();
+    free(ptr);
+    Some(text)
+}
+
+/// All plugins loaded at startup
+#[derive(Default)]
+pub struct PluginHost {
+    plugins: Vec<Arc<Plugin>>,
+    /// Libraries that failed to load, with the reason
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+impl PluginHost {
+    /// Load every dynamic library directly inside `dir`.
+    /// A missing directory yields an empty host.
+    pub fn load_dir(dir: &Path) -> Self {
+        let mut host = Self::default();
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return host;
+        };
+
+        let mut paths: Vec<PathBuf> = entries
+            .flatten()
+            .map(|e| e.path())
+            .filter(|p| p.extension().is_some_and(|e| e == std::env::consts::DLL_EXTENSION))
+            .collect();
+        paths.sort();
+
+        for path in paths {
+            match Plugin::load(&path) {
+                Ok(plugin) => {
+                    log::info!(
+                        "Loaded plugin {} {} from {}",
+                        plugin.manifest.name,
+                        plugin.manifest.version,
+                        path.display()
+                    );
+                    host.plugins.push(Arc::new(plugin));
+                }
+                Err(e) => {
+                    log::warn!("Failed to load plugin {}: {}", path.display(), e);
+                    host.errors.push((path, e));
+                }
+            }
+        }
+        host
+    }
+
+    pub fn plugins(&self) -> &[Arc<Plugin>] {
+        &self.plugins
+    }
+
+    /// The plugin registered for a custom request method
+    pub fn handler_for(&self, method: &str) -> Option<&Arc<Plugin>> {
+        self.plugins.iter().find(|p| p.manifest.methods.iter().any(|m| m == method))
+    }
+
+    /// Run the lint rules of every plugin on a document. Returns LSP diagnostics.
+    pub fn lint(&self, uri: &str, language_id: &str, text: &str) -> Vec<Value> {
+        let mut diagnostics = Vec::new();
+        for plugin in self.plugins.iter().filter(|p| !p.manifest.lint_rules.is_empty()) {
+            let params = json!({
+                "uri": uri,
+                "languageId": language_id,
+                "text": text,
+                "rules": plugin.manifest.lint_rules,
+            });
+            match plugin.call("logos/lint", &params) {
+                Ok(Value::Array(items)) => {
+                    for mut item in items {
+                        if let Some(object) = item.as_object_mut() {
+                            object.entry("source").or_insert_with(|| json!(plugin.manifest.name));
+                        }
+                        diagnostics.push(item);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("Plugin {} failed to lint {}: {}", plugin.manifest.name, uri, e),
+            }
+        }
+        diagnostics
+    }
+
+    /// Deliver a hook event to the plugins subscribed to it
+    pub fn notify(&self, event: &str, message: &Value) {
+        for plugin in self.plugins.iter().filter(|p| p.manifest.hooks.iter().any(|h| h == event)) {
+            if let Err(e) = plugin.call("logos/hook", message) {
+                log::warn!("Plugin {} failed to handle {} hook: {}", plugin.manifest.name, event, e);
+            }
+        }
+    }
+
+    /// Language adapters contributed by plugins
+    pub fn adapters(&self) -> Vec<PluginAdapter> {
+        self.plugins
+            .iter()
+            .flat_map(|plugin| {
+                plugin.manifest.languages.iter().zip(&plugin.extensions).map(|(language, extensions)| PluginAdapter {
+                    plugin: Arc::clone(plugin),
+                    language: language.id.clone(),
+                    extensions: extensions.clone(),
+                })
+            })
+            .collect()
+    }
+}
+
+/// Smart mode indexing for a plugin language.
+///
+/// `logos/analyze` gets `{uri, languageId, text}` and returns
+/// `{"symbols": [..]}`, a tree of serialized `logos_core::Symbol`s
+/// (`{"name", "kind": "class", "range", "selection_range", "children"}`).
+pub struct PluginAdapter {
+    plugin: Arc<Plugin>,
+    language: String,
+    extensions: Vec<&'static str>,
+}
+
+impl LanguageAdapter for PluginAdapter {
+    fn language_id(&self) -> &str {
+        &self.language
+    }
+
+    fn file_extensions(&self) -> &[&str] {
+        &self.extensions
+    }
+
+    /// What a plugin extracts can change with each of its releases
+    fn version(&self) -> &str {
+        &self.plugin.manifest.version
+    }
+
+    fn analyze(&self, uri: &str, source: &str) -> AnalysisResult {
+        let params = json!({ "uri": uri, "languageId": self.language, "text": source });
+        let symbols: Vec<Symbol> = match self.plugin.call("logos/analyze", &params) {
+            Ok(result) => serde_json::from_value(result.get("symbols").cloned().unwrap_or(result)).unwrap_or_else(|e| {
+                log::warn!("Plugin {} returned invalid symbols: {}", self.plugin.manifest.name, e);
+                Vec::new()
+            }),
+            Err(e) => {
+                log::warn!("Plugin {} failed to analyze {}: {}", self.plugin.manifest.name, uri, e);
+                Vec::new()
+            }
+        };
+
+        let mut result = AnalysisResult::default();
+        flatten_symbols(&symbols, uri, None, "", &mut result.symbols);
+        result
+    }
+}
+
+fn flatten_symbols(symbols: &[Symbol], uri: &str, parent: Option<SymbolId>, prefix: &str, out: &mut Vec<SmartSymbol>) {
+    for symbol in symbols {
+        let qualified_name = if prefix.is_empty() {
+            symbol.name.clone()
+        } else {
+            format!("{}.{}", prefix, symbol.name)
+        };
+        let mut builder = SymbolBuilder::new(
+            &symbol.name,
+            symbol.kind,
+            make_location(uri, symbol.range, symbol.selection_range),
+        )
+        .qualified_name(&qualified_name)
+        .exported(parent.is_none());
+        if let Some(parent) = parent {
+            builder = builder.parent(parent);
+        }
+        if let Some(documentation) = &symbol.documentation {
+            builder = builder.documentation(documentation);
+        }
+        let smart = builder.build();
+        let id = smart.id;
+        out.push(smart);
+        flatten_symbols(&symbol.children, uri, Some(id), &qualified_name, out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logos_core::{Range, SymbolKind};
+
+    #[test]
+    fn test_manifest_defaults_and_camel_case() {
+        let manifest: PluginManifest = serde_json::from_value(json!({
+            "name": "acme",
+            "lintRules": ["acme/no-todo"],
+            "languages": [{ "id": "acme", "extensions": ["acme", "ac"] }]
+        }))
+        .unwrap();
+        assert_eq!(manifest.name, "acme");
+        assert_eq!(manifest.version, "");
+        assert!(manifest.methods.is_empty() && manifest.hooks.is_empty());
+        assert_eq!(manifest.lint_rules, vec!["acme/no-todo"]);
+        assert_eq!(manifest.languages[0].extensions, vec!["acme", "ac"]);
+
+        assert!(serde_json::from_value::<PluginManifest>(json!({ "version": "1" })).is_err());
+    }
+
+    #[test]
+    fn test_flatten_symbols() {
+        let range = Range::from_coords(0, 0, 3, 0);
+        let symbols = vec![Symbol::new("Router".to_string(), SymbolKind::Class, range, range)
+            .with_children(vec![Symbol::new("route".to_string(), SymbolKind::Method, range, range)])];
+
+        let mut out = Vec::new();
+        flatten_symbols(&symbols, "file:///app.acme", None, "", &mut out);
+
+        assert_eq!(out.len(), 2);
+        assert_eq!((out[0].qualified_name.as_str(), out[0].parent, out[0].exported), ("Router", None, true));
+        assert_eq!((out[1].qualified_name.as_str(), out[1].parent, out[1].exported), ("Router.route", Some(out[0].id), false));
+        assert_eq!(out[1].location.uri, "file:///app.acme");
+    }
+
+    #[test]
+    fn test_load_dir_missing_and_invalid_libraries() {
+        let host = PluginHost::load_dir(Path::new("/nonexistent/logos-plugins"));
+        assert!(host.plugins().is_empty() && host.errors.is_empty());
+
+        let dir = tempfile::tempdir().unwrap();
+        let library = dir.path().join(format!("broken.{}", std::env::consts::DLL_EXTENSION));
+        std::fs::write(&library, "not a library").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "ignored").unwrap();
+
+        let host = PluginHost::load_dir(dir.path());
+        assert!(host.plugins().is_empty());
+        assert_eq!(host.errors.len(), 1);
+        assert_eq!(host.errors[0].0, library);
+    }
+
+    #[test]
+    fn test_intern_shares_extensions() {
+        let first = intern("acme");
+        assert!(std::ptr::eq(first, intern(&String::from("acme"))));
+    }
+
+    unsafe extern "C" fn reply(method: *const c_char, _params: *const c_char) -> *mut c_char {
+        let reply = match CStr::from_ptr(method).to_str().unwrap() {
+            "fail" => r#"{"error": "boom"}"#,
+            "structured" => r#"{"error": {"code": 3}}"#,
+            _ => r#"{"result": [1, 2]}"#,
+        };
+        CString::new(reply).unwrap().into_raw()
+    }
+
+    unsafe extern "C" fn free(ptr: *mut c_char) {
+        drop(CString::from_raw(ptr));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_call_results_and_errors() {
+        let plugin = Plugin {
+            manifest: serde_json::from_value(json!({ "name": "fake" })).unwrap(),
+            path: PathBuf::new(),
+            extensions: Vec::new(),
+            call: reply,
+            free,
+            _library: libloading::os::unix::Library::this().into(),
+        };
+        assert_eq!(plugin.call("ok", &json!({})), Ok(json!([1, 2])));
+        assert_eq!(plugin.call("fail", &json!({})), Err("boom".to_string()));
+        assert_eq!(plugin.call("structured", &json!({})), Err(r#"{"code":3}"#.to_string()));
+    }
+}
@@ -0,0 +1,335 @@
+//! External tool invocation
+//!
+//! Everything the daemon shells out to (git, hook commands) runs through
+//! [`ToolCommand`], so a hung or runaway tool can't wedge the server:
+//!
+//! - a timeout, after which the process is killed
+//! - a cap on captured output; a process writing past it is killed
+//! - a scrubbed environment: only the variables tools need to find their
+//!   binaries, configuration and locale are passed on, plus the ones set
+//!   explicitly
+//! - cancellation through a [`CancelToken`], checked while waiting
+//!
+//! Output is read on helper threads and stdin written on another, so a tool
+//! filling one pipe while waiting on the other never deadlocks. Output is
+//! collected until the timeout too: a background process the tool left
+//! holding its pipes doesn't hold up the result.
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Default time a tool gets before it is killed
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+/// Cap on each of stdout and stderr
+const MAX_OUTPUT: usize = 16 * 1024 * 1024;
+/// Interval between checks of a running process
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Variables passed on to tools; everything else is dropped
+const INHERITED_ENV: &[&str] = &[
+    "PATH", "HOME", "USER", "LOGNAME", "SHELL", "LANG", "LC_ALL", "LC_CTYPE", "TZ", "TMPDIR", "TEMP", "TMP",
+    "XDG_CONFIG_HOME", "XDG_CACHE_HOME", "XDG_DATA_HOME", "USERPROFILE", "APPDATA", "LOCALAPPDATA", "SYSTEMROOT",
+    "SYSTEMDRIVE", "COMSPEC", "PATHEXT", "WINDIR",
+];
+
+/// Shared flag that stops the processes it was given to
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// Why a tool produced no output
+#[derive(Debug)]
+pub enum ProcessError {
+    /// The program couldn't be started
+    Spawn(io::Error),
+    /// Waiting on the process failed
+    Wait(io::Error),
+    TimedOut(Duration),
+    /// Stdout or stderr went past the cap, in bytes
+    OutputTooLarge(usize),
+    Cancelled,
+}
+
+impl fmt::Display for ProcessError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Spawn(e) => write!(f, "failed to start: {}", e),
+            Self::Wait(e) => write!(f, "failed to wait: {}", e),
+            Self::TimedOut(timeout) => write!(f, "timed out after {:?}", timeout),
+            Self::OutputTooLarge(cap) => write!(f, "output exceeded {} bytes", cap),
+            Self::Cancelled => write!(f, "cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ProcessError {}
+
+/// Exit status and captured output of a finished tool
+#[derive(Debug)]
+pub struct ProcessOutput {
+    pub status: ExitStatus,
+    pub stdout: Vec<u8>,
+    pub stderr: Vec<u8>,
+}
+
+/// An external command with limits
+#[derive(Debug, Clone)]
+pub struct ToolCommand {
+    program: String,
+    args: Vec<String>,
+    cwd: Option<PathBuf>,
+    env: Vec<(String, String)>,
+    stdin: Option<Vec<u8>>,
+    timeout: Duration,
+    cancel: Option<CancelToken>,
+}
+
+impl ToolCommand {
+    pub fn new(program: impl Into<String>) -> Self {
+        Self {
+            program: program.into(),
+            args: Vec::new(),
+            cwd: None,
+            env: Vec::new(),
+            stdin: None,
+            timeout: DEFAULT_TIMEOUT,
+            cancel: None,
+        }
+    }
+
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args.extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn current_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cwd = Some(dir.into());
+        self
+    }
+
+    /// Set a variable on top of the scrubbed environment
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Bytes written to the tool's stdin, which is closed afterwards
+    pub fn stdin(mut self, input: impl Into<Vec<u8>>) -> Self {
+        self.stdin = Some(input.into());
+        self
+    }
+
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    pub fn cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    /// Run to completion on the calling thread
+    pub fn run(self) -> Result<ProcessOutput, ProcessError> {
+        let mut command = Command::new(&self.program);
+        command
+            .args(&self.args)
+            .env_clear()
+            .envs(INHERITED_ENV.iter().filter_map(|key| Some((*key, std::env::var_os(key)?))))
+            .envs(self.env.iter().map(|(key, value)| (key, value)))
+            .stdin(if self.stdin.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        if let Some(dir) = &self.cwd {
+            command.current_dir(dir);
+        }
+        let mut child = command.spawn().map_err(ProcessError::Spawn)?;
+
+        if let (Some(mut pipe), Some(input)) = (child.stdin.take(), self.stdin) {
+            // A tool that ignores its input closes the pipe early; that's fine
+            thread::spawn(move || {
+                let _ = pipe.write_all(&input);
+            });
+        }
+        let overflowed = Arc::new(AtomicBool::new(false));
+        let stdout = child.stdout.take().map(|pipe| capture(pipe, MAX_OUTPUT, Arc::clone(&overflowed)));
+        let stderr = child.stderr.take().map(|pipe| capture(pipe, MAX_OUTPUT, Arc::clone(&overflowed)));
+
+        let started = Instant::now();
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) => {}
+                Err(e) => return Err(stop(&mut child, ProcessError::Wait(e))),
+            }
+            if overflowed.load(Ordering::SeqCst) {
+                return Err(stop(&mut child, ProcessError::OutputTooLarge(MAX_OUTPUT)));
+            }
+            if self.cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+                return Err(stop(&mut child, ProcessError::Cancelled));
+            }
+            if started.elapsed() >= self.timeout {
+                return Err(stop(&mut child, ProcessError::TimedOut(self.timeout)));
+            }
+            thread::sleep(POLL_INTERVAL);
+        };
+
+        let deadline = started + self.timeout;
+        let (stdout, stderr) = (collect(stdout, deadline), collect(stderr, deadline));
+        // Output that raced the exit past the cap
+        if overflowed.load(Ordering::SeqCst) {
+            return Err(ProcessError::OutputTooLarge(MAX_OUTPUT));
+        }
+        Ok(ProcessOutput { status, stdout, stderr })
+    }
+
+    /// Run on a background thread, handing the result to `done`
+    pub fn spawn<F>(self, done: F)
+    where
+        F: FnOnce(Result<ProcessOutput, ProcessError>) + Send + 'static,
+    {
+        thread::spawn(move || done(self.run()));
+    }
+}
+
+/// Kill `child` and reap it. Output readers aren't joined: a grandchild can
+/// keep the pipes open.
+fn stop(child: &mut Child, error: ProcessError) -> ProcessError {
+    let _ = child.kill();
+    let _ = child.wait();
+    error
+}
+
+/// Read `pipe` to the end on a helper thread, handing on up to `cap` bytes
+/// as they come. Past the cap, `overflowed` is set and reading stops.
+fn capture<R: Read + Send + 'static>(mut pipe: R, cap: usize, overflowed: Arc<AtomicBool>) -> Receiver<Vec<u8>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut read = 0;
+        let mut buffer = [0u8; 8192];
+        loop {
+            match pipe.read(&mut buffer) {
+                Ok(0) | Err(_) => break,
+                Ok(n) if read + n > cap => {
+                    overflowed.store(true, Ordering::SeqCst);
+                    break;
+                }
+                Ok(n) => {
+                    read += n;
+                    if sender.send(buffer[..n].to_vec()).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+    receiver
+}
+
+/// Output of a [`capture`] until its pipe closes, or what came of it by
+/// `deadline` when a grandchild keeps the pipe open
+fn collect(output: Option<Receiver<Vec<u8>>>, deadline: Instant) -> Vec<u8> {
+    let mut collected = Vec::new();
+    if let Some(output) = output {
+        while let Ok(chunk) = output.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            collected.extend_from_slice(&chunk);
+        }
+    }
+    collected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_background_grandchild_does_not_hold_up_output() {
+        let started = Instant::now();
+        let output = ToolCommand::new("sh")
+            .args(["-c", "sleep 30 & echo done"])
+            .timeout(Duration::from_millis(500))
+            .run()
+            .unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"done\n");
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_timeout_kills_the_tool() {
+        let started = Instant::now();
+        let result = ToolCommand::new("sleep").arg("30").timeout(Duration::from_millis(200)).run();
+
+        assert!(matches!(result, Err(ProcessError::TimedOut(timeout)) if timeout == Duration::from_millis(200)));
+        assert!(started.elapsed() < Duration::from_secs(10));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_output_past_the_cap_kills_the_tool() {
+        let result = ToolCommand::new("head").args(["-c", "20000000", "/dev/zero"]).run();
+
+        assert!(matches!(result, Err(ProcessError::OutputTooLarge(MAX_OUTPUT))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_environment_is_scrubbed() {
+        std::env::set_var("LOGOS_PROCESS_TEST_SECRET", "leaked");
+        let output = ToolCommand::new("sh")
+            .args(["-c", "echo \"$LOGOS_PROCESS_TEST_SECRET|$LOGOS_PROCESS_TEST_SET|$PATH\""])
+            .env("LOGOS_PROCESS_TEST_SET", "set")
+            .run()
+            .unwrap();
+
+        let path = std::env::var("PATH").unwrap_or_default();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), format!("|set|{}\n", path));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cancel_token_stops_the_tool() {
+        let token = CancelToken::new();
+        let (sender, receiver) = mpsc::channel();
+        ToolCommand::new("sleep").arg("30").cancel_token(token.clone()).spawn(move |result| {
+            let _ = sender.send(result);
+        });
+
+        token.cancel();
+        assert!(token.is_cancelled());
+        let result = receiver.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert!(matches!(result, Err(ProcessError::Cancelled)));
+    }
+}
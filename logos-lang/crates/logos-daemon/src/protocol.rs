@@ -6,7 +6,6 @@ use serde_json::Value;
 /// JSON-RPC request
 #[derive(Debug, Deserialize)]
 pub struct Request {
-    pub jsonrpc: String,
     pub id: Option<RequestId>,
     pub method: String,
     #[serde(default)]
@@ -14,7 +13,7 @@ pub struct Request {
 }
 
 /// Request ID can be number or string
-#[derive(Debug, Clone, Deserialize, Serialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
 #[serde(untagged)]
 pub enum RequestId {
     Number(i64),
@@ -82,6 +81,14 @@ pub mod error_codes {
     pub const METHOD_NOT_FOUND: i32 = -32601;
     pub const INVALID_PARAMS: i32 = -32602;
     pub const INTERNAL_ERROR: i32 = -32603;
+    /// LSP: a request arrived before `initialize`
+    pub const SERVER_NOT_INITIALIZED: i32 = -32002;
+    /// LSP: the request was valid but could not be served
+    pub const REQUEST_FAILED: i32 = -32803;
+    /// LSP: the client cancelled the request
+    pub const REQUEST_CANCELLED: i32 = -32800;
+    /// LSP: the document changed since the result being continued
+    pub const CONTENT_MODIFIED: i32 = -32801;
 }
 
 /// JSON-RPC notification (no id, no response expected)
@@ -102,8 +109,70 @@ impl Notification {
     }
 }
 
+/// JSON-RPC request from the server to the client
+#[derive(Debug, Serialize)]
+pub struct ServerRequest {
+    pub jsonrpc: &'static str,
+    pub id: RequestId,
+    pub method: String,
+    pub params: Value,
+}
+
+impl ServerRequest {
+    pub fn new(id: RequestId, method: impl Into<String>, params: Value) -> Self {
+        Self {
+            jsonrpc: "2.0",
+            id,
+            method: method.into(),
+            params,
+        }
+    }
+}
+
+/// Response of the client to a [`ServerRequest`]
+#[derive(Debug, Deserialize)]
+pub struct ClientResponse {
+    pub id: RequestId,
+    #[serde(default)]
+    pub error: Option<Value>,
+}
+
+/// A message the server sends on its own rather than in reply to the
+/// message being handled
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Outgoing {
+    Notification(Notification),
+    Request(ServerRequest),
+    /// The late response of a request served in the background
+    Response(Response),
+}
+
+impl From<Notification> for Outgoing {
+    fn from(notification: Notification) -> Self {
+        Self::Notification(notification)
+    }
+}
+
+impl From<ServerRequest> for Outgoing {
+    fn from(request: ServerRequest) -> Self {
+        Self::Request(request)
+    }
+}
+
+impl From<Response> for Outgoing {
+    fn from(response: Response) -> Self {
+        Self::Response(response)
+    }
+}
+
 // LSP-like types for parameters
 
+#[derive(Debug, Deserialize)]
+pub struct CancelParams {
+    pub id: RequestId,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct InitializeParams {
@@ -112,6 +181,34 @@ pub struct InitializeParams {
     pub root_uri: Option<String>,
     #[serde(default)]
     pub capabilities: Value,
+    #[serde(default)]
+    pub initialization_options: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRename {
+    pub old_uri: String,
+    pub new_uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RenameFilesParams {
+    pub files: Vec<FileRename>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidChangeConfigurationParams {
+    #[serde(default)]
+    pub settings: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ArchiveParams {
+    /// `file:` URI of an archive, or `archive:` URI of an entry inside one
+    pub uri: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -124,7 +221,6 @@ pub struct TextDocumentIdentifier {
 #[serde(rename_all = "camelCase")]
 pub struct VersionedTextDocumentIdentifier {
     pub uri: String,
-    pub version: i64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -132,7 +228,6 @@ pub struct VersionedTextDocumentIdentifier {
 pub struct TextDocumentItem {
     pub uri: String,
     pub language_id: String,
-    pub version: i64,
     pub text: String,
 }
 
@@ -171,6 +266,20 @@ pub struct DidChangeTextDocumentParams {
     pub content_changes: Vec<TextDocumentContentChangeEvent>,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WillSaveTextDocumentParams {
+    pub text_document: TextDocumentIdentifier,
+    /// 1 = manual, 2 = after delay, 3 = focus out
+    pub reason: i32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DidSaveTextDocumentParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct DidCloseTextDocumentParams {
@@ -198,6 +307,64 @@ pub struct DocumentSymbolParams {
     pub text_document: TextDocumentIdentifier,
 }
 
+/// A part of a summarized outline, as named by its placeholder
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlineSection {
+    #[serde(default)]
+    pub parent: Option<usize>,
+    pub start: usize,
+    #[serde(default)]
+    pub trivial: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OutlinePageParams {
+    pub text_document: TextDocumentIdentifier,
+    pub section: OutlineSection,
+    /// Version of the document the placeholder came with
+    #[serde(default)]
+    pub version: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeActionParams {
+    pub text_document: TextDocumentIdentifier,
+    pub context: CodeActionContext,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeActionContext {
+    /// Diagnostics as the client received them, echoed back in quick fixes
+    #[serde(default)]
+    pub diagnostics: Vec<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InlayHintParams {
+    pub text_document: TextDocumentIdentifier,
+    pub range: Range,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Color {
+    pub red: f64,
+    pub green: f64,
+    pub blue: f64,
+    pub alpha: f64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColorPresentationParams {
+    pub color: Color,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct WorkspaceSymbolParams {
@@ -213,6 +380,132 @@ pub struct RefactorParams {
     pub range: Range,
 }
 
+/// Filters of logos/getAllTodoItems, all optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TodoFilterParams {
+    pub author: Option<String>,
+    #[serde(default)]
+    pub kinds: Vec<logos_index::TodoKind>,
+    pub min_priority: Option<u8>,
+    /// Glob over the file path, e.g. `src/**/*.rs`
+    pub path: Option<String>,
+}
+
+impl TodoFilterParams {
+    pub fn into_filter(self) -> logos_index::TodoFilter {
+        logos_index::TodoFilter {
+            author: self.author,
+            kinds: self.kinds,
+            min_priority: self.min_priority,
+            path: self.path,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelatedFilesParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResolveStackTraceParams {
+    /// Output of a failed run, with one or more traces in it
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FindLogStatementParams {
+    /// One line of program output
+    pub text: String,
+    /// Most matches to return
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Either a selection to describe or a permalink to resolve
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DescribeSelectionParams {
+    #[serde(default)]
+    pub text_document: Option<TextDocumentIdentifier>,
+    #[serde(default)]
+    pub range: Option<Range>,
+    /// A permalink such as `src/user.ts::User.greet#L10-L14`
+    #[serde(default)]
+    pub permalink: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceReplaceParams {
+    pub query: String,
+    /// May refer to capture groups (`$1`, `${name}`) in regex mode
+    pub replacement: String,
+    #[serde(default)]
+    pub options: ReplaceOptionsParams,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextSearchParams {
+    pub query: String,
+    #[serde(default)]
+    pub options: ReplaceOptionsParams,
+}
+
+/// Options of logos/workspaceReplace and logos/textSearch, all optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ReplaceOptionsParams {
+    pub regex: bool,
+    pub case_sensitive: bool,
+    pub whole_word: bool,
+    /// Globs over the file path; every file when empty
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub max_matches: Option<usize>,
+}
+
+impl ReplaceOptionsParams {
+    pub fn into_options(self) -> logos_index::ReplaceOptions {
+        logos_index::ReplaceOptions {
+            regex: self.regex,
+            case_sensitive: self.case_sensitive,
+            whole_word: self.whole_word,
+            include: self.include,
+            exclude: self.exclude,
+            max_matches: self.max_matches.unwrap_or(logos_index::replace::DEFAULT_MAX_MATCHES),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DumpAstParams {
+    pub text_document: TextDocumentIdentifier,
+    /// `sexp` (default) or `json`
+    #[serde(default)]
+    pub format: Option<String>,
+    /// Keep anonymous nodes such as keywords and punctuation
+    #[serde(default)]
+    pub include_anonymous: bool,
+    /// Dump only the smallest node covering this range
+    #[serde(default)]
+    pub range: Option<Range>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HeatmapParams {
+    pub text_document: TextDocumentIdentifier,
+    /// `complexity`, `churn` or `diagnostics`
+    pub kind: String,
+}
+
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct ExtractVariableParams {
@@ -291,6 +584,17 @@ pub struct CallHierarchyOutgoingCall {
     pub from_ranges: Vec<SerializableRange>,
 }
 
+// Type hierarchy types (LSP 3.17)
+
+/// A type in the type hierarchy, shaped like a call hierarchy item
+pub type TypeHierarchyItem = CallHierarchyItem;
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TypeHierarchyItemParams {
+    pub item: TypeHierarchyItem,
+}
+
 // Mode switching
 
 #[derive(Debug, Deserialize)]
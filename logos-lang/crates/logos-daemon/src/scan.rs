@@ -0,0 +1,157 @@
+//! Startup scan of the workspace
+//!
+//! Open documents are analyzed as they are opened; the scan covers the
+//! rest. After `initialized`, the files under the root whose language is
+//! known by name and that no ignore file or exclude glob leaves out are
+//! listed, then read and analyzed a batch at a time
+//! while no message is waiting, so workspace symbols and TODOs span the
+//! whole project without holding up requests. Progress is reported with
+//! `$/progress` under [`WorkspaceScan::TOKEN`].
+
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use logos_index::{is_skipped_name, IgnoreRules, Vfs};
+use logos_parser::LanguageId;
+use serde_json::{json, Value};
+
+use crate::protocol::Notification;
+
+/// Files analyzed between two checks for incoming messages
+const BATCH_SIZE: usize = 32;
+
+/// Files of the workspace not analyzed yet
+pub struct WorkspaceScan {
+    /// Remaining files, in reverse path order so the next one is last
+    pending: Vec<PathBuf>,
+    total: usize,
+    started: Instant,
+}
+
+impl WorkspaceScan {
+    /// Progress token of the scan
+    pub const TOKEN: &'static str = "logos/workspaceScan";
+
+    /// List the files under `root` with a known language, skipping hidden
+    /// and dependency directories, what the `.gitignore` and `.logosignore`
+    /// files list and what `exclude` matches, as the project indexer does
+    pub fn new(vfs: &dyn Vfs, root: &Path, exclude: &[String]) -> Self {
+        let mut pending = workspace_files(vfs, root, exclude);
+        pending.sort_by(|a, b| b.cmp(a));
+        Self { total: pending.len(), pending, started: Instant::now() }
+    }
+
+    /// The next files to analyze; empty once the scan is complete
+    pub fn next_batch(&mut self) -> Vec<PathBuf> {
+        let start = self.pending.len().saturating_sub(BATCH_SIZE);
+        let mut batch = self.pending.split_off(start);
+        batch.reverse();
+        batch
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    pub fn done(&self) -> usize {
+        self.total - self.pending.len()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn begin(&self) -> Notification {
+        progress(json!({
+            "kind": "begin",
+            "title": "Indexing workspace",
+            "message": format!("0/{} files", self.total),
+            "percentage": 0,
+            "cancellable": false,
+        }))
+    }
+
+    pub fn report(&self) -> Notification {
+        progress(json!({
+            "kind": "report",
+            "message": format!("{}/{} files", self.done(), self.total),
+            "percentage": self.done() * 100 / self.total.max(1),
+        }))
+    }
+
+    pub fn end(&self) -> Notification {
+        progress(json!({
+            "kind": "end",
+            "message": format!("Indexed {} files", self.total),
+        }))
+    }
+}
+
+fn progress(value: Value) -> Notification {
+    Notification::new("$/progress", json!({ "token": WorkspaceScan::TOKEN, "value": value }))
+}
+
+/// The files under `root` with a known language, leaving out what the
+/// startup scan does
+pub fn workspace_files(vfs: &dyn Vfs, root: &Path, exclude: &[String]) -> Vec<PathBuf> {
+    let mut rules = IgnoreRules::default();
+    rules.add_globs(exclude.iter().map(String::as_str));
+    let mut files = Vec::new();
+    collect_files(vfs, root, root, &mut rules, &mut files);
+    files
+}
+
+fn collect_files(vfs: &dyn Vfs, root: &Path, dir: &Path, rules: &mut IgnoreRules, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = vfs.read_dir(dir) else {
+        return;
+    };
+    rules.read_dir(vfs, root, dir);
+    for path in entries {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if is_skipped_name(name) {
+            continue;
+        }
+        let is_dir = vfs.is_dir(&path);
+        let relative = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+        if rules.is_ignored(&relative, is_dir) {
+            continue;
+        }
+        if is_dir {
+            collect_files(vfs, root, &path, rules, files);
+        } else if LanguageId::detect(name, "").is_some() && vfs.is_file(&path) {
+            files.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logos_index::MemoryFs;
+
+    #[test]
+    fn test_workspace_files_follow_ignore_rules() {
+        let vfs = MemoryFs::new();
+        for path in [
+            "/ws/main.py",
+            "/ws/notes.txt",
+            "/ws/gen/out.py",
+            "/ws/vendor/lib.py",
+            "/ws/node_modules/pkg/index.js",
+            "/ws/.hidden/x.py",
+        ] {
+            vfs.insert(path, "");
+        }
+        vfs.insert("/ws/.gitignore", "gen/\n");
+
+        let mut files = workspace_files(&vfs, Path::new("/ws"), &["vendor/**".to_string()]);
+        files.sort();
+        assert_eq!(files, vec![PathBuf::from("/ws/main.py")]);
+    }
+}
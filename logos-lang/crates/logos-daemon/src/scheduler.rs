@@ -0,0 +1,333 @@
+//! Request scheduling with priority lanes
+//!
+//! Messages are read on a separate thread and queued here. Interactive
+//! requests (completion, hover, signature help) jump ahead of queued
+//! background work (workspace-wide references, symbols, stats), so their
+//! latency is bounded by at most one in-flight background job. A starvation
+//! limit guarantees background work still makes progress.
+//!
+//! Document sync and lifecycle messages are barriers: they run after all
+//! work queued before them and before all work queued after them, so a
+//! request is answered against the documents as they were when it was sent
+//! and nothing queued is overtaken by `exit`.
+
+use std::collections::VecDeque;
+
+use serde::Deserialize;
+
+/// Priority lane of a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lane {
+    /// Latency-sensitive: served first
+    Interactive,
+    /// Throughput work: served when no interactive work is waiting
+    Background,
+    /// Changes what later requests see: served in arrival order with
+    /// respect to both lanes
+    Barrier,
+}
+
+/// Default number of consecutive interactive messages before one background
+/// message is let through
+pub const DEFAULT_STARVATION_LIMIT: usize = 32;
+
+/// Pick the lane for a JSON-RPC method
+pub fn lane_for_method(method: &str) -> Lane {
+    match method {
+        // Lifecycle and document sync must not overtake, nor be overtaken
+        // by, the requests around them
+        "initialize" | "initialized" | "shutdown" | "exit" | "textDocument/didOpen" | "textDocument/didChange"
+        | "textDocument/didClose" | "textDocument/willSave" | "textDocument/didSave"
+        | "workspace/didChangeConfiguration" | "workspace/didRenameFiles" => Lane::Barrier,
+
+        // Cancellation must reach the work it cancels before it runs
+        "$/cancelRequest" | "window/workDoneProgress/cancel" => Lane::Interactive,
+
+        // Keystroke-driven requests
+        "textDocument/completion"
+        | "textDocument/hover"
+        | "textDocument/signatureHelp"
+        | "textDocument/definition"
+        | "textDocument/documentHighlight"
+        | "textDocument/prepareRename"
+        | "textDocument/documentSymbol"
+        | "logos/getOutlinePage"
+        | "textDocument/semanticTokens/full"
+        | "textDocument/inlayHint"
+        | "textDocument/foldingRange"
+        | "textDocument/documentColor"
+        | "textDocument/colorPresentation"
+        | "logos/getInactiveRegions"
+        | "textDocument/codeAction" => Lane::Interactive,
+
+        _ => Lane::Background,
+    }
+}
+
+#[derive(Deserialize)]
+struct Envelope {
+    #[serde(default)]
+    method: Option<String>,
+}
+
+/// Pick the lane for a raw message body; unparseable messages go to the
+/// interactive lane so their error response is not delayed
+pub fn lane_for_message(body: &str) -> Lane {
+    match serde_json::from_str::<Envelope>(body) {
+        Ok(Envelope { method: Some(method) }) => lane_for_method(&method),
+        _ => Lane::Interactive,
+    }
+}
+
+/// A queued item with its arrival order
+#[derive(Debug)]
+struct Queued<T> {
+    seq: u64,
+    barrier: bool,
+    item: T,
+}
+
+/// Two-lane FIFO queue. Barriers wait in the interactive lane, so the
+/// interactive work after them waits too.
+#[derive(Debug)]
+pub struct Scheduler<T> {
+    interactive: VecDeque<Queued<T>>,
+    background: VecDeque<Queued<T>>,
+    starvation_limit: usize,
+    interactive_streak: usize,
+    next_seq: u64,
+}
+
+impl<T> Scheduler<T> {
+    pub fn new() -> Self {
+        Self::with_starvation_limit(DEFAULT_STARVATION_LIMIT)
+    }
+
+    pub fn with_starvation_limit(limit: usize) -> Self {
+        Self {
+            interactive: VecDeque::new(),
+            background: VecDeque::new(),
+            starvation_limit: limit.max(1),
+            interactive_streak: 0,
+            next_seq: 0,
+        }
+    }
+
+    pub fn push(&mut self, lane: Lane, item: T) {
+        let queued = Queued { seq: self.next_seq, barrier: lane == Lane::Barrier, item };
+        self.next_seq += 1;
+        match lane {
+            Lane::Interactive | Lane::Barrier => self.interactive.push_back(queued),
+            Lane::Background => self.background.push_back(queued),
+        }
+    }
+
+    /// Next item to run, preferring the interactive lane up to the next
+    /// barrier
+    pub fn pop(&mut self) -> Option<T> {
+        // Background work queued after a barrier waits for it
+        let barrier = self.interactive.iter().find(|queued| queued.barrier).map(|queued| queued.seq);
+        let background_ready = self.background.front().is_some_and(|next| barrier.is_none_or(|seq| next.seq < seq));
+        let background_first = match self.interactive.front() {
+            // Background work queued before a barrier runs before it
+            Some(next) if next.barrier => background_ready,
+            Some(_) => background_ready && self.interactive_streak >= self.starvation_limit,
+            None => background_ready,
+        };
+        let queued = if background_first {
+            self.interactive_streak = 0;
+            self.background.pop_front()
+        } else {
+            self.interactive_streak += 1;
+            self.interactive.pop_front()
+        };
+        queued.map(|queued| queued.item)
+    }
+
+    pub fn len(&self) -> usize {
+        self.interactive.len() + self.background.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.interactive.is_empty() && self.background.is_empty()
+    }
+}
+
+impl<T> Default for Scheduler<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A simulated job: lane, arrival time and cost in abstract time units
+    #[derive(Debug, Clone, Copy)]
+    struct Job {
+        id: usize,
+        lane: Lane,
+        arrival: u64,
+        cost: u64,
+    }
+
+    /// Run jobs through a single worker; returns (job, finish time) in run order
+    fn simulate(mut jobs: Vec<Job>, limit: usize) -> Vec<(Job, u64)> {
+        jobs.sort_by_key(|j| j.arrival);
+        let mut pending = jobs.into_iter().peekable();
+        let mut scheduler = Scheduler::with_starvation_limit(limit);
+        let mut now = 0;
+        let mut finished = Vec::new();
+
+        loop {
+            while let Some(job) = pending.next_if(|j| j.arrival <= now) {
+                scheduler.push(job.lane, job);
+            }
+            match scheduler.pop() {
+                Some(job) => {
+                    now += job.cost;
+                    finished.push((job, now));
+                }
+                None => match pending.peek() {
+                    Some(next) => now = next.arrival,
+                    None => break,
+                },
+            }
+        }
+        finished
+    }
+
+    #[test]
+    fn test_lane_classification() {
+        assert_eq!(lane_for_method("textDocument/completion"), Lane::Interactive);
+        assert_eq!(lane_for_method("textDocument/hover"), Lane::Interactive);
+        assert_eq!(lane_for_method("textDocument/signatureHelp"), Lane::Interactive);
+        assert_eq!(lane_for_method("textDocument/didChange"), Lane::Barrier);
+        assert_eq!(lane_for_method("exit"), Lane::Barrier);
+        assert_eq!(lane_for_method("$/cancelRequest"), Lane::Interactive);
+        assert_eq!(lane_for_method("logos/getInactiveRegions"), Lane::Interactive);
+        assert_eq!(lane_for_method("textDocument/inlayHint"), Lane::Interactive);
+        assert_eq!(lane_for_method("textDocument/foldingRange"), Lane::Interactive);
+        assert_eq!(lane_for_method("textDocument/references"), Lane::Background);
+        assert_eq!(lane_for_method("workspace/symbol"), Lane::Background);
+        assert_eq!(lane_for_method("logos/getWorkspaceStats"), Lane::Background);
+        assert_eq!(
+            lane_for_message(r#"{"jsonrpc":"2.0","id":1,"method":"textDocument/hover"}"#),
+            Lane::Interactive
+        );
+        assert_eq!(lane_for_message("not json"), Lane::Interactive);
+    }
+
+    #[test]
+    fn test_interactive_preempts_queued_background() {
+        let mut scheduler = Scheduler::new();
+        scheduler.push(Lane::Background, "refs-1");
+        scheduler.push(Lane::Background, "refs-2");
+        scheduler.push(Lane::Interactive, "hover");
+        scheduler.push(Lane::Interactive, "completion");
+
+        assert_eq!(scheduler.pop(), Some("hover"));
+        assert_eq!(scheduler.pop(), Some("completion"));
+        assert_eq!(scheduler.pop(), Some("refs-1"));
+        assert_eq!(scheduler.pop(), Some("refs-2"));
+        assert_eq!(scheduler.pop(), None);
+    }
+
+    #[test]
+    fn test_barriers_keep_arrival_order() {
+        let mut scheduler = Scheduler::new();
+        scheduler.push(Lane::Background, "refs-1");
+        scheduler.push(Lane::Interactive, "hover-1");
+        scheduler.push(Lane::Barrier, "didChange");
+        scheduler.push(Lane::Background, "refs-2");
+        scheduler.push(Lane::Interactive, "hover-2");
+        scheduler.push(Lane::Barrier, "exit");
+
+        // Interactive work still goes first between barriers
+        let order: Vec<_> = std::iter::from_fn(|| scheduler.pop()).collect();
+        assert_eq!(order, vec!["hover-1", "refs-1", "didChange", "hover-2", "refs-2", "exit"]);
+    }
+
+    #[test]
+    fn test_starvation_does_not_cross_a_barrier() {
+        let mut scheduler = Scheduler::with_starvation_limit(1);
+        scheduler.push(Lane::Interactive, "hover-1");
+        scheduler.push(Lane::Interactive, "hover-2");
+        scheduler.push(Lane::Barrier, "didChange");
+        scheduler.push(Lane::Background, "refs");
+
+        let order: Vec<_> = std::iter::from_fn(|| scheduler.pop()).collect();
+        assert_eq!(order, vec!["hover-1", "hover-2", "didChange", "refs"]);
+    }
+
+    #[test]
+    fn test_lanes_are_fifo() {
+        let mut scheduler = Scheduler::new();
+        for i in 0..5 {
+            scheduler.push(Lane::Interactive, i);
+        }
+        let order: Vec<_> = std::iter::from_fn(|| scheduler.pop()).collect();
+        assert_eq!(order, vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_background_not_starved() {
+        let mut scheduler = Scheduler::with_starvation_limit(3);
+        scheduler.push(Lane::Background, "bg");
+        for _ in 0..10 {
+            scheduler.push(Lane::Interactive, "ui");
+        }
+        let order: Vec<_> = std::iter::from_fn(|| scheduler.pop()).collect();
+        assert_eq!(order.iter().position(|s| *s == "bg"), Some(3));
+        assert_eq!(order.len(), 11);
+    }
+
+    #[test]
+    fn test_bounded_interactive_latency_under_heavy_indexing() {
+        const BG_COST: u64 = 50;
+        const UI_COST: u64 = 2;
+
+        // 200 expensive background jobs queued up front, interactive requests
+        // arriving every 37 time units throughout
+        let mut jobs: Vec<Job> = (0..200)
+            .map(|id| Job { id, lane: Lane::Background, arrival: 0, cost: BG_COST })
+            .collect();
+        jobs.extend((0..100).map(|i| Job {
+            id: 1000 + i,
+            lane: Lane::Interactive,
+            arrival: 1 + i as u64 * 37,
+            cost: UI_COST,
+        }));
+
+        let finished = simulate(jobs, DEFAULT_STARVATION_LIMIT);
+        assert_eq!(finished.len(), 300);
+
+        // Each interactive job waits for at most the background job in flight
+        // plus interactive jobs that arrived before it
+        let worst = finished
+            .iter()
+            .filter(|(j, _)| j.lane == Lane::Interactive)
+            .map(|(j, done)| done - j.arrival)
+            .max()
+            .unwrap();
+        assert!(worst <= BG_COST + 2 * UI_COST, "interactive latency {} exceeded bound", worst);
+
+        // Background work still completes, in submission order
+        let bg: Vec<_> = finished.iter().filter(|(j, _)| j.lane == Lane::Background).map(|(j, _)| j.id).collect();
+        assert_eq!(bg, (0..200).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_fifo_baseline_latency_is_unbounded() {
+        // Same workload with everything in one lane shows why lanes matter
+        let mut jobs: Vec<Job> = (0..200)
+            .map(|id| Job { id, lane: Lane::Background, arrival: 0, cost: 50 })
+            .collect();
+        jobs.push(Job { id: 1000, lane: Lane::Background, arrival: 1, cost: 2 });
+
+        let finished = simulate(jobs, DEFAULT_STARVATION_LIMIT);
+        let (job, done) = finished.iter().find(|(j, _)| j.id == 1000).unwrap();
+        assert!(done - job.arrival > 50 * 100);
+    }
+}
@@ -1,16 +1,46 @@
 //! JSON-RPC server implementation
 
 use log::{info, warn, debug};
+use logos_core::uri::UriMap;
+use serde::Serialize;
+use serde_json::Value;
 
-use crate::protocol::{Request, Response, error_codes};
+use crate::protocol::{CancelParams, ClientResponse, Request, Response, error_codes};
 use crate::state::State;
 use crate::handlers;
 
+/// Progress of the client through the initialization handshake
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Handshake {
+    /// Nothing but `initialize` (and `exit`) is served yet
+    AwaitingInitialize,
+    /// `initialize` was answered; notifications wait for `initialized`
+    AwaitingInitialized,
+    Ready,
+}
+
+/// What to do with a message at the current step of the handshake
+enum Gate {
+    Dispatch,
+    /// Keep the notification until `initialized`
+    Hold,
+    /// Ignore the notification
+    Drop,
+    /// Answer with this error instead of serving the request
+    Reject(Response),
+}
+
 /// Language service server
 pub struct Server {
     state: State,
     should_exit: bool,
     shutdown_requested: bool,
+    handshake: Handshake,
+    /// Notifications received between `initialize` and `initialized`,
+    /// replayed in order once the latter arrives
+    held: Vec<Request>,
+    /// Canonical keys of the URIs the client sends
+    uris: UriMap,
 }
 
 impl Server {
@@ -19,41 +49,167 @@ impl Server {
             state: State::new(),
             should_exit: false,
             shutdown_requested: false,
+            handshake: Handshake::AwaitingInitialize,
+            held: Vec::new(),
+            uris: UriMap::new(),
         }
     }
 
     /// Handle an incoming JSON-RPC message
     pub fn handle_message(&mut self, message: &str) -> Option<String> {
         // Parse the message
-        let request: Request = match serde_json::from_str(message) {
-            Ok(req) => req,
-            Err(e) => {
-                warn!("Failed to parse request: {}", e);
-                let response = Response::error(
-                    None,
-                    error_codes::PARSE_ERROR,
-                    format!("Parse error: {}", e),
-                );
-                return Some(serde_json::to_string(&response).unwrap());
+        let value: Value = match serde_json::from_str(message) {
+            Ok(value) => value,
+            Err(e) => return Some(parse_error(e)),
+        };
+
+        // The client answering a request of the server
+        if value.get("method").is_none() && value.get("id").is_some() {
+            match serde_json::from_value::<ClientResponse>(value) {
+                Ok(response) => self.state.handle_client_response(response),
+                Err(e) => warn!("Failed to parse response: {}", e),
             }
+            return None;
+        }
+
+        let mut request: Request = match serde_json::from_value(value) {
+            Ok(req) => req,
+            Err(e) => return Some(parse_error(e)),
         };
 
         debug!("Handling method: {}", request.method);
+        normalize_uris(&mut request.params, &mut self.uris);
+
+        match self.check_handshake(&request) {
+            Gate::Dispatch => {}
+            Gate::Hold => {
+                debug!("Holding {} until the client is initialized", request.method);
+                self.held.push(request);
+                return None;
+            }
+            Gate::Drop => {
+                warn!("Ignoring {} out of handshake order", request.method);
+                return None;
+            }
+            Gate::Reject(response) => return Some(serde_json::to_string(&response).unwrap()),
+        }
 
         // Dispatch to handler
         let response = self.dispatch(&request);
+        match request.method.as_str() {
+            // A failed `initialize` may be retried
+            "initialize" if response.as_ref().is_some_and(|r| r.error.is_some()) => {
+                self.handshake = Handshake::AwaitingInitialize
+            }
+            "initialized" => self.replay_held(),
+            _ => {}
+        }
 
         // If this was a notification (no id), don't send a response
         request.id.as_ref()?;
 
-        Some(serde_json::to_string(&response).unwrap())
+        // No response yet for a request served in the background
+        Some(self.encode(&response?))
     }
 
-    /// Dispatch a request to the appropriate handler
-    fn dispatch(&mut self, request: &Request) -> Response {
+    /// Serialize a message to the client, naming files as the client does
+    fn encode(&self, message: &impl Serialize) -> String {
+        if self.uris.is_identity() {
+            return serde_json::to_string(message).unwrap();
+        }
+        let mut value = serde_json::to_value(message).unwrap();
+        restore_uris(&mut value, &self.uris);
+        value.to_string()
+    }
+
+    /// Drain messages the server sends on its own: notifications (e.g.
+    /// window/showMessage), requests to the client and the responses of
+    /// requests served in the background
+    pub fn take_outgoing(&mut self) -> Vec<String> {
+        self.state.take_outgoing().iter().map(|message| self.encode(message)).collect()
+    }
+
+    /// Enforce the order of the handshake: requests before `initialize`
+    /// fail with `ServerNotInitialized` and notifications before it other
+    /// than `exit` are dropped, as LSP asks; a second `initialize` is
+    /// refused, and notifications between `initialize` and `initialized`
+    /// wait for the latter. Advances the handshake when `request` completes
+    /// a step of it.
+    fn check_handshake(&mut self, request: &Request) -> Gate {
+        let is_request = request.id.is_some();
+        match (request.method.as_str(), self.handshake) {
+            ("exit", _) => Gate::Dispatch,
+            ("initialize", Handshake::AwaitingInitialize) => {
+                self.handshake = Handshake::AwaitingInitialized;
+                Gate::Dispatch
+            }
+            ("initialize", _) => Gate::Reject(Response::error(
+                request.id.clone(),
+                error_codes::INVALID_REQUEST,
+                "Server is already initialized".to_string(),
+            )),
+            ("initialized", Handshake::AwaitingInitialized) => {
+                self.handshake = Handshake::Ready;
+                Gate::Dispatch
+            }
+            ("initialized", _) => Gate::Drop,
+            (_, Handshake::Ready) => Gate::Dispatch,
+            (method, Handshake::AwaitingInitialize) if is_request => Gate::Reject(Response::error(
+                request.id.clone(),
+                error_codes::SERVER_NOT_INITIALIZED,
+                format!("Server not initialized: {} received before initialize", method),
+            )),
+            (_, Handshake::AwaitingInitialize) => Gate::Drop,
+            (_, Handshake::AwaitingInitialized) if is_request => Gate::Dispatch,
+            (_, Handshake::AwaitingInitialized) => Gate::Hold,
+        }
+    }
+
+    /// Dispatch the notifications held back during the handshake
+    fn replay_held(&mut self) {
+        for request in std::mem::take(&mut self.held) {
+            debug!("Replaying held {}", request.method);
+            self.dispatch(&request);
+        }
+    }
+
+    /// Whether work is waiting for the server to be idle
+    pub fn has_background_work(&self) -> bool {
+        self.state.has_background_work()
+    }
+
+    /// Take one step of background work, such as a batch of the workspace
+    /// scan. Call it only while no message is waiting.
+    pub fn run_background_work(&mut self) {
+        self.state.run_background_work();
+    }
+
+    /// Refuse document requests for languages running degraded, instead of
+    /// answering with silently empty results
+    fn degraded_language_error(&self, request: &Request) -> Option<Response> {
+        request.id.as_ref()?;
+        if !request.method.starts_with("textDocument/") {
+            return None;
+        }
+        let uri = request.params.get("textDocument")?.get("uri")?.as_str()?;
+        let failure = self.state.language_failure_for(uri)?;
+        Some(Response::error(
+            request.id.clone(),
+            error_codes::REQUEST_FAILED,
+            format!("{} language service unavailable: {}", failure.language, failure.reason),
+        ))
+    }
+
+    /// Dispatch a request to the appropriate handler. Returns `None` when
+    /// the request is served in the background and answered later.
+    fn dispatch(&mut self, request: &Request) -> Option<Response> {
+        if let Some(response) = self.degraded_language_error(request) {
+            return Some(response);
+        }
+
         let id = request.id.clone();
 
-        match request.method.as_str() {
+        let response = match request.method.as_str() {
             // Lifecycle
             "initialize" => {
                 handlers::lifecycle::initialize(&mut self.state, &request.params, id)
@@ -64,6 +220,8 @@ impl Server {
             }
             "shutdown" => {
                 self.shutdown_requested = true;
+                self.state.tools_cancel.cancel();
+                self.state.save_index();
                 info!("Shutdown requested");
                 Response::null_result(id)
             }
@@ -72,6 +230,30 @@ impl Server {
                 info!("Exit requested");
                 Response::null_result(id)
             }
+            "$/cancelRequest" => {
+                match serde_json::from_value::<CancelParams>(request.params.clone()) {
+                    Ok(params) if self.state.cancel_job(&params.id) => info!("Cancelled request {:?}", params.id),
+                    Ok(params) => debug!("Nothing to cancel for request {:?}", params.id),
+                    Err(e) => warn!("Invalid cancel params: {}", e),
+                }
+                Response::null_result(id)
+            }
+            "window/workDoneProgress/cancel" => {
+                if let Some(token) = request.params.get("token") {
+                    self.state.cancel_job_with_token(token);
+                }
+                Response::null_result(id)
+            }
+
+            // Workspace
+            "workspace/didChangeConfiguration" => {
+                handlers::workspace::did_change_configuration(&mut self.state, &request.params);
+                Response::null_result(id)
+            }
+            "workspace/didRenameFiles" => {
+                handlers::workspace::did_rename_files(&mut self.state, &request.params);
+                Response::null_result(id)
+            }
 
             // Document synchronization
             "textDocument/didOpen" => {
@@ -86,6 +268,14 @@ impl Server {
                 handlers::document::did_close(&mut self.state, &request.params);
                 Response::null_result(id)
             }
+            "textDocument/willSave" => {
+                handlers::document::will_save(&self.state, &request.params);
+                Response::null_result(id)
+            }
+            "textDocument/didSave" => {
+                handlers::document::did_save(&mut self.state, &request.params);
+                Response::null_result(id)
+            }
 
             // Language features
             "textDocument/completion" => {
@@ -103,11 +293,14 @@ impl Server {
             "textDocument/documentSymbol" => {
                 handlers::symbols::document_symbols(&self.state, &request.params, id)
             }
+            "logos/getOutlinePage" => {
+                handlers::symbols::outline_page(&self.state, &request.params, id)
+            }
             "workspace/symbol" => {
                 handlers::symbols::workspace_symbols(&self.state, &request.params, id)
             }
             "textDocument/rename" => {
-                handlers::rename::handle(&self.state, &request.params, id)
+                return handlers::rename::handle(&mut self.state, &request.params, id);
             }
             "textDocument/prepareRename" => {
                 handlers::rename::prepare(&self.state, &request.params, id)
@@ -117,6 +310,27 @@ impl Server {
             "textDocument/diagnostic" => {
                 handlers::diagnostics::handle(&self.state, &request.params, id)
             }
+            "textDocument/semanticTokens/full" => {
+                handlers::semantic_tokens::handle_full(&self.state, &request.params, id)
+            }
+            "textDocument/inlayHint" => {
+                handlers::inlay_hints::handle(&self.state, &request.params, id)
+            }
+            "textDocument/foldingRange" => {
+                handlers::folding::handle(&self.state, &request.params, id)
+            }
+            "textDocument/documentColor" => {
+                handlers::color::document_colors(&self.state, &request.params, id)
+            }
+            "textDocument/colorPresentation" => {
+                handlers::color::color_presentations(&request.params, id)
+            }
+            "textDocument/codeLens" => {
+                handlers::coverage::code_lens(&self.state, &request.params, id)
+            }
+            "textDocument/codeAction" => {
+                handlers::code_action::handle(&self.state, &request.params, id)
+            }
 
             // Refactoring
             "logos/getRefactorActions" => {
@@ -132,7 +346,10 @@ impl Server {
                 handlers::refactor::can_safe_delete(&self.state, &request.params, id)
             }
             "logos/safeDelete" => {
-                handlers::refactor::safe_delete(&self.state, &request.params, id)
+                return handlers::refactor::safe_delete(&mut self.state, &request.params, id);
+            }
+            "logos/generateTestSkeleton" => {
+                handlers::refactor::generate_test_skeleton(&self.state, &request.params, id)
             }
 
             // Analysis
@@ -140,7 +357,10 @@ impl Server {
                 handlers::analysis::get_todo_items(&self.state, &request.params, id)
             }
             "logos/getAllTodoItems" => {
-                handlers::analysis::get_all_todo_items(&self.state, id)
+                handlers::analysis::get_all_todo_items(&self.state, &request.params, id)
+            }
+            "logos/createDiagnosticsBaseline" => {
+                handlers::diagnostics::create_baseline(&mut self.state, id)
             }
             "logos/getTodoStats" => {
                 handlers::analysis::get_todo_stats(&self.state, id)
@@ -148,6 +368,42 @@ impl Server {
             "logos/getUnusedSymbols" => {
                 handlers::analysis::get_unused_symbols(&self.state, &request.params, id)
             }
+            "logos/getInactiveRegions" => {
+                handlers::analysis::get_inactive_regions(&self.state, &request.params, id)
+            }
+            "logos/dumpAst" => {
+                handlers::analysis::dump_ast(&self.state, &request.params, id)
+            }
+            "logos/getBracketPairs" => {
+                handlers::analysis::get_bracket_pairs(&self.state, &request.params, id)
+            }
+            "logos/getCoverage" => {
+                handlers::coverage::get_coverage(&self.state, &request.params, id)
+            }
+            "logos/reloadCoverage" => {
+                handlers::coverage::reload_coverage(&mut self.state, id)
+            }
+            "logos/getHeatmap" => {
+                handlers::heatmap::get_heatmap(&self.state, &request.params, id)
+            }
+            "logos/getRelatedFiles" => {
+                handlers::related::get_related_files(&self.state, &request.params, id)
+            }
+            "logos/describeSelection" => {
+                handlers::permalink::describe_selection(&self.state, &request.params, id)
+            }
+            "logos/resolveStackTrace" => {
+                handlers::stack_trace::resolve_stack_trace(&self.state, &request.params, id)
+            }
+            "logos/findLogStatement" => {
+                handlers::stack_trace::find_log_statement(&self.state, &request.params, id)
+            }
+            "logos/workspaceReplace" => {
+                handlers::replace::workspace_replace(&self.state, &request.params, id)
+            }
+            "logos/textSearch" => {
+                handlers::replace::text_search(&self.state, &request.params, id)
+            }
 
             // Call Hierarchy (Smart mode)
             "textDocument/prepareCallHierarchy" => {
@@ -160,6 +416,20 @@ impl Server {
                 handlers::call_hierarchy::handle_outgoing_calls(&self.state, &request.params, id)
             }
 
+            // Type Hierarchy and implementations (Smart mode)
+            "textDocument/prepareTypeHierarchy" => {
+                handlers::type_hierarchy::handle_prepare(&self.state, &request.params, id)
+            }
+            "typeHierarchy/supertypes" => {
+                handlers::type_hierarchy::handle_supertypes(&self.state, &request.params, id)
+            }
+            "typeHierarchy/subtypes" => {
+                handlers::type_hierarchy::handle_subtypes(&self.state, &request.params, id)
+            }
+            "textDocument/implementation" => {
+                handlers::type_hierarchy::handle_implementation(&self.state, &request.params, id)
+            }
+
             // Mode switching
             "logos/setMode" => {
                 handlers::mode::handle_set_mode(&mut self.state, &request.params, id)
@@ -171,6 +441,30 @@ impl Server {
                 handlers::mode::handle_get_index_stats(&self.state, &request.params, id)
             }
 
+            // Workspace statistics
+            "logos/getWorkspaceStats" => {
+                handlers::stats::handle_get_workspace_stats(&self.state, &request.params, id)
+            }
+            "logos/getServerStats" => {
+                handlers::stats::handle_get_server_stats(&self.state, &request.params, id)
+            }
+            "logos/indexStats" => {
+                handlers::stats::handle_index_stats(&self.state, &request.params, id)
+            }
+
+            // Dependency archives
+            "logos/indexArchive" => {
+                handlers::archive::index_archive(&self.state, &request.params, id)
+            }
+            "logos/archiveContent" => {
+                handlers::archive::archive_content(&self.state, &request.params, id)
+            }
+
+            // Methods contributed by plugins
+            method if self.state.plugins.handler_for(method).is_some() => {
+                handlers::plugins::handle(&self.state, method, &request.params, id)
+            }
+
             // Unknown method
             _ => {
                 warn!("Method not found: {}", request.method);
@@ -180,7 +474,8 @@ impl Server {
                     format!("Method not found: {}", request.method),
                 )
             }
-        }
+        };
+        Some(response)
     }
 
     /// Check if the server should exit
@@ -194,3 +489,409 @@ impl Default for Server {
         Self::new()
     }
 }
+
+fn parse_error(e: serde_json::Error) -> String {
+    warn!("Failed to parse request: {}", e);
+    let response = Response::error(None, error_codes::PARSE_ERROR, format!("Parse error: {}", e));
+    serde_json::to_string(&response).unwrap()
+}
+
+/// Rewrite every URI in request params to its canonical key, so one file
+/// never ends up under several keys in the state and indexes
+fn normalize_uris(value: &mut Value, uris: &mut UriMap) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match value {
+                    Value::String(uri) if matches!(key.as_str(), "uri" | "oldUri" | "newUri" | "rootUri") => {
+                        *uri = uris.key(uri);
+                    }
+                    _ => normalize_uris(value, uris),
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| normalize_uris(item, uris)),
+        _ => {}
+    }
+}
+
+/// Spell the URIs of an outgoing message as the client does, in values and
+/// in the keys of maps such as the changes of a workspace edit
+fn restore_uris(value: &mut Value, uris: &UriMap) {
+    match value {
+        Value::String(uri) => {
+            if let Some(spelling) = uris.spelling(uri) {
+                *uri = spelling.to_string();
+            }
+        }
+        Value::Object(map) => {
+            if map.keys().any(|key| uris.spelling(key).is_some()) {
+                *map = std::mem::take(map)
+                    .into_iter()
+                    .map(|(key, value)| (uris.spelling(&key).map_or(key, str::to_string), value))
+                    .collect();
+            }
+            map.values_mut().for_each(|value| restore_uris(value, uris));
+        }
+        Value::Array(items) => items.iter_mut().for_each(|item| restore_uris(item, uris)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn request(server: &mut Server, id: i64, method: &str, params: Value) -> Value {
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        let reply = server.handle_message(&message.to_string()).expect("a response");
+        serde_json::from_str(&reply).unwrap()
+    }
+
+    fn notify(server: &mut Server, method: &str, params: Value) {
+        let message = json!({ "jsonrpc": "2.0", "method": method, "params": params });
+        assert_eq!(server.handle_message(&message.to_string()), None);
+    }
+
+    fn error_code(reply: &Value) -> Option<i64> {
+        reply["error"]["code"].as_i64()
+    }
+
+    fn ready_server() -> Server {
+        let mut server = Server::new();
+        request(&mut server, 0, "initialize", json!({}));
+        notify(&mut server, "initialized", json!({}));
+        for (uri, text) in [("file:///a.py", "def target():\n    pass\n"), ("file:///b.py", "def target():\n    pass\n")] {
+            notify(
+                &mut server,
+                "textDocument/didOpen",
+                json!({ "textDocument": { "uri": uri, "languageId": "python", "version": 1, "text": text } }),
+            );
+        }
+        server.take_outgoing();
+        server
+    }
+
+    fn send(server: &mut Server, id: i64, method: &str, params: Value) -> Option<String> {
+        let message = json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params });
+        server.handle_message(&message.to_string())
+    }
+
+    fn outgoing(server: &mut Server) -> Vec<Value> {
+        server.take_outgoing().iter().map(|m| serde_json::from_str(m).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_rename_is_answered_from_the_background() {
+        let mut server = ready_server();
+        let params = json!({
+            "textDocument": { "uri": "file:///a.py" },
+            "position": { "line": 0, "character": 5 },
+            "newName": "renamed",
+        });
+        assert_eq!(send(&mut server, 7, "textDocument/rename", params), None);
+        assert!(server.has_background_work());
+
+        while server.has_background_work() {
+            server.run_background_work();
+        }
+        let messages = outgoing(&mut server);
+        let response = messages.iter().find(|m| m["id"] == 7).expect("the rename response");
+        let changes = response["result"]["changes"].as_object().unwrap();
+        assert_eq!(changes.keys().collect::<Vec<_>>(), vec!["file:///a.py", "file:///b.py"]);
+        assert_eq!(changes["file:///b.py"][0]["newText"], "renamed");
+    }
+
+    #[test]
+    fn test_rename_fails_when_a_document_changes_meanwhile() {
+        let mut server = ready_server();
+        let params = json!({
+            "textDocument": { "uri": "file:///a.py" },
+            "position": { "line": 0, "character": 5 },
+            "newName": "renamed",
+        });
+        assert_eq!(send(&mut server, 7, "textDocument/rename", params), None);
+        notify(
+            &mut server,
+            "textDocument/didChange",
+            json!({
+                "textDocument": { "uri": "file:///b.py", "version": 2 },
+                "contentChanges": [{ "text": "\n\ndef target():\n    pass\n" }],
+            }),
+        );
+
+        while server.has_background_work() {
+            server.run_background_work();
+        }
+        let messages = outgoing(&mut server);
+        let response = messages.iter().find(|m| m["id"] == 7).expect("the rename response");
+        assert_eq!(error_code(response), Some(error_codes::CONTENT_MODIFIED as i64));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_answers_use_the_client_spelling_of_uris() {
+        use logos_core::uri::{canonicalize_uri, path_to_uri};
+
+        let root = std::env::temp_dir().join(format!("logos-server-uris-{}", std::process::id()));
+        let real = root.join("real");
+        std::fs::create_dir_all(&real).unwrap();
+        std::fs::write(real.join("a.py"), "def target():\n    pass\n").unwrap();
+        let link = root.join("link");
+        let _ = std::fs::remove_file(&link);
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+        let spelled = path_to_uri(&link.join("a.py"));
+        let key = canonicalize_uri(&spelled);
+        assert_ne!(spelled, key);
+
+        let mut server = ready_server();
+        notify(
+            &mut server,
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": spelled, "languageId": "python", "version": 1, "text": "def target():\n    pass\n" } }),
+        );
+        assert!(server.state.get_document(&key).is_some());
+
+        let reply = request(
+            &mut server,
+            1,
+            "textDocument/references",
+            json!({
+                "textDocument": { "uri": spelled },
+                "position": { "line": 0, "character": 5 },
+                "context": { "includeDeclaration": true },
+            }),
+        );
+        let uris: Vec<&str> = reply["result"].as_array().unwrap().iter().filter_map(|l| l["uri"].as_str()).collect();
+        assert!(uris.contains(&spelled.as_str()));
+        assert!(!uris.contains(&key.as_str()));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_rename_inside_a_body_renames_nothing() {
+        let mut server = ready_server();
+        let position = json!({ "textDocument": { "uri": "file:///a.py" }, "position": { "line": 1, "character": 5 } });
+        let reply = request(&mut server, 1, "textDocument/prepareRename", position.clone());
+        assert_eq!(reply["result"], json!(null));
+
+        let mut params = position;
+        params["newName"] = json!("renamed");
+        let reply = request(&mut server, 2, "textDocument/rename", params);
+        assert_eq!(reply["result"], json!(null));
+    }
+
+    #[test]
+    fn test_rename_leaves_generated_files_alone() {
+        let mut server = ready_server();
+        notify(
+            &mut server,
+            "textDocument/didOpen",
+            json!({ "textDocument": {
+                "uri": "file:///target_pb2.py",
+                "languageId": "python",
+                "version": 1,
+                "text": "# Generated by the protocol buffer compiler.  DO NOT EDIT!\ndef target():\n    pass\n",
+            } }),
+        );
+        let params = json!({
+            "textDocument": { "uri": "file:///a.py" },
+            "position": { "line": 0, "character": 5 },
+            "newName": "renamed",
+        });
+        send(&mut server, 9, "textDocument/rename", params);
+        while server.has_background_work() {
+            server.run_background_work();
+        }
+        let messages = outgoing(&mut server);
+        let response = messages.iter().find(|m| m["id"] == 9).expect("the rename response");
+        let changes = response["result"]["changes"].as_object().unwrap();
+        assert_eq!(changes.keys().collect::<Vec<_>>(), vec!["file:///a.py", "file:///b.py"]);
+    }
+
+    #[test]
+    fn test_definition_of_generated_client_goes_to_the_proto() {
+        let mut server = ready_server();
+        let proto = "syntax = \"proto3\";\n\nservice UserService {\n  rpc GetUser(GetUserRequest) returns (User);\n}\n";
+        let client = "const client = new UserServiceClient(address);\nclient.getUser(request);\n";
+        for (uri, language, text) in [("file:///user.proto", "proto", proto), ("file:///client.ts", "typescript", client)] {
+            notify(
+                &mut server,
+                "textDocument/didOpen",
+                json!({ "textDocument": { "uri": uri, "languageId": language, "version": 1, "text": text } }),
+            );
+        }
+
+        for (id, position, line) in [(10, (1, 9), 3), (11, (0, 22), 2)] {
+            let params = json!({
+                "textDocument": { "uri": "file:///client.ts" },
+                "position": { "line": position.0, "character": position.1 },
+            });
+            let reply = request(&mut server, id, "textDocument/definition", params);
+            assert_eq!(reply["result"]["uri"], "file:///user.proto");
+            assert_eq!(reply["result"]["range"]["start"]["line"], line);
+        }
+    }
+
+    #[test]
+    fn test_unresolved_name_has_no_definition() {
+        let mut server = ready_server();
+        let text = "def greet(name):\n    print(name)\n";
+        notify(
+            &mut server,
+            "textDocument/didOpen",
+            json!({ "textDocument": { "uri": "file:///c.py", "languageId": "python", "version": 1, "text": text } }),
+        );
+
+        // `print` is a builtin, not the function it is called from
+        let params = json!({ "textDocument": { "uri": "file:///c.py" }, "position": { "line": 1, "character": 5 } });
+        let reply = request(&mut server, 10, "textDocument/definition", params.clone());
+        assert_eq!(reply["result"], json!(null));
+        let reply = request(&mut server, 11, "textDocument/hover", params);
+        assert_eq!(reply["result"], json!(null));
+
+        // The enclosing function's own name still resolves
+        let params = json!({ "textDocument": { "uri": "file:///c.py" }, "position": { "line": 0, "character": 5 } });
+        let reply = request(&mut server, 12, "textDocument/definition", params);
+        assert_eq!(reply["result"]["range"]["start"]["line"], 0);
+    }
+
+    #[test]
+    fn test_index_stats_before_indexing() {
+        let mut server = ready_server();
+        let reply = request(&mut server, 12, "logos/indexStats", json!({}));
+        let stats = &reply["result"];
+        assert_eq!(stats["mode"], "basic");
+        assert_eq!(stats["status"], "openDocuments");
+        assert_eq!(stats["fileCount"], 2);
+        assert_eq!(stats["symbolsByLanguage"], json!({ "python": 2 }));
+        assert_eq!(stats["symbolCount"], 2);
+        assert_eq!(stats["pending"], json!({ "scanFiles": 0, "jobs": 0 }));
+        assert_eq!(stats["lastIndexedAt"], json!(null));
+    }
+
+    #[test]
+    fn test_text_search_covers_open_documents() {
+        let mut server = ready_server();
+        for (uri, text) in [
+            ("file:///ws/a.py", "import os\nTIMEOUT = 30\n"),
+            ("file:///ws/b.py", "from a import TIMEOUT\nwait(timeout=TIMEOUT)\n"),
+        ] {
+            notify(
+                &mut server,
+                "textDocument/didOpen",
+                json!({ "textDocument": { "uri": uri, "languageId": "python", "version": 1, "text": text } }),
+            );
+        }
+
+        let params = json!({ "query": "TIMEOUT", "options": { "caseSensitive": true } });
+        let reply = request(&mut server, 12, "logos/textSearch", params);
+        let files = reply["result"]["files"].as_array().unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[1]["uri"], "file:///ws/b.py");
+        assert_eq!(files[1]["matches"][1]["range"]["start"], json!({ "line": 1, "character": 13 }));
+        assert_eq!(files[1]["matches"][1]["lineText"], "wait(timeout=TIMEOUT)");
+        assert_eq!(reply["result"]["matchCount"], 3);
+
+        let params = json!({ "query": r"wait\(\w+=", "options": { "regex": true } });
+        let reply = request(&mut server, 13, "logos/textSearch", params);
+        assert_eq!(reply["result"]["matchCount"], 1);
+        let reply = request(&mut server, 14, "logos/textSearch", json!({ "query": "(", "options": { "regex": true } }));
+        assert_eq!(error_code(&reply), Some(error_codes::INVALID_PARAMS as i64));
+    }
+
+    #[test]
+    fn test_cancelled_rename_gets_no_edits() {
+        let mut server = ready_server();
+        let params = json!({
+            "textDocument": { "uri": "file:///a.py" },
+            "position": { "line": 0, "character": 5 },
+            "newName": "renamed",
+            "workDoneToken": "rename-1",
+        });
+        assert_eq!(send(&mut server, 8, "textDocument/rename", params), None);
+        notify(&mut server, "$/cancelRequest", json!({ "id": 8 }));
+
+        assert!(!server.has_background_work());
+        let messages = outgoing(&mut server);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0]["id"], 8);
+        assert_eq!(error_code(&messages[0]), Some(error_codes::REQUEST_CANCELLED as i64));
+    }
+
+    #[test]
+    fn test_configuration_change_keeps_trust() {
+        let trusted = |reply: &Value| reply["result"]["capabilities"]["experimental"]["workspaceTrust"]["trusted"].clone();
+
+        // Restricted until the client grants trust
+        let reply = request(&mut Server::new(), 0, "initialize", json!({}));
+        assert_eq!(trusted(&reply), json!(false));
+
+        let mut server = Server::new();
+        let reply = request(&mut server, 0, "initialize", json!({ "initializationOptions": { "trust": "trusted" } }));
+        assert_eq!(trusted(&reply), json!(true));
+        notify(&mut server, "initialized", json!({}));
+
+        // Settings that leave trust out, or are invalid, don't change it
+        notify(&mut server, "workspace/didChangeConfiguration", json!({ "settings": { "index": { "scan": false } } }));
+        assert!(server.state.trust.is_trusted());
+        notify(&mut server, "workspace/didChangeConfiguration", json!({ "settings": { "index": 1 } }));
+        assert!(server.state.trust.is_trusted());
+
+        notify(&mut server, "workspace/didChangeConfiguration", json!({ "settings": { "trust": "restricted" } }));
+        assert!(!server.state.trust.is_trusted());
+        notify(&mut server, "workspace/didChangeConfiguration", json!({ "settings": {} }));
+        assert!(!server.state.trust.is_trusted());
+    }
+
+    #[test]
+    fn test_requests_before_initialize_are_rejected() {
+        let mut server = Server::new();
+        let position = json!({ "textDocument": { "uri": "file:///early.py" }, "position": { "line": 0, "character": 0 } });
+
+        let reply = request(&mut server, 1, "textDocument/hover", position.clone());
+        assert_eq!(error_code(&reply), Some(error_codes::SERVER_NOT_INITIALIZED as i64));
+        assert_eq!(reply["id"], 1);
+        let reply = request(&mut server, 2, "shutdown", Value::Null);
+        assert_eq!(error_code(&reply), Some(error_codes::SERVER_NOT_INITIALIZED as i64));
+
+        let reply = request(&mut server, 3, "initialize", json!({}));
+        assert_eq!(error_code(&reply), None);
+        assert!(reply["result"]["capabilities"].is_object());
+
+        let reply = request(&mut server, 4, "initialize", json!({}));
+        assert_eq!(error_code(&reply), Some(error_codes::INVALID_REQUEST as i64));
+
+        // Requests are served between `initialize` and `initialized`
+        let reply = request(&mut server, 5, "textDocument/hover", position);
+        assert_ne!(error_code(&reply), Some(error_codes::SERVER_NOT_INITIALIZED as i64));
+    }
+
+    #[test]
+    fn test_notifications_wait_for_initialized() {
+        let mut server = Server::new();
+        let uri = "file:///held.py";
+        let did_open =
+            json!({ "textDocument": { "uri": uri, "languageId": "python", "version": 1, "text": "def held():\n    pass\n" } });
+        // Notifications before `initialize` are dropped, and `initialized`
+        // is out of order
+        notify(&mut server, "textDocument/didOpen", did_open.clone());
+        notify(&mut server, "initialized", json!({}));
+        request(&mut server, 1, "initialize", json!({}));
+        assert!(server.held.is_empty());
+        assert!(server.state.get_document(uri).is_none());
+
+        // Between `initialize` and `initialized` they wait
+        notify(&mut server, "textDocument/didOpen", did_open);
+        assert!(server.state.get_document(uri).is_none());
+
+        notify(&mut server, "initialized", json!({}));
+        assert!(server.state.get_document(uri).is_some());
+
+        // Once initialized, notifications are handled as they come
+        notify(&mut server, "textDocument/didClose", json!({ "textDocument": { "uri": uri } }));
+        assert!(server.state.get_document(uri).is_none());
+    }
+}
@@ -1,34 +1,53 @@
 //! Global state management for the language service
 
-use std::collections::HashMap;
-use std::path::PathBuf;
-use std::sync::Arc;
-use logos_core::Document;
-use logos_index::{ProjectIndexer, SymbolIndex, TodoIndex};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Instant, SystemTime};
+use logos_core::uri::uri_to_path;
+use logos_analysis::{AnalysisHost, AnalysisSnapshot};
+use logos_core::{CompletionInsertMode, Document, TextChange};
+use logos_index::persist::{self, LoadOutcome};
+use logos_index::{ArchiveFs, CompilationDatabase, Coverage, IndexingStats, OsFs, ProjectIndexer, Vfs};
+use logos_parser::query::QueryEngine;
+use logos_parser::LanguageId;
+use serde_json::{json, Value};
+use tree_sitter::Tree;
+
+use crate::baseline::Baseline;
+use crate::config::{ClientCapabilities, Settings, WorkspaceTrust};
+use crate::hooks::{self, HookEvent, HookRuns};
+use crate::plugins::PluginHost;
+use crate::process::CancelToken;
+use crate::jobs::{Job, RunningJob, WorkDone};
+use crate::protocol::{ClientResponse, Notification, Outgoing, RequestId};
+use crate::scan::WorkspaceScan;
 
 /// Intelligence mode
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum IntelligenceMode {
     /// Basic mode - lightweight LSP
+    #[default]
     Basic,
     /// Smart mode - full project indexing
     Smart,
 }
 
-impl Default for IntelligenceMode {
-    fn default() -> Self {
-        Self::Basic
-    }
+/// A language whose grammar or adapter is unavailable
+#[derive(Debug, Clone)]
+pub struct LanguageFailure {
+    /// Language id (e.g. "python")
+    pub language: String,
+    /// Human-readable reason
+    pub reason: String,
+    /// When the failure was first recorded
+    pub since: Instant,
 }
 
 /// Global state for the language service daemon
 pub struct State {
-    /// Open documents by URI
-    pub documents: HashMap<String, Document>,
-    /// Symbol index (Basic mode)
-    pub symbol_index: SymbolIndex,
-    /// TODO index
-    pub todo_index: TodoIndex,
+    /// Open documents and their symbols, TODOs and parse trees (Basic mode)
+    analysis: AnalysisHost,
     /// Project indexer (Smart mode)
     pub project_indexer: Option<Arc<ProjectIndexer>>,
     /// Current intelligence mode
@@ -37,18 +56,115 @@ pub struct State {
     pub initialized: bool,
     /// Root path of the workspace
     pub root_path: Option<String>,
+    /// Stats from the most recent workspace indexing run
+    pub last_index_stats: Option<IndexingStats>,
+    /// When the project index was last brought up to date with the disk
+    index_synced_at: Option<SystemTime>,
+    /// When a file was last re-indexed in Smart mode
+    pub last_reindex_at: Option<SystemTime>,
+    /// When the workspace scan last completed
+    pub last_scan_at: Option<SystemTime>,
+    /// Languages running in degraded mode, keyed by language id
+    pub language_failures: HashMap<String, LanguageFailure>,
+    /// Server start time
+    pub started_at: Instant,
+    /// User settings
+    pub settings: Settings,
+    /// Trust in the workspace, as last granted by the client
+    pub trust: WorkspaceTrust,
+    /// Features reported by the client in `initialize`
+    pub client_capabilities: ClientCapabilities,
+    /// Filesystem the project indexer reads from
+    pub vfs: Arc<dyn Vfs>,
+    /// Dependency archives mounted into `vfs`
+    pub archives: Arc<ArchiveFs>,
+    /// Plugins loaded at startup
+    pub plugins: PluginHost,
+    /// Accepted diagnostics, hidden from reports
+    pub baseline: Option<Baseline>,
+    /// Line coverage from the configured test reports
+    pub coverage: Option<Coverage>,
+    /// Compiled highlight queries
+    pub queries: Mutex<QueryEngine>,
+    /// Stops external tools still running, on shutdown
+    pub tools_cancel: CancelToken,
+    /// Document hooks whose commands are still running
+    hook_runs: HookRuns,
+    /// Startup scan of the workspace, while files are left to analyze
+    workspace_scan: Option<WorkspaceScan>,
+    /// Requests served in the background, oldest first
+    jobs: VecDeque<RunningJob>,
+    /// Id of the next request sent to the client
+    next_request_id: i64,
+    /// Notifications, requests and late responses waiting to be written
+    outgoing: Vec<Outgoing>,
 }
 
 impl State {
     pub fn new() -> Self {
+        let archives = Arc::new(ArchiveFs::new(Arc::new(OsFs)));
         Self {
-            documents: HashMap::new(),
-            symbol_index: SymbolIndex::new(),
-            todo_index: TodoIndex::new(),
+            analysis: AnalysisHost::new(),
             project_indexer: None,
             mode: IntelligenceMode::Basic,
             initialized: false,
             root_path: None,
+            last_index_stats: None,
+            index_synced_at: None,
+            last_reindex_at: None,
+            last_scan_at: None,
+            language_failures: HashMap::new(),
+            started_at: Instant::now(),
+            settings: Settings::default(),
+            trust: WorkspaceTrust::default(),
+            client_capabilities: ClientCapabilities::default(),
+            vfs: Arc::clone(&archives) as Arc<dyn Vfs>,
+            archives,
+            plugins: PluginHost::default(),
+            baseline: None,
+            coverage: None,
+            queries: Mutex::new(QueryEngine::new()),
+            tools_cancel: CancelToken::new(),
+            hook_runs: HookRuns::default(),
+            workspace_scan: None,
+            jobs: VecDeque::new(),
+            next_request_id: 1,
+            outgoing: Vec::new(),
+        }
+    }
+
+    /// Run the hooks configured for `event`
+    pub fn fire_hook(&self, event: HookEvent, payload: serde_json::Value) {
+        if !self.trust.is_trusted() {
+            return;
+        }
+        let root = self.root_path.as_deref().map(std::path::Path::new);
+        hooks::fire(&self.settings.hooks, &self.plugins, root, &self.tools_cancel, event, payload, None);
+    }
+
+    /// Run the hooks configured for `event` on the document at `uri`,
+    /// unless those started for it last time are still running
+    pub fn fire_document_hook(&self, event: HookEvent, uri: &str, payload: serde_json::Value) {
+        if !self.trust.is_trusted() {
+            return;
+        }
+        let Some(claim) = self.hook_runs.claim(event, uri) else {
+            log::debug!("{} hook still running for {}, skipped", event.as_str(), uri);
+            return;
+        };
+        let root = self.root_path.as_deref().map(std::path::Path::new);
+        hooks::fire(&self.settings.hooks, &self.plugins, root, &self.tools_cancel, event, payload, Some(claim));
+    }
+
+    /// Load plugins from the configured directory, replacing any loaded before
+    pub fn load_plugins(&mut self) {
+        if !self.trust.is_trusted() {
+            log::info!("Restricted workspace: plugins are not loaded");
+            self.plugins = PluginHost::default();
+            return;
+        }
+        if let Some(dir) = self.settings.plugins.resolved_directory() {
+            self.plugins = PluginHost::load_dir(&dir);
         }
     }
 
@@ -58,21 +174,73 @@ impl State {
             return Ok(());
         }
 
-        let indexer = ProjectIndexer::new();
+        let mut indexer = ProjectIndexer::with_vfs(Arc::clone(&self.vfs));
+        for adapter in self.plugins.adapters() {
+            indexer.register_adapter(Box::new(adapter));
+        }
 
-        // Index the workspace if root path is set
-        if let Some(ref root) = self.root_path {
-            let root_path = PathBuf::from(root);
-            if root_path.exists() {
+        // Index the workspace if root path is set, starting from the saved
+        // index when there is a usable one
+        if let Some(root) = self.root_path.clone() {
+            let root_path = PathBuf::from(&root);
+            if let Some(path) = self.settings.cpp.compile_commands_path(&root_path) {
+                self.load_compilation_database(&indexer, &path);
+            }
+            indexer.set_include_paths(self.settings.cpp.include_paths(&root_path));
+            indexer.set_exclude(self.settings.index.exclude.clone());
+            indexer.set_memory_budget(self.settings.index.memory_budget());
+            if self.vfs.is_dir(&root_path) {
                 log::info!("Starting Smart mode indexing for: {}", root);
-                match indexer.index_directory(&root_path) {
+                let started_at = SystemTime::now();
+                let cache_file = self.settings.index.cache_file(&root_path);
+                let outcome = cache_file.as_deref().map_or(LoadOutcome::Missing, persist::load);
+                let result = match outcome {
+                    LoadOutcome::Loaded(loaded) => {
+                        if let Some(version) = loaded.migrated_from {
+                            log::info!(
+                                "Migrated saved index from schema version {} to {}",
+                                version,
+                                loaded.header.schema_version
+                            );
+                        }
+                        indexer.get_index().restore(loaded.snapshot);
+                        indexer.refresh_directory(&root_path)
+                    }
+                    LoadOutcome::Rebuild(reason) => {
+                        log::warn!("Discarding saved index: {}", reason);
+                        self.outgoing.push(Notification::new(
+                            "window/showMessage",
+                            json!({
+                                "type": 3, // Info
+                                "message": format!("Logos: rebuilding the project index ({}).", reason),
+                            }),
+                        ).into());
+                        indexer.index_directory(&root_path)
+                    }
+                    LoadOutcome::Missing => indexer.index_directory(&root_path),
+                };
+                match result {
                     Ok(stats) => {
                         log::info!(
-                            "Indexed {} files, {} symbols, {} imports",
+                            "Indexed {} files ({} unchanged), {} symbols, {} imports in {:?}",
                             stats.files_indexed,
+                            stats.files_unchanged,
                             stats.symbols_found,
-                            stats.imports_found
+                            stats.imports_found,
+                            stats.duration
+                        );
+                        self.fire_hook(
+                            HookEvent::IndexComplete,
+                            json!({
+                                "root": root,
+                                "filesIndexed": stats.files_indexed,
+                                "symbolsFound": stats.symbols_found,
+                                "errors": stats.errors.len(),
+                                "durationMs": stats.duration.as_millis() as u64,
+                            }),
                         );
+                        self.last_index_stats = Some(stats);
+                        self.index_synced_at = Some(started_at);
                     }
                     Err(e) => {
                         log::warn!("Indexing error: {}", e);
@@ -83,9 +251,51 @@ impl State {
 
         self.project_indexer = Some(Arc::new(indexer));
         self.mode = IntelligenceMode::Smart;
+        self.sync_indexer_failures();
+        self.save_index();
         Ok(())
     }
 
+    fn load_compilation_database(&mut self, indexer: &ProjectIndexer, path: &Path) {
+        let db = self
+            .vfs
+            .read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|json| CompilationDatabase::from_json(&json));
+        match db {
+            Ok(db) => {
+                log::info!("Loaded {} compile commands from {}", db.len(), path.display());
+                indexer.set_compilation_database(Some(Arc::new(db)));
+            }
+            Err(e) => {
+                log::warn!("Failed to load {}: {}", path.display(), e);
+                self.outgoing.push(Notification::new(
+                    "window/showMessage",
+                    json!({
+                        "type": 2, // Warning
+                        "message": format!("Logos: can't read {}: {}", path.display(), e),
+                    }),
+                ).into());
+            }
+        }
+    }
+
+    /// Save the project index so the next start can skip unchanged files
+    pub fn save_index(&self) {
+        let (Some(indexer), Some(synced_at), Some(root)) =
+            (self.get_indexer(), self.index_synced_at, self.root_path.as_deref())
+        else {
+            return;
+        };
+        let Some(path) = self.settings.index.cache_file(std::path::Path::new(root)) else {
+            return;
+        };
+        match persist::save(&indexer.get_index(), &path, synced_at) {
+            Ok(()) => log::info!("Saved project index to {}", path.display()),
+            Err(e) => log::warn!("Failed to save project index to {}: {}", path.display(), e),
+        }
+    }
+
     /// Switch to Basic mode
     pub fn enable_basic_mode(&mut self) {
         self.project_indexer = None;
@@ -97,57 +307,379 @@ impl State {
         self.mode == IntelligenceMode::Smart
     }
 
+    /// Completion insertion mode after accounting for client snippet support
+    pub fn completion_insert_mode(&self) -> CompletionInsertMode {
+        self.settings
+            .completion
+            .insert_mode
+            .effective(self.client_capabilities.snippet_support)
+    }
+
     /// Get the project indexer (Smart mode only)
     pub fn get_indexer(&self) -> Option<&ProjectIndexer> {
         self.project_indexer.as_ref().map(|i| i.as_ref())
     }
 
-    /// Open a document
-    pub fn open_document(&mut self, uri: String, language_id: String, content: String) {
-        let doc = Document::new(uri.clone(), language_id, content.clone());
-        self.documents.insert(uri.clone(), doc);
-        // Index TODOs
-        self.todo_index.index_document(&uri, &content);
+    /// Replace the settings, re-analyzing open documents if the active
+    /// defines or features changed
+    pub fn apply_settings(&mut self, settings: Settings) {
+        self.analysis.set_cfg(settings.cfg.to_config());
+        let trust = settings.trust.unwrap_or(self.trust);
+        let trust_changed = trust != self.trust;
+        self.trust = trust;
+        self.settings = settings;
+        // Plugins load once at startup; a change of trust afterwards loads
+        // or drops them. Adapters already handed to the indexer keep their
+        // plugin until the next Smart mode start.
+        if trust_changed && self.initialized {
+            self.load_plugins();
+        }
+        self.load_grammars();
+        self.load_baseline();
+        self.load_coverage();
+        if let Some(indexer) = self.get_indexer() {
+            indexer.set_memory_budget(self.settings.index.memory_budget());
+        }
+    }
 
-        // Re-index in Smart mode
-        if let Some(ref indexer) = self.project_indexer {
-            if let Some(path) = uri_to_path(&uri) {
-                let _ = indexer.reindex_file(&path);
+    /// Check the bundled grammars against the node kinds their extractors
+    /// match on, degrading each language whose grammar no longer fits
+    pub fn check_grammars(&mut self) {
+        for (language, reason) in logos_parser::compat::check_all() {
+            self.analysis.mark_language_failed(language.as_str(), reason);
+        }
+        self.sync_analysis_failures();
+    }
+
+    /// Load the configured grammars that aren't loaded yet. A grammar that
+    /// fails to load degrades its own language only.
+    fn load_grammars(&mut self) {
+        if !self.trust.is_trusted() {
+            return;
+        }
+        let root = self.root_path.as_deref().map(PathBuf::from);
+        let resolve = |path: &Path| match &root {
+            Some(root) if path.is_relative() => root.join(path),
+            _ => path.to_path_buf(),
+        };
+        for spec in self.settings.grammars.clone() {
+            let spec = logos_parser::grammar::GrammarSpec {
+                library: resolve(&spec.library),
+                highlights: spec.highlights.as_deref().map(resolve),
+                ..spec
+            };
+            match logos_parser::grammar::registry().load(&spec) {
+                Ok(id) => log::info!("Loaded grammar {} from {}", id.as_str(), spec.library.display()),
+                Err(e) => self.record_language_failure(&spec.id, e.to_string()),
             }
         }
     }
 
-    /// Update a document
-    pub fn update_document(&mut self, uri: &str, content: String) {
-        if let Some(doc) = self.documents.get_mut(uri) {
-            doc.set_content(content.clone());
+    /// Read the coverage reports of the workspace that exist, merging them
+    pub fn load_coverage(&mut self) {
+        let Some(root) = self.root_path.as_deref() else {
+            return;
+        };
+        let mut coverage = Coverage::new();
+        for path in self.settings.coverage.report_paths(Path::new(root)) {
+            let Ok(content) = self.vfs.read_to_string(&path) else {
+                continue;
+            };
+            match Coverage::parse(&content) {
+                Ok(report) => {
+                    log::info!("Loaded coverage of {} files from {}", report.len(), path.display());
+                    coverage.merge(report);
+                }
+                Err(e) => log::warn!("Failed to read coverage report {}: {}", path.display(), e),
+            }
         }
-        // Re-index TODOs
-        self.todo_index.index_document(uri, &content);
+        self.coverage = (!coverage.is_empty()).then_some(coverage);
+    }
 
-        // Re-index in Smart mode
+    /// Read the diagnostics baseline of the workspace, if it has one
+    fn load_baseline(&mut self) {
+        let Some(root) = self.root_path.as_deref() else {
+            return;
+        };
+        let path = self.settings.diagnostics.baseline_path(Path::new(root));
+        self.baseline = match Baseline::load(&path) {
+            Ok(baseline) => baseline,
+            Err(e) => {
+                log::warn!("Failed to load {}: {}", path.display(), e);
+                self.outgoing.push(Notification::new(
+                    "window/showMessage",
+                    json!({
+                        "type": 2, // Warning
+                        "message": format!("Logos: can't read {}: {}", path.display(), e),
+                    }),
+                ).into());
+                None
+            }
+        };
+    }
+
+    /// Read-only view of the open documents and their analysis
+    pub fn analysis(&self) -> AnalysisSnapshot<'_> {
+        self.analysis.snapshot()
+    }
+
+    /// Open a document
+    pub fn open_document(&mut self, uri: String, language_id: String, content: String) {
+        self.analysis.open_document(uri.clone(), language_id, content);
+        self.sync_analysis_failures();
+        self.reindex_project_file(&uri);
+    }
+
+    /// Apply incremental edits to a document, reusing its last parse tree.
+    /// The project index reads files from disk, so it catches up on save.
+    pub fn edit_document(&mut self, uri: &str, changes: &[TextChange]) {
+        self.analysis.edit_document(uri, changes);
+        self.sync_analysis_failures();
+    }
+
+    /// Re-index a file in Smart mode
+    pub fn reindex_project_file(&mut self, uri: &str) {
         if let Some(ref indexer) = self.project_indexer {
             if let Some(path) = uri_to_path(uri) {
                 let _ = indexer.reindex_file(&path);
             }
+            self.last_reindex_at = Some(SystemTime::now());
+            self.sync_indexer_failures();
         }
     }
 
-    /// Close a document
+    /// Cached parse tree of an open document, kept current by each edit.
+    /// Handlers read it rather than parsing the document again.
+    pub fn get_tree(&self, uri: &str) -> Option<&Tree> {
+        self.analysis().tree(uri)
+    }
+
+    /// Mark a language as degraded and tell the client once
+    pub fn record_language_failure(&mut self, language: &str, reason: String) {
+        if self.language_failures.contains_key(language) {
+            return;
+        }
+        log::error!("Language service for {} is degraded: {}", language, reason);
+        self.outgoing.push(Notification::new(
+            "window/showMessage",
+            json!({
+                "type": 2, // Warning
+                "message": format!(
+                    "Logos: {} support is unavailable ({}). Other languages are unaffected.",
+                    language, reason
+                ),
+            }),
+        ).into());
+        self.language_failures.insert(
+            language.to_string(),
+            LanguageFailure {
+                language: language.to_string(),
+                reason,
+                since: Instant::now(),
+            },
+        );
+    }
+
+    fn sync_indexer_failures(&mut self) {
+        let failures = match self.project_indexer {
+            Some(ref indexer) => indexer.language_failures(),
+            None => return,
+        };
+        for (language, reason) in failures {
+            self.analysis.mark_language_failed(&language, reason);
+        }
+        self.sync_analysis_failures();
+    }
+
+    fn sync_analysis_failures(&mut self) {
+        for (language, reason) in self.analysis.take_new_failures() {
+            self.record_language_failure(&language, reason);
+        }
+    }
+
+    /// Degradation record for the language of an open document, if any
+    pub fn language_failure_for(&self, uri: &str) -> Option<&LanguageFailure> {
+        let doc = self.analysis().document(uri)?;
+        let lang = LanguageId::from_str(&doc.language_id)
+            .map(|l| l.as_str().to_string())
+            .unwrap_or_else(|| doc.language_id.clone());
+        self.language_failures.get(&lang)
+    }
+
+    /// Take the messages queued for the client
+    pub fn take_outgoing(&mut self) -> Vec<Outgoing> {
+        std::mem::take(&mut self.outgoing)
+    }
+
+    /// Re-key everything stored for a renamed file or directory
+    pub fn rename_files(&mut self, from: &str, to: &str) {
+        let (moved, todos) = self.analysis.rename_files(from, to);
+        let project_files = self.get_indexer().map_or(0, |indexer| indexer.rename_files(from, to));
+        log::info!(
+            "Renamed {} -> {}: {} indexed documents, {} with TODOs, {} project files",
+            from, to, moved, todos, project_files
+        );
+    }
+
+    /// Close a document. Its unsaved edits are discarded, so a file of the
+    /// workspace goes back to its content on disk.
     pub fn close_document(&mut self, uri: &str) {
-        self.documents.remove(uri);
-        self.symbol_index.remove_document(uri);
-        self.todo_index.remove_document(uri);
+        self.analysis.close_document(uri);
+        if self.analysis.is_workspace_file(uri) {
+            match uri_to_path(uri).and_then(|path| self.vfs.read_to_string(&path).ok()) {
+                Some(content) => self.analysis.index_file(uri, &content),
+                None => self.analysis.forget_file(uri),
+            }
+            self.sync_analysis_failures();
+        }
+    }
+
+    /// Start analyzing the files of the workspace in the background, for
+    /// workspace symbols and TODOs beyond the open documents
+    pub fn start_workspace_scan(&mut self) {
+        if !self.settings.index.scan || self.workspace_scan.is_some() {
+            return;
+        }
+        let Some(root) = self.root_path.as_deref().map(PathBuf::from).filter(|root| self.vfs.is_dir(root)) else {
+            return;
+        };
+        let scan = WorkspaceScan::new(self.vfs.as_ref(), &root, &self.settings.index.exclude);
+        log::info!("Scanning {} workspace files under {}", scan.total(), root.display());
+        self.outgoing.push(scan.begin().into());
+        self.workspace_scan = Some(scan);
+    }
+
+    /// Answer request `id` with `job`, reporting progress under the
+    /// `workDoneToken` of `params`, or a token created for it when the
+    /// client supports server-initiated progress
+    pub fn start_job(&mut self, id: RequestId, params: &Value, job: Box<dyn Job>) {
+        let progress = match params.get("workDoneToken").filter(|token| !token.is_null()) {
+            Some(token) => WorkDone::client(token.clone()),
+            None if self.client_capabilities.work_done_progress => {
+                let request_id = self.next_request_id;
+                self.next_request_id += 1;
+                let token = json!(format!("logos/job/{}", request_id));
+                let (progress, request) = WorkDone::create(RequestId::Number(request_id), token);
+                self.outgoing.push(request.into());
+                progress
+            }
+            None => WorkDone::none(),
+        };
+        log::info!("Queued {} ({} files)", job.title(), job.total());
+        self.jobs.push_back(RunningJob::new(id, job, progress));
+    }
+
+    /// Cancel the job answering request `id`; false when there is none
+    pub fn cancel_job(&mut self, id: &RequestId) -> bool {
+        let Some(index) = self.jobs.iter().position(|job| &job.id == id) else {
+            return false;
+        };
+        if let Some(job) = self.jobs.remove(index) {
+            self.outgoing.extend(job.cancel());
+        }
+        true
+    }
+
+    /// Cancel the job reporting progress under `token`
+    pub fn cancel_job_with_token(&mut self, token: &Value) -> bool {
+        match self.jobs.iter().find(|job| job.reports_to(token)).map(|job| job.id.clone()) {
+            Some(id) => self.cancel_job(&id),
+            None => false,
+        }
+    }
+
+    /// Take the client's answer to a request the server sent
+    pub fn handle_client_response(&mut self, response: ClientResponse) {
+        let accepted = response.error.is_none();
+        if !self.jobs.iter_mut().any(|job| job.acknowledge(&response.id, accepted)) {
+            log::debug!("Ignoring response to unknown request {:?}", response.id);
+        }
+    }
+
+    /// When the project index was last brought up to date with the disk
+    pub fn index_synced_at(&self) -> Option<SystemTime> {
+        self.index_synced_at
+    }
+
+    /// Files the workspace scan has left to analyze
+    pub fn pending_scan_files(&self) -> usize {
+        self.workspace_scan.as_ref().map_or(0, |scan| scan.total() - scan.done())
+    }
+
+    /// Background jobs waiting to finish
+    pub fn pending_jobs(&self) -> usize {
+        self.jobs.len()
+    }
+
+    /// Whether work is waiting for the server to be idle
+    pub fn has_background_work(&self) -> bool {
+        !self.jobs.is_empty() || self.workspace_scan.is_some()
+    }
+
+    /// Take the next step of background work: queued jobs first, as a
+    /// client waits on each, then the workspace scan
+    pub fn run_background_work(&mut self) {
+        if let Some(mut job) = self.jobs.pop_front() {
+            let (messages, done) = job.step(self);
+            self.outgoing.extend(messages);
+            if !done {
+                self.jobs.push_front(job);
+            }
+            return;
+        }
+        self.scan_workspace_batch();
+    }
+
+    /// Analyze the next batch of workspace files, reporting progress
+    fn scan_workspace_batch(&mut self) {
+        let Some(batch) = self.workspace_scan.as_mut().map(WorkspaceScan::next_batch) else {
+            return;
+        };
+        for path in batch {
+            match self.vfs.read_to_string(&path) {
+                Ok(content) => self.analysis.index_file(self.vfs.uri(&path), &content),
+                Err(e) => log::debug!("Skipping {}: {}", path.display(), e),
+            }
+        }
+        self.sync_analysis_failures();
+
+        let Some(scan) = self.workspace_scan.take() else {
+            return;
+        };
+        if scan.is_done() {
+            log::info!("Scanned {} workspace files in {:?}", scan.total(), scan.elapsed());
+            self.last_scan_at = Some(SystemTime::now());
+            self.outgoing.push(scan.end().into());
+        } else {
+            self.outgoing.push(scan.report().into());
+            self.workspace_scan = Some(scan);
+        }
     }
 
     /// Get a document by URI
     pub fn get_document(&self, uri: &str) -> Option<&Document> {
-        self.documents.get(uri)
+        self.analysis().document(uri)
     }
 
     /// Get all open document URIs
     pub fn get_open_documents(&self) -> Vec<String> {
-        self.documents.keys().cloned().collect()
+        self.analysis().documents().map(|(uri, _)| uri.clone()).collect()
+    }
+
+    /// Paths of the indexed and scanned files and open documents, sorted
+    pub fn workspace_files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self
+            .get_indexer()
+            .map(|indexer| indexer.get_index().symbols.files())
+            .unwrap_or_default()
+            .into_iter()
+            .chain(self.analysis.workspace_files().map(str::to_string))
+            .chain(self.get_open_documents())
+            .filter_map(|uri| uri_to_path(&uri))
+            .collect();
+        files.sort();
+        files.dedup();
+        files
     }
 }
 
@@ -157,11 +689,3 @@ impl Default for State {
     }
 }
 
-/// Convert a file URI to a path
-fn uri_to_path(uri: &str) -> Option<PathBuf> {
-    if uri.starts_with("file://") {
-        Some(PathBuf::from(&uri[7..]))
-    } else {
-        None
-    }
-}
@@ -0,0 +1,42 @@
+/*
+ * C ABI for the Logos analysis engine (logos-ffi).
+ *
+ * Strings are NUL-terminated UTF-8. JSON results are owned by the caller
+ * and must be released with logos_free_string. A LogosHost must not be
+ * used from two threads at once.
+ */
+
+#ifndef LOGOS_H
+#define LOGOS_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define LOGOS_OK 0
+#define LOGOS_INVALID_ARGUMENT (-1)
+
+typedef struct LogosHost LogosHost;
+
+LogosHost* logos_host_new(void);
+void logos_host_free(LogosHost* host);
+
+int32_t logos_open_document(LogosHost* host, const char* uri,
+                            const char* language_id, const char* text);
+int32_t logos_change_document(LogosHost* host, const char* uri, const char* text);
+int32_t logos_close_document(LogosHost* host, const char* uri);
+
+/* JSON array of {name, kind, detail, containerName, range, selectionRange} */
+char* logos_get_symbols_json(const LogosHost* host, const char* uri);
+/* JSON array of LSP diagnostics {range, severity, source, message} */
+char* logos_get_diagnostics_json(const LogosHost* host, const char* uri);
+
+void logos_free_string(char* ptr);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* LOGOS_H */
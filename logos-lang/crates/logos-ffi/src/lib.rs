@@ -0,0 +1,290 @@
+//! Logos FFI - C ABI for the analysis engine
+//!
+//! Wraps an [`AnalysisHost`] behind an opaque handle for hosts that can't
+//! run the daemon as a child process or load WASM, such as Swift or Kotlin
+//! apps and Electron native modules. Strings go in as NUL-terminated UTF-8;
+//! results come back as NUL-terminated JSON that the caller releases with
+//! `logos_free_string`. The declarations are in `include/logos.h`:
+//!
+//! ```c
+//! LogosHost* logos_host_new(void);
+//! void       logos_host_free(LogosHost* host);
+//! int32_t    logos_open_document(LogosHost* host, const char* uri,
+//!                                const char* language_id, const char* text);
+//! int32_t    logos_change_document(LogosHost* host, const char* uri, const char* text);
+//! int32_t    logos_close_document(LogosHost* host, const char* uri);
+//! char*      logos_get_symbols_json(const LogosHost* host, const char* uri);
+//! char*      logos_get_diagnostics_json(const LogosHost* host, const char* uri);
+//! void       logos_free_string(char* ptr);
+//! ```
+//!
+//! Functions returning `int32_t` give `LOGOS_OK` (0) on success and
+//! `LOGOS_INVALID_ARGUMENT` (-1) for a null handle or a null or non-UTF-8
+//! string. JSON getters return null in those cases. A handle must not be
+//! used from two threads at once.
+
+use std::ffi::{c_char, CStr, CString};
+
+use logos_analysis::AnalysisHost;
+use logos_core::Range;
+use serde_json::{json, Value};
+
+pub const LOGOS_OK: i32 = 0;
+pub const LOGOS_INVALID_ARGUMENT: i32 = -1;
+
+/// Opaque handle owning the open documents and their analysis
+pub struct LogosHost {
+    analysis: AnalysisHost,
+}
+
+/// Create an empty host. Release it with `logos_host_free`.
+#[no_mangle]
+pub extern "C" fn logos_host_new() -> *mut LogosHost {
+    Box::into_raw(Box::new(LogosHost { analysis: AnalysisHost::new() }))
+}
+
+/// Release a host and everything it holds
+///
+/// # Safety
+/// `host` must be null or a handle from `logos_host_new` that hasn't been
+/// freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn logos_host_free(host: *mut LogosHost) {
+    if !host.is_null() {
+        drop(Box::from_raw(host));
+    }
+}
+
+/// Open a document, or replace one with the same URI
+///
+/// # Safety
+/// `host` must be null or a live handle; the strings must be null or
+/// NUL-terminated.
+#[no_mangle]
+pub unsafe extern "C" fn logos_open_document(
+    host: *mut LogosHost,
+    uri: *const c_char,
+    language_id: *const c_char,
+    text: *const c_char,
+) -> i32 {
+    let (Some(host), Some(uri), Some(language_id), Some(text)) = (host.as_mut(), str_arg(uri), str_arg(language_id), str_arg(text)) else {
+        return LOGOS_INVALID_ARGUMENT;
+    };
+    host.analysis.open_document(uri, language_id, text);
+    LOGOS_OK
+}
+
+/// Replace the full text of an open document
+///
+/// # Safety
+/// As for `logos_open_document`.
+#[no_mangle]
+pub unsafe extern "C" fn logos_change_document(host: *mut LogosHost, uri: *const c_char, text: *const c_char) -> i32 {
+    let (Some(host), Some(uri), Some(text)) = (host.as_mut(), str_arg(uri), str_arg(text)) else {
+        return LOGOS_INVALID_ARGUMENT;
+    };
+    host.analysis.change_document(uri, text);
+    LOGOS_OK
+}
+
+/// Close a document
+///
+/// # Safety
+/// As for `logos_open_document`.
+#[no_mangle]
+pub unsafe extern "C" fn logos_close_document(host: *mut LogosHost, uri: *const c_char) -> i32 {
+    let (Some(host), Some(uri)) = (host.as_mut(), str_arg(uri)) else {
+        return LOGOS_INVALID_ARGUMENT;
+    };
+    host.analysis.close_document(uri);
+    LOGOS_OK
+}
+
+/// Symbols of a document as a JSON array of
+/// `{name, kind, detail, containerName, range, selectionRange}`, `kind`
+/// being an LSP `SymbolKind`. Unknown documents give `[]`.
+///
+/// # Safety
+/// As for `logos_open_document`. The result must be released with
+/// `logos_free_string`.
+#[no_mangle]
+pub unsafe extern "C" fn logos_get_symbols_json(host: *const LogosHost, uri: *const c_char) -> *mut c_char {
+    let (Some(host), Some(uri)) = (host.as_ref(), str_arg(uri)) else {
+        return std::ptr::null_mut();
+    };
+    let symbols: Vec<Value> = host
+        .analysis
+        .snapshot()
+        .document_symbols(uri)
+        .iter()
+        .map(|s| {
+            json!({
+                "name": s.name,
+                "kind": s.kind.to_monaco_kind(),
+                "detail": s.detail,
+                "containerName": s.container,
+                "range": range_json(&s.range),
+                "selectionRange": range_json(&s.selection_range),
+            })
+        })
+        .collect();
+    into_c_string(Value::from(symbols))
+}
+
+/// Syntax errors and inactive regions of a document as a JSON array of LSP
+/// diagnostics (`{range, severity, source, message, tags}`). Syntax errors
+/// are only reported for data files (JSON, YAML, TOML).
+///
+/// # Safety
+/// As for `logos_get_symbols_json`.
+#[no_mangle]
+pub unsafe extern "C" fn logos_get_diagnostics_json(host: *const LogosHost, uri: *const c_char) -> *mut c_char {
+    let (Some(host), Some(uri)) = (host.as_ref(), str_arg(uri)) else {
+        return std::ptr::null_mut();
+    };
+    let diagnostics: Vec<Value> = host
+        .analysis
+        .snapshot()
+        .diagnostics(uri)
+        .iter()
+        .map(|d| {
+            json!({
+                "range": range_json(&d.range),
+                "severity": d.severity as u8,
+                "source": d.source,
+                "message": d.message,
+                "tags": d.tags.iter().map(|t| *t as u8).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    into_c_string(Value::from(diagnostics))
+}
+
+/// Release a string returned by this library
+///
+/// # Safety
+/// `ptr` must be null or a string from one of the `logos_get_*` functions
+/// that hasn't been freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn logos_free_string(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        drop(CString::from_raw(ptr));
+    }
+}
+
+/// Borrow a C string argument
+///
+/// # Safety
+/// `ptr` must be null or NUL-terminated and outlive the returned slice.
+unsafe fn str_arg<'a>(ptr: *const c_char) -> Option<&'a str> {
+    if ptr.is_null() {
+        return None;
+    }
+    CStr::from_ptr(ptr).to_str().ok()
+}
+
+fn into_c_string(value: Value) -> *mut c_char {
+    // Serialized JSON escapes control characters, so it never holds a NUL
+    CString::new(value.to_string()).map_or(std::ptr::null_mut(), CString::into_raw)
+}
+
+fn range_json(range: &Range) -> Value {
+    json!({
+        "start": { "line": range.start.line, "character": range.start.column },
+        "end": { "line": range.end.line, "character": range.end.column },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logos_analysis::conformance::{Case, Outcome, CASES};
+    use logos_core::SymbolKind;
+
+    fn c(text: &str) -> CString {
+        CString::new(text).unwrap()
+    }
+
+    unsafe fn take_json(ptr: *mut c_char) -> Value {
+        assert!(!ptr.is_null());
+        let value = serde_json::from_str(CStr::from_ptr(ptr).to_str().unwrap()).unwrap();
+        logos_free_string(ptr);
+        value
+    }
+
+    #[test]
+    fn test_document_lifecycle() {
+        unsafe {
+            let host = logos_host_new();
+            let uri = c("file:///main.py");
+            let status = logos_open_document(host, uri.as_ptr(), c("python").as_ptr(), c("def main():\n    pass\n").as_ptr());
+            assert_eq!(status, LOGOS_OK);
+
+            let symbols = take_json(logos_get_symbols_json(host, uri.as_ptr()));
+            assert_eq!(symbols[0]["name"], "main");
+            assert_eq!(symbols[0]["selectionRange"]["start"], json!({ "line": 0, "character": 4 }));
+
+            logos_change_document(host, uri.as_ptr(), c("def other():\n    pass\n").as_ptr());
+            assert_eq!(take_json(logos_get_symbols_json(host, uri.as_ptr()))[0]["name"], "other");
+
+            logos_close_document(host, uri.as_ptr());
+            assert_eq!(take_json(logos_get_symbols_json(host, uri.as_ptr())), json!([]));
+            logos_host_free(host);
+        }
+    }
+
+    #[test]
+    fn test_diagnostics_and_invalid_arguments() {
+        unsafe {
+            let host = logos_host_new();
+            let uri = c("file:///package.json");
+            logos_open_document(host, uri.as_ptr(), c("json").as_ptr(), c("{\"a\": 1,}").as_ptr());
+            let diagnostics = take_json(logos_get_diagnostics_json(host, uri.as_ptr()));
+            assert_eq!(diagnostics[0]["message"], "Trailing comma");
+
+            assert_eq!(logos_open_document(host, std::ptr::null(), c("json").as_ptr(), c("{}").as_ptr()), LOGOS_INVALID_ARGUMENT);
+            assert_eq!(logos_close_document(std::ptr::null_mut(), uri.as_ptr()), LOGOS_INVALID_ARGUMENT);
+            assert!(logos_get_symbols_json(std::ptr::null(), uri.as_ptr()).is_null());
+            logos_free_string(std::ptr::null_mut());
+            logos_host_free(host);
+        }
+    }
+
+    /// Answer a conformance case through the C ABI, rendered as
+    /// `conformance::symbol_line` and `conformance::diagnostic_line` do
+    unsafe fn run_case(case: &Case) -> Outcome {
+        let host = logos_host_new();
+        let uri = c(case.uri);
+        let status = logos_open_document(host, uri.as_ptr(), c(case.language_id).as_ptr(), c(case.text).as_ptr());
+        assert_eq!(status, LOGOS_OK);
+        let symbols = take_json(logos_get_symbols_json(host, uri.as_ptr()));
+        let diagnostics = take_json(logos_get_diagnostics_json(host, uri.as_ptr()));
+        logos_host_free(host);
+
+        let start = |value: &Value, field: &str| format!("{}:{}", value[field]["start"]["line"], value[field]["start"]["character"]);
+        Outcome {
+            symbols: symbols
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|symbol| {
+                    let kind = symbol["kind"].as_u64().and_then(|kind| SymbolKind::from_monaco_kind(kind as u32)).unwrap();
+                    format!("{:?} {} {}", kind, symbol["name"].as_str().unwrap(), start(symbol, "selectionRange"))
+                })
+                .collect(),
+            diagnostics: diagnostics
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|diagnostic| format!("{} {}", start(diagnostic, "range"), diagnostic["message"].as_str().unwrap()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_c_abi_conforms() {
+        for case in CASES {
+            assert_eq!(unsafe { run_case(case) }, case.expected(), "{}", case.name);
+        }
+    }
+}
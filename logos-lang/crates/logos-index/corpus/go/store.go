@@ -0,0 +1,25 @@
+package store
+
+import (
+	"fmt"
+	str "strings"
+)
+
+const MaxItems = 10
+
+type Item struct {
+	Name string
+	Tags []string
+}
+
+type Saver interface {
+	Save(item Item) error
+}
+
+func (i *Item) Label() string {
+	return fmt.Sprintf("%s: %s", i.Name, str.Join(i.Tags, ","))
+}
+
+func newItem(name string) *Item {
+	return &Item{Name: name}
+}
@@ -0,0 +1,21 @@
+package com.example.shop;
+
+import java.util.List;
+import static java.util.Objects.requireNonNull;
+
+public class Cart extends Base implements Iterable<Item> {
+    private final List<Item> items;
+
+    public Cart(List<Item> items) {
+        this.items = requireNonNull(items);
+    }
+
+    public int size() {
+        return items.size();
+    }
+}
+
+enum Status {
+    OPEN,
+    CLOSED
+}
@@ -0,0 +1,19 @@
+import os
+from .store import save, load as fetch
+
+MAX_USERS = 10
+
+
+class User(Base):
+    def __init__(self, name):
+        self.name = name
+
+    @property
+    def label(self):
+        return os.path.basename(self.name)
+
+
+def register(name):
+    user = User(name)
+    save(user)
+    return user
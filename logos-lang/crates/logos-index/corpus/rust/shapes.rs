@@ -0,0 +1,26 @@
+use std::fmt::{self, Display};
+
+pub struct Circle {
+    pub radius: f64,
+}
+
+pub enum Shape {
+    Round(Circle),
+    Square,
+}
+
+impl Circle {
+    pub fn area(&self) -> f64 {
+        square(self.radius) * 3.0
+    }
+}
+
+impl Display for Circle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "circle")
+    }
+}
+
+fn square(x: f64) -> f64 {
+    x * x
+}
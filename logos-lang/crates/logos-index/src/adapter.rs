@@ -4,10 +4,15 @@
 //! Each language implements this trait to provide Smart Mode indexing.
 
 use crate::symbol_table::{
-    SmartSymbol, SymbolId, SymbolLocation, SymbolReference, TypeInfo, Visibility,
+    Attribute, SmartSymbol, SymbolId, SymbolLocation, SymbolReference, TypeInfo, Visibility,
 };
+use crate::compile_commands::CompilationDatabase;
+use crate::vfs::Vfs;
 use logos_core::{Range, SymbolKind};
+use logos_parser::Utf16Columns;
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::Arc;
 
 /// Import information extracted from source
 #[derive(Debug, Clone)]
@@ -93,6 +98,65 @@ pub struct AnalysisResult {
     pub references: Vec<SymbolReference>,
 }
 
+impl AnalysisResult {
+    /// The result with its ranges, built from tree-sitter points of
+    /// `source`, in UTF-16 columns as the protocol counts them
+    pub fn with_utf16_columns(mut self, source: &str) -> Self {
+        let columns = Utf16Columns::new(source);
+        let locations = self
+            .symbols
+            .iter_mut()
+            .map(|symbol| &mut symbol.location)
+            .chain(self.references.iter_mut().map(|reference| &mut reference.location));
+        for location in locations {
+            columns.convert_range(&mut location.range);
+            columns.convert_range(&mut location.selection_range);
+        }
+        let ranges = self
+            .imports
+            .iter_mut()
+            .map(|import| &mut import.location)
+            .chain(self.exports.iter_mut().map(|export| &mut export.location))
+            .chain(self.calls.iter_mut().map(|call| &mut call.location))
+            .chain(self.type_relations.iter_mut().map(|relation| &mut relation.location));
+        for range in ranges {
+            columns.convert_range(range);
+        }
+        self
+    }
+
+    /// Replace the ids of the symbols with [`SymbolId::stable`] ones, so
+    /// that analyzing the file again keeps the ids of the declarations that
+    /// didn't change. Declarations sharing file, qualified name and kind
+    /// are told apart by their order.
+    pub fn assign_stable_ids(&mut self) {
+        let mut counts: HashMap<(&str, &str, SymbolKind), u32> = HashMap::new();
+        let mut ids = HashMap::new();
+        for symbol in &self.symbols {
+            let count = counts.entry((symbol.location.uri.as_str(), symbol.qualified_name.as_str(), symbol.kind)).or_insert(0);
+            let stable = SymbolId::stable(&symbol.location.uri, &symbol.qualified_name, symbol.kind, *count);
+            *count += 1;
+            ids.insert(symbol.id, stable);
+        }
+
+        let remap = |id: &mut SymbolId| {
+            if let Some(&stable) = ids.get(id) {
+                *id = stable;
+            }
+        };
+        for symbol in &mut self.symbols {
+            remap(&mut symbol.id);
+            if let Some(parent) = &mut symbol.parent {
+                remap(parent);
+            }
+            symbol.children.iter_mut().for_each(remap);
+        }
+        for reference in &mut self.references {
+            remap(&mut reference.symbol_id);
+        }
+    }
+}
+
 /// Language adapter trait for Smart Mode indexing
 pub trait LanguageAdapter: Send + Sync {
     /// Returns the language identifier (e.g., "typescript", "rust")
@@ -101,6 +165,13 @@ pub trait LanguageAdapter: Send + Sync {
     /// Returns file extensions this adapter handles
     fn file_extensions(&self) -> &[&str];
 
+    /// Version of the extraction logic. Change it whenever `analyze` would
+    /// extract something different from the same source, so files indexed
+    /// with an older version, saved indexes included, are analyzed again.
+    fn version(&self) -> &str {
+        "1"
+    }
+
     /// Check if this adapter can handle the given file
     fn can_handle(&self, path: &Path) -> bool {
         if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
@@ -113,8 +184,16 @@ pub trait LanguageAdapter: Send + Sync {
     /// Analyze a source file and extract symbols, imports, exports, calls, etc.
     fn analyze(&self, uri: &str, source: &str) -> AnalysisResult;
 
-    /// Resolve an import path to an absolute file path
-    fn resolve_import(&self, from_file: &Path, import_path: &str) -> Option<std::path::PathBuf> {
+    /// Analyze several `(uri, source)` files, returning one result per file
+    /// in order. The indexer hands an adapter the files of a directory in
+    /// batches; adapters override this to share setup, such as a parser,
+    /// across them.
+    fn analyze_batch(&self, files: &[(&str, &str)]) -> Vec<AnalysisResult> {
+        files.iter().map(|(uri, source)| self.analyze(uri, source)).collect()
+    }
+
+    /// Resolve an import path to an absolute file path, probing files through `vfs`
+    fn resolve_import(&self, vfs: &dyn Vfs, from_file: &Path, import_path: &str) -> Option<std::path::PathBuf> {
         // Default implementation for relative imports
         if import_path.starts_with('.') {
             let parent = from_file.parent()?;
@@ -123,18 +202,26 @@ pub trait LanguageAdapter: Send + Sync {
             // Try with various extensions
             for ext in self.file_extensions() {
                 let with_ext = resolved.with_extension(ext);
-                if with_ext.exists() {
+                if vfs.exists(&with_ext) {
                     return Some(with_ext);
                 }
                 // Try index file
                 let index = resolved.join(format!("index.{}", ext));
-                if index.exists() {
+                if vfs.exists(&index) {
                     return Some(index);
                 }
             }
         }
         None
     }
+
+    /// Use the include directories and defines of a compilation database
+    /// (`None` to stop). Only the C and C++ adapters take one.
+    fn set_compilation_database(&self, _db: Option<Arc<CompilationDatabase>>) {}
+
+    /// Directories `#include`s are looked up in after those of the
+    /// compilation database. Only the C and C++ adapters take them.
+    fn set_include_paths(&self, _paths: Vec<std::path::PathBuf>) {}
 }
 
 /// Helper to create a SymbolLocation
@@ -191,6 +278,11 @@ impl SymbolBuilder {
         self
     }
 
+    pub fn attributes(mut self, attributes: Vec<Attribute>) -> Self {
+        self.symbol.attributes = attributes;
+        self
+    }
+
     pub fn exported(mut self, exported: bool) -> Self {
         self.symbol.exported = exported;
         self
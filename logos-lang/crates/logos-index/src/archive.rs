@@ -0,0 +1,334 @@
+//! Packaged sources browsed in place
+//!
+//! A `.jar`, `.whl` or `.crate` file mounted into an [`ArchiveFs`] reads as a
+//! directory of its text entries, so dependency sources can be indexed and
+//! opened without unpacking them. Entries are addressed to clients with
+//! `archive:` URIs such as `archive:file:///deps/guava.jar!/com/google/Foo.java`.
+
+use crate::vfs::{MemoryFs, Vfs};
+use flate2::read::GzDecoder;
+use logos_core::uri;
+use std::collections::BTreeMap;
+use std::io::{self, Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+/// Scheme prefix of URIs pointing into an archive
+pub const ARCHIVE_SCHEME: &str = "archive:";
+
+/// Entries larger than this are generated or data files, not sources worth browsing
+const MAX_ENTRY_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Whether a file is a supported archive, judged by its extension
+pub fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| matches!(e.to_ascii_lowercase().as_str(), "jar" | "whl" | "crate"))
+}
+
+/// URI of `entry` (relative to the archive root) inside `archive`
+pub fn archive_uri(archive: &Path, entry: &Path) -> String {
+    let entry = uri::path_to_uri(&Path::new("/").join(entry));
+    format!("{}{}!{}", ARCHIVE_SCHEME, uri::path_to_uri(archive), &entry["file://".len()..])
+}
+
+/// Split an `archive:` URI into the archive path and the entry path relative to it
+pub fn parse_archive_uri(archive_uri: &str) -> Option<(PathBuf, PathBuf)> {
+    let rest = archive_uri.strip_prefix(ARCHIVE_SCHEME)?;
+    let (archive, entry) = rest.split_once("!/")?;
+    let archive = uri::uri_to_path(archive)?;
+    let entry = uri::uri_to_path(&format!("file:///{}", entry))?;
+    let entry = entry.strip_prefix("/").ok()?.to_path_buf();
+    Some((archive, entry))
+}
+
+/// Mounts archives over another filesystem.
+///
+/// Paths below a mounted archive (`/deps/guava.jar/com/google/Foo.java`) are
+/// served from the archive; everything else goes to the base filesystem.
+/// Archives that are not mounted stay plain files, so walking a workspace
+/// does not descend into every dependency it vendors.
+pub struct ArchiveFs {
+    base: Arc<dyn Vfs>,
+    mounts: RwLock<BTreeMap<PathBuf, Arc<MemoryFs>>>,
+}
+
+impl ArchiveFs {
+    pub fn new(base: Arc<dyn Vfs>) -> Self {
+        Self { base, mounts: RwLock::new(BTreeMap::new()) }
+    }
+
+    /// Read an archive through the base filesystem and mount it at its own
+    /// path. Returns the number of text entries; remounting reloads it.
+    pub fn mount(&self, archive: &Path) -> io::Result<usize> {
+        let bytes = self.base.read(archive)?;
+        let entries = read_entries(archive, bytes)?;
+
+        let files = MemoryFs::new();
+        for (entry, content) in entries {
+            files.insert(archive.join(entry), content);
+        }
+        let count = files.len();
+
+        self.mounts
+            .write()
+            .map_err(|_| io::Error::other("poisoned lock"))?
+            .insert(archive.to_path_buf(), Arc::new(files));
+        Ok(count)
+    }
+
+    pub fn unmount(&self, archive: &Path) -> bool {
+        self.mounts.write().map(|mut m| m.remove(archive).is_some()).unwrap_or(false)
+    }
+
+    pub fn is_mounted(&self, archive: &Path) -> bool {
+        self.mounts.read().map(|m| m.contains_key(archive)).unwrap_or(false)
+    }
+
+    /// Mounted archive paths
+    pub fn mounts(&self) -> Vec<PathBuf> {
+        self.mounts.read().map(|m| m.keys().cloned().collect()).unwrap_or_default()
+    }
+
+    /// The mounted archive containing `path` (or mounted at `path`)
+    fn mount_for(&self, path: &Path) -> Option<(PathBuf, Arc<MemoryFs>)> {
+        let mounts = self.mounts.read().ok()?;
+        path.ancestors()
+            .find_map(|ancestor| mounts.get(ancestor).map(|fs| (ancestor.to_path_buf(), Arc::clone(fs))))
+    }
+}
+
+impl Vfs for ArchiveFs {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        match self.mount_for(path) {
+            Some((_, files)) => files.read_to_string(path),
+            None => self.base.read_to_string(path),
+        }
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        match self.mount_for(path) {
+            Some((_, files)) => files.read(path),
+            None => self.base.read(path),
+        }
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        match self.mount_for(path) {
+            Some((_, files)) => files.read_dir(path),
+            None => self.base.read_dir(path),
+        }
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        match self.mount_for(path) {
+            Some((_, files)) => files.is_file(path),
+            None => self.base.is_file(path),
+        }
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        match self.mount_for(path) {
+            Some((_, files)) => files.is_dir(path),
+            None => self.base.is_dir(path),
+        }
+    }
+
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        match self.mount_for(path) {
+            Some(_) => path.to_path_buf(),
+            None => self.base.canonicalize(path),
+        }
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        match self.mount_for(path) {
+            Some(_) => None,
+            None => self.base.modified(path),
+        }
+    }
+
+    fn uri(&self, path: &Path) -> String {
+        match self.mount_for(path) {
+            Some((archive, _)) => match path.strip_prefix(&archive) {
+                Ok(entry) if !entry.as_os_str().is_empty() => archive_uri(&archive, entry),
+                _ => uri::path_to_uri(&archive),
+            },
+            None => self.base.uri(path),
+        }
+    }
+}
+
+/// Text entries of an archive as (relative path, content); binary entries are skipped
+fn read_entries(archive: &Path, bytes: Vec<u8>) -> io::Result<Vec<(PathBuf, String)>> {
+    let is_crate = archive.extension().is_some_and(|e| e.eq_ignore_ascii_case("crate"));
+    if is_crate {
+        read_tar_gz(bytes)
+    } else {
+        read_zip(bytes)
+    }
+}
+
+/// `.crate` files are gzipped tarballs
+fn read_tar_gz(bytes: Vec<u8>) -> io::Result<Vec<(PathBuf, String)>> {
+    let mut tarball = tar::Archive::new(GzDecoder::new(Cursor::new(bytes)));
+    let mut entries = Vec::new();
+    for entry in tarball.entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() || entry.size() > MAX_ENTRY_BYTES {
+            continue;
+        }
+        let path = entry.path()?.into_owned();
+        if !is_safe_entry(&path) {
+            continue;
+        }
+        let mut content = String::new();
+        if entry.read_to_string(&mut content).is_ok() {
+            entries.push((path, content));
+        }
+    }
+    Ok(entries)
+}
+
+/// `.jar` and `.whl` files are zip archives
+fn read_zip(bytes: Vec<u8>) -> io::Result<Vec<(PathBuf, String)>> {
+    let mut zip = zip::ZipArchive::new(Cursor::new(bytes)).map_err(io::Error::other)?;
+    let mut entries = Vec::new();
+    for i in 0..zip.len() {
+        let mut file = zip.by_index(i).map_err(io::Error::other)?;
+        if !file.is_file() || file.size() > MAX_ENTRY_BYTES {
+            continue;
+        }
+        let Some(path) = file.enclosed_name() else {
+            continue;
+        };
+        let mut content = String::new();
+        if file.read_to_string(&mut content).is_ok() {
+            entries.push((path, content));
+        }
+    }
+    Ok(entries)
+}
+
+/// Reject absolute entries and `..` so an entry cannot escape its archive
+fn is_safe_entry(path: &Path) -> bool {
+    path.components().all(|c| matches!(c, std::path::Component::Normal(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    fn zip_bytes(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer.start_file(*name, zip::write::SimpleFileOptions::default()).unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn crate_bytes(files: &[(&str, &str)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (name, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, name, content.as_bytes()).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    /// Base filesystem holding binary archives
+    struct Disk(BTreeMap<PathBuf, Vec<u8>>);
+
+    impl Vfs for Disk {
+        fn read_to_string(&self, path: &Path) -> io::Result<String> {
+            String::from_utf8(self.read(path)?).map_err(io::Error::other)
+        }
+
+        fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+            self.0.get(path).cloned().ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+        }
+
+        fn read_dir(&self, _path: &Path) -> io::Result<Vec<PathBuf>> {
+            Ok(self.0.keys().cloned().collect())
+        }
+
+        fn is_file(&self, path: &Path) -> bool {
+            self.0.contains_key(path)
+        }
+
+        fn is_dir(&self, path: &Path) -> bool {
+            path == Path::new("/deps")
+        }
+    }
+
+    fn archives() -> ArchiveFs {
+        let mut disk = BTreeMap::new();
+        disk.insert(
+            PathBuf::from("/deps/lib.jar"),
+            zip_bytes(&[
+                ("com/acme/Widget.java", b"package com.acme;\npublic class Widget {}\n"),
+                ("com/acme/Widget.class", &[0xca, 0xfe, 0xba, 0xbe, 0xff]),
+                ("META-INF/MANIFEST.MF", b"Manifest-Version: 1.0\n"),
+            ]),
+        );
+        disk.insert(
+            PathBuf::from("/deps/serde-1.0.0.crate"),
+            crate_bytes(&[("serde-1.0.0/src/lib.rs", "pub trait Serialize {}\n")]),
+        );
+        ArchiveFs::new(Arc::new(Disk(disk)))
+    }
+
+    #[test]
+    fn test_mount_jar() {
+        let vfs = archives();
+        let jar = Path::new("/deps/lib.jar");
+        assert!(vfs.is_file(jar));
+        assert!(!vfs.is_dir(jar));
+
+        // The class file is binary and skipped
+        assert_eq!(vfs.mount(jar).unwrap(), 2);
+        assert!(vfs.is_dir(jar));
+        assert!(vfs.is_dir(&jar.join("com/acme")));
+        assert_eq!(
+            vfs.read_dir(&jar.join("com/acme")).unwrap(),
+            vec![jar.join("com/acme/Widget.java")]
+        );
+        assert!(vfs.read_to_string(&jar.join("com/acme/Widget.java")).unwrap().contains("class Widget"));
+
+        assert!(vfs.unmount(jar));
+        assert!(!vfs.exists(&jar.join("com/acme/Widget.java")));
+    }
+
+    #[test]
+    fn test_mount_crate() {
+        let vfs = archives();
+        let krate = Path::new("/deps/serde-1.0.0.crate");
+        assert_eq!(vfs.mount(krate).unwrap(), 1);
+        let lib = krate.join("serde-1.0.0/src/lib.rs");
+        assert_eq!(vfs.read_to_string(&lib).unwrap(), "pub trait Serialize {}\n");
+        assert_eq!(vfs.uri(&lib), "archive:file:///deps/serde-1.0.0.crate!/serde-1.0.0/src/lib.rs");
+        // Outside any mount the base filesystem decides
+        assert_eq!(vfs.uri(Path::new("/deps/lib.jar")), "file:///deps/lib.jar");
+    }
+
+    #[test]
+    fn test_archive_uri_round_trip() {
+        let uri = archive_uri(Path::new("/deps/my lib.whl"), Path::new("pkg/mod.py"));
+        assert_eq!(uri, "archive:file:///deps/my%20lib.whl!/pkg/mod.py");
+        assert_eq!(
+            parse_archive_uri(&uri),
+            Some((PathBuf::from("/deps/my lib.whl"), PathBuf::from("pkg/mod.py")))
+        );
+        assert_eq!(parse_archive_uri("file:///deps/lib.jar"), None);
+        assert!(is_archive(Path::new("/deps/A.JAR")));
+        assert!(!is_archive(Path::new("/deps/a.zip")));
+    }
+}
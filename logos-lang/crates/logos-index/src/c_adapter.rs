@@ -1,35 +1,48 @@
 //! C Language Adapter
 //!
 //! Pragmatic indexer for C:
-//! - Symbols: function definitions/declarations, struct/enum/typedef, global variables (best-effort)
-//! - Imports: #include directives
+//! - Symbols: function definitions/declarations, struct/enum/typedef, global variables (best-effort),
+//!   `#define` macros
+//! - Imports: #include directives, resolved against the include
+//!   directories of a compilation database when one is set
+//! - Preprocessor branches that are compiled out are skipped: `#if 0`
+//!   always, and branches that the database's `-D` defines rule out
 //! - Exports: treated as public for non-static (best-effort)
 //! - Calls: call_expression nodes (best-effort)
 
 use crate::adapter::{AnalysisResult, CallInfo, ImportInfo, ImportItem, LanguageAdapter, SymbolBuilder, make_location};
+use crate::symbol_table::SmartSymbol;
+use crate::compile_commands::CompilationDatabase;
 use crate::symbol_table::Visibility;
-use logos_core::{Position, Range, SymbolKind};
-use std::path::Path;
-use tree_sitter::{Node, Parser, Tree};
+use crate::vfs::Vfs;
+use logos_core::{uri, Position, Range, SymbolKind};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use logos_parser::conditional::{self, CfgConfig};
+use logos_parser::{pool, LanguageId};
+use tree_sitter::{Node, Tree};
 
 pub struct CAdapter {
-    parser: std::sync::Mutex<Parser>,
+    compile_db: RwLock<Option<Arc<CompilationDatabase>>>,
+    include_paths: RwLock<Vec<PathBuf>>,
 }
 
 impl CAdapter {
     pub fn new() -> Result<Self, String> {
-        let mut parser = Parser::new();
-        parser
-            .set_language(&tree_sitter_c::LANGUAGE.into())
+        // Fail early when the grammar can't be loaded
+        pool::shared()
+            .checkout(LanguageId::C)
             .map_err(|e| format!("Failed to set C language: {}", e))?;
         Ok(Self {
-            parser: std::sync::Mutex::new(parser),
+            compile_db: RwLock::new(None),
+            include_paths: RwLock::new(Vec::new()),
         })
     }
 
     fn parse(&self, source: &str) -> Option<Tree> {
-        let mut parser = self.parser.lock().ok()?;
-        parser.parse(source, None)
+        let mut parser = pool::shared().checkout(LanguageId::C).ok()?;
+        parser.parse(source, None).ok()
     }
 }
 
@@ -48,35 +61,140 @@ impl LanguageAdapter for CAdapter {
             None => return AnalysisResult::default(),
         };
 
+        let compile_db = self.compile_db.read().ok().and_then(|db| db.clone());
         let mut ctx = AnalysisContext {
             uri: uri.to_string(),
             source,
             result: AnalysisResult::default(),
+            defines: file_defines(compile_db.as_deref(), uri),
         };
 
         analyze_node(&tree.root_node(), &mut ctx);
-        ctx.result
+        let regions = compiled_out(LanguageId::C, &tree, source, ctx.defines);
+        let mut result = ctx.result.with_utf16_columns(source);
+        drop_compiled_out(&mut result, &regions);
+        result
     }
 
-    fn resolve_import(&self, from_file: &Path, import_path: &str) -> Option<std::path::PathBuf> {
-        // For `#include "x.h"` try relative to file dir
-        if !(import_path.starts_with('"') && import_path.ends_with('"')) {
-            return None;
+    fn resolve_import(&self, vfs: &dyn Vfs, from_file: &Path, import_path: &str) -> Option<PathBuf> {
+        let compile_db = self.compile_db.read().ok().and_then(|db| db.clone());
+        let include_paths = self.include_paths.read().map(|paths| paths.clone()).unwrap_or_default();
+        resolve_include(vfs, compile_db.as_deref(), &include_paths, from_file, import_path)
+    }
+
+    fn set_compilation_database(&self, db: Option<Arc<CompilationDatabase>>) {
+        if let Ok(mut compile_db) = self.compile_db.write() {
+            *compile_db = db;
+        }
+    }
+
+    fn set_include_paths(&self, paths: Vec<PathBuf>) {
+        if let Ok(mut include_paths) = self.include_paths.write() {
+            *include_paths = paths;
         }
-        let inner = import_path.trim_matches('"');
-        let parent = from_file.parent()?;
-        let resolved = parent.join(inner);
-        if resolved.exists() {
+    }
+}
+
+/// `-D` defines of the file behind `file_uri`, if the database lists it
+pub(crate) fn file_defines<'a>(db: Option<&'a CompilationDatabase>, file_uri: &str) -> Option<&'a HashMap<String, Option<String>>> {
+    let path = uri::uri_to_path(file_uri)?;
+    db?.flags_for(&path).map(|flags| &flags.defines)
+}
+
+/// Regions of `tree` that `defines` compile out; without defines, only the
+/// literal ones such as `#if 0`
+pub(crate) fn compiled_out(
+    lang: LanguageId,
+    tree: &Tree,
+    source: &str,
+    defines: Option<&HashMap<String, Option<String>>>,
+) -> Vec<Range> {
+    let config = CfgConfig { defines: defines.cloned(), features: None };
+    conditional::inactive_regions(lang, tree, source, &config)
+}
+
+/// Drop what was found in `regions`, both in UTF-16 columns
+pub(crate) fn drop_compiled_out(result: &mut AnalysisResult, regions: &[Range]) {
+    if regions.is_empty() {
+        return;
+    }
+    let live = |range: &Range| !regions.iter().any(|region| region.contains(range.start));
+    result.symbols.retain(|symbol| live(&symbol.location.selection_range));
+    result.references.retain(|reference| live(&reference.location.range));
+    result.imports.retain(|import| live(&import.location));
+    result.exports.retain(|export| live(&export.location));
+    result.calls.retain(|call| live(&call.location));
+    result.type_relations.retain(|relation| live(&relation.location));
+}
+
+/// Index symbol of a `#define`
+pub(crate) fn macro_symbol(node: &Node, source: &str, uri: &str) -> Option<SmartSymbol> {
+    let symbol = logos_parser::c::macro_symbol(node, source)?;
+    let location = make_location(uri, symbol.range, symbol.selection_range);
+    Some(
+        SymbolBuilder::new(symbol.name, symbol.kind, location)
+            .exported(true)
+            .visibility(Visibility::Public)
+            .build(),
+    )
+}
+
+/// Resolve an `#include` operand (`"x.h"` or `<x.h>`). Quoted includes are
+/// tried next to the including file first; then both forms are tried in the
+/// include directories the compilation database gives the file, then in the
+/// configured `include_paths`.
+pub(crate) fn resolve_include(
+    vfs: &dyn Vfs,
+    db: Option<&CompilationDatabase>,
+    include_paths: &[PathBuf],
+    from_file: &Path,
+    import_path: &str,
+) -> Option<PathBuf> {
+    let (inner, quoted) = if let Some(inner) = import_path.strip_prefix('"').and_then(|p| p.strip_suffix('"')) {
+        (inner, true)
+    } else if let Some(inner) = import_path.strip_prefix('<').and_then(|p| p.strip_suffix('>')) {
+        (inner, false)
+    } else {
+        return None;
+    };
+    if quoted {
+        let resolved = from_file.parent()?.join(inner);
+        if vfs.exists(&resolved) {
             return Some(resolved);
         }
-        None
     }
+    let db_dirs = db.and_then(|db| db.flags_for(from_file)).map(|flags| flags.include_dirs.as_slice()).unwrap_or_default();
+    db_dirs
+        .iter()
+        .chain(include_paths)
+        .map(|dir| dir.join(inner))
+        .find(|candidate| vfs.is_file(candidate))
+}
+
+/// Children of a `preproc_ifdef` to analyze. A branch is skipped only when
+/// `defines` settles it: `#ifdef X` with `X` defined drops the `#else`
+/// side, `#ifndef X` keeps only it. Without defines every branch is live.
+pub(crate) fn live_ifdef_children<'a>(node: &Node<'a>, source: &str, defines: Option<&HashMap<String, Option<String>>>) -> Vec<Node<'a>> {
+    let children: Vec<Node<'a>> = (0..node.named_child_count()).filter_map(|i| node.named_child(i)).collect();
+    let defined = node
+        .child_by_field_name("name")
+        .is_some_and(|name| defines.is_some_and(|d| d.contains_key(&source[name.byte_range()])));
+    if !defined {
+        return children;
+    }
+    let alternative = node.child_by_field_name("alternative").map(|n| n.id());
+    let is_ifndef = node.child(0).is_some_and(|directive| directive.kind() == "#ifndef");
+    children
+        .into_iter()
+        .filter(|child| (Some(child.id()) == alternative) == is_ifndef)
+        .collect()
 }
 
 struct AnalysisContext<'a> {
     uri: String,
     source: &'a str,
     result: AnalysisResult,
+    defines: Option<&'a HashMap<String, Option<String>>>,
 }
 
 impl<'a> AnalysisContext<'a> {
@@ -91,6 +209,14 @@ fn analyze_node(node: &Node, ctx: &mut AnalysisContext) {
         "function_definition" => analyze_function(node, ctx),
         "declaration" => analyze_declaration(node, ctx),
         "call_expression" => analyze_call(node, ctx),
+        "preproc_def" | "preproc_function_def" => {
+            ctx.result.symbols.extend(macro_symbol(node, ctx.source, &ctx.uri));
+        }
+        "preproc_ifdef" => {
+            for child in live_ifdef_children(node, ctx.source, ctx.defines) {
+                analyze_node(&child, ctx);
+            }
+        }
         _ => {
             for i in 0..node.named_child_count() {
                 if let Some(child) = node.named_child(i) {
@@ -268,9 +394,54 @@ int greet(User* u) {
 }
 "#;
         let result = adapter.analyze("file:///test.c", src);
-        assert!(result.imports.len() >= 1);
+        assert!(!result.imports.is_empty());
         assert!(result.symbols.iter().any(|s| s.name == "greet"));
-        assert!(result.calls.len() >= 1);
+        assert!(!result.calls.is_empty());
     }
-}
 
+    #[test]
+    fn c_includes_follow_compilation_database() {
+        let adapter = CAdapter::new().unwrap();
+        let db = CompilationDatabase::from_json(
+            r#"[{ "directory": "/proj", "file": "src/main.c", "arguments": ["cc", "-Iinclude", "-DUSE_A", "-c", "src/main.c"] }]"#,
+        )
+        .unwrap();
+        adapter.set_compilation_database(Some(Arc::new(db)));
+
+        let src = "#include <util.h>\n#ifdef USE_A\n#include \"a.h\"\n#else\n#include \"b.h\"\n#endif\n";
+        let result = adapter.analyze("file:///proj/src/main.c", src);
+        let includes: Vec<_> = result.imports.iter().map(|i| i.module_path.as_str()).collect();
+        assert_eq!(includes, vec!["<util.h>", "\"a.h\""]);
+
+        let vfs = crate::vfs::MemoryFs::new();
+        vfs.insert("/proj/include/util.h", "int util(void);");
+        let main = Path::new("/proj/src/main.c");
+        assert_eq!(adapter.resolve_import(&vfs, main, "<util.h>"), Some(PathBuf::from("/proj/include/util.h")));
+        assert_eq!(adapter.resolve_import(&vfs, main, "<stdio.h>"), None);
+
+        // Without a database every branch counts
+        adapter.set_compilation_database(None);
+        assert_eq!(adapter.analyze("file:///proj/src/main.c", src).imports.len(), 3);
+    }
+
+    #[test]
+    fn c_macros_and_dead_branches() {
+        let adapter = CAdapter::new().unwrap();
+        let src = "#define LIMIT 8
+#define MIN(a, b) ((a) < (b) ? (a) : (b))
+#if 0
+#include \"old.h\"
+int dead(void) { return legacy(); }
+#endif
+int live(void) { return MIN(1, 2); }
+";
+        let result = adapter.analyze("file:///test.c", src);
+        let symbols: Vec<_> = result.symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            symbols,
+            vec![("LIMIT", SymbolKind::Constant), ("MIN", SymbolKind::Function), ("live", SymbolKind::Function)]
+        );
+        assert!(result.imports.is_empty());
+        assert!(result.calls.iter().all(|call| call.callee_name != "legacy"));
+    }
+}
@@ -1,12 +1,16 @@
 //! TODO/FIXME comment scanner for code analysis
 //!
 //! Scans source code for TODO, FIXME, HACK, XXX, NOTE and other comment markers.
+//! An assignee can follow the marker in parentheses, in any comment style:
+//! `TODO(alice):`, `FIXME(@bob)!:`, `/* HACK (carol): ... */`.
 
 use logos_core::Range;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use crate::glob::PathGlob;
+
 /// The kind of TODO comment marker
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -119,7 +123,8 @@ impl CommentScanner {
 
         // Build the regex pattern
         // Matches: // TODO: text, /* TODO: text */, # TODO: text, -- TODO: text
-        // Also matches: TODO(author): text, TODO!: text (urgent)
+        // Also matches: TODO(author): text, TODO!: text (urgent), with the
+        // urgency marker on either side of the author
         let keywords: Vec<&str> = kind_map.keys().map(|s| s.as_str()).collect();
         let keywords_pattern = keywords.join("|");
 
@@ -127,11 +132,12 @@ impl CommentScanner {
         // (?://|/\*|#|--|;)?\s*  - Optional comment prefix
         // (TODO|FIXME|...)       - The keyword
         // (!)?                   - Optional urgency marker
-        // (?:\(([^)]+)\))?       - Optional (author) group
+        // (?:\s?\(([^)]+)\))?    - Optional (author) group
+        // (!)?                   - Urgency marker after the author
         // [:\s]+                 - Colon or whitespace separator
         // (.*)                   - The TODO text
         let pattern_str = format!(
-            r"(?://|/\*|#|--|;)?\s*\b({})\b(!)?(?:\(([^)]+)\))?[:\s]+(.*)$",
+            r"(?://|/\*|#|--|;)?\s*\b({})\b(!)?(?:\s?\(([^)]+)\))?(!)?[:\s]+(.*)$",
             keywords_pattern
         );
 
@@ -147,14 +153,17 @@ impl CommentScanner {
         for (line_idx, line) in source.lines().enumerate() {
             if let Some(captures) = self.pattern.captures(line) {
                 let keyword = captures.get(1).map(|m| m.as_str().to_uppercase());
-                let urgent = captures.get(2).is_some();
-                let author = captures.get(3).map(|m| m.as_str().to_string());
-                let text = captures.get(4).map(|m| m.as_str().trim().to_string()).unwrap_or_default();
+                let urgent = captures.get(2).is_some() || captures.get(4).is_some();
+                let author = captures
+                    .get(3)
+                    .map(|m| m.as_str().trim().trim_start_matches('@').to_string())
+                    .filter(|a| !a.is_empty());
+                let text = captures.get(5).map(|m| trim_comment_end(m.as_str())).unwrap_or_default();
 
                 if let Some(keyword) = keyword {
                     if let Some(&kind) = self.kind_map.get(&keyword) {
                         let match_start = captures.get(1).unwrap().start();
-                        let match_end = captures.get(4).map(|m| m.end()).unwrap_or(captures.get(1).unwrap().end());
+                        let match_end = captures.get(5).map(|m| m.end()).unwrap_or(captures.get(1).unwrap().end());
 
                         let priority = if urgent {
                             (kind.priority() + 1).min(5)
@@ -200,6 +209,56 @@ impl CommentScanner {
     }
 }
 
+/// Strip surrounding space and a block comment terminator from TODO text
+fn trim_comment_end(text: &str) -> String {
+    let text = text.trim();
+    ["*/", "-->", "*)"]
+        .iter()
+        .find_map(|end| text.strip_suffix(end))
+        .unwrap_or(text)
+        .trim_end()
+        .to_string()
+}
+
+/// Criteria for selecting TODO items; empty criteria match everything
+#[derive(Debug, Clone, Default)]
+pub struct TodoFilter {
+    /// Assignee, compared case-insensitively
+    pub author: Option<String>,
+    /// Accepted kinds; empty accepts all
+    pub kinds: Vec<TodoKind>,
+    /// Lowest priority to include
+    pub min_priority: Option<u8>,
+    /// Glob over the document path: `*` and `?` stay within a path
+    /// segment, `**` crosses segments. Relative patterns match any suffix
+    /// of whole segments.
+    pub path: Option<String>,
+}
+
+impl TodoFilter {
+    fn path_glob(&self) -> Option<PathGlob> {
+        PathGlob::new(self.path.as_deref()?)
+    }
+
+    fn accepts(&self, path: Option<&PathGlob>, uri: &str, item: &TodoItem) -> bool {
+        if !self.kinds.is_empty() && !self.kinds.contains(&item.kind) {
+            return false;
+        }
+        if self.min_priority.is_some_and(|min| item.priority < min) {
+            return false;
+        }
+        if let Some(author) = &self.author {
+            if !item.author.as_ref().is_some_and(|a| a.eq_ignore_ascii_case(author)) {
+                return false;
+            }
+        }
+        match path {
+            Some(glob) => glob.is_match(uri.strip_prefix("file://").unwrap_or(uri)),
+            None => true,
+        }
+    }
+}
+
 /// Index for storing and querying TODO items across a project
 #[derive(Debug, Default)]
 pub struct TodoIndex {
@@ -236,6 +295,22 @@ impl TodoIndex {
         self.by_document.remove(uri);
     }
 
+    /// Move TODOs of documents affected by renaming `from` to `to` (a file
+    /// or a directory). Returns the number of documents moved.
+    pub fn rename_documents(&mut self, from: &str, to: &str) -> usize {
+        let moved: Vec<(String, String)> = self
+            .by_document
+            .keys()
+            .filter_map(|uri| crate::rebase_uri(uri, from, to).map(|new| (uri.clone(), new)))
+            .collect();
+        for (old, new) in &moved {
+            if let Some(items) = self.by_document.remove(old) {
+                self.by_document.insert(new.clone(), items);
+            }
+        }
+        moved.len()
+    }
+
     /// Get all TODOs for a specific document
     pub fn get_document_todos(&self, uri: &str) -> &[TodoItem] {
         self.by_document.get(uri).map(|v| v.as_slice()).unwrap_or(&[])
@@ -249,11 +324,11 @@ impl TodoIndex {
                 todos.push((uri.as_str(), item));
             }
         }
-        // Sort by priority (descending) then by file and line
+        // Sort by priority (descending) then by location
         todos.sort_by(|a, b| {
             b.1.priority.cmp(&a.1.priority)
                 .then_with(|| a.0.cmp(b.0))
-                .then_with(|| a.1.line.cmp(&b.1.line))
+                .then_with(|| a.1.range.cmp(&b.1.range))
         });
         todos
     }
@@ -266,6 +341,15 @@ impl TodoIndex {
             .collect()
     }
 
+    /// TODOs matching `filter`, in the order of `get_all_todos`
+    pub fn query(&self, filter: &TodoFilter) -> Vec<(&str, &TodoItem)> {
+        let path = filter.path_glob();
+        self.get_all_todos()
+            .into_iter()
+            .filter(|(uri, item)| filter.accepts(path.as_ref(), uri, item))
+            .collect()
+    }
+
     /// Get total count of TODOs
     pub fn todo_count(&self) -> usize {
         self.by_document.values().map(|v| v.len()).sum()
@@ -281,6 +365,35 @@ impl TodoIndex {
         }
         counts
     }
+
+    /// Count of assigned TODOs per author; unassigned ones are left out
+    pub fn count_by_author(&self) -> HashMap<String, usize> {
+        let mut counts = HashMap::new();
+        for item in self.by_document.values().flatten() {
+            if let Some(author) = &item.author {
+                *counts.entry(author.clone()).or_insert(0) += 1;
+            }
+        }
+        counts
+    }
+
+    /// Rough estimate of memory held by the index, in bytes
+    pub fn estimated_memory_bytes(&self) -> usize {
+        self.by_document
+            .iter()
+            .map(|(uri, items)| {
+                uri.len()
+                    + items
+                        .iter()
+                        .map(|t| {
+                            std::mem::size_of::<TodoItem>()
+                                + t.text.len()
+                                + t.author.as_ref().map_or(0, |a| a.len())
+                        })
+                        .sum::<usize>()
+            })
+            .sum()
+    }
 }
 
 #[cfg(test)]
@@ -313,6 +426,17 @@ fn main() {
         assert_eq!(todos[0].author, Some("john".to_string()));
     }
 
+    #[test]
+    fn test_author_forms() {
+        let scanner = CommentScanner::default();
+        let source = "# FIXME(@bob)!: Crashes on empty input\n/* HACK (carol): Works around #12 */\n-- TODO( dave ) tune the index";
+        let todos = scanner.scan_file(source, "test.sql");
+        let authors: Vec<_> = todos.iter().map(|t| t.author.as_deref()).collect();
+        assert_eq!(authors, vec![Some("bob"), Some("carol"), Some("dave")]);
+        assert!(todos[0].priority > TodoKind::Fixme.priority());
+        assert_eq!(todos[1].text, "Works around #12");
+    }
+
     #[test]
     fn test_scan_urgent() {
         let scanner = CommentScanner::default();
@@ -341,4 +465,45 @@ fn main() {
         assert_eq!(index.get_document_todos("a.rs").len(), 2);
         assert_eq!(index.get_document_todos("b.rs").len(), 1);
     }
+
+    #[test]
+    fn test_todo_index_query() {
+        let mut index = TodoIndex::new();
+        index.index_document("file:///repo/src/a.rs", "// TODO(alice): First\n// FIXME(Bob): Second");
+        index.index_document("file:///repo/src/net/b.rs", "// BUG(alice): Third\n// NOTE: Fourth");
+        index.index_document("file:///repo/tests/c.py", "# TODO(alice): Fifth");
+
+        let texts = |filter: &TodoFilter| -> Vec<String> {
+            index.query(filter).iter().map(|(_, t)| t.text.clone()).collect()
+        };
+        let by_alice = TodoFilter { author: Some("Alice".to_string()), ..Default::default() };
+        assert_eq!(texts(&by_alice), vec!["Third", "First", "Fifth"]);
+
+        let in_src = TodoFilter { path: Some("src/**/*.rs".to_string()), min_priority: Some(3), ..Default::default() };
+        assert_eq!(texts(&in_src), vec!["Third", "Second"]);
+
+        let todos_in_tests = TodoFilter {
+            kinds: vec![TodoKind::Todo],
+            path: Some("/repo/tests/*".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(texts(&todos_in_tests), vec!["Fifth"]);
+
+        let counts = index.count_by_author();
+        assert_eq!(counts.get("alice"), Some(&3));
+        assert_eq!(counts.get("Bob"), Some(&1));
+        assert_eq!(counts.len(), 2);
+    }
+
+    #[test]
+    fn test_todo_index_rename_directory() {
+        let mut index = TodoIndex::new();
+        index.index_document("file:///src/a.rs", "// TODO: First");
+        index.index_document("file:///src2/b.rs", "// NOTE: Second");
+
+        assert_eq!(index.rename_documents("file:///src", "file:///lib"), 1);
+        assert!(index.get_document_todos("file:///src/a.rs").is_empty());
+        assert_eq!(index.get_document_todos("file:///lib/a.rs").len(), 1);
+        assert_eq!(index.get_document_todos("file:///src2/b.rs").len(), 1);
+    }
 }
@@ -0,0 +1,206 @@
+//! Compilation databases (`compile_commands.json`)
+//!
+//! Build systems (CMake, Bear, Meson, ...) record the compiler invocation of
+//! every translation unit. The C and C++ adapters use the include
+//! directories and `-D` defines of a file's invocation to resolve
+//! `#include`s that live outside the including file's directory. Headers
+//! have no entry of their own and borrow the flags of the nearest
+//! translation unit.
+
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+use serde::Deserialize;
+
+/// Preprocessor flags of one translation unit
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompileFlags {
+    /// `-I`, `-iquote` and `-isystem` directories, in command-line order
+    pub include_dirs: Vec<PathBuf>,
+    /// `-D` defines with their value, if any
+    pub defines: HashMap<String, Option<String>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Entry {
+    directory: String,
+    file: String,
+    #[serde(default)]
+    arguments: Option<Vec<String>>,
+    #[serde(default)]
+    command: Option<String>,
+}
+
+/// Flags of every file listed in a compilation database
+#[derive(Debug, Clone, Default)]
+pub struct CompilationDatabase {
+    files: HashMap<PathBuf, CompileFlags>,
+}
+
+impl CompilationDatabase {
+    /// Parse the contents of a `compile_commands.json`
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let entries: Vec<Entry> =
+            serde_json::from_str(json).map_err(|e| format!("Invalid compilation database: {}", e))?;
+        let mut files = HashMap::new();
+        for entry in entries {
+            let directory = PathBuf::from(&entry.directory);
+            let arguments = match (entry.arguments, entry.command) {
+                (Some(arguments), _) => arguments,
+                (None, Some(command)) => split_command(&command),
+                (None, None) => continue,
+            };
+            let file = normalize(&directory.join(&entry.file));
+            // The first entry of a file wins, as with clangd
+            files.entry(file).or_insert_with(|| parse_flags(&arguments, &directory));
+        }
+        Ok(Self { files })
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Flags for `file`: its own entry, or else that of the listed file
+    /// sharing the longest directory prefix with it (for headers)
+    pub fn flags_for(&self, file: &Path) -> Option<&CompileFlags> {
+        let file = normalize(file);
+        if let Some(flags) = self.files.get(&file) {
+            return Some(flags);
+        }
+        let dir = file.parent()?;
+        self.files
+            .iter()
+            .map(|(path, flags)| (shared_components(dir, path), path, flags))
+            .filter(|(shared, _, _)| *shared > 1)
+            // Ties go to the smallest path so the choice is stable
+            .max_by(|a, b| a.0.cmp(&b.0).then_with(|| b.1.cmp(a.1)))
+            .map(|(_, _, flags)| flags)
+    }
+}
+
+fn parse_flags(arguments: &[String], directory: &Path) -> CompileFlags {
+    let mut flags = CompileFlags::default();
+    let mut arguments = arguments.iter().skip(1);
+    while let Some(argument) = arguments.next() {
+        let mut value = |flag: &str| -> Option<String> {
+            let rest = argument.strip_prefix(flag)?;
+            if rest.is_empty() {
+                arguments.next().cloned()
+            } else {
+                Some(rest.to_string())
+            }
+        };
+        if let Some(dir) = value("-isystem").or_else(|| value("-iquote")).or_else(|| value("-I")) {
+            flags.include_dirs.push(normalize(&directory.join(dir)));
+        } else if let Some(define) = value("-D") {
+            match define.split_once('=') {
+                Some((name, value)) => flags.defines.insert(name.to_string(), Some(value.to_string())),
+                None => flags.defines.insert(define, None),
+            };
+        }
+    }
+    flags
+}
+
+/// Split a shell command line on whitespace, honouring quotes and
+/// backslash escapes
+fn split_command(command: &str) -> Vec<String> {
+    let mut arguments = Vec::new();
+    let mut current = String::new();
+    let mut in_argument = false;
+    let mut quote = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"') | None, '\\') => {
+                current.extend(chars.next());
+                in_argument = true;
+            }
+            (Some(_), c) => current.push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_argument = true;
+            }
+            (None, c) if c.is_whitespace() => {
+                if in_argument {
+                    arguments.push(std::mem::take(&mut current));
+                    in_argument = false;
+                }
+            }
+            (None, c) => {
+                current.push(c);
+                in_argument = true;
+            }
+        }
+    }
+    if in_argument {
+        arguments.push(current);
+    }
+    arguments
+}
+
+/// Resolve `.` and `..` without touching the disk; the files of a
+/// database may not exist where it's read
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+fn shared_components(a: &Path, b: &Path) -> usize {
+    a.components().zip(b.components()).take_while(|(x, y)| x == y).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arguments_and_command_forms() {
+        let json = r#"[
+            { "directory": "/proj/build", "file": "../src/main.c",
+              "arguments": ["cc", "-I../include", "-isystem", "/opt/sdk/include", "-DDEBUG", "-D", "LEVEL=2", "-c", "../src/main.c"] },
+            { "directory": "/proj", "file": "lib/util.c",
+              "command": "cc -I \"third party/inc\" -DNAME=\\\"x\\\" -c lib/util.c" }
+        ]"#;
+        let db = CompilationDatabase::from_json(json).unwrap();
+        assert_eq!(db.len(), 2);
+
+        let main = db.flags_for(Path::new("/proj/src/main.c")).unwrap();
+        assert_eq!(main.include_dirs, vec![PathBuf::from("/proj/include"), PathBuf::from("/opt/sdk/include")]);
+        assert_eq!(main.defines.get("DEBUG"), Some(&None));
+        assert_eq!(main.defines.get("LEVEL"), Some(&Some("2".to_string())));
+
+        let util = db.flags_for(Path::new("/proj/lib/util.c")).unwrap();
+        assert_eq!(util.include_dirs, vec![PathBuf::from("/proj/third party/inc")]);
+        assert_eq!(util.defines.get("NAME"), Some(&Some("\"x\"".to_string())));
+
+        assert!(CompilationDatabase::from_json("{}").is_err());
+    }
+
+    #[test]
+    fn test_headers_borrow_nearest_flags() {
+        let json = r#"[
+            { "directory": "/proj", "file": "src/a/a.c", "arguments": ["cc", "-Ia"] },
+            { "directory": "/proj", "file": "src/b/b.c", "arguments": ["cc", "-Ib"] }
+        ]"#;
+        let db = CompilationDatabase::from_json(json).unwrap();
+        let header = db.flags_for(Path::new("/proj/src/b/b.h")).unwrap();
+        assert_eq!(header.include_dirs, vec![PathBuf::from("/proj/b")]);
+        assert!(db.flags_for(Path::new("/elsewhere/x.h")).is_none());
+    }
+}
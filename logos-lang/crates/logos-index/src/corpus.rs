@@ -0,0 +1,164 @@
+//! Golden corpus for the symbol extractors and index adapters
+//!
+//! `corpus/<language>/` holds small source files, each with a snapshot
+//! `<file>.yaml` next to it listing what the outline extractor of the
+//! parser and the index adapter of its language find in it: symbols,
+//! imports, calls and type relations. Every file is checked against its
+//! snapshot, so a change in grammar handling shows up as a snapshot diff
+//! instead of as missing results in the editor.
+//!
+//! After an intended change, rerun with `UPDATE_CORPUS=1` to rewrite the
+//! snapshots and review their diff. A new file starts without a snapshot
+//! and fails until one is written.
+
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use logos_core::Symbol;
+use logos_parser::{pool, LanguageId};
+
+use crate::adapter::{AnalysisResult, LanguageAdapter};
+use crate::{CAdapter, CppAdapter, GoAdapter, JavaAdapter, KotlinAdapter, PythonAdapter, RustAdapter, TypeScriptAdapter};
+
+const CORPUS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/corpus");
+
+fn adapters() -> Vec<Box<dyn LanguageAdapter>> {
+    vec![
+        Box::new(TypeScriptAdapter::new().unwrap()),
+        Box::new(PythonAdapter::new().unwrap()),
+        Box::new(GoAdapter::new().unwrap()),
+        Box::new(RustAdapter::new().unwrap()),
+        Box::new(CAdapter::new().unwrap()),
+        Box::new(CppAdapter::new().unwrap()),
+        Box::new(JavaAdapter::new().unwrap()),
+        Box::new(KotlinAdapter::new().unwrap()),
+    ]
+}
+
+/// Source files of the corpus, in path order
+fn corpus_files() -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for dir in fs::read_dir(CORPUS_DIR).expect("corpus directory").flatten() {
+        let Ok(entries) = fs::read_dir(dir.path()) else {
+            continue;
+        };
+        files.extend(
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext != "yaml")),
+        );
+    }
+    files.sort();
+    files
+}
+
+fn snapshot_path(file: &Path) -> PathBuf {
+    let mut path = file.as_os_str().to_owned();
+    path.push(".yaml");
+    PathBuf::from(path)
+}
+
+/// What the extractor and the adapter find in `file`, as YAML
+fn render(file: &Path, source: &str, adapters: &[Box<dyn LanguageAdapter>]) -> String {
+    let mut out = String::new();
+
+    let lang = file.extension().and_then(|ext| ext.to_str()).and_then(LanguageId::from_extension);
+    if let Some(lang) = lang {
+        let tree = pool::shared()
+            .checkout(lang)
+            .and_then(|mut parser| parser.parse(source, None))
+            .unwrap_or_else(|e| panic!("{}: {}", file.display(), e));
+        let symbols = logos_parser::extract_symbols(lang, &tree, source);
+        if symbols.is_empty() {
+            out.push_str("symbols: []\n");
+        } else {
+            out.push_str("symbols:\n");
+            write_symbols(&mut out, 2, &symbols);
+        }
+    }
+
+    if let Some(adapter) = adapters.iter().find(|adapter| adapter.can_handle(file)) {
+        let result = adapter.analyze(&format!("file://{}", file.display()), source);
+        out.push_str("index:\n");
+        write_index(&mut out, &result);
+    }
+    out
+}
+
+/// `- <kind> <name> (line <n>)`, with the children nested under it
+fn write_symbols(out: &mut String, indent: usize, symbols: &[Symbol]) {
+    for symbol in symbols {
+        let line = symbol.selection_range.start.line + 1;
+        let _ = write!(out, "{:indent$}- {:?} {} (line {})", "", symbol.kind, symbol.name, line);
+        if symbol.children.is_empty() {
+            out.push('\n');
+        } else {
+            out.push_str(":\n");
+            write_symbols(out, indent + 4, &symbol.children);
+        }
+    }
+}
+
+fn write_index(out: &mut String, result: &AnalysisResult) {
+    let symbols: Vec<_> =
+        result.symbols.iter().map(|symbol| format!("{:?} {}", symbol.kind, symbol.qualified_name)).collect();
+    let imports: Vec<_> = result.imports.iter().map(|import| import.module_path.clone()).collect();
+    let calls: Vec<_> = result
+        .calls
+        .iter()
+        .map(|call| call.qualified_name.clone().unwrap_or_else(|| call.callee_name.clone()))
+        .collect();
+    let relations: Vec<_> = result
+        .type_relations
+        .iter()
+        .map(|relation| {
+            let verb = if relation.is_implements { "implements" } else { "extends" };
+            format!("{} {} {}", relation.child_name, verb, relation.parent_name)
+        })
+        .collect();
+
+    write_list(out, "symbols", &symbols);
+    write_list(out, "imports", &imports);
+    write_list(out, "calls", &calls);
+    write_list(out, "relations", &relations);
+}
+
+fn write_list(out: &mut String, key: &str, items: &[String]) {
+    if items.is_empty() {
+        let _ = writeln!(out, "  {}: []", key);
+        return;
+    }
+    let _ = writeln!(out, "  {}:", key);
+    for item in items {
+        let _ = writeln!(out, "    - {}", item);
+    }
+}
+
+#[test]
+fn corpus_matches_snapshots() {
+    let update = std::env::var_os("UPDATE_CORPUS").is_some();
+    let adapters = adapters();
+    let files = corpus_files();
+    assert!(!files.is_empty(), "no corpus files in {}", CORPUS_DIR);
+
+    let mut mismatches = Vec::new();
+    for file in files {
+        let source = fs::read_to_string(&file).unwrap();
+        let actual = render(&file, &source, &adapters);
+        let snapshot = snapshot_path(&file);
+        if update {
+            fs::write(&snapshot, &actual).unwrap();
+            continue;
+        }
+        if fs::read_to_string(&snapshot).ok().as_deref() != Some(actual.as_str()) {
+            mismatches.push(format!("{}:\n{}", file.display(), actual));
+        }
+    }
+    assert!(
+        mismatches.is_empty(),
+        "corpus output differs from the snapshots; rerun with UPDATE_CORPUS=1 to accept it\n\n{}",
+        mismatches.join("\n")
+    );
+}
@@ -0,0 +1,315 @@
+//! Test coverage reports
+//!
+//! Reads line coverage from lcov tracefiles, coverage.py JSON reports,
+//! JaCoCo XML reports and Go cover profiles. Reports name files relative to
+//! different roots (a source set, a module path, the working directory of
+//! the test run), so files are matched on their trailing path components.
+
+use logos_core::Range;
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+/// Report formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CoverageFormat {
+    Lcov,
+    /// Output of `coverage json`
+    CoveragePy,
+    JaCoCo,
+    /// Output of `go test -coverprofile`
+    GoCover,
+}
+
+impl CoverageFormat {
+    /// Guess the format of a report from its content
+    pub fn detect(content: &str) -> Option<Self> {
+        let start = content.trim_start();
+        if start.starts_with("mode:") {
+            Some(Self::GoCover)
+        } else if start.starts_with('{') {
+            Some(Self::CoveragePy)
+        } else if start.starts_with('<') && content.contains("<report") {
+            Some(Self::JaCoCo)
+        } else if content.lines().any(|line| line.starts_with("SF:")) {
+            Some(Self::Lcov)
+        } else {
+            None
+        }
+    }
+}
+
+/// Hit counts of the executable lines of one file
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileCoverage {
+    /// Hits per 0-based line; lines without an entry aren't executable
+    pub lines: BTreeMap<u32, u64>,
+}
+
+/// Covered and executable lines of a range
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CoverageSummary {
+    pub covered: u32,
+    pub total: u32,
+}
+
+impl CoverageSummary {
+    /// Covered share in percent, `None` without executable lines
+    pub fn percent(&self) -> Option<f64> {
+        (self.total > 0).then(|| self.covered as f64 * 100.0 / self.total as f64)
+    }
+}
+
+impl FileCoverage {
+    fn hit(&mut self, line: u32, hits: u64) {
+        let entry = self.lines.entry(line).or_insert(0);
+        *entry = (*entry).max(hits);
+    }
+
+    /// Summary of the lines within `range`
+    pub fn summary(&self, range: Range) -> CoverageSummary {
+        let mut summary = CoverageSummary::default();
+        for (_, hits) in self.lines.range(range.start.line..=range.end.line) {
+            summary.total += 1;
+            if *hits > 0 {
+                summary.covered += 1;
+            }
+        }
+        summary
+    }
+
+    /// Summary of the whole file
+    pub fn total(&self) -> CoverageSummary {
+        let covered = self.lines.values().filter(|hits| **hits > 0).count() as u32;
+        CoverageSummary { covered, total: self.lines.len() as u32 }
+    }
+}
+
+/// Line coverage of the files named in one or more reports
+#[derive(Debug, Clone, Default)]
+pub struct Coverage {
+    files: HashMap<PathBuf, FileCoverage>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parse a report, detecting its format
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let format = CoverageFormat::detect(content).ok_or("Unrecognized coverage report format")?;
+        Self::parse_as(format, content)
+    }
+
+    pub fn parse_as(format: CoverageFormat, content: &str) -> Result<Self, String> {
+        let mut coverage = Self::new();
+        match format {
+            CoverageFormat::Lcov => coverage.read_lcov(content),
+            CoverageFormat::CoveragePy => coverage.read_coverage_py(content)?,
+            CoverageFormat::JaCoCo => coverage.read_jacoco(content),
+            CoverageFormat::GoCover => coverage.read_go_cover(content)?,
+        }
+        Ok(coverage)
+    }
+
+    /// Add the files of `other`; lines covered by both keep the higher count
+    pub fn merge(&mut self, other: Coverage) {
+        for (path, file) in other.files {
+            let entry = self.files.entry(path).or_default();
+            for (line, hits) in file.lines {
+                entry.hit(line, hits);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Coverage of the file at `path`: the report entry sharing the most
+    /// trailing path components with it. A tie between entries leaves the
+    /// file unmatched rather than guessing.
+    pub fn file(&self, path: &Path) -> Option<&FileCoverage> {
+        best_suffix_match(path, self.files.iter().map(|(report_path, file)| (report_path.as_path(), file)))
+    }
+
+    fn read_lcov(&mut self, content: &str) {
+        let mut current: Option<&mut FileCoverage> = None;
+        for line in content.lines() {
+            if let Some(path) = line.strip_prefix("SF:") {
+                current = Some(self.files.entry(PathBuf::from(path.trim())).or_default());
+            } else if let Some(data) = line.strip_prefix("DA:") {
+                let mut fields = data.split(',');
+                let (Some(Ok(number)), Some(Ok(hits))) = (
+                    fields.next().map(|f| f.trim().parse::<u32>()),
+                    fields.next().map(|f| f.trim().parse::<u64>()),
+                ) else {
+                    continue;
+                };
+                if let (Some(file), Some(line)) = (current.as_deref_mut(), number.checked_sub(1)) {
+                    file.hit(line, hits);
+                }
+            } else if line.trim() == "end_of_record" {
+                current = None;
+            }
+        }
+    }
+
+    fn read_coverage_py(&mut self, content: &str) -> Result<(), String> {
+        let report: serde_json::Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+        let files = report.get("files").and_then(|f| f.as_object()).ok_or("Missing \"files\" in coverage.py report")?;
+        for (path, data) in files {
+            let file = self.files.entry(PathBuf::from(path)).or_default();
+            let lines = |key: &str| {
+                data.get(key)
+                    .and_then(|l| l.as_array())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|n| n.as_u64())
+                    .filter_map(|n| (n as u32).checked_sub(1))
+                    .collect::<Vec<_>>()
+            };
+            for line in lines("missing_lines") {
+                file.hit(line, 0);
+            }
+            for line in lines("executed_lines") {
+                file.hit(line, 1);
+            }
+        }
+        Ok(())
+    }
+
+    /// JaCoCo counts instructions, not executions: a line with covered
+    /// instructions gets one hit
+    fn read_jacoco(&mut self, content: &str) {
+        let (Ok(tag), Ok(attribute)) = (
+            Regex::new(r"<(package|sourcefile|line)\s([^>]*)>"),
+            Regex::new(r#"(\w+)="([^"]*)""#),
+        ) else {
+            return;
+        };
+        let mut package = String::new();
+        let mut current: Option<PathBuf> = None;
+        for captures in tag.captures_iter(content) {
+            let attributes: HashMap<&str, &str> = attribute
+                .captures_iter(captures.get(2).map_or("", |m| m.as_str()))
+                .filter_map(|a| Some((a.get(1)?.as_str(), a.get(2)?.as_str())))
+                .collect();
+            match &captures[1] {
+                "package" => package = attributes.get("name").copied().unwrap_or_default().to_string(),
+                "sourcefile" => {
+                    current = attributes.get("name").map(|name| Path::new(&package).join(name));
+                }
+                _ => {
+                    let (Some(path), Some(number)) = (&current, attributes.get("nr").and_then(|n| n.parse::<u32>().ok()))
+                    else {
+                        continue;
+                    };
+                    let covered = attributes.get("ci").and_then(|c| c.parse::<u64>().ok()).unwrap_or(0);
+                    if let Some(line) = number.checked_sub(1) {
+                        self.files.entry(path.clone()).or_default().hit(line, u64::from(covered > 0));
+                    }
+                }
+            }
+        }
+    }
+
+    fn read_go_cover(&mut self, content: &str) -> Result<(), String> {
+        // path/file.go:12.34,15.2 3 1
+        let block = Regex::new(r"^(.+):(\d+)\.\d+,(\d+)\.\d+ \d+ (\d+)$").map_err(|e| e.to_string())?;
+        for line in content.lines().skip_while(|line| !line.starts_with("mode:")).skip(1) {
+            let Some(captures) = block.captures(line.trim()) else {
+                continue;
+            };
+            let (Ok(start), Ok(end), Ok(hits)) =
+                (captures[2].parse::<u32>(), captures[3].parse::<u32>(), captures[4].parse::<u64>())
+            else {
+                continue;
+            };
+            let file = self.files.entry(PathBuf::from(&captures[1])).or_default();
+            for number in start.max(1)..=end {
+                file.hit(number - 1, hits);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The candidate sharing the most trailing path components with `path`;
+/// `None` when no candidate shares one or the best match is tied
+pub(crate) fn best_suffix_match<'a, T>(path: &Path, candidates: impl IntoIterator<Item = (&'a Path, T)>) -> Option<T> {
+    let mut best: Option<(usize, T)> = None;
+    let mut tied = false;
+    for (candidate, value) in candidates {
+        let common = path
+            .components()
+            .rev()
+            .zip(candidate.components().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+        if common == 0 {
+            continue;
+        }
+        match best {
+            Some((score, _)) if common < score => {}
+            Some((score, _)) if common == score => tied = true,
+            _ => {
+                best = Some((common, value));
+                tied = false;
+            }
+        }
+    }
+    if tied {
+        return None;
+    }
+    best.map(|(_, value)| value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(coverage: &Coverage, path: &str) -> Vec<(u32, u64)> {
+        coverage.file(Path::new(path)).unwrap().lines.iter().map(|(l, h)| (*l, *h)).collect()
+    }
+
+    #[test]
+    fn test_lcov_and_summary() {
+        let report = "TN:\nSF:/repo/src/lib.rs\nDA:1,3\nDA:2,0\nDA:4,1,abc\nend_of_record\n";
+        let coverage = Coverage::parse(report).unwrap();
+        assert_eq!(lines(&coverage, "/repo/src/lib.rs"), vec![(0, 3), (1, 0), (3, 1)]);
+        let file = coverage.file(Path::new("/repo/src/lib.rs")).unwrap();
+        assert_eq!(file.summary(Range::from_coords(1, 0, 3, 0)), CoverageSummary { covered: 1, total: 2 });
+        assert_eq!(file.total().percent().map(|p| p.round()), Some(67.0));
+    }
+
+    #[test]
+    fn test_coverage_py_and_suffix_matching() {
+        let report = r#"{"meta": {}, "files": {"pkg/app.py": {"executed_lines": [1, 2], "missing_lines": [5]}}}"#;
+        let coverage = Coverage::parse(report).unwrap();
+        assert_eq!(lines(&coverage, "/home/me/project/pkg/app.py"), vec![(0, 1), (1, 1), (4, 0)]);
+        assert!(coverage.file(Path::new("/home/me/project/other.py")).is_none());
+    }
+
+    #[test]
+    fn test_jacoco() {
+        let report = r#"<?xml version="1.0"?><report name="r"><package name="com/acme"><class name="com/acme/Foo"/>
+<sourcefile name="Foo.java"><line nr="3" mi="0" ci="4" mb="0" cb="0"/><line nr="7" mi="2" ci="0" mb="0" cb="0"/></sourcefile></package></report>"#;
+        let coverage = Coverage::parse(report).unwrap();
+        assert_eq!(lines(&coverage, "/p/src/main/java/com/acme/Foo.java"), vec![(2, 1), (6, 0)]);
+    }
+
+    #[test]
+    fn test_go_cover_and_ambiguous_files() {
+        let report = "mode: count\nexample.com/m/a/util.go:3.14,5.2 2 4\nexample.com/m/a/util.go:5.2,6.3 1 0\nexample.com/m/b/util.go:1.1,1.9 1 1\n";
+        let coverage = Coverage::parse(report).unwrap();
+        assert_eq!(lines(&coverage, "/src/m/a/util.go"), vec![(2, 4), (3, 4), (4, 4), (5, 0)]);
+        // Both entries share only the file name
+        assert!(coverage.file(Path::new("/src/other/util.go")).is_none());
+    }
+}
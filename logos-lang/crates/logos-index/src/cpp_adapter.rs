@@ -1,34 +1,47 @@
 //! C++ Language Adapter
 //!
 //! Pragmatic indexer for C++:
-//! - Symbols: function definitions, class/struct, namespaces (best-effort)
-//! - Imports: #include directives
+//! - Symbols: free functions, class/struct with fields and methods
+//!   (including those defined out of line, `void User::greet() {}`),
+//!   enums and their enumerators, namespaces (best-effort), `#define` macros
+//! - Imports: #include directives, resolved as for C, then in the
+//!   configured include paths
+//! - Type relations: base-class clauses
+//! - Compiled-out preprocessor branches are skipped as for C
 //! - Calls: call_expression nodes (best-effort)
 
-use crate::adapter::{AnalysisResult, CallInfo, ImportInfo, ImportItem, LanguageAdapter, SymbolBuilder, make_location};
+use crate::adapter::{AnalysisResult, CallInfo, ImportInfo, ImportItem, LanguageAdapter, SymbolBuilder, TypeRelation, make_location};
+use crate::c_adapter::{compiled_out, drop_compiled_out, file_defines, live_ifdef_children, macro_symbol, resolve_include};
+use crate::compile_commands::CompilationDatabase;
 use crate::symbol_table::Visibility;
+use crate::vfs::Vfs;
 use logos_core::{Position, Range, SymbolKind};
-use std::path::Path;
-use tree_sitter::{Node, Parser, Tree};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use logos_parser::{pool, LanguageId};
+use tree_sitter::{Node, Tree};
 
 pub struct CppAdapter {
-    parser: std::sync::Mutex<Parser>,
+    compile_db: RwLock<Option<Arc<CompilationDatabase>>>,
+    include_paths: RwLock<Vec<PathBuf>>,
 }
 
 impl CppAdapter {
     pub fn new() -> Result<Self, String> {
-        let mut parser = Parser::new();
-        parser
-            .set_language(&tree_sitter_cpp::LANGUAGE.into())
+        // Fail early when the grammar can't be loaded
+        pool::shared()
+            .checkout(LanguageId::Cpp)
             .map_err(|e| format!("Failed to set C++ language: {}", e))?;
         Ok(Self {
-            parser: std::sync::Mutex::new(parser),
+            compile_db: RwLock::new(None),
+            include_paths: RwLock::new(Vec::new()),
         })
     }
 
     fn parse(&self, source: &str) -> Option<Tree> {
-        let mut parser = self.parser.lock().ok()?;
-        parser.parse(source, None)
+        let mut parser = pool::shared().checkout(LanguageId::Cpp).ok()?;
+        parser.parse(source, None).ok()
     }
 }
 
@@ -47,29 +60,38 @@ impl LanguageAdapter for CppAdapter {
             None => return AnalysisResult::default(),
         };
 
+        let compile_db = self.compile_db.read().ok().and_then(|db| db.clone());
         let mut ctx = AnalysisContext {
             uri: uri.to_string(),
             source,
             result: AnalysisResult::default(),
             scope_stack: Vec::new(),
+            defines: file_defines(compile_db.as_deref(), uri),
         };
 
         analyze_node(&tree.root_node(), &mut ctx);
-        ctx.result
+        let regions = compiled_out(LanguageId::Cpp, &tree, source, ctx.defines);
+        let mut result = ctx.result.with_utf16_columns(source);
+        drop_compiled_out(&mut result, &regions);
+        result
     }
 
-    fn resolve_import(&self, from_file: &Path, import_path: &str) -> Option<std::path::PathBuf> {
-        // For `#include "x.h"` try relative to file dir
-        if !(import_path.starts_with('"') && import_path.ends_with('"')) {
-            return None;
+    fn resolve_import(&self, vfs: &dyn Vfs, from_file: &Path, import_path: &str) -> Option<PathBuf> {
+        let compile_db = self.compile_db.read().ok().and_then(|db| db.clone());
+        let include_paths = self.include_paths.read().map(|paths| paths.clone()).unwrap_or_default();
+        resolve_include(vfs, compile_db.as_deref(), &include_paths, from_file, import_path)
+    }
+
+    fn set_compilation_database(&self, db: Option<Arc<CompilationDatabase>>) {
+        if let Ok(mut compile_db) = self.compile_db.write() {
+            *compile_db = db;
         }
-        let inner = import_path.trim_matches('"');
-        let parent = from_file.parent()?;
-        let resolved = parent.join(inner);
-        if resolved.exists() {
-            return Some(resolved);
+    }
+
+    fn set_include_paths(&self, paths: Vec<PathBuf>) {
+        if let Ok(mut include_paths) = self.include_paths.write() {
+            *include_paths = paths;
         }
-        None
     }
 }
 
@@ -78,6 +100,7 @@ struct AnalysisContext<'a> {
     source: &'a str,
     result: AnalysisResult,
     scope_stack: Vec<ScopeInfo>,
+    defines: Option<&'a HashMap<String, Option<String>>>,
 }
 
 struct ScopeInfo {
@@ -110,6 +133,7 @@ fn analyze_node(node: &Node, ctx: &mut AnalysisContext) {
         "function_definition" => analyze_function(node, ctx),
         "class_specifier" | "struct_specifier" => analyze_class_or_struct(node, ctx),
         "class_declaration" | "struct_declaration" => analyze_class_decl(node, ctx),
+        "enum_specifier" => analyze_enum(node, ctx),
         // Some C++ constructs wrap class/struct in a type_definition/declaration
         "type_definition" | "declaration" => {
             for i in 0..node.named_child_count() {
@@ -117,6 +141,9 @@ fn analyze_node(node: &Node, ctx: &mut AnalysisContext) {
                     if ch.kind() == "class_specifier" || ch.kind() == "struct_specifier" {
                         analyze_class_or_struct(&ch, ctx);
                     }
+                    if ch.kind() == "enum_specifier" {
+                        analyze_enum(&ch, ctx);
+                    }
                     if ch.kind() == "class_declaration" || ch.kind() == "struct_declaration" {
                         analyze_class_decl(&ch, ctx);
                     }
@@ -125,6 +152,14 @@ fn analyze_node(node: &Node, ctx: &mut AnalysisContext) {
         }
         "namespace_definition" => analyze_namespace(node, ctx),
         "call_expression" => analyze_call(node, ctx),
+        "preproc_def" | "preproc_function_def" => {
+            ctx.result.symbols.extend(macro_symbol(node, ctx.source, &ctx.uri));
+        }
+        "preproc_ifdef" => {
+            for child in live_ifdef_children(node, ctx.source, ctx.defines) {
+                analyze_node(&child, ctx);
+            }
+        }
         _ => {
             for i in 0..node.named_child_count() {
                 if let Some(child) = node.named_child(i) {
@@ -191,18 +226,24 @@ fn analyze_include(node: &Node, ctx: &mut AnalysisContext) {
 
 fn analyze_function(node: &Node, ctx: &mut AnalysisContext) {
     // function_definition 在全局作用域：function_declarator 包含 identifier
-    let name_node = node
-        .child_by_field_name("declarator")
-        .and_then(find_identifier_in_declarator);
+    let declared = node.child_by_field_name("declarator").and_then(function_name);
+    let name_node = declared.or_else(|| node.child_by_field_name("declarator").and_then(find_identifier_in_declarator));
 
     if let Some(name_node) = name_node {
-        let name = ctx.get_text(&name_node);
+        // `void User::greet() {}` defines a method of `User` out of line
+        let text = ctx.get_text(&name_node);
+        let (kind, name, qualified) = match text.rsplit_once("::") {
+            Some((owner, name)) if name_node.kind() == "qualified_identifier" => {
+                (SymbolKind::Method, name.to_string(), ctx.qualified_name(&format!("{}::{}", owner, name)))
+            }
+            _ => (SymbolKind::Function, text.clone(), ctx.qualified_name(&text)),
+        };
         let location = make_location(&ctx.uri, node_to_range(node), node_to_range(&name_node));
 
-        let symbol = SymbolBuilder::new(name.clone(), SymbolKind::Function, location)
+        let symbol = SymbolBuilder::new(name.clone(), kind, location)
             .exported(true)
             .visibility(Visibility::Public)
-            .qualified_name(ctx.qualified_name(&name))
+            .qualified_name(qualified)
             .build();
 
         let symbol_id = symbol.id;
@@ -257,6 +298,7 @@ fn analyze_class_or_struct(node: &Node, ctx: &mut AnalysisContext) {
 
     let symbol_id = symbol.id;
     ctx.result.symbols.push(symbol);
+    analyze_base_classes(node, &name, ctx);
 
     // 分析类体：提取字段和方法
     if let Some(body) = node.child_by_field_name("body") {
@@ -344,21 +386,30 @@ fn analyze_field_declaration_list(node: &Node, ctx: &mut AnalysisContext, defaul
     }
 }
 
-fn analyze_field(node: &Node, ctx: &mut AnalysisContext) {
-    analyze_field_with_visibility(node, ctx, Visibility::Private);
-}
-
 fn analyze_field_with_visibility(node: &Node, ctx: &mut AnalysisContext, visibility: Visibility) {
+    // A nested type: `struct Inner { ... };` or `enum Kind { ... } kind;`
+    if let Some(ty) = node.child_by_field_name("type") {
+        if matches!(ty.kind(), "class_specifier" | "struct_specifier" | "enum_specifier") && ty.child_by_field_name("body").is_some() {
+            analyze_node(&ty, ctx);
+        }
+    }
+    let Some(declarator) = node.child_by_field_name("declarator") else {
+        return;
+    };
+
     // field_declaration 结构：type + declarator (field_identifier)
     // 查找 field_identifier 或 identifier
-    let name_node = find_first_named_of_kinds(*node, &["field_identifier", "identifier"]);
+    let method = function_name(declarator).filter(|name| name.kind() == "field_identifier");
+    let name_node = method.or_else(|| find_first_named_of_kinds(*node, &["field_identifier", "identifier"]));
 
     if let Some(name_node) = name_node {
         let name = ctx.get_text(&name_node);
         let location = make_location(&ctx.uri, node_to_range(node), node_to_range(&name_node));
+        // A method declared here and defined elsewhere
+        let kind = if method.is_some() { SymbolKind::Method } else { SymbolKind::Field };
 
         ctx.result.symbols.push(
-            SymbolBuilder::new(name.clone(), SymbolKind::Field, location)
+            SymbolBuilder::new(name.clone(), kind, location)
                 .parent(ctx.current_scope().map(|s| s.symbol_id).unwrap_or(crate::symbol_table::SymbolId(0)))
                 .visibility(visibility)
                 .exported(visibility == Visibility::Public)
@@ -368,10 +419,6 @@ fn analyze_field_with_visibility(node: &Node, ctx: &mut AnalysisContext, visibil
     }
 }
 
-fn analyze_method(node: &Node, ctx: &mut AnalysisContext) {
-    analyze_method_with_visibility(node, ctx, Visibility::Private);
-}
-
 fn analyze_method_with_visibility(node: &Node, ctx: &mut AnalysisContext, visibility: Visibility) {
     // function_definition 在类中：function_declarator 包含 field_identifier 或 identifier
     let name_node = node
@@ -435,6 +482,87 @@ fn analyze_namespace(node: &Node, ctx: &mut AnalysisContext) {
     }
 }
 
+fn analyze_enum(node: &Node, ctx: &mut AnalysisContext) {
+    let Some(name_node) = node.child_by_field_name("name") else {
+        return;
+    };
+    let name = ctx.get_text(&name_node);
+    let location = make_location(&ctx.uri, node_to_range(node), node_to_range(&name_node));
+    let symbol = SymbolBuilder::new(name.clone(), SymbolKind::Enum, location)
+        .exported(true)
+        .visibility(Visibility::Public)
+        .qualified_name(ctx.qualified_name(&name))
+        .build();
+    let symbol_id = symbol.id;
+    ctx.result.symbols.push(symbol);
+
+    let Some(body) = node.child_by_field_name("body") else {
+        return;
+    };
+    ctx.scope_stack.push(ScopeInfo { symbol_id, name });
+    for i in 0..body.named_child_count() {
+        let Some(enumerator) = body.named_child(i).filter(|n| n.kind() == "enumerator") else {
+            continue;
+        };
+        if let Some(name_node) = enumerator.child_by_field_name("name") {
+            let name = ctx.get_text(&name_node);
+            let location = make_location(&ctx.uri, node_to_range(&enumerator), node_to_range(&name_node));
+            ctx.result.symbols.push(
+                SymbolBuilder::new(name.clone(), SymbolKind::EnumMember, location)
+                    .parent(symbol_id)
+                    .exported(true)
+                    .visibility(Visibility::Public)
+                    .qualified_name(ctx.qualified_name(&name))
+                    .build(),
+            );
+        }
+    }
+    ctx.scope_stack.pop();
+}
+
+/// Extends relations of `class User : public Base, private Mixin<User>`
+fn analyze_base_classes(node: &Node, name: &str, ctx: &mut AnalysisContext) {
+    for i in 0..node.named_child_count() {
+        let Some(clause) = node.named_child(i).filter(|n| n.kind() == "base_class_clause") else {
+            continue;
+        };
+        for j in 0..clause.named_child_count() {
+            let Some(base) = clause.named_child(j) else {
+                continue;
+            };
+            if !matches!(base.kind(), "type_identifier" | "qualified_identifier" | "template_type") {
+                continue;
+            }
+            // Drop template arguments and the scope: `ns::Mixin<User>` -> `Mixin`
+            let text = ctx.get_text(&base);
+            let unqualified = text.split('<').next().unwrap_or(&text);
+            let parent_name = unqualified.rsplit("::").next().unwrap_or(unqualified).trim().to_string();
+            ctx.result.type_relations.push(TypeRelation {
+                child_name: name.to_string(),
+                parent_name,
+                is_implements: false,
+                location: node_to_range(&base),
+            });
+        }
+    }
+}
+
+/// Name a declarator declares a function by: the inner declarator of its
+/// function_declarator, behind any pointer or reference declarators.
+/// `None` when it doesn't declare a function.
+fn function_name<'a>(declarator: Node<'a>) -> Option<Node<'a>> {
+    let mut node = declarator;
+    loop {
+        match node.kind() {
+            "function_declarator" => return node.child_by_field_name("declarator"),
+            "pointer_declarator" | "reference_declarator" => {
+                node = node.child_by_field_name("declarator").or_else(|| node.named_child(node.named_child_count().checked_sub(1)?))?;
+            }
+            _ => return None,
+        }
+    }
+}
+
 fn find_first_named_of_kinds<'a>(node: Node<'a>, kinds: &[&str]) -> Option<Node<'a>> {
     // 先检查直接子节点
     for i in 0..node.named_child_count() {
@@ -550,7 +678,7 @@ namespace demo {
 int greet() { return 0; }
 "#;
         let result = adapter.analyze("file:///test.cpp", src);
-        assert!(result.imports.len() >= 1, "Should have includes");
+        assert!(!result.imports.is_empty(), "Should have includes");
         assert!(result.symbols.iter().any(|s| s.name == "demo"), "Should have namespace");
         assert!(result.symbols.iter().any(|s| s.name == "User" && s.kind == SymbolKind::Class), "Should have class User");
         assert!(result.symbols.iter().any(|s| s.name == "name" && s.kind == SymbolKind::Field), "Should have field name");
@@ -588,5 +716,57 @@ class MyClass {
         assert_eq!(public_method.kind, SymbolKind::Method);
         assert_eq!(public_method.visibility, Visibility::Public);
     }
+
+    #[test]
+    fn cpp_enums_bases_and_out_of_line_methods() {
+        let adapter = CppAdapter::new().unwrap();
+        let src = r#"
+namespace app {
+enum class Color { Red, Green };
+
+class Admin : public User, private ns::Mixin<Admin> {
+  public:
+    void greet();
+    struct Options { int level; };
+};
+
+void Admin::greet() { notify(); }
 }
+"#;
+        let result = adapter.analyze("file:///admin.cpp", src);
+        let color = result.symbols.iter().find(|s| s.name == "Color").unwrap();
+        assert_eq!(color.kind, SymbolKind::Enum);
+        let green = result.symbols.iter().find(|s| s.name == "Green").unwrap();
+        assert_eq!(green.kind, SymbolKind::EnumMember);
+        assert_eq!(green.parent, Some(color.id));
+        assert_eq!(green.qualified_name, "app::Color::Green");
+
+        let bases: Vec<_> = result.type_relations.iter().map(|r| (r.child_name.as_str(), r.parent_name.as_str())).collect();
+        assert_eq!(bases, vec![("Admin", "User"), ("Admin", "Mixin")]);
+
+        let greets: Vec<_> = result.symbols.iter().filter(|s| s.name == "greet").collect();
+        assert_eq!(greets.len(), 2);
+        assert!(greets.iter().all(|s| s.kind == SymbolKind::Method && s.qualified_name == "app::Admin::greet"));
+
+        let options = result.symbols.iter().find(|s| s.name == "Options").unwrap();
+        assert_eq!(options.qualified_name, "app::Admin::Options");
+        assert!(result.symbols.iter().any(|s| s.name == "level" && s.qualified_name == "app::Admin::Options::level"));
+        assert!(!result.symbols.iter().any(|s| s.name == "Options" && s.kind == SymbolKind::Field));
+    }
+
+    #[test]
+    fn cpp_includes_from_configured_paths() {
+        let adapter = CppAdapter::new().unwrap();
+        let vfs = crate::vfs::MemoryFs::new();
+        vfs.insert("/proj/src/widget.hpp", "");
+        vfs.insert("/proj/third_party/fmt/format.h", "");
+        let main = Path::new("/proj/src/main.cpp");
+
+        assert_eq!(adapter.resolve_import(&vfs, main, "\"widget.hpp\""), Some(PathBuf::from("/proj/src/widget.hpp")));
+        assert_eq!(adapter.resolve_import(&vfs, main, "<fmt/format.h>"), None);
 
+        adapter.set_include_paths(vec![PathBuf::from("/proj/include"), PathBuf::from("/proj/third_party")]);
+        assert_eq!(adapter.resolve_import(&vfs, main, "<fmt/format.h>"), Some(PathBuf::from("/proj/third_party/fmt/format.h")));
+        assert_eq!(adapter.resolve_import(&vfs, main, "<vector>"), None);
+    }
+}
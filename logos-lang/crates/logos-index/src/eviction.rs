@@ -0,0 +1,167 @@
+//! Memory budget for symbol details
+//!
+//! Documentation and type information make up most of what a large index
+//! holds, yet only the files being worked on need them. Past a budget,
+//! [`DetailLru`] picks the files whose details were used least recently;
+//! the symbol table drops those details, keeping names, kinds and
+//! locations so searches still find the symbols, and has them analyzed
+//! again when they're asked for.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Least recently used order of the files whose symbol details are in
+/// memory, with their size
+#[derive(Debug, Default)]
+pub(crate) struct DetailLru {
+    /// Bytes the details may take; unlimited when `None`
+    budget: Option<usize>,
+    /// Resident files: detail bytes and last use
+    files: HashMap<String, (usize, u64)>,
+    /// Resident files by last use, oldest first
+    order: BTreeMap<u64, String>,
+    /// Files whose details were dropped
+    evicted: HashSet<String>,
+    resident: usize,
+    clock: u64,
+}
+
+impl DetailLru {
+    pub fn set_budget(&mut self, budget: Option<usize>) {
+        self.budget = budget;
+    }
+
+    /// Bytes of details in memory
+    pub fn resident_bytes(&self) -> usize {
+        self.resident
+    }
+
+    pub fn is_evicted(&self, uri: &str) -> bool {
+        self.evicted.contains(uri)
+    }
+
+    /// Files whose details were dropped
+    pub fn evicted(&self) -> impl Iterator<Item = &str> {
+        self.evicted.iter().map(String::as_str)
+    }
+
+    /// Count `bytes` more details of `uri` in memory, as just used
+    pub fn add(&mut self, uri: &str, bytes: usize) {
+        self.evicted.remove(uri);
+        let size = self.files.get(uri).map_or(0, |&(size, _)| size);
+        self.touch_with(uri, size + bytes);
+        self.resident += bytes;
+    }
+
+    /// Mark the details of `uri` as just used
+    pub fn touch(&mut self, uri: &str) {
+        if let Some(&(size, _)) = self.files.get(uri) {
+            self.touch_with(uri, size);
+        }
+    }
+
+    fn touch_with(&mut self, uri: &str, size: usize) {
+        if let Some((_, used)) = self.files.get(uri) {
+            self.order.remove(used);
+        }
+        self.clock += 1;
+        self.order.insert(self.clock, uri.to_string());
+        self.files.insert(uri.to_string(), (size, self.clock));
+    }
+
+    /// Forget a file removed from the index
+    pub fn remove(&mut self, uri: &str) {
+        self.evicted.remove(uri);
+        if let Some((size, used)) = self.files.remove(uri) {
+            self.order.remove(&used);
+            self.resident -= size;
+        }
+    }
+
+    /// Record that the details of `uri` are not in memory, as when its
+    /// symbols were restored without them
+    pub fn mark_evicted(&mut self, uri: &str) {
+        if let Some((size, used)) = self.files.remove(uri) {
+            self.order.remove(&used);
+            self.resident -= size;
+        }
+        self.evicted.insert(uri.to_string());
+    }
+
+    /// Keep what is known of the details of `from` under `to`
+    pub fn rename(&mut self, from: &str, to: &str) {
+        if self.evicted.remove(from) {
+            self.evicted.insert(to.to_string());
+        }
+        if let Some((size, used)) = self.files.remove(from) {
+            self.order.insert(used, to.to_string());
+            self.files.insert(to.to_string(), (size, used));
+        }
+    }
+
+    /// Evict the least recently used files until the details fit the
+    /// budget, sparing `keep`; the files evicted
+    pub fn evict(&mut self, keep: &str) -> Vec<String> {
+        let Some(budget) = self.budget else {
+            return Vec::new();
+        };
+        let mut evicted = Vec::new();
+        let mut spared = None;
+        while self.resident > budget {
+            let Some((used, uri)) = self.order.pop_first() else {
+                break;
+            };
+            if uri == keep {
+                spared = Some((used, uri));
+                continue;
+            }
+            if let Some((size, _)) = self.files.remove(&uri) {
+                self.resident -= size;
+            }
+            self.evicted.insert(uri.clone());
+            evicted.push(uri);
+        }
+        if let Some((used, uri)) = spared {
+            self.order.insert(used, uri);
+        }
+        evicted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut lru = DetailLru::default();
+        lru.add("a", 40);
+        lru.add("b", 40);
+        lru.add("c", 40);
+        assert!(lru.evict("c").is_empty());
+
+        lru.set_budget(Some(100));
+        lru.touch("a");
+        assert_eq!(lru.evict("c"), vec!["b"]);
+        assert!(lru.is_evicted("b"));
+        assert_eq!(lru.resident_bytes(), 80);
+
+        // The file being worked on stays, even alone over the budget
+        lru.set_budget(Some(10));
+        assert_eq!(lru.evict("c"), vec!["a"]);
+        assert_eq!(lru.resident_bytes(), 40);
+
+        // Details read back count again; removed files are forgotten
+        lru.add("b", 40);
+        assert!(!lru.is_evicted("b"));
+        lru.remove("b");
+        lru.remove("a");
+        assert_eq!(lru.resident_bytes(), 40);
+        assert_eq!(lru.evicted().count(), 0);
+
+        // Restored without details
+        lru.mark_evicted("c");
+        lru.mark_evicted("d");
+        assert!(lru.is_evicted("c") && lru.is_evicted("d"));
+        assert_eq!(lru.resident_bytes(), 0);
+    }
+}
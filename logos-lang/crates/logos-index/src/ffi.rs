@@ -0,0 +1,427 @@
+//! Symbols linked across language boundaries
+//!
+//! A function bound through an FFI is declared on one side and implemented
+//! on the other, under the same name: a Rust `extern "C"` block imports C
+//! functions, a Rust `extern "C" fn` exports one to C, Python loads C
+//! functions through `ctypes` library handles or declares them to `cffi`,
+//! a C header declares what some source file defines, and a TypeScript
+//! `.d.ts` declares a JavaScript module. [`find_bindings`] lists the
+//! bindings of a document and [`FfiBinding::rank`] says which symbols of
+//! other files can be their counterpart, so go-to-definition can hop from
+//! one side to the other.
+
+use std::path::Path;
+
+use logos_core::{Position, Range, SymbolKind};
+use logos_parser::{LanguageId, Utf16Columns};
+use regex::Regex;
+use tree_sitter::{Node, Point, Tree};
+
+use crate::IndexedSymbol;
+
+/// Which side of which boundary a binding is on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingKind {
+    /// Function declared in a Rust `extern` block
+    RustImport,
+    /// Rust function exported with an `extern` ABI
+    RustExport,
+    /// C or C++ function prototype
+    CPrototype,
+    /// Function loaded from a `ctypes` library handle or a cffi `dlopen`
+    PythonCtypes,
+    /// Function declared in a cffi `ffi.cdef(...)` string
+    PythonCffi,
+    /// Declaration in a TypeScript `.d.ts` file
+    TypeDeclaration,
+}
+
+/// A name bound across a language boundary
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FfiBinding {
+    pub name: String,
+    /// Range of the name
+    pub range: Range,
+    pub kind: BindingKind,
+}
+
+/// Functions of a Python library handle: `CDLL`, `cdll.LoadLibrary`,
+/// `ffi.dlopen` and the like
+const LIBRARY_LOADERS: &[&str] = &["CDLL", "PyDLL", "WinDLL", "OleDLL", "LoadLibrary", "dlopen"];
+
+/// Attributes of a ctypes function that aren't library functions
+const FUNCTION_ATTRIBUTES: &[&str] = &["argtypes", "restype", "errcheck"];
+
+/// Bindings declared or used in a document of `language` at `path`
+pub fn find_bindings(language: LanguageId, path: &str, tree: &Tree, source: &str) -> Vec<FfiBinding> {
+    let mut found = Finder { source, columns: Utf16Columns::new(source), bindings: Vec::new(), handles: Vec::new() };
+    let root = tree.root_node();
+    match language.base() {
+        LanguageId::Rust => found.rust(&root),
+        LanguageId::C | LanguageId::Cpp => found.c(&root),
+        LanguageId::Python => {
+            found.python_handles(&root);
+            found.python(&root);
+        }
+        LanguageId::TypeScript if path.ends_with(".d.ts") => found.type_declarations(&root),
+        _ => {}
+    }
+    found.bindings
+}
+
+/// The binding whose name is at `position`, the end of the name included
+pub fn binding_at(bindings: &[FfiBinding], position: Position) -> Option<&FfiBinding> {
+    bindings.iter().find(|binding| binding.range.start <= position && position <= binding.range.end)
+}
+
+impl FfiBinding {
+    /// How well `symbol` fits as the other side of the binding found in
+    /// `uri`; lower is better, `None` when it can't be the other side
+    pub fn rank(&self, uri: &str, symbol: &IndexedSymbol) -> Option<u8> {
+        if symbol.name != self.name || symbol.uri == uri {
+            return None;
+        }
+        let language = language_of(&symbol.uri)?;
+        let is_c = matches!(language, LanguageId::C | LanguageId::Cpp);
+        let is_c_function = is_c && symbol.kind == SymbolKind::Function;
+        let is_rust_export = language == LanguageId::Rust && symbol.kind == SymbolKind::Function && is_exported(symbol);
+        match self.kind {
+            BindingKind::RustImport => is_c_function.then_some(0),
+            BindingKind::PythonCtypes | BindingKind::PythonCffi | BindingKind::CPrototype => {
+                if is_c_function {
+                    Some(0)
+                } else {
+                    is_rust_export.then_some(1)
+                }
+            }
+            BindingKind::RustExport => (is_c && symbol.kind != SymbolKind::Function).then_some(0),
+            BindingKind::TypeDeclaration => {
+                if language.base() != LanguageId::JavaScript {
+                    return None;
+                }
+                Some(if stem(&symbol.uri) == stem(uri) { 0 } else { 1 })
+            }
+        }
+    }
+}
+
+/// Whether a Rust function has an `extern` ABI, from its signature
+fn is_exported(symbol: &IndexedSymbol) -> bool {
+    symbol.detail.as_deref().is_some_and(|detail| detail.split_whitespace().any(|word| word == "extern"))
+}
+
+fn language_of(uri: &str) -> Option<LanguageId> {
+    Path::new(uri).extension().and_then(|ext| ext.to_str()).and_then(LanguageId::from_extension)
+}
+
+/// File name up to its first dot: `index` for `index.d.ts` and `index.js`
+fn stem(uri: &str) -> &str {
+    let name = uri.rsplit('/').next().unwrap_or(uri);
+    name.split('.').next().unwrap_or(name)
+}
+
+struct Finder<'a> {
+    source: &'a str,
+    columns: Utf16Columns<'a>,
+    bindings: Vec<FfiBinding>,
+    /// Python names bound to a library handle
+    handles: Vec<String>,
+}
+
+impl<'a> Finder<'a> {
+    fn text(&self, node: &Node) -> &'a str {
+        node.utf8_text(self.source.as_bytes()).unwrap_or("")
+    }
+
+    fn push(&mut self, name_node: &Node, kind: BindingKind) {
+        let range = Range::new(
+            self.columns.position(name_node.start_position()),
+            self.columns.position(name_node.end_position()),
+        );
+        self.bindings.push(FfiBinding { name: self.text(name_node).to_string(), range, kind });
+    }
+
+    fn rust(&mut self, node: &Node) {
+        match node.kind() {
+            "foreign_mod_item" => {
+                let Some(body) = node.child_by_field_name("body") else {
+                    return;
+                };
+                let mut cursor = body.walk();
+                for item in body.named_children(&mut cursor) {
+                    if item.kind() == "function_signature_item" {
+                        if let Some(name) = item.child_by_field_name("name") {
+                            self.push(&name, BindingKind::RustImport);
+                        }
+                    }
+                }
+                return;
+            }
+            "function_item" => {
+                let mut cursor = node.walk();
+                let exported = node
+                    .children(&mut cursor)
+                    .any(|child| child.kind() == "function_modifiers" && self.text(&child).contains("extern"));
+                if exported {
+                    if let Some(name) = node.child_by_field_name("name") {
+                        self.push(&name, BindingKind::RustExport);
+                    }
+                }
+            }
+            _ => {}
+        }
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            self.rust(&child);
+        }
+    }
+
+    fn c(&mut self, node: &Node) {
+        if matches!(node.kind(), "function_definition" | "compound_statement") {
+            return;
+        }
+        if node.kind() == "declaration" {
+            let mut cursor = node.walk();
+            for declarator in node.children_by_field_name("declarator", &mut cursor) {
+                if let Some(name) = function_name(declarator) {
+                    self.push(&name, BindingKind::CPrototype);
+                }
+            }
+            return;
+        }
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            self.c(&child);
+        }
+    }
+
+    /// Names assigned a library handle, anywhere in the module
+    fn python_handles(&mut self, node: &Node) {
+        if node.kind() == "assignment" {
+            let left = node.child_by_field_name("left");
+            let right = node.child_by_field_name("right");
+            if let (Some(left), Some(right)) = (left, right) {
+                if left.kind() == "identifier" && right.kind() == "call" && self.loads_library(&right) {
+                    self.handles.push(self.text(&left).to_string());
+                }
+            }
+        }
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            self.python_handles(&child);
+        }
+    }
+
+    fn loads_library(&self, call: &Node) -> bool {
+        let Some(function) = call.child_by_field_name("function") else {
+            return false;
+        };
+        let name = self.text(&function).rsplit('.').next().unwrap_or("");
+        LIBRARY_LOADERS.contains(&name)
+    }
+
+    fn python(&mut self, node: &Node) {
+        match node.kind() {
+            "attribute" => {
+                let object = node.child_by_field_name("object");
+                let attribute = node.child_by_field_name("attribute");
+                if let (Some(object), Some(attribute)) = (object, attribute) {
+                    let on_handle = object.kind() == "identifier" && self.handles.iter().any(|h| h == self.text(&object));
+                    if on_handle && !FUNCTION_ATTRIBUTES.contains(&self.text(&attribute)) {
+                        self.push(&attribute, BindingKind::PythonCtypes);
+                    }
+                }
+            }
+            "call" => {
+                let is_cdef = node
+                    .child_by_field_name("function")
+                    .is_some_and(|function| self.text(&function).ends_with(".cdef"));
+                if is_cdef {
+                    self.cdef(node);
+                    return;
+                }
+            }
+            _ => {}
+        }
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            self.python(&child);
+        }
+    }
+
+    /// Functions declared in the string literals of an `ffi.cdef(...)` call
+    fn cdef(&mut self, call: &Node) {
+        let Some(arguments) = call.child_by_field_name("arguments") else {
+            return;
+        };
+        let Ok(declared) = Regex::new(r"([A-Za-z_]\w*)\s*\(") else {
+            return;
+        };
+        let mut cursor = arguments.walk();
+        for string in arguments.named_children(&mut cursor).filter(|arg| arg.kind() == "string") {
+            let start = string.start_byte();
+            let text = self.text(&string);
+            // The first name followed by `(` in each declaration is the
+            // function, the others are parameters of function pointers
+            let mut offset = 0;
+            for declaration in text.split(';') {
+                if let Some(name) = declared.captures(declaration).and_then(|c| c.get(1)) {
+                    let from = start + offset + name.start();
+                    let range = Range::new(self.position_of(from), self.position_of(from + name.len()));
+                    self.bindings.push(FfiBinding {
+                        name: name.as_str().to_string(),
+                        range,
+                        kind: BindingKind::PythonCffi,
+                    });
+                }
+                offset += declaration.len() + 1;
+            }
+        }
+    }
+
+    fn position_of(&self, byte: usize) -> Position {
+        let before = &self.source[..byte];
+        let row = before.matches('\n').count();
+        let column = byte - before.rfind('\n').map_or(0, |newline| newline + 1);
+        self.columns.position(Point::new(row, column))
+    }
+
+    fn type_declarations(&mut self, node: &Node) {
+        match node.kind() {
+            "function_signature" | "function_declaration" | "class_declaration" | "method_signature" => {
+                if let Some(name) = node.child_by_field_name("name") {
+                    self.push(&name, BindingKind::TypeDeclaration);
+                }
+            }
+            "variable_declarator" => {
+                if let Some(name) = node.child_by_field_name("name").filter(|name| name.kind() == "identifier") {
+                    self.push(&name, BindingKind::TypeDeclaration);
+                }
+            }
+            _ => {}
+        }
+        let mut cursor = node.walk();
+        for child in node.named_children(&mut cursor) {
+            self.type_declarations(&child);
+        }
+    }
+}
+
+/// Name of the function a C declarator declares, through pointers and
+/// parentheses
+fn function_name(declarator: Node) -> Option<Node> {
+    match declarator.kind() {
+        "function_declarator" => {
+            let inner = declarator.child_by_field_name("declarator")?;
+            (inner.kind() == "identifier").then_some(inner)
+        }
+        "pointer_declarator" | "parenthesized_declarator" | "attributed_declarator" => {
+            let mut cursor = declarator.walk();
+            let found = declarator.named_children(&mut cursor).find_map(function_name);
+            found
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logos_parser::pool;
+
+    fn bindings(language: LanguageId, path: &str, source: &str) -> Vec<(String, BindingKind)> {
+        let tree = pool::shared().checkout(language).and_then(|mut parser| parser.parse(source, None)).unwrap();
+        find_bindings(language, path, &tree, source).into_iter().map(|b| (b.name, b.kind)).collect()
+    }
+
+    fn symbol(uri: &str, name: &str, kind: SymbolKind, detail: Option<&str>) -> IndexedSymbol {
+        IndexedSymbol {
+            name: name.to_string(),
+            kind,
+            uri: uri.to_string(),
+            range: Range::from_coords(0, 0, 0, 1),
+            selection_range: Range::from_coords(0, 0, 0, 1),
+            container: None,
+            detail: detail.map(str::to_string),
+            documentation: None,
+            generated: false,
+        }
+    }
+
+    #[test]
+    fn test_rust_bindings() {
+        let source = "extern \"C\" {\n    fn crc32(data: *const u8, len: usize) -> u32;\n}\n\n#[no_mangle]\npub extern \"C\" fn checksum(data: *const u8) -> u32 { 0 }\n\nfn helper() {}\n";
+        assert_eq!(
+            bindings(LanguageId::Rust, "/src/lib.rs", source),
+            vec![("crc32".to_string(), BindingKind::RustImport), ("checksum".to_string(), BindingKind::RustExport)]
+        );
+    }
+
+    #[test]
+    fn test_c_prototypes() {
+        let source = "int add(int a, int b);\nchar *name(void);\nint counter;\nint twice(int x) { int y(int); return 2 * x; }\n";
+        assert_eq!(
+            bindings(LanguageId::C, "/include/math.h", source),
+            vec![("add".to_string(), BindingKind::CPrototype), ("name".to_string(), BindingKind::CPrototype)]
+        );
+    }
+
+    #[test]
+    fn test_python_bindings() {
+        let source = "import ctypes\nfrom cffi import FFI\n\nlib = ctypes.CDLL(\"libmath.so\")\nlib.add.argtypes = [ctypes.c_int, ctypes.c_int]\nprint(lib.add(1, 2))\n\nffi = FFI()\nffi.cdef(\"\"\"\n    int scale(int x, int (*f)(int));\n\"\"\")\nother.add\n";
+        assert_eq!(
+            bindings(LanguageId::Python, "/app.py", source),
+            vec![
+                ("add".to_string(), BindingKind::PythonCtypes),
+                ("add".to_string(), BindingKind::PythonCtypes),
+                ("scale".to_string(), BindingKind::PythonCffi),
+            ]
+        );
+
+        let tree = pool::shared().checkout(LanguageId::Python).and_then(|mut parser| parser.parse(source, None)).unwrap();
+        let found = find_bindings(LanguageId::Python, "/app.py", &tree, source);
+        let scale = binding_at(&found, Position::new(9, 10)).unwrap();
+        assert_eq!(scale.name, "scale");
+        assert_eq!(scale.range, Range::from_coords(9, 8, 9, 13));
+    }
+
+    #[test]
+    fn test_type_declarations_only_in_dts() {
+        let source = "export declare function greet(name: string): string;\nexport declare class Greeter {\n    hello(): void;\n}\n";
+        assert_eq!(
+            bindings(LanguageId::TypeScript, "/lib/index.d.ts", source),
+            vec![
+                ("greet".to_string(), BindingKind::TypeDeclaration),
+                ("Greeter".to_string(), BindingKind::TypeDeclaration),
+                ("hello".to_string(), BindingKind::TypeDeclaration),
+            ]
+        );
+        assert!(bindings(LanguageId::TypeScript, "/lib/index.ts", source).is_empty());
+    }
+
+    #[test]
+    fn test_rank() {
+        let binding = |kind| FfiBinding { name: "add".to_string(), range: Range::from_coords(0, 0, 0, 3), kind };
+        let c_definition = symbol("file:///src/math.c", "add", SymbolKind::Function, Some("int add(int a, int b)"));
+        let c_prototype = symbol("file:///include/math.h", "add", SymbolKind::Variable, None);
+        let rust_export = symbol("file:///src/lib.rs", "add", SymbolKind::Function, Some("pub extern \"C\" fn add(a: i32, b: i32) -> i32"));
+        let rust_plain = symbol("file:///src/util.rs", "add", SymbolKind::Function, Some("fn add(a: i32, b: i32) -> i32"));
+
+        let python = binding(BindingKind::PythonCtypes);
+        assert_eq!(python.rank("file:///app.py", &c_definition), Some(0));
+        assert_eq!(python.rank("file:///app.py", &rust_export), Some(1));
+        assert_eq!(python.rank("file:///app.py", &rust_plain), None);
+
+        assert_eq!(binding(BindingKind::RustImport).rank("file:///src/ffi.rs", &c_definition), Some(0));
+        assert_eq!(binding(BindingKind::RustImport).rank("file:///src/ffi.rs", &c_prototype), None);
+        assert_eq!(binding(BindingKind::RustExport).rank("file:///src/lib.rs", &c_prototype), Some(0));
+        assert_eq!(binding(BindingKind::CPrototype).rank("file:///include/math.h", &rust_export), Some(1));
+
+        let declaration = binding(BindingKind::TypeDeclaration);
+        let sibling = symbol("file:///lib/math.js", "add", SymbolKind::Function, None);
+        let elsewhere = symbol("file:///lib/other.mjs", "add", SymbolKind::Function, None);
+        assert_eq!(declaration.rank("file:///lib/math.d.ts", &sibling), Some(0));
+        assert_eq!(declaration.rank("file:///lib/math.d.ts", &elsewhere), Some(1));
+        assert_eq!(declaration.rank("file:///lib/math.d.ts", &rust_export), None);
+    }
+}
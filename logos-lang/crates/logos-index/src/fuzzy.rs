@@ -0,0 +1,139 @@
+//! Fuzzy matching of symbol names
+//!
+//! A query matches a name when its characters appear in the name in order,
+//! compared as [`NameMatch`] says. Each match is scored by how it lines up
+//! with the name's words — the start of the name, the letter after `_`,
+//! `-`, `.` or a digit, camelCase humps — and how many characters it
+//! matches in a row, so `gds` finds `getDocumentSymbols` and
+//! `get_document_symbols` and ranks both above `gradients`.
+
+use crate::inverted::{compose_name, fold_name, normalize_name};
+use crate::NameMatch;
+
+/// Score of a query character matched at the start of a word
+const WORD_START: i32 = 8;
+/// Score of a query character matched right after the previous one
+const CONSECUTIVE: i32 = 8;
+/// Cost of each name character skipped between two matched ones
+const GAP: i32 = 1;
+
+/// A query, prepared for scoring many names
+#[derive(Debug, Clone)]
+pub struct FuzzyQuery {
+    chars: Vec<char>,
+    matching: NameMatch,
+}
+
+impl FuzzyQuery {
+    pub fn new(query: &str, matching: NameMatch) -> Self {
+        Self { chars: fold(query, matching).chars().collect(), matching }
+    }
+
+    /// Score of the best match of the query in `name`, higher is better;
+    /// `None` when the query's characters don't all appear in order. An
+    /// empty query matches every name with 0.
+    pub fn score(&self, name: &str) -> Option<i32> {
+        if self.chars.is_empty() {
+            return Some(0);
+        }
+        let name = self.name_chars(name);
+        if name.len() < self.chars.len() {
+            return None;
+        }
+
+        // Best score of the query so far with its last character matched
+        // at each name position
+        let mut previous: Vec<Option<i32>> = Vec::new();
+        for (i, &q) in self.chars.iter().enumerate() {
+            let mut current = vec![None; name.len()];
+            // Best score ending before the position, less the gap to it;
+            // the first character may start anywhere
+            let mut before = (i == 0).then_some(0);
+            for (j, &(c, word_start)) in name.iter().enumerate() {
+                if c == q {
+                    let score = 1 + if word_start { WORD_START } else { 0 };
+                    let after_gap = before.map(|s| s + score);
+                    let in_a_row = (i > 0 && j > 0).then(|| previous[j - 1]).flatten().map(|s| s + score + CONSECUTIVE);
+                    current[j] = after_gap.max(in_a_row);
+                }
+                if i > 0 {
+                    before = before.map(|s| s - GAP).max(previous[j]);
+                }
+            }
+            previous = current;
+        }
+        previous.into_iter().flatten().max()
+    }
+
+    /// Characters of `name` as the query is compared with them, each
+    /// flagged when it starts a word
+    fn name_chars(&self, name: &str) -> Vec<(char, bool)> {
+        let chars: Vec<char> = compose_name(name).chars().collect();
+        let mut folded = Vec::with_capacity(chars.len());
+        for (i, &c) in chars.iter().enumerate() {
+            let start = is_word_start(i.checked_sub(1).map(|p| chars[p]), c, chars.get(i + 1).copied());
+            for (k, f) in fold(c.encode_utf8(&mut [0; 4]), self.matching).chars().enumerate() {
+                folded.push((f, start && k == 0));
+            }
+        }
+        folded
+    }
+}
+
+fn fold(text: &str, matching: NameMatch) -> String {
+    match matching {
+        NameMatch::Normalized => normalize_name(text),
+        NameMatch::Folded => fold_name(text),
+    }
+}
+
+/// Whether `c`, between `previous` and `next`, starts a word: `get` and
+/// `Document` in `getDocument`, `Server` in `HTTPServer`, `v` in `utf8_v2`
+fn is_word_start(previous: Option<char>, c: char, next: Option<char>) -> bool {
+    let Some(previous) = previous else {
+        return true;
+    };
+    if !c.is_alphanumeric() {
+        return false;
+    }
+    !previous.is_alphanumeric()
+        || (previous.is_lowercase() && c.is_uppercase())
+        || (previous.is_uppercase() && c.is_uppercase() && next.is_some_and(char::is_lowercase))
+        || (previous.is_numeric() && !c.is_numeric())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn score(query: &str, name: &str) -> Option<i32> {
+        FuzzyQuery::new(query, NameMatch::Normalized).score(name)
+    }
+
+    #[test]
+    fn test_word_starts() {
+        assert!(score("gds", "getDocumentSymbols").is_some());
+        assert!(score("gds", "get_document_symbols").is_some());
+        assert!(score("gds", "gradients").is_some());
+        assert!(score("gds", "getDocument").is_none());
+        assert!(score("gds", "getDocumentSymbols") > score("gds", "gradients"));
+        assert!(score("hs", "HTTPServer") > score("hs", "hashes"));
+        assert_eq!(score("", "anything"), Some(0));
+    }
+
+    #[test]
+    fn test_consecutive_characters() {
+        // A run inside a word beats the same characters scattered
+        assert!(score("load", "reload") > score("load", "lookAhead"));
+        assert!(score("sym", "getDocumentSymbols") > score("sym", "saveYourMind"));
+        assert!(score("user", "UserService") > score("user", "updateSearchResults"));
+    }
+
+    #[test]
+    fn test_folded_matching() {
+        assert!(score("cm", "caféMenu").is_some());
+        assert!(score("cafem", "caféMenu").is_none());
+        assert!(FuzzyQuery::new("cafem", NameMatch::Folded).score("caféMenu").is_some());
+        assert!(FuzzyQuery::new("strs", NameMatch::Folded).score("straßeService").is_some());
+    }
+}
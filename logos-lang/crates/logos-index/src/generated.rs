@@ -0,0 +1,128 @@
+//! Detection of generated files and build artifacts
+//!
+//! Code written by a generator or a bundler is indexed like any other
+//! file, so definitions in it resolve, but its symbols are tagged: it
+//! isn't meant to be edited, and its declarations shadow the sources they
+//! were generated from in searches. A file is generated when its name is
+//! one a common generator gives (`*_pb2.py`, `*.pb.go`, `*.min.js`,
+//! `*.g.dart`, ...), when it lies in a build output directory, when its
+//! head carries a marker (`@generated`, `Code generated ... DO NOT EDIT`,
+//! `<auto-generated>`), or when it is minified.
+
+use crate::symbol_table::{Attribute, SmartSymbol};
+
+/// Attribute of the Smart index symbols declared in generated files
+pub const GENERATED_ATTRIBUTE: &str = "generated";
+
+/// File name endings of generator and bundler output
+const GENERATED_SUFFIXES: &[&str] = &[
+    "_pb2.py",
+    "_pb2.pyi",
+    "_pb2_grpc.py",
+    ".pb.go",
+    ".pb.h",
+    ".pb.cc",
+    "_pb.js",
+    "_pb.d.ts",
+    "_grpc_pb.js",
+    ".pb.swift",
+    ".g.dart",
+    ".freezed.dart",
+    ".designer.cs",
+    ".g.cs",
+    ".min.js",
+    ".min.mjs",
+    ".min.css",
+    ".bundle.js",
+    ".chunk.js",
+];
+
+/// Directories holding build output
+const GENERATED_DIRS: &[&str] = &["dist", "generated", "__generated__"];
+
+/// Lines at the head of a file searched for a marker
+const MARKER_LINES: usize = 10;
+
+/// Average line length above which code counts as minified
+const MINIFIED_LINE_LENGTH: usize = 500;
+
+/// Whether the file at `path` (a path or URI) with content `source` is
+/// generated
+pub fn is_generated(path: &str, source: &str) -> bool {
+    is_generated_path(path) || has_generated_marker(source) || is_minified(source)
+}
+
+/// Whether the name or a directory of `path` says the file is generated
+pub fn is_generated_path(path: &str) -> bool {
+    let path = path.replace('\\', "/");
+    let mut segments: Vec<&str> = path.split('/').filter(|segment| !segment.is_empty()).collect();
+    let Some(name) = segments.pop() else {
+        return false;
+    };
+    let name = name.to_lowercase();
+    GENERATED_SUFFIXES.iter().any(|suffix| name.ends_with(suffix))
+        || segments.iter().any(|segment| GENERATED_DIRS.contains(segment))
+}
+
+/// Whether the head of `source` carries a generator's marker
+pub fn has_generated_marker(source: &str) -> bool {
+    source.lines().take(MARKER_LINES).any(|line| {
+        let lower = line.to_lowercase();
+        lower.contains("@generated")
+            || lower.contains("<auto-generated")
+            || lower.contains("do not edit")
+            || (lower.contains("generated by") && lower.contains("protoc"))
+            || lower.contains("autogenerated file")
+    })
+}
+
+/// Whether `source` looks minified: long lines on average, not a data
+/// file's single long line among short ones
+pub fn is_minified(source: &str) -> bool {
+    let lines = source.lines().filter(|line| !line.trim().is_empty()).count();
+    lines > 0 && source.len() / lines > MINIFIED_LINE_LENGTH
+}
+
+/// Tag `symbols`, declared in a generated file, with [`GENERATED_ATTRIBUTE`]
+pub fn tag_generated(symbols: &mut [SmartSymbol]) {
+    for symbol in symbols {
+        if !symbol.is_generated() {
+            symbol.attributes.push(Attribute { name: GENERATED_ATTRIBUTE.to_string(), arguments: Vec::new() });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_paths() {
+        assert!(is_generated_path("file:///ws/proto/user_pb2.py"));
+        assert!(is_generated_path("/ws/api/user.pb.go"));
+        assert!(is_generated_path("/ws/static/vendor.MIN.js"));
+        assert!(is_generated_path("/ws/web/dist/index.js"));
+        assert!(is_generated_path("C:\\ws\\Forms\\Main.Designer.cs"));
+        assert!(!is_generated_path("/ws/src/build.rs"));
+        assert!(!is_generated_path("/ws/src/generator.py"));
+    }
+
+    #[test]
+    fn test_generated_markers() {
+        assert!(has_generated_marker("// Code generated by protoc-gen-go. DO NOT EDIT.\npackage api\n"));
+        assert!(has_generated_marker("/**\n * @generated SignedSource<<abc>>\n */\n"));
+        assert!(has_generated_marker("// <auto-generated>\n//     This code was generated by a tool.\n"));
+        assert!(!has_generated_marker("def generate():\n    pass\n"));
+        let late = format!("{}// @generated\n", "x = 1\n".repeat(MARKER_LINES));
+        assert!(!has_generated_marker(&late));
+    }
+
+    #[test]
+    fn test_minified() {
+        let minified = format!("!function(){{{}}}();\n", "var a=1;".repeat(200));
+        assert!(is_minified(&minified));
+        assert!(is_generated("/ws/static/app.js", &minified));
+        assert!(!is_minified("function add(a, b) {\n    return a + b;\n}\n"));
+        assert!(!is_minified(""));
+    }
+}
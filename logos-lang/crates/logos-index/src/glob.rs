@@ -0,0 +1,171 @@
+//! Path globs for filters and per-path settings
+//!
+//! `*` and `?` stay within a path segment and `**` crosses segments. A
+//! pattern starting with `/` is anchored at the root; any other pattern
+//! matches a suffix of whole segments, so `src/*.rs` matches
+//! `/repo/src/lib.rs`.
+//!
+//! [`IgnoreRules`] reads `.gitignore` and `.logosignore` patterns, and
+//! the exclude globs of the settings, into the same globs.
+
+use std::path::Path;
+
+use regex::Regex;
+
+use crate::vfs::Vfs;
+
+#[derive(Debug, Clone)]
+pub struct PathGlob {
+    regex: Regex,
+}
+
+impl PathGlob {
+    pub fn new(glob: &str) -> Option<Self> {
+        let mut pattern = String::from(if glob.starts_with('/') { "^" } else { "(?:^|/)" });
+        let mut chars = glob.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '*' if chars.peek() == Some(&'*') => {
+                    chars.next();
+                    // `**/` also matches no directory at all
+                    if chars.peek() == Some(&'/') {
+                        chars.next();
+                        pattern.push_str("(?:.*/)?");
+                    } else {
+                        pattern.push_str(".*");
+                    }
+                }
+                '*' => pattern.push_str("[^/]*"),
+                '?' => pattern.push_str("[^/]"),
+                c => pattern.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        pattern.push('$');
+        Regex::new(&pattern).ok().map(|regex| Self { regex })
+    }
+
+    /// Whether `path`, `/`-separated, matches
+    pub fn is_match(&self, path: &str) -> bool {
+        self.regex.is_match(path)
+    }
+}
+
+/// Ignore files read in each directory of a workspace walk
+pub const IGNORE_FILES: &[&str] = &[".gitignore", ".logosignore"];
+
+/// Patterns of the `.gitignore` and `.logosignore` files of a workspace,
+/// and exclude globs configured by the user. Paths are given relative to
+/// the root; negated patterns (`!keep.log`) are not supported and are
+/// dropped.
+#[derive(Debug, Clone, Default)]
+pub struct IgnoreRules {
+    /// Globs and whether they only apply to directories
+    globs: Vec<(PathGlob, bool)>,
+}
+
+impl IgnoreRules {
+    /// Patterns of an ignore file at the root
+    pub fn parse(text: &str) -> Self {
+        let mut rules = Self::default();
+        rules.add_file("", text);
+        rules
+    }
+
+    /// Add the patterns of an ignore file in `dir`, relative to the root;
+    /// they only apply under `dir`
+    pub fn add_file(&mut self, dir: &str, text: &str) {
+        let dir = dir.trim_matches('/');
+        for line in text.lines().map(str::trim_end) {
+            if line.is_empty() || line.starts_with('#') || line.starts_with('!') {
+                continue;
+            }
+            let dir_only = line.ends_with('/');
+            let pattern = line.trim_end_matches('/');
+            // A slash before the end anchors the pattern at the directory
+            // of the file
+            let pattern = match (pattern.contains('/'), dir.is_empty()) {
+                (true, true) => format!("/{}", pattern.trim_start_matches('/')),
+                (true, false) => format!("/{}/{}", dir, pattern.trim_start_matches('/')),
+                (false, true) => pattern.to_string(),
+                (false, false) => format!("/{}/**/{}", dir, pattern),
+            };
+            if let Some(glob) = PathGlob::new(&pattern) {
+                self.globs.push((glob, dir_only));
+            }
+        }
+    }
+
+    /// Add exclude globs, written as ignore-file patterns relative to the
+    /// root (`node_modules/`, `vendor/**/*.js`)
+    pub fn add_globs<'a>(&mut self, globs: impl IntoIterator<Item = &'a str>) {
+        for glob in globs {
+            self.add_file("", glob);
+        }
+    }
+
+    /// Add the ignore files of `dir`, a directory under `root`
+    pub fn read_dir(&mut self, vfs: &dyn Vfs, root: &Path, dir: &Path) {
+        let relative = dir.strip_prefix(root).unwrap_or(Path::new("")).to_string_lossy().replace('\\', "/");
+        for name in IGNORE_FILES {
+            if let Ok(text) = vfs.read_to_string(&dir.join(name)) {
+                self.add_file(&relative, &text);
+            }
+        }
+    }
+
+    /// Whether `path`, relative to the root and `/`-separated, is ignored
+    pub fn is_ignored(&self, path: &str, is_dir: bool) -> bool {
+        let path = format!("/{}", path.trim_start_matches('/'));
+        self.globs.iter().any(|(glob, dir_only)| (is_dir || !dir_only) && glob.is_match(&path))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments() {
+        let glob = PathGlob::new("src/*.rs").unwrap();
+        assert!(glob.is_match("/repo/src/lib.rs"));
+        assert!(glob.is_match("src/lib.rs"));
+        assert!(!glob.is_match("/repo/src/a/lib.rs"));
+        assert!(!glob.is_match("/repo/mysrc/lib.rs"));
+    }
+
+    #[test]
+    fn test_double_star_and_anchor() {
+        let glob = PathGlob::new("tests/**/*.py").unwrap();
+        assert!(glob.is_match("tests/test_a.py"));
+        assert!(glob.is_match("/repo/tests/unit/deep/test_a.py"));
+        let anchored = PathGlob::new("/repo/*").unwrap();
+        assert!(anchored.is_match("/repo/a.go"));
+        assert!(!anchored.is_match("/other/repo/a.go"));
+    }
+
+    #[test]
+    fn test_ignore_rules() {
+        let rules = IgnoreRules::parse("# comment\n*.log\nout/\ndocs/generated\n!keep.log\n");
+        assert!(rules.is_ignored("a/b/trace.log", false));
+        assert!(rules.is_ignored("out", true));
+        assert!(rules.is_ignored("src/out", true));
+        assert!(!rules.is_ignored("out", false));
+        assert!(rules.is_ignored("docs/generated", true));
+        assert!(!rules.is_ignored("src/docs/generated", true));
+        assert!(rules.is_ignored("keep.log", false));
+    }
+
+    #[test]
+    fn test_nested_ignore_files_and_globs() {
+        let mut rules = IgnoreRules::default();
+        rules.add_file("web", "*.min.js\n/generated/\n");
+        rules.add_globs(["vendor/", "third_party/**/*.c"]);
+        assert!(rules.is_ignored("web/lib/app.min.js", false));
+        assert!(!rules.is_ignored("app.min.js", false));
+        assert!(rules.is_ignored("web/generated", true));
+        assert!(!rules.is_ignored("web/src/generated", true));
+        assert!(rules.is_ignored("src/vendor", true));
+        assert!(rules.is_ignored("third_party/zlib/inflate.c", false));
+        assert!(!rules.is_ignored("src/inflate.c", false));
+    }
+}
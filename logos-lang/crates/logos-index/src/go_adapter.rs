@@ -2,34 +2,35 @@
 //!
 //! Implements the LanguageAdapter trait for Go files.
 //! This is a pragmatic (not fully semantic) indexer:
-//! - Symbols: functions, methods, types, vars/consts (package-level)
-//! - Imports: import specs
+//! - Symbols: the package, functions, methods (under their receiver type),
+//!   types, vars/consts (package-level)
+//! - Imports: import specs, resolved to a package directory through the
+//!   module path in the nearest `go.mod`
 //! - Exports: inferred from Go export rule (Capitalized identifiers)
 //! - Calls: call expressions
 
 use crate::adapter::{AnalysisResult, CallInfo, ImportInfo, ImportItem, LanguageAdapter, SymbolBuilder, make_location};
 use crate::symbol_table::{SymbolId, Visibility};
+use crate::vfs::Vfs;
 use logos_core::{Position, Range, SymbolKind};
-use tree_sitter::{Node, Parser, Tree};
+use logos_parser::{pool, LanguageId};
+use std::path::{Path, PathBuf};
+use tree_sitter::{Node, Tree};
 
-pub struct GoAdapter {
-    parser: std::sync::Mutex<Parser>,
-}
+pub struct GoAdapter;
 
 impl GoAdapter {
     pub fn new() -> Result<Self, String> {
-        let mut parser = Parser::new();
-        parser
-            .set_language(&tree_sitter_go::LANGUAGE.into())
+        // Fail early when the grammar can't be loaded
+        pool::shared()
+            .checkout(LanguageId::Go)
             .map_err(|e| format!("Failed to set Go language: {}", e))?;
-        Ok(Self {
-            parser: std::sync::Mutex::new(parser),
-        })
+        Ok(Self)
     }
 
     fn parse(&self, source: &str) -> Option<Tree> {
-        let mut parser = self.parser.lock().ok()?;
-        parser.parse(source, None)
+        let mut parser = pool::shared().checkout(LanguageId::Go).ok()?;
+        parser.parse(source, None).ok()
     }
 }
 
@@ -53,10 +54,71 @@ impl LanguageAdapter for GoAdapter {
             source,
             result: AnalysisResult::default(),
             scope_stack: Vec::new(),
+            receivers: Vec::new(),
         };
 
         analyze_node(&tree.root_node(), &mut ctx);
-        ctx.result
+        link_methods(&mut ctx.result, &ctx.receivers);
+        ctx.result.with_utf16_columns(source)
+    }
+
+    fn resolve_import(&self, vfs: &dyn Vfs, from_file: &Path, import_path: &str) -> Option<PathBuf> {
+        // Only packages of the importing file's own module are in the workspace
+        let (root, module) = find_module(vfs, from_file)?;
+        let dir = if import_path == module {
+            root
+        } else {
+            root.join(import_path.strip_prefix(&module)?.strip_prefix('/')?)
+        };
+        // A package is a directory; its first source file stands for it
+        let mut files: Vec<PathBuf> = vfs
+            .read_dir(&dir)
+            .ok()?
+            .into_iter()
+            .filter(|path| {
+                let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+                name.ends_with(".go") && !name.ends_with("_test.go") && vfs.is_file(path)
+            })
+            .collect();
+        files.sort();
+        files.into_iter().next()
+    }
+}
+
+/// Directory and module path of the `go.mod` nearest above `file`
+fn find_module(vfs: &dyn Vfs, file: &Path) -> Option<(PathBuf, String)> {
+    for dir in file.ancestors().skip(1) {
+        let manifest = dir.join("go.mod");
+        if !vfs.exists(&manifest) {
+            continue;
+        }
+        let content = vfs.read_to_string(&manifest).ok()?;
+        let module = content.lines().find_map(|line| {
+            let rest = line.trim().strip_prefix("module")?;
+            let path = rest.split("//").next()?.trim().trim_matches('"');
+            (rest.starts_with(char::is_whitespace) && !path.is_empty()).then(|| path.to_string())
+        })?;
+        return Some((dir.to_path_buf(), module));
+    }
+    None
+}
+
+/// Make each method a child of its receiver type when the file declares
+/// the type
+fn link_methods(result: &mut AnalysisResult, receivers: &[(SymbolId, String)]) {
+    for (method_id, receiver) in receivers {
+        let Some(type_index) = result
+            .symbols
+            .iter()
+            .position(|s| &s.name == receiver && matches!(s.kind, SymbolKind::Struct | SymbolKind::Interface | SymbolKind::Class))
+        else {
+            continue;
+        };
+        let type_id = result.symbols[type_index].id;
+        result.symbols[type_index].children.push(*method_id);
+        if let Some(method) = result.symbols.iter_mut().find(|s| s.id == *method_id) {
+            method.parent = Some(type_id);
+        }
     }
 }
 
@@ -65,6 +127,8 @@ struct AnalysisContext<'a> {
     source: &'a str,
     result: AnalysisResult,
     scope_stack: Vec<ScopeInfo>,
+    /// Methods with the name of their receiver type
+    receivers: Vec<(SymbolId, String)>,
 }
 
 struct ScopeInfo {
@@ -93,6 +157,8 @@ impl<'a> AnalysisContext<'a> {
 
 fn analyze_node(node: &Node, ctx: &mut AnalysisContext) {
     match node.kind() {
+        "package_clause" => analyze_package(node, ctx),
+
         // imports
         "import_declaration" => analyze_import(node, ctx),
 
@@ -123,6 +189,21 @@ fn is_exported_go(name: &str) -> bool {
     name.chars().next().map(|c| c.is_uppercase()).unwrap_or(false)
 }
 
+fn analyze_package(node: &Node, ctx: &mut AnalysisContext) {
+    // package main
+    let mut cursor = node.walk();
+    let Some(name_node) = node.named_children(&mut cursor).find(|n| n.kind() == "package_identifier") else {
+        return;
+    };
+    let name = ctx.get_text(&name_node);
+    let location = make_location(&ctx.uri, node_to_range(node), node_to_range(&name_node));
+    let symbol = SymbolBuilder::new(name.clone(), SymbolKind::Package, location)
+        .visibility(Visibility::Public)
+        .qualified_name(name)
+        .build();
+    ctx.result.symbols.push(symbol);
+}
+
 fn analyze_import(node: &Node, ctx: &mut AnalysisContext) {
     // import "fmt"
     // import alias "pkg"
@@ -206,6 +287,7 @@ fn analyze_method(node: &Node, ctx: &mut AnalysisContext) {
     // Methods can be exported too (capitalized), but we still keep visibility consistent
     let exported = is_exported_go(&name);
     let visibility = if exported { Visibility::Public } else { Visibility::Private };
+    let receiver = node.child_by_field_name("receiver").and_then(|r| receiver_type(&r, ctx));
 
     let location = make_location(
         &ctx.uri,
@@ -213,15 +295,24 @@ fn analyze_method(node: &Node, ctx: &mut AnalysisContext) {
         name_node.map(|n| node_to_range(&n)).unwrap_or_else(|| node_to_range(node)),
     );
 
-    let symbol = SymbolBuilder::new(name.clone(), SymbolKind::Method, location)
-        .parent(ctx.current_scope().map(|s| s.symbol_id).unwrap_or(SymbolId(0)))
+    let qualified_name = match &receiver {
+        Some(receiver) => format!("{}.{}", receiver, name),
+        None => ctx.qualified_name(&name),
+    };
+    let mut builder = SymbolBuilder::new(name.clone(), SymbolKind::Method, location)
         .exported(exported)
         .visibility(visibility)
-        .qualified_name(ctx.qualified_name(&name))
-        .build();
+        .qualified_name(qualified_name);
+    if let Some(scope) = ctx.current_scope() {
+        builder = builder.parent(scope.symbol_id);
+    }
+    let symbol = builder.build();
 
     let symbol_id = symbol.id;
     ctx.result.symbols.push(symbol);
+    if let Some(receiver) = receiver {
+        ctx.receivers.push((symbol_id, receiver));
+    }
 
     if let Some(body) = node.child_by_field_name("body") {
         ctx.scope_stack.push(ScopeInfo { symbol_id, name });
@@ -230,6 +321,16 @@ fn analyze_method(node: &Node, ctx: &mut AnalysisContext) {
     }
 }
 
+/// Name of the type of a receiver list: `User` for `(u *User)` and
+/// `(s Stack[T])`
+fn receiver_type(receiver: &Node, ctx: &AnalysisContext) -> Option<String> {
+    let mut cursor = receiver.walk();
+    let param = receiver.named_children(&mut cursor).find(|n| n.kind() == "parameter_declaration")?;
+    let text = ctx.get_text(&param.child_by_field_name("type")?);
+    let name = text.trim_start_matches(['*', '(', ' ']).split(['[', ')']).next()?.trim();
+    (!name.is_empty()).then(|| name.to_string())
+}
+
 fn analyze_type_declaration(node: &Node, ctx: &mut AnalysisContext) {
     // type Foo struct { ... }
     for i in 0..node.named_child_count() {
@@ -301,7 +402,7 @@ fn analyze_call(node: &Node, ctx: &mut AnalysisContext) {
     // call_expression: function + arguments
     if let Some(func) = node.child_by_field_name("function") {
         let text = ctx.get_text(&func);
-        let (callee_name, qualified_name) = if let Some(last) = text.split('.').last() {
+        let (callee_name, qualified_name) = if let Some(last) = text.split('.').next_back() {
             (last.to_string(), if text.contains('.') { Some(text) } else { None })
         } else {
             (text.clone(), None)
@@ -370,5 +471,41 @@ func helper() {}
         assert!(result.symbols.iter().any(|s| s.name == "helper"));
         assert!(result.calls.len() >= 2);
     }
+
+    #[test]
+    fn go_package_and_receivers() {
+        let adapter = GoAdapter::new().unwrap();
+        let src = "package store\n\nfunc (s *Stack[T]) Push(v T) {}\n\ntype Stack[T any] struct{}\n\nfunc (Other) Pop() {}\n";
+        let result = adapter.analyze("file:///store.go", src);
+
+        let package = result.symbols.iter().find(|s| s.kind == SymbolKind::Package).unwrap();
+        assert_eq!(package.name, "store");
+        let stack = result.symbols.iter().find(|s| s.name == "Stack").unwrap();
+        let push = result.symbols.iter().find(|s| s.name == "Push").unwrap();
+        assert_eq!(push.qualified_name, "Stack.Push");
+        assert_eq!(push.parent, Some(stack.id));
+        assert_eq!(stack.children, vec![push.id]);
+        let pop = result.symbols.iter().find(|s| s.name == "Pop").unwrap();
+        assert_eq!((pop.qualified_name.as_str(), pop.parent), ("Other.Pop", None));
+    }
+
+    #[test]
+    fn go_resolve_import_through_go_mod() {
+        let vfs = crate::vfs::MemoryFs::new();
+        vfs.insert("/ws/go.mod", "module example.com/app\n\ngo 1.22\n");
+        vfs.insert("/ws/cmd/main.go", "package main\n");
+        vfs.insert("/ws/internal/db/db_test.go", "package db\n");
+        vfs.insert("/ws/internal/db/conn.go", "package db\n");
+        vfs.insert("/ws/internal/db/query.go", "package db\n");
+
+        let adapter = GoAdapter::new().unwrap();
+        let from = Path::new("/ws/cmd/main.go");
+        assert_eq!(
+            adapter.resolve_import(&vfs, from, "example.com/app/internal/db"),
+            Some(PathBuf::from("/ws/internal/db/conn.go"))
+        );
+        assert_eq!(adapter.resolve_import(&vfs, from, "example.com/application/db"), None);
+        assert_eq!(adapter.resolve_import(&vfs, from, "fmt"), None);
+    }
 }
 
@@ -4,86 +4,240 @@
 
 use crate::adapter::{AnalysisResult, LanguageAdapter};
 use crate::c_adapter::CAdapter;
+use crate::compile_commands::CompilationDatabase;
 use crate::cpp_adapter::CppAdapter;
+use crate::generated::{is_generated, tag_generated};
+use crate::glob::IgnoreRules;
 use crate::go_adapter::GoAdapter;
 use crate::java_adapter::JavaAdapter;
+use crate::kotlin_adapter::KotlinAdapter;
 use crate::python_adapter::PythonAdapter;
 use crate::rust_adapter::RustAdapter;
-use crate::symbol_table::{CallSite, CallType, ProjectIndex};
+use crate::symbol_table::{is_type_kind, CallSite, CallType, FileRecord, ProjectIndex};
 use crate::typescript_adapter::TypeScriptAdapter;
-use std::fs;
-use std::path::Path;
-use std::sync::Arc;
+use crate::vfs::{OsFs, Vfs};
+use logos_core::uri;
+use logos_parser::LanguageId;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Whether a file or directory named `name` is left out of workspace walks:
+/// hidden entries and dependency or build output directories
+pub fn is_skipped_name(name: &str) -> bool {
+    name.starts_with('.') || matches!(name, "node_modules" | "target" | "dist" | "build" | "__pycache__")
+}
+
+/// Most files of a directory handed to an adapter at once
+const BATCH_SIZE: usize = 32;
+
+/// State of a walk over the files of a directory
+struct Walk<'a> {
+    root: &'a Path,
+    /// Exclude globs, and the patterns of the ignore files read so far
+    rules: IgnoreRules,
+    /// Whether files indexed as they are now are left out
+    refresh: bool,
+    /// URIs of the files found, when refreshing
+    seen: HashSet<String>,
+}
 
 /// Project indexer that coordinates language adapters
 pub struct ProjectIndexer {
     /// The project index containing all indexed data
     pub index: Arc<ProjectIndex>,
     /// Available language adapters
-    adapters: Vec<Box<dyn LanguageAdapter>>,
+    adapters: Vec<Arc<dyn LanguageAdapter>>,
+    /// Languages whose adapter failed to load or crashed, with the reason
+    failures: Mutex<HashMap<String, String>>,
+    /// Filesystem all reads go through
+    vfs: Arc<dyn Vfs>,
+    /// Threads that analyze files of a directory at the same time
+    worker_threads: usize,
+    /// Globs of files and directories left out, on top of the ignore files
+    exclude: Vec<String>,
 }
 
 impl ProjectIndexer {
     pub fn new() -> Self {
+        Self::with_vfs(Arc::new(OsFs))
+    }
+
+    /// Create an indexer that reads files through `vfs` instead of the local disk
+    pub fn with_vfs(vfs: Arc<dyn Vfs>) -> Self {
         let mut indexer = Self {
             index: Arc::new(ProjectIndex::new()),
             adapters: Vec::new(),
+            failures: Mutex::new(HashMap::new()),
+            vfs,
+            worker_threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            exclude: Vec::new(),
         };
 
-        // Register built-in adapters
-        if let Ok(ts_adapter) = TypeScriptAdapter::new() {
-            indexer.register_adapter(Box::new(ts_adapter));
-        }
-        if let Ok(py_adapter) = PythonAdapter::new() {
-            indexer.register_adapter(Box::new(py_adapter));
-        }
-        if let Ok(go_adapter) = GoAdapter::new() {
-            indexer.register_adapter(Box::new(go_adapter));
-        }
-        if let Ok(rust_adapter) = RustAdapter::new() {
-            indexer.register_adapter(Box::new(rust_adapter));
+        // Register built-in adapters; a grammar that fails to load only disables its language
+        indexer.register_builtin("typescript", TypeScriptAdapter::new());
+        indexer.register_builtin("python", PythonAdapter::new());
+        indexer.register_builtin("go", GoAdapter::new());
+        indexer.register_builtin("rust", RustAdapter::new());
+        indexer.register_builtin("c", CAdapter::new());
+        indexer.register_builtin("cpp", CppAdapter::new());
+        indexer.register_builtin("java", JavaAdapter::new());
+        indexer.register_builtin("kotlin", KotlinAdapter::new());
+
+        indexer
+    }
+
+    fn register_builtin<A: LanguageAdapter + 'static>(&mut self, language: &str, adapter: Result<A, String>) {
+        match adapter {
+            Ok(adapter) => self.register_adapter(Box::new(adapter)),
+            Err(e) => self.record_failure(language, e),
         }
-        if let Ok(c_adapter) = CAdapter::new() {
-            indexer.register_adapter(Box::new(c_adapter));
+    }
+
+    fn record_failure(&self, language: &str, reason: String) {
+        if let Ok(mut failures) = self.failures.lock() {
+            failures.entry(language.to_string()).or_insert(reason);
         }
-        if let Ok(cpp_adapter) = CppAdapter::new() {
-            indexer.register_adapter(Box::new(cpp_adapter));
+    }
+
+    /// Languages whose adapter failed to load or crashed, with the reason
+    pub fn language_failures(&self) -> HashMap<String, String> {
+        self.failures.lock().map(|f| f.clone()).unwrap_or_default()
+    }
+
+    /// Resolve C and C++ includes with the flags of a compilation database,
+    /// for files indexed from now on
+    pub fn set_compilation_database(&self, db: Option<Arc<CompilationDatabase>>) {
+        for adapter in &self.adapters {
+            adapter.set_compilation_database(db.clone());
         }
-        if let Ok(java_adapter) = JavaAdapter::new() {
-            indexer.register_adapter(Box::new(java_adapter));
+    }
+
+    /// Look up C and C++ includes in `paths` too, after the directories of
+    /// the compilation database, for files indexed from now on
+    pub fn set_include_paths(&self, paths: Vec<std::path::PathBuf>) {
+        for adapter in &self.adapters {
+            adapter.set_include_paths(paths.clone());
         }
+    }
 
-        indexer
+    /// Analyze files of a directory on `threads` threads (at least one);
+    /// defaults to the available parallelism
+    pub fn set_worker_threads(&mut self, threads: usize) {
+        self.worker_threads = threads.max(1);
+    }
+
+    /// Leave out the files and directories matching `globs`, written as
+    /// `.gitignore` patterns relative to the indexed directory, on top of
+    /// what the `.gitignore` and `.logosignore` files list
+    pub fn set_exclude(&mut self, globs: Vec<String>) {
+        self.exclude = globs;
+    }
+
+    /// Ignore rules of a walk, before the ignore files are read
+    fn ignore_rules(&self) -> IgnoreRules {
+        let mut rules = IgnoreRules::default();
+        rules.add_globs(self.exclude.iter().map(String::as_str));
+        rules
     }
 
     /// Register a language adapter
     pub fn register_adapter(&mut self, adapter: Box<dyn LanguageAdapter>) {
-        self.adapters.push(adapter);
+        self.adapters.push(Arc::from(adapter));
+    }
+
+    /// Keep the documentation and type information of indexed symbols
+    /// within `bytes`, unlimited with `None`. Past it, those of the least
+    /// recently used files are dropped, and the files analyzed again when
+    /// their symbols are asked for. Adapters registered later can't read
+    /// their files' details back.
+    pub fn set_memory_budget(&self, bytes: Option<usize>) {
+        let adapters = self.adapters.clone();
+        let vfs = Arc::clone(&self.vfs);
+        self.index.symbols.set_detail_budget(
+            bytes,
+            Arc::new(move |uri: &str| {
+                let path = uri::uri_to_path(uri)
+                    .or_else(|| crate::archive::parse_archive_uri(uri).map(|(archive, entry)| archive.join(entry)))?;
+                let adapter = find_adapter(&adapters, vfs.as_ref(), &path)?;
+                let content = vfs.read_to_string(&path).ok()?;
+                let result = panic::catch_unwind(AssertUnwindSafe(|| adapter.analyze(uri, &content))).ok()?;
+                Some(result.symbols)
+            }),
+        );
     }
 
-    /// Find an adapter for a file
     fn find_adapter(&self, path: &Path) -> Option<&dyn LanguageAdapter> {
-        self.adapters
-            .iter()
-            .find(|a| a.can_handle(path))
-            .map(|a| a.as_ref())
+        find_adapter(&self.adapters, self.vfs.as_ref(), path)
     }
 
     /// Index a single file
     pub fn index_file(&self, path: &Path) -> Result<AnalysisResult, String> {
+        let (adapter, uri, content) = self.read_file(path)?;
+        let result = self.analyze(adapter, &uri, &content)?;
+        self.add_analysis(path, adapter, &uri, &content, &result);
+        self.add_type_relations(&result);
+        Ok(result)
+    }
+
+    /// Find the adapter for a file and read it, with its URI
+    fn read_file(&self, path: &Path) -> Result<(&dyn LanguageAdapter, String, String), String> {
         let adapter = self
             .find_adapter(path)
             .ok_or_else(|| format!("No adapter found for {:?}", path))?;
 
-        let content = fs::read_to_string(path)
+        let content = self
+            .vfs
+            .read_to_string(path)
             .map_err(|e| format!("Failed to read file {:?}: {}", path, e))?;
 
-        let uri = path_to_uri(path);
-        let result = adapter.analyze(&uri, &content);
+        Ok((adapter, self.path_to_uri(path), content))
+    }
+
+    /// Analyze the content of a file without touching the index
+    fn analyze(&self, adapter: &dyn LanguageAdapter, uri: &str, content: &str) -> Result<AnalysisResult, String> {
+        let result = panic::catch_unwind(AssertUnwindSafe(|| adapter.analyze(uri, content)))
+            .map_err(|_| {
+                let reason = format!("{} adapter crashed while analyzing {}", adapter.language_id(), uri);
+                self.record_failure(adapter.language_id(), reason.clone());
+                reason
+            })?;
+        Ok(finish_analysis(uri, content, result))
+    }
 
-        // Add symbols to the index
+    /// Analyze `(uri, content)` files of one adapter together. If the batch
+    /// crashes, the files are analyzed one by one so only those that crash
+    /// on their own fail.
+    fn analyze_batch(&self, adapter: &dyn LanguageAdapter, files: &[(&str, &str)]) -> Vec<Result<AnalysisResult, String>> {
+        match panic::catch_unwind(AssertUnwindSafe(|| adapter.analyze_batch(files))) {
+            Ok(results) if results.len() == files.len() => files
+                .iter()
+                .zip(results)
+                .map(|(&(uri, content), result)| Ok(finish_analysis(uri, content, result)))
+                .collect(),
+            _ => files.iter().map(|&(uri, content)| self.analyze(adapter, uri, content)).collect(),
+        }
+    }
+
+    /// Add the symbols, calls, imports and exports of a file analyzed from
+    /// `content` to the index, and record what it was analyzed from. Type
+    /// relations are added separately, by [`Self::add_type_relations`], as
+    /// their parents may be declared in other files.
+    fn add_analysis(&self, path: &Path, adapter: &dyn LanguageAdapter, uri: &str, content: &str, result: &AnalysisResult) {
+        let record = FileRecord::new(content, self.vfs.modified(path), adapter.language_id(), adapter.version());
+        self.index.files.insert(uri.to_string(), record);
+
+        // Add symbols to the index, linking the subtypes that were
+        // waiting for them
         for symbol in &result.symbols {
             self.index.symbols.add_symbol(symbol.clone());
+            if is_type_kind(symbol.kind) {
+                self.index.type_hierarchy.link_unresolved(&symbol.name, symbol.id);
+            }
         }
 
         // Add call sites to call graph
@@ -100,7 +254,7 @@ impl ProjectIndexer {
                     caller: caller_scope.id,
                     callee: caller_scope.id, // Placeholder - should be resolved
                     location: crate::symbol_table::SymbolLocation {
-                        uri: uri.clone(),
+                        uri: uri.to_string(),
                         range: call.location,
                         selection_range: call.location,
                     },
@@ -114,7 +268,29 @@ impl ProjectIndexer {
             }
         }
 
-        // Add type relationships
+        // Add imports to dependency graph, keyed by the canonical paths
+        // behind the symbol URIs
+        let file_path = self.vfs.canonicalize(path);
+        for import in &result.imports {
+            if let Some(resolved) = adapter.resolve_import(self.vfs.as_ref(), path, &import.module_path) {
+                self.index.dependencies.add_import(file_path.clone(), self.vfs.canonicalize(&resolved));
+            }
+        }
+
+        // Set exports
+        let export_symbols: Vec<_> = result
+            .symbols
+            .iter()
+            .filter(|s| s.exported)
+            .map(|s| s.id)
+            .collect();
+        self.index.dependencies.set_exports(file_path, export_symbols);
+    }
+
+    /// Link the types of an analyzed file to their supertypes: a parent
+    /// declared in the same file first, then any indexed type of that name.
+    /// A parent not indexed is linked when a type of its name is.
+    fn add_type_relations(&self, result: &AnalysisResult) {
         for relation in &result.type_relations {
             // Find the child symbol
             if let Some(child) = result
@@ -122,97 +298,235 @@ impl ProjectIndexer {
                 .iter()
                 .find(|s| s.name == relation.child_name)
             {
-                // Find or create the parent symbol reference
-                // In a full implementation, we'd resolve across files
-                if let Some(parent) = result
+                let parent_id = result
                     .symbols
                     .iter()
                     .find(|s| s.name == relation.parent_name)
-                {
-                    if relation.is_implements {
-                        self.index.type_hierarchy.add_implements(child.id, parent.id);
-                    } else {
-                        self.index.type_hierarchy.add_extends(child.id, parent.id);
+                    .map(|s| s.id)
+                    .or_else(|| {
+                        self.index
+                            .symbols
+                            .find_by_name(&relation.parent_name)
+                            .into_iter()
+                            .find(|s| is_type_kind(s.kind))
+                            .map(|s| s.id)
+                    });
+                match parent_id {
+                    Some(parent_id) if relation.is_implements => {
+                        self.index.type_hierarchy.add_implements(child.id, parent_id)
                     }
+                    Some(parent_id) => self.index.type_hierarchy.add_extends(child.id, parent_id),
+                    None => self.index.type_hierarchy.add_unresolved(child.id, &relation.parent_name, relation.is_implements),
                 }
             }
         }
+    }
 
-        // Add imports to dependency graph
-        let file_path = path.to_path_buf();
-        for import in &result.imports {
-            if let Some(resolved) = adapter.resolve_import(path, &import.module_path) {
-                self.index.dependencies.add_import(file_path.clone(), resolved);
-            }
-        }
+    /// Index a directory recursively. Files are analyzed on the worker
+    /// threads and merged into the index as they finish; type relations
+    /// are linked once every file's symbols are in.
+    pub fn index_directory(&self, dir: &Path) -> Result<IndexingStats, String> {
+        let mut stats = IndexingStats::default();
+        let started = Instant::now();
 
-        // Set exports
-        let export_symbols: Vec<_> = result
-            .symbols
-            .iter()
-            .filter(|s| s.exported)
-            .map(|s| s.id)
-            .collect();
-        self.index.dependencies.set_exports(file_path, export_symbols);
+        let mut files = Vec::new();
+        let mut walk = Walk { root: dir, rules: self.ignore_rules(), refresh: false, seen: HashSet::new() };
+        self.collect_files(dir, &mut walk, &mut stats, &mut files)?;
+        self.index_files(files, false, &mut stats);
 
-        Ok(result)
+        stats.duration = started.elapsed();
+        Ok(stats)
     }
 
-    /// Index a directory recursively
-    pub fn index_directory(&self, dir: &Path) -> Result<IndexingStats, String> {
+    /// Bring an index, such as one restored from disk, up to date with
+    /// `dir`. Files are analyzed again when their content changed, when
+    /// they were indexed by another version of their adapter, or when they
+    /// weren't indexed yet; files that no longer exist are dropped.
+    pub fn refresh_directory(&self, dir: &Path) -> Result<IndexingStats, String> {
         let mut stats = IndexingStats::default();
+        let started = Instant::now();
+
+        let mut walk = Walk { root: dir, rules: self.ignore_rules(), refresh: true, seen: HashSet::new() };
+        let mut files = Vec::new();
+        self.collect_files(dir, &mut walk, &mut stats, &mut files)?;
+        self.index_files(files, true, &mut stats);
 
-        self.index_directory_recursive(dir, &mut stats)?;
+        let prefix = format!("{}/", self.vfs.uri(dir).trim_end_matches('/'));
+        let mut indexed = self.index.symbols.files();
+        indexed.extend(self.index.files.iter().map(|entry| entry.key().clone()));
+        indexed.sort_unstable();
+        indexed.dedup();
+        for uri in indexed {
+            if uri.starts_with(&prefix) && !walk.seen.contains(&uri) {
+                self.index.remove_file(&uri);
+            }
+        }
 
+        stats.duration = started.elapsed();
         Ok(stats)
     }
 
-    fn index_directory_recursive(&self, dir: &Path, stats: &mut IndexingStats) -> Result<(), String> {
-        let entries = fs::read_dir(dir)
+    /// List the files under `dir` an adapter handles, with its language,
+    /// leaving out what the ignore rules of the walk match. When refreshing,
+    /// files whose modification time and adapter are those they were
+    /// indexed with are counted and left out.
+    fn collect_files(
+        &self,
+        dir: &Path,
+        walk: &mut Walk,
+        stats: &mut IndexingStats,
+        files: &mut Vec<(PathBuf, String)>,
+    ) -> Result<(), String> {
+        let entries = self
+            .vfs
+            .read_dir(dir)
             .map_err(|e| format!("Failed to read directory {:?}: {}", dir, e))?;
+        walk.rules.read_dir(self.vfs.as_ref(), walk.root, dir);
 
-        for entry in entries.flatten() {
-            let path = entry.path();
+        for path in entries {
 
             // Skip hidden files and common ignored directories
-            if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-                if name.starts_with('.')
-                    || name == "node_modules"
-                    || name == "target"
-                    || name == "dist"
-                    || name == "build"
-                    || name == "__pycache__"
-                {
+            if path.file_name().and_then(|n| n.to_str()).is_some_and(is_skipped_name) {
+                continue;
+            }
+
+            let is_dir = self.vfs.is_dir(&path);
+            let relative = path.strip_prefix(walk.root).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+            if walk.rules.is_ignored(&relative, is_dir) {
+                continue;
+            }
+
+            if is_dir {
+                self.collect_files(&path, walk, stats, files)?;
+            } else if self.vfs.is_file(&path) {
+                let Some(adapter) = self.find_adapter(&path) else {
                     continue;
+                };
+                if walk.refresh {
+                    let uri = self.path_to_uri(&path);
+                    let unchanged = self.index.files.get(&uri).is_some_and(|record| {
+                        record.is_current(adapter.language_id(), adapter.version())
+                            && record.is_unmodified(self.vfs.modified(&path))
+                    });
+                    walk.seen.insert(uri);
+                    if unchanged {
+                        stats.files_unchanged += 1;
+                        continue;
+                    }
                 }
+                files.push((path, adapter.language_id().to_string()));
             }
+        }
 
-            if path.is_dir() {
-                self.index_directory_recursive(&path, stats)?;
-            } else if path.is_file()
-                && self.find_adapter(&path).is_some() {
-                    match self.index_file(&path) {
-                        Ok(result) => {
-                            stats.files_indexed += 1;
-                            stats.symbols_found += result.symbols.len();
-                            stats.imports_found += result.imports.len();
-                            stats.exports_found += result.exports.len();
-                            stats.calls_found += result.calls.len();
-                            stats.type_relations_found += result.type_relations.len();
-                        }
-                        Err(e) => {
-                            stats.errors.push(format!("{:?}: {}", path, e));
+        Ok(())
+    }
+
+    /// Analyze `files` on the worker threads. With `reindex`, a file whose
+    /// content and adapter are those it was indexed with is counted as
+    /// unchanged; any other drops what the index held for it first. Each
+    /// worker takes the next file in turn; the index tables lock per entry,
+    /// so workers merge their results without waiting on each other.
+    fn index_files(&self, files: Vec<(PathBuf, String)>, reindex: bool, stats: &mut IndexingStats) {
+        let batches = batches(files);
+        let next = AtomicUsize::new(0);
+        let workers = self.worker_threads.min(batches.len()).max(1);
+
+        let partials: Vec<(IndexingStats, Vec<AnalysisResult>)> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..workers)
+                .map(|_| {
+                    scope.spawn(|| {
+                        let mut stats = IndexingStats::default();
+                        let mut with_relations = Vec::new();
+                        while let Some(batch) = batches.get(next.fetch_add(1, Ordering::Relaxed)) {
+                            self.index_batch(batch, reindex, &mut stats, &mut with_relations);
                         }
+                        (stats, with_relations)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+                .collect()
+        });
+
+        for (partial, with_relations) in partials {
+            stats.merge(partial);
+            for result in &with_relations {
+                self.add_type_relations(result);
+            }
+        }
+        stats.errors.sort();
+    }
+
+    /// Read and analyze a batch of files of one language, adding them to the
+    /// index. Results with type relations are kept in `with_relations`.
+    fn index_batch(
+        &self,
+        batch: &[(PathBuf, String)],
+        reindex: bool,
+        stats: &mut IndexingStats,
+        with_relations: &mut Vec<AnalysisResult>,
+    ) {
+        let mut read = Vec::with_capacity(batch.len());
+        for (path, language) in batch {
+            let (adapter, uri, content) = match self.read_file(path) {
+                Ok(file) => file,
+                Err(e) => {
+                    if reindex {
+                        self.index.remove_file(&self.path_to_uri(path));
                     }
+                    stats.errors.push(format!("{:?}: {}", path, e));
+                    continue;
                 }
+            };
+            if reindex {
+                if self.is_indexed(path, adapter, &uri, &content) {
+                    stats.files_unchanged += 1;
+                    continue;
+                }
+                self.index.remove_file(&uri);
+            }
+            read.push((path, language, adapter, uri, content));
         }
+        // Batches hold one language, so one adapter
+        let Some(&(_, _, adapter, _, _)) = read.first() else {
+            return;
+        };
 
-        Ok(())
+        let sources: Vec<(&str, &str)> = read.iter().map(|(_, _, _, uri, content)| (uri.as_str(), content.as_str())).collect();
+        let results = self.analyze_batch(adapter, &sources);
+        for ((path, language, adapter, uri, content), result) in read.iter().zip(results) {
+            match result {
+                Ok(result) => {
+                    self.add_analysis(path, *adapter, uri, content, &result);
+                    stats.record(language, &result);
+                    if !result.type_relations.is_empty() {
+                        with_relations.push(result);
+                    }
+                }
+                Err(e) => stats.errors.push(format!("{:?}: {}", path, e)),
+            }
+        }
+    }
+
+    /// Whether the index holds the file at `path` as `adapter` extracts it
+    /// from `content`. Its recorded modification time is brought up to date,
+    /// so the next refresh needn't read it.
+    fn is_indexed(&self, path: &Path, adapter: &dyn LanguageAdapter, uri: &str, content: &str) -> bool {
+        let Some(mut record) = self.index.files.get_mut(uri) else {
+            return false;
+        };
+        if !record.is_current(adapter.language_id(), adapter.version()) || !record.has_content(content) {
+            return false;
+        }
+        record.set_modified(self.vfs.modified(path));
+        true
     }
 
     /// Re-index a single file (for incremental updates)
     pub fn reindex_file(&self, path: &Path) -> Result<AnalysisResult, String> {
-        let uri = path_to_uri(path);
+        let uri = self.path_to_uri(path);
 
         // Remove old data for this file
         self.index.remove_file(&uri);
@@ -221,6 +535,29 @@ impl ProjectIndexer {
         self.index_file(path)
     }
 
+    /// Re-key files affected by renaming `from` to `to` (URIs of a file or a
+    /// directory). What was indexed for the moved files is kept and moved
+    /// in place, without analyzing them again. Returns the number of files
+    /// moved.
+    pub fn rename_files(&self, from: &str, to: &str) -> usize {
+        let moved: Vec<(String, String)> = self
+            .index
+            .files
+            .iter()
+            .filter_map(|record| crate::rebase_uri(record.key(), from, to).map(|new| (record.key().clone(), new)))
+            .collect();
+
+        for (old, new) in &moved {
+            self.index.rename_file(old, new);
+        }
+        moved.len()
+    }
+
+    /// Canonical URI of a file as seen through the VFS
+    fn path_to_uri(&self, path: &Path) -> String {
+        self.vfs.uri(path)
+    }
+
     /// Get the project index
     pub fn get_index(&self) -> Arc<ProjectIndex> {
         Arc::clone(&self.index)
@@ -233,26 +570,104 @@ impl Default for ProjectIndexer {
     }
 }
 
+/// Stable IDs and generated-code tags for what an adapter extracted from a
+/// file
+fn finish_analysis(uri: &str, content: &str, mut result: AnalysisResult) -> AnalysisResult {
+    result.assign_stable_ids();
+    if is_generated(uri, content) {
+        tag_generated(&mut result.symbols);
+    }
+    result
+}
+
+/// Group `(path, language)` files by directory and language, in batches of
+/// at most [`BATCH_SIZE`] files for one adapter to analyze together
+fn batches(files: Vec<(PathBuf, String)>) -> Vec<Vec<(PathBuf, String)>> {
+    let mut groups: BTreeMap<(PathBuf, String), Vec<(PathBuf, String)>> = BTreeMap::new();
+    for (path, language) in files {
+        let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+        groups.entry((dir, language.clone())).or_default().push((path, language));
+    }
+    groups
+        .into_values()
+        .flat_map(|group| group.chunks(BATCH_SIZE).map(<[_]>::to_vec).collect::<Vec<_>>())
+        .collect()
+}
+
+/// Find an adapter for a file. Files without an extension are matched by
+/// name or content (`SConstruct`, `#!/usr/bin/env python3`).
+fn find_adapter<'a>(adapters: &'a [Arc<dyn LanguageAdapter>], vfs: &dyn Vfs, path: &Path) -> Option<&'a dyn LanguageAdapter> {
+    if let Some(adapter) = adapters.iter().find(|a| a.can_handle(path)) {
+        return Some(adapter.as_ref());
+    }
+    if path.extension().is_some() {
+        return None;
+    }
+    let file_name = path.file_name()?.to_str()?;
+    let lang = LanguageId::from_file_name(file_name)
+        .or_else(|| LanguageId::from_content(&vfs.read_to_string(path).ok()?))?
+        .base();
+    adapters
+        .iter()
+        .find(|a| LanguageId::from_str(a.language_id()).map(LanguageId::base) == Some(lang))
+        .map(|a| a.as_ref())
+}
+
 /// Statistics from indexing
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct IndexingStats {
     pub files_indexed: usize,
+    /// Files skipped by a refresh because they hadn't changed
+    pub files_unchanged: usize,
+    /// Indexed file count keyed by adapter language id
+    pub files_by_language: HashMap<String, usize>,
     pub symbols_found: usize,
     pub imports_found: usize,
     pub exports_found: usize,
     pub calls_found: usize,
     pub type_relations_found: usize,
     pub errors: Vec<String>,
+    /// Wall-clock time spent indexing
+    pub duration: Duration,
 }
 
-/// Convert a file path to a URI
-fn path_to_uri(path: &Path) -> String {
-    format!("file://{}", path.to_string_lossy())
+impl IndexingStats {
+    /// Count a file of `language` indexed with `result`
+    fn record(&mut self, language: &str, result: &AnalysisResult) {
+        self.files_indexed += 1;
+        *self.files_by_language.entry(language.to_string()).or_insert(0) += 1;
+        self.symbols_found += result.symbols.len();
+        self.imports_found += result.imports.len();
+        self.exports_found += result.exports.len();
+        self.calls_found += result.calls.len();
+        self.type_relations_found += result.type_relations.len();
+    }
+
+    /// Add the counts of `other`, gathered on another worker
+    fn merge(&mut self, other: IndexingStats) {
+        self.files_indexed += other.files_indexed;
+        self.files_unchanged += other.files_unchanged;
+        for (language, count) in other.files_by_language {
+            *self.files_by_language.entry(language).or_insert(0) += count;
+        }
+        self.symbols_found += other.symbols_found;
+        self.imports_found += other.imports_found;
+        self.exports_found += other.exports_found;
+        self.calls_found += other.calls_found;
+        self.type_relations_found += other.type_relations_found;
+        self.errors.extend(other.errors);
+    }
 }
 
+/// Convert a file path to a URI
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::archive::ArchiveFs;
+    use crate::symbol_table::SmartSymbol;
+    use crate::vfs::MemoryFs;
+    use std::fs;
+    use std::path::PathBuf;
     use std::io::Write;
     use tempfile::tempdir;
 
@@ -315,4 +730,369 @@ export class User {
         assert_eq!(stats.files_indexed, 2);
         assert!(stats.symbols_found >= 3);
     }
+
+    struct CrashingAdapter;
+
+    impl LanguageAdapter for CrashingAdapter {
+        fn language_id(&self) -> &str {
+            "crashy"
+        }
+
+        fn file_extensions(&self) -> &[&str] {
+            &["crashy"]
+        }
+
+        fn analyze(&self, _uri: &str, _source: &str) -> AnalysisResult {
+            panic!("adapter bug");
+        }
+    }
+
+    #[test]
+    fn test_adapter_crash_is_recorded() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("bad.crashy"), "boom").unwrap();
+        fs::write(dir.path().join("ok.ts"), "export function ok() {}").unwrap();
+
+        let mut indexer = ProjectIndexer::new();
+        indexer.register_adapter(Box::new(CrashingAdapter));
+        let stats = indexer.index_directory(dir.path()).unwrap();
+
+        // Other languages keep indexing
+        assert_eq!(stats.files_indexed, 1);
+        assert_eq!(stats.errors.len(), 1);
+        assert!(indexer.language_failures().contains_key("crashy"));
+    }
+
+    /// Records the size of each batch it analyzes
+    struct BatchingAdapter(Arc<Mutex<Vec<usize>>>);
+
+    impl LanguageAdapter for BatchingAdapter {
+        fn language_id(&self) -> &str {
+            "batchy"
+        }
+
+        fn file_extensions(&self) -> &[&str] {
+            &["batchy"]
+        }
+
+        fn analyze(&self, uri: &str, _source: &str) -> AnalysisResult {
+            let location = crate::adapter::make_location(uri, Default::default(), Default::default());
+            let symbol = crate::adapter::SymbolBuilder::new("item", logos_core::SymbolKind::Variable, location).build();
+            AnalysisResult { symbols: vec![symbol], ..Default::default() }
+        }
+
+        fn analyze_batch(&self, files: &[(&str, &str)]) -> Vec<AnalysisResult> {
+            self.0.lock().unwrap().push(files.len());
+            files.iter().map(|(uri, source)| self.analyze(uri, source)).collect()
+        }
+    }
+
+    #[test]
+    fn test_files_of_a_directory_are_analyzed_together() {
+        let vfs = MemoryFs::new();
+        for path in ["/ws/a/1.batchy", "/ws/a/2.batchy", "/ws/a/3.batchy", "/ws/b/4.batchy"] {
+            vfs.insert(path, "item");
+        }
+        let batches = Arc::new(Mutex::new(Vec::new()));
+        let mut indexer = ProjectIndexer::with_vfs(Arc::new(vfs));
+        indexer.register_adapter(Box::new(BatchingAdapter(batches.clone())));
+        let stats = indexer.index_directory(Path::new("/ws")).unwrap();
+
+        assert_eq!((stats.files_indexed, stats.symbols_found), (4, 4));
+        let mut sizes = batches.lock().unwrap().clone();
+        sizes.sort();
+        assert_eq!(sizes, vec![1, 3]);
+    }
+
+    #[test]
+    fn test_rename_directory() {
+        let dir = tempdir().unwrap();
+        let old_dir = dir.path().join("old");
+        fs::create_dir(&old_dir).unwrap();
+        fs::write(old_dir.join("user.ts"), "export class User {}\n").unwrap();
+
+        let indexer = ProjectIndexer::new();
+        indexer.index_directory(dir.path()).unwrap();
+
+        let new_dir = dir.path().join("new");
+        fs::rename(&old_dir, &new_dir).unwrap();
+        let moved = indexer.rename_files(&indexer.path_to_uri(&old_dir), &indexer.path_to_uri(&new_dir));
+        assert_eq!(moved, 1);
+
+        let files = indexer.get_index().symbols.files();
+        assert_eq!(files, vec![indexer.path_to_uri(&new_dir.join("user.ts"))]);
+    }
+
+    #[test]
+    fn test_rename_moves_index_in_place() {
+        let vfs = MemoryFs::new();
+        vfs.insert("/ws/src/main.py", "from .util import helper\n\ndef main():\n    helper()\n");
+        vfs.insert("/ws/src/util.py", "def helper():\n    pass\n");
+        let indexer = ProjectIndexer::with_vfs(Arc::new(vfs));
+        indexer.index_directory(Path::new("/ws")).unwrap();
+        let index = indexer.get_index();
+        assert_eq!(indexer.rename_files("file:///ws/src", "file:///ws/lib"), 2);
+
+        // Nothing exists at the new paths, so the index was moved rather than rebuilt
+        let helper = index.symbols.find_by_name("helper");
+        assert_eq!(helper.len(), 1);
+        assert_eq!(helper[0].location.uri, "file:///ws/lib/util.py");
+        assert_eq!(index.symbols.get(helper[0].id).unwrap().name, "helper");
+        let mut files = index.symbols.files();
+        files.sort();
+        assert_eq!(files, vec!["file:///ws/lib/main.py", "file:///ws/lib/util.py"]);
+        assert!(index.files.contains_key("file:///ws/lib/util.py"));
+        assert!(!index.files.contains_key("file:///ws/src/util.py"));
+        let deps = index.dependencies.get_imports(&PathBuf::from("/ws/lib/main.py"));
+        assert_eq!(deps, vec![PathBuf::from("/ws/lib/util.py")]);
+        let importers = index.dependencies.get_importers(&PathBuf::from("/ws/lib/util.py"));
+        assert_eq!(importers, vec![PathBuf::from("/ws/lib/main.py")]);
+        assert!(!index.call_graph.is_empty());
+        assert!(index.call_graph.all().iter().all(|call| call.location.uri.starts_with("file:///ws/lib/")));
+    }
+
+    #[test]
+    fn test_rename_then_recreate_old_path() {
+        let vfs = Arc::new(MemoryFs::new());
+        vfs.insert("/ws/a.py", "class Base:\n    pass\n\nclass Helper(Base):\n    def run(self):\n        pass\n");
+        let indexer = ProjectIndexer::with_vfs(vfs.clone());
+        indexer.index_directory(Path::new("/ws")).unwrap();
+        let index = indexer.get_index();
+
+        vfs.insert("/ws/b.py", vfs.remove(Path::new("/ws/a.py")).unwrap());
+        indexer.rename_files("file:///ws/a.py", "file:///ws/b.py");
+        let moved = index.symbols.get_file_symbols("file:///ws/b.py");
+
+        // The moved symbols have the ids analyzing the file at its new path gives
+        let (adapter, uri, content) = indexer.read_file(Path::new("/ws/b.py")).unwrap();
+        let fresh = indexer.analyze(adapter, &uri, &content).unwrap();
+        let mut moved_ids: Vec<_> = moved.iter().map(|s| s.id).collect();
+        let mut fresh_ids: Vec<_> = fresh.symbols.iter().map(|s| s.id).collect();
+        moved_ids.sort_by_key(|id| id.0);
+        fresh_ids.sort_by_key(|id| id.0);
+        assert_eq!(moved_ids, fresh_ids);
+        let helper = moved.iter().find(|s| s.name == "Helper").unwrap();
+        let run = moved.iter().find(|s| s.name == "run").unwrap();
+        assert_eq!(run.parent, Some(helper.id));
+        let base = moved.iter().find(|s| s.name == "Base").unwrap();
+        assert_eq!(index.type_hierarchy.get_supertypes(helper.id), vec![base.id]);
+
+        // A new file at the old path doesn't take over the moved symbols
+        vfs.insert("/ws/a.py", "class Helper:\n    pass\n");
+        indexer.index_file(Path::new("/ws/a.py")).unwrap();
+        let mut uris: Vec<_> = index.symbols.find_by_name("Helper").into_iter().map(|s| s.location.uri).collect();
+        uris.sort();
+        assert_eq!(uris, vec!["file:///ws/a.py", "file:///ws/b.py"]);
+        assert!(index.symbols.get_file_symbols("file:///ws/b.py").iter().all(|s| s.location.uri == "file:///ws/b.py"));
+
+        index.remove_file("file:///ws/a.py");
+        assert_eq!(index.symbols.get_file_symbols("file:///ws/b.py").len(), moved.len());
+    }
+
+    #[test]
+    fn test_index_through_memory_vfs() {
+        let vfs = MemoryFs::new();
+        vfs.insert("/ws/src/main.py", "from .util import helper\n\ndef main():\n    helper()\n");
+        vfs.insert("/ws/src/util.py", "def helper():\n    pass\n");
+        vfs.insert("/ws/node_modules/dep/index.js", "function skipped() {}\n");
+
+        let indexer = ProjectIndexer::with_vfs(Arc::new(vfs));
+        let stats = indexer.index_directory(Path::new("/ws")).unwrap();
+
+        assert_eq!(stats.files_indexed, 2);
+        let mut files = indexer.get_index().symbols.files();
+        files.sort();
+        assert_eq!(files, vec!["file:///ws/src/main.py", "file:///ws/src/util.py"]);
+        let deps = indexer.get_index().dependencies.get_imports(&PathBuf::from("/ws/src/main.py"));
+        assert_eq!(deps, vec![PathBuf::from("/ws/src/util.py")]);
+    }
+
+    #[test]
+    fn test_refresh_skips_unchanged_files() {
+        let vfs = Arc::new(MemoryFs::new());
+        vfs.insert("/ws/a.py", "def a():\n    pass\n");
+        vfs.insert("/ws/b.py", "def b():\n    pass\n");
+        vfs.insert("/ws/empty.py", "");
+        let indexer = ProjectIndexer::with_vfs(vfs.clone());
+        let index = indexer.get_index();
+        indexer.index_directory(Path::new("/ws")).unwrap();
+        assert_eq!(index.files.len(), 3);
+
+        // Without modification times, the content hashes tell
+        let stats = indexer.refresh_directory(Path::new("/ws")).unwrap();
+        assert_eq!((stats.files_indexed, stats.files_unchanged), (0, 3));
+
+        // A changed file, and one indexed by an older version of its adapter
+        vfs.insert("/ws/b.py", "def b2():\n    pass\n");
+        index.files.get_mut("file:///ws/a.py").unwrap().adapter_version = "0".to_string();
+        let stats = indexer.refresh_directory(Path::new("/ws")).unwrap();
+        assert_eq!((stats.files_indexed, stats.files_unchanged), (2, 1));
+        assert!(index.symbols.find_by_name("b").is_empty());
+        assert_eq!(index.symbols.find_by_name("b2").len(), 1);
+        assert_eq!(index.symbols.find_by_name("a").len(), 1);
+        assert_eq!(index.files.get("file:///ws/a.py").unwrap().adapter_version, "1");
+    }
+
+    #[test]
+    fn test_memory_budget_evicts_and_reads_back_details() {
+        let vfs = MemoryFs::new();
+        vfs.insert("/ws/a.py", "def a() -> int:\n    return 1\n");
+        vfs.insert("/ws/b.py", "def b() -> str:\n    return ''\n");
+        let mut indexer = ProjectIndexer::with_vfs(Arc::new(vfs));
+        indexer.set_worker_threads(1);
+        indexer.set_memory_budget(Some(1));
+        indexer.index_directory(Path::new("/ws")).unwrap();
+        let index = indexer.get_index();
+        let type_expr = |symbol: &SmartSymbol| symbol.type_info.as_ref().map(|t| t.type_expr.clone());
+
+        // Only the file indexed last keeps its details
+        assert_eq!(index.symbols.evicted_files(), 1);
+        let a = index.symbols.find_by_name("a").remove(0);
+        assert_eq!(type_expr(&a), None);
+        assert_eq!(type_expr(&index.symbols.get(a.id).unwrap()).as_deref(), Some("int"));
+
+        // Reading them back evicts the others
+        assert_eq!(type_expr(&index.symbols.find_by_name("b").remove(0)), None);
+        assert_eq!(index.symbols.all().iter().filter(|s| s.type_info.is_some()).count(), 2);
+        assert_eq!(index.symbols.evicted_files(), 1);
+    }
+
+    #[test]
+    fn test_type_hierarchy_survives_indexing_order() {
+        let vfs = MemoryFs::new();
+        vfs.insert("/ws/base.py", "class Base:\n    pass\n");
+        vfs.insert("/ws/child.py", "from base import Base\n\nclass Child(Base):\n    pass\n");
+        let indexer = ProjectIndexer::with_vfs(Arc::new(vfs));
+        let index = indexer.get_index();
+
+        // The child first: its parent isn't indexed yet
+        indexer.index_file(Path::new("/ws/child.py")).unwrap();
+        indexer.index_file(Path::new("/ws/base.py")).unwrap();
+        let child = &index.symbols.find_by_name("Child")[0];
+        let base = &index.symbols.find_by_name("Base")[0];
+        assert_eq!(index.type_hierarchy.get_supertypes(child.id), vec![base.id]);
+
+        // Re-indexing the parent keeps its ID
+        indexer.reindex_file(Path::new("/ws/base.py")).unwrap();
+        assert_eq!(index.symbols.find_by_name("Base")[0].id, base.id);
+        let base = &index.symbols.find_by_name("Base")[0];
+        let subtypes: Vec<_> = index.subtypes(base.id).into_iter().map(|s| s.name).collect();
+        assert_eq!(subtypes, vec!["Child"]);
+        assert_eq!(index.implementations(base.id).len(), 1);
+    }
+
+    #[test]
+    fn test_symbol_ids_survive_reanalysis() {
+        let vfs = Arc::new(MemoryFs::new());
+        vfs.insert("/ws/shapes.py", "class Shape:\n    def area(self):\n        pass\n");
+        let indexer = ProjectIndexer::with_vfs(vfs.clone());
+        let index = indexer.get_index();
+        indexer.index_file(Path::new("/ws/shapes.py")).unwrap();
+        let shape = index.symbols.find_by_name("Shape").remove(0);
+        let area = index.symbols.find_by_name("area").remove(0);
+        assert!(shape.id.is_stable());
+        assert_eq!(area.parent, Some(shape.id));
+
+        // Declarations added before them leave their ids alone
+        vfs.insert("/ws/shapes.py", "def unit():\n    pass\n\nclass Shape:\n    def area(self):\n        pass\n");
+        indexer.reindex_file(Path::new("/ws/shapes.py")).unwrap();
+        assert_eq!(index.symbols.find_by_name("Shape")[0].id, shape.id);
+        assert_eq!(index.symbols.find_by_name("area")[0].id, area.id);
+        assert_eq!(index.symbols.find_by_name("area")[0].parent, Some(shape.id));
+    }
+
+    #[test]
+    fn test_parallel_indexing_matches_serial() {
+        let vfs = MemoryFs::new();
+        for i in 0..40 {
+            vfs.insert(format!("/ws/pkg{}/mod{}.py", i % 4, i), format!("def f{}():\n    pass\n", i));
+        }
+        // The subclass is analyzed before the file declaring its base
+        vfs.insert("/ws/a_child.py", "class Child(Base):\n    pass\n");
+        vfs.insert("/ws/z_base.py", "class Base:\n    pass\n");
+        let vfs: Arc<dyn Vfs> = Arc::new(vfs);
+
+        let mut serial = ProjectIndexer::with_vfs(Arc::clone(&vfs));
+        serial.set_worker_threads(1);
+        let serial_stats = serial.index_directory(Path::new("/ws")).unwrap();
+
+        let mut parallel = ProjectIndexer::with_vfs(vfs);
+        parallel.set_worker_threads(8);
+        let stats = parallel.index_directory(Path::new("/ws")).unwrap();
+
+        assert_eq!(stats.files_indexed, 42);
+        assert_eq!(stats.files_indexed, serial_stats.files_indexed);
+        assert_eq!(stats.symbols_found, serial_stats.symbols_found);
+        assert_eq!(stats.files_by_language, serial_stats.files_by_language);
+
+        let index = parallel.get_index();
+        let mut files = index.symbols.files();
+        files.sort();
+        let mut serial_files = serial.get_index().symbols.files();
+        serial_files.sort();
+        assert_eq!(files, serial_files);
+
+        let child = &index.symbols.find_by_name("Child")[0];
+        let base = &index.symbols.find_by_name("Base")[0];
+        assert_eq!(index.type_hierarchy.get_supertypes(child.id), vec![base.id]);
+    }
+
+    #[test]
+    fn test_ignore_files_and_exclude_globs() {
+        let vfs = MemoryFs::new();
+        vfs.insert("/ws/.gitignore", "generated/\n");
+        vfs.insert("/ws/app.py", "def main():\n    pass\n");
+        vfs.insert("/ws/generated/schema.py", "def schema():\n    pass\n");
+        vfs.insert("/ws/lib/.logosignore", "*_pb2.py\n");
+        vfs.insert("/ws/lib/api_pb2.py", "def message():\n    pass\n");
+        vfs.insert("/ws/lib/api.py", "def call():\n    pass\n");
+        vfs.insert("/ws/vendor/six.py", "def moves():\n    pass\n");
+        vfs.insert("/ws/api_pb2.py", "def root_message():\n    pass\n");
+
+        let mut indexer = ProjectIndexer::with_vfs(Arc::new(vfs));
+        indexer.set_exclude(vec!["vendor/".to_string()]);
+        indexer.index_directory(Path::new("/ws")).unwrap();
+
+        let mut files = indexer.get_index().symbols.files();
+        files.sort();
+        assert_eq!(files, vec!["file:///ws/api_pb2.py", "file:///ws/app.py", "file:///ws/lib/api.py"]);
+    }
+
+    #[test]
+    fn test_index_extensionless_files() {
+        let vfs = MemoryFs::new();
+        vfs.insert("/ws/bin/deploy", "#!/usr/bin/env python3\n\ndef deploy():\n    pass\n");
+        vfs.insert("/ws/SConstruct", "def build():\n    pass\n");
+        vfs.insert("/ws/LICENSE", "MIT License\n");
+
+        let indexer = ProjectIndexer::with_vfs(Arc::new(vfs));
+        let stats = indexer.index_directory(Path::new("/ws")).unwrap();
+
+        assert_eq!(stats.files_indexed, 2);
+        let mut files = indexer.get_index().symbols.files();
+        files.sort();
+        assert_eq!(files, vec!["file:///ws/SConstruct", "file:///ws/bin/deploy"]);
+    }
+
+    #[test]
+    fn test_index_mounted_archive() {
+        let dir = tempdir().unwrap();
+        let jar = dir.path().join("widgets.jar");
+        let mut writer = zip::ZipWriter::new(fs::File::create(&jar).unwrap());
+        writer.start_file("com/acme/Widget.java", zip::write::SimpleFileOptions::default()).unwrap();
+        writer.write_all(b"package com.acme;\npublic class Widget {}\n").unwrap();
+        writer.finish().unwrap();
+
+        let archives = Arc::new(ArchiveFs::new(Arc::new(OsFs)));
+        let indexer = ProjectIndexer::with_vfs(Arc::clone(&archives) as Arc<dyn Vfs>);
+        archives.mount(&jar).unwrap();
+        let stats = indexer.index_directory(&jar).unwrap();
+
+        assert_eq!(stats.files_indexed, 1);
+        let widget = &indexer.get_index().symbols.find_by_name("Widget")[0];
+        assert_eq!(
+            widget.location.uri,
+            format!("archive:{}!/com/acme/Widget.java", uri::path_to_uri(&jar))
+        );
+    }
 }
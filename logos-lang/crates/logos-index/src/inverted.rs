@@ -1,6 +1,71 @@
 //! Inverted index for fast symbol lookup
 
 use std::collections::{HashMap, HashSet};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Minimum prefix length (in chars) that gets its own index entry
+const MIN_PREFIX_CHARS: usize = 2;
+
+/// Normalize a name for indexing and lookup: NFC composition followed by
+/// lowercase folding, so `Café`, `cafe\u{301}` and `CAFÉ` share one key.
+pub fn normalize_name(name: &str) -> String {
+    let folded: String = name.nfc().flat_map(char::to_lowercase).collect();
+    // Lowercasing can emit combining marks (e.g. `İ`), so recompose once more.
+    folded.nfc().collect()
+}
+
+/// Whether two spellings name the same identifier: equal once composed,
+/// so `café` written precomposed and with a combining accent are one name.
+/// Case still matters.
+pub fn same_identifier(a: &str, b: &str) -> bool {
+    a == b || a.nfc().eq(b.nfc())
+}
+
+/// Composed (NFC) form of an identifier, as edits should write it
+pub fn compose_name(name: &str) -> String {
+    name.nfc().collect()
+}
+
+/// How a query is compared with names
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NameMatch {
+    /// Compared after [`normalize_name`]
+    #[default]
+    Normalized,
+    /// Compared after [`fold_name`] as well, so `cafe` finds `café` and
+    /// `strasse` finds `Straße`
+    Folded,
+}
+
+/// [`normalize_name`] with diacritics dropped and the Latin letters that
+/// have no decomposition spelled out in ASCII
+pub fn fold_name(name: &str) -> String {
+    let mut folded = String::with_capacity(name.len());
+    for c in normalize_name(name).nfd().filter(|c| !is_combining_mark(*c)) {
+        match c {
+            'ß' => folded.push_str("ss"),
+            'æ' => folded.push_str("ae"),
+            'œ' => folded.push_str("oe"),
+            'þ' => folded.push_str("th"),
+            'ø' => folded.push('o'),
+            'đ' | 'ð' => folded.push('d'),
+            'ł' => folded.push('l'),
+            'ı' => folded.push('i'),
+            c => folded.push(c),
+        }
+    }
+    folded
+}
+
+/// Char-boundary-safe prefixes of an already normalized name, shortest first.
+fn prefixes(normalized: &str) -> impl Iterator<Item = &str> {
+    normalized
+        .char_indices()
+        .map(|(i, c)| i + c.len_utf8())
+        .skip(MIN_PREFIX_CHARS - 1)
+        .map(move |end| &normalized[..end])
+}
 
 #[derive(Debug, Default)]
 pub struct InvertedIndex {
@@ -11,31 +76,87 @@ impl InvertedIndex {
     pub fn new() -> Self { Self::default() }
 
     pub fn add(&mut self, name: &str, uri: &str) {
-        let name_lower = name.to_lowercase();
-        self.index.entry(name_lower.clone()).or_default().insert(uri.to_string());
-        for i in 2..=name_lower.len() {
-            let prefix = &name_lower[..i];
+        let normalized = normalize_name(name);
+        self.index.entry(normalized.clone()).or_default().insert(uri.to_string());
+        for prefix in prefixes(&normalized) {
             self.index.entry(prefix.to_string()).or_default().insert(uri.to_string());
         }
     }
 
     pub fn remove(&mut self, name: &str, uri: &str) {
-        let name_lower = name.to_lowercase();
-        for i in 2..=name_lower.len() {
-            let prefix = &name_lower[..i];
-            if let Some(uris) = self.index.get_mut(prefix) {
+        let normalized = normalize_name(name);
+        for key in std::iter::once(normalized.as_str()).chain(prefixes(&normalized)) {
+            if let Some(uris) = self.index.get_mut(key) {
                 uris.remove(uri);
                 if uris.is_empty() {
-                    self.index.remove(prefix);
+                    self.index.remove(key);
                 }
             }
         }
     }
 
     pub fn search(&self, query: &str) -> Vec<String> {
-        let query_lower = query.to_lowercase();
-        self.index.get(&query_lower).map(|uris| uris.iter().cloned().collect()).unwrap_or_default()
+        let query = normalize_name(query);
+        self.index.get(&query).map(|uris| uris.iter().cloned().collect()).unwrap_or_default()
     }
 
     pub fn clear(&mut self) { self.index.clear(); }
+
+    /// Rough estimate of memory held by the index, in bytes
+    pub fn estimated_memory_bytes(&self) -> usize {
+        self.index
+            .iter()
+            .map(|(key, uris)| key.len() + uris.iter().map(|u| u.len()).sum::<usize>())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_non_ascii_prefixes_do_not_panic() {
+        let mut index = InvertedIndex::new();
+        index.add("größe", "file:///a.py");
+        index.add("名前を取得", "file:///b.js");
+
+        assert_eq!(index.search("grö"), vec!["file:///a.py".to_string()]);
+        assert_eq!(index.search("名前"), vec!["file:///b.js".to_string()]);
+        assert!(index.search("名").is_empty());
+    }
+
+    #[test]
+    fn test_normalization_and_case_folding() {
+        let mut index = InvertedIndex::new();
+        // Decomposed form: `e` + combining acute accent
+        index.add("Cafe\u{301}Service", "file:///a.py");
+
+        assert_eq!(index.search("café"), vec!["file:///a.py".to_string()]);
+        assert_eq!(index.search("CAFÉSERVICE"), vec!["file:///a.py".to_string()]);
+        assert_eq!(normalize_name("ÄBC"), normalize_name("a\u{308}bc"));
+    }
+
+    #[test]
+    fn test_identifier_comparisons() {
+        assert!(same_identifier("cafe\u{301}", "café"));
+        assert!(!same_identifier("Café", "café"));
+        assert_eq!(compose_name("cafe\u{301}"), "café");
+
+        assert_eq!(fold_name("Café"), "cafe");
+        assert_eq!(fold_name("STRAßE"), "strasse");
+        assert_eq!(fold_name("Ærøskøbing"), "aeroskobing");
+        // Scripts without diacritics and emoji pass through
+        assert_eq!(fold_name("名前🦀"), "名前🦀");
+    }
+
+    #[test]
+    fn test_remove_non_ascii() {
+        let mut index = InvertedIndex::new();
+        index.add("Ünïcode", "file:///a.py");
+        index.remove("Ünïcode", "file:///a.py");
+
+        assert!(index.search("ün").is_empty());
+        assert!(index.search("ünïcode").is_empty());
+    }
 }
@@ -0,0 +1,495 @@
+//! Kotlin Language Adapter
+//!
+//! Pragmatic indexer for Kotlin:
+//! - Symbols: classes/interfaces/enums/objects, functions, properties
+//! - Imports: import directives (with `as` aliases)
+//! - Exports: everything not `private` (Kotlin's default visibility is public)
+//! - Calls: call_expression nodes (best-effort)
+//! - Type relations: delegation specifiers (superclass calls vs interfaces)
+
+use crate::adapter::{
+    AnalysisResult, CallInfo, ImportInfo, ImportItem, LanguageAdapter, SymbolBuilder, TypeRelation, make_location,
+};
+use crate::symbol_table::{SymbolId, Visibility};
+use crate::vfs::Vfs;
+use logos_core::{Position, Range, SymbolKind};
+use std::path::Path;
+use logos_parser::{pool, LanguageId};
+use tree_sitter::{Node, Tree};
+
+pub struct KotlinAdapter;
+
+impl KotlinAdapter {
+    pub fn new() -> Result<Self, String> {
+        // Fail early when the grammar can't be loaded
+        pool::shared()
+            .checkout(LanguageId::Kotlin)
+            .map_err(|e| format!("Failed to set Kotlin language: {}", e))?;
+        Ok(Self)
+    }
+
+    fn parse(&self, source: &str) -> Option<Tree> {
+        let mut parser = pool::shared().checkout(LanguageId::Kotlin).ok()?;
+        parser.parse(source, None).ok()
+    }
+}
+
+impl LanguageAdapter for KotlinAdapter {
+    fn language_id(&self) -> &str {
+        "kotlin"
+    }
+
+    fn file_extensions(&self) -> &[&str] {
+        &["kt", "kts"]
+    }
+
+    fn analyze(&self, uri: &str, source: &str) -> AnalysisResult {
+        let tree = match self.parse(source) {
+            Some(t) => t,
+            None => return AnalysisResult::default(),
+        };
+
+        let mut ctx = AnalysisContext {
+            uri: uri.to_string(),
+            source,
+            result: AnalysisResult::default(),
+            scope_stack: Vec::new(),
+        };
+
+        analyze_node(&tree.root_node(), &mut ctx);
+        ctx.result.with_utf16_columns(source)
+    }
+
+    fn resolve_import(&self, _vfs: &dyn Vfs, _from_file: &Path, _import_path: &str) -> Option<std::path::PathBuf> {
+        // Kotlin imports are package paths; don't resolve to files here.
+        None
+    }
+}
+
+struct AnalysisContext<'a> {
+    uri: String,
+    source: &'a str,
+    result: AnalysisResult,
+    scope_stack: Vec<ScopeInfo>,
+}
+
+struct ScopeInfo {
+    symbol_id: SymbolId,
+    name: String,
+}
+
+impl<'a> AnalysisContext<'a> {
+    fn get_text(&self, node: &Node) -> String {
+        self.source[node.byte_range()].to_string()
+    }
+
+    fn current_scope(&self) -> Option<&ScopeInfo> {
+        self.scope_stack.last()
+    }
+
+    fn parent_id(&self) -> SymbolId {
+        self.current_scope().map(|s| s.symbol_id).unwrap_or(SymbolId(0))
+    }
+
+    fn qualified_name(&self, name: &str) -> String {
+        if self.scope_stack.is_empty() {
+            name.to_string()
+        } else {
+            let prefix: Vec<_> = self.scope_stack.iter().map(|s| s.name.as_str()).collect();
+            format!("{}.{}", prefix.join("."), name)
+        }
+    }
+}
+
+fn find_child<'a>(node: &Node<'a>, kind: &str) -> Option<Node<'a>> {
+    (0..node.named_child_count())
+        .filter_map(|i| node.named_child(i))
+        .find(|child| child.kind() == kind)
+}
+
+fn has_token(node: &Node, kind: &str) -> bool {
+    (0..node.child_count())
+        .filter_map(|i| node.child(i))
+        .any(|child| !child.is_named() && child.kind() == kind)
+}
+
+fn has_modifier(node: &Node, ctx: &AnalysisContext, modifier: &str) -> bool {
+    find_child(node, "modifiers").is_some_and(|modifiers| {
+        (0..modifiers.named_child_count())
+            .filter_map(|i| modifiers.named_child(i))
+            .any(|m| ctx.get_text(&m) == modifier)
+    })
+}
+
+fn visibility_and_export(node: &Node, ctx: &AnalysisContext) -> (Visibility, bool) {
+    if has_modifier(node, ctx, "private") {
+        (Visibility::Private, false)
+    } else if has_modifier(node, ctx, "protected") {
+        (Visibility::Protected, true)
+    } else if has_modifier(node, ctx, "internal") {
+        (Visibility::Internal, true)
+    } else {
+        (Visibility::Public, true)
+    }
+}
+
+fn analyze_node(node: &Node, ctx: &mut AnalysisContext) {
+    match node.kind() {
+        "import" => analyze_import(node, ctx),
+
+        "class_declaration" => analyze_class(node, ctx),
+        "object_declaration" | "companion_object" => analyze_object(node, ctx),
+
+        "function_declaration" => analyze_function(node, ctx),
+        "property_declaration" => analyze_property(node, ctx),
+
+        "call_expression" => analyze_call(node, ctx),
+
+        _ => analyze_children(node, ctx),
+    }
+}
+
+fn analyze_children(node: &Node, ctx: &mut AnalysisContext) {
+    for i in 0..node.named_child_count() {
+        if let Some(child) = node.named_child(i) {
+            analyze_node(&child, ctx);
+        }
+    }
+}
+
+fn analyze_import(node: &Node, ctx: &mut AnalysisContext) {
+    // import foo.bar.Baz
+    // import foo.bar.Baz as Qux
+    let Some(path_node) = find_child(node, "qualified_identifier") else {
+        return;
+    };
+    let module_path = ctx.get_text(&path_node);
+    let alias = (0..node.named_child_count())
+        .filter_map(|i| node.named_child(i))
+        .find(|child| child.kind() == "identifier")
+        .map(|n| ctx.get_text(&n));
+
+    ctx.result.imports.push(ImportInfo {
+        module_path: module_path.clone(),
+        items: vec![ImportItem {
+            name: module_path.rsplit('.').next().unwrap_or(&module_path).to_string(),
+            alias,
+            is_type: false,
+        }],
+        is_type_only: false,
+        location: node_to_range(node),
+    });
+}
+
+fn analyze_class(node: &Node, ctx: &mut AnalysisContext) {
+    let name_node = match node.child_by_field_name("name") {
+        Some(n) => n,
+        None => return,
+    };
+    let name = ctx.get_text(&name_node);
+    let kind = if has_token(node, "interface") {
+        SymbolKind::Interface
+    } else if has_modifier(node, ctx, "enum") {
+        SymbolKind::Enum
+    } else {
+        SymbolKind::Class
+    };
+    let (visibility, exported) = visibility_and_export(node, ctx);
+
+    let location = make_location(&ctx.uri, node_to_range(node), node_to_range(&name_node));
+    let sym = SymbolBuilder::new(name.clone(), kind, location)
+        .parent(ctx.parent_id())
+        .visibility(visibility)
+        .exported(exported)
+        .qualified_name(ctx.qualified_name(&name))
+        .build();
+    let id = sym.id;
+    ctx.result.symbols.push(sym);
+
+    analyze_supertypes(node, &name, ctx);
+
+    ctx.scope_stack.push(ScopeInfo { symbol_id: id, name });
+    for i in 0..node.named_child_count() {
+        if let Some(child) = node.named_child(i) {
+            match child.kind() {
+                "primary_constructor" => analyze_constructor_properties(&child, ctx),
+                "class_body" | "enum_class_body" => analyze_class_body(&child, ctx),
+                _ => {}
+            }
+        }
+    }
+    ctx.scope_stack.pop();
+}
+
+fn analyze_object(node: &Node, ctx: &mut AnalysisContext) {
+    let name_node = node.child_by_field_name("name");
+    let name = name_node
+        .map(|n| ctx.get_text(&n))
+        .unwrap_or_else(|| "Companion".to_string());
+    let (visibility, exported) = visibility_and_export(node, ctx);
+
+    let selection = name_node.map(|n| node_to_range(&n)).unwrap_or_else(|| node_to_range(node));
+    let location = make_location(&ctx.uri, node_to_range(node), selection);
+    let sym = SymbolBuilder::new(name.clone(), SymbolKind::Object, location)
+        .parent(ctx.parent_id())
+        .visibility(visibility)
+        .exported(exported)
+        .qualified_name(ctx.qualified_name(&name))
+        .build();
+    let id = sym.id;
+    ctx.result.symbols.push(sym);
+
+    analyze_supertypes(node, &name, ctx);
+
+    if let Some(body) = find_child(node, "class_body") {
+        ctx.scope_stack.push(ScopeInfo { symbol_id: id, name });
+        analyze_class_body(&body, ctx);
+        ctx.scope_stack.pop();
+    }
+}
+
+fn analyze_supertypes(node: &Node, name: &str, ctx: &mut AnalysisContext) {
+    let Some(specifiers) = find_child(node, "delegation_specifiers") else {
+        return;
+    };
+    for i in 0..specifiers.named_child_count() {
+        let Some(specifier) = specifiers.named_child(i) else {
+            continue;
+        };
+        let Some(target) = specifier.named_child(0) else {
+            continue;
+        };
+        // `Base()` calls a superclass constructor; a bare type is an interface
+        let (type_node, is_implements) = match target.kind() {
+            "constructor_invocation" => (find_child(&target, "user_type").unwrap_or(target), false),
+            _ => (target, true),
+        };
+        ctx.result.type_relations.push(TypeRelation {
+            child_name: name.to_string(),
+            parent_name: ctx.get_text(&type_node),
+            is_implements,
+            location: node_to_range(&type_node),
+        });
+    }
+}
+
+fn analyze_class_body(body: &Node, ctx: &mut AnalysisContext) {
+    for i in 0..body.named_child_count() {
+        if let Some(child) = body.named_child(i) {
+            match child.kind() {
+                "enum_entry" => analyze_enum_entry(&child, ctx),
+                "secondary_constructor" => analyze_secondary_constructor(&child, ctx),
+                _ => analyze_node(&child, ctx),
+            }
+        }
+    }
+}
+
+fn analyze_enum_entry(node: &Node, ctx: &mut AnalysisContext) {
+    if let Some(name_node) = find_child(node, "identifier") {
+        let name = ctx.get_text(&name_node);
+        let location = make_location(&ctx.uri, node_to_range(node), node_to_range(&name_node));
+        let sym = SymbolBuilder::new(name.clone(), SymbolKind::EnumMember, location)
+            .parent(ctx.parent_id())
+            .exported(true)
+            .qualified_name(ctx.qualified_name(&name))
+            .build();
+        ctx.result.symbols.push(sym);
+    }
+}
+
+fn analyze_constructor_properties(node: &Node, ctx: &mut AnalysisContext) {
+    let Some(params) = find_child(node, "class_parameters") else {
+        return;
+    };
+    for i in 0..params.named_child_count() {
+        let Some(param) = params.named_child(i) else {
+            continue;
+        };
+        if param.kind() != "class_parameter" || !(has_token(&param, "val") || has_token(&param, "var")) {
+            continue;
+        }
+        if let Some(name_node) = find_child(&param, "identifier") {
+            let name = ctx.get_text(&name_node);
+            let (visibility, exported) = visibility_and_export(&param, ctx);
+            let location = make_location(&ctx.uri, node_to_range(&param), node_to_range(&name_node));
+            let sym = SymbolBuilder::new(name.clone(), SymbolKind::Property, location)
+                .parent(ctx.parent_id())
+                .visibility(visibility)
+                .exported(exported)
+                .qualified_name(ctx.qualified_name(&name))
+                .build();
+            ctx.result.symbols.push(sym);
+        }
+    }
+}
+
+fn analyze_secondary_constructor(node: &Node, ctx: &mut AnalysisContext) {
+    let Some(owner) = ctx.current_scope().map(|s| s.name.clone()) else {
+        return;
+    };
+    let (visibility, exported) = visibility_and_export(node, ctx);
+    let selection = node
+        .child(0)
+        .filter(|c| c.kind() == "constructor")
+        .map(|c| node_to_range(&c))
+        .unwrap_or_else(|| node_to_range(node));
+
+    let location = make_location(&ctx.uri, node_to_range(node), selection);
+    let sym = SymbolBuilder::new(owner.clone(), SymbolKind::Constructor, location)
+        .parent(ctx.parent_id())
+        .visibility(visibility)
+        .exported(exported)
+        .qualified_name(ctx.qualified_name(&owner))
+        .build();
+    ctx.result.symbols.push(sym);
+
+    analyze_children(node, ctx);
+}
+
+fn analyze_function(node: &Node, ctx: &mut AnalysisContext) {
+    let name_node = match node.child_by_field_name("name") {
+        Some(n) => n,
+        None => return,
+    };
+    let name = ctx.get_text(&name_node);
+    let (visibility, exported) = visibility_and_export(node, ctx);
+    let kind = if ctx.scope_stack.is_empty() { SymbolKind::Function } else { SymbolKind::Method };
+
+    let location = make_location(&ctx.uri, node_to_range(node), node_to_range(&name_node));
+    let sym = SymbolBuilder::new(name.clone(), kind, location)
+        .parent(ctx.parent_id())
+        .visibility(visibility)
+        .exported(exported)
+        .qualified_name(ctx.qualified_name(&name))
+        .build();
+    ctx.result.symbols.push(sym);
+
+    // Recurse into body for calls
+    if let Some(body) = find_child(node, "function_body") {
+        analyze_node(&body, ctx);
+    }
+}
+
+fn analyze_property(node: &Node, ctx: &mut AnalysisContext) {
+    let (visibility, exported) = visibility_and_export(node, ctx);
+    let kind = if has_modifier(node, ctx, "const") {
+        SymbolKind::Constant
+    } else if ctx.scope_stack.is_empty() {
+        SymbolKind::Variable
+    } else {
+        SymbolKind::Property
+    };
+
+    let mut declarations = Vec::new();
+    if let Some(decl) = find_child(node, "variable_declaration") {
+        declarations.push(decl);
+    }
+    if let Some(multi) = find_child(node, "multi_variable_declaration") {
+        declarations.extend(
+            (0..multi.named_child_count())
+                .filter_map(|i| multi.named_child(i))
+                .filter(|d| d.kind() == "variable_declaration"),
+        );
+    }
+
+    for decl in declarations {
+        if let Some(name_node) = find_child(&decl, "identifier") {
+            let name = ctx.get_text(&name_node);
+            let location = make_location(&ctx.uri, node_to_range(node), node_to_range(&name_node));
+            let sym = SymbolBuilder::new(name.clone(), kind, location)
+                .parent(ctx.parent_id())
+                .visibility(visibility)
+                .exported(exported)
+                .qualified_name(ctx.qualified_name(&name))
+                .build();
+            ctx.result.symbols.push(sym);
+        }
+    }
+
+    // Initializers and accessors may contain calls
+    analyze_children(node, ctx);
+}
+
+fn analyze_call(node: &Node, ctx: &mut AnalysisContext) {
+    if let Some(callee) = node.named_child(0) {
+        let text = ctx.get_text(&callee);
+        let callee_name = text.rsplit('.').next().unwrap_or(&text).trim().to_string();
+        let qualified_name = if text.contains('.') { Some(text.clone()) } else { None };
+        // Kotlin has no `new`; a capitalized callee is most likely a constructor
+        let is_constructor = callee_name.chars().next().is_some_and(|c| c.is_uppercase());
+
+        ctx.result.calls.push(CallInfo {
+            callee_name,
+            qualified_name,
+            location: node_to_range(node),
+            is_constructor,
+        });
+    }
+
+    // nested calls
+    analyze_children(node, ctx);
+}
+
+fn node_to_range(node: &Node) -> Range {
+    let start = node.start_position();
+    let end = node.end_position();
+    Range {
+        start: Position {
+            line: start.row as u32,
+            column: start.column as u32,
+        },
+        end: Position {
+            line: end.row as u32,
+            column: end.column as u32,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kotlin_basic_symbols_imports_calls() {
+        let adapter = KotlinAdapter::new().unwrap();
+        let src = r#"
+package com.example
+
+import kotlin.math.max
+import com.example.util.Logger as Log
+
+data class User(val name: String, private val age: Int) : Entity(), Named {
+    fun greet(): String = Log.format(name)
+    companion object {
+        fun create(): User = User("a", max(1, 2))
+    }
+}
+
+object Registry {
+    const val MAX = 3
+}
+
+private fun helper() {}
+"#;
+        let result = adapter.analyze("file:///User.kt", src);
+
+        assert_eq!(result.imports.len(), 2);
+        assert_eq!(result.imports[1].items[0].alias.as_deref(), Some("Log"));
+
+        let find = |name: &str| result.symbols.iter().find(|s| s.name == name).unwrap();
+        assert_eq!(find("User").kind, SymbolKind::Class);
+        assert_eq!(find("name").kind, SymbolKind::Property);
+        assert!(!find("age").exported);
+        assert_eq!(find("greet").kind, SymbolKind::Method);
+        assert_eq!(find("greet").qualified_name, "User.greet");
+        assert_eq!(find("Registry").kind, SymbolKind::Object);
+        assert_eq!(find("MAX").kind, SymbolKind::Constant);
+        assert!(!find("helper").exported);
+
+        assert!(result.type_relations.iter().any(|r| r.parent_name == "Entity" && !r.is_implements));
+        assert!(result.type_relations.iter().any(|r| r.parent_name == "Named" && r.is_implements));
+        assert!(result.calls.iter().any(|c| c.callee_name == "User" && c.is_constructor));
+        assert!(result.calls.iter().any(|c| c.callee_name == "max"));
+    }
+}
@@ -1,7 +1,17 @@
 //! Logos Index - Symbol indexing for fast lookup
 
 pub mod adapter;
+pub mod archive;
 pub mod comments;
+pub mod compile_commands;
+#[cfg(test)]
+mod corpus;
+pub mod coverage;
+mod eviction;
+pub mod ffi;
+pub mod fuzzy;
+pub mod generated;
+pub mod glob;
 pub mod c_adapter;
 pub mod cpp_adapter;
 pub mod go_adapter;
@@ -9,28 +19,60 @@ pub mod incremental;
 pub mod indexer;
 pub mod inverted;
 pub mod java_adapter;
+pub mod kotlin_adapter;
+pub mod log_statement;
+pub mod merge;
+pub mod permalink;
+pub mod persist;
 pub mod python_adapter;
+pub mod related;
+pub mod replace;
 pub mod rust_adapter;
+pub mod schema;
+pub mod stack_trace;
 pub mod symbol_table;
+pub mod tags;
+pub mod trigram;
 pub mod typescript_adapter;
+pub mod vfs;
 
 pub use adapter::{
     AnalysisResult, CallInfo, ExportInfo, ImportInfo, ImportItem, LanguageAdapter,
     SymbolBuilder, TypeRelation, make_location,
 };
-pub use comments::{CommentScanner, ScannerConfig, TodoIndex, TodoItem, TodoKind};
+pub use archive::{ArchiveFs, ARCHIVE_SCHEME, archive_uri, is_archive, parse_archive_uri};
+pub use comments::{CommentScanner, ScannerConfig, TodoFilter, TodoIndex, TodoItem, TodoKind};
+pub use compile_commands::{CompilationDatabase, CompileFlags};
+pub use coverage::{Coverage, CoverageFormat, CoverageSummary, FileCoverage};
+pub use ffi::{binding_at, find_bindings, BindingKind, FfiBinding};
+pub use fuzzy::FuzzyQuery;
+pub use generated::{is_generated, GENERATED_ATTRIBUTE};
+pub use glob::{IgnoreRules, PathGlob};
 pub use c_adapter::CAdapter;
 pub use cpp_adapter::CppAdapter;
 pub use go_adapter::GoAdapter;
-pub use indexer::{IndexingStats, ProjectIndexer};
+pub use indexer::{is_skipped_name, IndexingStats, ProjectIndexer};
 pub use java_adapter::JavaAdapter;
+pub use kotlin_adapter::KotlinAdapter;
+pub use log_statement::{find_log_statements, LogMatch};
+pub use inverted::{compose_name, fold_name, same_identifier, NameMatch};
+pub use merge::{MergedSymbol, dedup_locations, merge_symbols};
+pub use permalink::Permalink;
+pub use persist::{IndexHeader, IndexSnapshot, LoadOutcome, LoadedIndex, INDEX_SCHEMA_VERSION};
 pub use python_adapter::PythonAdapter;
+pub use related::{related_files, test_file_path, RelatedFile, RelatedKind};
+pub use replace::{workspace_replace, FileReplacement, ReplaceEdit, ReplaceOptions, WorkspaceReplacement};
 pub use rust_adapter::RustAdapter;
+pub use schema::is_schema;
+pub use stack_trace::{parse_stack_trace, FrameResolver, StackFrame, TraceFormat};
 pub use symbol_table::{
-    Attribute, CallGraph, CallSite, CallType, DependencyGraph, ProjectIndex, SmartSymbol, SymbolId,
-    SymbolLocation, SymbolReference, SymbolTable, TypeHierarchy, TypeInfo, Visibility,
+    Attribute, CallGraph, CallSite, CallType, DependencyGraph, FileRecord, Hydrator, ProjectIndex, SmartSymbol, SymbolId,
+    SymbolLocation, SymbolReference, SymbolTable, TypeHierarchy, TypeInfo, Visibility, is_type_kind,
 };
+pub use tags::write_tags;
+pub use trigram::{FileMatches, TextMatch, TextSearchResult, TrigramIndex};
 pub use typescript_adapter::TypeScriptAdapter;
+pub use vfs::{MemoryFs, OsFs, OverlayFs, Vfs};
 use logos_core::{Position, Range, Symbol, SymbolKind};
 use std::collections::HashMap;
 
@@ -42,6 +84,12 @@ pub struct IndexedSymbol {
     pub range: Range,
     pub selection_range: Range,
     pub container: Option<String>,
+    /// Extra info such as a function signature
+    pub detail: Option<String>,
+    /// Doc comment of the declaration
+    pub documentation: Option<String>,
+    /// Declared in a generated file; see [`generated`]
+    pub generated: bool,
 }
 
 impl IndexedSymbol {
@@ -53,10 +101,31 @@ impl IndexedSymbol {
             range: symbol.range,
             selection_range: symbol.selection_range,
             container,
+            detail: symbol.detail.clone(),
+            documentation: symbol.documentation.clone(),
+            generated: false,
         }
     }
 }
 
+/// Rewrite `uri` for a rename of `from` to `to`.
+///
+/// `from` may be a file or a directory; URIs below a renamed directory keep
+/// their relative path. Returns `None` when `uri` is not affected.
+pub fn rebase_uri(uri: &str, from: &str, to: &str) -> Option<String> {
+    if uri == from {
+        return Some(to.to_string());
+    }
+    let from_dir = from.trim_end_matches('/');
+    let rest = uri.strip_prefix(from_dir)?.strip_prefix('/')?;
+    Some(format!("{}/{}", to.trim_end_matches('/'), rest))
+}
+
+/// Sort key under which a range nested inside another compares smaller
+fn range_size_key(range: &Range) -> (u32, std::cmp::Reverse<Position>, Position) {
+    (range.end.line - range.start.line, std::cmp::Reverse(range.start), range.end)
+}
+
 #[derive(Debug, Default)]
 pub struct SymbolIndex {
     by_document: HashMap<String, Vec<IndexedSymbol>>,
@@ -93,27 +162,147 @@ impl SymbolIndex {
         }
     }
 
+    /// Tag the symbols of `uri` as declared in a generated file
+    pub fn mark_generated(&mut self, uri: &str) {
+        for symbol in self.by_document.get_mut(uri).into_iter().flatten() {
+            symbol.generated = true;
+        }
+    }
+
     pub fn get_document_symbols(&self, uri: &str) -> &[IndexedSymbol] {
         self.by_document.get(uri).map(|v| v.as_slice()).unwrap_or(&[])
     }
 
     pub fn search(&self, query: &str) -> Vec<&IndexedSymbol> {
-        let uris = self.inverted.search(query);
+        self.search_with(query, NameMatch::Normalized)
+    }
+
+    /// Symbols whose name contains `query`, compared as `matching` says,
+    /// in location order
+    pub fn search_with(&self, query: &str, matching: NameMatch) -> Vec<&IndexedSymbol> {
         let mut results = Vec::new();
-        for uri in uris {
-            if let Some(symbols) = self.by_document.get(&uri) {
-                for symbol in symbols {
-                    if symbol.name.to_lowercase().contains(&query.to_lowercase()) {
-                        results.push(symbol);
+        match matching {
+            NameMatch::Normalized => {
+                let uris = self.inverted.search(query);
+                let query = inverted::normalize_name(query);
+                for uri in uris {
+                    if let Some(symbols) = self.by_document.get(&uri) {
+                        for symbol in symbols {
+                            if inverted::normalize_name(&symbol.name).contains(&query) {
+                                results.push(symbol);
+                            }
+                        }
                     }
                 }
             }
+            // The inverted index keys normalized names only, so folded
+            // matching scans every symbol
+            NameMatch::Folded => {
+                let query = inverted::fold_name(query);
+                results.extend(
+                    self.by_document.values().flatten().filter(|symbol| inverted::fold_name(&symbol.name).contains(&query)),
+                );
+            }
         }
+        results.sort_by(|a, b| (&a.uri, a.range, &a.name).cmp(&(&b.uri, b.range, &b.name)));
+        results
+    }
+
+    /// Declarations named exactly `name`, up to composition, in location
+    /// order
+    pub fn find_named(&self, name: &str) -> Vec<&IndexedSymbol> {
+        let mut results = self.search(name);
+        results.retain(|symbol| inverted::same_identifier(&symbol.name, name));
+        results
+    }
+
+    /// Declarations in interface definitions (see [`schema`]) that code
+    /// named `name` may be generated from, in location order
+    pub fn find_schema_definitions(&self, name: &str) -> Vec<&IndexedSymbol> {
+        let mut results: Vec<&IndexedSymbol> = self
+            .by_document
+            .iter()
+            .filter(|(uri, _)| schema::is_schema(uri))
+            .flat_map(|(_, symbols)| symbols)
+            .filter(|symbol| schema::generates(symbol, name))
+            .collect();
+        results.sort_by(|a, b| (&a.uri, a.range).cmp(&(&b.uri, b.range)));
         results
     }
 
+    /// Symbols whose name matches `query` fuzzily (see [`fuzzy`]), best
+    /// match first; equally good ones go shortest name first, then in
+    /// location order
+    pub fn search_fuzzy(&self, query: &str, matching: NameMatch) -> Vec<&IndexedSymbol> {
+        let query = FuzzyQuery::new(query, matching);
+        let mut scored: Vec<(i32, &IndexedSymbol)> = self
+            .by_document
+            .values()
+            .flatten()
+            .filter_map(|symbol| Some((query.score(&symbol.name)?, symbol)))
+            .collect();
+        scored.sort_by(|(score_a, a), (score_b, b)| {
+            score_b
+                .cmp(score_a)
+                .then_with(|| a.name.chars().count().cmp(&b.name.chars().count()))
+                .then_with(|| (&a.uri, a.range, &a.name).cmp(&(&b.uri, b.range, &b.name)))
+        });
+        scored.into_iter().map(|(_, symbol)| symbol).collect()
+    }
+
+    /// Fuzzy search, ranked, collapsing duplicate entries of the same
+    /// logical symbol. Symbols of generated files come after the others.
+    pub fn search_merged(&self, query: &str, matching: NameMatch) -> Vec<MergedSymbol<'_>> {
+        let mut merged = merge_symbols(self.search_fuzzy(query, matching));
+        merged.sort_by_key(|m| m.symbol.generated);
+        merged
+    }
+
+    /// Most specific symbol at `position`.
+    ///
+    /// A symbol whose name is under the cursor wins; otherwise the innermost
+    /// symbol whose definition encloses the position is returned.
     pub fn find_at_position(&self, uri: &str, position: Position) -> Option<&IndexedSymbol> {
-        self.by_document.get(uri)?.iter().find(|s| s.selection_range.contains(position))
+        self.find_declared_at(uri, position).or_else(|| {
+            self.by_document
+                .get(uri)?
+                .iter()
+                .filter(|s| s.range.contains(position))
+                .min_by_key(|s| range_size_key(&s.range))
+        })
+    }
+
+    /// Symbol whose name is under the cursor at `position`, ignoring the
+    /// bodies that enclose it
+    pub fn find_declared_at(&self, uri: &str, position: Position) -> Option<&IndexedSymbol> {
+        self.by_document
+            .get(uri)?
+            .iter()
+            .filter(|s| s.selection_range.contains(position))
+            .min_by_key(|s| range_size_key(&s.selection_range))
+    }
+
+    /// Move documents affected by renaming `from` to `to` (a file or a
+    /// directory). Returns the `(old, new)` URI pairs that were moved.
+    pub fn rename_documents(&mut self, from: &str, to: &str) -> Vec<(String, String)> {
+        let moved: Vec<(String, String)> = self
+            .by_document
+            .keys()
+            .filter_map(|uri| rebase_uri(uri, from, to).map(|new| (uri.clone(), new)))
+            .collect();
+
+        for (old, new) in &moved {
+            let Some(mut symbols) = self.by_document.remove(old) else {
+                continue;
+            };
+            for symbol in &mut symbols {
+                self.inverted.remove(&symbol.name, old);
+                self.inverted.add(&symbol.name, new);
+                symbol.uri = new.clone();
+            }
+            self.by_document.insert(new.clone(), symbols);
+        }
+        moved
     }
 
     pub fn documents(&self) -> impl Iterator<Item = &str> {
@@ -123,4 +312,213 @@ impl SymbolIndex {
     pub fn symbol_count(&self) -> usize {
         self.by_document.values().map(|v| v.len()).sum()
     }
+
+    pub fn count_by_kind(&self) -> HashMap<SymbolKind, usize> {
+        let mut counts = HashMap::new();
+        for symbol in self.by_document.values().flatten() {
+            *counts.entry(symbol.kind).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Rough estimate of memory held by the index, in bytes
+    pub fn estimated_memory_bytes(&self) -> usize {
+        let symbols: usize = self
+            .by_document
+            .iter()
+            .map(|(uri, symbols)| {
+                uri.len()
+                    + symbols
+                        .iter()
+                        .map(|s| {
+                            std::mem::size_of::<IndexedSymbol>()
+                                + s.name.len()
+                                + s.uri.len()
+                                + s.container.as_ref().map_or(0, |c| c.len())
+                        })
+                        .sum::<usize>()
+            })
+            .sum();
+        symbols + self.inverted.estimated_memory_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sym(name: &str, kind: SymbolKind, range: (u32, u32, u32, u32), name_at: (u32, u32)) -> Symbol {
+        let (line, col) = name_at;
+        Symbol::new(
+            name.to_string(),
+            kind,
+            Range::from_coords(range.0, range.1, range.2, range.3),
+            Range::from_coords(line, col, line, col + name.len() as u32),
+        )
+    }
+
+    fn find(index: &SymbolIndex, line: u32, column: u32) -> Option<&str> {
+        index
+            .find_at_position("file:///test", Position::new(line, column))
+            .map(|s| s.name.as_str())
+    }
+
+    #[test]
+    fn test_rebase_uri() {
+        assert_eq!(rebase_uri("file:///a/b.py", "file:///a/b.py", "file:///a/c.py").as_deref(), Some("file:///a/c.py"));
+        assert_eq!(rebase_uri("file:///a/x/y.py", "file:///a", "file:///b/").as_deref(), Some("file:///b/x/y.py"));
+        assert_eq!(rebase_uri("file:///a/x/y.py", "file:///a/", "file:///b").as_deref(), Some("file:///b/x/y.py"));
+        // Sibling with a shared prefix is untouched
+        assert_eq!(rebase_uri("file:///ab/y.py", "file:///a", "file:///b"), None);
+    }
+
+    #[test]
+    fn test_generated_symbols_come_last_in_search() {
+        let mut index = SymbolIndex::new();
+        index.index_document("file:///a/user_pb2.py", &[sym("User", SymbolKind::Class, (0, 0, 1, 0), (0, 6))]);
+        index.index_document("file:///b/user.py", &[sym("User", SymbolKind::Class, (0, 0, 1, 0), (0, 6))]);
+        index.mark_generated("file:///a/user_pb2.py");
+
+        let uris: Vec<_> = index.search_merged("User", NameMatch::Normalized).iter().map(|m| m.symbol.uri.as_str()).collect();
+        assert_eq!(uris, vec!["file:///b/user.py", "file:///a/user_pb2.py"]);
+    }
+
+    #[test]
+    fn test_search_merged_is_fuzzy_and_ranked() {
+        let mut index = SymbolIndex::new();
+        index.index_document("file:///a.ts", &[
+            sym("gradients", SymbolKind::Variable, (0, 0, 0, 20), (0, 9)),
+            sym("getDocumentSymbols", SymbolKind::Function, (1, 0, 2, 0), (1, 18)),
+            sym("getDocument", SymbolKind::Function, (3, 0, 4, 0), (3, 11)),
+        ]);
+        index.index_document("file:///b.py", &[sym("get_document_symbols", SymbolKind::Function, (0, 0, 1, 0), (0, 20))]);
+
+        let names: Vec<_> = index.search_merged("gds", NameMatch::Normalized).iter().map(|m| m.symbol.name.as_str()).collect();
+        assert_eq!(names, vec!["getDocumentSymbols", "get_document_symbols", "gradients"]);
+        // Plain substrings still match
+        assert_eq!(index.search_merged("document", NameMatch::Normalized).len(), 3);
+    }
+
+    #[test]
+    fn test_rename_documents() {
+        let mut index = SymbolIndex::new();
+        index.index_document("file:///src/pkg/a.py", &[sym("alpha", SymbolKind::Function, (0, 0, 1, 0), (0, 4))]);
+        index.index_document("file:///src/other.py", &[sym("beta", SymbolKind::Function, (0, 0, 1, 0), (0, 4))]);
+
+        let moved = index.rename_documents("file:///src/pkg", "file:///src/lib");
+        assert_eq!(moved, vec![("file:///src/pkg/a.py".to_string(), "file:///src/lib/a.py".to_string())]);
+
+        assert!(index.get_document_symbols("file:///src/pkg/a.py").is_empty());
+        let symbols = index.get_document_symbols("file:///src/lib/a.py");
+        assert_eq!(symbols[0].uri, "file:///src/lib/a.py");
+        let found = index.search("alpha");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].uri, "file:///src/lib/a.py");
+        assert_eq!(index.get_document_symbols("file:///src/other.py").len(), 1);
+    }
+
+    #[test]
+    fn test_search_is_in_location_order() {
+        let mut index = SymbolIndex::new();
+        for uri in ["file:///c.py", "file:///a.py", "file:///b.py"] {
+            index.index_document(uri, &[
+                sym("load_late", SymbolKind::Function, (5, 0, 6, 0), (5, 4)),
+                sym("load_early", SymbolKind::Function, (0, 0, 1, 0), (0, 4)),
+            ]);
+        }
+
+        let found: Vec<_> = index.search("load").iter().map(|s| (s.uri.as_str(), s.name.as_str())).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("file:///a.py", "load_early"),
+                ("file:///a.py", "load_late"),
+                ("file:///b.py", "load_early"),
+                ("file:///b.py", "load_late"),
+                ("file:///c.py", "load_early"),
+                ("file:///c.py", "load_late"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_search_unicode_names() {
+        let mut index = SymbolIndex::new();
+        index.index_document("file:///a.js", &[
+            sym("cafe\u{301}Menu", SymbolKind::Function, (0, 0, 1, 0), (0, 9)),
+            sym("名前を取得", SymbolKind::Function, (2, 0, 3, 0), (2, 9)),
+            sym("🦀count", SymbolKind::Variable, (4, 0, 4, 12), (4, 6)),
+            sym("caféMenuItem", SymbolKind::Class, (5, 0, 6, 0), (5, 6)),
+        ]);
+
+        let names = |found: Vec<&IndexedSymbol>| found.iter().map(|s| s.name.clone()).collect::<Vec<_>>();
+        assert_eq!(names(index.search("CaféMenu")).len(), 2);
+        assert_eq!(names(index.search("名前")), vec!["名前を取得"]);
+        assert_eq!(names(index.search("🦀c")), vec!["🦀count"]);
+        assert!(index.search("cafemenu").is_empty());
+        assert_eq!(names(index.search_with("cafemenu", NameMatch::Folded)).len(), 2);
+        // Exact up to composition: the longer name isn't a match
+        assert_eq!(names(index.find_named("caféMenu")), vec!["cafe\u{301}Menu"]);
+    }
+
+    #[test]
+    fn test_find_at_position_nested_classes() {
+        // class Outer:
+        //     class Inner:
+        //         def method(self):
+        //             pass
+        //     def after(self):
+        //         pass
+        let symbols = vec![sym("Outer", SymbolKind::Class, (0, 0, 5, 12), (0, 6)).with_children(vec![
+            sym("Inner", SymbolKind::Class, (1, 4, 3, 16), (1, 10))
+                .with_children(vec![sym("method", SymbolKind::Method, (2, 8, 3, 16), (2, 12))]),
+            sym("after", SymbolKind::Method, (4, 4, 5, 12), (4, 8)),
+        ])];
+        let mut index = SymbolIndex::new();
+        index.index_document("file:///test", &symbols);
+
+        assert_eq!(find(&index, 0, 7), Some("Outer"));
+        assert_eq!(find(&index, 1, 11), Some("Inner"));
+        assert_eq!(find(&index, 2, 14), Some("method"));
+        // Inside the method body, not on any name
+        assert_eq!(find(&index, 3, 14), Some("method"));
+        assert!(index.find_declared_at("file:///test", Position::new(3, 14)).is_none());
+        assert_eq!(index.find_declared_at("file:///test", Position::new(2, 14)).map(|s| s.name.as_str()), Some("method"));
+        assert_eq!(find(&index, 5, 10), Some("after"));
+        assert_eq!(find(&index, 7, 0), None);
+    }
+
+    #[test]
+    fn test_find_at_position_closures() {
+        // function outer() { const cb = () => { return 1; }; return cb; }
+        let symbols = vec![sym("outer", SymbolKind::Function, (0, 0, 0, 64), (0, 9)).with_children(vec![
+            sym("cb", SymbolKind::Function, (0, 25, 0, 50), (0, 25)),
+        ])];
+        let mut index = SymbolIndex::new();
+        index.index_document("file:///test", &symbols);
+
+        assert_eq!(find(&index, 0, 40), Some("cb"));
+        assert_eq!(find(&index, 0, 55), Some("outer"));
+        assert_eq!(find(&index, 0, 10), Some("outer"));
+    }
+
+    #[test]
+    fn test_find_at_position_namespaces() {
+        // namespace a {
+        // namespace b {
+        // void f() {}
+        // }
+        // }
+        let symbols = vec![sym("a", SymbolKind::Namespace, (0, 0, 4, 1), (0, 10)).with_children(vec![
+            sym("b", SymbolKind::Namespace, (1, 0, 3, 1), (1, 10))
+                .with_children(vec![sym("f", SymbolKind::Function, (2, 0, 2, 11), (2, 5))]),
+        ])];
+        let mut index = SymbolIndex::new();
+        index.index_document("file:///test", &symbols);
+
+        assert_eq!(find(&index, 2, 9), Some("f"));
+        assert_eq!(find(&index, 3, 0), Some("b"));
+        assert_eq!(find(&index, 4, 0), Some("a"));
+        assert_eq!(find(&index, 1, 10), Some("b"));
+    }
 }
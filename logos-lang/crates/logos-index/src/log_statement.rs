@@ -0,0 +1,223 @@
+//! Log statement search: from a line of program output back to the code
+//! that printed it
+//!
+//! String literals are split into their literal segments by cutting out
+//! placeholders (`%s`, `%.2f`, `{}`, `{name}`, `${expr}`, `$name`,
+//! `#{expr}`) and escapes. A log line matches a literal when the segments
+//! appear in it in order; whatever the line adds around them (timestamps,
+//! levels, the interpolated values) is ignored. Literals on one source line
+//! are matched together, so `"a " + x + " b"` counts as one format.
+
+use crate::vfs::Vfs;
+use logos_core::Range;
+use logos_parser::{is_string_literal, pool, LanguageId, Utf16Columns};
+use regex::Regex;
+use std::path::{Path, PathBuf};
+use tree_sitter::Node;
+
+/// Share of a literal that must be found in the log line
+const MIN_LITERAL_SHARE: f64 = 0.5;
+/// Matched characters below which a match is noise
+const MIN_MATCHED_CHARS: usize = 4;
+
+/// A source line whose string literals produce a log line
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogMatch {
+    pub path: PathBuf,
+    /// Range from the first to the last literal of the line
+    pub range: Range,
+    /// The literals as written, joined with a space
+    pub literal: String,
+    /// Between 0 and 1: the mean of the share of the literal found in the
+    /// log line and the share of the log line it explains
+    pub score: f64,
+}
+
+/// Placeholders and escapes of the common format syntaxes
+fn placeholder_pattern() -> Option<Regex> {
+    Regex::new(
+        r#"%[-+ #0]*(?:\d+|\*)?(?:\.(?:\d+|\*))?[hlLqjzt]*[diouxXeEfFgGaAcspqvTtbw%]|\$\{[^}]*\}|#\{[^}]*\}|\{[^{}]*\}|\$[A-Za-z_]\w*|\\(?:[nrt0"'\\]|u[0-9a-fA-F]{4})"#,
+    )
+    .ok()
+}
+
+/// Literal segments of a format string, trimmed; segments without a letter
+/// or digit are dropped
+pub fn format_segments(format: &str) -> Vec<&str> {
+    placeholder_pattern().map_or_else(Vec::new, |placeholder| segments(&placeholder, format))
+}
+
+fn segments<'a>(placeholder: &Regex, format: &'a str) -> Vec<&'a str> {
+    placeholder
+        .split(format)
+        .map(str::trim)
+        .filter(|segment| segment.chars().any(char::is_alphanumeric))
+        .collect()
+}
+
+/// Score of `line` against the segments of a format, `None` when too little
+/// of the format is in the line. Segments are looked up in order, each
+/// after the end of the previous match.
+pub fn match_score(line: &str, segments: &[&str]) -> Option<f64> {
+    let total: usize = segments.iter().map(|s| s.len()).sum();
+    let mut matched = 0;
+    let mut from = 0;
+    for segment in segments {
+        if let Some(offset) = line[from..].find(segment) {
+            matched += segment.len();
+            from += offset + segment.len();
+        }
+    }
+    let explained = line.trim().len();
+    if matched < MIN_MATCHED_CHARS || total == 0 || explained == 0 {
+        return None;
+    }
+    let literal_share = matched as f64 / total as f64;
+    if literal_share < MIN_LITERAL_SHARE {
+        return None;
+    }
+    Some((literal_share + matched as f64 / explained as f64) / 2.0)
+}
+
+/// Source lines among `files` whose literals match `line`, best first
+pub fn find_log_statements(vfs: &dyn Vfs, files: &[PathBuf], line: &str, limit: usize) -> Vec<LogMatch> {
+    let Some(placeholder) = placeholder_pattern() else {
+        return Vec::new();
+    };
+    let words = words(line);
+    let mut matches = Vec::new();
+    for path in files {
+        let Some(lang) = path.extension().and_then(|e| e.to_str()).and_then(LanguageId::from_extension) else {
+            continue;
+        };
+        if lang.is_markup() {
+            continue;
+        }
+        let Ok(source) = vfs.read_to_string(path) else {
+            continue;
+        };
+        // Cheap check before parsing: some word of the line is in the file
+        if !words.iter().any(|word| source.contains(word)) {
+            continue;
+        }
+        matches.extend(match_file(&placeholder, path, lang, &source, line));
+    }
+    matches.sort_by(|a, b| b.score.total_cmp(&a.score).then_with(|| a.path.cmp(&b.path)));
+    matches.truncate(limit);
+    matches
+}
+
+/// Words of a log line long enough to tell files apart
+fn words(line: &str) -> Vec<&str> {
+    line.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| word.chars().count() >= 4 && word.chars().any(char::is_alphabetic))
+        .collect()
+}
+
+fn match_file(placeholder: &Regex, path: &Path, lang: LanguageId, source: &str, line: &str) -> Vec<LogMatch> {
+    let Some(tree) = pool::shared().checkout(lang).ok().and_then(|mut parser| parser.parse(source, None).ok()) else {
+        return Vec::new();
+    };
+    let mut literals = Vec::new();
+    collect_literals(tree.root_node(), &mut literals);
+    let columns = Utf16Columns::new(source);
+
+    // Literals grouped by the line they start on
+    let mut matches = Vec::new();
+    let mut rest = literals.as_slice();
+    while let Some(first) = rest.first() {
+        let row = first.start_position().row;
+        let count = rest.iter().take_while(|node| node.start_position().row == row).count();
+        let (group, tail) = rest.split_at(count);
+        rest = tail;
+
+        let formats: Vec<&str> = group.iter().filter_map(|node| literal_content(node, source)).collect();
+        let segments: Vec<&str> = formats.iter().flat_map(|format| segments(placeholder, format)).collect();
+        let Some(score) = match_score(line, &segments) else {
+            continue;
+        };
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        let texts: Vec<&str> = group.iter().filter_map(|node| source.get(node.byte_range())).collect();
+        matches.push(LogMatch {
+            path: path.to_path_buf(),
+            range: Range::new(columns.position(first.start_position()), columns.position(last.end_position())),
+            literal: texts.join(" "),
+            score,
+        });
+    }
+    matches
+}
+
+/// Outermost string literals, in source order
+fn collect_literals<'a>(node: Node<'a>, literals: &mut Vec<Node<'a>>) {
+    if is_string_literal(&node) {
+        literals.push(node);
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_literals(child, literals);
+    }
+}
+
+/// Source text between the delimiters of a literal
+fn literal_content<'a>(node: &Node, source: &'a str) -> Option<&'a str> {
+    let count = node.child_count();
+    if count < 2 {
+        return None;
+    }
+    let open = node.child(0)?;
+    let close = node.child(count - 1)?;
+    source.get(open.end_byte()..close.start_byte())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+
+    #[test]
+    fn test_format_segments() {
+        assert_eq!(format_segments("User %s logged in after %.2f s\\n"), vec!["User", "logged in after", "s"]);
+        assert_eq!(format_segments("Loaded {count} items from {}"), vec!["Loaded", "items from"]);
+        assert_eq!(format_segments("retry ${n}/$max: #{reason}"), vec!["retry"]);
+    }
+
+    #[test]
+    fn test_match_score() {
+        let segments = ["User", "logged in after"];
+        let line = "2024-05-01 12:00:01 INFO  User alice logged in after 0.25 s";
+        let score = match_score(line, &segments).unwrap();
+        assert!(score > 0.3 && score < 1.0);
+        // Out of order segments don't count
+        assert_eq!(match_score("logged in after User", &segments), None);
+        assert_eq!(match_score("nothing here", &segments), None);
+    }
+
+    #[test]
+    fn test_find_log_statements() {
+        let vfs = MemoryFs::new();
+        vfs.insert(
+            "/ws/app.py",
+            "import logging\n\ndef login(user):\n    logging.info(\"User %s logged in\", user)\n    print(f\"Session {user.id} started\")\n",
+        );
+        vfs.insert("/ws/main.go", "package main\n\nfunc run() {\n\tlog.Printf(\"User %v logged out\", u)\n}\n");
+        vfs.insert("/ws/server.js", "console.log('Listening on ' + port + ' with ' + workers + ' workers');\n");
+        let files: Vec<PathBuf> = ["/ws/app.py", "/ws/main.go", "/ws/server.js"].into_iter().map(PathBuf::from).collect();
+
+        let found = find_log_statements(&vfs, &files, "INFO:root:User bob logged in", 5);
+        assert_eq!(found[0].path, PathBuf::from("/ws/app.py"));
+        assert_eq!(found[0].range.start.line, 3);
+        assert_eq!(found[0].literal, "\"User %s logged in\"");
+
+        let found = find_log_statements(&vfs, &files, "Session 42 started", 5);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].range.start.line, 4);
+
+        let found = find_log_statements(&vfs, &files, "Listening on 8080 with 4 workers", 5);
+        assert_eq!(found[0].path, PathBuf::from("/ws/server.js"));
+        assert!(found[0].score > 0.7);
+    }
+}
@@ -0,0 +1,151 @@
+//! Canonicalization of symbol search results
+//!
+//! The same logical symbol can be indexed more than once, e.g. a declaration
+//! plus an `export { foo }` clause. Merging collapses those entries into one
+//! result that keeps a primary location and lists the others as alternates.
+
+use crate::inverted::normalize_name;
+use crate::IndexedSymbol;
+use logos_core::{Location, Range, SymbolKind};
+use std::collections::HashMap;
+
+/// Identity of a logical symbol: uri, normalized name, kind and container
+type CanonicalKey = (String, String, SymbolKind, Option<String>);
+
+/// One logical symbol, with its primary entry and alternate locations
+#[derive(Debug, Clone)]
+pub struct MergedSymbol<'a> {
+    /// Entry chosen as the primary (declaration) location
+    pub symbol: &'a IndexedSymbol,
+    /// Other places the same symbol was indexed
+    pub alternate_locations: Vec<Location>,
+}
+
+impl MergedSymbol<'_> {
+    pub fn location(&self) -> Location {
+        Location::new(self.symbol.uri.clone(), self.symbol.range)
+    }
+}
+
+/// Identity of a logical symbol within a document
+fn canonical_key(symbol: &IndexedSymbol) -> CanonicalKey {
+    (
+        symbol.uri.clone(),
+        normalize_name(&symbol.name),
+        symbol.kind,
+        symbol.container.clone(),
+    )
+}
+
+/// Rough size of a range as (lines, columns)
+fn span(range: &Range) -> (u32, u32) {
+    let lines = range.end.line.saturating_sub(range.start.line);
+    let columns = if lines == 0 {
+        range.end.column.saturating_sub(range.start.column)
+    } else {
+        range.end.column
+    };
+    (lines, columns)
+}
+
+/// Prefer the entry that looks like the declaration: the widest range wins,
+/// ties go to the earliest position.
+fn is_better_primary(candidate: &IndexedSymbol, current: &IndexedSymbol) -> bool {
+    let (cand, cur) = (span(&candidate.range), span(&current.range));
+    cand > cur || (cand == cur && candidate.range.start < current.range.start)
+}
+
+/// Merge duplicate entries of the same logical symbol, preserving the order in
+/// which each symbol was first seen.
+pub fn merge_symbols<'a, I>(symbols: I) -> Vec<MergedSymbol<'a>>
+where
+    I: IntoIterator<Item = &'a IndexedSymbol>,
+{
+    let mut merged: Vec<MergedSymbol<'a>> = Vec::new();
+    let mut slots: HashMap<CanonicalKey, usize> = HashMap::new();
+
+    for symbol in symbols {
+        let key = canonical_key(symbol);
+        let Some(&slot) = slots.get(&key) else {
+            slots.insert(key, merged.len());
+            merged.push(MergedSymbol { symbol, alternate_locations: Vec::new() });
+            continue;
+        };
+
+        let entry = &mut merged[slot];
+        if entry.symbol.range == symbol.range {
+            continue;
+        }
+        if is_better_primary(symbol, entry.symbol) {
+            let previous = std::mem::replace(&mut entry.symbol, symbol);
+            entry.alternate_locations.push(Location::new(previous.uri.clone(), previous.range));
+        } else if !entry.alternate_locations.iter().any(|l| l.range == symbol.range) {
+            entry.alternate_locations.push(Location::new(symbol.uri.clone(), symbol.range));
+        }
+    }
+
+    for entry in &mut merged {
+        let primary = entry.symbol.range;
+        entry.alternate_locations.retain(|l| l.range != primary);
+        entry.alternate_locations.sort_by_key(|l| l.range.start);
+    }
+
+    merged
+}
+
+/// Sort locations into location order and drop repeats
+pub fn dedup_locations(mut locations: Vec<Location>) -> Vec<Location> {
+    locations.sort();
+    locations.dedup();
+    locations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sym(name: &str, uri: &str, range: Range) -> IndexedSymbol {
+        IndexedSymbol {
+            name: name.to_string(),
+            kind: SymbolKind::Function,
+            uri: uri.to_string(),
+            range,
+            selection_range: range,
+            container: None,
+            detail: None,
+            documentation: None,
+            generated: false,
+        }
+    }
+
+    #[test]
+    fn test_declaration_and_export_merge() {
+        let export = sym("foo", "file:///a.ts", Range::from_coords(10, 9, 10, 12));
+        let decl = sym("foo", "file:///a.ts", Range::from_coords(0, 0, 3, 1));
+        let merged = merge_symbols([&export, &decl]);
+
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].symbol.range, decl.range);
+        assert_eq!(merged[0].alternate_locations.len(), 1);
+        assert_eq!(merged[0].alternate_locations[0].range, export.range);
+    }
+
+    #[test]
+    fn test_distinct_symbols_kept() {
+        let a = sym("foo", "file:///a.ts", Range::from_coords(0, 0, 1, 0));
+        let b = sym("foo", "file:///b.ts", Range::from_coords(0, 0, 1, 0));
+        let exact_dup = a.clone();
+        let merged = merge_symbols([&a, &b, &exact_dup]);
+
+        assert_eq!(merged.len(), 2);
+        assert!(merged.iter().all(|m| m.alternate_locations.is_empty()));
+    }
+
+    #[test]
+    fn test_dedup_locations() {
+        let loc = Location::new("file:///a.ts".to_string(), Range::from_coords(1, 0, 1, 3));
+        let other = Location::new("file:///a.ts".to_string(), Range::from_coords(2, 0, 2, 3));
+        let result = dedup_locations(vec![other.clone(), loc.clone(), loc.clone()]);
+        assert_eq!(result, vec![loc, other]);
+    }
+}
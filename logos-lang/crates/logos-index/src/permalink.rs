@@ -0,0 +1,163 @@
+//! Stable references to a piece of code, for sharing and deep links
+//!
+//! A permalink names a file relative to the workspace root, the enclosing
+//! symbol by its qualified name, and 1-based lines:
+//!
+//! - `src/user.ts::User.greet#L10-L14`
+//! - `src/user.ts#L3` (a single line outside any symbol)
+//!
+//! The path ends at the first `::`, so qualified names may contain `::`
+//! themselves (`src/lib.rs::parser::Parser.parse#L40-L52`). The symbol
+//! outlives edits that move it, so it is preferred over the lines when the
+//! link is resolved.
+
+use crate::IndexedSymbol;
+use logos_core::Range;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Permalink {
+    /// Path relative to the workspace root, `/`-separated
+    pub path: String,
+    /// Qualified name of the enclosing symbol, names joined with `.`
+    pub symbol: Option<String>,
+    /// First line, 1-based
+    pub start_line: u32,
+    /// Last line, 1-based and inclusive
+    pub end_line: u32,
+}
+
+impl Permalink {
+    /// Link to `range` of a document with `symbols`. A range ending at the
+    /// start of a line doesn't take that line in.
+    pub fn new(path: impl Into<String>, symbols: &[IndexedSymbol], range: Range) -> Self {
+        let end = if range.end.column == 0 && range.end.line > range.start.line { range.end.line - 1 } else { range.end.line };
+        Self {
+            path: path.into(),
+            symbol: qualified_name(&enclosing_symbols(symbols, range)),
+            start_line: range.start.line + 1,
+            end_line: end + 1,
+        }
+    }
+
+    /// Parse a permalink; `None` when `text` doesn't hold one
+    pub fn parse(text: &str) -> Option<Self> {
+        let (target, lines) = text.trim().rsplit_once("#L")?;
+        let (start, end) = match lines.split_once('-') {
+            Some((start, end)) => (start, end.strip_prefix('L').unwrap_or(end)),
+            None => (lines, lines),
+        };
+        let (start_line, end_line): (u32, u32) = (start.parse().ok()?, end.parse().ok()?);
+        if start_line == 0 || end_line < start_line {
+            return None;
+        }
+        let (path, symbol) = match target.split_once("::") {
+            Some((path, symbol)) => (path, Some(symbol.to_string()).filter(|s| !s.is_empty())),
+            None => (target, None),
+        };
+        if path.is_empty() {
+            return None;
+        }
+        Some(Self { path: path.to_string(), symbol, start_line, end_line })
+    }
+
+    /// Range of the linked lines, 0-based, from the start of the first to
+    /// the start of the line after the last
+    pub fn line_range(&self) -> Range {
+        Range::from_coords(self.start_line - 1, 0, self.end_line, 0)
+    }
+
+    /// The linked symbol among a document's `symbols`, if it still exists
+    pub fn find_symbol<'a>(&self, symbols: &'a [IndexedSymbol]) -> Option<&'a IndexedSymbol> {
+        let symbol = self.symbol.as_deref()?;
+        symbols.iter().find(|candidate| {
+            let chain = enclosing_symbols(symbols, candidate.range);
+            chain.last().is_some_and(|last| std::ptr::eq(*last, *candidate)) && qualified_name(&chain).as_deref() == Some(symbol)
+        })
+    }
+}
+
+impl fmt::Display for Permalink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path)?;
+        if let Some(symbol) = &self.symbol {
+            write!(f, "::{}", symbol)?;
+        }
+        if self.end_line == self.start_line {
+            write!(f, "#L{}", self.start_line)
+        } else {
+            write!(f, "#L{}-L{}", self.start_line, self.end_line)
+        }
+    }
+}
+
+/// Symbols whose range holds `range`, outermost first
+fn enclosing_symbols(symbols: &[IndexedSymbol], range: Range) -> Vec<&IndexedSymbol> {
+    let mut enclosing: Vec<&IndexedSymbol> = symbols
+        .iter()
+        .filter(|symbol| symbol.range.start <= range.start && range.end <= symbol.range.end)
+        .collect();
+    enclosing.sort_by_key(|symbol| std::cmp::Reverse(crate::range_size_key(&symbol.range)));
+    enclosing
+}
+
+fn qualified_name(chain: &[&IndexedSymbol]) -> Option<String> {
+    (!chain.is_empty()).then(|| chain.iter().map(|symbol| symbol.name.as_str()).collect::<Vec<_>>().join("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logos_core::{Position, Symbol, SymbolKind};
+
+    fn symbols() -> Vec<IndexedSymbol> {
+        let greet = Symbol::new(
+            "greet".to_string(),
+            SymbolKind::Method,
+            Range::from_coords(9, 2, 13, 3),
+            Range::from_coords(9, 2, 9, 7),
+        );
+        let user = Symbol::new(
+            "User".to_string(),
+            SymbolKind::Class,
+            Range::from_coords(2, 0, 20, 1),
+            Range::from_coords(2, 6, 2, 10),
+        )
+        .with_children(vec![greet]);
+        let mut index = crate::SymbolIndex::new();
+        index.index_document("file:///ws/src/user.ts", &[user]);
+        index.get_document_symbols("file:///ws/src/user.ts").to_vec()
+    }
+
+    #[test]
+    fn test_describe_selection() {
+        let symbols = symbols();
+        let link = Permalink::new("src/user.ts", &symbols, Range::from_coords(9, 4, 13, 0));
+        assert_eq!(link.to_string(), "src/user.ts::User.greet#L10-L13");
+
+        let link = Permalink::new("src/user.ts", &symbols, Range::new(Position::new(0, 0), Position::new(0, 5)));
+        assert_eq!(link.to_string(), "src/user.ts#L1");
+    }
+
+    #[test]
+    fn test_parse_round_trip() {
+        for text in ["src/user.ts::User.greet#L10-L14", "src/user.ts#L3", "src/lib.rs::parser::Parser.parse#L40-L52"] {
+            assert_eq!(Permalink::parse(text).unwrap().to_string(), text);
+        }
+        let link = Permalink::parse("src/lib.rs::parser::Parser.parse#L40-52").unwrap();
+        assert_eq!((link.path.as_str(), link.symbol.as_deref()), ("src/lib.rs", Some("parser::Parser.parse")));
+        assert_eq!(link.line_range(), Range::from_coords(39, 0, 52, 0));
+        assert_eq!(Permalink::parse("src/a.ts#L5-L2"), None);
+        assert_eq!(Permalink::parse("src/a.ts"), None);
+        assert_eq!(Permalink::parse("#L1"), None);
+    }
+
+    #[test]
+    fn test_find_symbol() {
+        let symbols = symbols();
+        let link = Permalink::parse("src/user.ts::User.greet#L1-L2").unwrap();
+        assert_eq!(link.find_symbol(&symbols).map(|s| s.range.start.line), Some(9));
+        let link = Permalink::parse("src/user.ts::greet#L1").unwrap();
+        assert!(link.find_symbol(&symbols).is_none());
+    }
+}
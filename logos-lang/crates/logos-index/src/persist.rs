@@ -0,0 +1,382 @@
+//! Index persistence
+//!
+//! A saved index is one JSON document: a header carrying the schema version
+//! and an [`IndexSnapshot`]. Files written under an older schema are brought
+//! up to date by running [`MIGRATIONS`] on the raw JSON, one version at a
+//! time. A file that can't be (written by a newer release, corrupt, or a
+//! failed migration) loads as [`LoadOutcome::Rebuild`], so callers reindex
+//! from scratch rather than trusting it.
+//!
+//! The snapshot records what each file was analyzed from — content hash,
+//! modification time, adapter version — so a refresh analyzes again only
+//! the files that changed or whose adapter did.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::symbol_table::{CallSite, FileRecord, ProjectIndex, SmartSymbol, SymbolId, SymbolReference};
+
+/// Version of the saved index layout. Bump it on any change to
+/// [`IndexSnapshot`] or the types it contains, and register a [`Migration`]
+/// from the previous version.
+pub const INDEX_SCHEMA_VERSION: u32 = 3;
+
+const MAGIC: &str = "logos-index";
+
+/// One schema step: rewrites a document of version `from` in place into
+/// version `from + 1`
+pub struct Migration {
+    pub from: u32,
+    pub migrate: fn(&mut Value) -> Result<(), String>,
+}
+
+/// Registered schema steps
+pub const MIGRATIONS: &[Migration] = &[
+    Migration { from: 1, migrate: add_file_records },
+    Migration { from: 2, migrate: add_evicted_files },
+];
+
+/// Schema 2 records what each file was analyzed from. Files of an older
+/// index have no record, so a refresh analyzes them all again.
+fn add_file_records(document: &mut Value) -> Result<(), String> {
+    let index = document["index"].as_object_mut().ok_or("no index")?;
+    index.insert("files".to_string(), Value::Array(Vec::new()));
+    Ok(())
+}
+
+/// Schema 3 saves the symbols of evicted files without their details, and
+/// lists those files. Older indexes saved every detail, so none is missing.
+fn add_evicted_files(document: &mut Value) -> Result<(), String> {
+    let index = document["index"].as_object_mut().ok_or("no index")?;
+    index.insert("evicted".to_string(), Value::Array(Vec::new()));
+    Ok(())
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexHeader {
+    pub magic: String,
+    pub schema_version: u32,
+    /// Crate version that wrote the file
+    pub generator: String,
+    /// When the indexing run captured in the file started, in milliseconds
+    /// since the Unix epoch
+    pub indexed_at: u64,
+}
+
+impl IndexHeader {
+    pub fn indexed_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.indexed_at)
+    }
+}
+
+/// Everything a [`ProjectIndex`] holds, in serializable form
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct IndexSnapshot {
+    pub symbols: Vec<SmartSymbol>,
+    pub references: Vec<SymbolReference>,
+    pub calls: Vec<CallSite>,
+    pub extends: Vec<(SymbolId, SymbolId)>,
+    pub implements: Vec<(SymbolId, SymbolId)>,
+    pub imports: Vec<(PathBuf, PathBuf)>,
+    pub exports: Vec<(PathBuf, Vec<SymbolId>)>,
+    /// What each file was analyzed from, by URI
+    pub files: Vec<(String, FileRecord)>,
+    /// Files whose symbols are saved without their details, read back from
+    /// the files when asked for
+    pub evicted: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct IndexFile {
+    header: IndexHeader,
+    index: IndexSnapshot,
+}
+
+/// A saved index read back
+#[derive(Debug)]
+pub struct LoadedIndex {
+    pub header: IndexHeader,
+    pub snapshot: IndexSnapshot,
+    /// Schema version the file was written with, when it had to be migrated
+    pub migrated_from: Option<u32>,
+}
+
+#[derive(Debug)]
+pub enum LoadOutcome {
+    /// Nothing saved yet
+    Missing,
+    Loaded(Box<LoadedIndex>),
+    /// The file can't be used; the reason is meant for the user
+    Rebuild(String),
+}
+
+impl ProjectIndex {
+    /// Capture the index contents
+    pub fn snapshot(&self) -> IndexSnapshot {
+        IndexSnapshot {
+            symbols: self.symbols.all_stored(),
+            references: self.symbols.all_references(),
+            calls: self.call_graph.all(),
+            extends: self.type_hierarchy.extends_edges(),
+            implements: self.type_hierarchy.implements_edges(),
+            imports: self.dependencies.import_edges(),
+            exports: self.dependencies.all_exports(),
+            files: self.files.iter().map(|entry| (entry.key().clone(), entry.value().clone())).collect(),
+            evicted: self.symbols.evicted_uris(),
+        }
+    }
+
+    /// Add the contents of a snapshot to the index
+    pub fn restore(&self, snapshot: IndexSnapshot) {
+        if let Some(max) = snapshot.symbols.iter().map(|s| s.id).max_by_key(|id| id.0) {
+            SymbolId::reserve_through(max);
+        }
+        for symbol in snapshot.symbols {
+            self.symbols.add_symbol(symbol);
+        }
+        self.symbols.mark_evicted(&snapshot.evicted);
+        for reference in snapshot.references {
+            self.symbols.add_reference(reference);
+        }
+        for call in snapshot.calls {
+            self.call_graph.add_call(call);
+        }
+        for (subtype, supertype) in snapshot.extends {
+            self.type_hierarchy.add_extends(subtype, supertype);
+        }
+        for (implementor, interface) in snapshot.implements {
+            self.type_hierarchy.add_implements(implementor, interface);
+        }
+        for (from, to) in snapshot.imports {
+            self.dependencies.add_import(from, to);
+        }
+        for (file, symbols) in snapshot.exports {
+            self.dependencies.set_exports(file, symbols);
+        }
+        for (uri, record) in snapshot.files {
+            self.files.insert(uri, record);
+        }
+    }
+}
+
+/// Write `index` to `path`, replacing any previous file atomically.
+/// `indexed_at` is when the indexing run that produced it started.
+pub fn save(index: &ProjectIndex, path: &Path, indexed_at: SystemTime) -> io::Result<()> {
+    let file = IndexFile {
+        header: IndexHeader {
+            magic: MAGIC.to_string(),
+            schema_version: INDEX_SCHEMA_VERSION,
+            generator: env!("CARGO_PKG_VERSION").to_string(),
+            indexed_at: indexed_at.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+        },
+        index: index.snapshot(),
+    };
+    let json = serde_json::to_vec(&file).map_err(io::Error::other)?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let partial = path.with_extension("tmp");
+    fs::write(&partial, json)?;
+    fs::rename(&partial, path)
+}
+
+/// Read a saved index, migrating it to the current schema
+pub fn load(path: &Path) -> LoadOutcome {
+    load_with(path, MIGRATIONS, INDEX_SCHEMA_VERSION)
+}
+
+fn load_with(path: &Path, migrations: &[Migration], current: u32) -> LoadOutcome {
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return LoadOutcome::Missing,
+        Err(e) => return LoadOutcome::Rebuild(format!("the saved index could not be read: {}", e)),
+    };
+    let mut document: Value = match serde_json::from_slice(&bytes) {
+        Ok(document) => document,
+        Err(e) => return LoadOutcome::Rebuild(format!("the saved index is corrupt: {}", e)),
+    };
+
+    let header = match serde_json::from_value::<IndexHeader>(document["header"].clone()) {
+        Ok(header) if header.magic == MAGIC => header,
+        _ => return LoadOutcome::Rebuild("the saved index has no valid header".to_string()),
+    };
+    if header.schema_version > current {
+        return LoadOutcome::Rebuild(format!(
+            "the saved index uses schema version {}, newer than this release supports ({})",
+            header.schema_version, current
+        ));
+    }
+
+    let migrated_from = (header.schema_version < current).then_some(header.schema_version);
+    let mut version = header.schema_version;
+    while version < current {
+        let Some(step) = migrations.iter().find(|m| m.from == version) else {
+            return LoadOutcome::Rebuild(format!("no migration from index schema version {}", version));
+        };
+        if let Err(e) = (step.migrate)(&mut document) {
+            return LoadOutcome::Rebuild(format!("migrating the index from schema version {} failed: {}", version, e));
+        }
+        version += 1;
+        document["header"]["schemaVersion"] = Value::from(version);
+    }
+
+    match serde_json::from_value::<IndexFile>(document) {
+        Ok(file) => LoadOutcome::Loaded(Box::new(LoadedIndex { header: file.header, snapshot: file.index, migrated_from })),
+        Err(e) => LoadOutcome::Rebuild(format!("the saved index is corrupt: {}", e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adapter::{make_location, SymbolBuilder};
+    use logos_core::{Range, SymbolKind};
+    use tempfile::tempdir;
+
+    fn sample_index() -> ProjectIndex {
+        let index = ProjectIndex::new();
+        let range = Range::default();
+        let base = SymbolBuilder::new("Base", SymbolKind::Class, make_location("file:///a.ts", range, range)).build();
+        let derived = SymbolBuilder::new("Derived", SymbolKind::Class, make_location("file:///b.ts", range, range)).build();
+        index.type_hierarchy.add_extends(derived.id, base.id);
+        index.dependencies.add_import(PathBuf::from("/b.ts"), PathBuf::from("/a.ts"));
+        index.symbols.add_symbol(base);
+        index.symbols.add_symbol(derived);
+        index.files.insert("file:///a.ts".to_string(), FileRecord::new("class Base {}", None, "typescript", "1"));
+        index
+    }
+
+    #[test]
+    fn test_save_and_load_round_trip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("cache").join("index.json");
+        let index = sample_index();
+        save(&index, &path, SystemTime::now()).unwrap();
+
+        let LoadOutcome::Loaded(loaded) = load(&path) else {
+            panic!("expected a loaded index");
+        };
+        assert_eq!(loaded.migrated_from, None);
+        let restored = ProjectIndex::new();
+        restored.restore(loaded.snapshot);
+
+        let derived = restored.symbols.find_by_name("Derived").remove(0);
+        let base = restored.symbols.find_by_name("Base").remove(0);
+        assert_eq!(restored.type_hierarchy.get_supertypes(derived.id), vec![base.id]);
+        assert_eq!(restored.dependencies.get_imports(&PathBuf::from("/b.ts")), vec![PathBuf::from("/a.ts")]);
+        assert!(SymbolId::new().0 > derived.id.0.max(base.id.0));
+        assert!(restored.files.get("file:///a.ts").is_some_and(|record| record.has_content("class Base {}")));
+    }
+
+    #[test]
+    fn test_migrations_and_rebuild() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("index.json");
+        assert!(matches!(load(&path), LoadOutcome::Missing));
+
+        save(&sample_index(), &path, SystemTime::now()).unwrap();
+
+        // A release on schema 4 renames `calls` to `callSites`, and 5 renames it back
+        fn rename(document: &mut Value, from: &str, to: &str) -> Result<(), String> {
+            let index = document["index"].as_object_mut().ok_or("no index")?;
+            let value = index.remove(from).ok_or("missing field")?;
+            index.insert(to.to_string(), value);
+            Ok(())
+        }
+        let steps = [
+            Migration { from: 3, migrate: |d| rename(d, "calls", "callSites") },
+            Migration { from: 4, migrate: |d| rename(d, "callSites", "calls") },
+        ];
+        match load_with(&path, &steps, 5) {
+            LoadOutcome::Loaded(loaded) => {
+                assert_eq!(loaded.migrated_from, Some(3));
+                assert_eq!(loaded.header.schema_version, 5);
+                assert_eq!(loaded.snapshot.symbols.len(), 2);
+            }
+            other => panic!("expected a migrated index, got {:?}", other),
+        }
+
+        // No path to the current schema, or a file from a newer release
+        assert!(matches!(load_with(&path, &steps[..1], 5), LoadOutcome::Rebuild(_)));
+        assert!(matches!(load_with(&path, &[], 0), LoadOutcome::Rebuild(_)));
+
+        fs::write(&path, "{not json").unwrap();
+        assert!(matches!(load(&path), LoadOutcome::Rebuild(_)));
+    }
+
+    #[test]
+    fn test_schema_1_loads_without_file_records() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("index.json");
+        save(&sample_index(), &path, SystemTime::now()).unwrap();
+
+        // Rewrite the file as schema 1 wrote it
+        let mut document: Value = serde_json::from_slice(&fs::read(&path).unwrap()).unwrap();
+        document["header"]["schemaVersion"] = Value::from(1);
+        document["index"].as_object_mut().unwrap().remove("files");
+        document["index"].as_object_mut().unwrap().remove("evicted");
+        fs::write(&path, serde_json::to_vec(&document).unwrap()).unwrap();
+
+        let LoadOutcome::Loaded(loaded) = load(&path) else {
+            panic!("expected a migrated index");
+        };
+        assert_eq!(loaded.migrated_from, Some(1));
+        assert_eq!(loaded.snapshot.symbols.len(), 2);
+        assert!(loaded.snapshot.files.is_empty());
+        assert!(loaded.snapshot.evicted.is_empty());
+    }
+
+    #[test]
+    fn test_evicted_files_saved_without_reading_back() {
+        use crate::symbol_table::{Hydrator, TypeInfo};
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let symbol = |uri: &str, name: &str| {
+            let range = Range::default();
+            SymbolBuilder::new(name, SymbolKind::Function, make_location(uri, range, range))
+                .type_info(TypeInfo::simple("() -> int"))
+                .build()
+        };
+        let read_back = Arc::new(AtomicUsize::new(0));
+        let hydrator = |read_back: &Arc<AtomicUsize>| -> Hydrator {
+            let read_back = Arc::clone(read_back);
+            Arc::new(move |uri: &str| {
+                read_back.fetch_add(1, Ordering::SeqCst);
+                let name = if uri == "file:///a.py" { "a" } else { "b" };
+                Some(vec![symbol(uri, name)])
+            })
+        };
+
+        // Over the budget, only b.py, last asked for, keeps its details
+        let index = ProjectIndex::new();
+        index.symbols.add_symbol(symbol("file:///a.py", "a"));
+        index.symbols.add_symbol(symbol("file:///b.py", "b"));
+        index.symbols.set_detail_budget(Some(1), hydrator(&read_back));
+        index.symbols.get_file_symbols("file:///b.py");
+        read_back.store(0, Ordering::SeqCst);
+
+        let snapshot = index.snapshot();
+        assert_eq!(read_back.load(Ordering::SeqCst), 0);
+        assert_eq!(snapshot.evicted, vec!["file:///a.py".to_string()]);
+        let saved_a = snapshot.symbols.iter().find(|s| s.name == "a").unwrap();
+        assert!(saved_a.type_info.is_none());
+
+        // Restored, a.py is read back when its symbols are asked for
+        let restored = ProjectIndex::new();
+        restored.symbols.set_detail_budget(None, hydrator(&read_back));
+        restored.restore(snapshot);
+        assert_eq!(restored.symbols.evicted_files(), 1);
+        let a = restored.symbols.get_file_symbols("file:///a.py").remove(0);
+        assert_eq!(a.type_info.map(|t| t.type_expr), Some("() -> int".to_string()));
+        assert_eq!(read_back.load(Ordering::SeqCst), 1);
+        assert_eq!(restored.symbols.evicted_files(), 0);
+    }
+}
@@ -1,37 +1,48 @@
 //! Python Language Adapter
 //!
 //! Implements the LanguageAdapter trait for Python files.
-//! Extracts symbols, imports, exports, and call relationships.
+//! Extracts symbols, imports, exports, and call relationships. Decorators
+//! are kept as symbol attributes, and relative imports resolve to files.
 
 use crate::adapter::{
     AnalysisResult, CallInfo, ExportInfo, ImportInfo, ImportItem, LanguageAdapter,
     SymbolBuilder, TypeRelation, make_location,
 };
-use crate::symbol_table::{SymbolId, TypeInfo, Visibility};
+use crate::symbol_table::{Attribute, SymbolId, TypeInfo, Visibility};
+use crate::vfs::Vfs;
 use logos_core::{Position, Range, SymbolKind};
 use std::path::Path;
-use tree_sitter::{Node, Parser, Tree};
+use logos_parser::{pool, LanguageId};
+use tree_sitter::{Node, Tree};
 
 /// Python language adapter
-pub struct PythonAdapter {
-    parser: std::sync::Mutex<Parser>,
-}
+pub struct PythonAdapter;
 
 impl PythonAdapter {
     pub fn new() -> Result<Self, String> {
-        let mut parser = Parser::new();
-        parser
-            .set_language(&tree_sitter_python::LANGUAGE.into())
+        // Fail early when the grammar can't be loaded
+        pool::shared()
+            .checkout(LanguageId::Python)
             .map_err(|e| format!("Failed to set Python language: {}", e))?;
-
-        Ok(Self {
-            parser: std::sync::Mutex::new(parser),
-        })
+        Ok(Self)
     }
 
     fn parse(&self, source: &str) -> Option<Tree> {
-        let mut parser = self.parser.lock().ok()?;
-        parser.parse(source, None)
+        let mut parser = pool::shared().checkout(LanguageId::Python).ok()?;
+        parser.parse(source, None).ok()
+    }
+
+    fn analyze_tree(&self, uri: &str, source: &str, tree: &Tree) -> AnalysisResult {
+        let mut context = AnalysisContext {
+            uri: uri.to_string(),
+            source,
+            result: AnalysisResult::default(),
+            scope_stack: Vec::new(),
+        };
+
+        analyze_node(&tree.root_node(), &mut context);
+
+        context.result.with_utf16_columns(source)
     }
 }
 
@@ -51,24 +62,25 @@ impl LanguageAdapter for PythonAdapter {
     }
 
     fn analyze(&self, uri: &str, source: &str) -> AnalysisResult {
-        let tree = match self.parse(source) {
-            Some(t) => t,
-            None => return AnalysisResult::default(),
-        };
-
-        let mut context = AnalysisContext {
-            uri: uri.to_string(),
-            source,
-            result: AnalysisResult::default(),
-            scope_stack: Vec::new(),
-        };
-
-        analyze_node(&tree.root_node(), &mut context);
+        match self.parse(source) {
+            Some(tree) => self.analyze_tree(uri, source, &tree),
+            None => AnalysisResult::default(),
+        }
+    }
 
-        context.result
+    fn analyze_batch(&self, files: &[(&str, &str)]) -> Vec<AnalysisResult> {
+        // One parser from the pool for the whole batch
+        let mut parser = pool::shared().checkout(LanguageId::Python).ok();
+        files
+            .iter()
+            .map(|(uri, source)| match parser.as_mut().and_then(|p| p.parse(source, None).ok()) {
+                Some(tree) => self.analyze_tree(uri, source, &tree),
+                None => AnalysisResult::default(),
+            })
+            .collect()
     }
 
-    fn resolve_import(&self, from_file: &Path, import_path: &str) -> Option<std::path::PathBuf> {
+    fn resolve_import(&self, vfs: &dyn Vfs, from_file: &Path, import_path: &str) -> Option<std::path::PathBuf> {
         // Skip standard library imports
         if !import_path.starts_with('.') {
             return None;
@@ -87,22 +99,32 @@ impl LanguageAdapter for PythonAdapter {
         if module_name.is_empty() {
             // Just dots - import from parent package
             let init = base.join("__init__.py");
-            if init.exists() {
+            if vfs.exists(&init) {
                 return Some(init);
             }
         } else {
             // Try as directory with __init__.py
             let dir_path = base.join(module_name.replace('.', "/"));
             let init = dir_path.join("__init__.py");
-            if init.exists() {
+            if vfs.exists(&init) {
                 return Some(init);
             }
 
             // Try as .py file
             let file_path = base.join(format!("{}.py", module_name.replace('.', "/")));
-            if file_path.exists() {
+            if vfs.exists(&file_path) {
                 return Some(file_path);
             }
+
+            // `from . import name` where the package's __init__.py defines name
+            let package = match module_name.rsplit_once('.') {
+                Some((package, _)) => base.join(package.replace('.', "/")),
+                None => base,
+            };
+            let init = package.join("__init__.py");
+            if vfs.exists(&init) {
+                return Some(init);
+            }
         }
 
         None
@@ -120,6 +142,8 @@ struct AnalysisContext<'a> {
 struct ScopeInfo {
     symbol_id: SymbolId,
     name: String,
+    /// Functions defined directly in a class are methods
+    is_class: bool,
 }
 
 impl<'a> AnalysisContext<'a> {
@@ -221,10 +245,8 @@ fn analyze_import(node: &Node, ctx: &mut AnalysisContext) {
 
 fn analyze_import_from(node: &Node, ctx: &mut AnalysisContext) {
     // from foo import bar, baz
-    let module_name = node
-        .child_by_field_name("module_name")
-        .map(|n| ctx.get_text(&n))
-        .unwrap_or_default();
+    let module_node = node.child_by_field_name("module_name");
+    let module_name = module_node.map(|n| ctx.get_text(&n)).unwrap_or_default();
 
     let mut import = ImportInfo {
         module_path: module_name,
@@ -235,6 +257,9 @@ fn analyze_import_from(node: &Node, ctx: &mut AnalysisContext) {
 
     for i in 0..node.named_child_count() {
         if let Some(child) = node.named_child(i) {
+            if module_node.is_some_and(|module| module.id() == child.id()) {
+                continue;
+            }
             match child.kind() {
                 "dotted_name" | "identifier" => {
                     let name = ctx.get_text(&child);
@@ -272,7 +297,19 @@ fn analyze_import_from(node: &Node, ctx: &mut AnalysisContext) {
         }
     }
 
-    if !import.module_path.is_empty() || !import.items.is_empty() {
+    // `from . import sibling` may import the module `.sibling`: each name
+    // gets its own import, resolved to the module or to the package
+    let dots_only = !import.module_path.is_empty() && import.module_path.chars().all(|c| c == '.');
+    if dots_only && !import.items.iter().any(|item| item.name == "*") {
+        for item in import.items {
+            ctx.result.imports.push(ImportInfo {
+                module_path: format!("{}{}", import.module_path, item.name),
+                items: vec![item],
+                is_type_only: false,
+                location: import.location,
+            });
+        }
+    } else if !import.module_path.is_empty() || !import.items.is_empty() {
         ctx.result.imports.push(import);
     }
 }
@@ -292,44 +329,18 @@ fn analyze_function(node: &Node, ctx: &mut AnalysisContext) {
         Visibility::Public
     };
 
-    // Check for decorators
-    let mut is_staticmethod = false;
-    let mut is_classmethod = false;
-    let mut is_property = false;
-
-    if let Some(decorators) = node.child_by_field_name("decorator") {
-        let dec_text = ctx.get_text(&decorators);
-        is_staticmethod = dec_text.contains("staticmethod");
-        is_classmethod = dec_text.contains("classmethod");
-        is_property = dec_text.contains("property");
-    }
-
-    // Also check siblings for decorators
-    if let Some(parent) = node.parent() {
-        for i in 0..parent.named_child_count() {
-            if let Some(sibling) = parent.named_child(i) {
-                if sibling.kind() == "decorator" {
-                    let dec_text = ctx.get_text(&sibling);
-                    if dec_text.contains("staticmethod") {
-                        is_staticmethod = true;
-                    }
-                    if dec_text.contains("classmethod") {
-                        is_classmethod = true;
-                    }
-                    if dec_text.contains("property") {
-                        is_property = true;
-                    }
-                }
-            }
-        }
-    }
+    let attributes = decorators(node, ctx);
+    let is_property = attributes.iter().any(|a| {
+        a.name.ends_with("property") || a.name.ends_with(".setter") || a.name.ends_with(".deleter")
+    });
+    let in_class = ctx.current_scope().is_some_and(|scope| scope.is_class);
 
-    let kind = if is_property {
+    let kind = if is_property && in_class {
         SymbolKind::Property
-    } else if ctx.scope_stack.is_empty() {
-        SymbolKind::Function
-    } else {
+    } else if in_class {
         SymbolKind::Method
+    } else {
+        SymbolKind::Function
     };
 
     let location = make_location(
@@ -353,8 +364,13 @@ fn analyze_function(node: &Node, ctx: &mut AnalysisContext) {
 
     let mut builder = SymbolBuilder::new(name.clone(), kind, location)
         .visibility(visibility)
+        .attributes(attributes)
         .qualified_name(ctx.qualified_name(&name));
 
+    if let Some(scope) = ctx.current_scope() {
+        builder = builder.parent(scope.symbol_id);
+    }
+
     if let Some(ti) = type_info {
         builder = builder.type_info(ti);
     }
@@ -381,12 +397,47 @@ fn analyze_function(node: &Node, ctx: &mut AnalysisContext) {
         ctx.scope_stack.push(ScopeInfo {
             symbol_id,
             name: name.clone(),
+            is_class: false,
         });
         analyze_node(&body, ctx);
         ctx.scope_stack.pop();
     }
 }
 
+/// Decorators of a function or class definition, in source order:
+/// `@app.route("/", methods=["GET"])` gives the attribute `app.route` with
+/// its two arguments as written
+fn decorators(node: &Node, ctx: &AnalysisContext) -> Vec<Attribute> {
+    let Some(parent) = node.parent().filter(|p| p.kind() == "decorated_definition") else {
+        return Vec::new();
+    };
+    let mut attributes = Vec::new();
+    for i in 0..parent.named_child_count() {
+        let Some(expression) = parent.named_child(i).filter(|d| d.kind() == "decorator").and_then(|d| d.named_child(0))
+        else {
+            continue;
+        };
+        let attribute = match (expression.kind(), expression.child_by_field_name("function")) {
+            ("call", Some(function)) => {
+                let arguments = expression
+                    .child_by_field_name("arguments")
+                    .map(|args| {
+                        let mut cursor = args.walk();
+                        args.named_children(&mut cursor)
+                            .filter(|arg| arg.kind() != "comment")
+                            .map(|arg| ctx.get_text(&arg))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                Attribute { name: ctx.get_text(&function), arguments }
+            }
+            _ => Attribute { name: ctx.get_text(&expression), arguments: Vec::new() },
+        };
+        attributes.push(attribute);
+    }
+    attributes
+}
+
 fn analyze_class(node: &Node, ctx: &mut AnalysisContext) {
     let name_node = node.child_by_field_name("name");
     let name = name_node
@@ -408,8 +459,13 @@ fn analyze_class(node: &Node, ctx: &mut AnalysisContext) {
 
     let mut builder = SymbolBuilder::new(name.clone(), SymbolKind::Class, location)
         .visibility(visibility)
+        .attributes(decorators(node, ctx))
         .qualified_name(ctx.qualified_name(&name));
 
+    if let Some(scope) = ctx.current_scope() {
+        builder = builder.parent(scope.symbol_id);
+    }
+
     // Module-level classes are exported by default
     if ctx.scope_stack.is_empty() && !name.starts_with('_') {
         builder = builder.exported(true);
@@ -450,6 +506,7 @@ fn analyze_class(node: &Node, ctx: &mut AnalysisContext) {
         ctx.scope_stack.push(ScopeInfo {
             symbol_id,
             name: name.clone(),
+            is_class: true,
         });
 
         // Analyze class body members
@@ -626,6 +683,12 @@ class User:
         assert_eq!(class_sym.kind, SymbolKind::Class);
         assert!(class_sym.exported);
 
+        // Methods are scoped to their class, top-level definitions to nothing
+        let greet = result.symbols.iter().find(|s| s.name == "greet").unwrap();
+        assert_eq!(greet.parent, Some(class_sym.id));
+        assert_eq!(greet.qualified_name, "User.greet");
+        assert_eq!(class_sym.parent, None);
+
         let private_sym = result.symbols.iter().find(|s| s.name == "_private_method").unwrap();
         assert_eq!(private_sym.visibility, Visibility::Protected);
     }
@@ -695,4 +758,55 @@ _private_var = 42
         let private_var = result.symbols.iter().find(|s| s.name == "_private_var").unwrap();
         assert_eq!(private_var.visibility, Visibility::Private);
     }
+
+    #[test]
+    fn test_decorators_and_nested_functions() {
+        let adapter = PythonAdapter::new().unwrap();
+        let source = r#"
+@dataclass(frozen=True)
+class Point:
+    x: int
+
+    @property
+    def norm(self):
+        def square(v):
+            return v * v
+        return square(self.x)
+
+@app.route("/", methods=["GET"])
+def index():
+    pass
+"#;
+        let result = adapter.analyze("file:///test.py", source);
+
+        let find = |name: &str| result.symbols.iter().find(|s| s.name == name).unwrap();
+        let point = find("Point");
+        assert_eq!(point.attributes[0].name, "dataclass");
+        assert_eq!(point.attributes[0].arguments, vec!["frozen=True"]);
+        assert_eq!(find("norm").kind, SymbolKind::Property);
+        assert_eq!(find("square").kind, SymbolKind::Function);
+        let index = find("index");
+        assert_eq!(index.attributes[0].name, "app.route");
+        assert_eq!(index.attributes[0].arguments, vec!["\"/\"", "methods=[\"GET\"]"]);
+    }
+
+    #[test]
+    fn test_resolve_relative_imports() {
+        let vfs = crate::vfs::MemoryFs::new();
+        vfs.insert("/ws/pkg/__init__.py", "VERSION = 1\n");
+        vfs.insert("/ws/pkg/util.py", "def helper():\n    pass\n");
+        vfs.insert("/ws/pkg/sub/main.py", "from .. import util, VERSION\n");
+
+        let adapter = PythonAdapter::new().unwrap();
+        let result = adapter.analyze("file:///ws/pkg/sub/main.py", "from .. import util, VERSION\nfrom os import path\n");
+        let paths: Vec<_> = result.imports.iter().map(|i| i.module_path.as_str()).collect();
+        assert_eq!(paths, vec!["..util", "..VERSION", "os"]);
+        assert_eq!(result.imports[2].items.iter().map(|i| i.name.as_str()).collect::<Vec<_>>(), vec!["path"]);
+
+        let from = Path::new("/ws/pkg/sub/main.py");
+        let resolve = |path: &str| adapter.resolve_import(&vfs, from, path);
+        assert_eq!(resolve("..util"), Some(std::path::PathBuf::from("/ws/pkg/util.py")));
+        assert_eq!(resolve("..VERSION"), Some(std::path::PathBuf::from("/ws/pkg/__init__.py")));
+        assert_eq!(resolve("os"), None);
+    }
 }
@@ -0,0 +1,316 @@
+//! Related files: header/source, test/implementation and component/style
+//! counterparts of a file
+//!
+//! Files are related when they share a base name once test and style
+//! markers are stripped (`test_foo.py`, `foo_test.go`, `Foo.test.tsx`,
+//! `FooTest.java`, `Foo.module.css` all have base `foo`/`Foo`). Candidates
+//! are looked up next to the file, in `tests`/`__tests__` directories
+//! around it, across `src/main` ↔ `src/test` and, for C and C++, across
+//! `include` ↔ `src`.
+//!
+//! [`test_file_path`] also names the conventional test file when there is
+//! none yet.
+
+use crate::vfs::Vfs;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// How a related file relates to the file it was found for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RelatedKind {
+    /// Header of a C or C++ source file
+    Header,
+    /// Source file of a C or C++ header
+    Source,
+    Test,
+    /// Code under test, for a test file
+    Implementation,
+    /// Stylesheet of a component
+    Style,
+    /// Component using a stylesheet
+    Component,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelatedFile {
+    pub path: PathBuf,
+    pub kind: RelatedKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Family {
+    C,
+    Web,
+    Python,
+    Go,
+    Jvm,
+    Rust,
+    CSharp,
+    Swift,
+    Dart,
+    Elixir,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Header,
+    Code,
+    Test,
+    Style,
+}
+
+/// Base name, language family and role of a file name
+fn classify(file_name: &str) -> Option<(String, Family, Role)> {
+    let (stem, ext) = file_name.rsplit_once('.')?;
+    let family = match ext {
+        "h" | "hh" | "hpp" | "hxx" | "c" | "cc" | "cpp" | "cxx" => Family::C,
+        "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" | "vue" | "svelte" | "css" | "scss"
+        | "sass" | "less" => Family::Web,
+        "py" => Family::Python,
+        "go" => Family::Go,
+        "java" | "kt" | "kts" | "scala" => Family::Jvm,
+        "rs" => Family::Rust,
+        "cs" => Family::CSharp,
+        "swift" => Family::Swift,
+        "dart" => Family::Dart,
+        "ex" | "exs" => Family::Elixir,
+        _ => return None,
+    };
+    if stem.is_empty() {
+        return None;
+    }
+    if matches!(ext, "css" | "scss" | "sass" | "less") {
+        let base = stem.strip_suffix(".module").unwrap_or(stem);
+        return Some((base.to_string(), family, Role::Style));
+    }
+    if family == Family::C && ext.starts_with('h') {
+        return Some((stem.to_string(), family, Role::Header));
+    }
+    let test_base = match family {
+        Family::Web => stem.strip_suffix(".test").or_else(|| stem.strip_suffix(".spec")),
+        Family::Python => stem.strip_prefix("test_").or_else(|| stem.strip_suffix("_test")),
+        Family::Jvm | Family::CSharp | Family::Swift => stem.strip_suffix("Tests").or_else(|| stem.strip_suffix("Test")),
+        Family::Go | Family::C | Family::Rust | Family::Dart | Family::Elixir => stem.strip_suffix("_test"),
+    };
+    match test_base {
+        Some(base) if !base.is_empty() => Some((base.to_string(), family, Role::Test)),
+        _ => Some((stem.to_string(), family, Role::Code)),
+    }
+}
+
+/// Kind of a file with `role` relative to one with role `from`, if they are
+/// counterparts
+fn relation(from: Role, role: Role) -> Option<RelatedKind> {
+    match (from, role) {
+        (Role::Code, Role::Header) => Some(RelatedKind::Header),
+        (Role::Header, Role::Code) => Some(RelatedKind::Source),
+        (Role::Code | Role::Header, Role::Test) => Some(RelatedKind::Test),
+        (Role::Test, Role::Code) => Some(RelatedKind::Implementation),
+        (Role::Test, Role::Header) => Some(RelatedKind::Header),
+        (Role::Code, Role::Style) => Some(RelatedKind::Style),
+        (Role::Style, Role::Code) => Some(RelatedKind::Component),
+        (Role::Style, Role::Test) => Some(RelatedKind::Test),
+        _ => None,
+    }
+}
+
+/// Directories that may hold counterparts of a file in `dir`
+fn candidate_dirs(dir: &Path, family: Family) -> Vec<PathBuf> {
+    const TEST_DIRS: [&str; 3] = ["tests", "test", "__tests__"];
+    let mut dirs = vec![dir.to_path_buf()];
+    dirs.extend(TEST_DIRS.iter().map(|name| dir.join(name)));
+    let in_test_dir = dir
+        .file_name()
+        .and_then(|name| name.to_str())
+        .is_some_and(|name| TEST_DIRS.contains(&name));
+    if let (true, Some(parent)) = (in_test_dir, dir.parent()) {
+        dirs.push(parent.to_path_buf());
+    }
+
+    let mut swaps = vec![("main", "test"), ("test", "main")];
+    if family == Family::C {
+        swaps.extend([("include", "src"), ("src", "include")]);
+    }
+    for (from, to) in swaps {
+        if let Some(mirrored) = swap_component(dir, from, to) {
+            dirs.push(mirrored);
+        }
+    }
+    dirs.dedup();
+    dirs
+}
+
+/// `dir` with its last `from` component replaced by `to`
+fn swap_component(dir: &Path, from: &str, to: &str) -> Option<PathBuf> {
+    let components: Vec<_> = dir.components().collect();
+    let index = components.iter().rposition(|c| c.as_os_str() == from)?;
+    let mut swapped = PathBuf::new();
+    for (i, component) in components.iter().enumerate() {
+        if i == index {
+            swapped.push(to);
+        } else {
+            swapped.push(component);
+        }
+    }
+    Some(swapped)
+}
+
+/// Counterparts of `path` that exist in `vfs`, ordered by kind and path
+pub fn related_files(vfs: &dyn Vfs, path: &Path) -> Vec<RelatedFile> {
+    let Some((base, family, role)) = path.file_name().and_then(|name| name.to_str()).and_then(classify) else {
+        return Vec::new();
+    };
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+
+    let mut related = Vec::new();
+    for dir in candidate_dirs(dir, family) {
+        for candidate in vfs.read_dir(&dir).unwrap_or_default() {
+            if candidate == path || related.iter().any(|r: &RelatedFile| r.path == candidate) || !vfs.is_file(&candidate) {
+                continue;
+            }
+            let Some((other_base, other_family, other_role)) =
+                candidate.file_name().and_then(|name| name.to_str()).and_then(classify)
+            else {
+                continue;
+            };
+            if other_base != base || other_family != family {
+                continue;
+            }
+            if let Some(kind) = relation(role, other_role) {
+                related.push(RelatedFile { path: candidate, kind });
+            }
+        }
+    }
+    related.sort_by(|a, b| a.kind.cmp(&b.kind).then_with(|| a.path.cmp(&b.path)));
+    related
+}
+
+/// Test file for the code in `path`: an existing one when there is one,
+/// otherwise where the language's conventions put it. Rust tests live in
+/// the file itself. `None` for test files and files without a convention.
+pub fn test_file_path(vfs: &dyn Vfs, path: &Path) -> Option<PathBuf> {
+    let (base, family, role) = path.file_name().and_then(|name| name.to_str()).and_then(classify)?;
+    if !matches!(role, Role::Code | Role::Header) {
+        return None;
+    }
+    if let Some(existing) = related_files(vfs, path).into_iter().find(|r| r.kind == RelatedKind::Test) {
+        return Some(existing.path);
+    }
+
+    let dir = path.parent()?;
+    let ext = path.extension()?.to_str()?;
+    let name = match family {
+        Family::Rust => return Some(path.to_path_buf()),
+        Family::Python => format!("test_{}.py", base),
+        Family::Go => format!("{}_test.go", base),
+        Family::Web => format!("{}.test.{}", base, ext),
+        Family::C => format!("{}_test.{}", base, if ext == "h" || ext == "c" { "c" } else { "cpp" }),
+        Family::Jvm => {
+            let dir = swap_component(dir, "main", "test").unwrap_or_else(|| dir.to_path_buf());
+            return Some(dir.join(format!("{}Test.{}", base, ext)));
+        }
+        Family::CSharp | Family::Swift => format!("{}Tests.{}", base, ext),
+        Family::Dart | Family::Elixir => {
+            let ext = if family == Family::Elixir { "exs" } else { ext };
+            let dir = swap_component(dir, "lib", "test").unwrap_or_else(|| dir.to_path_buf());
+            return Some(dir.join(format!("{}_test.{}", base, ext)));
+        }
+    };
+    Some(dir.join(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+
+    fn related(vfs: &MemoryFs, path: &str) -> Vec<(String, RelatedKind)> {
+        related_files(vfs, Path::new(path))
+            .into_iter()
+            .map(|r| (r.path.to_string_lossy().into_owned(), r.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_header_source_and_include_dirs() {
+        let vfs = MemoryFs::new();
+        for file in ["/p/src/foo.cpp", "/p/include/foo.h", "/p/src/foo_test.cpp", "/p/src/bar.cpp", "/p/src/foo.py"] {
+            vfs.insert(file, "");
+        }
+        assert_eq!(
+            related(&vfs, "/p/src/foo.cpp"),
+            vec![
+                ("/p/include/foo.h".to_string(), RelatedKind::Header),
+                ("/p/src/foo_test.cpp".to_string(), RelatedKind::Test),
+            ]
+        );
+        assert_eq!(
+            related(&vfs, "/p/include/foo.h"),
+            vec![
+                ("/p/src/foo.cpp".to_string(), RelatedKind::Source),
+                ("/p/src/foo_test.cpp".to_string(), RelatedKind::Test),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tests_and_implementations() {
+        let vfs = MemoryFs::new();
+        for file in [
+            "/p/pkg/foo.py",
+            "/p/pkg/tests/test_foo.py",
+            "/p/src/main/java/a/Foo.java",
+            "/p/src/test/java/a/FooTest.java",
+        ] {
+            vfs.insert(file, "");
+        }
+        assert_eq!(related(&vfs, "/p/pkg/foo.py"), vec![("/p/pkg/tests/test_foo.py".to_string(), RelatedKind::Test)]);
+        assert_eq!(
+            related(&vfs, "/p/pkg/tests/test_foo.py"),
+            vec![("/p/pkg/foo.py".to_string(), RelatedKind::Implementation)]
+        );
+        assert_eq!(
+            related(&vfs, "/p/src/test/java/a/FooTest.java"),
+            vec![("/p/src/main/java/a/Foo.java".to_string(), RelatedKind::Implementation)]
+        );
+    }
+
+    #[test]
+    fn test_component_test_and_style() {
+        let vfs = MemoryFs::new();
+        for file in ["/p/ui/Foo.tsx", "/p/ui/Foo.test.tsx", "/p/ui/Foo.module.css", "/p/ui/FooBar.css"] {
+            vfs.insert(file, "");
+        }
+        assert_eq!(
+            related(&vfs, "/p/ui/Foo.tsx"),
+            vec![
+                ("/p/ui/Foo.test.tsx".to_string(), RelatedKind::Test),
+                ("/p/ui/Foo.module.css".to_string(), RelatedKind::Style),
+            ]
+        );
+        assert_eq!(
+            related(&vfs, "/p/ui/Foo.module.css"),
+            vec![
+                ("/p/ui/Foo.test.tsx".to_string(), RelatedKind::Test),
+                ("/p/ui/Foo.tsx".to_string(), RelatedKind::Component),
+            ]
+        );
+        assert!(related(&vfs, "/p/ui/README").is_empty());
+    }
+
+    #[test]
+    fn test_test_file_path() {
+        let vfs = MemoryFs::new();
+        vfs.insert("/p/pkg/tests/test_foo.py", "");
+        let path = |p: &str| test_file_path(&vfs, Path::new(p)).map(|t| t.to_string_lossy().into_owned());
+        assert_eq!(path("/p/pkg/foo.py").as_deref(), Some("/p/pkg/tests/test_foo.py"));
+        assert_eq!(path("/p/pkg/bar.py").as_deref(), Some("/p/pkg/test_bar.py"));
+        assert_eq!(path("/p/src/main/java/a/Foo.java").as_deref(), Some("/p/src/test/java/a/FooTest.java"));
+        assert_eq!(path("/p/ui/Foo.tsx").as_deref(), Some("/p/ui/Foo.test.tsx"));
+        assert_eq!(path("/p/src/lib.rs").as_deref(), Some("/p/src/lib.rs"));
+        assert_eq!(path("/p/pkg/tests/test_foo.py"), None);
+    }
+}
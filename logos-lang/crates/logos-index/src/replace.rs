@@ -0,0 +1,264 @@
+//! Plain-text find and replace across a workspace
+//!
+//! The query is a literal string or a regular expression; in regex mode
+//! the replacement may refer to capture groups (`$1`, `${name}`, `$$` for
+//! a dollar sign), in literal mode it is inserted as written. Files are
+//! found by walking the workspace roots, skipping what project indexing
+//! skips and what the `.gitignore` and `.logosignore` files list. Binary
+//! files (with a NUL byte) and files that aren't UTF-8 are left alone.
+//!
+//! Nothing is written: the result lists the edits of each file with the
+//! text they replace, for the client to preview and apply.
+
+use crate::glob::{IgnoreRules, PathGlob};
+use crate::indexer::is_skipped_name;
+use crate::vfs::Vfs;
+use logos_core::{Document, Range};
+use regex::{Regex, RegexBuilder};
+use std::path::{Path, PathBuf};
+
+/// Matches reported before the search stops
+pub const DEFAULT_MAX_MATCHES: usize = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct ReplaceOptions {
+    /// Treat the query as a regular expression
+    pub regex: bool,
+    pub case_sensitive: bool,
+    /// Only match at word boundaries
+    pub whole_word: bool,
+    /// Globs a file must match one of; every file when empty
+    pub include: Vec<String>,
+    /// Globs of files to leave out
+    pub exclude: Vec<String>,
+    pub max_matches: usize,
+}
+
+impl Default for ReplaceOptions {
+    fn default() -> Self {
+        Self {
+            regex: false,
+            case_sensitive: true,
+            whole_word: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            max_matches: DEFAULT_MAX_MATCHES,
+        }
+    }
+}
+
+/// One match and what replaces it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReplaceEdit {
+    pub range: Range,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+/// Edits of one file, in document order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileReplacement {
+    pub path: PathBuf,
+    pub edits: Vec<ReplaceEdit>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct WorkspaceReplacement {
+    /// Files with at least one match, sorted by path
+    pub files: Vec<FileReplacement>,
+    /// Whether the search stopped at `max_matches`
+    pub truncated: bool,
+}
+
+impl WorkspaceReplacement {
+    pub fn match_count(&self) -> usize {
+        self.files.iter().map(|file| file.edits.len()).sum()
+    }
+}
+
+/// The pattern `query` stands for under `options`
+pub fn compile_query(query: &str, options: &ReplaceOptions) -> Result<Regex, String> {
+    if query.is_empty() {
+        return Err("Empty query".to_string());
+    }
+    let pattern = if options.regex { query.to_string() } else { regex::escape(query) };
+    let pattern = if options.whole_word { format!(r"\b(?:{})\b", pattern) } else { pattern };
+    RegexBuilder::new(&pattern)
+        .case_insensitive(!options.case_sensitive)
+        .multi_line(true)
+        .build()
+        .map_err(|e| format!("Invalid pattern: {}", e))
+}
+
+/// Edits replacing every match of `pattern` in `text`. In regex mode
+/// `replacement` is expanded against the captures of each match.
+pub fn replace_in_text(pattern: &Regex, replacement: &str, expand: bool, text: &str, limit: usize) -> Vec<ReplaceEdit> {
+    let document = Document::new(String::new(), String::new(), text.to_string());
+    pattern
+        .captures_iter(text)
+        .take(limit)
+        .filter_map(|captures| {
+            let found = captures.get(0)?;
+            let mut new_text = String::new();
+            if expand {
+                captures.expand(replacement, &mut new_text);
+            } else {
+                new_text.push_str(replacement);
+            }
+            Some(ReplaceEdit {
+                range: Range::new(document.position_at(found.start()), document.position_at(found.end())),
+                old_text: found.as_str().to_string(),
+                new_text,
+            })
+        })
+        .collect()
+}
+
+/// Edits replacing `query` with `replacement` in the files under `roots`
+pub fn workspace_replace(
+    vfs: &dyn Vfs,
+    roots: &[PathBuf],
+    query: &str,
+    replacement: &str,
+    options: &ReplaceOptions,
+) -> Result<WorkspaceReplacement, String> {
+    let pattern = compile_query(query, options)?;
+    let include: Vec<PathGlob> = options.include.iter().filter_map(|glob| PathGlob::new(glob)).collect();
+    let exclude: Vec<PathGlob> = options.exclude.iter().filter_map(|glob| PathGlob::new(glob)).collect();
+
+    let mut files = Vec::new();
+    for root in roots {
+        walk(vfs, root, root, &mut IgnoreRules::default(), &mut files);
+    }
+    files.sort();
+    files.dedup();
+
+    let mut result = WorkspaceReplacement::default();
+    let mut remaining = options.max_matches;
+    for path in files {
+        let display = path.to_string_lossy().replace('\\', "/");
+        if (!include.is_empty() && !include.iter().any(|glob| glob.is_match(&display)))
+            || exclude.iter().any(|glob| glob.is_match(&display))
+        {
+            continue;
+        }
+        let Ok(text) = vfs.read_to_string(&path) else {
+            continue;
+        };
+        if text.contains('\0') {
+            continue;
+        }
+        if remaining == 0 {
+            // Only stop once another file would have contributed
+            if pattern.is_match(&text) {
+                result.truncated = true;
+                break;
+            }
+            continue;
+        }
+        let edits = replace_in_text(&pattern, replacement, options.regex, &text, remaining + 1);
+        if edits.len() > remaining {
+            result.truncated = true;
+        }
+        let edits: Vec<ReplaceEdit> = edits.into_iter().take(remaining).collect();
+        remaining -= edits.len();
+        if !edits.is_empty() {
+            result.files.push(FileReplacement { path, edits });
+        }
+    }
+    Ok(result)
+}
+
+/// Files under `dir` not skipped by name or by `rules`, to which the
+/// ignore files of each directory are added
+fn walk(vfs: &dyn Vfs, root: &Path, dir: &Path, rules: &mut IgnoreRules, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = vfs.read_dir(dir) else {
+        return;
+    };
+    rules.read_dir(vfs, root, dir);
+    for path in entries {
+        if path.file_name().and_then(|n| n.to_str()).is_some_and(is_skipped_name) {
+            continue;
+        }
+        let is_dir = vfs.is_dir(&path);
+        let relative = path.strip_prefix(root).unwrap_or(&path).to_string_lossy().replace('\\', "/");
+        if rules.is_ignored(&relative, is_dir) {
+            continue;
+        }
+        if is_dir {
+            walk(vfs, root, &path, rules, files);
+        } else if vfs.is_file(&path) {
+            files.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+    use logos_core::Position;
+
+    fn workspace() -> MemoryFs {
+        let vfs = MemoryFs::new();
+        vfs.insert("/ws/.gitignore", "*.log\ngenerated/\n");
+        vfs.insert("/ws/src/main.py", "old_name = 1\nprint(old_name)\n");
+        vfs.insert("/ws/src/util.py", "def helper(): return 'old_name'\n");
+        vfs.insert("/ws/run.log", "old_name\n");
+        vfs.insert("/ws/generated/out.py", "old_name = 2\n");
+        vfs.insert("/ws/node_modules/dep/index.js", "old_name\n");
+        vfs
+    }
+
+    #[test]
+    fn test_literal_replace_respects_ignore_rules() {
+        let vfs = workspace();
+        let result =
+            workspace_replace(&vfs, &[PathBuf::from("/ws")], "old_name", "new_name", &ReplaceOptions::default()).unwrap();
+
+        let paths: Vec<_> = result.files.iter().map(|f| f.path.to_string_lossy().to_string()).collect();
+        assert_eq!(paths, vec!["/ws/src/main.py", "/ws/src/util.py"]);
+        assert_eq!(result.match_count(), 3);
+        assert!(!result.truncated);
+        let edit = &result.files[0].edits[1];
+        assert_eq!(edit.range, Range::new(Position::new(1, 6), Position::new(1, 14)));
+        assert_eq!((edit.old_text.as_str(), edit.new_text.as_str()), ("old_name", "new_name"));
+    }
+
+    #[test]
+    fn test_regex_captures_and_options() {
+        let options = ReplaceOptions { regex: true, ..ReplaceOptions::default() };
+        let pattern = compile_query(r"(\w+)\.get\((\w+)\)", &options).unwrap();
+        let edits = replace_in_text(&pattern, "$1[$2]", true, "x = café.get(k)\n", 10);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].new_text, "café[k]");
+        // Columns count UTF-16 units
+        assert_eq!(edits[0].range.end, Position::new(0, 15));
+
+        // Literal mode leaves `$` alone
+        let pattern = compile_query("a.b", &ReplaceOptions::default()).unwrap();
+        let edits = replace_in_text(&pattern, "$1", false, "a.b axb", 10);
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].new_text, "$1");
+
+        let options = ReplaceOptions { case_sensitive: false, whole_word: true, ..ReplaceOptions::default() };
+        let pattern = compile_query("id", &options).unwrap();
+        assert_eq!(replace_in_text(&pattern, "key", false, "ID idle id", 10).len(), 2);
+        assert!(compile_query("(", &ReplaceOptions { regex: true, ..ReplaceOptions::default() }).is_err());
+        assert!(compile_query("", &ReplaceOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_globs_and_limit() {
+        let vfs = workspace();
+        let roots = [PathBuf::from("/ws")];
+        let options = ReplaceOptions { exclude: vec!["util.py".to_string()], ..ReplaceOptions::default() };
+        let result = workspace_replace(&vfs, &roots, "old_name", "x", &options).unwrap();
+        assert_eq!(result.files.len(), 1);
+
+        let options = ReplaceOptions { max_matches: 2, ..ReplaceOptions::default() };
+        let result = workspace_replace(&vfs, &roots, "old_name", "x", &options).unwrap();
+        assert_eq!(result.match_count(), 2);
+        assert!(result.truncated);
+    }
+}
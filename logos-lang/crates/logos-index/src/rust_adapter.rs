@@ -1,35 +1,41 @@
 //! Rust Language Adapter
 //!
 //! Pragmatic indexer for Rust:
-//! - Symbols: fn/struct/enum/trait/type/mod/const/static
-//! - Imports: use declarations (best-effort string extraction)
-//! - Exports: inferred from `pub` visibility (best-effort)
+//! - Symbols: fn/struct/enum/trait/type/mod/const/static, enum variants,
+//!   struct fields, trait methods, and the methods of `impl` blocks, which
+//!   become children of their type when the file declares it
+//! - Visibility: `pub` is public, `pub(crate)`, `pub(super)` and
+//!   `pub(in path)` internal, anything else private
+//! - Imports: one per path of a `use` tree, keeping the imported name in the
+//!   path since `use a::b` may name module `b` or item `b` of `a`; `mod foo;`
+//!   declarations count as imports of the module file
+//! - Type relations: `impl Trait for Type` and supertraits
 //! - Calls: call_expression (best-effort)
 
-use crate::adapter::{AnalysisResult, CallInfo, ImportInfo, ImportItem, LanguageAdapter, SymbolBuilder, make_location};
+use crate::adapter::{
+    AnalysisResult, CallInfo, ImportInfo, ImportItem, LanguageAdapter, SymbolBuilder, TypeRelation, make_location,
+};
 use crate::symbol_table::{SymbolId, Visibility};
+use crate::vfs::Vfs;
 use logos_core::{Position, Range, SymbolKind};
-use std::path::Path;
-use tree_sitter::{Node, Parser, Tree};
+use std::path::{Path, PathBuf};
+use logos_parser::{pool, LanguageId};
+use tree_sitter::{Node, Tree};
 
-pub struct RustAdapter {
-    parser: std::sync::Mutex<Parser>,
-}
+pub struct RustAdapter;
 
 impl RustAdapter {
     pub fn new() -> Result<Self, String> {
-        let mut parser = Parser::new();
-        parser
-            .set_language(&tree_sitter_rust::LANGUAGE.into())
+        // Fail early when the grammar can't be loaded
+        pool::shared()
+            .checkout(LanguageId::Rust)
             .map_err(|e| format!("Failed to set Rust language: {}", e))?;
-        Ok(Self {
-            parser: std::sync::Mutex::new(parser),
-        })
+        Ok(Self)
     }
 
     fn parse(&self, source: &str) -> Option<Tree> {
-        let mut parser = self.parser.lock().ok()?;
-        parser.parse(source, None)
+        let mut parser = pool::shared().checkout(LanguageId::Rust).ok()?;
+        parser.parse(source, None).ok()
     }
 }
 
@@ -53,16 +59,90 @@ impl LanguageAdapter for RustAdapter {
             source,
             result: AnalysisResult::default(),
             scope_stack: Vec::new(),
+            modules: Vec::new(),
+            methods: Vec::new(),
         };
 
         analyze_node(&tree.root_node(), &mut ctx);
-        ctx.result
+        let methods = std::mem::take(&mut ctx.methods);
+        link_methods(&mut ctx.result, &methods);
+        ctx.result.with_utf16_columns(source)
     }
 
-    fn resolve_import(&self, from_file: &Path, import_path: &str) -> Option<std::path::PathBuf> {
-        // Rust `use` paths are module paths, not file paths. Keep default behavior off.
-        let _ = (from_file, import_path);
-        None
+    /// Resolve a `use` path to the file of the deepest module it names.
+    /// `crate::` starts at the crate root (the nearest directory above the
+    /// file with a `lib.rs` or `main.rs`), `super::` at the parent module,
+    /// and other paths at the submodules of the importing file. Module `a`
+    /// of directory `dir` is `dir/a.rs` or `dir/a/mod.rs`. Paths into
+    /// other crates don't resolve.
+    fn resolve_import(&self, vfs: &dyn Vfs, from_file: &Path, import_path: &str) -> Option<PathBuf> {
+        let root = crate_root(vfs, from_file)?;
+        let mut dir = module_dir(from_file);
+        // Whether the path left the importing module
+        let mut moved = false;
+        for segment in import_path.split("::").map(str::trim) {
+            match segment {
+                "crate" => dir = root.clone(),
+                "self" => continue,
+                "super" => dir = dir.parent()?.to_path_buf(),
+                name => {
+                    let child = dir.join(name);
+                    if module_file(vfs, &root, &child).is_none() {
+                        break;
+                    }
+                    dir = child;
+                }
+            }
+            moved = true;
+        }
+        if !moved {
+            return None;
+        }
+        module_file(vfs, &root, &dir)
+    }
+}
+
+/// Directory of the crate sources `file` belongs to
+fn crate_root(vfs: &dyn Vfs, file: &Path) -> Option<PathBuf> {
+    file.ancestors()
+        .skip(1)
+        .find(|dir| vfs.is_file(&dir.join("lib.rs")) || vfs.is_file(&dir.join("main.rs")))
+        .map(Path::to_path_buf)
+}
+
+/// Directory holding the submodules of the module `file` defines: its own
+/// directory for `mod.rs` and crate roots, `foo/` beside `foo.rs` otherwise
+fn module_dir(file: &Path) -> PathBuf {
+    match file.file_name().and_then(|name| name.to_str()) {
+        Some("mod.rs" | "lib.rs" | "main.rs") => file.parent().map(Path::to_path_buf).unwrap_or_default(),
+        _ => file.with_extension(""),
+    }
+}
+
+/// Source file of the module whose submodules live in `dir`
+fn module_file(vfs: &dyn Vfs, root: &Path, dir: &Path) -> Option<PathBuf> {
+    let candidates = if dir == root {
+        [dir.join("lib.rs"), dir.join("main.rs")]
+    } else {
+        [dir.with_extension("rs"), dir.join("mod.rs")]
+    };
+    candidates.into_iter().find(|path| vfs.is_file(path))
+}
+
+/// Make each method of an `impl` block a child of its type when the file
+/// declares the type
+fn link_methods(result: &mut AnalysisResult, methods: &[(SymbolId, String)]) {
+    for (method_id, type_name) in methods {
+        let Some(type_index) = result.symbols.iter().position(|s| {
+            &s.name == type_name && matches!(s.kind, SymbolKind::Struct | SymbolKind::Enum | SymbolKind::Class)
+        }) else {
+            continue;
+        };
+        let type_id = result.symbols[type_index].id;
+        result.symbols[type_index].children.push(*method_id);
+        if let Some(method) = result.symbols.iter_mut().find(|s| s.id == *method_id) {
+            method.parent = Some(type_id);
+        }
     }
 }
 
@@ -71,6 +151,10 @@ struct AnalysisContext<'a> {
     source: &'a str,
     result: AnalysisResult,
     scope_stack: Vec<ScopeInfo>,
+    /// Names of the inline modules around the current node
+    modules: Vec<String>,
+    /// Methods of `impl` blocks with the name of their type
+    methods: Vec<(SymbolId, String)>,
 }
 
 struct ScopeInfo {
@@ -97,26 +181,41 @@ impl<'a> AnalysisContext<'a> {
     }
 }
 
-fn has_pub_modifier(node: &Node, ctx: &AnalysisContext) -> bool {
-    // best-effort: scan immediate children for "pub"
-    for i in 0..node.child_count() {
-        if let Some(ch) = node.child(i) {
-            if ctx.get_text(&ch) == "pub" {
-                return true;
-            }
-        }
+fn visibility_of(node: &Node, ctx: &AnalysisContext) -> Visibility {
+    let mut cursor = node.walk();
+    let modifier = node.children(&mut cursor).find(|child| child.kind() == "visibility_modifier");
+    let Some(text) = modifier.map(|m| ctx.get_text(&m).split_whitespace().collect::<String>()) else {
+        return Visibility::Private;
+    };
+    match text.as_str() {
+        "pub" => Visibility::Public,
+        "pub(self)" => Visibility::Private,
+        _ => Visibility::Internal,
     }
-    false
+}
+
+/// Name of a type as written in an `impl` header or a bound: `Vec` for
+/// `Vec<T>`, `Foo` for `crate::model::Foo` or `&'a mut Foo`
+fn base_type_name(text: &str) -> String {
+    let text = text.trim_start_matches(['&', ' ']);
+    let text = match text.strip_prefix('\'') {
+        Some(rest) => rest.split_once(' ').map_or("", |(_, ty)| ty),
+        None => text,
+    };
+    let text = text.trim_start_matches("mut ").trim_start_matches("dyn ").trim();
+    let path = text.split('<').next().unwrap_or(text);
+    path.rsplit("::").next().unwrap_or(path).trim().to_string()
 }
 
 fn analyze_node(node: &Node, ctx: &mut AnalysisContext) {
     match node.kind() {
         "use_declaration" => analyze_use(node, ctx),
 
-        "function_item" => analyze_fn(node, ctx),
+        "function_item" | "function_signature_item" => analyze_fn(node, ctx),
         "struct_item" => analyze_struct(node, ctx),
         "enum_item" => analyze_enum(node, ctx),
         "trait_item" => analyze_trait(node, ctx),
+        "impl_item" => analyze_impl(node, ctx),
         "type_item" => analyze_type_alias(node, ctx),
         "mod_item" => analyze_mod(node, ctx),
         "const_item" => analyze_const(node, ctx),
@@ -124,50 +223,102 @@ fn analyze_node(node: &Node, ctx: &mut AnalysisContext) {
 
         "call_expression" => analyze_call(node, ctx),
 
-        _ => {
+        _ => analyze_children(node, ctx),
+    }
+}
+
+fn analyze_children(node: &Node, ctx: &mut AnalysisContext) {
+    for i in 0..node.named_child_count() {
+        if let Some(child) = node.named_child(i) {
+            analyze_node(&child, ctx);
+        }
+    }
+}
+
+/// Analyze the children of `node` inside the scope of symbol `id`
+fn analyze_in_scope(node: &Node, id: SymbolId, name: String, ctx: &mut AnalysisContext) {
+    ctx.scope_stack.push(ScopeInfo { symbol_id: id, name });
+    analyze_children(node, ctx);
+    ctx.scope_stack.pop();
+}
+
+fn analyze_use(node: &Node, ctx: &mut AnalysisContext) {
+    // `use a::{b, c::d as e, f::*};` gives `a::b`, `a::c::d` as `e` and `a::f::*`
+    let Some(argument) = node.child_by_field_name("argument") else {
+        return;
+    };
+    let mut paths = Vec::new();
+    use_paths(&argument, "", ctx, &mut paths);
+
+    for (path, alias) in paths {
+        // `use std::io::{self, Read}` imports `std::io` itself
+        let path = path.strip_suffix("::self").unwrap_or(&path);
+        let (module_path, items) = match path.strip_suffix('*') {
+            Some(module) => (module.trim_end_matches("::").to_string(), Vec::new()),
+            None => {
+                let name = path.rsplit("::").next().unwrap_or(path).to_string();
+                (path.to_string(), vec![ImportItem { name, alias, is_type: false }])
+            }
+        };
+        if module_path.is_empty() {
+            continue;
+        }
+        ctx.result.imports.push(ImportInfo {
+            module_path,
+            items,
+            is_type_only: false,
+            location: node_to_range(node),
+        });
+    }
+}
+
+/// Paths a `use` tree brings in, with their alias
+fn use_paths(node: &Node, prefix: &str, ctx: &AnalysisContext, paths: &mut Vec<(String, Option<String>)>) {
+    let join = |path: &Node| {
+        let text: String = ctx.get_text(path).split_whitespace().collect();
+        if prefix.is_empty() { text } else { format!("{}::{}", prefix, text) }
+    };
+    match node.kind() {
+        "use_as_clause" => {
+            if let Some(path) = node.child_by_field_name("path") {
+                paths.push((join(&path), node.child_by_field_name("alias").map(|alias| ctx.get_text(&alias))));
+            }
+        }
+        "scoped_use_list" => {
+            let prefix = node.child_by_field_name("path").map_or_else(|| prefix.to_string(), |path| join(&path));
+            if let Some(list) = node.child_by_field_name("list") {
+                use_paths(&list, &prefix, ctx, paths);
+            }
+        }
+        "use_list" => {
             for i in 0..node.named_child_count() {
                 if let Some(child) = node.named_child(i) {
-                    analyze_node(&child, ctx);
+                    use_paths(&child, prefix, ctx, paths);
                 }
             }
         }
+        kind if kind.ends_with("comment") => {}
+        _ => paths.push((join(node), None)),
     }
 }
 
-fn analyze_use(node: &Node, ctx: &mut AnalysisContext) {
-    // `use foo::bar as baz;`
-    // In Rust grammar, the tree can be nested. We'll grab the whole text as module_path.
-    let text = ctx.get_text(node);
-    let module_path = text
-        .trim()
-        .trim_start_matches("use")
-        .trim_end_matches(';')
-        .trim()
-        .to_string();
-    if module_path.is_empty() {
-        return;
-    }
-    ctx.result.imports.push(ImportInfo {
-        module_path: module_path.clone(),
-        items: vec![ImportItem {
-            name: module_path,
-            alias: None,
-            is_type: false,
-        }],
-        is_type_only: false,
-        location: node_to_range(node),
-    });
-}
-
-fn push_symbol(ctx: &mut AnalysisContext, name: String, kind: SymbolKind, node: &Node, name_node: &Node, exported: bool) -> SymbolId {
-    let visibility = if exported { Visibility::Public } else { Visibility::Private };
+fn push_symbol(
+    ctx: &mut AnalysisContext,
+    name: String,
+    kind: SymbolKind,
+    node: &Node,
+    name_node: &Node,
+    visibility: Visibility,
+) -> SymbolId {
     let location = make_location(&ctx.uri, node_to_range(node), node_to_range(name_node));
-    let sym = SymbolBuilder::new(name.clone(), kind, location)
-        .parent(ctx.current_scope().map(|s| s.symbol_id).unwrap_or(SymbolId(0)))
-        .exported(exported)
+    let mut builder = SymbolBuilder::new(name.clone(), kind, location)
+        .exported(visibility != Visibility::Private)
         .visibility(visibility)
-        .qualified_name(ctx.qualified_name(&name))
-        .build();
+        .qualified_name(ctx.qualified_name(&name));
+    if let Some(scope) = ctx.current_scope() {
+        builder = builder.parent(scope.symbol_id);
+    }
+    let sym = builder.build();
     let id = sym.id;
     ctx.result.symbols.push(sym);
     id
@@ -179,8 +330,20 @@ fn analyze_fn(node: &Node, ctx: &mut AnalysisContext) {
         None => return,
     };
     let name = ctx.get_text(&name_node);
-    let exported = has_pub_modifier(node, ctx);
-    let id = push_symbol(ctx, name.clone(), SymbolKind::Function, node, &name_node, exported);
+    // Functions declared in a trait or an impl block are methods
+    let owner = node.parent().and_then(|list| list.parent()).map(|owner| owner.kind());
+    let kind = match owner {
+        Some("trait_item" | "impl_item") => SymbolKind::Method,
+        _ => SymbolKind::Function,
+    };
+    let visibility = match owner {
+        // Trait methods are as visible as their trait
+        Some("trait_item") => ctx.current_scope().and_then(|scope| {
+            ctx.result.symbols.iter().find(|s| s.id == scope.symbol_id).map(|s| s.visibility)
+        }).unwrap_or(Visibility::Private),
+        _ => visibility_of(node, ctx),
+    };
+    let id = push_symbol(ctx, name.clone(), kind, node, &name_node, visibility);
     if let Some(body) = node.child_by_field_name("body") {
         ctx.scope_stack.push(ScopeInfo { symbol_id: id, name });
         analyze_node(&body, ctx);
@@ -188,14 +351,78 @@ fn analyze_fn(node: &Node, ctx: &mut AnalysisContext) {
     }
 }
 
+fn analyze_impl(node: &Node, ctx: &mut AnalysisContext) {
+    let Some(type_node) = node.child_by_field_name("type") else {
+        return;
+    };
+    let type_name = base_type_name(&ctx.get_text(&type_node));
+    let trait_node = node.child_by_field_name("trait");
+    if let Some(trait_node) = trait_node {
+        ctx.result.type_relations.push(TypeRelation {
+            child_name: type_name.clone(),
+            parent_name: base_type_name(&ctx.get_text(&trait_node)),
+            is_implements: true,
+            location: node_to_range(node),
+        });
+    }
+    let Some(body) = node.child_by_field_name("body") else {
+        return;
+    };
+
+    for i in 0..body.named_child_count() {
+        let Some(item) = body.named_child(i) else {
+            continue;
+        };
+        if item.kind() != "function_item" {
+            analyze_node(&item, ctx);
+            continue;
+        }
+        let Some(name_node) = item.child_by_field_name("name") else {
+            continue;
+        };
+        let name = ctx.get_text(&name_node);
+        // Methods of a trait impl are as visible as the trait
+        let visibility = if trait_node.is_some() { Visibility::Public } else { visibility_of(&item, ctx) };
+        let qualified = format!("{}::{}", type_name, name);
+        let location = make_location(&ctx.uri, node_to_range(&item), node_to_range(&name_node));
+        let method = SymbolBuilder::new(name, SymbolKind::Method, location)
+            .exported(visibility != Visibility::Private)
+            .visibility(visibility)
+            .qualified_name(ctx.qualified_name(&qualified))
+            .build();
+        let id = method.id;
+        ctx.result.symbols.push(method);
+        ctx.methods.push((id, type_name.clone()));
+        if let Some(body) = item.child_by_field_name("body") {
+            ctx.scope_stack.push(ScopeInfo { symbol_id: id, name: qualified });
+            analyze_node(&body, ctx);
+            ctx.scope_stack.pop();
+        }
+    }
+}
+
 fn analyze_struct(node: &Node, ctx: &mut AnalysisContext) {
     let name_node = match node.child_by_field_name("name") {
         Some(n) => n,
         None => return,
     };
     let name = ctx.get_text(&name_node);
-    let exported = has_pub_modifier(node, ctx);
-    let _ = push_symbol(ctx, name, SymbolKind::Struct, node, &name_node, exported);
+    let visibility = visibility_of(node, ctx);
+    let id = push_symbol(ctx, name.clone(), SymbolKind::Struct, node, &name_node, visibility);
+    if let Some(body) = node.child_by_field_name("body").filter(|body| body.kind() == "field_declaration_list") {
+        ctx.scope_stack.push(ScopeInfo { symbol_id: id, name });
+        for i in 0..body.named_child_count() {
+            let Some(field) = body.named_child(i).filter(|field| field.kind() == "field_declaration") else {
+                continue;
+            };
+            if let Some(field_name) = field.child_by_field_name("name") {
+                let name = ctx.get_text(&field_name);
+                let visibility = visibility_of(&field, ctx);
+                push_symbol(ctx, name, SymbolKind::Field, &field, &field_name, visibility);
+            }
+        }
+        ctx.scope_stack.pop();
+    }
 }
 
 fn analyze_enum(node: &Node, ctx: &mut AnalysisContext) {
@@ -204,8 +431,22 @@ fn analyze_enum(node: &Node, ctx: &mut AnalysisContext) {
         None => return,
     };
     let name = ctx.get_text(&name_node);
-    let exported = has_pub_modifier(node, ctx);
-    let _ = push_symbol(ctx, name, SymbolKind::Enum, node, &name_node, exported);
+    let visibility = visibility_of(node, ctx);
+    let id = push_symbol(ctx, name.clone(), SymbolKind::Enum, node, &name_node, visibility);
+    if let Some(body) = node.child_by_field_name("body") {
+        ctx.scope_stack.push(ScopeInfo { symbol_id: id, name });
+        for i in 0..body.named_child_count() {
+            let Some(variant) = body.named_child(i).filter(|variant| variant.kind() == "enum_variant") else {
+                continue;
+            };
+            if let Some(variant_name) = variant.child_by_field_name("name") {
+                // Variants are as visible as their enum
+                let name = ctx.get_text(&variant_name);
+                push_symbol(ctx, name, SymbolKind::EnumMember, &variant, &variant_name, visibility);
+            }
+        }
+        ctx.scope_stack.pop();
+    }
 }
 
 fn analyze_trait(node: &Node, ctx: &mut AnalysisContext) {
@@ -214,8 +455,29 @@ fn analyze_trait(node: &Node, ctx: &mut AnalysisContext) {
         None => return,
     };
     let name = ctx.get_text(&name_node);
-    let exported = has_pub_modifier(node, ctx);
-    let _ = push_symbol(ctx, name, SymbolKind::Interface, node, &name_node, exported);
+    let visibility = visibility_of(node, ctx);
+    let id = push_symbol(ctx, name.clone(), SymbolKind::Interface, node, &name_node, visibility);
+
+    // `trait Shape: Debug + Clone` extends both
+    if let Some(bounds) = node.child_by_field_name("bounds") {
+        for i in 0..bounds.named_child_count() {
+            let Some(bound) = bounds
+                .named_child(i)
+                .filter(|bound| matches!(bound.kind(), "type_identifier" | "scoped_type_identifier" | "generic_type"))
+            else {
+                continue;
+            };
+            ctx.result.type_relations.push(TypeRelation {
+                child_name: name.clone(),
+                parent_name: base_type_name(&ctx.get_text(&bound)),
+                is_implements: false,
+                location: node_to_range(&bound),
+            });
+        }
+    }
+    if let Some(body) = node.child_by_field_name("body") {
+        analyze_in_scope(&body, id, name, ctx);
+    }
 }
 
 fn analyze_type_alias(node: &Node, ctx: &mut AnalysisContext) {
@@ -224,9 +486,9 @@ fn analyze_type_alias(node: &Node, ctx: &mut AnalysisContext) {
         None => return,
     };
     let name = ctx.get_text(&name_node);
-    let exported = has_pub_modifier(node, ctx);
+    let visibility = visibility_of(node, ctx);
     // logos-core 没有 TypeAlias：这里用 Class 表示 type alias
-    let _ = push_symbol(ctx, name, SymbolKind::Class, node, &name_node, exported);
+    let _ = push_symbol(ctx, name, SymbolKind::Class, node, &name_node, visibility);
 }
 
 fn analyze_mod(node: &Node, ctx: &mut AnalysisContext) {
@@ -235,16 +497,26 @@ fn analyze_mod(node: &Node, ctx: &mut AnalysisContext) {
         None => return,
     };
     let name = ctx.get_text(&name_node);
-    let exported = has_pub_modifier(node, ctx);
-    let id = push_symbol(ctx, name.clone(), SymbolKind::Module, node, &name_node, exported);
-    if let Some(body) = node.child_by_field_name("body") {
-        ctx.scope_stack.push(ScopeInfo { symbol_id: id, name });
-        for i in 0..body.named_child_count() {
-            if let Some(child) = body.named_child(i) {
-                analyze_node(&child, ctx);
-            }
+    let visibility = visibility_of(node, ctx);
+    let id = push_symbol(ctx, name.clone(), SymbolKind::Module, node, &name_node, visibility);
+    match node.child_by_field_name("body") {
+        Some(body) => {
+            ctx.modules.push(name.clone());
+            analyze_in_scope(&body, id, name, ctx);
+            ctx.modules.pop();
+        }
+        None => {
+            // `mod foo;` pulls in the file of the module
+            let mut path = vec!["self".to_string()];
+            path.extend(ctx.modules.iter().cloned());
+            path.push(name);
+            ctx.result.imports.push(ImportInfo {
+                module_path: path.join("::"),
+                items: Vec::new(),
+                is_type_only: false,
+                location: node_to_range(node),
+            });
         }
-        ctx.scope_stack.pop();
     }
 }
 
@@ -254,8 +526,8 @@ fn analyze_const(node: &Node, ctx: &mut AnalysisContext) {
         None => return,
     };
     let name = ctx.get_text(&name_node);
-    let exported = has_pub_modifier(node, ctx);
-    let _ = push_symbol(ctx, name, SymbolKind::Constant, node, &name_node, exported);
+    let visibility = visibility_of(node, ctx);
+    let _ = push_symbol(ctx, name, SymbolKind::Constant, node, &name_node, visibility);
 }
 
 fn analyze_static(node: &Node, ctx: &mut AnalysisContext) {
@@ -264,14 +536,14 @@ fn analyze_static(node: &Node, ctx: &mut AnalysisContext) {
         None => return,
     };
     let name = ctx.get_text(&name_node);
-    let exported = has_pub_modifier(node, ctx);
-    let _ = push_symbol(ctx, name, SymbolKind::Variable, node, &name_node, exported);
+    let visibility = visibility_of(node, ctx);
+    let _ = push_symbol(ctx, name, SymbolKind::Variable, node, &name_node, visibility);
 }
 
 fn analyze_call(node: &Node, ctx: &mut AnalysisContext) {
     if let Some(function) = node.child_by_field_name("function") {
         let text = ctx.get_text(&function);
-        let callee_name = text.split("::").last().unwrap_or(&text).split('.').last().unwrap_or(&text).to_string();
+        let callee_name = text.split("::").last().unwrap_or(&text).split('.').next_back().unwrap_or(&text).to_string();
         let qualified = if text.contains("::") || text.contains('.') {
             Some(text)
         } else {
@@ -326,10 +598,86 @@ fn helper() {}
 pub const MAX: usize = 10;
 "#;
         let result = adapter.analyze("file:///test.rs", src);
-        assert!(result.imports.len() >= 1);
+        assert!(!result.imports.is_empty());
         assert!(result.symbols.iter().any(|s| s.name == "User" && s.exported));
         assert!(result.symbols.iter().any(|s| s.name == "helper"));
-        assert!(result.calls.len() >= 1);
+        assert!(!result.calls.is_empty());
     }
+
+    #[test]
+    fn rust_impls_traits_and_visibility() {
+        let adapter = RustAdapter::new().unwrap();
+        let src = r#"
+use crate::model::{self, Id as UserId, store::*};
+mod cache;
+
+pub(crate) enum Role { Admin, Guest }
+
+pub trait Named: std::fmt::Debug + Clone {
+    fn name(&self) -> String;
+}
+
+#[derive(Debug, Clone)]
+pub struct User { id: UserId, pub(super) role: Role }
+
+impl Named for User {
+    fn name(&self) -> String { String::new() }
+}
+
+impl<'a> User {
+    fn check(&self) {}
 }
+"#;
+        let result = adapter.analyze("file:///src/lib.rs", src);
+
+        let imports: Vec<_> = result.imports.iter().map(|i| i.module_path.as_str()).collect();
+        assert_eq!(imports, vec!["crate::model", "crate::model::Id", "crate::model::store", "self::cache"]);
+        assert_eq!(result.imports[1].items[0].alias.as_deref(), Some("UserId"));
+        assert!(result.imports[2].items.is_empty());
+
+        let symbol = |name: &str| result.symbols.iter().find(|s| s.name == name).unwrap();
+        assert_eq!(symbol("Role").visibility, Visibility::Internal);
+        assert_eq!(symbol("Admin").kind, SymbolKind::EnumMember);
+        assert_eq!(symbol("role").visibility, Visibility::Internal);
+        assert_eq!(symbol("id").visibility, Visibility::Private);
+
+        let user = symbol("User");
+        let methods: Vec<_> = user.children.iter().filter_map(|id| result.symbols.iter().find(|s| s.id == *id)).collect();
+        let names: Vec<_> = methods.iter().map(|m| (m.name.as_str(), m.qualified_name.as_str(), m.visibility)).collect();
+        assert!(names.contains(&("name", "User::name", Visibility::Public)));
+        assert!(names.contains(&("check", "User::check", Visibility::Private)));
+        // The trait's own method is declared under the trait
+        let trait_method = result.symbols.iter().find(|s| s.qualified_name == "Named::name").unwrap();
+        assert_eq!((trait_method.kind, trait_method.parent), (SymbolKind::Method, Some(symbol("Named").id)));
+
+        let relations: Vec<_> = result
+            .type_relations
+            .iter()
+            .map(|r| (r.child_name.as_str(), r.parent_name.as_str(), r.is_implements))
+            .collect();
+        assert_eq!(
+            relations,
+            vec![("Named", "Debug", false), ("Named", "Clone", false), ("User", "Named", true)]
+        );
+    }
+
+    #[test]
+    fn rust_resolve_import_through_modules() {
+        let vfs = crate::vfs::MemoryFs::new();
+        vfs.insert("/ws/src/lib.rs", "pub mod model;\n");
+        vfs.insert("/ws/src/model/mod.rs", "pub mod store;\n");
+        vfs.insert("/ws/src/model/store.rs", "");
+        vfs.insert("/ws/src/model/store/disk.rs", "");
+        vfs.insert("/ws/src/cache.rs", "");
 
+        let adapter = RustAdapter::new().unwrap();
+        let store = Path::new("/ws/src/model/store.rs");
+        assert_eq!(adapter.resolve_import(&vfs, store, "self::disk"), Some(PathBuf::from("/ws/src/model/store/disk.rs")));
+        assert_eq!(adapter.resolve_import(&vfs, store, "super::Model"), Some(PathBuf::from("/ws/src/model/mod.rs")));
+        assert_eq!(adapter.resolve_import(&vfs, store, "crate::cache::get"), Some(PathBuf::from("/ws/src/cache.rs")));
+        assert_eq!(adapter.resolve_import(&vfs, store, "crate::Config"), Some(PathBuf::from("/ws/src/lib.rs")));
+        let lib = Path::new("/ws/src/lib.rs");
+        assert_eq!(adapter.resolve_import(&vfs, lib, "self::model"), Some(PathBuf::from("/ws/src/model/mod.rs")));
+        assert_eq!(adapter.resolve_import(&vfs, lib, "std::collections::HashMap"), None);
+    }
+}
@@ -0,0 +1,136 @@
+//! Interface definitions and the code generated from them
+//!
+//! Generators derive the names of what they write from the schema: a
+//! protobuf `service UserService` becomes a `UserServiceClient`, a
+//! `UserServiceStub` or a `UserServiceServicer`, its `rpc GetUser` a
+//! `getUser` or `get_user` method, a field `user_id` `userId`, `UserId` or
+//! `getUserId`. [`generated_names`] lists the names a schema declaration
+//! may go by in generated code, so that a call into a generated client
+//! leads back to the definition it was generated from rather than to the
+//! generated file.
+
+use logos_core::SymbolKind;
+
+use crate::IndexedSymbol;
+
+/// File extensions of interface definitions
+const SCHEMA_EXTENSIONS: &[&str] = &[".proto"];
+
+/// Suffixes gRPC and Connect generators add to a service's name
+const SERVICE_SUFFIXES: &[&str] = &["Client", "PromiseClient", "Stub", "Servicer", "Server", "Handler"];
+
+/// Prefixes of the accessors generated for a field
+const ACCESSOR_PREFIXES: &[&str] = &["get", "set", "has", "clear", "Get"];
+
+/// Whether the file at `uri` is an interface definition
+pub fn is_schema(uri: &str) -> bool {
+    SCHEMA_EXTENSIONS.iter().any(|extension| uri.ends_with(extension))
+}
+
+/// Names the code generated from the schema declaration `symbol` may go
+/// by, the declaration's own name first
+pub fn generated_names(symbol: &IndexedSymbol) -> Vec<String> {
+    let name = symbol.name.as_str();
+    let mut names = vec![name.to_string()];
+    match symbol.kind {
+        SymbolKind::Interface => {
+            names.extend(SERVICE_SUFFIXES.iter().map(|suffix| format!("{}{}", name, suffix)));
+        }
+        SymbolKind::Method => {
+            names.push(lower_camel(name));
+            names.push(snake_case(name));
+        }
+        SymbolKind::Field => {
+            let upper = upper_camel(name);
+            names.push(lower_camel(name));
+            names.extend(ACCESSOR_PREFIXES.iter().map(|prefix| format!("{}{}", prefix, upper)));
+            names.push(upper);
+        }
+        // A nested message is `Outer_Inner` in Go and C++
+        SymbolKind::Struct | SymbolKind::Enum => {
+            if let Some(container) = &symbol.container {
+                names.push(format!("{}_{}", container, name));
+            }
+        }
+        _ => {}
+    }
+    names.dedup();
+    names
+}
+
+/// Whether code named `name` may be generated from `symbol`
+pub fn generates(symbol: &IndexedSymbol, name: &str) -> bool {
+    generated_names(symbol).iter().any(|generated| generated == name)
+}
+
+/// `user_id` and `userId` as `UserId`
+fn upper_camel(name: &str) -> String {
+    name.split('_').filter(|part| !part.is_empty()).map(capitalize).collect()
+}
+
+/// `user_id` as `userId`, `GetUser` as `getUser`
+fn lower_camel(name: &str) -> String {
+    let upper = upper_camel(name);
+    let mut chars = upper.chars();
+    match chars.next() {
+        Some(first) => first.to_lowercase().chain(chars).collect(),
+        None => upper,
+    }
+}
+
+/// `GetUser` as `get_user`
+fn snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+fn capitalize(part: &str) -> String {
+    let mut chars = part.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logos_core::{Range, Symbol};
+
+    fn symbol(name: &str, kind: SymbolKind, container: Option<&str>) -> IndexedSymbol {
+        let range = Range::from_coords(0, 0, 0, 1);
+        let symbol = Symbol::new(name.to_string(), kind, range, range);
+        IndexedSymbol::from_symbol(&symbol, "file:///ws/user.proto", container.map(String::from))
+    }
+
+    #[test]
+    fn test_generated_names() {
+        let service = symbol("UserService", SymbolKind::Interface, None);
+        assert!(generates(&service, "UserServiceClient"));
+        assert!(generates(&service, "UserServiceServicer"));
+        assert!(!generates(&service, "UserServiceFactory"));
+
+        let rpc = symbol("GetUser", SymbolKind::Method, Some("UserService"));
+        assert_eq!(generated_names(&rpc), vec!["GetUser", "getUser", "get_user"]);
+
+        let field = symbol("user_id", SymbolKind::Field, Some("User"));
+        for name in ["user_id", "userId", "UserId", "getUserId", "GetUserId", "setUserId"] {
+            assert!(generates(&field, name), "{}", name);
+        }
+
+        let nested = symbol("Address", SymbolKind::Struct, Some("User"));
+        assert!(generates(&nested, "User_Address"));
+        assert!(is_schema("file:///ws/proto/user.proto"));
+        assert!(!is_schema("file:///ws/proto/user_pb.js"));
+    }
+}
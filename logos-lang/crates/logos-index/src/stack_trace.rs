@@ -0,0 +1,249 @@
+//! Stack traces pasted from runtime error output
+//!
+//! Reads the frames of Python tracebacks, Node.js error stacks, Java and
+//! Kotlin exception traces, Rust panics and backtraces, and Go panics, and
+//! maps the files they name onto the workspace. Traces are often captured
+//! elsewhere (a container, a CI runner, a packaged jar), so frames whose
+//! path doesn't exist locally are matched against the indexed files on
+//! their trailing path components.
+
+use crate::coverage::best_suffix_match;
+use crate::vfs::Vfs;
+use logos_core::uri::uri_to_path;
+use regex::Regex;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+/// Runtimes whose traces are recognized
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TraceFormat {
+    Python,
+    Node,
+    /// Java and other JVM languages
+    Java,
+    Rust,
+    Go,
+}
+
+/// One frame of a trace
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StackFrame {
+    pub format: TraceFormat,
+    /// 0-based line of the trace text the frame was read from
+    pub trace_line: u32,
+    /// Byte range of the location (`file:line:column`) within that line
+    pub span: std::ops::Range<usize>,
+    /// File as written in the trace; JVM traces give the file name only
+    pub file: String,
+    /// 0-based
+    pub line: u32,
+    /// 0-based, when the trace gives one
+    pub column: Option<u32>,
+    pub function: Option<String>,
+}
+
+/// Frames of all traces in `text`, in order
+pub fn parse_stack_trace(text: &str) -> Vec<StackFrame> {
+    let (Ok(python), Ok(java), Ok(rust_panic), Ok(rust_frame), Ok(go), Ok(node), Ok(numbered), Ok(go_call)) = (
+        Regex::new(r#"^\s*File "(?P<file>[^"<][^"]*)", line (?P<line>\d+)(?:, in (?P<function>\S+))?"#),
+        Regex::new(r"^\s*at (?:[\w.$@-]+/)*(?P<function>[\w$.<>]+)\((?P<file>[\w$.-]+):(?P<line>\d+)\)"),
+        Regex::new(r"panicked at (?:'.*', )?(?P<file>[^\s:']+\.rs):(?P<line>\d+):(?P<column>\d+)"),
+        Regex::new(r"^\s*at (?P<file>\S+\.rs):(?P<line>\d+):(?P<column>\d+)\s*$"),
+        Regex::new(r"^\s+(?P<file>\S+\.go):(?P<line>\d+)(?: \+0x[0-9a-f]+)?\s*$"),
+        Regex::new(r"^\s*at (?:(?:async )?(?P<function>[^()]+?) \()?(?P<file>[^()\s]+?):(?P<line>\d+):(?P<column>\d+)\)?\s*$"),
+        // Function line above a Rust backtrace location: `  3: app::main`
+        Regex::new(r"^\s*\d+: (?P<function>\S+)"),
+        // Function line above a Go location: `main.(*T).Run(0xc000010000)`
+        Regex::new(r"^(?P<function>\S+)\(.*\)$"),
+    ) else {
+        return Vec::new();
+    };
+
+    let mut frames = Vec::new();
+    let mut previous = "";
+    for (number, line) in text.lines().enumerate() {
+        let matched = [
+            (TraceFormat::Python, &python),
+            (TraceFormat::Java, &java),
+            (TraceFormat::Rust, &rust_panic),
+            (TraceFormat::Rust, &rust_frame),
+            (TraceFormat::Go, &go),
+            (TraceFormat::Node, &node),
+        ]
+        .into_iter()
+        .find_map(|(format, pattern)| Some((format, pattern.captures(line)?)));
+        let Some((format, captures)) = matched else {
+            previous = line;
+            continue;
+        };
+        let file = &captures["file"];
+        // Frames inside the Node runtime have no source to open
+        if format == TraceFormat::Node && (file.starts_with("node:") || file.starts_with("internal/")) {
+            previous = line;
+            continue;
+        }
+        let Some(line_number) = captures["line"].parse::<u32>().ok().and_then(|n| n.checked_sub(1)) else {
+            previous = line;
+            continue;
+        };
+        let column = captures
+            .name("column")
+            .and_then(|c| c.as_str().parse::<u32>().ok())
+            .and_then(|c| c.checked_sub(1));
+        let start = captures.name("file").map_or(0, |m| m.start());
+        let end = captures.name("column").or_else(|| captures.name("line")).map_or(line.len(), |m| m.end());
+        let file = file.to_string();
+        let function = match format {
+            TraceFormat::Rust if captures.name("function").is_none() => numbered.captures(previous),
+            TraceFormat::Go => go_call.captures(previous),
+            _ => Some(captures),
+        }
+        .and_then(|c| c.name("function").map(|f| f.as_str().trim().to_string()));
+
+        frames.push(StackFrame {
+            format,
+            trace_line: number as u32,
+            span: start..end,
+            file,
+            line: line_number,
+            column,
+            function,
+        });
+        previous = line;
+    }
+    frames
+}
+
+/// Maps the files named by frames to files of the workspace
+pub struct FrameResolver<'a> {
+    vfs: &'a dyn Vfs,
+    roots: Vec<PathBuf>,
+    /// Indexed files, for frames whose path doesn't exist locally
+    files: Vec<PathBuf>,
+}
+
+impl<'a> FrameResolver<'a> {
+    pub fn new(vfs: &'a dyn Vfs, roots: Vec<PathBuf>, files: Vec<PathBuf>) -> Self {
+        Self { vfs, roots, files }
+    }
+
+    /// Local file of a frame: the path itself, the path under a workspace
+    /// root, or the indexed file it ends with
+    pub fn resolve(&self, frame: &StackFrame) -> Option<PathBuf> {
+        let path = if frame.file.starts_with("file://") {
+            uri_to_path(&frame.file)?
+        } else {
+            PathBuf::from(&frame.file)
+        };
+        if path.is_absolute() {
+            if self.vfs.is_file(&path) {
+                return Some(path);
+            }
+        } else if let Some(local) = self.roots.iter().map(|root| root.join(&path)).find(|p| self.vfs.is_file(p)) {
+            return Some(local);
+        }
+        // JVM frames name the file only; the package of the class gives
+        // its directories
+        let path = match (frame.format, &frame.function) {
+            (TraceFormat::Java, Some(function)) => jvm_source_path(function, &frame.file),
+            _ => path,
+        };
+        best_suffix_match(&path, self.files.iter().map(|file| (file.as_path(), file))).cloned()
+    }
+
+    /// Whether `path` is inside one of the workspace roots, as opposed to
+    /// a library or the runtime
+    pub fn in_workspace(&self, path: &Path) -> bool {
+        self.roots.iter().any(|root| path.starts_with(root))
+    }
+}
+
+/// `com/acme/Foo.java` for `com.acme.Foo$Inner.run` in `Foo.java`
+fn jvm_source_path(function: &str, file: &str) -> PathBuf {
+    let mut segments: Vec<&str> = function.split('.').collect();
+    // Method and class
+    segments.truncate(segments.len().saturating_sub(2));
+    let mut path: PathBuf = segments.into_iter().collect();
+    path.push(file);
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+
+    /// Format, file, line, column and function of a frame
+    type FrameSummary = (TraceFormat, String, u32, Option<u32>, Option<String>);
+
+    fn summary(text: &str) -> Vec<FrameSummary> {
+        parse_stack_trace(text)
+            .into_iter()
+            .map(|f| (f.format, f.file, f.line, f.column, f.function))
+            .collect()
+    }
+
+    #[test]
+    fn test_python_and_node_traces() {
+        let text = "Traceback (most recent call last):\n  File \"/app/main.py\", line 12, in run\n    go()\n  File \"<frozen runpy>\", line 3, in <module>\nValueError: bad\n";
+        assert_eq!(summary(text), vec![(TraceFormat::Python, "/app/main.py".to_string(), 11, None, Some("run".to_string()))]);
+        assert_eq!(parse_stack_trace(text)[0].span, 8..30);
+
+        let text = "TypeError: x is undefined\n    at Server.handle (/srv/app/server.js:40:7)\n    at async run (file:///srv/app/index.mjs:3:1)\n    at node:internal/main:10:5\n    at /srv/app/cb.js:1:2\n";
+        assert_eq!(
+            summary(text),
+            vec![
+                (TraceFormat::Node, "/srv/app/server.js".to_string(), 39, Some(6), Some("Server.handle".to_string())),
+                (TraceFormat::Node, "file:///srv/app/index.mjs".to_string(), 2, Some(0), Some("run".to_string())),
+                (TraceFormat::Node, "/srv/app/cb.js".to_string(), 0, Some(1), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_java_rust_and_go_traces() {
+        let text = "java.lang.IllegalStateException: boom\n\tat com.acme.Foo$Inner.run(Foo.java:42)\n\tat java.base/java.lang.Thread.run(Thread.java:833)\n\tat sun.reflect.Native(Native Method)\n";
+        let frames = summary(text);
+        assert_eq!(frames.len(), 2);
+        assert_eq!(frames[0], (TraceFormat::Java, "Foo.java".to_string(), 41, None, Some("com.acme.Foo$Inner.run".to_string())));
+        assert_eq!(frames[1].4.as_deref(), Some("java.lang.Thread.run"));
+
+        let text = "thread 'main' panicked at src/main.rs:5:9:\nboom\nstack backtrace:\n   3: app::parse\n             at ./src/parse.rs:20:13\n";
+        assert_eq!(
+            summary(text),
+            vec![
+                (TraceFormat::Rust, "src/main.rs".to_string(), 4, Some(8), None),
+                (TraceFormat::Rust, "./src/parse.rs".to_string(), 19, Some(12), Some("app::parse".to_string())),
+            ]
+        );
+
+        let text = "panic: runtime error\n\ngoroutine 1 [running]:\nmain.(*Server).Run(0xc000010000)\n\t/home/ci/app/server.go:27 +0x1d\n";
+        assert_eq!(
+            summary(text),
+            vec![(TraceFormat::Go, "/home/ci/app/server.go".to_string(), 26, None, Some("main.(*Server).Run".to_string()))]
+        );
+    }
+
+    #[test]
+    fn test_resolve_frames() {
+        let vfs = MemoryFs::new();
+        vfs.insert("/ws/src/main.rs", "");
+        vfs.insert("/ws/app/server.go", "");
+        vfs.insert("/ws/src/main/java/com/acme/Foo.java", "");
+        vfs.insert("/ws/src/main/java/org/other/Foo.java", "");
+        let files = ["/ws/src/main.rs", "/ws/app/server.go", "/ws/src/main/java/com/acme/Foo.java", "/ws/src/main/java/org/other/Foo.java"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect();
+        let resolver = FrameResolver::new(&vfs, vec![PathBuf::from("/ws")], files);
+        let resolve = |text: &str| parse_stack_trace(text).first().and_then(|frame| resolver.resolve(frame));
+
+        assert_eq!(resolve("thread 'main' panicked at src/main.rs:5:9:"), Some(PathBuf::from("/ws/src/main.rs")));
+        // Captured on another machine
+        assert_eq!(resolve("\t/home/ci/app/server.go:27 +0x1d"), Some(PathBuf::from("/ws/app/server.go")));
+        assert_eq!(resolve("\tat com.acme.Foo.run(Foo.java:3)"), Some(PathBuf::from("/ws/src/main/java/com/acme/Foo.java")));
+        assert_eq!(resolve("  File \"/usr/lib/python3/json/missing.py\", line 1, in f"), None);
+        assert!(resolver.in_workspace(Path::new("/ws/src/main.rs")));
+    }
+}
@@ -8,12 +8,17 @@
 //! - Type hierarchy (inheritance, implementations)
 
 use dashmap::DashMap;
-use logos_core::{Position, Range, SymbolKind};
+use logos_core::{uri, Position, Range, SymbolKind};
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, RwLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::archive::ARCHIVE_SCHEME;
+use crate::eviction::DetailLru;
+use crate::inverted::normalize_name;
 
 /// Unique identifier for a symbol
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -25,10 +30,42 @@ impl Default for SymbolId {
     }
 }
 
+static NEXT_SYMBOL_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Set in the ids derived from a declaration; counted ids stay below it.
+/// Both kinds fit the 53 bits a JavaScript number holds exactly.
+const STABLE_ID_BIT: u64 = 1 << 52;
+
 impl SymbolId {
     pub fn new() -> Self {
-        static COUNTER: AtomicU64 = AtomicU64::new(1);
-        Self(COUNTER.fetch_add(1, Ordering::SeqCst))
+        Self(NEXT_SYMBOL_ID.fetch_add(1, Ordering::SeqCst))
+    }
+
+    /// Id of the declaration of `qualified_name` as `kind` in the file at
+    /// `uri`, the same on every analysis. `disambiguator` tells apart the
+    /// declarations sharing all three, such as overloads.
+    pub fn stable(uri: &str, qualified_name: &str, kind: SymbolKind, disambiguator: u32) -> Self {
+        let (kind, disambiguator) = (format!("{:?}", kind), disambiguator.to_string());
+        let mut hasher = blake3::Hasher::new();
+        for part in [uri, qualified_name, kind.as_str(), disambiguator.as_str()] {
+            hasher.update(part.as_bytes());
+            hasher.update(&[0]);
+        }
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(&hasher.finalize().as_bytes()[..8]);
+        Self((u64::from_le_bytes(bytes) & (STABLE_ID_BIT - 1)) | STABLE_ID_BIT)
+    }
+
+    /// Whether the id was derived from a declaration by [`Self::stable`]
+    pub fn is_stable(self) -> bool {
+        self.0 & STABLE_ID_BIT != 0
+    }
+
+    /// Make sure new ids are above `id`, e.g. after loading a saved index
+    pub fn reserve_through(id: SymbolId) {
+        if !id.is_stable() {
+            NEXT_SYMBOL_ID.fetch_max(id.0 + 1, Ordering::SeqCst);
+        }
     }
 }
 
@@ -121,8 +158,15 @@ pub struct SmartSymbol {
     pub qualified_name: String,
 }
 
-/// Location of a symbol
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl SmartSymbol {
+    /// Whether the symbol is declared in a generated file
+    pub fn is_generated(&self) -> bool {
+        self.attributes.iter().any(|attribute| attribute.name == crate::generated::GENERATED_ATTRIBUTE)
+    }
+}
+
+/// Location of a symbol. Locations order by URI, then range.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct SymbolLocation {
     /// File path
     pub uri: String,
@@ -173,6 +217,14 @@ pub struct CallSite {
     pub call_type: CallType,
 }
 
+/// Analyzes the file behind a URI again, for the details of the symbols
+/// evicted from it
+pub type Hydrator = Arc<dyn Fn(&str) -> Option<Vec<SmartSymbol>> + Send + Sync>;
+
+/// Documentation and type information of the symbols of a file, by
+/// [`declaration_key`]
+type FileDetails = HashMap<(String, Range), (Option<String>, Option<TypeInfo>)>;
+
 /// The main symbol table structure
 pub struct SymbolTable {
     /// All symbols indexed by ID
@@ -189,6 +241,12 @@ pub struct SymbolTable {
 
     /// All references
     references: DashMap<SymbolId, Vec<SymbolReference>>,
+
+    /// Memory taken by symbol details, by file
+    details: Mutex<DetailLru>,
+
+    /// Where evicted details are read back from
+    hydrator: RwLock<Option<Hydrator>>,
 }
 
 impl SymbolTable {
@@ -199,6 +257,8 @@ impl SymbolTable {
             name_index: DashMap::new(),
             qualified_name_index: DashMap::new(),
             references: DashMap::new(),
+            details: Mutex::new(DetailLru::default()),
+            hydrator: RwLock::new(None),
         }
     }
 
@@ -208,12 +268,13 @@ impl SymbolTable {
         let name = symbol.name.clone();
         let qualified_name = symbol.qualified_name.clone();
         let uri = symbol.location.uri.clone();
+        let detail_bytes = details_size(&symbol);
 
         // Add to main index
         self.symbols.insert(id, symbol);
 
         // Add to file index
-        self.file_symbols.entry(uri).or_default().push(id);
+        self.file_symbols.entry(uri.clone()).or_default().push(id);
 
         // Add to name index
         self.name_index.entry(name).or_default().push(id);
@@ -221,35 +282,146 @@ impl SymbolTable {
         // Add to qualified name index
         self.qualified_name_index.insert(qualified_name, id);
 
+        let evicted = {
+            let mut lru = self.lru();
+            lru.add(&uri, detail_bytes);
+            lru.evict(&uri)
+        };
+        self.drop_details(&evicted);
+
         id
     }
 
-    /// Get a symbol by ID
+    /// Keep the documentation and type information of symbols within
+    /// `budget` bytes, unlimited with `None`. Past it, those of the least
+    /// recently used files are dropped, and read back with `hydrate` when
+    /// the files' symbols are asked for.
+    pub fn set_detail_budget(&self, budget: Option<usize>, hydrate: Hydrator) {
+        if let Ok(mut hydrator) = self.hydrator.write() {
+            *hydrator = Some(hydrate);
+        }
+        let evicted = {
+            let mut lru = self.lru();
+            lru.set_budget(budget);
+            lru.evict("")
+        };
+        self.drop_details(&evicted);
+    }
+
+    /// Bytes taken by the details of symbols in memory
+    pub fn detail_bytes(&self) -> usize {
+        self.lru().resident_bytes()
+    }
+
+    /// Number of files whose symbol details were evicted
+    pub fn evicted_files(&self) -> usize {
+        self.lru().evicted().count()
+    }
+
+    /// Files whose symbol details were evicted
+    pub fn evicted_uris(&self) -> Vec<String> {
+        self.lru().evicted().map(String::from).collect()
+    }
+
+    /// Record that the symbols of `uris` are held without their details,
+    /// to be read back when asked for
+    pub fn mark_evicted(&self, uris: &[String]) {
+        let mut lru = self.lru();
+        for uri in uris {
+            lru.mark_evicted(uri);
+        }
+    }
+
+    fn lru(&self) -> MutexGuard<'_, DetailLru> {
+        self.details.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn hydrator(&self) -> Option<Hydrator> {
+        self.hydrator.read().ok().and_then(|hydrator| hydrator.clone())
+    }
+
+    /// Drop the details of the symbols of `uris`
+    fn drop_details(&self, uris: &[String]) {
+        for uri in uris {
+            let ids = self.file_symbols.get(uri).map(|ids| ids.clone()).unwrap_or_default();
+            for id in ids {
+                if let Some(mut symbol) = self.symbols.get_mut(&id) {
+                    symbol.documentation = None;
+                    symbol.type_info = None;
+                }
+            }
+        }
+    }
+
+    /// Make sure the details of the symbols of `uri` are in memory, reading
+    /// them back when they were evicted, and mark them as just used
+    fn load_details(&self, uri: &str) {
+        let evicted = {
+            let mut lru = self.lru();
+            lru.touch(uri);
+            lru.is_evicted(uri)
+        };
+        let Some(fresh) = self.hydrator().filter(|_| evicted).and_then(|hydrate| hydrate(uri)) else {
+            return;
+        };
+        let mut details = details_by_declaration(fresh);
+        let mut bytes = 0;
+        let ids = self.file_symbols.get(uri).map(|ids| ids.clone()).unwrap_or_default();
+        for id in ids {
+            if let Some(mut symbol) = self.symbols.get_mut(&id) {
+                if let Some((documentation, type_info)) = details.remove(&declaration_key(&symbol)) {
+                    symbol.documentation = documentation;
+                    symbol.type_info = type_info;
+                }
+                bytes += details_size(&symbol);
+            }
+        }
+
+        let evicted = {
+            let mut lru = self.lru();
+            lru.add(uri, bytes);
+            lru.evict(uri)
+        };
+        self.drop_details(&evicted);
+    }
+
+    /// Get a symbol by ID, with its details
     pub fn get(&self, id: SymbolId) -> Option<SmartSymbol> {
+        let uri = self.symbols.get(&id)?.location.uri.clone();
+        self.load_details(&uri);
         self.symbols.get(&id).map(|s| s.clone())
     }
 
-    /// Find symbols by name
+    /// Find symbols by name, in location order. The symbols of evicted
+    /// files come without their details; [`Self::get`] reads them back.
     pub fn find_by_name(&self, name: &str) -> Vec<SmartSymbol> {
-        self.name_index
+        let mut symbols: Vec<_> = self
+            .name_index
             .get(name)
             .map(|ids| {
                 ids.iter()
                     .filter_map(|id| self.symbols.get(id).map(|s| s.clone()))
                     .collect()
             })
-            .unwrap_or_default()
+            .unwrap_or_default();
+        sort_symbols(&mut symbols);
+        symbols
     }
 
     /// Find symbol by qualified name
     pub fn find_by_qualified_name(&self, qualified_name: &str) -> Option<SmartSymbol> {
-        self.qualified_name_index
-            .get(qualified_name)
-            .and_then(|id| self.symbols.get(&id).map(|s| s.clone()))
+        let id = *self.qualified_name_index.get(qualified_name)?;
+        self.get(id)
     }
 
-    /// Get all symbols in a file
+    /// Get all symbols in a file, with their details
     pub fn get_file_symbols(&self, uri: &str) -> Vec<SmartSymbol> {
+        self.load_details(uri);
+        self.stored_file_symbols(uri)
+    }
+
+    /// The symbols of a file as held, without reading back evicted details
+    fn stored_file_symbols(&self, uri: &str) -> Vec<SmartSymbol> {
         self.file_symbols
             .get(uri)
             .map(|ids| {
@@ -262,6 +434,7 @@ impl SymbolTable {
 
     /// Remove all symbols from a file
     pub fn remove_file(&self, uri: &str) {
+        self.lru().remove(uri);
         if let Some((_, ids)) = self.file_symbols.remove(uri) {
             for id in ids {
                 if let Some((_, symbol)) = self.symbols.remove(&id) {
@@ -278,6 +451,52 @@ impl SymbolTable {
         }
     }
 
+    /// Move the symbols of a file and the references in it from `from` to
+    /// `to`. Stable ids are derived from the URI, so they are given the ids
+    /// analyzing the file at `to` would; returns the ids that changed.
+    pub fn rename_file(&self, from: &str, to: &str) -> HashMap<SymbolId, SymbolId> {
+        self.lru().rename(from, to);
+        let Some((_, ids)) = self.file_symbols.remove(from) else {
+            return HashMap::new();
+        };
+        let symbols: Vec<SmartSymbol> = ids.iter().filter_map(|id| self.symbols.remove(id).map(|(_, s)| s)).collect();
+        let remap = rebased_ids(&symbols, from, to);
+        let new_id = |id: SymbolId| remap.get(&id).copied().unwrap_or(id);
+
+        for mut symbol in symbols {
+            let old = symbol.id;
+            symbol.id = new_id(old);
+            symbol.parent = symbol.parent.map(new_id);
+            symbol.children.iter_mut().for_each(|child| *child = new_id(*child));
+            symbol.location.uri = to.to_string();
+            if let Some(mut entry) = self.name_index.get_mut(&symbol.name) {
+                entry.iter_mut().filter(|id| **id == old).for_each(|id| *id = symbol.id);
+            }
+            if let Some(mut entry) = self.qualified_name_index.get_mut(&symbol.qualified_name) {
+                if *entry == old {
+                    *entry = symbol.id;
+                }
+            }
+            self.symbols.insert(symbol.id, symbol);
+        }
+        self.file_symbols.insert(to.to_string(), ids.into_iter().map(new_id).collect());
+
+        for (old, new) in &remap {
+            if let Some((_, references)) = self.references.remove(old) {
+                self.references.insert(*new, references);
+            }
+        }
+        for mut references in self.references.iter_mut() {
+            for reference in references.iter_mut() {
+                reference.symbol_id = new_id(reference.symbol_id);
+                if reference.location.uri == from {
+                    reference.location.uri = to.to_string();
+                }
+            }
+        }
+        remap
+    }
+
     /// Add a reference to a symbol
     pub fn add_reference(&self, reference: SymbolReference) {
         self.references
@@ -286,25 +505,31 @@ impl SymbolTable {
             .push(reference);
     }
 
-    /// Get all references to a symbol
+    /// Get all references to a symbol, in location order
     pub fn get_references(&self, id: SymbolId) -> Vec<SymbolReference> {
-        self.references
+        let mut references = self
+            .references
             .get(&id)
             .map(|refs| refs.clone())
-            .unwrap_or_default()
+            .unwrap_or_default();
+        references.sort_by(|a, b| a.location.cmp(&b.location));
+        references
     }
 
-    /// Search symbols by query
+    /// Search symbols by query, in location order. Names are compared
+    /// composed and case-folded, as in the workspace symbol index. The
+    /// symbols of evicted files come without their details.
     pub fn search(&self, query: &str) -> Vec<SmartSymbol> {
-        let query_lower = query.to_lowercase();
+        let query = normalize_name(query);
         let mut results = Vec::new();
 
         for entry in self.symbols.iter() {
-            if entry.name.to_lowercase().contains(&query_lower) {
+            if normalize_name(&entry.name).contains(&query) {
                 results.push(entry.clone());
             }
         }
 
+        sort_symbols(&mut results);
         results
     }
 
@@ -315,6 +540,44 @@ impl SymbolTable {
             .find(|s| s.location.selection_range.contains(position))
     }
 
+    /// All symbols, in no particular order, with their details. Those of
+    /// evicted files are read back for the copies only.
+    pub fn all(&self) -> Vec<SmartSymbol> {
+        let mut symbols: Vec<SmartSymbol> = self.symbols.iter().map(|s| s.clone()).collect();
+        let evicted = self.evicted_uris();
+        let Some(hydrate) = self.hydrator().filter(|_| !evicted.is_empty()) else {
+            return symbols;
+        };
+        let mut details: HashMap<String, FileDetails> = evicted
+            .into_iter()
+            .filter_map(|uri| {
+                let fresh = hydrate(&uri)?;
+                Some((uri, details_by_declaration(fresh)))
+            })
+            .collect();
+        for symbol in &mut symbols {
+            let Some(file) = details.get_mut(&symbol.location.uri) else {
+                continue;
+            };
+            if let Some((documentation, type_info)) = file.remove(&declaration_key(symbol)) {
+                symbol.documentation = documentation;
+                symbol.type_info = type_info;
+            }
+        }
+        symbols
+    }
+
+    /// All symbols as held, in no particular order: those of evicted files
+    /// come without their details
+    pub fn all_stored(&self) -> Vec<SmartSymbol> {
+        self.symbols.iter().map(|s| s.clone()).collect()
+    }
+
+    /// All references, in no particular order
+    pub fn all_references(&self) -> Vec<SymbolReference> {
+        self.references.iter().flat_map(|refs| refs.clone()).collect()
+    }
+
     /// Get symbol count
     pub fn len(&self) -> usize {
         self.symbols.len()
@@ -325,10 +588,82 @@ impl SymbolTable {
         self.symbols.is_empty()
     }
 
-    /// Get all file URIs
+    /// Get all file URIs, sorted
     pub fn files(&self) -> Vec<String> {
-        self.file_symbols.iter().map(|e| e.key().clone()).collect()
+        let mut files: Vec<_> = self.file_symbols.iter().map(|e| e.key().clone()).collect();
+        files.sort();
+        files
     }
+
+    /// Count symbols grouped by file URI
+    pub fn count_by_file(&self) -> HashMap<String, usize> {
+        self.file_symbols.iter().map(|e| (e.key().clone(), e.len())).collect()
+    }
+
+    /// Count symbols grouped by kind
+    pub fn count_by_kind(&self) -> HashMap<SymbolKind, usize> {
+        let mut counts = HashMap::new();
+        for entry in self.symbols.iter() {
+            *counts.entry(entry.kind).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Rough estimate of heap and inline memory held by the table, in bytes
+    pub fn estimated_memory_bytes(&self) -> usize {
+        let id_size = std::mem::size_of::<SymbolId>();
+        let symbols: usize = self
+            .symbols
+            .iter()
+            .map(|s| {
+                std::mem::size_of::<SmartSymbol>()
+                    + s.name.len()
+                    + s.qualified_name.len()
+                    + s.location.uri.len()
+                    + s.documentation.as_ref().map_or(0, |d| d.len())
+                    + s.children.len() * id_size
+            })
+            .sum();
+        let files: usize = self.file_symbols.iter().map(|e| e.key().len() + e.len() * id_size).sum();
+        let names: usize = self.name_index.iter().map(|e| e.key().len() + e.len() * id_size).sum();
+        let qualified: usize = self.qualified_name_index.iter().map(|e| e.key().len() + id_size).sum();
+        let references: usize = self
+            .references
+            .iter()
+            .map(|e| e.len() * std::mem::size_of::<SymbolReference>())
+            .sum();
+        symbols + files + names + qualified + references
+    }
+}
+
+/// What tells a declaration apart from the others of its file, across
+/// analyses of the same content
+fn declaration_key(symbol: &SmartSymbol) -> (String, Range) {
+    (symbol.qualified_name.clone(), symbol.location.selection_range)
+}
+
+fn details_by_declaration(symbols: Vec<SmartSymbol>) -> FileDetails {
+    symbols
+        .into_iter()
+        .map(|symbol| (declaration_key(&symbol), (symbol.documentation, symbol.type_info)))
+        .collect()
+}
+
+/// Rough size of the details of a symbol, in bytes
+fn details_size(symbol: &SmartSymbol) -> usize {
+    fn type_bytes(info: &TypeInfo) -> usize {
+        std::mem::size_of::<TypeInfo>()
+            + info.type_expr.len()
+            + info.type_params.iter().map(String::len).sum::<usize>()
+            + info.return_type.as_deref().map_or(0, type_bytes)
+            + info.param_types.iter().map(type_bytes).sum::<usize>()
+    }
+    symbol.documentation.as_ref().map_or(0, String::len) + symbol.type_info.as_ref().map_or(0, type_bytes)
+}
+
+/// Order symbols by location, then name
+fn sort_symbols(symbols: &mut [SmartSymbol]) {
+    symbols.sort_by(|a, b| a.location.cmp(&b.location).then_with(|| a.name.cmp(&b.name)));
 }
 
 impl Default for SymbolTable {
@@ -368,20 +703,14 @@ impl CallGraph {
             .insert(call);
     }
 
-    /// Get all functions called by a function
+    /// Get all functions called by a function, by call site location
     pub fn get_callees(&self, caller: SymbolId) -> Vec<CallSite> {
-        self.callers
-            .get(&caller)
-            .map(|calls| calls.iter().cloned().collect())
-            .unwrap_or_default()
+        sorted_calls(self.callers.get(&caller).map(|calls| calls.iter().cloned().collect()))
     }
 
-    /// Get all functions that call a function
+    /// Get all functions that call a function, by call site location
     pub fn get_callers(&self, callee: SymbolId) -> Vec<CallSite> {
-        self.callees
-            .get(&callee)
-            .map(|calls| calls.iter().cloned().collect())
-            .unwrap_or_default()
+        sorted_calls(self.callees.get(&callee).map(|calls| calls.iter().cloned().collect()))
     }
 
     /// Remove all calls from a file
@@ -395,6 +724,35 @@ impl CallGraph {
         }
     }
 
+    /// Move the calls made in a file from `from` to `to`, and the calls
+    /// of the symbols whose ids changed to their new ids
+    pub fn rename_file(&self, from: &str, to: &str, remap: &HashMap<SymbolId, SymbolId>) {
+        let rename = |mut call: CallSite| {
+            call.caller = remap.get(&call.caller).copied().unwrap_or(call.caller);
+            call.callee = remap.get(&call.callee).copied().unwrap_or(call.callee);
+            if call.location.uri == from {
+                call.location.uri = to.to_string();
+            }
+            call
+        };
+        for map in [&self.callers, &self.callees] {
+            rekey(map, remap);
+            for mut entry in map.iter_mut() {
+                let affected = |call: &CallSite| {
+                    call.location.uri == from || remap.contains_key(&call.caller) || remap.contains_key(&call.callee)
+                };
+                if entry.iter().any(affected) {
+                    *entry = entry.drain().map(rename).collect();
+                }
+            }
+        }
+    }
+
+    /// All call sites, in no particular order
+    pub fn all(&self) -> Vec<CallSite> {
+        self.callers.iter().flat_map(|calls| calls.iter().cloned().collect::<Vec<_>>()).collect()
+    }
+
     /// Get the total number of call sites
     pub fn len(&self) -> usize {
         self.callers.iter().map(|e| e.len()).sum()
@@ -406,6 +764,43 @@ impl CallGraph {
     }
 }
 
+fn sorted_calls(calls: Option<Vec<CallSite>>) -> Vec<CallSite> {
+    let mut calls = calls.unwrap_or_default();
+    calls.sort_by(|a, b| a.location.cmp(&b.location).then_with(|| (a.caller.0, a.callee.0).cmp(&(b.caller.0, b.callee.0))));
+    calls
+}
+
+/// Ids analyzing `symbols`, read from `from`, at `to` would give them, by
+/// their current ids. Only stable ids change; the disambiguator of each is
+/// found again by rederiving its id under `from`.
+fn rebased_ids(symbols: &[SmartSymbol], from: &str, to: &str) -> HashMap<SymbolId, SymbolId> {
+    let mut counts: HashMap<(&str, SymbolKind), u32> = HashMap::new();
+    for symbol in symbols {
+        *counts.entry((symbol.qualified_name.as_str(), symbol.kind)).or_default() += 1;
+    }
+    symbols
+        .iter()
+        .filter(|symbol| symbol.id.is_stable())
+        .filter_map(|symbol| {
+            let count = counts[&(symbol.qualified_name.as_str(), symbol.kind)];
+            let disambiguator = (0..count)
+                .find(|&d| SymbolId::stable(from, &symbol.qualified_name, symbol.kind, d) == symbol.id)?;
+            Some((symbol.id, SymbolId::stable(to, &symbol.qualified_name, symbol.kind, disambiguator)))
+        })
+        .collect()
+}
+
+/// Move the entries of `map` under the ids they were remapped to
+fn rekey<V>(map: &DashMap<SymbolId, V>, remap: &HashMap<SymbolId, SymbolId>) {
+    let moved: Vec<(SymbolId, V)> = remap
+        .iter()
+        .filter_map(|(old, new)| map.remove(old).map(|(_, value)| (*new, value)))
+        .collect();
+    for (id, value) in moved {
+        map.insert(id, value);
+    }
+}
+
 impl Default for CallGraph {
     fn default() -> Self {
         Self::new()
@@ -434,7 +829,13 @@ impl PartialEq for CallSite {
 
 impl Eq for CallSite {}
 
-/// Type hierarchy for tracking inheritance
+/// Type hierarchy for tracking inheritance.
+///
+/// Relations link symbol IDs, which change when a file is re-indexed. A
+/// relation whose parent isn't indexed, yet or any more, is kept by the
+/// parent's name until a type of that name is indexed again (see
+/// [`Self::link_unresolved`]), so the hierarchy doesn't depend on the
+/// order files are indexed in.
 pub struct TypeHierarchy {
     /// Supertype relationships: subtype -> supertypes
     supertypes: DashMap<SymbolId, Vec<SymbolId>>,
@@ -444,6 +845,9 @@ pub struct TypeHierarchy {
     implements: DashMap<SymbolId, Vec<SymbolId>>,
     /// Interface implementors: interface -> implementors
     implementors: DashMap<SymbolId, Vec<SymbolId>>,
+    /// Relations to a parent not indexed: parent name -> (child, whether
+    /// the child implements the parent)
+    unresolved: DashMap<String, Vec<(SymbolId, bool)>>,
 }
 
 impl TypeHierarchy {
@@ -453,19 +857,120 @@ impl TypeHierarchy {
             subtypes: DashMap::new(),
             implements: DashMap::new(),
             implementors: DashMap::new(),
+            unresolved: DashMap::new(),
         }
     }
 
     /// Add an inheritance relationship
     pub fn add_extends(&self, subtype: SymbolId, supertype: SymbolId) {
-        self.supertypes.entry(subtype).or_default().push(supertype);
-        self.subtypes.entry(supertype).or_default().push(subtype);
+        link(&self.supertypes, &self.subtypes, subtype, supertype);
     }
 
     /// Add an implementation relationship
     pub fn add_implements(&self, implementor: SymbolId, interface: SymbolId) {
-        self.implements.entry(implementor).or_default().push(interface);
-        self.implementors.entry(interface).or_default().push(implementor);
+        link(&self.implements, &self.implementors, implementor, interface);
+    }
+
+    /// Add a relationship of `child` to a parent named `parent_name` that
+    /// isn't indexed
+    pub fn add_unresolved(&self, child: SymbolId, parent_name: &str, is_implements: bool) {
+        let mut pending = self.unresolved.entry(parent_name.to_string()).or_default();
+        if !pending.contains(&(child, is_implements)) {
+            pending.push((child, is_implements));
+        }
+    }
+
+    /// Link the relations waiting for a parent named `name` to the newly
+    /// indexed type `parent`
+    pub fn link_unresolved(&self, name: &str, parent: SymbolId) {
+        if let Some((_, pending)) = self.unresolved.remove(name) {
+            for (child, is_implements) in pending {
+                if is_implements {
+                    self.add_implements(child, parent);
+                } else {
+                    self.add_extends(child, parent);
+                }
+            }
+        }
+    }
+
+    /// Drop the types `removed`, given with their names. Their relations
+    /// to their parents go; those of their remaining children wait for a
+    /// type of the same name.
+    pub fn remove_types(&self, removed: &[(SymbolId, String)]) {
+        let ids: HashSet<SymbolId> = removed.iter().map(|(id, _)| *id).collect();
+        for (id, name) in removed {
+            for (up, down, is_implements) in [(&self.supertypes, &self.subtypes, false), (&self.implements, &self.implementors, true)] {
+                if let Some((_, parents)) = up.remove(id) {
+                    for parent in parents {
+                        if let Some(mut children) = down.get_mut(&parent) {
+                            children.retain(|child| child != id);
+                        }
+                    }
+                }
+                if let Some((_, children)) = down.remove(id) {
+                    for child in children.into_iter().filter(|child| !ids.contains(child)) {
+                        if let Some(mut parents) = up.get_mut(&child) {
+                            parents.retain(|parent| parent != id);
+                        }
+                        self.add_unresolved(child, name, is_implements);
+                    }
+                }
+            }
+        }
+        self.unresolved.retain(|_, pending| {
+            pending.retain(|(child, _)| !ids.contains(child));
+            !pending.is_empty()
+        });
+    }
+
+    /// Follow symbols whose ids changed to their new ids
+    pub fn remap_ids(&self, remap: &HashMap<SymbolId, SymbolId>) {
+        let new_id = |id: &mut SymbolId| {
+            if let Some(new) = remap.get(id) {
+                *id = *new;
+            }
+        };
+        for map in [&self.supertypes, &self.subtypes, &self.implements, &self.implementors] {
+            rekey(map, remap);
+            for mut ids in map.iter_mut() {
+                ids.iter_mut().for_each(new_id);
+            }
+        }
+        for mut pending in self.unresolved.iter_mut() {
+            pending.iter_mut().for_each(|(child, _)| new_id(child));
+        }
+    }
+
+    /// Types `type_id` extends or implements directly
+    pub fn direct_supertypes(&self, type_id: SymbolId) -> Vec<SymbolId> {
+        let mut parents = self.get_supertypes(type_id);
+        parents.extend(self.get_interfaces(type_id));
+        parents
+    }
+
+    /// Types extending or implementing `type_id` directly
+    pub fn direct_subtypes(&self, type_id: SymbolId) -> Vec<SymbolId> {
+        let mut children = self.get_subtypes(type_id);
+        children.extend(self.get_implementors(type_id));
+        children
+    }
+
+    /// Types extending or implementing `type_id`, directly or not, nearest
+    /// first
+    pub fn all_subtypes(&self, type_id: SymbolId) -> Vec<SymbolId> {
+        let mut seen = HashSet::from([type_id]);
+        let mut found = Vec::new();
+        let mut queue = VecDeque::from([type_id]);
+        while let Some(current) = queue.pop_front() {
+            for child in self.direct_subtypes(current) {
+                if seen.insert(child) {
+                    found.push(child);
+                    queue.push_back(child);
+                }
+            }
+        }
+        found
     }
 
     /// Get all supertypes of a type
@@ -499,6 +1004,34 @@ impl TypeHierarchy {
             .map(|v| v.clone())
             .unwrap_or_default()
     }
+
+    /// All `(subtype, supertype)` pairs
+    pub fn extends_edges(&self) -> Vec<(SymbolId, SymbolId)> {
+        edges(&self.supertypes)
+    }
+
+    /// All `(implementor, interface)` pairs
+    pub fn implements_edges(&self) -> Vec<(SymbolId, SymbolId)> {
+        edges(&self.implements)
+    }
+}
+
+/// Record `child -> parent` in `up` and `parent -> child` in `down`, once
+fn link(up: &DashMap<SymbolId, Vec<SymbolId>>, down: &DashMap<SymbolId, Vec<SymbolId>>, child: SymbolId, parent: SymbolId) {
+    let mut parents = up.entry(child).or_default();
+    if !parents.contains(&parent) {
+        parents.push(parent);
+        down.entry(parent).or_default().push(child);
+    }
+}
+
+fn edges(map: &DashMap<SymbolId, Vec<SymbolId>>) -> Vec<(SymbolId, SymbolId)> {
+    map.iter()
+        .flat_map(|entry| {
+            let from = *entry.key();
+            entry.value().iter().map(move |to| (from, *to)).collect::<Vec<_>>()
+        })
+        .collect()
 }
 
 impl Default for TypeHierarchy {
@@ -537,20 +1070,14 @@ impl DependencyGraph {
         self.exports.insert(file, symbols);
     }
 
-    /// Get files imported by a file
+    /// Get files imported by a file, sorted
     pub fn get_imports(&self, file: &PathBuf) -> Vec<PathBuf> {
-        self.imports
-            .get(file)
-            .map(|v| v.iter().cloned().collect())
-            .unwrap_or_default()
+        sorted_paths(self.imports.get(file).map(|v| v.iter().cloned().collect()))
     }
 
-    /// Get files that import a file
+    /// Get files that import a file, sorted
     pub fn get_importers(&self, file: &PathBuf) -> Vec<PathBuf> {
-        self.imported_by
-            .get(file)
-            .map(|v| v.iter().cloned().collect())
-            .unwrap_or_default()
+        sorted_paths(self.imported_by.get(file).map(|v| v.iter().cloned().collect()))
     }
 
     /// Get exported symbols from a file
@@ -585,18 +1112,138 @@ impl DependencyGraph {
         self.exports.remove(file);
     }
 
+    /// Move a file of the graph from `from` to `to`, with its imports in
+    /// both directions and its exports, whose ids may have changed
+    pub fn rename_file(&self, from: &Path, to: &Path, remap: &HashMap<SymbolId, SymbolId>) {
+        let rename = |path: PathBuf| if path == from { to.to_path_buf() } else { path };
+        if let Some((_, imported)) = self.imports.remove(from) {
+            for imported_file in &imported {
+                if let Some(mut entry) = self.imported_by.get_mut(imported_file) {
+                    entry.remove(from);
+                    entry.insert(to.to_path_buf());
+                }
+            }
+            self.imports.insert(to.to_path_buf(), imported.into_iter().map(rename).collect());
+        }
+        if let Some((_, importers)) = self.imported_by.remove(from) {
+            for importer in &importers {
+                if let Some(mut entry) = self.imports.get_mut(importer) {
+                    entry.remove(from);
+                    entry.insert(to.to_path_buf());
+                }
+            }
+            self.imported_by.insert(to.to_path_buf(), importers.into_iter().map(rename).collect());
+        }
+        if let Some((_, mut exports)) = self.exports.remove(from) {
+            exports.iter_mut().for_each(|id| *id = remap.get(id).copied().unwrap_or(*id));
+            self.exports.insert(to.to_path_buf(), exports);
+        }
+    }
+
+    /// All `(importer, imported)` file pairs
+    pub fn import_edges(&self) -> Vec<(PathBuf, PathBuf)> {
+        self.imports
+            .iter()
+            .flat_map(|entry| {
+                let from = entry.key().clone();
+                entry.value().iter().map(move |to| (from.clone(), to.clone())).collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Exported symbols of every file
+    pub fn all_exports(&self) -> Vec<(PathBuf, Vec<SymbolId>)> {
+        self.exports.iter().map(|e| (e.key().clone(), e.value().clone())).collect()
+    }
+
+    /// Files reachable from `file` through imports, with the number of
+    /// import hops to each; `file` itself is at distance 0
+    pub fn import_distances(&self, file: &Path) -> HashMap<PathBuf, usize> {
+        let mut distances = HashMap::from([(file.to_path_buf(), 0)]);
+        let mut pending = VecDeque::from([file.to_path_buf()]);
+        while let Some(current) = pending.pop_front() {
+            let distance = distances[&current] + 1;
+            for imported in self.get_imports(&current) {
+                if !distances.contains_key(&imported) {
+                    distances.insert(imported.clone(), distance);
+                    pending.push_back(imported);
+                }
+            }
+        }
+        distances
+    }
+
     /// Get the number of indexed files
     pub fn file_count(&self) -> usize {
         self.exports.len()
     }
 }
 
+fn sorted_paths(paths: Option<Vec<PathBuf>>) -> Vec<PathBuf> {
+    let mut paths = paths.unwrap_or_default();
+    paths.sort();
+    paths
+}
+
 impl Default for DependencyGraph {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// What an indexed file was analyzed from, to tell whether it needs
+/// analyzing again
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileRecord {
+    /// blake3 hash of the content, in hex
+    pub hash: String,
+    /// Modification time in milliseconds since the Unix epoch, when the
+    /// filesystem tracks one
+    pub modified: Option<u64>,
+    /// Language id of the adapter that analyzed the file
+    pub language: String,
+    /// Version of that adapter's extraction logic
+    pub adapter_version: String,
+}
+
+impl FileRecord {
+    pub fn new(content: &str, modified: Option<SystemTime>, language: &str, adapter_version: &str) -> Self {
+        Self {
+            hash: blake3::hash(content.as_bytes()).to_hex().to_string(),
+            modified: modified.map(millis_since_epoch),
+            language: language.to_string(),
+            adapter_version: adapter_version.to_string(),
+        }
+    }
+
+    /// Whether a file modified at `modified` is still the one recorded.
+    /// Without a modification time, only the content can tell.
+    pub fn is_unmodified(&self, modified: Option<SystemTime>) -> bool {
+        self.modified.is_some() && self.modified == modified.map(millis_since_epoch)
+    }
+
+    /// Record that the file, unchanged, was last modified at `modified`
+    pub fn set_modified(&mut self, modified: Option<SystemTime>) {
+        self.modified = modified.map(millis_since_epoch);
+    }
+
+    /// Whether the file was analyzed from `content`
+    pub fn has_content(&self, content: &str) -> bool {
+        self.hash == blake3::hash(content.as_bytes()).to_hex().as_str()
+    }
+
+    /// Whether the adapter for `language` at `adapter_version` would
+    /// extract what the file was indexed with
+    pub fn is_current(&self, language: &str, adapter_version: &str) -> bool {
+        self.language == language && self.adapter_version == adapter_version
+    }
+}
+
+fn millis_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
 /// Combined project index containing all index structures
 pub struct ProjectIndex {
     /// Symbol table
@@ -607,6 +1254,8 @@ pub struct ProjectIndex {
     pub type_hierarchy: Arc<TypeHierarchy>,
     /// Dependency graph
     pub dependencies: Arc<DependencyGraph>,
+    /// What each indexed file was analyzed from, keyed by URI
+    pub files: DashMap<String, FileRecord>,
 }
 
 impl ProjectIndex {
@@ -616,15 +1265,144 @@ impl ProjectIndex {
             call_graph: Arc::new(CallGraph::new()),
             type_hierarchy: Arc::new(TypeHierarchy::new()),
             dependencies: Arc::new(DependencyGraph::new()),
+            files: DashMap::new(),
         }
     }
 
     /// Remove all data for a file (for incremental updates)
     pub fn remove_file(&self, uri: &str) {
+        let types: Vec<(SymbolId, String)> = self
+            .symbols
+            .stored_file_symbols(uri)
+            .into_iter()
+            .filter(|s| is_type_kind(s.kind))
+            .map(|s| (s.id, s.name))
+            .collect();
+        self.type_hierarchy.remove_types(&types);
         self.symbols.remove_file(uri);
         self.call_graph.remove_file(uri);
-        self.dependencies.remove_file(&PathBuf::from(uri));
+        if let Some(path) = uri::uri_to_path(uri) {
+            self.dependencies.remove_file(&path);
+        }
+        self.files.remove(uri);
+    }
+
+    /// Move all data of a file from `from` to `to` without analyzing it
+    /// again. Its symbols get the ids analyzing it at `to` would give.
+    pub fn rename_file(&self, from: &str, to: &str) {
+        let remap = self.symbols.rename_file(from, to);
+        self.call_graph.rename_file(from, to, &remap);
+        self.type_hierarchy.remap_ids(&remap);
+        if let (Some(old), Some(new)) = (uri::uri_to_path(from), uri::uri_to_path(to)) {
+            self.dependencies.rename_file(&old, &new, &remap);
+        }
+        if let Some((_, record)) = self.files.remove(from) {
+            self.files.insert(to.to_string(), record);
+        }
+    }
+
+    /// Symbols named `name`, best match for a reference in `from_uri` first:
+    /// declarations in that file, then in the files it imports (nearest
+    /// first), then the rest of the workspace. At the same distance, archive
+    /// entries and declarations in generated files come last.
+    pub fn resolve_name(&self, name: &str, from_uri: &str) -> Vec<SmartSymbol> {
+        let distances = uri::uri_to_path(from_uri)
+            .map(|path| self.dependencies.import_distances(&path))
+            .unwrap_or_default();
+        let mut candidates = self.symbols.find_by_name(name);
+        candidates.sort_by_cached_key(|symbol| {
+            let uri = &symbol.location.uri;
+            let distance = if uri == from_uri {
+                0
+            } else {
+                uri::uri_to_path(uri)
+                    .and_then(|path| distances.get(&path).copied())
+                    .unwrap_or(usize::MAX)
+            };
+            (distance, uri.starts_with(ARCHIVE_SCHEME), symbol.is_generated(), uri.clone())
+        });
+        candidates
     }
+
+    /// Whether `symbol` may be referenced from `position` in `uri`.
+    ///
+    /// Private members are visible inside their owning type, protected ones
+    /// also inside its subtypes. Private or protected symbols without an
+    /// owner are file-local.
+    pub fn is_accessible(&self, symbol: &SmartSymbol, uri: &str, position: Position) -> bool {
+        if matches!(symbol.visibility, Visibility::Public | Visibility::Internal) {
+            return true;
+        }
+        let Some(owner) = symbol.parent.and_then(|id| self.symbols.get(id)) else {
+            return symbol.location.uri == uri;
+        };
+        if owner.location.uri == uri && owner.location.range.contains(position) {
+            return true;
+        }
+        symbol.visibility == Visibility::Protected
+            && self
+                .symbols
+                .get_file_symbols(uri)
+                .iter()
+                .filter(|s| is_type_kind(s.kind) && s.location.range.contains(position))
+                .any(|s| self.is_subtype_of(s.id, owner.id))
+    }
+
+    /// Types `type_id` extends or implements directly, in location order
+    pub fn supertypes(&self, type_id: SymbolId) -> Vec<SmartSymbol> {
+        self.symbols_of(self.type_hierarchy.direct_supertypes(type_id))
+    }
+
+    /// Types extending or implementing `type_id` directly, in location
+    /// order
+    pub fn subtypes(&self, type_id: SymbolId) -> Vec<SmartSymbol> {
+        self.symbols_of(self.type_hierarchy.direct_subtypes(type_id))
+    }
+
+    /// Implementations of `type_id`: the types extending or implementing
+    /// it across the workspace, directly or not, in location order
+    pub fn implementations(&self, type_id: SymbolId) -> Vec<SmartSymbol> {
+        self.symbols_of(self.type_hierarchy.all_subtypes(type_id))
+    }
+
+    fn symbols_of(&self, ids: Vec<SymbolId>) -> Vec<SmartSymbol> {
+        let mut symbols: Vec<SmartSymbol> = ids.into_iter().filter_map(|id| self.symbols.get(id)).collect();
+        sort_symbols(&mut symbols);
+        symbols.dedup_by_key(|s| s.id);
+        symbols
+    }
+
+    /// Whether `ty` extends or implements `ancestor`, directly or not
+    fn is_subtype_of(&self, ty: SymbolId, ancestor: SymbolId) -> bool {
+        let mut seen = HashSet::new();
+        let mut pending = vec![ty];
+        while let Some(current) = pending.pop() {
+            if !seen.insert(current) {
+                continue;
+            }
+            for parent in self
+                .type_hierarchy
+                .get_supertypes(current)
+                .into_iter()
+                .chain(self.type_hierarchy.get_interfaces(current))
+            {
+                if parent == ancestor {
+                    return true;
+                }
+                pending.push(parent);
+            }
+        }
+        false
+    }
+}
+
+/// Whether symbols of `kind` declare a type that can take part in the
+/// type hierarchy
+pub fn is_type_kind(kind: SymbolKind) -> bool {
+    matches!(
+        kind,
+        SymbolKind::Class | SymbolKind::Struct | SymbolKind::Interface | SymbolKind::Enum
+    )
 }
 
 impl Default for ProjectIndex {
@@ -680,6 +1458,116 @@ mod tests {
         // Test search
         let searched = table.search("fo");
         assert_eq!(searched.len(), 1);
+
+        // Test stats
+        assert_eq!(table.count_by_kind().get(&SymbolKind::Function), Some(&1));
+        assert!(table.estimated_memory_bytes() >= std::mem::size_of::<SmartSymbol>());
+    }
+
+    #[test]
+    fn test_stable_ids() {
+        let id = SymbolId::stable("file:///a.ts", "Shape.area", SymbolKind::Method, 0);
+        assert_eq!(id, SymbolId::stable("file:///a.ts", "Shape.area", SymbolKind::Method, 0));
+        assert_ne!(id, SymbolId::stable("file:///a.ts", "Shape.area", SymbolKind::Method, 1));
+        assert_ne!(id, SymbolId::stable("file:///b.ts", "Shape.area", SymbolKind::Method, 0));
+        assert_ne!(id, SymbolId::stable("file:///a.ts", "Shape.area", SymbolKind::Property, 0));
+        assert!(id.is_stable() && id.0 < 1 << 53);
+        assert!(!SymbolId::new().is_stable());
+    }
+
+    #[test]
+    fn test_detail_budget_reads_back_evicted_details() {
+        use crate::adapter::{make_location, SymbolBuilder};
+
+        let symbol = |uri: &str, name: &str| {
+            SymbolBuilder::new(name, SymbolKind::Function, make_location(uri, Range::default(), Range::default()))
+                .type_info(TypeInfo::simple("() -> int"))
+                .documentation("Returns one")
+                .build()
+        };
+        let table = SymbolTable::new();
+        let a = table.add_symbol(symbol("file:///a.py", "a"));
+        table.add_symbol(symbol("file:///b.py", "b"));
+
+        let read_back = Arc::new(Mutex::new(Vec::new()));
+        let hydrate: Hydrator = {
+            let read_back = read_back.clone();
+            Arc::new(move |uri: &str| {
+                read_back.lock().unwrap().push(uri.to_string());
+                let name = if uri == "file:///a.py" { "a" } else { "b" };
+                Some(vec![symbol(uri, name)])
+            })
+        };
+        // Room for the details of one file: the older one goes
+        table.set_detail_budget(Some(details_size(&symbol("file:///a.py", "a"))), hydrate);
+        assert_eq!(table.evicted_files(), 1);
+        assert!(table.find_by_name("a")[0].type_info.is_none());
+        assert!(table.find_by_name("b")[0].type_info.is_some());
+
+        let symbol = table.get(a).unwrap();
+        assert_eq!(symbol.type_info.map(|t| t.type_expr), Some("() -> int".to_string()));
+        assert_eq!(symbol.documentation.as_deref(), Some("Returns one"));
+        assert_eq!(*read_back.lock().unwrap(), vec!["file:///a.py"]);
+        // Reading `a` back pushed `b` out
+        assert!(table.find_by_name("b")[0].type_info.is_none());
+        assert_eq!(table.evicted_files(), 1);
+    }
+
+    #[test]
+    fn test_resolve_name_prefers_imports() {
+        use crate::adapter::{make_location, SymbolBuilder};
+
+        let index = ProjectIndex::new();
+        let range = Range::default();
+        for uri in ["file:///p/a.py", "file:///p/b.py", "file:///p/c.py", "file:///p/z.py"] {
+            index.symbols.add_symbol(SymbolBuilder::new("helper", SymbolKind::Function, make_location(uri, range, range)).build());
+        }
+        // main -> c -> b; a and z are unrelated
+        index.dependencies.add_import(PathBuf::from("/p/main.py"), PathBuf::from("/p/c.py"));
+        index.dependencies.add_import(PathBuf::from("/p/c.py"), PathBuf::from("/p/b.py"));
+
+        let from_main: Vec<_> = index
+            .resolve_name("helper", "file:///p/main.py")
+            .into_iter()
+            .map(|s| s.location.uri)
+            .collect();
+        assert_eq!(from_main, vec!["file:///p/c.py", "file:///p/b.py", "file:///p/a.py", "file:///p/z.py"]);
+        assert_eq!(index.resolve_name("helper", "file:///p/z.py")[0].location.uri, "file:///p/z.py");
+    }
+
+    #[test]
+    fn test_is_accessible() {
+        use crate::adapter::{make_location, SymbolBuilder};
+
+        let range = |start: u32, end: u32| Range {
+            start: Position { line: start, column: 0 },
+            end: Position { line: end, column: 0 },
+        };
+        let index = ProjectIndex::new();
+        let base = SymbolBuilder::new("Base", SymbolKind::Class, make_location("file:///a.java", range(0, 10), range(0, 1))).build();
+        let secret = SymbolBuilder::new("secret", SymbolKind::Field, make_location("file:///a.java", range(1, 2), range(1, 2)))
+            .parent(base.id)
+            .visibility(Visibility::Private)
+            .build();
+        let shared = SymbolBuilder::new("shared", SymbolKind::Field, make_location("file:///a.java", range(2, 3), range(2, 3)))
+            .parent(base.id)
+            .visibility(Visibility::Protected)
+            .build();
+        let derived = SymbolBuilder::new("Derived", SymbolKind::Class, make_location("file:///b.java", range(0, 10), range(0, 1))).build();
+        let other = SymbolBuilder::new("Other", SymbolKind::Class, make_location("file:///b.java", range(20, 30), range(20, 21))).build();
+        index.type_hierarchy.add_extends(derived.id, base.id);
+        for symbol in [&base, &secret, &shared, &derived, &other] {
+            index.symbols.add_symbol(symbol.clone());
+        }
+
+        let inside_base = Position { line: 5, column: 0 };
+        let inside_derived = Position { line: 5, column: 0 };
+        let inside_other = Position { line: 25, column: 0 };
+        assert!(index.is_accessible(&secret, "file:///a.java", inside_base));
+        assert!(!index.is_accessible(&secret, "file:///b.java", inside_derived));
+        assert!(index.is_accessible(&shared, "file:///b.java", inside_derived));
+        assert!(!index.is_accessible(&shared, "file:///b.java", inside_other));
+        assert!(index.is_accessible(&base, "file:///b.java", inside_other));
     }
 
     #[test]
@@ -714,4 +1602,26 @@ mod tests {
         let callers = graph.get_callers(callee);
         assert_eq!(callers.len(), 1);
     }
+
+    #[test]
+    fn test_type_hierarchy() {
+        let hierarchy = TypeHierarchy::new();
+        let (shape, polygon, square, drawable) = (SymbolId::new(), SymbolId::new(), SymbolId::new(), SymbolId::new());
+        hierarchy.add_extends(polygon, shape);
+        hierarchy.add_extends(square, polygon);
+        hierarchy.add_extends(square, polygon);
+        hierarchy.add_implements(square, drawable);
+
+        assert_eq!(hierarchy.direct_supertypes(square), vec![polygon, drawable]);
+        assert_eq!(hierarchy.direct_subtypes(polygon), vec![square]);
+        assert_eq!(hierarchy.all_subtypes(shape), vec![polygon, square]);
+
+        // A removed parent's children wait for a type of its name
+        hierarchy.remove_types(&[(polygon, "Polygon".to_string())]);
+        assert!(hierarchy.all_subtypes(shape).is_empty());
+        assert_eq!(hierarchy.direct_supertypes(square), vec![drawable]);
+        let polygon = SymbolId::new();
+        hierarchy.link_unresolved("Polygon", polygon);
+        assert_eq!(hierarchy.direct_supertypes(square), vec![polygon, drawable]);
+    }
 }
@@ -0,0 +1,152 @@
+//! Tags file export
+//!
+//! Writes the symbol table in the extended format of universal-ctags, so
+//! editors and tools that read tags files get the symbols our extractors
+//! find. Each entry addresses its symbol by line number and carries
+//! `kind:`, `line:` and, for nested symbols, a scope field named by the
+//! parent's kind (`class:Shape`). Entries are sorted by name so readers can
+//! binary search them.
+
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use logos_core::uri::uri_to_path;
+use logos_core::SymbolKind;
+
+use crate::symbol_table::{SmartSymbol, SymbolTable};
+
+const PROGRAM_NAME: &str = "logos-daemon";
+
+/// One line of a tags file
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct TagEntry {
+    name: String,
+    path: String,
+    line: u32,
+    kind: &'static str,
+    scope: Option<(&'static str, String)>,
+}
+
+/// Write every symbol of `symbols` as a tags file. Paths are made relative
+/// to `base`, the directory the tags file lives in, when they're under it.
+/// Returns the number of entries written.
+pub fn write_tags<W: Write>(symbols: &SymbolTable, base: &Path, out: &mut W) -> io::Result<usize> {
+    let mut entries: Vec<TagEntry> = symbols
+        .all()
+        .iter()
+        .filter_map(|symbol| tag_entry(symbols, symbol, base))
+        .collect();
+    entries.sort();
+    entries.dedup();
+
+    writeln!(out, "!_TAG_FILE_FORMAT\t2\t/extended format; --format=1 will not append ;\" to lines/")?;
+    writeln!(out, "!_TAG_FILE_SORTED\t1\t/0=unsorted, 1=sorted, 2=foldcase/")?;
+    writeln!(out, "!_TAG_PROGRAM_NAME\t{}\t//", PROGRAM_NAME)?;
+    writeln!(out, "!_TAG_PROGRAM_VERSION\t{}\t//", env!("CARGO_PKG_VERSION"))?;
+    for entry in &entries {
+        write!(out, "{}\t{}\t{};\"\tkind:{}\tline:{}", entry.name, entry.path, entry.line, entry.kind, entry.line)?;
+        if let Some((scope_kind, scope)) = &entry.scope {
+            write!(out, "\t{}:{}", scope_kind, scope)?;
+        }
+        writeln!(out)?;
+    }
+    Ok(entries.len())
+}
+
+fn tag_entry(symbols: &SymbolTable, symbol: &SmartSymbol, base: &Path) -> Option<TagEntry> {
+    // Tabs and newlines would break the line format
+    if symbol.name.is_empty() || symbol.name.contains(['\t', '\n', '\r']) {
+        return None;
+    }
+    let path = uri_to_path(&symbol.location.uri)?;
+    let path = relative_path(&path, base);
+    let path = path.to_str().filter(|p| !p.contains(['\t', '\n', '\r']))?.to_string();
+    let scope = symbol
+        .parent
+        .and_then(|id| symbols.get(id))
+        .map(|parent| (ctags_kind(parent.kind), parent.name));
+    Some(TagEntry {
+        name: symbol.name.clone(),
+        path,
+        line: symbol.location.selection_range.start.line + 1,
+        kind: ctags_kind(symbol.kind),
+        scope,
+    })
+}
+
+fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    path.strip_prefix(base).map_or_else(|_| path.to_path_buf(), Path::to_path_buf)
+}
+
+/// Long kind names as universal-ctags uses them across its parsers
+fn ctags_kind(kind: SymbolKind) -> &'static str {
+    match kind {
+        SymbolKind::File => "file",
+        SymbolKind::Module => "module",
+        SymbolKind::Namespace => "namespace",
+        SymbolKind::Package => "package",
+        SymbolKind::Class => "class",
+        SymbolKind::Method | SymbolKind::Constructor => "method",
+        SymbolKind::Property => "property",
+        SymbolKind::Field => "member",
+        SymbolKind::Enum => "enum",
+        SymbolKind::Interface => "interface",
+        SymbolKind::Function | SymbolKind::Operator => "function",
+        SymbolKind::Variable => "variable",
+        SymbolKind::Constant => "constant",
+        SymbolKind::EnumMember => "enumerator",
+        SymbolKind::Struct => "struct",
+        SymbolKind::Event => "event",
+        SymbolKind::TypeParameter => "typeparam",
+        SymbolKind::String
+        | SymbolKind::Number
+        | SymbolKind::Boolean
+        | SymbolKind::Array
+        | SymbolKind::Object
+        | SymbolKind::Key
+        | SymbolKind::Null => "key",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::indexer::ProjectIndexer;
+    use std::fs;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_tags_are_sorted_with_scopes() {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("shapes.py"),
+            "class Shape:\n    def area(self):\n        return 0\n\ndef build():\n    pass\n",
+        )
+        .unwrap();
+        let indexer = ProjectIndexer::new();
+        indexer.index_directory(dir.path()).unwrap();
+
+        let mut out = Vec::new();
+        let count = write_tags(&indexer.get_index().symbols, dir.path(), &mut out).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let entries: Vec<&str> = text.lines().filter(|l| !l.starts_with("!_TAG_")).collect();
+
+        assert_eq!(count, entries.len());
+        assert!(text.starts_with("!_TAG_FILE_FORMAT\t2\t"));
+        assert_eq!(
+            entries,
+            vec![
+                "Shape\tshapes.py\t1;\"\tkind:class\tline:1",
+                "area\tshapes.py\t2;\"\tkind:method\tline:2\tclass:Shape",
+                "build\tshapes.py\t5;\"\tkind:function\tline:5",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_paths_outside_base_stay_absolute() {
+        let base = Path::new("/work/project");
+        assert_eq!(relative_path(Path::new("/work/project/src/a.rs"), base), PathBuf::from("src/a.rs"));
+        assert_eq!(relative_path(Path::new("/elsewhere/b.rs"), base), PathBuf::from("/elsewhere/b.rs"));
+    }
+}
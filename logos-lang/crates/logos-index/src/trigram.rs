@@ -0,0 +1,417 @@
+//! Full-text search over indexed documents
+//!
+//! Each document's text is kept along with the set of trigrams (three
+//! byte windows, ASCII letters lowercased) it contains. A query is reduced
+//! to the literal text every match must contain — the whole query in
+//! literal mode, the runs of plain characters outside groups in regex
+//! mode — and only documents holding all of its trigrams are searched.
+//! A query with no usable trigram (shorter than three bytes, or a regex
+//! made of classes and alternations) searches every document.
+//!
+//! Queries are compiled as for [`workspace_replace`](crate::workspace_replace),
+//! with the same options; include and exclude globs match file paths.
+
+use std::collections::{HashMap, HashSet};
+
+use logos_core::uri::uri_to_path;
+use logos_core::{Document, Range};
+
+use crate::glob::PathGlob;
+use crate::rebase_uri;
+use crate::replace::{compile_query, ReplaceOptions};
+
+type Trigram = [u8; 3];
+
+/// One match of a text search
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextMatch {
+    pub range: Range,
+    /// Text of the line the match starts on, without its line break
+    pub line_text: String,
+}
+
+/// Matches in one document, in document order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileMatches {
+    pub uri: String,
+    pub matches: Vec<TextMatch>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TextSearchResult {
+    /// Documents with at least one match, sorted by URI
+    pub files: Vec<FileMatches>,
+    /// Whether the search stopped at `max_matches`
+    pub truncated: bool,
+}
+
+impl TextSearchResult {
+    pub fn match_count(&self) -> usize {
+        self.files.iter().map(|file| file.matches.len()).sum()
+    }
+}
+
+struct IndexedText {
+    text: String,
+    trigrams: HashSet<Trigram>,
+}
+
+#[derive(Default)]
+pub struct TrigramIndex {
+    documents: HashMap<String, IndexedText>,
+    /// Documents containing each trigram
+    postings: HashMap<Trigram, HashSet<String>>,
+}
+
+impl TrigramIndex {
+    pub fn new() -> Self { Self::default() }
+
+    /// Index `text` as the content of `uri`, replacing what was indexed
+    /// for it. Binary text (with a NUL byte) is not indexed.
+    pub fn index_document(&mut self, uri: &str, text: &str) {
+        if text.contains('\0') {
+            self.remove_document(uri);
+            return;
+        }
+        let trigrams = trigrams(text.as_bytes(), false);
+        let old = self.documents.remove(uri).map(|doc| doc.trigrams).unwrap_or_default();
+        for trigram in old.difference(&trigrams) {
+            self.remove_posting(trigram, uri);
+        }
+        for trigram in trigrams.difference(&old) {
+            self.postings.entry(*trigram).or_default().insert(uri.to_string());
+        }
+        self.documents.insert(uri.to_string(), IndexedText { text: text.to_string(), trigrams });
+    }
+
+    pub fn remove_document(&mut self, uri: &str) {
+        if let Some(doc) = self.documents.remove(uri) {
+            for trigram in &doc.trigrams {
+                self.remove_posting(trigram, uri);
+            }
+        }
+    }
+
+    /// Move documents affected by renaming `from` to `to` (a file or a
+    /// directory). Returns the number of documents moved.
+    pub fn rename_documents(&mut self, from: &str, to: &str) -> usize {
+        let moved: Vec<(String, String)> = self
+            .documents
+            .keys()
+            .filter_map(|uri| rebase_uri(uri, from, to).map(|new| (uri.clone(), new)))
+            .collect();
+        for (old, new) in &moved {
+            if let Some(doc) = self.documents.remove(old) {
+                for trigram in &doc.trigrams {
+                    self.remove_posting(trigram, old);
+                    self.postings.entry(*trigram).or_default().insert(new.clone());
+                }
+                self.documents.insert(new.clone(), doc);
+            }
+        }
+        moved.len()
+    }
+
+    /// Indexed text of `uri`
+    pub fn text(&self, uri: &str) -> Option<&str> {
+        self.documents.get(uri).map(|doc| doc.text.as_str())
+    }
+
+    pub fn len(&self) -> usize {
+        self.documents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.documents.is_empty()
+    }
+
+    /// Matches of `query` in the indexed documents
+    pub fn search(&self, query: &str, options: &ReplaceOptions) -> Result<TextSearchResult, String> {
+        let pattern = compile_query(query, options)?;
+        let include: Vec<PathGlob> = options.include.iter().filter_map(|glob| PathGlob::new(glob)).collect();
+        let exclude: Vec<PathGlob> = options.exclude.iter().filter_map(|glob| PathGlob::new(glob)).collect();
+
+        let mut result = TextSearchResult::default();
+        let mut remaining = options.max_matches;
+        for uri in self.candidates(query, options) {
+            let path = uri_to_path(uri).map_or_else(|| uri.to_string(), |p| p.to_string_lossy().replace('\\', "/"));
+            if (!include.is_empty() && !include.iter().any(|glob| glob.is_match(&path)))
+                || exclude.iter().any(|glob| glob.is_match(&path))
+            {
+                continue;
+            }
+            let text = &self.documents[uri].text;
+            if remaining == 0 {
+                // Only stop once another file would have contributed
+                if pattern.is_match(text) {
+                    result.truncated = true;
+                    break;
+                }
+                continue;
+            }
+            let document = Document::new(String::new(), String::new(), text.clone());
+            let mut matches = Vec::new();
+            for found in pattern.find_iter(text) {
+                if matches.len() == remaining {
+                    result.truncated = true;
+                    break;
+                }
+                let line_start = text[..found.start()].rfind('\n').map_or(0, |i| i + 1);
+                let line_end = text[found.start()..].find('\n').map_or(text.len(), |i| found.start() + i);
+                matches.push(TextMatch {
+                    range: Range::new(document.position_at(found.start()), document.position_at(found.end())),
+                    line_text: text[line_start..line_end].trim_end_matches('\r').to_string(),
+                });
+            }
+            remaining -= matches.len();
+            if !matches.is_empty() {
+                result.files.push(FileMatches { uri: uri.to_string(), matches });
+            }
+            if result.truncated {
+                break;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Documents that may match `query`, sorted
+    fn candidates(&self, query: &str, options: &ReplaceOptions) -> Vec<&str> {
+        let literals = if options.regex { required_literals(query) } else { Some(vec![query.to_string()]) };
+        // Case-insensitive matching folds more than ASCII letters, so only
+        // ASCII trigrams can rule a document out
+        let ascii_only = !options.case_sensitive || (options.regex && query.contains("(?"));
+        let required: HashSet<Trigram> = literals
+            .iter()
+            .flatten()
+            .flat_map(|literal| trigrams(literal.as_bytes(), ascii_only))
+            .collect();
+
+        let mut uris: Vec<&str> = if required.is_empty() {
+            self.documents.keys().map(String::as_str).collect()
+        } else {
+            let mut postings: Vec<&HashSet<String>> = Vec::with_capacity(required.len());
+            for trigram in &required {
+                match self.postings.get(trigram) {
+                    Some(uris) => postings.push(uris),
+                    None => return Vec::new(),
+                }
+            }
+            postings.sort_by_key(|uris| uris.len());
+            let (rarest, rest) = postings.split_first().expect("at least one trigram");
+            rarest
+                .iter()
+                .filter(|uri| rest.iter().all(|uris| uris.contains(*uri)))
+                .map(String::as_str)
+                .collect()
+        };
+        uris.sort_unstable();
+        uris
+    }
+
+    fn remove_posting(&mut self, trigram: &Trigram, uri: &str) {
+        if let Some(uris) = self.postings.get_mut(trigram) {
+            uris.remove(uri);
+            if uris.is_empty() {
+                self.postings.remove(trigram);
+            }
+        }
+    }
+}
+
+/// Trigrams of `bytes` with ASCII letters lowercased; with `ascii_only`,
+/// only those made of ASCII bytes
+fn trigrams(bytes: &[u8], ascii_only: bool) -> HashSet<Trigram> {
+    bytes
+        .windows(3)
+        .filter(|window| !ascii_only || window.is_ascii())
+        .map(|window| [window[0].to_ascii_lowercase(), window[1].to_ascii_lowercase(), window[2].to_ascii_lowercase()])
+        .collect()
+}
+
+/// Runs of plain characters that every match of the regex `pattern`
+/// contains, or `None` when an alternation or extended mode leaves no
+/// run certain. Conservative: whatever isn't a plain character ends a run,
+/// a character made optional by a quantifier is dropped, and groups are
+/// skipped as a whole.
+fn required_literals(pattern: &str) -> Option<Vec<String>> {
+    let mut literals = Vec::new();
+    let mut run = String::new();
+    let mut depth = 0usize;
+    let mut chars = pattern.chars().peekable();
+    let mut end_run = |run: &mut String| {
+        if !run.is_empty() {
+            literals.push(std::mem::take(run));
+        }
+    };
+    while let Some(c) = chars.next() {
+        match c {
+            '|' if depth == 0 => return None,
+            '(' => {
+                end_run(&mut run);
+                depth += 1;
+                if chars.peek() == Some(&'?') {
+                    let flags: String =
+                        chars.clone().skip(1).take_while(|c| c.is_ascii_alphabetic() || *c == '-').collect();
+                    if flags.contains('x') {
+                        return None;
+                    }
+                }
+            }
+            ')' => {
+                end_run(&mut run);
+                depth = depth.saturating_sub(1);
+            }
+            '[' => {
+                end_run(&mut run);
+                skip_class(&mut chars);
+            }
+            '?' | '*' => {
+                run.pop();
+                end_run(&mut run);
+            }
+            '{' => {
+                run.pop();
+                end_run(&mut run);
+                for c in chars.by_ref() {
+                    if c == '}' {
+                        break;
+                    }
+                }
+            }
+            '+' | '.' | '^' | '$' => end_run(&mut run),
+            '\\' => match chars.next() {
+                Some(escaped) if escaped.is_ascii_punctuation() && depth == 0 => run.push(escaped),
+                Some(escaped) if escaped.is_ascii_punctuation() => {}
+                Some(class) => {
+                    end_run(&mut run);
+                    skip_escape_argument(class, &mut chars);
+                }
+                None => {}
+            },
+            c if depth == 0 => run.push(c),
+            _ => {}
+        }
+    }
+    end_run(&mut run);
+    Some(literals)
+}
+
+/// Skip the rest of a character class, after its `[`
+fn skip_class(chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    // A `]` first (after an optional `^`) is a member, not the end
+    if chars.peek() == Some(&'^') {
+        chars.next();
+    }
+    if chars.peek() == Some(&']') {
+        chars.next();
+    }
+    let mut depth = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Skip what follows an escape such as `\x41`, `\u{1F980}` or `\pL`
+fn skip_escape_argument(class: char, chars: &mut std::iter::Peekable<std::str::Chars<'_>>) {
+    let digits = match class {
+        'x' => 2,
+        'u' => 4,
+        'U' => 8,
+        'p' | 'P' => 1,
+        _ => return,
+    };
+    if chars.peek() == Some(&'{') {
+        for c in chars.by_ref() {
+            if c == '}' {
+                break;
+            }
+        }
+    } else {
+        for _ in 0..digits {
+            chars.next();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logos_core::Position;
+
+    fn index() -> TrigramIndex {
+        let mut index = TrigramIndex::new();
+        index.index_document("file:///ws/src/main.py", "import os\nconfig = load_config()\nprint(config)\n");
+        index.index_document("file:///ws/src/util.py", "def load_config():\n    return {}\n");
+        index.index_document("file:///ws/README.md", "Call `load_config` first.\n");
+        index
+    }
+
+    fn uris(result: &TextSearchResult) -> Vec<&str> {
+        result.files.iter().map(|f| f.uri.as_str()).collect()
+    }
+
+    #[test]
+    fn test_literal_search() {
+        let index = index();
+        let result = index.search("load_config", &ReplaceOptions::default()).unwrap();
+        assert_eq!(uris(&result), vec!["file:///ws/README.md", "file:///ws/src/main.py", "file:///ws/src/util.py"]);
+        let found = &result.files[1].matches[0];
+        assert_eq!(found.range, Range::new(Position::new(1, 9), Position::new(1, 20)));
+        assert_eq!(found.line_text, "config = load_config()");
+
+        let options =
+            ReplaceOptions { case_sensitive: false, include: vec!["src/**".to_string()], ..ReplaceOptions::default() };
+        let result = index.search("LOAD_CONFIG", &options).unwrap();
+        assert_eq!(uris(&result), vec!["file:///ws/src/main.py", "file:///ws/src/util.py"]);
+        assert!(index.search("LOAD_CONFIG", &ReplaceOptions::default()).unwrap().files.is_empty());
+    }
+
+    #[test]
+    fn test_regex_search_and_limit() {
+        let index = index();
+        let options = ReplaceOptions { regex: true, ..ReplaceOptions::default() };
+        let result = index.search(r"def \w+\(", &options).unwrap();
+        assert_eq!(uris(&result), vec!["file:///ws/src/util.py"]);
+        assert_eq!(index.search("co?nfig|os", &options).unwrap().files.len(), 3);
+
+        let options = ReplaceOptions { max_matches: 2, ..ReplaceOptions::default() };
+        let result = index.search("config", &options).unwrap();
+        assert_eq!(result.match_count(), 2);
+        assert!(result.truncated);
+    }
+
+    #[test]
+    fn test_updates() {
+        let mut index = index();
+        index.index_document("file:///ws/src/util.py", "def read_settings():\n    return {}\n");
+        index.remove_document("file:///ws/README.md");
+        let result = index.search("load_config", &ReplaceOptions::default()).unwrap();
+        assert_eq!(uris(&result), vec!["file:///ws/src/main.py"]);
+
+        assert_eq!(index.rename_documents("file:///ws/src", "file:///ws/lib"), 2);
+        let result = index.search("read_settings", &ReplaceOptions::default()).unwrap();
+        assert_eq!(uris(&result), vec!["file:///ws/lib/util.py"]);
+    }
+
+    #[test]
+    fn test_required_literals() {
+        assert_eq!(required_literals(r"def \w+\(").unwrap(), vec!["def ", "("]);
+        assert_eq!(required_literals("colou?r").unwrap(), vec!["colo", "r"]);
+        assert_eq!(required_literals(r"(?i)user(name)?[_-]id\x41").unwrap(), vec!["user", "id"]);
+        assert_eq!(required_literals(r"a{2}bc\.d").unwrap(), vec!["bc.d"]);
+        assert!(required_literals("foo|bar").is_none());
+        assert_eq!(required_literals("(foo|bar)baz").unwrap(), vec!["baz"]);
+    }
+}
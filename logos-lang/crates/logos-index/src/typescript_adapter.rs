@@ -1,37 +1,53 @@
 //! TypeScript Language Adapter
 //!
 //! Implements the LanguageAdapter trait for TypeScript/JavaScript files.
-//! Extracts symbols, imports, exports, and call relationships.
+//! Extracts symbols, imports, exports, and call relationships. Relative
+//! imports resolve against the importing file; bare ones such as
+//! `@app/components/Button` through the `paths` and `baseUrl` of the
+//! nearest `tsconfig.json` or `jsconfig.json`, following its `extends`,
+//! then to the entry of a package in `node_modules` that its
+//! `package.json` names, its declaration file preferred.
 
 use crate::adapter::{
     AnalysisResult, CallInfo, ExportInfo, ImportInfo, ImportItem, LanguageAdapter,
     SymbolBuilder, TypeRelation, make_location,
 };
+use crate::compile_commands::normalize;
 use crate::symbol_table::{SymbolId, TypeInfo, Visibility};
+use crate::vfs::Vfs;
 use logos_core::{Position, Range, SymbolKind};
-use std::path::Path;
-use tree_sitter::{Node, Parser, Tree};
+use std::path::{Path, PathBuf};
+use logos_parser::{pool, LanguageId};
+use tree_sitter::{Node, Tree};
 
 /// TypeScript/JavaScript language adapter
-pub struct TypeScriptAdapter {
-    parser: std::sync::Mutex<Parser>,
-}
+pub struct TypeScriptAdapter;
 
 impl TypeScriptAdapter {
     pub fn new() -> Result<Self, String> {
-        let mut parser = Parser::new();
-        parser
-            .set_language(&tree_sitter_typescript::LANGUAGE_TSX.into())
-            .map_err(|e| format!("Failed to set TypeScript language: {}", e))?;
+        // Fail early when the grammars can't be loaded
+        for dialect in [LanguageId::TypeScript, LanguageId::TypeScriptReact] {
+            pool::shared()
+                .checkout(dialect)
+                .map_err(|e| format!("Failed to set {} language: {}", dialect.as_str(), e))?;
+        }
+        Ok(Self)
+    }
 
-        Ok(Self {
-            parser: std::sync::Mutex::new(parser),
-        })
+    fn parse(&self, uri: &str, source: &str) -> Option<Tree> {
+        let mut parser = pool::shared().checkout(dialect(uri)).ok()?;
+        parser.parse(source, None).ok()
     }
+}
 
-    fn parse(&self, source: &str) -> Option<Tree> {
-        let mut parser = self.parser.lock().ok()?;
-        parser.parse(source, None)
+/// Grammar for a file: TypeScript for `.ts`, where `<T>expr` is a type
+/// assertion, and TSX for everything else. JavaScript goes through TSX too,
+/// which parses JavaScript with JSX into the node kinds the analysis reads.
+fn dialect(uri: &str) -> LanguageId {
+    let extension = uri.rsplit_once('.').map_or("", |(_, ext)| ext);
+    match LanguageId::from_extension(extension) {
+        Some(LanguageId::TypeScript) => LanguageId::TypeScript,
+        _ => LanguageId::TypeScriptReact,
     }
 }
 
@@ -51,7 +67,7 @@ impl LanguageAdapter for TypeScriptAdapter {
     }
 
     fn analyze(&self, uri: &str, source: &str) -> AnalysisResult {
-        let tree = match self.parse(source) {
+        let tree = match self.parse(uri, source) {
             Some(t) => t,
             None => return AnalysisResult::default(),
         };
@@ -66,41 +82,307 @@ impl LanguageAdapter for TypeScriptAdapter {
 
         analyze_node(&tree.root_node(), &mut context);
 
-        context.result
+        context.result.with_utf16_columns(source)
     }
 
-    fn resolve_import(&self, from_file: &Path, import_path: &str) -> Option<std::path::PathBuf> {
-        // Skip node_modules imports
-        if !import_path.starts_with('.') && !import_path.starts_with('/') {
-            return None;
+    fn resolve_import(&self, vfs: &dyn Vfs, from_file: &Path, import_path: &str) -> Option<PathBuf> {
+        if import_path.starts_with('.') || import_path.starts_with('/') {
+            return self.resolve_file(vfs, &from_file.parent()?.join(import_path));
         }
+        let mapped = TsConfig::find(vfs, from_file)
+            .and_then(|config| config.candidates(import_path).iter().find_map(|path| self.resolve_file(vfs, path)));
+        mapped.or_else(|| self.resolve_package(vfs, from_file, import_path))
+    }
+}
 
-        let parent = from_file.parent()?;
-        let resolved = parent.join(import_path);
-
-        // Try exact match first
-        if resolved.exists() && resolved.is_file() {
-            return Some(resolved);
+impl TypeScriptAdapter {
+    /// The source file an import of `path` loads: `path` itself, `path`
+    /// with a source extension, or an index file in the directory `path`
+    fn resolve_file(&self, vfs: &dyn Vfs, path: &Path) -> Option<PathBuf> {
+        if vfs.is_file(path) {
+            return Some(path.to_path_buf());
         }
-
-        // Try with extensions
         for ext in self.file_extensions() {
-            let with_ext = resolved.with_extension(ext);
-            if with_ext.exists() {
+            let with_ext = path.with_extension(ext);
+            if vfs.exists(&with_ext) {
                 return Some(with_ext);
             }
         }
-
-        // Try index files
         for ext in self.file_extensions() {
-            let index = resolved.join(format!("index.{}", ext));
-            if index.exists() {
+            let index = path.join(format!("index.{}", ext));
+            if vfs.exists(&index) {
                 return Some(index);
             }
         }
-
         None
     }
+
+    /// Entry of the package `module` names, or of the file of it after the
+    /// package name, in the `node_modules` nearest above `from_file` that
+    /// has the package or its `@types` typings. Declaration files win over
+    /// JavaScript.
+    fn resolve_package(&self, vfs: &dyn Vfs, from_file: &Path, module: &str) -> Option<PathBuf> {
+        let (package, subpath) = split_package(module)?;
+        let typings = format!("@types/{}", package.trim_start_matches('@').replace('/', "__"));
+        let modules = from_file
+            .ancestors()
+            .skip(1)
+            .map(|dir| dir.join("node_modules"))
+            .find(|modules| vfs.is_dir(&modules.join(package)) || vfs.is_dir(&modules.join(&typings)))?;
+
+        let mut entries = entry_points(vfs, &modules.join(package), subpath);
+        entries.extend(entry_points(vfs, &modules.join(&typings), subpath));
+        entries
+            .iter()
+            .find_map(|path| declaration_for(vfs, path))
+            .or_else(|| entries.iter().find_map(|path| self.resolve_file(vfs, path)))
+    }
+}
+
+/// Conditions of `exports` entries followed, in order of preference
+const EXPORT_CONDITIONS: &[&str] = &["types", "import", "module", "default", "require", "node"];
+
+/// `@scope/name/sub/path` as `@scope/name` and `sub/path`; `None` for a
+/// Node builtin (`node:fs`) or a malformed name
+fn split_package(module: &str) -> Option<(&str, &str)> {
+    if module.contains(':') {
+        return None;
+    }
+    let name_end = if module.starts_with('@') {
+        let scope_end = module.find('/')?;
+        module[scope_end + 1..].find('/').map_or(module.len(), |i| scope_end + 1 + i)
+    } else {
+        module.find('/').unwrap_or(module.len())
+    };
+    let (package, rest) = module.split_at(name_end);
+    (!package.is_empty()).then_some((package, rest.trim_start_matches('/')))
+}
+
+/// Files the package in `dir` may load for `subpath` (empty for the
+/// package itself), in order: the `exports` entry, then `types`,
+/// `typings`, `module` and `main`, then the package directory, or for a
+/// subpath the path under it
+fn entry_points(vfs: &dyn Vfs, dir: &Path, subpath: &str) -> Vec<PathBuf> {
+    if !vfs.is_dir(dir) {
+        return Vec::new();
+    }
+    let manifest: serde_json::Value = vfs
+        .read_to_string(&dir.join("package.json"))
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default();
+
+    let key = if subpath.is_empty() { ".".to_string() } else { format!("./{}", subpath) };
+    let mut targets = manifest.get("exports").map(|exports| export_targets(exports, &key)).unwrap_or_default();
+    if subpath.is_empty() {
+        for field in ["types", "typings", "module", "main"] {
+            targets.extend(manifest.get(field).and_then(|t| t.as_str()).map(String::from));
+        }
+    }
+    let mut entries: Vec<PathBuf> = targets.iter().map(|target| normalize(&dir.join(target))).collect();
+    entries.push(dir.join(subpath));
+    entries
+}
+
+/// Targets of the `exports` of a package for `key` (`.` or `./sub/path`),
+/// subpath patterns (`./utils/*`) included
+fn export_targets(exports: &serde_json::Value, key: &str) -> Vec<String> {
+    let Some(map) = exports.as_object() else {
+        // A bare target or a list of them stands for the package itself
+        return if key == "." { conditional_targets(exports) } else { Vec::new() };
+    };
+    if !map.keys().any(|k| k.starts_with('.')) {
+        return if key == "." { conditional_targets(exports) } else { Vec::new() };
+    }
+    if let Some(entry) = map.get(key) {
+        return conditional_targets(entry);
+    }
+    let mut targets = Vec::new();
+    for (pattern, entry) in map {
+        let wildcard = pattern
+            .split_once('*')
+            .and_then(|(prefix, suffix)| key.strip_prefix(prefix)?.strip_suffix(suffix));
+        if let Some(wildcard) = wildcard {
+            targets.extend(conditional_targets(entry).into_iter().map(|target| target.replace('*', wildcard)));
+        }
+    }
+    targets
+}
+
+/// Paths of an `exports` entry: itself, each of a list, or the entries of
+/// the conditions followed
+fn conditional_targets(entry: &serde_json::Value) -> Vec<String> {
+    match entry {
+        serde_json::Value::String(target) => vec![target.clone()],
+        serde_json::Value::Array(targets) => targets.iter().flat_map(conditional_targets).collect(),
+        serde_json::Value::Object(conditions) => EXPORT_CONDITIONS
+            .iter()
+            .filter_map(|condition| conditions.get(*condition))
+            .flat_map(conditional_targets)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Declaration file describing the module at `path`: `path` itself when
+/// it is one, the `.d.ts` next to a JavaScript or extensionless path, or
+/// the `index.d.ts` of a directory
+fn declaration_for(vfs: &dyn Vfs, path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    if [".d.ts", ".d.mts", ".d.cts"].iter().any(|suffix| name.ends_with(suffix)) {
+        return vfs.is_file(path).then(|| path.to_path_buf());
+    }
+    let (stem, declaration) = match name.rsplit_once('.') {
+        Some((stem, "mjs")) => (stem, "d.mts"),
+        Some((stem, "cjs")) => (stem, "d.cts"),
+        Some((stem, "js" | "jsx")) => (stem, "d.ts"),
+        _ => (name, "d.ts"),
+    };
+    [path.with_file_name(format!("{}.{}", stem, declaration)), path.join("index.d.ts")]
+        .into_iter()
+        .find(|candidate| vfs.is_file(candidate))
+}
+
+/// Config files whose `compilerOptions` map module names, nearest first
+const CONFIG_FILES: &[&str] = &["tsconfig.json", "jsconfig.json"];
+
+/// `extends` chains followed before giving up on a cycle
+const MAX_EXTENDS: usize = 8;
+
+/// Module name mapping of a `tsconfig.json`, with what it extends applied
+#[derive(Debug, Default)]
+struct TsConfig {
+    /// Directory bare module names are looked up in
+    base_url: Option<PathBuf>,
+    /// `paths` patterns with their substitutions, and the directory of the
+    /// config declaring them, which they are relative to without a base URL
+    paths: Vec<(String, Vec<String>)>,
+    paths_dir: PathBuf,
+}
+
+impl TsConfig {
+    /// Config of the nearest `tsconfig.json` or `jsconfig.json` above `file`
+    fn find(vfs: &dyn Vfs, file: &Path) -> Option<Self> {
+        file.ancestors().skip(1).find_map(|dir| {
+            CONFIG_FILES
+                .iter()
+                .map(|name| dir.join(name))
+                .find(|path| vfs.is_file(path))
+                .and_then(|path| Self::load(vfs, &path, 0))
+        })
+    }
+
+    fn load(vfs: &dyn Vfs, path: &Path, depth: usize) -> Option<Self> {
+        let json: serde_json::Value = serde_json::from_str(&strip_jsonc(&vfs.read_to_string(path).ok()?)).ok()?;
+        let dir = path.parent()?;
+
+        // Only configs in the workspace can be read; packages in
+        // node_modules (`@tsconfig/node18`) are skipped
+        let extends = json.get("extends").and_then(|e| e.as_str()).filter(|e| e.starts_with('.'));
+        let mut config = match extends {
+            Some(extends) if depth < MAX_EXTENDS => {
+                let base = if extends.ends_with(".json") { extends.to_string() } else { format!("{}.json", extends) };
+                Self::load(vfs, &normalize(&dir.join(base)), depth + 1).unwrap_or_default()
+            }
+            _ => Self::default(),
+        };
+
+        let options = json.get("compilerOptions");
+        if let Some(base_url) = options.and_then(|o| o.get("baseUrl")).and_then(|b| b.as_str()) {
+            config.base_url = Some(normalize(&dir.join(base_url)));
+        }
+        if let Some(paths) = options.and_then(|o| o.get("paths")).and_then(|p| p.as_object()) {
+            config.paths = paths
+                .iter()
+                .map(|(pattern, targets)| {
+                    let targets = targets.as_array().into_iter().flatten().filter_map(|t| t.as_str().map(String::from));
+                    (pattern.clone(), targets.collect())
+                })
+                .collect();
+            config.paths_dir = dir.to_path_buf();
+        }
+        Some(config)
+    }
+
+    /// Paths an import of `module` may load, in the order to try them: the
+    /// substitutions of the `paths` pattern matching it, an exact pattern
+    /// over a wildcard one and the wildcard one with the longest prefix
+    /// over others, then the module under the base URL
+    fn candidates(&self, module: &str) -> Vec<PathBuf> {
+        let paths_base = self.base_url.as_deref().unwrap_or(&self.paths_dir);
+        let mut best: Option<(&[String], &str, usize)> = None;
+        for (pattern, targets) in &self.paths {
+            let matched = match pattern.split_once('*') {
+                None if pattern == module => Some(("", usize::MAX)),
+                None => None,
+                Some((prefix, suffix)) => module
+                    .strip_prefix(prefix)
+                    .and_then(|rest| rest.strip_suffix(suffix))
+                    .map(|wildcard| (wildcard, prefix.len())),
+            };
+            if let Some((wildcard, rank)) = matched {
+                if best.map(|(_, _, best_rank)| best_rank) < Some(rank) {
+                    best = Some((targets, wildcard, rank));
+                }
+            }
+        }
+
+        let mut candidates: Vec<PathBuf> = best
+            .into_iter()
+            .flat_map(|(targets, wildcard, _)| {
+                targets.iter().map(move |target| normalize(&paths_base.join(target.replacen('*', wildcard, 1))))
+            })
+            .collect();
+        if let Some(base_url) = &self.base_url {
+            candidates.push(base_url.join(module));
+        }
+        candidates
+    }
+}
+
+/// JSON with the comments and trailing commas a `tsconfig.json` may have
+/// removed
+fn strip_jsonc(text: &str) -> String {
+    let mut json = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                json.push(c);
+                while let Some(c) = chars.next() {
+                    json.push(c);
+                    match c {
+                        '\\' => json.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                while chars.next_if(|&c| c != '\n').is_some() {}
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    previous = c;
+                }
+            }
+            // A trailing comma before the bracket is dropped
+            '}' | ']' => {
+                let trimmed = json.trim_end().len();
+                if json[..trimmed].ends_with(',') {
+                    json.truncate(trimmed - 1);
+                }
+                json.push(c);
+            }
+            c => json.push(c),
+        }
+    }
+    json
 }
 
 /// Context for analysis traversal
@@ -1066,6 +1348,100 @@ fn node_to_range(node: &Node) -> Range {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::vfs::MemoryFs;
+
+    #[test]
+    fn test_resolve_import_through_tsconfig_paths() {
+        let vfs = MemoryFs::new();
+        vfs.insert(
+            "/repo/tsconfig.base.json",
+            r#"{
+                // Shared by every package
+                "compilerOptions": {
+                    "baseUrl": ".",
+                    "paths": {
+                        "@app/*": ["packages/app/src/*"],
+                        "@app/config": ["packages/app/config/index.ts"], /* exact wins */
+                        "@lib/*": ["packages/missing/*", "packages/lib/src/*",],
+                    },
+                },
+            }"#,
+        );
+        vfs.insert("/repo/packages/app/tsconfig.json", r#"{ "extends": "../../tsconfig.base" }"#);
+        for file in [
+            "/repo/packages/app/src/main.ts",
+            "/repo/packages/app/src/components/Button.tsx",
+            "/repo/packages/app/config/index.ts",
+            "/repo/packages/lib/src/format/index.ts",
+            "/repo/shared/util.ts",
+        ] {
+            vfs.insert(file, "");
+        }
+
+        let adapter = TypeScriptAdapter::new().unwrap();
+        let main = Path::new("/repo/packages/app/src/main.ts");
+        let resolve = |module: &str| adapter.resolve_import(&vfs, main, module);
+        assert_eq!(resolve("@app/components/Button"), Some(PathBuf::from("/repo/packages/app/src/components/Button.tsx")));
+        assert_eq!(resolve("@app/config"), Some(PathBuf::from("/repo/packages/app/config/index.ts")));
+        assert_eq!(resolve("@lib/format"), Some(PathBuf::from("/repo/packages/lib/src/format/index.ts")));
+        // Under the base URL
+        assert_eq!(resolve("shared/util"), Some(PathBuf::from("/repo/shared/util.ts")));
+        assert_eq!(resolve("react"), None);
+    }
+
+    #[test]
+    fn test_resolve_import_of_packages() {
+        let vfs = MemoryFs::new();
+        let packages = [
+            ("lib-a", r#"{ "main": "dist/index.js", "types": "dist/index.d.ts" }"#),
+            (
+                "lib-b",
+                r#"{ "exports": {
+                    ".": { "import": { "types": "./types/b.d.mts", "default": "./esm/b.mjs" }, "require": "./cjs/b.cjs" },
+                    "./utils/*": "./esm/utils/*.mjs"
+                } }"#,
+            ),
+            ("lodash", r#"{ "main": "lodash.js" }"#),
+            ("@types/lodash", r#"{ "types": "index.d.ts" }"#),
+            ("@scope/pkg", r#"{ "module": "es/index.js" }"#),
+        ];
+        for (name, manifest) in packages {
+            vfs.insert(format!("/repo/node_modules/{}/package.json", name), manifest);
+        }
+        for file in [
+            "lib-a/dist/index.js",
+            "lib-a/dist/index.d.ts",
+            "lib-b/types/b.d.mts",
+            "lib-b/esm/b.mjs",
+            "lib-b/esm/utils/format.mjs",
+            "lib-b/esm/utils/format.d.mts",
+            "lodash/lodash.js",
+            "@types/lodash/index.d.ts",
+            "@scope/pkg/es/index.js",
+        ] {
+            vfs.insert(format!("/repo/node_modules/{}", file), "");
+        }
+
+        let adapter = TypeScriptAdapter::new().unwrap();
+        let main = Path::new("/repo/app/src/main.ts");
+        let resolve = |module: &str| adapter.resolve_import(&vfs, main, module);
+        let module = |file: &str| Some(PathBuf::from(format!("/repo/node_modules/{}", file)));
+        assert_eq!(resolve("lib-a"), module("lib-a/dist/index.d.ts"));
+        assert_eq!(resolve("lib-b"), module("lib-b/types/b.d.mts"));
+        assert_eq!(resolve("lib-b/utils/format"), module("lib-b/esm/utils/format.d.mts"));
+        assert_eq!(resolve("lodash"), module("@types/lodash/index.d.ts"));
+        assert_eq!(resolve("@scope/pkg"), module("@scope/pkg/es/index.js"));
+        assert_eq!(resolve("missing"), None);
+        assert_eq!(resolve("node:fs"), None);
+        assert_eq!(split_package("@scope/pkg/a/b"), Some(("@scope/pkg", "a/b")));
+    }
+
+    #[test]
+    fn test_strip_jsonc() {
+        let text = "{ \"a\": \"// not a comment\", // comment\n \"b\": [1, 2, /* c */ ], }";
+        let json: serde_json::Value = serde_json::from_str(&strip_jsonc(text)).unwrap();
+        assert_eq!(json, serde_json::json!({ "a": "// not a comment", "b": [1, 2] }));
+    }
 
     #[test]
     fn test_simple_function() {
@@ -1138,6 +1514,22 @@ import path from 'path';
         assert!(type_import.is_type_only);
     }
 
+    #[test]
+    fn test_dialects() {
+        let adapter = TypeScriptAdapter::new().unwrap();
+        // A type assertion, which TSX would read as an unclosed element
+        let source = "const n = <number>value;\nexport function size() {\n    return n;\n}\n";
+        let result = adapter.analyze("file:///size.ts", source);
+        assert!(result.symbols.iter().any(|s| s.name == "size"));
+        assert!(result.symbols.iter().any(|s| s.name == "n"));
+
+        let source = "export function App() {\n    return <div className=\"app\">{title}</div>;\n}\n";
+        let result = adapter.analyze("file:///App.tsx", source);
+        assert_eq!(result.exports.len(), 1);
+        assert_eq!(dialect("file:///App.jsx"), LanguageId::TypeScriptReact);
+        assert_eq!(dialect("file:///src/util.mts"), LanguageId::TypeScript);
+    }
+
     #[test]
     fn test_interface_extends() {
         let adapter = TypeScriptAdapter::new().unwrap();
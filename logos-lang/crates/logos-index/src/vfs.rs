@@ -0,0 +1,255 @@
+//! Virtual filesystem used by the indexer
+//!
+//! The indexer and the language adapters never touch `std::fs` directly;
+//! they go through a [`Vfs`] so indexing can run against sources other than
+//! the local disk (in-memory overlays of unsaved buffers, archives, remote
+//! agents, or a browser-hosted workspace).
+
+use logos_core::uri;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+/// Read-only filesystem view
+pub trait Vfs: Send + Sync {
+    /// Read a whole file as UTF-8
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    /// Read a whole file as bytes
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.read_to_string(path).map(String::into_bytes)
+    }
+
+    /// Entries directly inside a directory, as full paths
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>>;
+
+    fn is_file(&self, path: &Path) -> bool;
+
+    fn is_dir(&self, path: &Path) -> bool;
+
+    fn exists(&self, path: &Path) -> bool {
+        self.is_file(path) || self.is_dir(path)
+    }
+
+    /// Canonical form of a path (e.g. with symlinks resolved); identity by default
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        path.to_path_buf()
+    }
+
+    /// URI clients use to open a file
+    fn uri(&self, path: &Path) -> String {
+        uri::path_to_uri(&self.canonicalize(path))
+    }
+
+    /// Last modification time, when the source tracks one. Files without
+    /// one are always treated as changed.
+    fn modified(&self, _path: &Path) -> Option<SystemTime> {
+        None
+    }
+}
+
+/// The local disk
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsFs;
+
+impl Vfs for OsFs {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut entries: Vec<PathBuf> = fs::read_dir(path)?.flatten().map(|e| e.path()).collect();
+        entries.sort();
+        Ok(entries)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+}
+
+/// In-memory files; directories are implied by the file paths
+#[derive(Debug, Default)]
+pub struct MemoryFs {
+    files: RwLock<BTreeMap<PathBuf, String>>,
+}
+
+impl MemoryFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&self, path: impl Into<PathBuf>, content: impl Into<String>) {
+        if let Ok(mut files) = self.files.write() {
+            files.insert(path.into(), content.into());
+        }
+    }
+
+    pub fn remove(&self, path: &Path) -> Option<String> {
+        self.files.write().ok()?.remove(path)
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.read().map(|f| f.len()).unwrap_or(0)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Vfs for MemoryFs {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.files
+            .read()
+            .ok()
+            .and_then(|files| files.get(path).cloned())
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display())))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        if !self.is_dir(path) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display())));
+        }
+        let files = self.files.read().map_err(|_| io::Error::other("poisoned lock"))?;
+        let mut entries: Vec<PathBuf> = files
+            .keys()
+            .filter_map(|file| {
+                let rest = file.strip_prefix(path).ok()?;
+                rest.components().next().map(|first| path.join(first))
+            })
+            .collect();
+        entries.dedup();
+        Ok(entries)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.files.read().map(|f| f.contains_key(path)).unwrap_or(false)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.files
+            .read()
+            .map(|f| f.keys().any(|file| file != path && file.starts_with(path)))
+            .unwrap_or(false)
+    }
+}
+
+/// An in-memory layer over another filesystem; overlay files win
+pub struct OverlayFs {
+    overlay: MemoryFs,
+    base: Arc<dyn Vfs>,
+}
+
+impl OverlayFs {
+    pub fn new(base: Arc<dyn Vfs>) -> Self {
+        Self { overlay: MemoryFs::new(), base }
+    }
+
+    /// The overlay layer, e.g. for unsaved editor buffers
+    pub fn overlay(&self) -> &MemoryFs {
+        &self.overlay
+    }
+}
+
+impl Vfs for OverlayFs {
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.overlay.read_to_string(path).or_else(|_| self.base.read_to_string(path))
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.overlay.read(path).or_else(|_| self.base.read(path))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut entries = self.base.read_dir(path).unwrap_or_default();
+        entries.extend(self.overlay.read_dir(path).unwrap_or_default());
+        if entries.is_empty() && !self.is_dir(path) {
+            return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} not found", path.display())));
+        }
+        entries.sort();
+        entries.dedup();
+        Ok(entries)
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.overlay.is_file(path) || self.base.is_file(path)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.overlay.is_dir(path) || self.base.is_dir(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> PathBuf {
+        if self.overlay.exists(path) {
+            path.to_path_buf()
+        } else {
+            self.base.canonicalize(path)
+        }
+    }
+
+    fn modified(&self, path: &Path) -> Option<SystemTime> {
+        if self.overlay.exists(path) {
+            None
+        } else {
+            self.base.modified(path)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_fs() {
+        let vfs = MemoryFs::new();
+        vfs.insert("/ws/src/a.py", "a");
+        vfs.insert("/ws/src/pkg/b.py", "b");
+        vfs.insert("/ws/README", "r");
+
+        assert!(vfs.is_dir(Path::new("/ws")));
+        assert!(vfs.is_dir(Path::new("/ws/src/pkg")));
+        assert!(vfs.is_file(Path::new("/ws/src/a.py")));
+        assert!(!vfs.exists(Path::new("/ws/src/c.py")));
+        assert_eq!(vfs.read_to_string(Path::new("/ws/src/pkg/b.py")).unwrap(), "b");
+        assert_eq!(
+            vfs.read_dir(Path::new("/ws/src")).unwrap(),
+            vec![PathBuf::from("/ws/src/a.py"), PathBuf::from("/ws/src/pkg")]
+        );
+        assert!(vfs.read_dir(Path::new("/nowhere")).is_err());
+    }
+
+    #[test]
+    fn test_overlay_wins() {
+        let base = MemoryFs::new();
+        base.insert("/ws/a.py", "disk");
+        base.insert("/ws/b.py", "disk");
+        let vfs = OverlayFs::new(Arc::new(base));
+        vfs.overlay().insert("/ws/a.py", "unsaved");
+        vfs.overlay().insert("/ws/new.py", "new");
+
+        assert_eq!(vfs.read_to_string(Path::new("/ws/a.py")).unwrap(), "unsaved");
+        assert_eq!(vfs.read_to_string(Path::new("/ws/b.py")).unwrap(), "disk");
+        assert_eq!(vfs.read_dir(Path::new("/ws")).unwrap().len(), 3);
+    }
+}
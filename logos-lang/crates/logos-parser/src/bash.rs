@@ -0,0 +1,171 @@
+//! Shell script (Bash) parsing and symbol extraction
+
+use std::collections::HashSet;
+
+use logos_core::{Symbol, SymbolKind};
+use tree_sitter::{Node, Tree};
+use crate::node_to_range;
+
+/// Extract symbols from a Bash AST: function definitions and variables
+/// assigned outside of functions
+pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let mut seen = HashSet::new();
+    let root = tree.root_node();
+    extract_symbols_from_node(&root, source, &mut symbols, &mut seen);
+    symbols
+}
+
+fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol>, seen: &mut HashSet<String>) {
+    match node.kind() {
+        "function_definition" => {
+            if let Some(name_node) = node.child_by_field_name("name") {
+                let name = get_node_text(&name_node, source);
+                symbols.push(Symbol::new(name, SymbolKind::Function, node_to_range(node), node_to_range(&name_node)));
+            }
+            // Variables assigned in a function body are not top-level
+        }
+        "variable_assignment" => {
+            push_variable(node, node, source, SymbolKind::Variable, None, symbols, seen);
+        }
+        "declaration_command" => {
+            // `export A=1`, `readonly B=2`, `declare -r C=3`
+            let command = node.child(0).map(|c| get_node_text(&c, source)).unwrap_or_default();
+            let readonly = command == "readonly"
+                || (command == "declare" && has_flag(node, source, 'r'));
+            let kind = if readonly { SymbolKind::Constant } else { SymbolKind::Variable };
+            let detail = (command == "export").then(|| command.clone());
+            for i in 0..node.named_child_count() {
+                if let Some(child) = node.named_child(i) {
+                    if child.kind() == "variable_assignment" {
+                        push_variable(&child, node, source, kind, detail.clone(), symbols, seen);
+                    }
+                }
+            }
+        }
+        "command" | "subshell" | "command_substitution" | "process_substitution" => {
+            // `FOO=1 cmd` only sets FOO for cmd, and subshell assignments
+            // don't survive into the script
+        }
+        _ => {
+            for i in 0..node.named_child_count() {
+                if let Some(child) = node.named_child(i) {
+                    extract_symbols_from_node(&child, source, symbols, seen);
+                }
+            }
+        }
+    }
+}
+
+/// Add the variable assigned by `assignment`; a reassignment of a name
+/// already listed is skipped
+fn push_variable(
+    assignment: &Node,
+    statement: &Node,
+    source: &str,
+    kind: SymbolKind,
+    detail: Option<String>,
+    symbols: &mut Vec<Symbol>,
+    seen: &mut HashSet<String>,
+) {
+    let Some(name_node) = assignment.child_by_field_name("name") else {
+        return;
+    };
+    // `arr[0]=x` assigns into an existing array
+    if name_node.kind() != "variable_name" {
+        return;
+    }
+    let name = get_node_text(&name_node, source);
+    if !seen.insert(name.clone()) {
+        return;
+    }
+    let mut symbol = Symbol::new(name, kind, node_to_range(statement), node_to_range(&name_node));
+    symbol.detail = detail;
+    symbols.push(symbol);
+}
+
+/// Whether a declaration command has a short flag like `-r` or `-rx`
+fn has_flag(node: &Node, source: &str, flag: char) -> bool {
+    for i in 0..node.named_child_count() {
+        if let Some(child) = node.named_child(i) {
+            if child.kind() == "word" {
+                let text = get_node_text(&child, source);
+                if text.starts_with('-') && text.contains(flag) {
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+fn get_node_text(node: &Node, source: &str) -> String {
+    source[node.byte_range()].to_string()
+}
+
+/// Get Bash keywords and common builtins
+pub fn get_keywords() -> &'static [&'static str] {
+    &[
+        "case", "coproc", "do", "done", "elif", "else", "esac", "fi", "for",
+        "function", "if", "in", "select", "then", "time", "until", "while",
+        "break", "continue", "declare", "echo", "eval", "exec", "exit",
+        "export", "local", "printf", "read", "readonly", "return", "set",
+        "shift", "source", "test", "trap", "unset",
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LanguageId, LanguageParser};
+
+    fn parse(source: &str) -> Vec<Symbol> {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Bash).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        extract_symbols(&tree, source)
+    }
+
+    #[test]
+    fn test_functions_and_variables() {
+        let symbols = parse(
+            r#"#!/bin/bash
+NAME="demo"
+export PATH=/usr/bin
+readonly MAX=3
+declare -r LIMIT=5
+NAME="again"
+build() {
+  local target=$1
+  echo "$target"
+}
+function clean {
+  rm -rf out
+}
+if [ -z "$CI" ]; then VERBOSE=1; fi
+"#,
+        );
+
+        let names: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("NAME", SymbolKind::Variable),
+                ("PATH", SymbolKind::Variable),
+                ("MAX", SymbolKind::Constant),
+                ("LIMIT", SymbolKind::Constant),
+                ("build", SymbolKind::Function),
+                ("clean", SymbolKind::Function),
+                ("VERBOSE", SymbolKind::Variable),
+            ]
+        );
+        assert_eq!(symbols[1].detail.as_deref(), Some("export"));
+    }
+
+    #[test]
+    fn test_subshell_assignments_are_ignored() {
+        let symbols = parse("(TMP=1; echo $TMP)\nOUT=$(X=2; echo $X)\n");
+        let names: Vec<_> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["OUT"]);
+    }
+}
@@ -0,0 +1,166 @@
+//! Bracket pairs and indentation from the parse tree
+//!
+//! An opening bracket pairs with the next closing bracket of its kind under
+//! the same parent node. Brackets inside strings and comments aren't
+//! tokens, so they never pair, and brackets that error recovery inserted
+//! are skipped. The depth of a pair counts the pairs around it, for rainbow
+//! colouring.
+//!
+//! The indentation level of a line counts the constructs open across it:
+//! bracket pairs opened on an earlier line, and in Python the blocks the
+//! line belongs to. A line starting with a closing bracket sits at the
+//! level of the line that opened it.
+
+use logos_core::Range;
+use tree_sitter::{Node, Tree};
+
+use crate::{node_to_range, LanguageId, Utf16Columns};
+
+const BRACKETS: &[(&str, &str)] = &[("(", ")"), ("[", "]"), ("{", "}")];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BracketPair {
+    pub open: Range,
+    pub close: Range,
+    /// Number of pairs enclosing this one
+    pub depth: u32,
+}
+
+/// Bracket pairs of a document, in order of their opening bracket
+pub fn bracket_pairs(tree: &Tree, source: &str) -> Vec<BracketPair> {
+    let mut pairs = tree_pairs(tree);
+    let columns = Utf16Columns::new(source);
+    for pair in &mut pairs {
+        columns.convert_range(&mut pair.open);
+        columns.convert_range(&mut pair.close);
+    }
+    pairs
+}
+
+/// Pairs in tree-sitter columns
+pub(crate) fn tree_pairs(tree: &Tree) -> Vec<BracketPair> {
+    let mut pairs = Vec::new();
+    collect_pairs(tree.root_node(), 0, &mut pairs);
+    pairs.sort_by_key(|pair| pair.open.start);
+    pairs
+}
+
+fn collect_pairs(node: Node, depth: u32, pairs: &mut Vec<BracketPair>) {
+    // Brackets opened among the children and not closed yet
+    let mut open: Vec<(&str, Range)> = Vec::new();
+    let mut cursor = node.walk();
+    for child in node.children(&mut cursor) {
+        if child.is_missing() {
+            continue;
+        }
+        let kind = child.kind();
+        if !child.is_named() {
+            if let Some((opener, _)) = BRACKETS.iter().find(|(opener, _)| *opener == kind) {
+                open.push((*opener, node_to_range(&child)));
+                continue;
+            }
+            if let Some((opener, _)) = BRACKETS.iter().find(|(_, closer)| *closer == kind) {
+                if open.last().is_some_and(|(last, _)| last == opener) {
+                    if let Some((_, range)) = open.pop() {
+                        pairs.push(BracketPair { open: range, close: node_to_range(&child), depth: depth + open.len() as u32 });
+                    }
+                }
+                continue;
+            }
+        }
+        collect_pairs(child, depth + open.len() as u32, pairs);
+    }
+}
+
+/// Indentation level of each line of `source`, a trailing empty line
+/// included
+pub fn indent_levels(lang: LanguageId, tree: &Tree, source: &str) -> Vec<u32> {
+    let lines: Vec<&str> = source.split('\n').collect();
+    // Level changes at each line, summed up below
+    let mut changes = vec![0i64; lines.len() + 1];
+    let mut indent = |first: usize, last: usize| {
+        if first <= last && first < lines.len() {
+            changes[first] += 1;
+            changes[(last + 1).min(lines.len())] -= 1;
+        }
+    };
+
+    for pair in tree_pairs(tree) {
+        let (open, close) = (pair.open.start.line as usize, pair.close.start.line as usize);
+        if close == open {
+            continue;
+        }
+        let line = lines.get(close).copied().unwrap_or_default();
+        let leads = line.len() - line.trim_start().len() == pair.close.start.column as usize;
+        indent(open + 1, if leads { close - 1 } else { close });
+    }
+    if lang.base() == LanguageId::Python {
+        collect_blocks(tree.root_node(), &mut indent);
+    }
+
+    let mut level = 0i64;
+    changes[..lines.len()]
+        .iter()
+        .map(|change| {
+            level += change;
+            level.max(0) as u32
+        })
+        .collect()
+}
+
+/// Python blocks on lines of their own, as the line ranges they indent
+fn collect_blocks(node: Node, indent: &mut impl FnMut(usize, usize)) {
+    if node.kind() == "block" {
+        let start = node.start_position().row;
+        let header = node.parent().map_or(start, |parent| parent.start_position().row);
+        let end = node.end_position();
+        let last = if end.column == 0 && end.row > start { end.row - 1 } else { end.row };
+        if header < start {
+            indent(start, last);
+        }
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_blocks(child, indent);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LanguageParser;
+    use logos_core::Position;
+
+    fn parse(lang: LanguageId, source: &str) -> Tree {
+        let mut parser = LanguageParser::new();
+        parser.set_language(lang).unwrap();
+        parser.parse(source, None).unwrap()
+    }
+
+    #[test]
+    fn test_bracket_pairs() {
+        let source = "function f(a) {\n  if (a) {\n    return [1, \"(\", 2];\n  }\n}\n";
+        let tree = parse(LanguageId::JavaScript, source);
+        let pairs = bracket_pairs(&tree, source);
+        let opens: Vec<_> = pairs.iter().map(|p| (p.open.start, p.depth)).collect();
+        assert_eq!(
+            opens,
+            vec![
+                (Position::new(0, 10), 0),
+                (Position::new(0, 14), 0),
+                (Position::new(1, 5), 1),
+                (Position::new(1, 9), 1),
+                (Position::new(2, 11), 2),
+            ]
+        );
+        assert_eq!(pairs[1].close.start, Position::new(4, 0));
+        assert_eq!(indent_levels(LanguageId::JavaScript, &tree, source), vec![0, 1, 2, 1, 0, 0]);
+    }
+
+    #[test]
+    fn test_python_indentation() {
+        let source = "def f(x):\n    if x:\n        return (1,\n                2)\n    return 0\n";
+        let tree = parse(LanguageId::Python, source);
+        assert_eq!(indent_levels(LanguageId::Python, &tree, source), vec![0, 1, 2, 3, 1, 0]);
+    }
+}
@@ -1,17 +1,89 @@
 //! C-specific parsing and symbol extraction
+//!
+//! Besides declarations, the preprocessor contributes `#define` macros as
+//! symbols and `#include` directives as imports. Branches that no
+//! configuration compiles (`#if 0`) are dead code: nothing declared or
+//! included in them is extracted.
 
-use logos_core::{Symbol, SymbolKind};
+use logos_core::{Range, Symbol, SymbolKind};
 use tree_sitter::{Node, Tree};
-use crate::node_to_range;
+use crate::{conditional, node_to_range, signature_between, Utf16Columns};
+
+/// An `#include` directive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Include {
+    /// The operand without its delimiters: `stdio.h`, `util/log.h`
+    pub path: String,
+    /// `<...>` rather than `"..."`
+    pub system: bool,
+    /// Range of the operand, delimiters included
+    pub range: Range,
+}
 
 /// Extract symbols from a C AST
 pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
     let mut symbols = Vec::new();
     let root = tree.root_node();
     extract_symbols_from_node(&root, source, &mut symbols);
+    conditional::remove_inactive_symbols(&mut symbols, &conditional::dead_preprocessor_regions(tree, source));
     symbols
 }
 
+/// `#include` directives of a C or C++ source in source order, leaving out
+/// those in dead branches
+pub fn includes(tree: &Tree, source: &str) -> Vec<Include> {
+    let dead = conditional::dead_preprocessor_regions(tree, source);
+    let mut includes = Vec::new();
+    collect_includes(&tree.root_node(), source, &dead, &mut includes);
+    let columns = Utf16Columns::new(source);
+    for include in &mut includes {
+        columns.convert_range(&mut include.range);
+    }
+    includes
+}
+
+fn collect_includes(node: &Node, source: &str, dead: &[Range], includes: &mut Vec<Include>) {
+    for i in 0..node.named_child_count() {
+        let Some(child) = node.named_child(i) else {
+            continue;
+        };
+        if child.kind() != "preproc_include" {
+            collect_includes(&child, source, dead, includes);
+            continue;
+        }
+        let Some(operand) = child.child_by_field_name("path") else {
+            continue;
+        };
+        let range = node_to_range(&operand);
+        if dead.iter().any(|region| region.contains(range.start)) {
+            continue;
+        }
+        includes.push(Include {
+            path: get_node_text(&operand, source).trim_matches(['"', '<', '>']).to_string(),
+            system: operand.kind() == "system_lib_string",
+            range,
+        });
+    }
+}
+
+/// Symbol of a `#define`: a constant for an object-like macro, a function
+/// for a function-like one, with the definition as detail
+pub fn macro_symbol(node: &Node, source: &str) -> Option<Symbol> {
+    let name_node = node.child_by_field_name("name")?;
+    let (kind, end) = match node.child_by_field_name("parameters") {
+        Some(parameters) => (SymbolKind::Function, parameters.end_byte()),
+        None => (SymbolKind::Constant, node.child_by_field_name("value").unwrap_or(name_node).end_byte()),
+    };
+    let mut symbol = Symbol::new(
+        get_node_text(&name_node, source),
+        kind,
+        node_to_range(node),
+        node_to_range(&name_node),
+    );
+    symbol.detail = Some(signature_between(source, node.start_byte(), end));
+    Some(symbol)
+}
+
 fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol>) {
     match node.kind() {
         "function_definition" => {
@@ -98,16 +170,8 @@ fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol
                 }
             }
         }
-        "preproc_def" => {
-            if let Some(name_node) = node.child_by_field_name("name") {
-                let name = get_node_text(&name_node, source);
-                symbols.push(Symbol::new(
-                    name,
-                    SymbolKind::Constant,
-                    node_to_range(node),
-                    node_to_range(&name_node),
-                ));
-            }
+        "preproc_def" | "preproc_function_def" => {
+            symbols.extend(macro_symbol(node, source));
         }
         _ => {
             for i in 0..node.named_child_count() {
@@ -119,7 +183,7 @@ fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol
     }
 }
 
-fn find_identifier_info(node: &Node, source: &str) -> Option<(String, crate::Range)> {
+fn find_identifier_info(node: &Node, source: &str) -> Option<(String, Range)> {
     if node.kind() == "identifier" {
         return Some((get_node_text(node, source), crate::node_to_range(node)));
     }
@@ -188,3 +252,52 @@ pub fn get_keywords() -> &'static [&'static str] {
         "_Static_assert", "_Thread_local",
     ]
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LanguageId, LanguageParser};
+    use logos_core::Position;
+
+    fn parse(lang: LanguageId, source: &str) -> Tree {
+        let mut parser = LanguageParser::new();
+        parser.set_language(lang).unwrap();
+        parser.parse(source, None).unwrap()
+    }
+
+    #[test]
+    fn test_macros_and_dead_branches() {
+        let source = "#define MAX 16\n#define SQUARE(x) ((x) * (x))\n#if 0\nint dead(void) { return 0; }\n#define OLD 1\n#else\nint live(void) { return 1; }\n#endif\n";
+        let tree = parse(LanguageId::C, source);
+        let symbols = extract_symbols(&tree, source);
+        let names: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind, s.detail.as_deref())).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("MAX", SymbolKind::Constant, Some("#define MAX 16")),
+                ("SQUARE", SymbolKind::Function, Some("#define SQUARE(x)")),
+                ("live", SymbolKind::Function, None),
+            ]
+        );
+
+        let tree = parse(LanguageId::Cpp, source);
+        let names: Vec<_> = crate::cpp::extract_symbols(&tree, source).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["MAX", "SQUARE", "live"]);
+    }
+
+    #[test]
+    fn test_includes() {
+        let source = "#include <stdio.h>\n#include \"util/log.h\"\n#if 0\n#include \"old.h\"\n#endif\n#ifdef EXTRA\n#include <extra.h>\n#endif\n";
+        let tree = parse(LanguageId::C, source);
+        let found: Vec<_> = includes(&tree, source).into_iter().map(|i| (i.path, i.system)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("stdio.h".to_string(), true),
+                ("util/log.h".to_string(), false),
+                ("extra.h".to_string(), true),
+            ]
+        );
+        assert_eq!(includes(&tree, source)[1].range.start, Position::new(1, 9));
+    }
+}
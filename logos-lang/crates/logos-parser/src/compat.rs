@@ -0,0 +1,308 @@
+//! Versions and node kinds of the bundled grammars
+//!
+//! Extractors find declarations by node kind name. A grammar upgrade that
+//! renames a kind still builds and parses; the extractor just stops
+//! finding anything. Each bundled grammar is recorded here with the
+//! version it is pinned to and the node kinds its extractors match on, and
+//! a language whose grammar lacks one of them fails to load with the
+//! missing kinds named, instead of giving empty outlines.
+//!
+//! The checks run once per language, the first time a parser is set to it.
+
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+
+use tree_sitter::{Language, LANGUAGE_VERSION, MIN_COMPATIBLE_LANGUAGE_VERSION};
+
+use crate::{LanguageId, ParseError};
+
+/// A grammar crate compiled into the parser
+struct BundledGrammar {
+    languages: &'static [LanguageId],
+    crate_name: &'static str,
+    /// Version pinned in the workspace manifest
+    version: &'static str,
+    /// Named node kinds the extractors of its languages match on
+    kinds: &'static [&'static str],
+}
+
+const BUNDLED: &[BundledGrammar] = &[
+    BundledGrammar {
+        languages: &[LanguageId::Python],
+        crate_name: "tree-sitter-python",
+        version: "0.23.6",
+        kinds: &["function_definition", "class_definition", "decorated_definition", "assignment", "import_from_statement", "block"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Go],
+        crate_name: "tree-sitter-go",
+        version: "0.23.4",
+        kinds: &["function_declaration", "method_declaration", "type_declaration", "struct_type", "interface_type", "import_declaration"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Rust],
+        crate_name: "tree-sitter-rust",
+        version: "0.23.3",
+        kinds: &["function_item", "struct_item", "enum_item", "trait_item", "impl_item", "mod_item", "use_declaration"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::C, LanguageId::Protobuf],
+        crate_name: "tree-sitter-c",
+        version: "0.23.4",
+        kinds: &["function_definition", "struct_specifier", "enum_specifier", "type_definition", "preproc_def", "preproc_include"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Cpp],
+        crate_name: "tree-sitter-cpp",
+        version: "0.23.4",
+        kinds: &["function_definition", "class_specifier", "namespace_definition", "template_declaration", "field_declaration", "qualified_identifier"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Java],
+        crate_name: "tree-sitter-java",
+        version: "0.23.5",
+        kinds: &["class_declaration", "interface_declaration", "enum_declaration", "record_declaration", "method_declaration", "import_declaration"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::JavaScript, LanguageId::JavaScriptReact],
+        crate_name: "tree-sitter-javascript",
+        version: "0.23.1",
+        kinds: &["function_declaration", "class_declaration", "method_definition", "lexical_declaration", "export_statement", "import_statement"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::TypeScript, LanguageId::TypeScriptReact],
+        crate_name: "tree-sitter-typescript",
+        version: "0.23.2",
+        kinds: &["function_declaration", "class_declaration", "interface_declaration", "type_alias_declaration", "enum_declaration", "method_definition"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Kotlin],
+        crate_name: "tree-sitter-kotlin-ng",
+        version: "1.1.0",
+        kinds: &["class_declaration", "object_declaration", "function_declaration", "property_declaration"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Swift],
+        crate_name: "tree-sitter-swift",
+        version: "0.6.0",
+        kinds: &["class_declaration", "protocol_declaration", "function_declaration", "property_declaration"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::CSharp],
+        crate_name: "tree-sitter-c-sharp",
+        version: "0.23.1",
+        kinds: &["namespace_declaration", "class_declaration", "interface_declaration", "method_declaration", "property_declaration", "using_directive"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Bash, LanguageId::Dockerfile],
+        crate_name: "tree-sitter-bash",
+        version: "0.23.3",
+        kinds: &["function_definition", "variable_assignment", "declaration_command", "command"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Html],
+        crate_name: "tree-sitter-html",
+        version: "0.23.2",
+        kinds: &["element", "script_element", "style_element", "attribute_name"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Css],
+        crate_name: "tree-sitter-css",
+        version: "0.23.2",
+        kinds: &["rule_set", "declaration", "keyframes_statement", "media_statement", "color_value", "class_selector"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Scss],
+        crate_name: "tree-sitter-scss",
+        version: "1.0.0",
+        kinds: &["rule_set", "declaration", "mixin_statement"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Json],
+        crate_name: "tree-sitter-json",
+        version: "0.24.8",
+        kinds: &["object", "array", "pair", "string"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Yaml],
+        crate_name: "tree-sitter-yaml",
+        version: "0.7.2",
+        kinds: &["block_mapping", "block_sequence", "block_mapping_pair", "block_sequence_item", "plain_scalar"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Toml],
+        crate_name: "tree-sitter-toml-ng",
+        version: "0.7.0",
+        kinds: &["pair", "table", "table_array_element", "inline_table"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Markdown],
+        crate_name: "tree-sitter-md",
+        version: "0.3.2",
+        kinds: &["atx_heading", "setext_heading", "section", "fenced_code_block"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Sql],
+        crate_name: "tree-sitter-sequel",
+        version: "0.3.11",
+        kinds: &["create_table", "create_function", "create_index", "cte"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Elixir],
+        crate_name: "tree-sitter-elixir",
+        version: "0.3.5",
+        kinds: &["call", "identifier", "binary_operator", "do_block"],
+    },
+    BundledGrammar {
+        languages: &[LanguageId::Dart],
+        crate_name: "tree-sitter-dart",
+        version: "0.0.4",
+        kinds: &["class_definition", "mixin_declaration", "extension_declaration", "enum_declaration", "function_signature"],
+    },
+];
+
+/// Metadata of a bundled grammar, for diagnostics
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GrammarInfo {
+    pub language: LanguageId,
+    pub crate_name: &'static str,
+    /// Version pinned in the workspace manifest
+    pub version: &'static str,
+    /// ABI version of the grammar's tables
+    pub abi_version: usize,
+    pub node_kind_count: usize,
+    /// Hash of the grammar's node kind names, which changes when a kind is
+    /// added, removed or renamed
+    pub node_kind_hash: u64,
+}
+
+fn bundled(lang: LanguageId) -> Option<&'static BundledGrammar> {
+    BUNDLED.iter().find(|grammar| grammar.languages.contains(&lang))
+}
+
+/// Metadata of the grammar of a built-in language; `None` for grammars
+/// loaded at runtime
+pub fn info(lang: LanguageId) -> Option<GrammarInfo> {
+    let bundled = bundled(lang)?;
+    let language = lang.grammar().ok()?;
+    Some(GrammarInfo {
+        language: lang,
+        crate_name: bundled.crate_name,
+        version: bundled.version,
+        abi_version: language.version(),
+        node_kind_count: language.node_kind_count(),
+        node_kind_hash: node_kind_hash(&language),
+    })
+}
+
+/// Metadata of each bundled grammar, under the first language using it
+pub fn all() -> Vec<GrammarInfo> {
+    BUNDLED.iter().filter_map(|grammar| info(grammar.languages[0])).collect()
+}
+
+/// FNV-1a hash of the node kind names of `language`, in id order
+pub fn node_kind_hash(language: &Language) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for id in 0..language.node_kind_count() as u16 {
+        let name = language.node_kind_for_id(id).unwrap_or_default();
+        for byte in name.bytes().chain([0]) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+/// Whether the tables of `language` can be read by the linked tree-sitter
+pub fn check_abi(language: &Language) -> Result<(), String> {
+    let version = language.version();
+    if !(MIN_COMPATIBLE_LANGUAGE_VERSION..=LANGUAGE_VERSION).contains(&version) {
+        return Err(format!(
+            "grammar ABI {} is not in the supported range {}..={}",
+            version, MIN_COMPATIBLE_LANGUAGE_VERSION, LANGUAGE_VERSION
+        ));
+    }
+    Ok(())
+}
+
+/// Named node kinds of `expected` that `language` doesn't have
+pub fn missing_kinds(language: &Language, expected: &[&'static str]) -> Vec<&'static str> {
+    expected.iter().copied().filter(|kind| language.id_for_node_kind(kind, true) == 0).collect()
+}
+
+/// Check the grammar of `lang` against what its extractors expect. The
+/// result is computed once per language; loaded grammars only get the ABI
+/// check at load time and always pass here.
+pub fn check(lang: LanguageId) -> Result<(), ParseError> {
+    static CHECKED: OnceLock<Mutex<HashMap<LanguageId, Result<(), String>>>> = OnceLock::new();
+    let Some(bundled) = bundled(lang) else {
+        return Ok(());
+    };
+
+    let checked = CHECKED.get_or_init(Mutex::default);
+    if let Some(result) = checked.lock().ok().and_then(|checked| checked.get(&lang).cloned()) {
+        return result.map_err(ParseError::LanguageError);
+    }
+    let result = validate(lang, bundled);
+    if let Ok(mut checked) = checked.lock() {
+        checked.insert(lang, result.clone());
+    }
+    result.map_err(ParseError::LanguageError)
+}
+
+fn validate(lang: LanguageId, bundled: &BundledGrammar) -> Result<(), String> {
+    let failed = |reason: String| format!("{} {}: {}", bundled.crate_name, bundled.version, reason);
+    let language = lang.grammar().map_err(|e| failed(e.to_string()))?;
+    check_abi(&language).map_err(failed)?;
+    let missing = missing_kinds(&language, bundled.kinds);
+    if !missing.is_empty() {
+        return Err(failed(format!(
+            "node kinds {} are missing; the grammar no longer matches its extractor",
+            missing.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Check every built-in language, returning those that fail with the reason
+pub fn check_all() -> Vec<(LanguageId, String)> {
+    BUNDLED
+        .iter()
+        .flat_map(|grammar| grammar.languages.iter().copied())
+        .filter_map(|lang| check(lang).err().map(|e| (lang, e.to_string())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundled_grammars_match_extractors() {
+        assert_eq!(check_all(), Vec::new());
+
+        let python = info(LanguageId::Python).unwrap();
+        assert_eq!(python.crate_name, "tree-sitter-python");
+        assert!(python.node_kind_count > 0);
+        assert_eq!(python.node_kind_hash, node_kind_hash(&LanguageId::Python.grammar().unwrap()));
+        assert_ne!(python.node_kind_hash, info(LanguageId::Go).unwrap().node_kind_hash);
+        assert_eq!(info(LanguageId::Custom("zig")), None);
+        assert_eq!(all().len(), BUNDLED.len());
+    }
+
+    #[test]
+    fn test_renamed_kinds_are_reported() {
+        let language = LanguageId::Python.grammar().unwrap();
+        assert_eq!(missing_kinds(&language, &["function_definition", "async_function_definition"]), vec!["async_function_definition"]);
+
+        let renamed = BundledGrammar {
+            languages: &[LanguageId::Python],
+            crate_name: "tree-sitter-python",
+            version: "0.23.6",
+            kinds: &["class_definition", "function_def"],
+        };
+        let reason = validate(LanguageId::Python, &renamed).unwrap_err();
+        assert!(reason.contains("tree-sitter-python 0.23.6"));
+        assert!(reason.contains("function_def "));
+    }
+}
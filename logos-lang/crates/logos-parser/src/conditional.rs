@@ -0,0 +1,422 @@
+//! Conditional compilation: regions of C, C++ and Rust sources that the
+//! active configuration compiles out
+//!
+//! A region is only reported inactive when the configuration settles its
+//! condition. `#ifdef DEBUG` with no known defines, or a `#[cfg]` on a
+//! target property we don't model, stays active.
+
+use std::collections::{HashMap, HashSet};
+
+use logos_core::{Position, Range, Symbol};
+use tree_sitter::{Node, Tree};
+
+use crate::{node_to_range, LanguageId, Utf16Columns};
+
+/// Active defines and features
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CfgConfig {
+    /// C/C++ preprocessor defines with their value. When set, names that
+    /// aren't listed count as undefined; when `None`, only literal
+    /// conditions such as `#if 0` are decided.
+    pub defines: Option<HashMap<String, Option<String>>>,
+    /// Enabled Cargo features, deciding `#[cfg(feature = "...")]`; `None`
+    /// when unknown
+    pub features: Option<HashSet<String>>,
+}
+
+/// Regions of a document that the configuration leaves out: the bodies of
+/// preprocessor branches that aren't taken, and Rust items (with their
+/// attributes) whose `#[cfg]` doesn't hold. Other languages have none.
+pub fn inactive_regions(lang: LanguageId, tree: &Tree, source: &str, config: &CfgConfig) -> Vec<Range> {
+    let mut regions = Vec::new();
+    match lang {
+        LanguageId::C | LanguageId::Cpp => collect_preprocessor(&tree.root_node(), source, config, &mut regions),
+        LanguageId::Rust => collect_cfg(&tree.root_node(), source, config, &mut regions),
+        _ => {}
+    }
+    let columns = Utf16Columns::new(source);
+    for region in &mut regions {
+        columns.convert_range(region);
+    }
+    regions
+}
+
+/// Preprocessor branches that no configuration compiles, such as the body
+/// of `#if 0`, in tree-sitter columns for extractors to skip
+pub fn dead_preprocessor_regions(tree: &Tree, source: &str) -> Vec<Range> {
+    let mut regions = Vec::new();
+    collect_preprocessor(&tree.root_node(), source, &CfgConfig::default(), &mut regions);
+    regions
+}
+
+/// Drop symbols declared in inactive regions, at any depth
+pub fn remove_inactive_symbols(symbols: &mut Vec<Symbol>, regions: &[Range]) {
+    if regions.is_empty() {
+        return;
+    }
+    symbols.retain(|symbol| !regions.iter().any(|region| region.contains(symbol.selection_range.start)));
+    for symbol in symbols {
+        remove_inactive_symbols(&mut symbol.children, regions);
+    }
+}
+
+fn collect_preprocessor(node: &Node, source: &str, config: &CfgConfig, regions: &mut Vec<Range>) {
+    for i in 0..node.named_child_count() {
+        let Some(child) = node.named_child(i) else {
+            continue;
+        };
+        match child.kind() {
+            "preproc_if" | "preproc_ifdef" => conditional_chain(&child, source, config, regions),
+            _ => collect_preprocessor(&child, source, config, regions),
+        }
+    }
+}
+
+/// Walk the branches of one `#if`/`#ifdef` ... `#endif`
+fn conditional_chain(node: &Node, source: &str, config: &CfgConfig, regions: &mut Vec<Range>) {
+    let endif = (0..node.child_count())
+        .rev()
+        .filter_map(|i| node.child(i))
+        .find(|c| c.kind() == "#endif")
+        .map_or_else(|| node_to_range(node).end, |e| line_start(e.start_position().row));
+
+    // Whether an earlier branch is certainly taken, or might be
+    let mut taken = false;
+    let mut undecided = false;
+    let mut branch = Some(*node);
+    while let Some(current) = branch {
+        let alternative = current.child_by_field_name("alternative");
+        let header = current
+            .child_by_field_name("condition")
+            .or_else(|| current.child_by_field_name("name"))
+            .or_else(|| current.child(0))
+            .unwrap_or(current);
+        let body = Range::new(
+            line_start(header.end_position().row + 1),
+            alternative.map_or(endif, |a| line_start(a.start_position().row)),
+        );
+
+        let condition = match current.kind() {
+            "preproc_if" | "preproc_elif" => current
+                .child_by_field_name("condition")
+                .and_then(|c| eval_c(&c, source, config))
+                .map(|v| v != 0),
+            "preproc_ifdef" | "preproc_elifdef" => {
+                let negated = current.child(0).is_some_and(|d| d.kind().ends_with("ndef"));
+                let name = current.child_by_field_name("name").map(|n| &source[n.byte_range()]);
+                match (name, &config.defines) {
+                    (Some(name), Some(defines)) => Some(defines.contains_key(name) != negated),
+                    _ => None,
+                }
+            }
+            "preproc_else" => Some(true),
+            _ => None,
+        };
+        let active = if taken {
+            Some(false)
+        } else {
+            match condition {
+                Some(true) if undecided => None,
+                other => other,
+            }
+        };
+
+        if active == Some(false) {
+            if body.start < body.end {
+                regions.push(body);
+            }
+        } else {
+            for i in 0..current.named_child_count() {
+                let Some(child) = current.named_child(i) else {
+                    continue;
+                };
+                if Some(child) == alternative || child == header {
+                    continue;
+                }
+                match child.kind() {
+                    "preproc_if" | "preproc_ifdef" => conditional_chain(&child, source, config, regions),
+                    _ => collect_preprocessor(&child, source, config, regions),
+                }
+            }
+        }
+        taken |= active == Some(true);
+        undecided |= active.is_none();
+        branch = alternative;
+    }
+}
+
+fn line_start(row: usize) -> Position {
+    Position::new(row as u32, 0)
+}
+
+/// Value of a `#if` expression; `None` when it depends on something unknown
+fn eval_c(node: &Node, source: &str, config: &CfgConfig) -> Option<i64> {
+    let text = &source[node.byte_range()];
+    match node.kind() {
+        "number_literal" => {
+            let digits = text.trim_end_matches(['u', 'U', 'l', 'L']);
+            match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+                Some(hex) => i64::from_str_radix(hex, 16).ok(),
+                None => digits.parse().ok(),
+            }
+        }
+        "true" => Some(1),
+        "false" => Some(0),
+        "identifier" => match config.defines.as_ref()?.get(text) {
+            None => Some(0),
+            Some(None) => Some(1),
+            Some(Some(value)) => value.trim().parse().ok(),
+        },
+        "preproc_defined" => {
+            let name = node.named_child(0)?;
+            Some(config.defines.as_ref()?.contains_key(&source[name.byte_range()]) as i64)
+        }
+        "parenthesized_expression" => eval_c(&node.named_child(0)?, source, config),
+        "unary_expression" => {
+            let value = eval_c(&node.child_by_field_name("argument")?, source, config);
+            match &source[node.child_by_field_name("operator")?.byte_range()] {
+                "!" => value.map(|v| (v == 0) as i64),
+                "-" => value.map(|v| -v),
+                "~" => value.map(|v| !v),
+                _ => None,
+            }
+        }
+        "binary_expression" => {
+            let left = eval_c(&node.child_by_field_name("left")?, source, config);
+            let right = eval_c(&node.child_by_field_name("right")?, source, config);
+            let operator = &source[node.child_by_field_name("operator")?.byte_range()];
+            match operator {
+                // A known operand can settle these on its own
+                "&&" => match (left, right) {
+                    (Some(0), _) | (_, Some(0)) => Some(0),
+                    (Some(_), Some(_)) => Some(1),
+                    _ => None,
+                },
+                "||" => match (left, right) {
+                    (Some(l), _) if l != 0 => Some(1),
+                    (_, Some(r)) if r != 0 => Some(1),
+                    (Some(_), Some(_)) => Some(0),
+                    _ => None,
+                },
+                _ => {
+                    let (l, r) = (left?, right?);
+                    match operator {
+                        "==" => Some((l == r) as i64),
+                        "!=" => Some((l != r) as i64),
+                        "<" => Some((l < r) as i64),
+                        "<=" => Some((l <= r) as i64),
+                        ">" => Some((l > r) as i64),
+                        ">=" => Some((l >= r) as i64),
+                        "+" => l.checked_add(r),
+                        "-" => l.checked_sub(r),
+                        "*" => l.checked_mul(r),
+                        "/" => l.checked_div(r),
+                        _ => None,
+                    }
+                }
+            }
+        }
+        _ => None,
+    }
+}
+
+fn collect_cfg(node: &Node, source: &str, config: &CfgConfig, regions: &mut Vec<Range>) {
+    // Start of the attributes of the next item when one of them disables it
+    let mut disabled_from: Option<Position> = None;
+    for i in 0..node.named_child_count() {
+        let Some(child) = node.named_child(i) else {
+            continue;
+        };
+        match child.kind() {
+            "attribute_item" => {
+                if disabled_from.is_none() && cfg_holds(&child, source, config) == Some(false) {
+                    disabled_from = Some(node_to_range(&child).start);
+                }
+            }
+            // `#![cfg(..)]` disables everything around it
+            "inner_attribute_item" => {
+                if cfg_holds(&child, source, config) == Some(false) {
+                    regions.push(node_to_range(node));
+                    return;
+                }
+            }
+            "line_comment" | "block_comment" => {}
+            _ => match disabled_from.take() {
+                Some(start) => regions.push(Range::new(start, node_to_range(&child).end)),
+                None => collect_cfg(&child, source, config, regions),
+            },
+        }
+    }
+}
+
+/// Whether the predicate of a `cfg` attribute holds; `None` for other
+/// attributes and undecidable predicates
+fn cfg_holds(attribute_item: &Node, source: &str, config: &CfgConfig) -> Option<bool> {
+    let attribute = attribute_item.named_child(0).filter(|a| a.kind() == "attribute")?;
+    let path = attribute.named_child(0)?;
+    if &source[path.byte_range()] != "cfg" {
+        return None;
+    }
+    let arguments = &source[attribute.child_by_field_name("arguments")?.byte_range()];
+    let mut parser = CfgParser { tokens: cfg_tokens(arguments), pos: 0, config };
+    parser.expect("(")?;
+    let value = parser.predicate()?;
+    parser.expect(")")?;
+    value
+}
+
+fn cfg_tokens(text: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        match c {
+            '(' | ')' | ',' | '=' => tokens.push(c.to_string()),
+            '"' => {
+                let mut end = text.len();
+                for (i, c) in chars.by_ref() {
+                    if c == '"' {
+                        end = i + 1;
+                        break;
+                    }
+                }
+                tokens.push(text[start..end].to_string());
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut end = start + c.len_utf8();
+                while let Some(&(i, c)) = chars.peek() {
+                    if !(c.is_alphanumeric() || c == '_') {
+                        break;
+                    }
+                    end = i + c.len_utf8();
+                    chars.next();
+                }
+                tokens.push(text[start..end].to_string());
+            }
+            _ => {}
+        }
+    }
+    tokens
+}
+
+/// Three-valued evaluation of a cfg predicate (`Some(None)` is "unknown";
+/// the outer `None` a malformed predicate)
+struct CfgParser<'a> {
+    tokens: Vec<String>,
+    pos: usize,
+    config: &'a CfgConfig,
+}
+
+impl CfgParser<'_> {
+    fn next(&mut self) -> Option<&str> {
+        let token = self.tokens.get(self.pos)?;
+        self.pos += 1;
+        Some(token)
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn expect(&mut self, token: &str) -> Option<()> {
+        (self.next()? == token).then_some(())
+    }
+
+    fn predicate(&mut self) -> Option<Option<bool>> {
+        let name = self.next()?.to_string();
+        match (name.as_str(), self.peek()) {
+            ("all" | "any" | "not", Some("(")) => {
+                self.next();
+                let mut values = Vec::new();
+                while self.peek() != Some(")") {
+                    values.push(self.predicate()?);
+                    if self.peek() == Some(",") {
+                        self.next();
+                    }
+                }
+                self.next();
+                Some(match name.as_str() {
+                    "all" if values.contains(&Some(false)) => Some(false),
+                    "all" if values.iter().all(Option::is_some) => Some(true),
+                    "any" if values.contains(&Some(true)) => Some(true),
+                    "any" if values.iter().all(Option::is_some) => Some(false),
+                    "not" if values.len() == 1 => values[0].map(|v| !v),
+                    _ => None,
+                })
+            }
+            (_, Some("=")) => {
+                self.next();
+                let value = self.next()?.trim_matches('"').to_string();
+                Some(self.option(&name, Some(&value)))
+            }
+            _ => Some(self.option(&name, None)),
+        }
+    }
+
+    /// Value of a cfg option, for the host target as rust-analyzer assumes
+    fn option(&self, name: &str, value: Option<&str>) -> Option<bool> {
+        use std::env::consts::{FAMILY, OS};
+        match (name, value) {
+            ("test" | "debug_assertions", None) => Some(true),
+            ("unix" | "windows", None) => Some(name == FAMILY),
+            ("target_family", Some(value)) => Some(value == FAMILY),
+            ("target_os", Some(value)) => Some(value == OS),
+            ("feature", Some(value)) => self.config.features.as_ref().map(|f| f.contains(value)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LanguageParser;
+
+    fn regions(lang: LanguageId, source: &str, config: &CfgConfig) -> Vec<Range> {
+        let mut parser = LanguageParser::new();
+        parser.set_language(lang).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        inactive_regions(lang, &tree, source, config)
+    }
+
+    #[test]
+    fn test_preprocessor_branches() {
+        let source = "#if 0\nint dead;\n#elif defined(FOO) && !BAR\nint foo;\n#else\nint other;\n#endif\n#ifndef GUARD_H\nint guard;\n#endif\n";
+
+        // Without defines only the literal `#if 0` is decided
+        assert_eq!(regions(LanguageId::C, source, &CfgConfig::default()), vec![Range::from_coords(1, 0, 2, 0)]);
+
+        let config = CfgConfig {
+            defines: Some(HashMap::from([("FOO".to_string(), None)])),
+            ..CfgConfig::default()
+        };
+        assert_eq!(
+            regions(LanguageId::Cpp, source, &config),
+            vec![Range::from_coords(1, 0, 2, 0), Range::from_coords(5, 0, 6, 0)]
+        );
+
+        let config = CfgConfig { defines: Some(HashMap::new()), ..CfgConfig::default() };
+        assert_eq!(
+            regions(LanguageId::C, source, &config),
+            vec![Range::from_coords(1, 0, 2, 0), Range::from_coords(3, 0, 4, 0)]
+        );
+    }
+
+    #[test]
+    fn test_rust_cfg_items_and_symbols() {
+        let source = "#[cfg(feature = \"extra\")]\n#[inline]\nfn extra() {}\n\n#[cfg(not(test))]\nfn prod() {}\n\n#[cfg(any(feature = \"extra\", target_arch = \"wasm32\"))]\nfn maybe() {}\n\nfn always() {}\n";
+        let config = CfgConfig { features: Some(HashSet::new()), ..CfgConfig::default() };
+        let inactive = regions(LanguageId::Rust, source, &config);
+        assert_eq!(inactive, vec![Range::from_coords(0, 0, 2, 13), Range::from_coords(4, 0, 5, 12)]);
+
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Rust).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let mut symbols = crate::extract_symbols(LanguageId::Rust, &tree, source);
+        remove_inactive_symbols(&mut symbols, &inactive);
+        let names: Vec<_> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["maybe", "always"]);
+
+        // Unknown features decide nothing
+        assert_eq!(regions(LanguageId::Rust, source, &CfgConfig::default()), vec![Range::from_coords(4, 0, 5, 12)]);
+    }
+}
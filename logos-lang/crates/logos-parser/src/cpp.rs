@@ -2,13 +2,18 @@
 
 use logos_core::{Range, Symbol, SymbolKind};
 use tree_sitter::{Node, Tree};
-use crate::node_to_range;
+use crate::{c, conditional, node_to_range, signature};
 
-/// Extract symbols from a C++ AST
+/// `#include` directives are read as in C
+pub use crate::c::{includes, Include};
+
+/// Extract symbols from a C++ AST, with `#define` macros and without the
+/// declarations of dead branches (`#if 0`)
 pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
     let mut symbols = Vec::new();
     let root = tree.root_node();
     extract_symbols_from_node(&root, source, &mut symbols);
+    conditional::remove_inactive_symbols(&mut symbols, &conditional::dead_preprocessor_regions(tree, source));
     symbols
 }
 
@@ -17,12 +22,9 @@ fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol
         "function_definition" => {
             if let Some(declarator) = node.child_by_field_name("declarator") {
                 if let Some((name, sel_range)) = find_function_name_info(&declarator, source) {
-                    symbols.push(Symbol::new(
-                        name,
-                        SymbolKind::Function,
-                        node_to_range(node),
-                        sel_range,
-                    ));
+                    let mut symbol = Symbol::new(name, SymbolKind::Function, node_to_range(node), sel_range);
+                    symbol.detail = Some(signature(node, source));
+                    symbols.push(symbol);
                 }
             }
         }
@@ -96,6 +98,9 @@ fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol
                 }
             }
         }
+        "preproc_def" | "preproc_function_def" => {
+            symbols.extend(c::macro_symbol(node, source));
+        }
         "enum_specifier" => {
             if let Some(name_node) = node.child_by_field_name("name") {
                 let name = get_node_text(&name_node, source);
@@ -164,20 +169,19 @@ fn extract_class_members(node: &Node, source: &str, symbols: &mut Vec<Symbol>) {
                 "function_definition" | "declaration" => {
                     if let Some(declarator) = child.child_by_field_name("declarator") {
                         if let Some((name, sel_range)) = find_function_name_info(&declarator, source) {
-                            let kind = if child.kind() == "function_definition" {
-                                SymbolKind::Method
-                            } else if declarator.kind() == "function_declarator" {
+                            let kind = if child.kind() == "function_definition"
+                                || declarator.kind() == "function_declarator"
+                            {
                                 SymbolKind::Method
                             } else {
                                 SymbolKind::Field
                             };
 
-                            symbols.push(Symbol::new(
-                                name,
-                                kind,
-                                node_to_range(&child),
-                                sel_range,
-                            ));
+                            let mut symbol = Symbol::new(name, kind, node_to_range(&child), sel_range);
+                            if kind == SymbolKind::Method {
+                                symbol.detail = Some(signature(&child, source));
+                            }
+                            symbols.push(symbol);
                         }
                     }
                 }
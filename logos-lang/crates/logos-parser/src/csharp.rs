@@ -0,0 +1,338 @@
+//! C#-specific parsing and symbol extraction
+
+use logos_core::{Range, Symbol, SymbolKind};
+use tree_sitter::{Node, Tree};
+use crate::node_to_range;
+
+/// Extract symbols from a C# AST
+pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let root = tree.root_node();
+
+    // `namespace Foo;` scopes every declaration after it in the file
+    let mut file_namespace: Option<Symbol> = None;
+    for i in 0..root.named_child_count() {
+        if let Some(child) = root.named_child(i) {
+            if child.kind() == "file_scoped_namespace_declaration" {
+                if let Some(name_node) = child.child_by_field_name("name") {
+                    let name = get_node_text(&name_node, source);
+                    let range = Range::new(node_to_range(&child).start, node_to_range(&root).end);
+                    symbols.extend(file_namespace.take());
+                    file_namespace = Some(Symbol::new(
+                        name,
+                        SymbolKind::Namespace,
+                        range,
+                        node_to_range(&name_node),
+                    ));
+                }
+                continue;
+            }
+            match file_namespace.as_mut() {
+                Some(namespace) => extract_symbols_from_node(&child, source, &mut namespace.children),
+                None => extract_symbols_from_node(&child, source, &mut symbols),
+            }
+        }
+    }
+    symbols.extend(file_namespace);
+
+    symbols
+}
+
+fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol>) {
+    match node.kind() {
+        "namespace_declaration" => {
+            if let Some(name_node) = node.child_by_field_name("name") {
+                let name = get_node_text(&name_node, source);
+                let mut symbol = Symbol::new(
+                    name,
+                    SymbolKind::Namespace,
+                    node_to_range(node),
+                    node_to_range(&name_node),
+                );
+
+                if let Some(body) = node.child_by_field_name("body") {
+                    let mut children = Vec::new();
+                    for i in 0..body.named_child_count() {
+                        if let Some(child) = body.named_child(i) {
+                            extract_symbols_from_node(&child, source, &mut children);
+                        }
+                    }
+                    symbol.children = children;
+                }
+
+                symbols.push(symbol);
+            }
+        }
+        "class_declaration" | "record_declaration" | "struct_declaration" | "interface_declaration" => {
+            if let Some(name_node) = node.child_by_field_name("name") {
+                let name = get_node_text(&name_node, source);
+                let kind = match node.kind() {
+                    "struct_declaration" => SymbolKind::Struct,
+                    "interface_declaration" => SymbolKind::Interface,
+                    _ => SymbolKind::Class,
+                };
+                let mut symbol = Symbol::new(
+                    name,
+                    kind,
+                    node_to_range(node),
+                    node_to_range(&name_node),
+                );
+                if node.kind() == "record_declaration" {
+                    symbol.detail = Some("record".to_string());
+                }
+
+                if let Some(body) = node.child_by_field_name("body") {
+                    let mut children = Vec::new();
+                    extract_type_members(&body, source, &mut children);
+                    symbol.children = children;
+                }
+
+                symbols.push(symbol);
+            }
+        }
+        "enum_declaration" => {
+            if let Some(name_node) = node.child_by_field_name("name") {
+                let name = get_node_text(&name_node, source);
+                let mut symbol = Symbol::new(
+                    name,
+                    SymbolKind::Enum,
+                    node_to_range(node),
+                    node_to_range(&name_node),
+                );
+
+                if let Some(body) = node.child_by_field_name("body") {
+                    let mut children = Vec::new();
+                    extract_enum_members(&body, source, &mut children);
+                    symbol.children = children;
+                }
+
+                symbols.push(symbol);
+            }
+        }
+        // Top-level statements and bodies hold no declarations worth outlining
+        "global_statement" | "block" | "arrow_expression_clause" => {}
+        _ => {
+            for i in 0..node.named_child_count() {
+                if let Some(child) = node.named_child(i) {
+                    extract_symbols_from_node(&child, source, symbols);
+                }
+            }
+        }
+    }
+}
+
+fn extract_type_members(node: &Node, source: &str, symbols: &mut Vec<Symbol>) {
+    for i in 0..node.named_child_count() {
+        if let Some(child) = node.named_child(i) {
+            match child.kind() {
+                "method_declaration" | "constructor_declaration" => {
+                    if let Some(name_node) = child.child_by_field_name("name") {
+                        let name = get_node_text(&name_node, source);
+                        let kind = if child.kind() == "constructor_declaration" {
+                            SymbolKind::Constructor
+                        } else {
+                            SymbolKind::Method
+                        };
+                        let mut symbol = Symbol::new(
+                            name,
+                            kind,
+                            node_to_range(&child),
+                            node_to_range(&name_node),
+                        );
+                        if let Some(params) = child.child_by_field_name("parameters") {
+                            symbol.detail = Some(get_node_text(&params, source));
+                        }
+                        symbols.push(symbol);
+                    }
+                }
+                "property_declaration" => {
+                    if let Some(name_node) = child.child_by_field_name("name") {
+                        let name = get_node_text(&name_node, source);
+                        let mut symbol = Symbol::new(
+                            name,
+                            SymbolKind::Property,
+                            node_to_range(&child),
+                            node_to_range(&name_node),
+                        );
+                        if let Some(type_node) = child.child_by_field_name("type") {
+                            symbol.detail = Some(get_node_text(&type_node, source));
+                        }
+                        symbols.push(symbol);
+                    }
+                }
+                "field_declaration" | "event_field_declaration" => {
+                    let kind = if child.kind() == "event_field_declaration" {
+                        SymbolKind::Event
+                    } else if has_modifier(&child, source, "const") {
+                        SymbolKind::Constant
+                    } else {
+                        SymbolKind::Field
+                    };
+                    extract_variable_declarators(&child, source, kind, symbols);
+                }
+                _ => extract_symbols_from_node(&child, source, symbols),
+            }
+        }
+    }
+}
+
+/// `int a, b;` declares one field per declarator
+fn extract_variable_declarators(node: &Node, source: &str, kind: SymbolKind, symbols: &mut Vec<Symbol>) {
+    for i in 0..node.named_child_count() {
+        if let Some(declaration) = node.named_child(i) {
+            if declaration.kind() != "variable_declaration" {
+                continue;
+            }
+            for j in 0..declaration.named_child_count() {
+                if let Some(declarator) = declaration.named_child(j) {
+                    if declarator.kind() != "variable_declarator" {
+                        continue;
+                    }
+                    if let Some(name_node) = declarator.child_by_field_name("name") {
+                        let name = get_node_text(&name_node, source);
+                        symbols.push(Symbol::new(
+                            name,
+                            kind,
+                            node_to_range(node),
+                            node_to_range(&name_node),
+                        ));
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn extract_enum_members(node: &Node, source: &str, symbols: &mut Vec<Symbol>) {
+    for i in 0..node.named_child_count() {
+        if let Some(child) = node.named_child(i) {
+            if child.kind() == "enum_member_declaration" {
+                if let Some(name_node) = child.child_by_field_name("name") {
+                    let name = get_node_text(&name_node, source);
+                    symbols.push(Symbol::new(
+                        name,
+                        SymbolKind::EnumMember,
+                        node_to_range(&child),
+                        node_to_range(&name_node),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn has_modifier(node: &Node, source: &str, modifier: &str) -> bool {
+    (0..node.named_child_count())
+        .filter_map(|i| node.named_child(i))
+        .any(|child| child.kind() == "modifier" && get_node_text(&child, source) == modifier)
+}
+
+fn get_node_text(node: &Node, source: &str) -> String {
+    source[node.byte_range()].to_string()
+}
+
+/// Get C# keywords
+pub fn get_keywords() -> &'static [&'static str] {
+    &[
+        "abstract", "as", "async", "await", "base", "bool", "break", "byte",
+        "case", "catch", "char", "checked", "class", "const", "continue",
+        "decimal", "default", "delegate", "do", "double", "else", "enum",
+        "event", "explicit", "extern", "false", "finally", "fixed", "float",
+        "for", "foreach", "get", "goto", "if", "implicit", "in", "init", "int",
+        "interface", "internal", "is", "lock", "long", "namespace", "new",
+        "null", "object", "operator", "out", "override", "params", "partial",
+        "private", "protected", "public", "readonly", "record", "ref",
+        "return", "sbyte", "sealed", "set", "short", "sizeof", "stackalloc",
+        "static", "string", "struct", "switch", "this", "throw", "true", "try",
+        "typeof", "uint", "ulong", "unchecked", "unsafe", "ushort", "using",
+        "var", "virtual", "void", "volatile", "when", "where", "while", "yield",
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LanguageId, LanguageParser};
+
+    fn parse(source: &str) -> Vec<Symbol> {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::CSharp).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        extract_symbols(&tree, source)
+    }
+
+    fn names(symbols: &[Symbol]) -> Vec<(&str, SymbolKind)> {
+        symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect()
+    }
+
+    #[test]
+    fn test_namespaces_and_members() {
+        let symbols = parse(
+            r#"
+using System;
+namespace Acme.Widgets {
+    public interface IShape { double Area(); }
+    public class Circle : IShape {
+        private const double Pi = 3.14;
+        private double radius, other;
+        public string Name { get; set; }
+        public event EventHandler Changed;
+        public Circle(double r) { radius = r; }
+        public double Area() { var local = 1; return Pi * radius; }
+        public enum Kind { Small, Large }
+    }
+    public struct Point { public int X; }
+}
+"#,
+        );
+
+        assert_eq!(names(&symbols), vec![("Acme.Widgets", SymbolKind::Namespace)]);
+        let namespace = &symbols[0];
+        assert_eq!(
+            names(&namespace.children),
+            vec![
+                ("IShape", SymbolKind::Interface),
+                ("Circle", SymbolKind::Class),
+                ("Point", SymbolKind::Struct),
+            ]
+        );
+        assert_eq!(names(&namespace.children[0].children), vec![("Area", SymbolKind::Method)]);
+
+        let circle = &namespace.children[1];
+        assert_eq!(
+            names(&circle.children),
+            vec![
+                ("Pi", SymbolKind::Constant),
+                ("radius", SymbolKind::Field),
+                ("other", SymbolKind::Field),
+                ("Name", SymbolKind::Property),
+                ("Changed", SymbolKind::Event),
+                ("Circle", SymbolKind::Constructor),
+                ("Area", SymbolKind::Method),
+                ("Kind", SymbolKind::Enum),
+            ]
+        );
+        assert_eq!(circle.children[5].detail.as_deref(), Some("(double r)"));
+        assert_eq!(circle.children[7].children.len(), 2);
+    }
+
+    #[test]
+    fn test_file_scoped_namespace() {
+        let symbols = parse(
+            r#"
+namespace Acme;
+
+public record Person(string First, string Last);
+class Program { static void Main() {} }
+"#,
+        );
+
+        assert_eq!(names(&symbols), vec![("Acme", SymbolKind::Namespace)]);
+        assert_eq!(
+            names(&symbols[0].children),
+            vec![("Person", SymbolKind::Class), ("Program", SymbolKind::Class)]
+        );
+        assert_eq!(symbols[0].children[0].detail.as_deref(), Some("record"));
+        assert_eq!(symbols[0].children[1].children[0].name, "Main");
+    }
+}
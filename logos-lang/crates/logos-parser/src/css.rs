@@ -0,0 +1,450 @@
+//! CSS and SCSS parsing, symbol extraction and colors
+
+use logos_core::{Range, Symbol, SymbolKind};
+use tree_sitter::{Node, Tree};
+use crate::{node_to_range, Utf16Columns};
+
+/// Named colors recognized in property values: the basic keywords and the
+/// most used extended ones
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aqua", 0x00ffff), ("beige", 0xf5f5dc), ("black", 0x000000), ("blue", 0x0000ff),
+    ("brown", 0xa52a2a), ("coral", 0xff7f50), ("crimson", 0xdc143c), ("cyan", 0x00ffff),
+    ("darkgray", 0xa9a9a9), ("darkgrey", 0xa9a9a9), ("fuchsia", 0xff00ff), ("gold", 0xffd700),
+    ("gray", 0x808080), ("green", 0x008000), ("grey", 0x808080), ("indigo", 0x4b0082),
+    ("ivory", 0xfffff0), ("khaki", 0xf0e68c), ("lavender", 0xe6e6fa), ("lightgray", 0xd3d3d3),
+    ("lightgrey", 0xd3d3d3), ("lime", 0x00ff00), ("magenta", 0xff00ff), ("maroon", 0x800000),
+    ("navy", 0x000080), ("olive", 0x808000), ("orange", 0xffa500), ("pink", 0xffc0cb),
+    ("purple", 0x800080), ("rebeccapurple", 0x663399), ("red", 0xff0000), ("salmon", 0xfa8072),
+    ("silver", 0xc0c0c0), ("skyblue", 0x87ceeb), ("slategray", 0x708090), ("steelblue", 0x4682b4),
+    ("teal", 0x008080), ("tomato", 0xff6347), ("turquoise", 0x40e0d0), ("violet", 0xee82ee),
+    ("white", 0xffffff), ("whitesmoke", 0xf5f5f5), ("yellow", 0xffff00),
+];
+
+/// An sRGB color, each component between 0 and 1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub red: f64,
+    pub green: f64,
+    pub blue: f64,
+    pub alpha: f64,
+}
+
+/// A color written in a stylesheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColorInformation {
+    pub range: Range,
+    pub color: Color,
+}
+
+/// Extract symbols from a CSS or SCSS AST.
+///
+/// Rule sets are listed under their selector text, custom properties
+/// (`--name`) and SCSS variables as variables, and block at-rules such as
+/// `@media` under their prelude. Keyframes, SCSS mixins and functions are
+/// listed by name. Nested rules and declarations become children.
+pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let root = tree.root_node();
+    extract_symbols_from_node(&root, source, &mut symbols);
+    symbols
+}
+
+fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol>) {
+    for i in 0..node.named_child_count() {
+        let Some(child) = node.named_child(i) else {
+            continue;
+        };
+        match child.kind() {
+            "rule_set" => {
+                let Some(selectors) = find_child(&child, "selectors") else {
+                    continue;
+                };
+                let name = collapse_whitespace(&get_node_text(&selectors, source));
+                push_block(&child, name, SymbolKind::Class, &selectors, source, symbols);
+            }
+            "declaration" => {
+                let Some(property) = find_child(&child, "property_name") else {
+                    continue;
+                };
+                let name = get_node_text(&property, source);
+                if name.starts_with("--") || name.starts_with('$') {
+                    symbols.push(Symbol::new(name, SymbolKind::Variable, node_to_range(&child), node_to_range(&property)));
+                }
+            }
+            "keyframes_statement" => {
+                let Some(name_node) = find_child(&child, "keyframes_name") else {
+                    continue;
+                };
+                let name = get_node_text(&name_node, source);
+                // The from/to steps aren't worth listing
+                let mut symbol = Symbol::new(name, SymbolKind::Object, node_to_range(&child), node_to_range(&name_node));
+                symbol.detail = Some("@keyframes".to_string());
+                symbols.push(symbol);
+            }
+            "media_statement" | "supports_statement" | "at_rule" => {
+                let Some(block) = find_child(&child, "block") else {
+                    // `@import`, `@charset` and other statements without a body
+                    continue;
+                };
+                let prelude = &source[child.start_byte()..block.start_byte()];
+                let name = collapse_whitespace(prelude);
+                push_block(&child, name, SymbolKind::Namespace, &child, source, symbols);
+            }
+            "mixin_statement" | "function_statement" => {
+                let Some(name_node) = child.child_by_field_name("name") else {
+                    continue;
+                };
+                let name = get_node_text(&name_node, source);
+                let detail = if child.kind() == "mixin_statement" { "@mixin" } else { "@function" };
+                let mut symbol = Symbol::new(name, SymbolKind::Function, node_to_range(&child), node_to_range(&name_node));
+                symbol.detail = Some(detail.to_string());
+                symbols.push(symbol);
+            }
+            _ => extract_symbols_from_node(&child, source, symbols),
+        }
+    }
+}
+
+/// Push a symbol for a rule or at-rule, with the contents of its block as
+/// children
+fn push_block(node: &Node, name: String, kind: SymbolKind, selection: &Node, source: &str, symbols: &mut Vec<Symbol>) {
+    let mut symbol = Symbol::new(name, kind, node_to_range(node), node_to_range(selection));
+    if let Some(block) = find_child(node, "block") {
+        extract_symbols_from_node(&block, source, &mut symbol.children);
+    }
+    symbols.push(symbol);
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Colors written in a CSS or SCSS document: hex colors, `rgb()`,
+/// `rgba()`, `hsl()` and `hsla()` calls with literal arguments, and named
+/// colors in property values. Ranges are in UTF-16 columns.
+pub fn colors(tree: &Tree, source: &str) -> Vec<ColorInformation> {
+    let mut colors = Vec::new();
+    collect_colors(tree.root_node(), source, &mut colors);
+    let columns = Utf16Columns::new(source);
+    for color in &mut colors {
+        columns.convert_range(&mut color.range);
+    }
+    colors
+}
+
+fn collect_colors(node: Node, source: &str, colors: &mut Vec<ColorInformation>) {
+    let text = get_node_text(&node, source);
+    let color = match node.kind() {
+        "color_value" => hex_color(text.trim_start_matches('#')),
+        "call_expression" => color_function(&node, source),
+        "plain_value" if in_declaration(&node) => named_color(&text),
+        _ => None,
+    };
+    if let Some(color) = color {
+        colors.push(ColorInformation { range: node_to_range(&node), color });
+        return;
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_colors(child, source, colors);
+    }
+}
+
+/// Whether a value belongs to a declaration, as opposed to a selector or
+/// an at-rule prelude
+fn in_declaration(node: &Node) -> bool {
+    let mut parent = node.parent();
+    while let Some(node) = parent {
+        match node.kind() {
+            "declaration" => return true,
+            "block" | "rule_set" | "stylesheet" => return false,
+            _ => parent = node.parent(),
+        }
+    }
+    false
+}
+
+/// `#rgb`, `#rgba`, `#rrggbb` or `#rrggbbaa`, without the `#`
+fn hex_color(hex: &str) -> Option<Color> {
+    let digits: Vec<u8> = hex.chars().map(|c| c.to_digit(16).map(|d| d as u8)).collect::<Option<_>>()?;
+    let channels: Vec<f64> = match digits.len() {
+        3 | 4 => digits.iter().map(|d| f64::from(d * 17) / 255.0).collect(),
+        6 | 8 => digits.chunks(2).map(|pair| f64::from(pair[0] * 16 + pair[1]) / 255.0).collect(),
+        _ => return None,
+    };
+    Some(Color { red: channels[0], green: channels[1], blue: channels[2], alpha: channels.get(3).copied().unwrap_or(1.0) })
+}
+
+fn named_color(name: &str) -> Option<Color> {
+    let name = name.to_ascii_lowercase();
+    if name == "transparent" {
+        return Some(Color { red: 0.0, green: 0.0, blue: 0.0, alpha: 0.0 });
+    }
+    let (_, rgb) = NAMED_COLORS.iter().find(|(named, _)| *named == name)?;
+    let channel = |shift: u32| f64::from((rgb >> shift) & 0xff) / 255.0;
+    Some(Color { red: channel(16), green: channel(8), blue: channel(0), alpha: 1.0 })
+}
+
+/// `rgb(255, 0, 0)`, `rgba(255 0 0 / 50%)`, `hsl(120deg 100% 50%)`
+fn color_function(call: &Node, source: &str) -> Option<Color> {
+    let name = get_node_text(&find_child(call, "function_name")?, source).to_ascii_lowercase();
+    let arguments = get_node_text(&find_child(call, "arguments")?, source);
+    let args: Vec<&str> = arguments
+        .trim_start_matches('(')
+        .trim_end_matches(')')
+        .split(|c: char| c == ',' || c == '/' || c.is_whitespace())
+        .filter(|arg| !arg.is_empty())
+        .collect();
+    if !(3..=4).contains(&args.len()) {
+        return None;
+    }
+    let alpha = match args.get(3) {
+        Some(arg) => fraction(arg, 1.0)?,
+        None => 1.0,
+    };
+    match name.as_str() {
+        "rgb" | "rgba" => Some(Color {
+            red: fraction(args[0], 255.0)?,
+            green: fraction(args[1], 255.0)?,
+            blue: fraction(args[2], 255.0)?,
+            alpha,
+        }),
+        "hsl" | "hsla" => {
+            let hue = args[0].trim_end_matches("deg").parse::<f64>().ok()?.rem_euclid(360.0);
+            Some(hsl_to_rgb(hue, fraction(args[1], 100.0)?, fraction(args[2], 100.0)?, alpha))
+        }
+        _ => None,
+    }
+}
+
+/// A number out of `scale`, or a percentage, as a fraction between 0 and 1
+fn fraction(arg: &str, scale: f64) -> Option<f64> {
+    let value = match arg.strip_suffix('%') {
+        Some(percent) => percent.parse::<f64>().ok()? / 100.0,
+        None => arg.parse::<f64>().ok()? / scale,
+    };
+    Some(value.clamp(0.0, 1.0))
+}
+
+fn hsl_to_rgb(hue: f64, saturation: f64, lightness: f64, alpha: f64) -> Color {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0) % 2.0 - 1.0).abs());
+    let m = lightness - chroma / 2.0;
+    let (red, green, blue) = match (hue / 60.0) as u32 {
+        0 => (chroma, x, 0.0),
+        1 => (x, chroma, 0.0),
+        2 => (0.0, chroma, x),
+        3 => (0.0, x, chroma),
+        4 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    Color { red: red + m, green: green + m, blue: blue + m, alpha }
+}
+
+/// Hue in degrees, saturation and lightness in percents
+fn rgb_to_hsl(color: Color) -> (u32, u32, u32) {
+    let Color { red, green, blue, .. } = color;
+    let max = red.max(green).max(blue);
+    let min = red.min(green).min(blue);
+    let lightness = (max + min) / 2.0;
+    let delta = max - min;
+    let (hue, saturation) = if delta <= f64::EPSILON {
+        (0.0, 0.0)
+    } else {
+        let hue = if max == red {
+            ((green - blue) / delta).rem_euclid(6.0)
+        } else if max == green {
+            (blue - red) / delta + 2.0
+        } else {
+            (red - green) / delta + 4.0
+        };
+        (hue * 60.0, delta / (1.0 - (2.0 * lightness - 1.0).abs()))
+    };
+    ((hue.round() as u32) % 360, (saturation * 100.0).round() as u32, (lightness * 100.0).round() as u32)
+}
+
+/// Ways to write a color: hex, `rgb()` and `hsl()`, with the alpha
+/// channel when the color isn't opaque
+pub fn color_presentations(color: Color) -> Vec<String> {
+    let [red, green, blue] = [color.red, color.green, color.blue].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+    let (hue, saturation, lightness) = rgb_to_hsl(color);
+    if color.alpha >= 1.0 {
+        return vec![
+            format!("#{:02x}{:02x}{:02x}", red, green, blue),
+            format!("rgb({}, {}, {})", red, green, blue),
+            format!("hsl({}, {}%, {}%)", hue, saturation, lightness),
+        ];
+    }
+    let alpha = (color.alpha.clamp(0.0, 1.0) * 100.0).round() / 100.0;
+    vec![
+        format!("#{:02x}{:02x}{:02x}{:02x}", red, green, blue, (alpha * 255.0).round() as u8),
+        format!("rgba({}, {}, {}, {})", red, green, blue, alpha),
+        format!("hsla({}, {}%, {}%, {})", hue, saturation, lightness, alpha),
+    ]
+}
+
+/// Class names a selector uses, with their byte offsets in it: `btn` and
+/// `active` in `a.btn:not(.active)`. Attribute selectors and strings
+/// aren't searched.
+pub fn selector_classes(selector: &str) -> Vec<(usize, &str)> {
+    let is_name = |c: char| c.is_alphanumeric() || c == '-' || c == '_';
+    let mut classes = Vec::new();
+    let mut in_attribute = false;
+    let mut quote = None;
+    for (i, c) in selector.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[') => in_attribute = true,
+            (None, ']') => in_attribute = false,
+            (None, '.') if !in_attribute => {
+                let start = i + 1;
+                let name_len = selector[start..].find(|c: char| !is_name(c)).unwrap_or(selector.len() - start);
+                let name = &selector[start..start + name_len];
+                if name.starts_with(|c: char| is_name(c) && !c.is_ascii_digit()) {
+                    classes.push((start, name));
+                }
+            }
+            _ => {}
+        }
+    }
+    classes
+}
+
+fn find_child<'a>(node: &Node<'a>, kind: &str) -> Option<Node<'a>> {
+    (0..node.named_child_count())
+        .filter_map(|i| node.named_child(i))
+        .find(|child| child.kind() == kind)
+}
+
+fn get_node_text(node: &Node, source: &str) -> String {
+    source[node.byte_range()].to_string()
+}
+
+/// Get CSS at-rules and common property names
+pub fn get_keywords() -> &'static [&'static str] {
+    &[
+        "@charset", "@container", "@font-face", "@import", "@keyframes",
+        "@layer", "@media", "@namespace", "@page", "@supports",
+        "!important", "inherit", "initial", "unset", "revert",
+        "background", "border", "color", "display", "flex", "font-family",
+        "font-size", "grid", "height", "margin", "padding", "position",
+        "transform", "transition", "width", "z-index",
+    ]
+}
+
+/// Get SCSS directives on top of the CSS keywords
+pub fn get_scss_keywords() -> &'static [&'static str] {
+    &[
+        "@charset", "@container", "@font-face", "@import", "@keyframes",
+        "@layer", "@media", "@namespace", "@page", "@supports",
+        "!important", "inherit", "initial", "unset", "revert",
+        "background", "border", "color", "display", "flex", "font-family",
+        "font-size", "grid", "height", "margin", "padding", "position",
+        "transform", "transition", "width", "z-index",
+        "@use", "@forward", "@mixin", "@include", "@function", "@return",
+        "@extend", "@if", "@else", "@each", "@for", "@while", "@debug",
+        "@warn", "@error", "!default", "!global",
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LanguageId, LanguageParser};
+
+    fn parse(lang: LanguageId, source: &str) -> Vec<Symbol> {
+        let mut parser = LanguageParser::new();
+        parser.set_language(lang).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        extract_symbols(&tree, source)
+    }
+
+    #[test]
+    fn test_css_symbols() {
+        let symbols = parse(
+            LanguageId::Css,
+            r#":root { --main-color: #333; }
+.btn,
+a.link:hover > span { color: var(--main-color); }
+@import url("base.css");
+@media (max-width: 600px) {
+  .btn { padding: 0; }
+}
+@keyframes spin { from { opacity: 0; } to { opacity: 1; } }
+"#,
+        );
+
+        let names: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            names,
+            vec![
+                (":root", SymbolKind::Class),
+                (".btn, a.link:hover > span", SymbolKind::Class),
+                ("@media (max-width: 600px)", SymbolKind::Namespace),
+                ("spin", SymbolKind::Object),
+            ]
+        );
+        assert_eq!(symbols[0].children[0].name, "--main-color");
+        assert_eq!(symbols[0].children[0].kind, SymbolKind::Variable);
+        assert_eq!(symbols[2].children[0].name, ".btn");
+        assert!(symbols[3].children.is_empty());
+    }
+
+    #[test]
+    fn test_scss_symbols() {
+        let symbols = parse(
+            LanguageId::Scss,
+            r#"$primary: #333;
+@mixin flex($dir) { display: flex; }
+@function double($n) { @return $n * 2; }
+.card {
+  &__title { @include flex(row); }
+  @media print { display: none; }
+}
+"#,
+        );
+
+        let names: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("$primary", SymbolKind::Variable),
+                ("flex", SymbolKind::Function),
+                ("double", SymbolKind::Function),
+                (".card", SymbolKind::Class),
+            ]
+        );
+        assert_eq!(symbols[1].detail.as_deref(), Some("@mixin"));
+        let nested: Vec<_> = symbols[3].children.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(nested, vec!["&__title", "@media print"]);
+    }
+
+    #[test]
+    fn test_colors() {
+        let source = ".a { color: #f00; background: rgba(0, 0, 255, 50%); border: 1px solid red; }\n.b { color: hsl(120deg 100% 50%); fill: var(--x); }\n";
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Css).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let found = colors(&tree, source);
+
+        let rgba: Vec<_> = found.iter().map(|c| (c.color.red, c.color.green, c.color.blue, c.color.alpha)).collect();
+        assert_eq!(
+            rgba,
+            vec![(1.0, 0.0, 0.0, 1.0), (0.0, 0.0, 1.0, 0.5), (1.0, 0.0, 0.0, 1.0), (0.0, 1.0, 0.0, 1.0)]
+        );
+        assert_eq!(found[0].range, Range::from_coords(0, 12, 0, 16));
+
+        assert_eq!(color_presentations(found[0].color), vec!["#ff0000", "rgb(255, 0, 0)", "hsl(0, 100%, 50%)"]);
+        assert_eq!(color_presentations(found[1].color), vec!["#0000ff80", "rgba(0, 0, 255, 0.5)", "hsla(240, 100%, 50%, 0.5)"]);
+    }
+
+    #[test]
+    fn test_selector_classes() {
+        let classes = selector_classes("a.btn:not(.active) [data-x=\".no\"] > .x-1, .5");
+        let names: Vec<_> = classes.iter().map(|(_, name)| *name).collect();
+        assert_eq!(names, vec!["btn", "active", "x-1"]);
+        assert_eq!(classes[1].0, 11);
+    }
+}
@@ -0,0 +1,313 @@
+//! Dart parsing and symbol extraction
+
+use logos_core::{Range, Symbol, SymbolKind};
+use tree_sitter::{Node, Tree};
+use crate::node_to_range;
+
+/// Extract symbols from a Dart AST.
+///
+/// Classes, mixins and extensions give classes with their members as
+/// children, enums their constants, and top-level functions, getters and
+/// variables a symbol each. Constructors are named as written (`Point`,
+/// `Point.origin`).
+pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
+    let root = tree.root_node();
+    let mut symbols = Vec::new();
+    let mut cursor = root.walk();
+    let children: Vec<Node> = root.named_children(&mut cursor).collect();
+
+    for (i, node) in children.iter().enumerate() {
+        match node.kind() {
+            "class_definition" | "mixin_declaration" | "extension_declaration" => {
+                symbols.extend(class_symbol(node, source));
+            }
+            "enum_declaration" => {
+                symbols.extend(enum_symbol(node, source));
+            }
+            "lambda_expression" => {
+                // Top-level functions parse as a signature plus a body
+                if let Some(signature) = node.child_by_field_name("parameters") {
+                    symbols.extend(signature_symbol(&signature, node_to_range(node), source, false));
+                }
+            }
+            "function_signature" | "getter_signature" | "setter_signature" => {
+                // Top-level getters and setters leave the body as a sibling
+                let range = match children.get(i + 1).filter(|next| next.kind() == "function_body") {
+                    Some(body) => Range::new(node_to_range(node).start, node_to_range(body).end),
+                    None => node_to_range(node),
+                };
+                symbols.extend(signature_symbol(node, range, source, false));
+            }
+            "local_variable_declaration" => {
+                symbols.extend(variable_symbols(node, source));
+            }
+            _ => {}
+        }
+    }
+    symbols
+}
+
+fn class_symbol(node: &Node, source: &str) -> Option<Symbol> {
+    let name_node = node
+        .child_by_field_name("name")
+        .or_else(|| find_child(node, "identifier"))?;
+    let name = get_node_text(&name_node, source);
+    let mut symbol = Symbol::new(name, SymbolKind::Class, node_to_range(node), node_to_range(&name_node));
+    symbol.detail = match node.kind() {
+        "mixin_declaration" => Some("mixin".to_string()),
+        "extension_declaration" => node
+            .child_by_field_name("class")
+            .map(|on| format!("extension on {}", get_node_text(&on, source))),
+        _ => None,
+    };
+
+    let body = node
+        .child_by_field_name("body")
+        .or_else(|| find_child(node, "class_body"));
+    if let Some(body) = body {
+        for i in 0..body.named_child_count() {
+            if let Some(member) = body.named_child(i) {
+                if member.kind() == "class_member_definition" {
+                    symbol.children.extend(member_symbols(&member, source));
+                }
+            }
+        }
+    }
+    Some(symbol)
+}
+
+fn member_symbols(member: &Node, source: &str) -> Vec<Symbol> {
+    let range = node_to_range(member);
+    let Some(inner) = find_child(member, "method_signature").or_else(|| find_child(member, "declaration")) else {
+        return Vec::new();
+    };
+    for i in 0..inner.named_child_count() {
+        let Some(child) = inner.named_child(i) else {
+            continue;
+        };
+        match child.kind() {
+            "function_signature" | "getter_signature" | "setter_signature" | "operator_signature"
+            | "constructor_signature" | "factory_constructor_signature" | "constant_constructor_signature"
+            | "redirecting_factory_constructor_signature" => {
+                return signature_symbol(&child, range, source, true).into_iter().collect();
+            }
+            "initialized_identifier_list" | "static_final_declaration_list" => {
+                return (0..child.named_child_count())
+                    .filter_map(|j| child.named_child(j))
+                    .filter_map(|declaration| find_child(&declaration, "identifier"))
+                    .map(|name| {
+                        Symbol::new(get_node_text(&name, source), SymbolKind::Field, range, node_to_range(&name))
+                    })
+                    .collect();
+            }
+            _ => {}
+        }
+    }
+    Vec::new()
+}
+
+/// Symbol for a function, getter, setter, operator or constructor
+/// signature; `in_class` makes functions methods
+fn signature_symbol(signature: &Node, range: Range, source: &str, in_class: bool) -> Option<Symbol> {
+    let kind = match signature.kind() {
+        "function_signature" if in_class => SymbolKind::Method,
+        "function_signature" => SymbolKind::Function,
+        "getter_signature" | "setter_signature" => SymbolKind::Property,
+        "operator_signature" => SymbolKind::Operator,
+        _ => SymbolKind::Constructor,
+    };
+
+    if kind == SymbolKind::Operator {
+        // The operator follows the `operator` keyword; a return type precedes it
+        let operator = (0..signature.child_count())
+            .filter_map(|i| signature.child(i))
+            .skip_while(|child| child.kind() != "operator")
+            .nth(1)?;
+        let name = format!("operator {}", get_node_text(&operator, source));
+        return Some(Symbol::new(name, kind, range, node_to_range(&operator)));
+    }
+
+    // Constructors spell `Class.named` as several identifiers
+    let names: Vec<Node> = (0..signature.named_child_count())
+        .filter_map(|i| signature.named_child(i))
+        .filter(|child| child.kind() == "identifier")
+        .collect();
+    let (first, last) = match kind {
+        SymbolKind::Constructor => (*names.first()?, *names.last()?),
+        _ => {
+            let name = signature.child_by_field_name("name").or_else(|| names.first().copied())?;
+            (name, name)
+        }
+    };
+    let name = source[first.start_byte()..last.end_byte()].to_string();
+    let selection = Range::new(node_to_range(&first).start, node_to_range(&last).end);
+    let mut symbol = Symbol::new(name, kind, range, selection);
+    if let Some(parameters) = find_child(signature, "formal_parameter_list") {
+        symbol.detail = Some(get_node_text(&parameters, source));
+    }
+    Some(symbol)
+}
+
+fn enum_symbol(node: &Node, source: &str) -> Option<Symbol> {
+    let name_node = node.child_by_field_name("name")?;
+    let name = get_node_text(&name_node, source);
+    let mut symbol = Symbol::new(name, SymbolKind::Enum, node_to_range(node), node_to_range(&name_node));
+    if let Some(body) = node.child_by_field_name("body") {
+        symbol.children = (0..body.named_child_count())
+            .filter_map(|i| body.named_child(i))
+            .filter(|c| c.kind() == "enum_constant")
+            .filter_map(|constant| {
+                let name = constant.child_by_field_name("name")?;
+                Some(Symbol::new(
+                    get_node_text(&name, source),
+                    SymbolKind::EnumMember,
+                    node_to_range(&constant),
+                    node_to_range(&name),
+                ))
+            })
+            .collect();
+    }
+    Some(symbol)
+}
+
+/// Top-level `var`, `final` and `const` declarations; `const` gives
+/// constants
+fn variable_symbols(node: &Node, source: &str) -> Vec<Symbol> {
+    let Some(definition) = find_child(node, "initialized_variable_definition") else {
+        return Vec::new();
+    };
+    let kind = if find_child(&definition, "const_builtin").is_some() {
+        SymbolKind::Constant
+    } else {
+        SymbolKind::Variable
+    };
+    let range = node_to_range(node);
+    let mut names: Vec<Node> = definition.child_by_field_name("name").into_iter().collect();
+    names.extend(
+        (0..definition.named_child_count())
+            .filter_map(|i| definition.named_child(i))
+            .filter(|c| c.kind() == "initialized_identifier")
+            .filter_map(|c| find_child(&c, "identifier")),
+    );
+    names
+        .iter()
+        .map(|name| Symbol::new(get_node_text(name, source), kind, range, node_to_range(name)))
+        .collect()
+}
+
+fn find_child<'a>(node: &Node<'a>, kind: &str) -> Option<Node<'a>> {
+    (0..node.named_child_count())
+        .filter_map(|i| node.named_child(i))
+        .find(|child| child.kind() == kind)
+}
+
+fn get_node_text(node: &Node, source: &str) -> String {
+    source[node.byte_range()].to_string()
+}
+
+/// Get Dart keywords
+pub fn get_keywords() -> &'static [&'static str] {
+    &[
+        "abstract", "as", "assert", "async", "await", "base", "break", "case",
+        "catch", "class", "const", "continue", "covariant", "default", "deferred",
+        "do", "dynamic", "else", "enum", "export", "extends", "extension",
+        "external", "factory", "false", "final", "finally", "for", "Function",
+        "get", "hide", "if", "implements", "import", "in", "interface", "is",
+        "late", "library", "mixin", "new", "null", "on", "operator", "part",
+        "required", "rethrow", "return", "sealed", "set", "show", "static",
+        "super", "switch", "sync", "this", "throw", "true", "try", "typedef",
+        "var", "void", "when", "while", "with", "yield",
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LanguageId, LanguageParser};
+
+    fn parse(source: &str) -> Vec<Symbol> {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Dart).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        extract_symbols(&tree, source)
+    }
+
+    #[test]
+    fn test_class_members() {
+        let source = r#"class Circle extends Shape with Printable {
+  static const int sides = 0;
+  final double radius;
+  Circle(this.radius);
+  Circle.unit() : radius = 1;
+  factory Circle.parse(String s) => Circle(1);
+  double get area => 3.14 * radius * radius;
+  void draw() { print('o'); }
+  bool operator ==(Object other) => true;
+}
+"#;
+        let symbols = parse(source);
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "Circle");
+        let members: Vec<_> = symbols[0].children.iter().map(|c| (c.name.as_str(), c.kind)).collect();
+        assert_eq!(
+            members,
+            vec![
+                ("sides", SymbolKind::Field),
+                ("radius", SymbolKind::Field),
+                ("Circle", SymbolKind::Constructor),
+                ("Circle.unit", SymbolKind::Constructor),
+                ("Circle.parse", SymbolKind::Constructor),
+                ("area", SymbolKind::Property),
+                ("draw", SymbolKind::Method),
+                ("operator ==", SymbolKind::Operator),
+            ]
+        );
+        assert_eq!(symbols[0].children[6].detail.as_deref(), Some("()"));
+    }
+
+    #[test]
+    fn test_top_level_declarations() {
+        let source = r#"import 'package:flutter/material.dart';
+
+const int kMax = 3;
+
+mixin Printable on Object {
+  void printMe() {}
+}
+
+enum Color { red, green }
+
+extension StringX on String {
+  bool get isBlank => trim().isEmpty;
+}
+
+String get label => 'x';
+
+int add(int a, int b) => a + b;
+
+Future<void> main() async {
+  runApp(MyApp());
+}
+"#;
+        let symbols = parse(source);
+        let names: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("kMax", SymbolKind::Constant),
+                ("Printable", SymbolKind::Class),
+                ("Color", SymbolKind::Enum),
+                ("StringX", SymbolKind::Class),
+                ("label", SymbolKind::Property),
+                ("add", SymbolKind::Function),
+                ("main", SymbolKind::Function),
+            ]
+        );
+        assert_eq!(symbols[1].detail.as_deref(), Some("mixin"));
+        assert_eq!(symbols[1].children[0].name, "printMe");
+        assert_eq!(symbols[2].children.len(), 2);
+        assert_eq!(symbols[3].detail.as_deref(), Some("extension on String"));
+        assert_eq!(symbols[4].range.end.line, 14);
+        assert_eq!(symbols[5].detail.as_deref(), Some("(int a, int b)"));
+    }
+}
@@ -0,0 +1,265 @@
+//! Language detection for files their extension doesn't identify
+//!
+//! In order of confidence:
+//!
+//! 1. well-known file names (`SConstruct`, `Pipfile`, `.bashrc`)
+//! 2. the interpreter of a `#!` line, through `env` and its flags
+//! 3. an Emacs (`-*- mode: python -*-`) or Vim (`vim: ft=python`) modeline
+//! 4. keyword sniffing: each language has a few line patterns typical of
+//!    it, and the language matching the most of them wins, given two
+//!    matches and no tie
+//!
+//! Makefiles have no grammar of their own; they go to the shell grammar,
+//! which reads their comments and recipe commands.
+
+use std::sync::OnceLock;
+
+use regex::Regex;
+
+use crate::LanguageId;
+
+/// Lines looked at by modeline search and keyword sniffing
+const SNIFF_LINES: usize = 64;
+/// Patterns a language must match to be sniffed
+const MIN_SNIFF_SCORE: usize = 2;
+
+impl LanguageId {
+    /// Language of a file from its name and content: the extension, then
+    /// the name, then the content
+    pub fn detect(file_name: &str, content: &str) -> Option<Self> {
+        // `.bashrc` is a name, not an extension
+        let extension = file_name.rsplit_once('.').filter(|(stem, _)| !stem.is_empty()).map(|(_, ext)| ext);
+        extension
+            .and_then(Self::from_extension)
+            .or_else(|| Self::from_file_name(file_name))
+            .or_else(|| Self::from_content(content))
+    }
+
+    /// Language of a well-known file name without a telling extension
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        match file_name {
+            "Makefile" | "makefile" | "GNUmakefile" | "PKGBUILD" | "APKBUILD"
+            | ".bashrc" | ".bash_profile" | ".bash_login" | ".bash_logout" | ".profile" | ".zshrc" | ".zshenv"
+            | ".zprofile" | ".envrc" => Some(Self::Bash),
+            "SConstruct" | "SConscript" | "BUILD" | "WORKSPACE" | "Snakefile" | ".pythonrc" => Some(Self::Python),
+            "Pipfile" | "Cargo.lock" | "poetry.lock" | "uv.lock" => Some(Self::Toml),
+            "Pipfile.lock" | ".babelrc" | ".eslintrc" | ".prettierrc" | ".jshintrc" | ".swcrc" => Some(Self::Json),
+            ".clang-format" | ".clang-tidy" | ".clangd" => Some(Self::Yaml),
+            "Dockerfile" | "Containerfile" => Some(Self::Dockerfile),
+            _ if file_name.starts_with("Dockerfile.") || file_name.starts_with("Containerfile.") => Some(Self::Dockerfile),
+            _ if file_name.starts_with("Makefile.") => Some(Self::Bash),
+            _ => None,
+        }
+    }
+
+    /// Language of a source from its shebang, modeline or keywords
+    pub fn from_content(content: &str) -> Option<Self> {
+        let head: Vec<&str> = content.lines().take(SNIFF_LINES).collect();
+        let first = head.first().copied().unwrap_or_default();
+        first
+            .strip_prefix("#!")
+            .and_then(shebang_language)
+            .or_else(|| head.iter().find_map(|line| modeline_language(line)))
+            .or_else(|| sniff(&head))
+    }
+}
+
+/// Language of the interpreter of a `#!` line: `/usr/bin/python3`,
+/// `/usr/bin/env -S node --no-warnings`
+fn shebang_language(line: &str) -> Option<LanguageId> {
+    let mut words = line.split_whitespace();
+    let mut program = words.next()?.rsplit('/').next()?;
+    if program == "env" {
+        program = words.find(|word| !word.starts_with('-') && !word.contains('='))?;
+    }
+    interpreter_language(program)
+}
+
+fn interpreter_language(program: &str) -> Option<LanguageId> {
+    // `python3.12`, `pypy3`
+    let name = program.trim_end_matches(|c: char| c.is_ascii_digit() || c == '.');
+    match name {
+        "python" | "pypy" => Some(LanguageId::Python),
+        "sh" | "bash" | "zsh" | "dash" | "ksh" | "mksh" | "ash" => Some(LanguageId::Bash),
+        "node" | "nodejs" | "bun" | "qjs" => Some(LanguageId::JavaScript),
+        "deno" | "ts-node" | "tsx" => Some(LanguageId::TypeScript),
+        "elixir" => Some(LanguageId::Elixir),
+        "dart" => Some(LanguageId::Dart),
+        "swift" => Some(LanguageId::Swift),
+        "kotlin" => Some(LanguageId::Kotlin),
+        "rust-script" | "cargo" => Some(LanguageId::Rust),
+        _ => None,
+    }
+}
+
+/// Language named by an Emacs or Vim modeline on `line`
+fn modeline_language(line: &str) -> Option<LanguageId> {
+    static MODELINE: OnceLock<Option<Regex>> = OnceLock::new();
+    let modeline = MODELINE
+        .get_or_init(|| {
+            Regex::new(r"-\*-\s*(?:[^:;]*?mode:\s*)?([\w+#-]+)\s*(?:;[^*]*)?-\*-|\bvim?:.*\b(?:ft|filetype|syntax)=([\w+#-]+)").ok()
+        })
+        .as_ref()?;
+    let captures = modeline.captures(line)?;
+    let name = captures.get(1).or_else(|| captures.get(2))?.as_str();
+    LanguageId::builtin_from_str(name).or_else(|| interpreter_language(name))
+}
+
+/// Line patterns typical of each language, matched at the start of a line
+/// (leading whitespace aside)
+const SNIFF_PATTERNS: &[(LanguageId, &[&str])] = &[
+    (LanguageId::Python, &[
+        r"def \w+\(.*\)\s*(->.*)?:\s*$",
+        r"class \w+(\(.*\))?:\s*$",
+        r"(from [\w.]+ )?import [\w., ]+$",
+        r#"if __name__ == ['"]__main__['"]:"#,
+        r"(elif .*|else|try|except.*|finally):\s*$",
+    ]),
+    (LanguageId::Bash, &[
+        r"(export|local|readonly) \w+=",
+        r"(if|while) \[\[? .* \]\]?;? *(then|do)?$",
+        r"(fi|done|esac)$",
+        r"\w+\(\)\s*\{",
+        r"(echo|printf) ",
+        r"set -[euxo]",
+    ]),
+    (LanguageId::JavaScript, &[
+        r"(const|let|var) \w+ = require\(",
+        r"(module\.)?exports(\.\w+)? =",
+        r"function\*? \w+\(",
+        r"(const|let) \w+ = (async )?\(.*\) =>",
+        r"console\.(log|error)\(",
+    ]),
+    (LanguageId::TypeScript, &[
+        r"(export )?interface \w+",
+        r"(export )?type \w+ = ",
+        r"(const|let) \w+: \w+",
+        r"import type ",
+        r"function \w+\(.*: \w+.*\)",
+    ]),
+    (LanguageId::Go, &[
+        r"package \w+$",
+        r"func (\(.*\) )?\w+\(",
+        r"import \($",
+        r"\w+ := ",
+    ]),
+    (LanguageId::Rust, &[
+        r"(pub )?fn \w+",
+        r"use \w+(::\w+)+",
+        r"let (mut )?\w+",
+        r"impl\b",
+        r"#\[derive\(",
+    ]),
+    (LanguageId::C, &[
+        r"#include [<\x22]",
+        r"#define \w+",
+        r"(static )?(int|void|char) \**\w+\(",
+        r"typedef struct",
+    ]),
+    (LanguageId::Cpp, &[
+        r"#include <\w+>$",
+        r"namespace \w+",
+        r"template ?<",
+        r"std::",
+        r"class \w+.*\{?$",
+    ]),
+    (LanguageId::Elixir, &[
+        r"defmodule [\w.]+ do",
+        r"defp? \w+.* do$",
+        r"end$",
+        r"(alias|import|use) [A-Z][\w.]*",
+    ]),
+    (LanguageId::Dockerfile, &[
+        r"FROM \S+",
+        r"(RUN|CMD|ENTRYPOINT) ",
+        r"(COPY|ADD) ",
+        r"(WORKDIR|EXPOSE|LABEL|USER) ",
+    ]),
+    (LanguageId::Protobuf, &[
+        r#"syntax = "proto[23]";"#,
+        r"message \w+ \{",
+        r"rpc \w+\(.*\) returns",
+        r"(repeated|optional) [\w.]+ \w+ = \d+;",
+    ]),
+    (LanguageId::Sql, &[
+        r"(?i)select .* from",
+        r"(?i)create (table|view|index)",
+        r"(?i)insert into",
+        r"(?i)(alter|drop) table",
+    ]),
+];
+
+struct Sniffer {
+    language: LanguageId,
+    patterns: Vec<Regex>,
+}
+
+fn sniffers() -> &'static [Sniffer] {
+    static SNIFFERS: OnceLock<Vec<Sniffer>> = OnceLock::new();
+    SNIFFERS.get_or_init(|| {
+        SNIFF_PATTERNS
+            .iter()
+            .map(|(language, patterns)| Sniffer {
+                language: *language,
+                patterns: patterns.iter().filter_map(|p| Regex::new(&format!("^(?:{})", p)).ok()).collect(),
+            })
+            .collect()
+    })
+}
+
+/// Language whose patterns the most lines match, counting each pattern once
+fn sniff(lines: &[&str]) -> Option<LanguageId> {
+    let lines: Vec<&str> = lines.iter().map(|line| line.trim_start()).filter(|line| !line.is_empty()).collect();
+    let mut scores: Vec<(LanguageId, usize)> = sniffers()
+        .iter()
+        .map(|sniffer| {
+            let score = sniffer.patterns.iter().filter(|p| lines.iter().any(|line| p.is_match(line))).count();
+            (sniffer.language, score)
+        })
+        .collect();
+    scores.sort_by_key(|(_, score)| std::cmp::Reverse(*score));
+    match scores.as_slice() {
+        [(language, best), rest @ ..] if *best >= MIN_SNIFF_SCORE && rest.first().is_none_or(|(_, next)| next < best) => {
+            Some(*language)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_file_names_and_extensions() {
+        assert_eq!(LanguageId::detect("main.py", ""), Some(LanguageId::Python));
+        assert_eq!(LanguageId::detect("Makefile", ""), Some(LanguageId::Bash));
+        assert_eq!(LanguageId::detect("Dockerfile.dev", ""), Some(LanguageId::Dockerfile));
+        assert_eq!(LanguageId::detect("build.dockerfile", ""), Some(LanguageId::Dockerfile));
+        assert_eq!(LanguageId::detect("SConstruct", ""), Some(LanguageId::Python));
+        assert_eq!(LanguageId::detect("Cargo.lock", ""), Some(LanguageId::Toml));
+        assert_eq!(LanguageId::detect("LICENSE", "MIT License\n\nPermission is hereby granted"), None);
+    }
+
+    #[test]
+    fn test_shebangs_and_modelines() {
+        assert_eq!(LanguageId::from_content("#!/usr/bin/python3.12\nprint(1)\n"), Some(LanguageId::Python));
+        assert_eq!(LanguageId::from_content("#!/usr/bin/env -S node --no-warnings\n"), Some(LanguageId::JavaScript));
+        assert_eq!(LanguageId::from_content("#!/usr/bin/env FOO=1 bash\n"), Some(LanguageId::Bash));
+        assert_eq!(LanguageId::from_content("#!/usr/bin/env deno run\n"), Some(LanguageId::TypeScript));
+        assert_eq!(LanguageId::from_content("#!/usr/bin/perl\n"), None);
+        assert_eq!(LanguageId::from_content("# -*- mode: python; coding: utf-8 -*-\nx = 1\n"), Some(LanguageId::Python));
+        assert_eq!(LanguageId::from_content("x\n# vim: set ft=sh:\n"), Some(LanguageId::Bash));
+    }
+
+    #[test]
+    fn test_keyword_sniffing() {
+        let python = "import os\n\ndef main(args):\n    if args:\n        return 1\n\nif __name__ == '__main__':\n    main([])\n";
+        assert_eq!(LanguageId::from_content(python), Some(LanguageId::Python));
+        let shell = "set -eu\nexport PATH=/opt/bin:$PATH\nif [ -z \"$1\" ]; then\n  echo usage\nfi\n";
+        assert_eq!(LanguageId::from_content(shell), Some(LanguageId::Bash));
+        let go = "package main\n\nimport (\n\t\"fmt\"\n)\n\nfunc main() {\n\tx := 1\n\tfmt.Println(x)\n}\n";
+        assert_eq!(LanguageId::from_content(go), Some(LanguageId::Go));
+        assert_eq!(LanguageId::from_content("hello world\n"), None);
+    }
+}
@@ -0,0 +1,257 @@
+//! Documentation of declarations
+//!
+//! Python documents a declaration with the docstring that opens its body.
+//! Other languages put a comment block right above it, with nothing but
+//! attributes or annotations in between:
+//!
+//! - `///` and `/** */` in Rust, C#, Swift and Dart
+//! - `/** */` (JSDoc, Javadoc, KDoc) in JavaScript, TypeScript, Java and
+//!   Kotlin
+//! - any comment in Go, C, C++, shell scripts and protobuf, where plain
+//!   comments are the convention
+//!
+//! Comment markers, leading `*`s and common indentation are removed; the
+//! text is returned as written, which is usually markdown already.
+
+use logos_core::Symbol;
+use tree_sitter::{Node, Point, Tree};
+
+use crate::LanguageId;
+
+/// Node kinds wrapping a declaration without being one, which the comment
+/// is written above
+const WRAPPER_KINDS: &[&str] = &["export_statement", "decorated_definition", "template_declaration", "ambient_declaration"];
+
+/// Node kinds that may sit between a doc comment and its declaration
+const ATTRIBUTE_KINDS: &[&str] = &["attribute_item", "attribute_list", "attribute", "annotation", "marker_annotation", "decorator"];
+
+/// Which comments document a declaration
+enum Style {
+    /// `///` line comments and `/**` blocks
+    Slashes,
+    /// `/**` blocks only
+    Block,
+    /// Any comment
+    Any,
+}
+
+fn style(lang: LanguageId) -> Option<Style> {
+    match lang.base() {
+        LanguageId::Rust | LanguageId::CSharp | LanguageId::Swift | LanguageId::Dart => Some(Style::Slashes),
+        LanguageId::JavaScript | LanguageId::TypeScript | LanguageId::Java | LanguageId::Kotlin => Some(Style::Block),
+        LanguageId::Go | LanguageId::C | LanguageId::Cpp | LanguageId::Bash | LanguageId::Protobuf => Some(Style::Any),
+        _ => None,
+    }
+}
+
+/// Set the documentation of `symbols` (children included), whose ranges
+/// are still in tree-sitter columns
+pub fn attach_documentation(lang: LanguageId, tree: &Tree, source: &str, symbols: &mut [Symbol]) {
+    for symbol in symbols {
+        let start = Point::new(symbol.range.start.line as usize, symbol.range.start.column as usize);
+        let end = Point::new(symbol.range.end.line as usize, symbol.range.end.column as usize);
+        if let Some(node) = tree.root_node().descendant_for_point_range(start, end) {
+            symbol.documentation = documentation(lang, &node, source);
+        }
+        attach_documentation(lang, tree, source, &mut symbol.children);
+    }
+}
+
+/// Documentation of the declaration `node`
+pub fn documentation(lang: LanguageId, node: &Node, source: &str) -> Option<String> {
+    if lang == LanguageId::Python {
+        return docstring(node, source);
+    }
+    let style = style(lang)?;
+    let anchor = anchor(*node);
+
+    // Comments above the declaration, nearest first
+    let mut comments = Vec::new();
+    let mut next_row = anchor.start_position().row;
+    let mut sibling = anchor.prev_named_sibling();
+    while let Some(previous) = sibling {
+        if comments.is_empty() && ATTRIBUTE_KINDS.contains(&previous.kind()) {
+            next_row = previous.start_position().row;
+            sibling = previous.prev_named_sibling();
+            continue;
+        }
+        let adjacent = previous.end_position().row + 1 >= next_row;
+        if !previous.kind().contains("comment") || !adjacent || !starts_line(&previous, source) {
+            break;
+        }
+        let text = source.get(previous.byte_range()).unwrap_or_default();
+        if !is_doc_comment(&style, text) {
+            break;
+        }
+        comments.push(text);
+        next_row = previous.start_position().row;
+        sibling = previous.prev_named_sibling();
+    }
+
+    let lines: Vec<String> = comments.iter().rev().flat_map(|comment| comment_lines(comment)).collect();
+    join(dedent(&lines))
+}
+
+/// The node a comment above `node` precedes: `node`, or the wrapper or
+/// statement that starts on its line
+fn anchor(node: Node) -> Node {
+    let mut anchor = node;
+    while let Some(parent) = anchor.parent() {
+        if parent.parent().is_none() {
+            break;
+        }
+        let wraps = WRAPPER_KINDS.contains(&parent.kind());
+        let leads = anchor.prev_named_sibling().is_none() && parent.start_position().row == anchor.start_position().row;
+        if !wraps && !leads {
+            break;
+        }
+        anchor = parent;
+    }
+    anchor
+}
+
+/// Whether only whitespace precedes `node` on its line, so that it isn't a
+/// trailing comment of the line above
+fn starts_line(node: &Node, source: &str) -> bool {
+    let line_start = source[..node.start_byte()].rfind('\n').map_or(0, |i| i + 1);
+    source[line_start..node.start_byte()].trim().is_empty()
+}
+
+fn is_doc_comment(style: &Style, text: &str) -> bool {
+    let block = text.starts_with("/**") && text != "/**/";
+    match style {
+        Style::Slashes => block || (text.starts_with("///") && !text.starts_with("////")),
+        Style::Block => block,
+        Style::Any => !text.starts_with("#!"),
+    }
+}
+
+/// Lines of a comment without its markers
+fn comment_lines(comment: &str) -> Vec<String> {
+    if let Some(body) = comment.strip_prefix("/*") {
+        let body = body.trim_start_matches(['*', '!']).strip_suffix("*/").unwrap_or(body).trim_end_matches('*');
+        return body
+            .lines()
+            .map(|line| {
+                let trimmed = line.trim_start();
+                match trimmed.strip_prefix('*') {
+                    Some(rest) => rest.strip_prefix(' ').unwrap_or(rest).to_string(),
+                    None => line.to_string(),
+                }
+            })
+            .collect();
+    }
+    comment
+        .lines()
+        .map(|line| {
+            let line = line.trim_start();
+            let rest = line.trim_start_matches('/').trim_start_matches('!');
+            let rest = if rest.len() == line.len() { line.trim_start_matches('#') } else { rest };
+            rest.strip_prefix(' ').unwrap_or(rest).to_string()
+        })
+        .collect()
+}
+
+/// Docstring opening the body of a Python function or class
+fn docstring(node: &Node, source: &str) -> Option<String> {
+    let definition = if node.kind() == "decorated_definition" {
+        node.child_by_field_name("definition")?
+    } else {
+        *node
+    };
+    let body = definition.child_by_field_name("body")?;
+    let first = body.named_child(0)?;
+    let string = first.named_child(0).filter(|_| first.kind() == "expression_statement")?;
+    if string.kind() != "string" {
+        return None;
+    }
+    let text = source.get(string.byte_range())?;
+    let text = text.trim_start_matches(|c: char| c.is_ascii_alphabetic());
+    let quote = ["\"\"\"", "'''", "\"", "'"].into_iter().find(|q| text.starts_with(q))?;
+    let content = text.strip_prefix(quote)?.strip_suffix(quote)?;
+
+    // As `inspect.cleandoc`: the first line trimmed, the rest dedented
+    let mut lines = content.lines();
+    let first_line = lines.next().unwrap_or_default().trim().to_string();
+    let rest: Vec<String> = lines.map(str::to_string).collect();
+    join(std::iter::once(first_line).chain(dedent(&rest)).collect())
+}
+
+/// Lines with their common indentation and trailing whitespace removed
+fn dedent(lines: &[String]) -> Vec<String> {
+    let indent = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    lines.iter().map(|line| line.get(indent..).unwrap_or_default().trim_end().to_string()).collect()
+}
+
+/// Lines joined without the blank lines at either end; `None` when all
+/// are blank
+fn join(lines: Vec<String>) -> Option<String> {
+    let start = lines.iter().position(|line| !line.is_empty())?;
+    let end = lines.iter().rposition(|line| !line.is_empty())?;
+    Some(lines[start..=end].join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{extract_symbols, LanguageId, LanguageParser};
+
+    fn docs(lang: LanguageId, source: &str) -> Vec<(String, Option<String>)> {
+        let mut parser = LanguageParser::new();
+        parser.set_language(lang).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let mut out = Vec::new();
+        let mut pending = extract_symbols(lang, &tree, source);
+        while let Some(symbol) = pending.pop() {
+            out.push((symbol.name.clone(), symbol.documentation.clone()));
+            pending.extend(symbol.children);
+        }
+        out.sort();
+        out
+    }
+
+    fn doc_of(symbols: &[(String, Option<String>)], name: &str) -> Option<String> {
+        symbols.iter().find(|(n, _)| n == name).and_then(|(_, doc)| doc.clone())
+    }
+
+    #[test]
+    fn test_python_docstrings() {
+        let source = "class Greeter:\n    \"\"\"Says hello.\n\n    Politely.\n    \"\"\"\n\n    def greet(self):\n        '''One line.'''\n        return 1\n\ndef bare():\n    pass\n";
+        let symbols = docs(LanguageId::Python, source);
+        assert_eq!(doc_of(&symbols, "Greeter").as_deref(), Some("Says hello.\n\nPolitely."));
+        assert_eq!(doc_of(&symbols, "greet").as_deref(), Some("One line."));
+        assert_eq!(doc_of(&symbols, "bare"), None);
+    }
+
+    #[test]
+    fn test_rust_doc_comments() {
+        let source = "// Not documentation\n\n/// Parses a file.\n///\n/// # Errors\n#[inline]\npub fn parse() {}\n\n// plain\nfn other() {}\n";
+        let symbols = docs(LanguageId::Rust, source);
+        assert_eq!(doc_of(&symbols, "parse").as_deref(), Some("Parses a file.\n\n# Errors"));
+        assert_eq!(doc_of(&symbols, "other"), None);
+    }
+
+    #[test]
+    fn test_jsdoc_and_javadoc() {
+        let source = "/**\n * Adds numbers.\n * @param a first\n */\nexport function add(a, b) { return a + b; }\n\n/* not a doc */\nfunction sub() {}\n";
+        let symbols = docs(LanguageId::TypeScript, source);
+        assert_eq!(doc_of(&symbols, "add").as_deref(), Some("Adds numbers.\n@param a first"));
+        assert_eq!(doc_of(&symbols, "sub"), None);
+
+        let source = "class Box {\n    /** The size. */\n    @Override\n    public int size() { return 0; }\n}\n";
+        let symbols = docs(LanguageId::Java, source);
+        assert_eq!(doc_of(&symbols, "size").as_deref(), Some("The size."));
+    }
+
+    #[test]
+    fn test_go_comments() {
+        let source = "package main\n\n// Run starts the server.\n// It blocks.\nfunc Run() {}\n\nfunc x() {} // trailing\nfunc Stop() {}\n";
+        let symbols = docs(LanguageId::Go, source);
+        assert_eq!(doc_of(&symbols, "Run").as_deref(), Some("Run starts the server.\nIt blocks."));
+        assert_eq!(doc_of(&symbols, "Stop"), None);
+    }
+}
@@ -0,0 +1,346 @@
+//! Dockerfile symbol extraction and checks
+//!
+//! Dockerfiles parse with the shell grammar, which reads their comments
+//! and commands but not their structure, so stages and instructions come
+//! from scanning the lines. An instruction continues on the next line
+//! after the escape character (`\`, or the one set by an `# escape=`
+//! directive), and comment lines inside a continuation are skipped, as
+//! Docker does.
+
+use std::collections::HashMap;
+
+use logos_core::{Diagnostic, DiagnosticTag, Position, Range, Symbol, SymbolKind};
+
+use crate::Utf16Columns;
+
+/// Instructions Docker knows
+const INSTRUCTIONS: &[&str] = &[
+    "ADD", "ARG", "CMD", "COPY", "ENTRYPOINT", "ENV", "EXPOSE", "FROM", "HEALTHCHECK", "LABEL",
+    "MAINTAINER", "ONBUILD", "RUN", "SHELL", "STOPSIGNAL", "USER", "VOLUME", "WORKDIR",
+];
+
+/// Longest instruction detail shown in the outline
+const MAX_DETAIL_CHARS: usize = 60;
+
+/// A whitespace-separated word of an instruction, in byte columns
+#[derive(Debug)]
+struct Word<'a> {
+    text: &'a str,
+    range: Range,
+}
+
+/// An instruction with its continuation lines
+#[derive(Debug)]
+struct Instruction<'a> {
+    /// The instruction word as written (`run`, `RUN`)
+    keyword: Word<'a>,
+    args: Vec<Word<'a>>,
+    range: Range,
+}
+
+impl Instruction<'_> {
+    fn name(&self) -> String {
+        self.keyword.text.to_ascii_uppercase()
+    }
+
+    fn args_text(&self) -> String {
+        self.args.iter().map(|word| word.text).collect::<Vec<_>>().join(" ")
+    }
+}
+
+/// Escape character set by an `# escape=` parser directive. Directives
+/// are only read in the comments that open the file.
+fn escape_char(source: &str) -> char {
+    for line in source.lines() {
+        let Some(comment) = line.trim().strip_prefix('#') else {
+            break;
+        };
+        let Some((key, value)) = comment.split_once('=') else {
+            break;
+        };
+        if key.trim().eq_ignore_ascii_case("escape") {
+            if let Some(c) = value.trim().chars().next() {
+                return c;
+            }
+        }
+    }
+    '\\'
+}
+
+fn words(line: &str, line_no: u32) -> Vec<Word<'_>> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (i, c) in line.char_indices().chain(std::iter::once((line.len(), ' '))) {
+        match (c.is_whitespace(), start) {
+            (true, Some(s)) => {
+                words.push(Word {
+                    text: &line[s..i],
+                    range: Range::from_coords(line_no, s as u32, line_no, i as u32),
+                });
+                start = None;
+            }
+            (false, None) => start = Some(i),
+            _ => {}
+        }
+    }
+    words
+}
+
+fn instructions(source: &str) -> Vec<Instruction<'_>> {
+    let escape = escape_char(source);
+    let mut instructions = Vec::new();
+    let mut current: Option<Instruction> = None;
+
+    for (line_no, line) in source.lines().enumerate() {
+        let line_no = line_no as u32;
+        // Comments and blank lines inside a continuation are dropped too
+        let trimmed = line.trim();
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        let content = line.trim_end();
+        let (content, continues) = match content.strip_suffix(escape) {
+            Some(rest) => (rest, true),
+            None => (content, false),
+        };
+        let mut line_words = words(content, line_no);
+        let end = Position::new(line_no, line.trim_end().len() as u32);
+
+        match current.as_mut() {
+            Some(instruction) => {
+                instruction.args.append(&mut line_words);
+                instruction.range.end = end;
+            }
+            None => {
+                if line_words.is_empty() {
+                    continue;
+                }
+                let keyword = line_words.remove(0);
+                let start = keyword.range.start;
+                current = Some(Instruction { keyword, args: line_words, range: Range::new(start, end) });
+            }
+        }
+        if !continues {
+            instructions.extend(current.take());
+        }
+    }
+    instructions.extend(current);
+    instructions
+}
+
+/// Variables an `ARG` or `ENV` instruction declares: `NAME`, `NAME=value`,
+/// and the legacy `ENV NAME value`
+fn declared_names<'a>(instruction: &Instruction<'a>) -> Vec<Word<'a>> {
+    let args: Vec<&Word> = instruction.args.iter().filter(|word| !word.text.starts_with("--")).collect();
+    let legacy = instruction.name() == "ENV" && args.first().is_some_and(|word| !word.text.contains('='));
+    let declared = if legacy { &args[..args.len().min(1)] } else { &args[..] };
+    declared
+        .iter()
+        .filter_map(|word| {
+            let name = word.text.split('=').next().unwrap_or_default();
+            let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            (valid && (legacy || instruction.name() == "ARG" || word.text.contains('='))).then(|| Word {
+                text: name,
+                range: Range::from_coords(
+                    word.range.start.line,
+                    word.range.start.column,
+                    word.range.start.line,
+                    word.range.start.column + name.len() as u32,
+                ),
+            })
+        })
+        .collect()
+}
+
+/// Stage name of a `FROM image AS name` instruction
+fn stage_name<'a, 'b>(from: &'b Instruction<'a>) -> Option<&'b Word<'a>> {
+    let args: Vec<&Word> = from.args.iter().filter(|word| !word.text.starts_with("--")).collect();
+    match args.as_slice() {
+        [_, keyword, name, ..] if keyword.text.eq_ignore_ascii_case("as") => Some(*name),
+        _ => None,
+    }
+}
+
+fn instruction_symbol(instruction: &Instruction) -> Vec<Symbol> {
+    let name = instruction.name();
+    if name == "ARG" || name == "ENV" {
+        return declared_names(instruction)
+            .into_iter()
+            .map(|word| {
+                let mut symbol = Symbol::new(word.text.to_string(), SymbolKind::Variable, instruction.range, word.range);
+                symbol.detail = Some(name.clone());
+                symbol
+            })
+            .collect();
+    }
+    let mut detail = instruction.args_text();
+    if detail.chars().count() > MAX_DETAIL_CHARS {
+        detail = format!("{}…", detail.chars().take(MAX_DETAIL_CHARS).collect::<String>());
+    }
+    let mut symbol = Symbol::new(name, SymbolKind::Key, instruction.range, instruction.keyword.range);
+    symbol.detail = Some(detail).filter(|detail| !detail.is_empty());
+    vec![symbol]
+}
+
+/// Extract symbols from a Dockerfile: one per build stage, named by its
+/// `AS` name or its position, with its instructions as children. `ARG`
+/// and `ENV` give one variable per name; `ARG`s before the first `FROM`
+/// are top-level.
+pub fn extract_symbols(source: &str) -> Vec<Symbol> {
+    let mut symbols: Vec<Symbol> = Vec::new();
+    let mut stage: Option<Symbol> = None;
+    let mut index = 0;
+
+    for instruction in instructions(source) {
+        if instruction.name() == "FROM" {
+            symbols.extend(stage.take());
+            let (name, selection) = match stage_name(&instruction) {
+                Some(word) => (word.text.to_string(), word.range),
+                None => (format!("stage {}", index), instruction.keyword.range),
+            };
+            let mut symbol = Symbol::new(name, SymbolKind::Module, instruction.range, selection);
+            symbol.detail = instruction.args.iter().find(|word| !word.text.starts_with("--")).map(|word| word.text.to_string());
+            stage = Some(symbol);
+            index += 1;
+            continue;
+        }
+        let children = instruction_symbol(&instruction);
+        match stage.as_mut() {
+            Some(stage) => {
+                stage.range.end = instruction.range.end;
+                stage.children.extend(children);
+            }
+            None => symbols.extend(children),
+        }
+    }
+    symbols.extend(stage);
+    symbols
+}
+
+/// Mistakes Docker would reject or warn about: unknown instructions,
+/// instructions before the first `FROM`, missing arguments, stage names
+/// used twice, and the deprecated `MAINTAINER`. Ranges are in UTF-16
+/// columns.
+pub fn diagnostics(source: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut stages: HashMap<String, Range> = HashMap::new();
+    let mut seen_from = false;
+
+    for instruction in instructions(source) {
+        let name = instruction.name();
+        let keyword = instruction.keyword.range;
+        if !INSTRUCTIONS.contains(&name.as_str()) {
+            diagnostics.push(diagnostic(Diagnostic::error(keyword, format!("Unknown instruction `{}`", instruction.keyword.text)), "unknown-instruction"));
+            continue;
+        }
+        if instruction.args.is_empty() {
+            diagnostics.push(diagnostic(Diagnostic::error(keyword, format!("`{}` needs arguments", name)), "missing-arguments"));
+        }
+        match name.as_str() {
+            "FROM" => {
+                seen_from = true;
+                if let Some(word) = stage_name(&instruction) {
+                    let stage = word.text.to_ascii_lowercase();
+                    if stages.insert(stage, word.range).is_some() {
+                        diagnostics.push(diagnostic(
+                            Diagnostic::error(word.range, format!("Stage `{}` is already defined", word.text)),
+                            "duplicate-stage",
+                        ));
+                    }
+                }
+            }
+            // Global build arguments may come before the first stage
+            "ARG" => {}
+            _ if !seen_from => {
+                diagnostics.push(diagnostic(
+                    Diagnostic::error(keyword, format!("`{}` before the first `FROM`", name)),
+                    "missing-from",
+                ));
+            }
+            _ => {}
+        }
+        if name == "MAINTAINER" {
+            diagnostics.push(diagnostic(
+                Diagnostic::warning(keyword, "`MAINTAINER` is deprecated, use `LABEL maintainer=...`".to_string())
+                    .with_tag(DiagnosticTag::Deprecated),
+                "deprecated-instruction",
+            ));
+        }
+    }
+    Utf16Columns::new(source).convert_diagnostics(&mut diagnostics);
+    diagnostics
+}
+
+fn diagnostic(diagnostic: Diagnostic, code: &str) -> Diagnostic {
+    diagnostic.with_code(code.to_string()).with_source("logos-parser".to_string())
+}
+
+/// Get Dockerfile instructions as keywords
+pub fn get_keywords() -> &'static [&'static str] {
+    INSTRUCTIONS
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = r#"# syntax=docker/dockerfile:1
+ARG GO_VERSION=1.22
+FROM golang:${GO_VERSION} AS build
+ENV CGO_ENABLED=0 GOOS=linux
+RUN apt-get update \
+    # keep the image small
+    && apt-get install -y git
+COPY . /src
+
+FROM scratch
+COPY --from=build /src/app /app
+ENTRYPOINT ["/app"]
+"#;
+
+    #[test]
+    fn test_stages_and_instructions() {
+        let symbols = extract_symbols(SOURCE);
+        let outline: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind, s.detail.as_deref())).collect();
+        assert_eq!(
+            outline,
+            vec![
+                ("GO_VERSION", SymbolKind::Variable, Some("ARG")),
+                ("build", SymbolKind::Module, Some("golang:${GO_VERSION}")),
+                ("stage 1", SymbolKind::Module, Some("scratch")),
+            ]
+        );
+        let build: Vec<_> = symbols[1].children.iter().map(|s| (s.name.as_str(), s.detail.as_deref())).collect();
+        assert_eq!(
+            build,
+            vec![
+                ("CGO_ENABLED", Some("ENV")),
+                ("GOOS", Some("ENV")),
+                ("RUN", Some("apt-get update && apt-get install -y git")),
+                ("COPY", Some(". /src")),
+            ]
+        );
+        assert_eq!(symbols[1].range, Range::from_coords(2, 0, 7, 11));
+        assert_eq!(symbols[1].children[2].range, Range::from_coords(4, 0, 6, 29));
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let source = "# escape=`\nRUN echo hi\nFROM alpine AS base\nFROM alpine as BASE\nMAINTAINER me\nRUNN make `\n  all\nWORKDIR\n";
+        let found: Vec<_> = diagnostics(source)
+            .into_iter()
+            .map(|d| (d.range.start.line, d.code.unwrap_or_default()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (1, "missing-from".to_string()),
+                (3, "duplicate-stage".to_string()),
+                (4, "deprecated-instruction".to_string()),
+                (5, "unknown-instruction".to_string()),
+                (7, "missing-arguments".to_string()),
+            ]
+        );
+        assert!(diagnostics(SOURCE).is_empty());
+    }
+}
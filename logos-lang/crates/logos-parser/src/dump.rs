@@ -0,0 +1,161 @@
+//! AST dumps for debugging grammars and external tooling
+//!
+//! Both formats carry field names and ranges. Anonymous nodes (punctuation
+//! and keywords) are left out unless asked for.
+
+use logos_core::Range;
+use serde::Serialize;
+use tree_sitter::{Node, TreeCursor};
+
+use crate::node_to_range;
+
+/// A syntax node and its subtree, as serialized to JSON
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AstNode {
+    pub kind: String,
+    /// Field of the parent this node fills, e.g. `name`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub named: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub error: bool,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub missing: bool,
+    pub range: Range,
+    pub byte_range: std::ops::Range<usize>,
+    /// Source text, for leaves only
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<AstNode>,
+}
+
+/// Node tree rooted at `node`
+pub fn to_ast(node: Node, source: &str, include_anonymous: bool) -> AstNode {
+    let mut cursor = node.walk();
+    ast_node(&mut cursor, None, source, include_anonymous)
+}
+
+fn ast_node(cursor: &mut TreeCursor, field: Option<&str>, source: &str, include_anonymous: bool) -> AstNode {
+    let node = cursor.node();
+    let mut children = Vec::new();
+    if cursor.goto_first_child() {
+        loop {
+            if include_anonymous || cursor.node().is_named() {
+                let field = cursor.field_name();
+                children.push(ast_node(cursor, field, source, include_anonymous));
+            }
+            if !cursor.goto_next_sibling() {
+                break;
+            }
+        }
+        cursor.goto_parent();
+    }
+    let text = (node.child_count() == 0).then(|| source.get(node.byte_range()).unwrap_or_default().to_string());
+    AstNode {
+        kind: node.kind().to_string(),
+        field: field.map(str::to_string),
+        named: node.is_named(),
+        error: node.is_error(),
+        missing: node.is_missing(),
+        range: node_to_range(&node),
+        byte_range: node.byte_range(),
+        text,
+        children,
+    }
+}
+
+/// S-expression of the subtree at `node`, one node per line, in the layout
+/// of `tree-sitter parse`:
+///
+/// ```text
+/// (function_definition [0, 0] - [0, 17]
+///   name: (identifier [0, 4] - [0, 9])
+///   ...)
+/// ```
+///
+/// Anonymous nodes are written as their quoted text.
+pub fn to_sexp(node: Node, include_anonymous: bool) -> String {
+    let mut out = String::new();
+    let mut cursor = node.walk();
+    write_sexp(&mut cursor, None, 0, include_anonymous, &mut out);
+    out
+}
+
+fn write_sexp(cursor: &mut TreeCursor, field: Option<&str>, depth: usize, include_anonymous: bool, out: &mut String) {
+    let node = cursor.node();
+    if depth > 0 {
+        out.push('\n');
+    }
+    out.push_str(&"  ".repeat(depth));
+    if let Some(field) = field {
+        out.push_str(field);
+        out.push_str(": ");
+    }
+    let start = node.start_position();
+    let end = node.end_position();
+    let range = format!("[{}, {}] - [{}, {}]", start.row, start.column, end.row, end.column);
+    if !node.is_named() {
+        out.push_str(&format!("{:?} {}", node.kind(), range));
+        return;
+    }
+    let kind = if node.is_missing() { format!("MISSING {}", node.kind()) } else { node.kind().to_string() };
+    out.push_str(&format!("({} {}", kind, range));
+    if cursor.goto_first_child() {
+        loop {
+            if include_anonymous || cursor.node().is_named() {
+                let field = cursor.field_name();
+                write_sexp(cursor, field, depth + 1, include_anonymous, out);
+            }
+            if !cursor.goto_next_sibling() {
+                break;
+            }
+        }
+        cursor.goto_parent();
+    }
+    out.push(')');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LanguageId, LanguageParser};
+
+    fn parse(source: &str) -> tree_sitter::Tree {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Python).unwrap();
+        parser.parse(source, None).unwrap()
+    }
+
+    #[test]
+    fn test_sexp_with_fields_and_ranges() {
+        let tree = parse("def f(): pass\n");
+        let function = tree.root_node().child(0).unwrap();
+        assert_eq!(
+            to_sexp(function, false),
+            "(function_definition [0, 0] - [0, 13]\n  name: (identifier [0, 4] - [0, 5])\n  parameters: (parameters [0, 5] - [0, 7])\n  body: (block [0, 9] - [0, 13]\n    (pass_statement [0, 9] - [0, 13])))"
+        );
+        let with_anonymous = to_sexp(function, true);
+        assert!(with_anonymous.starts_with("(function_definition [0, 0] - [0, 13]\n  \"def\" [0, 0] - [0, 3]\n"));
+    }
+
+    #[test]
+    fn test_json_tree() {
+        let source = "x = 1\n";
+        let tree = parse(source);
+        let ast = to_ast(tree.root_node(), source, false);
+        assert_eq!(ast.kind, "module");
+        let assignment = &ast.children[0].children[0];
+        assert_eq!(assignment.kind, "assignment");
+        let left = &assignment.children[0];
+        assert_eq!((left.field.as_deref(), left.text.as_deref()), (Some("left"), Some("x")));
+        assert_eq!(assignment.children[1].byte_range, 4..5);
+
+        let json = serde_json::to_value(&assignment.children[1]).unwrap();
+        assert_eq!(json["kind"], "integer");
+        assert_eq!(json["field"], "right");
+        assert!(json.get("children").is_none());
+        assert!(json.get("error").is_none());
+    }
+}
@@ -0,0 +1,175 @@
+//! Elixir parsing and symbol extraction
+//!
+//! Elixir has no declaration syntax: `defmodule`, `def` and friends are
+//! ordinary macro calls, so symbols are recognized by the call target.
+
+use std::collections::HashSet;
+
+use logos_core::{Symbol, SymbolKind};
+use tree_sitter::{Node, Tree};
+use crate::node_to_range;
+
+/// Extract symbols from an Elixir AST.
+///
+/// `defmodule` and `defimpl` give modules and `defprotocol` an interface,
+/// each with the definitions of its `do` block as children. `def`, `defp`,
+/// `defmacro(p)`, `defguard(p)` and `defdelegate` give functions whose
+/// detail is the defining macro and arity; further clauses of a function
+/// already listed are skipped.
+pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
+    let root = tree.root_node();
+    extract_block(&root, source)
+}
+
+fn extract_block(node: &Node, source: &str) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let mut seen = HashSet::new();
+    for i in 0..node.named_child_count() {
+        let Some(child) = node.named_child(i) else {
+            continue;
+        };
+        if child.kind() != "call" {
+            continue;
+        }
+        let Some(symbol) = definition_symbol(&child, source) else {
+            continue;
+        };
+        let key = (symbol.name.clone(), symbol.detail.clone());
+        if seen.insert(key) {
+            symbols.push(symbol);
+        }
+    }
+    symbols
+}
+
+fn definition_symbol(call: &Node, source: &str) -> Option<Symbol> {
+    let target = call.child_by_field_name("target")?;
+    let macro_name = get_node_text(&target, source);
+    let arguments = find_child(call, "arguments")?;
+    let head = arguments.named_child(0)?;
+
+    match macro_name.as_str() {
+        "defmodule" | "defprotocol" | "defimpl" => {
+            if head.kind() != "alias" {
+                return None;
+            }
+            let kind = if macro_name == "defprotocol" { SymbolKind::Interface } else { SymbolKind::Module };
+            let mut symbol = Symbol::new(get_node_text(&head, source), kind, node_to_range(call), node_to_range(&head));
+            if let Some(body) = find_child(call, "do_block") {
+                symbol.children = extract_block(&body, source);
+            }
+            Some(symbol)
+        }
+        "def" | "defp" | "defmacro" | "defmacrop" | "defguard" | "defguardp" | "defdelegate" => {
+            let (name_node, arity) = function_head(&head)?;
+            let name = get_node_text(&name_node, source);
+            let mut symbol = Symbol::new(name, SymbolKind::Function, node_to_range(call), node_to_range(&name_node));
+            symbol.detail = Some(format!("{}/{}", macro_name, arity));
+            Some(symbol)
+        }
+        _ => None,
+    }
+}
+
+/// Name and arity of a function head: `name`, `name(args)` or
+/// `name(args) when guard`
+fn function_head<'a>(head: &Node<'a>) -> Option<(Node<'a>, usize)> {
+    match head.kind() {
+        "identifier" => Some((*head, 0)),
+        "call" => {
+            let name = head.child_by_field_name("target")?;
+            if name.kind() != "identifier" {
+                return None;
+            }
+            let arity = find_child(head, "arguments").map_or(0, |args| args.named_child_count());
+            Some((name, arity))
+        }
+        "binary_operator" => function_head(&head.child_by_field_name("left")?),
+        _ => None,
+    }
+}
+
+fn find_child<'a>(node: &Node<'a>, kind: &str) -> Option<Node<'a>> {
+    (0..node.named_child_count())
+        .filter_map(|i| node.named_child(i))
+        .find(|child| child.kind() == kind)
+}
+
+fn get_node_text(node: &Node, source: &str) -> String {
+    source[node.byte_range()].to_string()
+}
+
+/// Get Elixir keywords: reserved words and the definition macros
+pub fn get_keywords() -> &'static [&'static str] {
+    &[
+        "after", "alias", "and", "case", "catch", "cond", "def", "defdelegate",
+        "defexception", "defguard", "defguardp", "defimpl", "defmacro",
+        "defmacrop", "defmodule", "defp", "defprotocol", "defstruct", "do",
+        "else", "end", "false", "fn", "for", "if", "import", "in", "nil", "not",
+        "or", "quote", "raise", "receive", "require", "rescue", "true", "try",
+        "unless", "unquote", "use", "when", "with",
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LanguageId, LanguageParser};
+
+    fn parse(source: &str) -> Vec<Symbol> {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Elixir).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        extract_symbols(&tree, source)
+    }
+
+    #[test]
+    fn test_module_functions() {
+        let source = r#"defmodule MyApp.Accounts do
+  @moduledoc "Accounts"
+  def greet(name, opts \\ []) when is_binary(name), do: "hi"
+  defp helper(x) do
+    x + 1
+  end
+  defmacro trace(ast), do: ast
+  def zero, do: 0
+end
+"#;
+        let symbols = parse(source);
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].name, "MyApp.Accounts");
+        assert_eq!(symbols[0].kind, SymbolKind::Module);
+        let children: Vec<_> = symbols[0]
+            .children
+            .iter()
+            .map(|c| (c.name.as_str(), c.detail.as_deref().unwrap()))
+            .collect();
+        assert_eq!(
+            children,
+            vec![("greet", "def/2"), ("helper", "defp/1"), ("trace", "defmacro/1"), ("zero", "def/0")]
+        );
+        assert_eq!(symbols[0].children[0].selection_range.start.line, 2);
+    }
+
+    #[test]
+    fn test_clauses_and_nested_protocols() {
+        let source = r#"defmodule Math do
+  def fact(0), do: 1
+  def fact(n), do: n * fact(n - 1)
+  def fact(n, acc), do: n * acc
+  defprotocol Size do
+    def size(data)
+  end
+end
+"#;
+        let symbols = parse(source);
+        let children = &symbols[0].children;
+        let names: Vec<_> = children.iter().map(|c| (c.name.as_str(), c.kind)).collect();
+        assert_eq!(
+            names,
+            vec![("fact", SymbolKind::Function), ("fact", SymbolKind::Function), ("Size", SymbolKind::Interface)]
+        );
+        assert_eq!(children[1].detail.as_deref(), Some("def/2"));
+        assert_eq!(children[2].children[0].name, "size");
+    }
+}
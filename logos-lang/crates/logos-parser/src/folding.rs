@@ -0,0 +1,244 @@
+//! Folding ranges from the parse tree
+//!
+//! A document folds at:
+//!
+//! - bracket pairs spanning lines, up to the line before the closing
+//!   bracket so that it stays visible
+//! - HTML elements spanning lines, up to the line before their end tag
+//! - constructs without a closing bracket: Python blocks, YAML mappings
+//!   and sequence items, Markdown sections and Elixir `do` blocks
+//! - block comments and runs of line comments
+//! - runs of imports
+//! - `#region` / `#endregion` marker comments (`// #region`, `# region`)
+//!
+//! When several ranges start on one line, the widest is kept.
+
+use tree_sitter::{Node, Tree};
+
+use crate::{brackets, LanguageId};
+
+/// Statements that make up an import run
+const IMPORT_KINDS: &[&str] = &[
+    "import_statement", "import_from_statement", "import_declaration", "use_declaration",
+    "preproc_include", "using_directive", "import_header",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoldingKind {
+    Comment,
+    Imports,
+    Region,
+}
+
+impl FoldingKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FoldingKind::Comment => "comment",
+            FoldingKind::Imports => "imports",
+            FoldingKind::Region => "region",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FoldingRange {
+    pub start_line: u32,
+    /// Last line hidden by the fold
+    pub end_line: u32,
+    pub kind: Option<FoldingKind>,
+}
+
+/// Folding ranges of a document, in order of their first line
+pub fn folding_ranges(lang: LanguageId, tree: &Tree, source: &str) -> Vec<FoldingRange> {
+    let mut ranges: Vec<FoldingRange> = brackets::tree_pairs(tree)
+        .into_iter()
+        .map(|pair| fold(pair.open.start.line, pair.close.start.line.saturating_sub(1), None))
+        .collect();
+
+    let mut comments = Vec::new();
+    collect_ranges(lang.base(), tree.root_node(), &mut ranges, &mut comments);
+    comment_ranges(&comments, source, &mut ranges);
+
+    ranges.retain(|range| range.end_line > range.start_line);
+    ranges.sort_by_key(|range| (range.start_line, std::cmp::Reverse(range.end_line)));
+    ranges.dedup_by_key(|range| range.start_line);
+    ranges
+}
+
+fn fold(start_line: u32, end_line: u32, kind: Option<FoldingKind>) -> FoldingRange {
+    FoldingRange { start_line, end_line, kind }
+}
+
+/// Last line with content of `node`; a node ending at the start of a line
+/// (after its trailing newline) ends on the line before
+fn last_line(node: &Node) -> u32 {
+    let end = node.end_position();
+    if end.column == 0 && end.row > node.start_position().row {
+        end.row as u32 - 1
+    } else {
+        end.row as u32
+    }
+}
+
+fn collect_ranges<'a>(
+    lang: LanguageId,
+    node: Node<'a>,
+    ranges: &mut Vec<FoldingRange>,
+    comments: &mut Vec<Node<'a>>,
+) {
+    let start = node.start_position().row as u32;
+    match (lang, node.kind()) {
+        (_, kind) if kind.contains("comment") => {
+            comments.push(node);
+            return;
+        }
+        (LanguageId::Python, "block") => {
+            // The block starts after its header; the header line stays visible
+            let header = node.parent().map_or(start, |parent| parent.start_position().row as u32);
+            ranges.push(fold(header, last_line(&node), None));
+        }
+        (LanguageId::Yaml, "block_mapping_pair" | "block_sequence_item") | (LanguageId::Markdown, "section") => {
+            ranges.push(fold(start, last_line(&node), None));
+        }
+        (LanguageId::Elixir, "do_block") => {
+            ranges.push(fold(start, last_line(&node).saturating_sub(1), None));
+        }
+        (LanguageId::Html, "element" | "script_element" | "style_element") => {
+            let closed = node.child(node.child_count().saturating_sub(1)).is_some_and(|last| last.kind() == "end_tag");
+            let end = last_line(&node);
+            ranges.push(fold(start, if closed { end.saturating_sub(1) } else { end }, None));
+        }
+        _ => {}
+    }
+
+    // Runs of imports among the children
+    let mut run: Option<(u32, u32)> = None;
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if IMPORT_KINDS.contains(&child.kind()) {
+            let end = last_line(&child);
+            run = Some(run.map_or((child.start_position().row as u32, end), |(first, _)| (first, end)));
+        } else if !child.kind().contains("comment") {
+            if let Some((first, last)) = run.take() {
+                ranges.push(fold(first, last, Some(FoldingKind::Imports)));
+            }
+        }
+        collect_ranges(lang, child, ranges, comments);
+    }
+    if let Some((first, last)) = run {
+        ranges.push(fold(first, last, Some(FoldingKind::Imports)));
+    }
+}
+
+/// First word of a comment after its delimiters, lowercased: `region` for
+/// `// #region`, `# region` and `<!-- #region -->`
+fn marker(text: &str) -> Option<String> {
+    let word = text
+        .trim_start_matches(|c: char| c.is_whitespace() || matches!(c, '/' | '*' | '#' | '-' | '!' | '<' | ';' | '%'))
+        .split_whitespace()
+        .next()?;
+    Some(word.trim_end_matches("-->").to_ascii_lowercase())
+}
+
+/// Folds of block comments, line comment runs and region markers
+fn comment_ranges(comments: &[Node], source: &str, ranges: &mut Vec<FoldingRange>) {
+    let mut regions: Vec<u32> = Vec::new();
+    // First and last line of the current run of line comments
+    let mut run: Option<(u32, u32)> = None;
+
+    for comment in comments {
+        let start = comment.start_position().row as u32;
+        let text = &source[comment.byte_range()];
+        match marker(text).as_deref() {
+            Some("region") => {
+                flush(&mut run, ranges);
+                regions.push(start);
+                continue;
+            }
+            Some("endregion") => {
+                flush(&mut run, ranges);
+                if let Some(open) = regions.pop() {
+                    ranges.push(fold(open, start, Some(FoldingKind::Region)));
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        let end = last_line(comment);
+        if end > start {
+            flush(&mut run, ranges);
+            ranges.push(fold(start, end, Some(FoldingKind::Comment)));
+            continue;
+        }
+        // Only comments on lines of their own join a run
+        let line_start = source[..comment.start_byte()].rfind('\n').map_or(0, |i| i + 1);
+        if !source[line_start..comment.start_byte()].trim().is_empty() {
+            flush(&mut run, ranges);
+            continue;
+        }
+        run = match run {
+            Some((first, last)) if last + 1 == start => Some((first, start)),
+            _ => {
+                flush(&mut run, ranges);
+                Some((start, start))
+            }
+        };
+    }
+    flush(&mut run, ranges);
+}
+
+fn flush(run: &mut Option<(u32, u32)>, ranges: &mut Vec<FoldingRange>) {
+    if let Some((first, last)) = run.take() {
+        ranges.push(fold(first, last, Some(FoldingKind::Comment)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LanguageParser;
+
+    fn folds(lang: LanguageId, source: &str) -> Vec<(u32, u32, Option<FoldingKind>)> {
+        let mut parser = LanguageParser::new();
+        parser.set_language(lang).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        folding_ranges(lang, &tree, source)
+            .into_iter()
+            .map(|range| (range.start_line, range.end_line, range.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_python_folds() {
+        let source = r#"import os
+import sys
+
+# region helpers
+def f(x):
+    # first
+    # second
+    return [
+        x,
+    ]
+
+x = f(1)
+# endregion
+"#;
+        let found = folds(LanguageId::Python, source);
+        assert!(found.contains(&(0, 1, Some(FoldingKind::Imports))));
+        assert!(found.contains(&(3, 12, Some(FoldingKind::Region))));
+        assert!(found.contains(&(4, 9, None)));
+        assert!(found.contains(&(5, 6, Some(FoldingKind::Comment))));
+        assert!(found.contains(&(7, 8, None)));
+    }
+
+    #[test]
+    fn test_html_and_css_folds() {
+        let html = "<ul>\n  <li>a</li>\n  <li>b</li>\n</ul>\n<!-- one\ntwo -->\n";
+        assert_eq!(folds(LanguageId::Html, html), vec![(0, 2, None), (4, 5, Some(FoldingKind::Comment))]);
+
+        let css = ".btn {\n  color: red;\n}\n@media print {\n  .btn {\n    display: none;\n  }\n}\n";
+        assert_eq!(folds(LanguageId::Css, css), vec![(0, 1, None), (3, 6, None), (4, 5, None)]);
+    }
+}
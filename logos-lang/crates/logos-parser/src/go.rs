@@ -2,7 +2,7 @@
 
 use logos_core::{Symbol, SymbolKind};
 use tree_sitter::{Node, Tree};
-use crate::node_to_range;
+use crate::{node_to_range, signature};
 
 /// Extract symbols from a Go AST
 pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
@@ -24,9 +24,7 @@ fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol
                     node_to_range(&name_node),
                 );
 
-                if let Some(params) = node.child_by_field_name("parameters") {
-                    symbol.detail = Some(get_node_text(&params, source));
-                }
+                symbol.detail = Some(signature(node, source));
 
                 symbols.push(symbol);
             }
@@ -41,9 +39,7 @@ fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol
                     node_to_range(&name_node),
                 );
 
-                if let Some(receiver) = node.child_by_field_name("receiver") {
-                    symbol.detail = Some(format!("receiver: {}", get_node_text(&receiver, source)));
-                }
+                symbol.detail = Some(signature(node, source));
 
                 symbols.push(symbol);
             }
@@ -123,7 +119,10 @@ fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol
 fn extract_struct_fields(node: &Node, source: &str, symbols: &mut Vec<Symbol>) {
     for i in 0..node.named_child_count() {
         if let Some(child) = node.named_child(i) {
-            if child.kind() == "field_declaration" {
+            // The fields of a `struct_type` are in its `field_declaration_list`
+            if child.kind() == "field_declaration_list" {
+                extract_struct_fields(&child, source, symbols);
+            } else if child.kind() == "field_declaration" {
                 if let Some(name_node) = child.child_by_field_name("name") {
                     let name = get_node_text(&name_node, source);
                     let mut symbol = Symbol::new(
@@ -0,0 +1,178 @@
+//! Grammars loaded at runtime
+//!
+//! A grammar built with `tree-sitter build` is a shared library exporting
+//! `tree_sitter_<name>()`, which returns the grammar's language tables.
+//! Loading one adds a language without rebuilding: it gets a
+//! [`LanguageId::Custom`] id, its files parse through the same parsers and
+//! pool as built-in languages, and it highlights when the configuration
+//! names a highlights query.
+//!
+//! Loaded libraries stay loaded for the life of the process: trees and
+//! parsers hold pointers into their tables.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{OnceLock, RwLock};
+
+use libloading::Library;
+use serde::Deserialize;
+use tree_sitter::Language;
+use tree_sitter_language::LanguageFn;
+
+use crate::{compat, LanguageId, ParseError};
+
+/// A grammar to load, as written in the configuration
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GrammarSpec {
+    /// Language id documents are opened with, e.g. `zig`
+    pub id: String,
+    /// Shared library built from the grammar
+    pub library: PathBuf,
+    /// Exported language function; `tree_sitter_<id>` by default
+    pub symbol: Option<String>,
+    /// File extensions of the language, without the dot
+    pub extensions: Vec<String>,
+    /// `highlights.scm` of the grammar, for semantic tokens
+    pub highlights: Option<PathBuf>,
+}
+
+struct LoadedGrammar {
+    language: Language,
+    extensions: Vec<String>,
+    highlights: Option<&'static str>,
+}
+
+/// Grammars loaded from shared libraries, keyed by language id
+#[derive(Default)]
+pub struct GrammarRegistry {
+    grammars: RwLock<HashMap<&'static str, LoadedGrammar>>,
+}
+
+impl GrammarRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the grammar of `spec`. An id that is already loaded keeps its
+    /// first grammar; ids of built-in languages are refused.
+    pub fn load(&self, spec: &GrammarSpec) -> Result<LanguageId, ParseError> {
+        if spec.id.is_empty() {
+            return Err(ParseError::LanguageError("Grammar without an id".to_string()));
+        }
+        if LanguageId::builtin_from_str(&spec.id).is_some() {
+            return Err(ParseError::LanguageError(format!("{} is a built-in language", spec.id)));
+        }
+        if let Some(id) = self.by_id(&spec.id) {
+            return Ok(id);
+        }
+
+        let symbol = spec.symbol.clone().unwrap_or_else(|| format!("tree_sitter_{}", spec.id.replace('-', "_")));
+        let failed = |e: String| ParseError::LanguageError(format!("{}: {}", spec.library.display(), e));
+        // SAFETY: loading runs the library's initializers; grammars are
+        // configured by the user, like plugins
+        let library = unsafe { Library::new(&spec.library) }.map_err(|e| failed(e.to_string()))?;
+        // SAFETY: the symbol is a tree-sitter language function, which takes
+        // no arguments and returns a pointer to static tables in `library`
+        let language = unsafe {
+            let function = library
+                .get::<unsafe extern "C" fn() -> *const ()>(symbol.as_bytes())
+                .map_err(|e| failed(e.to_string()))?;
+            Language::new(LanguageFn::from_raw(*function))
+        };
+        compat::check_abi(&language).map_err(failed)?;
+        let highlights = match &spec.highlights {
+            Some(path) => {
+                let query = std::fs::read_to_string(path).map_err(|e| failed(format!("{}: {}", path.display(), e)))?;
+                Some(&*Box::leak(query.into_boxed_str()))
+            }
+            None => None,
+        };
+
+        let mut grammars = self.grammars.write().map_err(|_| failed("registry lock poisoned".to_string()))?;
+        // Never unloaded, whichever registry loaded it: `language` and the
+        // trees and parsers built from it point into the library
+        std::mem::forget(library);
+        // Interned once per id; ids are never unloaded
+        let id: &'static str = Box::leak(spec.id.to_lowercase().into_boxed_str());
+        grammars.insert(id, LoadedGrammar {
+            language,
+            extensions: spec.extensions.iter().map(|e| e.trim_start_matches('.').to_lowercase()).collect(),
+            highlights,
+        });
+        Ok(LanguageId::Custom(id))
+    }
+
+    /// Id of a loaded grammar
+    pub fn by_id(&self, id: &str) -> Option<LanguageId> {
+        let grammars = self.grammars.read().ok()?;
+        let (id, _) = grammars.get_key_value(id.to_lowercase().as_str())?;
+        Some(LanguageId::Custom(id))
+    }
+
+    /// Loaded grammar handling files with extension `ext`
+    pub fn by_extension(&self, ext: &str) -> Option<LanguageId> {
+        let ext = ext.to_lowercase();
+        let grammars = self.grammars.read().ok()?;
+        grammars
+            .iter()
+            .find(|(_, grammar)| grammar.extensions.contains(&ext))
+            .map(|(id, _)| LanguageId::Custom(id))
+    }
+
+    pub fn language(&self, id: &str) -> Option<Language> {
+        self.grammars.read().ok()?.get(id).map(|grammar| grammar.language.clone())
+    }
+
+    pub fn highlights(&self, id: &str) -> Option<&'static str> {
+        self.grammars.read().ok()?.get(id)?.highlights
+    }
+
+    /// Ids of the loaded grammars, sorted
+    pub fn ids(&self) -> Vec<LanguageId> {
+        let Ok(grammars) = self.grammars.read() else {
+            return Vec::new();
+        };
+        let mut ids: Vec<_> = grammars.keys().map(|id| LanguageId::Custom(id)).collect();
+        ids.sort_by_key(|id| id.as_str());
+        ids
+    }
+}
+
+/// Process-wide registry consulted by [`LanguageId`]
+pub fn registry() -> &'static GrammarRegistry {
+    static REGISTRY: OnceLock<GrammarRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(GrammarRegistry::new)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rejected_specs() {
+        let registry = GrammarRegistry::new();
+        let builtin = GrammarSpec { id: "Python".to_string(), ..GrammarSpec::default() };
+        assert!(registry.load(&builtin).is_err());
+
+        let missing = GrammarSpec {
+            id: "zig".to_string(),
+            library: PathBuf::from("/nonexistent/libtree-sitter-zig.so"),
+            extensions: vec!["zig".to_string()],
+            ..GrammarSpec::default()
+        };
+        assert!(registry.load(&missing).is_err());
+        assert_eq!(registry.by_id("zig"), None);
+        assert_eq!(registry.by_extension("zig"), None);
+        assert!(registry.ids().is_empty());
+    }
+
+    #[test]
+    fn test_custom_ids_round_trip() {
+        // Without a loaded grammar, a custom id has no parser
+        let id = LanguageId::Custom("zig");
+        assert_eq!(id.as_str(), "zig");
+        assert!(crate::LanguageParser::new().set_language(id).is_err());
+        assert_eq!(LanguageId::from_str("zig"), None);
+    }
+}
@@ -0,0 +1,276 @@
+//! Syntax highlighting from the grammars' highlight queries
+//!
+//! Runs each language's `highlights.scm` over a parse tree and flattens
+//! the captures into non-overlapping, single-line tokens. Where captures
+//! nest, the innermost wins. For the same node, the grammars disagree on
+//! precedence: some queries open with catch-all patterns such as
+//! `(identifier) @variable` and refine them later, others list specific
+//! patterns first. Bare single-node patterns therefore always lose, and
+//! among the others the later pattern wins, which is where refinements by
+//! predicate (builtins, constants) sit in both styles.
+
+use std::sync::OnceLock;
+
+use logos_core::{Position, Range};
+use tree_sitter::Tree;
+
+use crate::query::QueryEngine;
+use crate::{LanguageId, ParseError};
+
+/// Semantic token types, in legend order. The first five are the lite
+/// tokenizer's, so its indices stay valid under this legend.
+pub const TOKEN_TYPES: [&str; 15] = [
+    "keyword", "string", "comment", "number", "function",
+    "method", "macro", "type", "class", "namespace",
+    "parameter", "variable", "property", "operator", "decorator",
+];
+
+/// Semantic token modifiers, in legend order
+pub const TOKEN_MODIFIERS: [&str; 2] = ["readonly", "defaultLibrary"];
+
+/// A highlighted span
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HighlightToken {
+    /// Single-line range; columns in UTF-16 code units
+    pub range: Range,
+    /// Capture name of the highlight query, e.g. `function.method`
+    pub scope: String,
+}
+
+impl HighlightToken {
+    /// Index of the token type in [`TOKEN_TYPES`] and the modifier bits for
+    /// [`TOKEN_MODIFIERS`]; `None` for scopes editors don't color, such as
+    /// punctuation
+    pub fn semantic_type(&self) -> Option<(u32, u32)> {
+        semantic_type(&self.scope)
+    }
+}
+
+/// Highlight query of a language, `None` when its grammar ships none
+pub fn highlights_query(lang: LanguageId) -> Option<&'static str> {
+    let query = match lang {
+        LanguageId::Python => tree_sitter_python::HIGHLIGHTS_QUERY,
+        LanguageId::Go => tree_sitter_go::HIGHLIGHTS_QUERY,
+        LanguageId::Rust => tree_sitter_rust::HIGHLIGHTS_QUERY,
+        LanguageId::C => tree_sitter_c::HIGHLIGHT_QUERY,
+        LanguageId::Cpp => tree_sitter_cpp::HIGHLIGHT_QUERY,
+        LanguageId::Java => tree_sitter_java::HIGHLIGHTS_QUERY,
+        LanguageId::JavaScript => tree_sitter_javascript::HIGHLIGHT_QUERY,
+        LanguageId::JavaScriptReact => {
+            static QUERY: OnceLock<String> = OnceLock::new();
+            QUERY.get_or_init(|| {
+                format!("{}\n{}", tree_sitter_javascript::HIGHLIGHT_QUERY, tree_sitter_javascript::JSX_HIGHLIGHT_QUERY)
+            })
+        }
+        LanguageId::TypeScript => {
+            // The TypeScript query only adds to the JavaScript one
+            static QUERY: OnceLock<String> = OnceLock::new();
+            QUERY.get_or_init(|| {
+                format!("{}\n{}", tree_sitter_javascript::HIGHLIGHT_QUERY, tree_sitter_typescript::HIGHLIGHTS_QUERY)
+            })
+        }
+        LanguageId::TypeScriptReact => {
+            static QUERY: OnceLock<String> = OnceLock::new();
+            QUERY.get_or_init(|| {
+                format!(
+                    "{}\n{}\n{}",
+                    tree_sitter_javascript::HIGHLIGHT_QUERY,
+                    tree_sitter_javascript::JSX_HIGHLIGHT_QUERY,
+                    tree_sitter_typescript::HIGHLIGHTS_QUERY
+                )
+            })
+        }
+        // Neither grammar has a query; their lexical highlighting will do
+        LanguageId::Kotlin | LanguageId::Protobuf => return None,
+        LanguageId::Swift => tree_sitter_swift::HIGHLIGHTS_QUERY,
+        // These grammar releases don't export their queries
+        LanguageId::CSharp => include_str!("../queries/c_sharp/highlights.scm"),
+        LanguageId::Bash | LanguageId::Dockerfile => tree_sitter_bash::HIGHLIGHT_QUERY,
+        LanguageId::Html => tree_sitter_html::HIGHLIGHTS_QUERY,
+        LanguageId::Css => tree_sitter_css::HIGHLIGHTS_QUERY,
+        LanguageId::Scss => tree_sitter_scss::HIGHLIGHTS_QUERY,
+        LanguageId::Json => tree_sitter_json::HIGHLIGHTS_QUERY,
+        LanguageId::Yaml => tree_sitter_yaml::HIGHLIGHTS_QUERY,
+        LanguageId::Toml => tree_sitter_toml_ng::HIGHLIGHTS_QUERY,
+        LanguageId::Markdown => tree_sitter_md::HIGHLIGHT_QUERY_BLOCK,
+        LanguageId::Sql => tree_sitter_sequel::HIGHLIGHTS_QUERY,
+        LanguageId::Elixir => tree_sitter_elixir::HIGHLIGHTS_QUERY,
+        LanguageId::Dart => include_str!("../queries/dart/highlights.scm"),
+        LanguageId::Custom(id) => return crate::grammar::registry().highlights(id),
+    };
+    Some(query)
+}
+
+/// Highlight tokens of `tree` in document order, or `None` for languages
+/// without a highlight query
+pub fn highlight(
+    engine: &mut QueryEngine,
+    lang: LanguageId,
+    tree: &Tree,
+    source: &str,
+) -> Result<Option<Vec<HighlightToken>>, ParseError> {
+    let Some(query) = highlights_query(lang) else {
+        return Ok(None);
+    };
+    let compiled = engine.compile(lang, query)?;
+    let fallback: Vec<bool> = (0..compiled.pattern_count())
+        .map(|i| {
+            let start = compiled.start_byte_for_pattern(i);
+            let end = if i + 1 < compiled.pattern_count() { compiled.start_byte_for_pattern(i + 1) } else { query.len() };
+            is_fallback_pattern(&query[start..end])
+        })
+        .collect();
+    let captures = engine.captures(lang, query, tree, source, None)?;
+
+    // Paint each byte with its innermost capture: smaller spans first, the
+    // preferred pattern first among spans of equal size, and a byte keeps
+    // the first span painted on it
+    let spans: Vec<(std::ops::Range<usize>, &str)> =
+        captures.iter().map(|c| (c.byte_range.clone(), c.name.as_str())).collect();
+    let mut order: Vec<usize> = (0..spans.len()).collect();
+    order.sort_by_key(|&i| {
+        let pattern = captures[i].pattern_index;
+        (spans[i].0.len(), std::cmp::Reverse((!fallback[pattern], pattern)))
+    });
+    let mut painted: Vec<Option<u32>> = vec![None; source.len()];
+    for i in order {
+        for byte in painted[spans[i].0.clone()].iter_mut() {
+            byte.get_or_insert(i as u32);
+        }
+    }
+
+    let mut tokens = Vec::new();
+    let (mut line, mut column) = (0u32, 0u32);
+    let mut current: Option<(u32, Position)> = None;
+    let mut flush = |current: &mut Option<(u32, Position)>, end: Position| {
+        if let Some((span, start)) = current.take() {
+            if end.column > start.column {
+                let scope = spans[span as usize].1.to_string();
+                tokens.push(HighlightToken { range: Range::new(start, end), scope });
+            }
+        }
+    };
+    for (offset, c) in source.char_indices() {
+        let here = Position::new(line, column);
+        let span = if c == '\n' || c == '\r' { None } else { painted[offset] };
+        if current.map(|(s, _)| s) != span {
+            flush(&mut current, here);
+            current = span.map(|s| (s, here));
+        }
+        if c == '\n' {
+            line += 1;
+            column = 0;
+        } else {
+            column += c.len_utf16() as u32;
+        }
+    }
+    flush(&mut current, Position::new(line, column));
+    Ok(Some(tokens))
+}
+
+/// Whether a pattern is a lone node with a capture, like
+/// `(identifier) @variable`: a default for nodes no other pattern claims
+fn is_fallback_pattern(pattern: &str) -> bool {
+    let code: String = pattern
+        .lines()
+        .map(|line| line.split(';').next().unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let Some(rest) = code.trim().strip_prefix('(') else {
+        return false;
+    };
+    let Some((node, capture)) = rest.split_once(')') else {
+        return false;
+    };
+    let capture = capture.trim();
+    node.chars().all(|c| c.is_alphanumeric() || c == '_')
+        && capture.starts_with('@')
+        && !capture.contains(char::is_whitespace)
+}
+
+fn semantic_type(scope: &str) -> Option<(u32, u32)> {
+    const READONLY: u32 = 1;
+    const DEFAULT_LIBRARY: u32 = 2;
+    let builtin = if scope.ends_with(".builtin") { DEFAULT_LIBRARY } else { 0 };
+    let token_type = match scope {
+        "function.method" | "method" => "method",
+        "function.macro" | "macro" => "macro",
+        "variable.parameter" | "parameter" => "parameter",
+        "constant.numeric" | "number" | "float" => "number",
+        "constant.builtin" | "boolean" => "keyword",
+        "constructor" => "class",
+        "attribute" => "decorator",
+        "tag" => "type",
+        "module" | "namespace" => "namespace",
+        "property" | "field" | "variable.member" => "property",
+        "string.regex" | "string.regexp" => "string",
+        "escape" | "string.escape" | "string.special" => "string",
+        _ => match scope.split('.').next().unwrap_or(scope) {
+            "keyword" | "conditional" | "repeat" | "include" | "exception" => "keyword",
+            "string" | "character" => "string",
+            "comment" => "comment",
+            "function" => "function",
+            "type" => "type",
+            "variable" => "variable",
+            "constant" => return Some((index_of("variable"), READONLY | builtin)),
+            "operator" => "operator",
+            _ => return None,
+        },
+    };
+    Some((index_of(token_type), builtin))
+}
+
+fn index_of(token_type: &str) -> u32 {
+    TOKEN_TYPES.iter().position(|t| *t == token_type).expect("token type in legend") as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LanguageParser;
+
+    const ALL: [LanguageId; 26] = [
+        LanguageId::Python, LanguageId::Go, LanguageId::Rust, LanguageId::C, LanguageId::Cpp,
+        LanguageId::Java, LanguageId::JavaScript, LanguageId::JavaScriptReact, LanguageId::TypeScript,
+        LanguageId::TypeScriptReact, LanguageId::Kotlin,
+        LanguageId::Swift, LanguageId::CSharp, LanguageId::Bash, LanguageId::Html, LanguageId::Css,
+        LanguageId::Scss, LanguageId::Json, LanguageId::Yaml, LanguageId::Toml, LanguageId::Markdown,
+        LanguageId::Sql, LanguageId::Elixir, LanguageId::Dart, LanguageId::Dockerfile,
+        LanguageId::Protobuf,
+    ];
+
+    #[test]
+    fn test_queries_compile() {
+        let mut engine = QueryEngine::new();
+        for lang in ALL {
+            if let Some(query) = highlights_query(lang) {
+                if let Err(e) = engine.compile(lang, query) {
+                    panic!("{}: {}", lang.as_str(), e);
+                }
+            }
+        }
+        assert_eq!(&TOKEN_TYPES[..5], &crate::lite::LiteTokenKind::LEGEND[..]);
+    }
+
+    #[test]
+    fn test_python_tokens() {
+        let source = "def greet(name):\n    return \"hi \\n\" + name  # done\n";
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Python).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let tokens = highlight(&mut QueryEngine::new(), LanguageId::Python, &tree, source).unwrap().unwrap();
+        let found: Vec<_> = tokens
+            .iter()
+            .map(|t| (t.range.start.line, t.range.start.column, t.range.end.column, t.scope.as_str()))
+            .collect();
+        assert!(found.contains(&(0, 0, 3, "keyword")));
+        assert!(found.contains(&(0, 4, 9, "function")));
+        assert!(found.contains(&(1, 4, 10, "keyword")));
+        // The escape sequence splits the string around it
+        assert!(found.contains(&(1, 11, 15, "string")));
+        assert!(found.contains(&(1, 15, 17, "escape")));
+        assert!(found.contains(&(1, 17, 18, "string")));
+        assert!(found.contains(&(1, 27, 33, "comment")));
+        assert_eq!(tokens[0].semantic_type(), Some((0, 0)));
+    }
+}
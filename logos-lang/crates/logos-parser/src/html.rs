@@ -0,0 +1,266 @@
+//! HTML parsing: anchor symbols and embedded script/style regions
+
+use logos_core::{Position, Range, Symbol, SymbolKind};
+use tree_sitter::{Node, Tree};
+use crate::{node_to_range, Injection};
+
+/// Extract symbols from an HTML AST.
+///
+/// Elements with an `id` (or an `<a name>`) become `#id` keys and each of
+/// their classes a `.class` symbol, nested under the closest anchored
+/// ancestor. The tag name is the detail.
+pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let root = tree.root_node();
+    extract_symbols_from_node(&root, source, &mut symbols);
+    symbols
+}
+
+fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol>) {
+    for i in 0..node.named_child_count() {
+        let Some(child) = node.named_child(i) else {
+            continue;
+        };
+        if child.kind() != "element" {
+            extract_symbols_from_node(&child, source, symbols);
+            continue;
+        }
+
+        let tag = find_child(&child, "start_tag").or_else(|| find_child(&child, "self_closing_tag"));
+        let mut anchors = tag.map(|tag| anchors(&child, &tag, source)).unwrap_or_default();
+        match anchors.first_mut() {
+            Some(anchor) => {
+                extract_symbols_from_node(&child, source, &mut anchor.children);
+                symbols.append(&mut anchors);
+            }
+            None => extract_symbols_from_node(&child, source, symbols),
+        }
+    }
+}
+
+/// Symbols for the id and classes of one element. The id (when present)
+/// comes first so that nested anchors can be attached to it.
+fn anchors(element: &Node, tag: &Node, source: &str) -> Vec<Symbol> {
+    let tag_name = find_child(tag, "tag_name").map(|n| get_node_text(&n, source)).unwrap_or_default();
+    let mut id = None;
+    let mut classes = Vec::new();
+
+    for i in 0..tag.named_child_count() {
+        let Some(attribute) = tag.named_child(i) else {
+            continue;
+        };
+        if attribute.kind() != "attribute" {
+            continue;
+        }
+        let Some(name) = find_child(&attribute, "attribute_name").map(|n| get_node_text(&n, source)) else {
+            continue;
+        };
+        let Some(value) = attribute_value(&attribute) else {
+            continue;
+        };
+        let text = get_node_text(&value, source);
+        match name.to_ascii_lowercase().as_str() {
+            "id" => id = Some((format!("#{}", text.trim()), node_to_range(&value))),
+            "name" if tag_name.eq_ignore_ascii_case("a") && id.is_none() => {
+                id = Some((format!("#{}", text.trim()), node_to_range(&value)))
+            }
+            "class" => {
+                for (offset, class) in split_whitespace_indices(&text) {
+                    classes.push((format!(".{}", class), token_range(&value, &text, offset, class.len())));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let range = node_to_range(element);
+    let mut symbols = Vec::new();
+    if let Some((name, selection)) = id {
+        let mut symbol = Symbol::new(name, SymbolKind::Key, range, selection);
+        symbol.detail = Some(tag_name.clone());
+        symbols.push(symbol);
+    }
+    for (name, selection) in classes {
+        let mut symbol = Symbol::new(name, SymbolKind::Class, range, selection);
+        symbol.detail = Some(tag_name.clone());
+        symbols.push(symbol);
+    }
+    symbols
+}
+
+/// Embedded script and style regions, with the language of their content.
+/// Scripts with a `src` or a non-script `type` (templates) are skipped.
+pub fn injections(tree: &Tree, source: &str) -> Vec<Injection> {
+    let mut injections = Vec::new();
+    collect_injections(&tree.root_node(), source, &mut injections);
+    injections
+}
+
+fn collect_injections(node: &Node, source: &str, injections: &mut Vec<Injection>) {
+    let language = match node.kind() {
+        "script_element" => script_language(node, source),
+        "style_element" => match tag_attribute(node, source, "lang").as_deref() {
+            None | Some("css") => Some("css"),
+            Some("scss") => Some("scss"),
+            Some(_) => None,
+        },
+        _ => {
+            for i in 0..node.named_child_count() {
+                if let Some(child) = node.named_child(i) {
+                    collect_injections(&child, source, injections);
+                }
+            }
+            return;
+        }
+    };
+
+    let Some(language) = language else {
+        return;
+    };
+    if let Some(text) = find_child(node, "raw_text") {
+        if !get_node_text(&text, source).trim().is_empty() {
+            injections.push(Injection {
+                language: language.to_string(),
+                range: node_to_range(&text),
+                byte_range: text.byte_range(),
+                holes: Vec::new(),
+            });
+        }
+    }
+}
+
+fn script_language(node: &Node, source: &str) -> Option<&'static str> {
+    if tag_attribute(node, source, "src").is_some() {
+        return None;
+    }
+    if let Some(lang) = tag_attribute(node, source, "lang") {
+        return match lang.as_str() {
+            "ts" | "typescript" => Some("typescript"),
+            "js" | "javascript" => Some("javascript"),
+            _ => None,
+        };
+    }
+    match tag_attribute(node, source, "type").map(|t| t.to_ascii_lowercase()).as_deref() {
+        None | Some("") | Some("module") | Some("text/javascript") | Some("application/javascript") => Some("javascript"),
+        Some("text/typescript") | Some("application/typescript") => Some("typescript"),
+        Some("importmap") | Some("application/json") | Some("application/ld+json") => Some("json"),
+        Some(_) => None,
+    }
+}
+
+/// Value of an attribute on the start tag of `element`
+fn tag_attribute(element: &Node, source: &str, name: &str) -> Option<String> {
+    let tag = find_child(element, "start_tag")?;
+    for i in 0..tag.named_child_count() {
+        let attribute = tag.named_child(i)?;
+        if attribute.kind() != "attribute" {
+            continue;
+        }
+        let attribute_name = find_child(&attribute, "attribute_name").map(|n| get_node_text(&n, source));
+        if attribute_name.is_some_and(|n| n.eq_ignore_ascii_case(name)) {
+            return Some(attribute_value(&attribute).map(|v| get_node_text(&v, source)).unwrap_or_default());
+        }
+    }
+    None
+}
+
+fn attribute_value<'a>(attribute: &Node<'a>) -> Option<Node<'a>> {
+    find_child(attribute, "attribute_value")
+        .or_else(|| find_child(attribute, "quoted_attribute_value").and_then(|q| find_child(&q, "attribute_value")))
+}
+
+/// Range of `len` bytes at `offset` inside `value`; the whole value when
+/// it spans lines
+fn token_range(value: &Node, text: &str, offset: usize, len: usize) -> Range {
+    if text[..offset].contains('\n') {
+        return node_to_range(value);
+    }
+    let start = value.start_position();
+    Range::new(
+        Position::new(start.row as u32, (start.column + offset) as u32),
+        Position::new(start.row as u32, (start.column + offset + len) as u32),
+    )
+}
+
+fn split_whitespace_indices(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split(char::is_whitespace)
+        .scan(0, |offset, part| {
+            let start = *offset;
+            *offset += part.len() + 1;
+            Some((start, part))
+        })
+        .filter(|(_, part)| !part.is_empty())
+}
+
+fn find_child<'a>(node: &Node<'a>, kind: &str) -> Option<Node<'a>> {
+    (0..node.named_child_count())
+        .filter_map(|i| node.named_child(i))
+        .find(|child| child.kind() == kind)
+}
+
+fn get_node_text(node: &Node, source: &str) -> String {
+    source[node.byte_range()].to_string()
+}
+
+/// Get common HTML element names
+pub fn get_keywords() -> &'static [&'static str] {
+    &[
+        "a", "article", "aside", "body", "button", "div", "footer", "form",
+        "h1", "h2", "h3", "head", "header", "html", "img", "input", "label",
+        "li", "link", "main", "meta", "nav", "ol", "option", "p", "script",
+        "section", "select", "span", "style", "table", "tbody", "td",
+        "template", "textarea", "th", "thead", "title", "tr", "ul",
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LanguageId, LanguageParser};
+
+    fn parse(source: &str) -> Tree {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Html).unwrap();
+        parser.parse(source, None).unwrap()
+    }
+
+    #[test]
+    fn test_anchor_symbols() {
+        let source = r#"<body>
+  <div id="app" class="container  wide">
+    <a name="top"></a>
+    <p class="note">Hi</p>
+  </div>
+</body>"#;
+        let symbols = extract_symbols(&parse(source), source);
+
+        let names: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            names,
+            vec![("#app", SymbolKind::Key), (".container", SymbolKind::Class), (".wide", SymbolKind::Class)]
+        );
+        assert_eq!(symbols[0].detail.as_deref(), Some("div"));
+        assert_eq!(symbols[2].selection_range, Range::from_coords(1, 34, 1, 38));
+        let nested: Vec<_> = symbols[0].children.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(nested, vec!["#top", ".note"]);
+    }
+
+    #[test]
+    fn test_script_and_style_injections() {
+        let source = r#"<head>
+  <style>.main { color: red; }</style>
+  <script type="module">
+    function init() {}
+  </script>
+  <script src="app.js"></script>
+  <script type="text/template"><b>x</b></script>
+  <script type="application/ld+json">{"a": 1}</script>
+</head>"#;
+        let injections = injections(&parse(source), source);
+
+        let languages: Vec<_> = injections.iter().map(|i| i.language.as_str()).collect();
+        assert_eq!(languages, vec!["css", "javascript", "json"]);
+        assert_eq!(&source[injections[0].byte_range.clone()], ".main { color: red; }");
+        assert_eq!(injections[1].range.start, Position::new(2, 24));
+    }
+}
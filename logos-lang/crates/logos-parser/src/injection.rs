@@ -0,0 +1,318 @@
+//! Embedded-language regions
+//!
+//! Besides the structural regions of HTML (`<script>`, `<style>`) and
+//! Markdown (fenced code), string literals can carry another language:
+//!
+//! - a literal after a `language=<id>` comment, in any language with
+//!   comments (`# language=sql` in Python, `/* language=jsx */` in
+//!   TypeScript)
+//! - tagged template literals in JavaScript and TypeScript (`sql`, `css`,
+//!   `html`, `gql`, `styled.div`)
+//!
+//! Regions are parsed on their own; [`offset_symbols`] and
+//! [`offset_diagnostics`] move what comes out of them back into document
+//! coordinates.
+
+use std::borrow::Cow;
+
+use logos_core::{Diagnostic, Position, Range, Symbol};
+use tree_sitter::{Node, Tree};
+
+use crate::{html, is_string_literal, markdown, LanguageId, Utf16Columns};
+
+/// A region of a document written in another language, such as a
+/// `<script>` block in HTML
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Injection {
+    /// Language id of the region content (`"javascript"`, `"css"`, ...),
+    /// which may be a language this build can't parse
+    pub language: String,
+    pub range: Range,
+    pub byte_range: std::ops::Range<usize>,
+    /// Interpolations inside the region (`${id}`, `{name}`), which belong to
+    /// the host language
+    pub holes: Vec<std::ops::Range<usize>>,
+}
+
+impl Injection {
+    /// Language of the region, if this build has a grammar for it
+    pub fn language_id(&self) -> Option<LanguageId> {
+        match self.language.to_lowercase().as_str() {
+            "postgresql" | "postgres" | "mysql" | "sqlite" | "plsql" | "tsql" => Some(LanguageId::Sql),
+            other => LanguageId::from_str(other),
+        }
+    }
+
+    /// Text of the region with its holes blanked out. Blanking keeps every
+    /// byte in place, so positions in the text map back by offsetting.
+    pub fn content<'a>(&self, source: &'a str) -> Cow<'a, str> {
+        let text = &source[self.byte_range.clone()];
+        if self.holes.is_empty() {
+            return Cow::Borrowed(text);
+        }
+        let mut content = text.to_string();
+        for hole in &self.holes {
+            let start = hole.start - self.byte_range.start;
+            let end = hole.end - self.byte_range.start;
+            let blank: String = text[start..end].bytes().map(|b| if b == b'\n' { '\n' } else { ' ' }).collect();
+            content.replace_range(start..end, &blank);
+        }
+        Cow::Owned(content)
+    }
+}
+
+/// Embedded-language regions of a document, in source order
+pub fn injections(lang: LanguageId, tree: &Tree, source: &str) -> Vec<Injection> {
+    let mut injections = match lang {
+        LanguageId::Html => html::injections(tree, source),
+        LanguageId::Markdown => markdown::injections(tree, source),
+        LanguageId::JavaScript | LanguageId::JavaScriptReact | LanguageId::TypeScript | LanguageId::TypeScriptReact => {
+            tagged_templates(tree, source)
+        }
+        _ => Vec::new(),
+    };
+    injections.extend(annotated_strings(tree, source));
+    injections.sort_by_key(|injection| injection.byte_range.start);
+    // A tagged template can carry a language comment as well
+    injections.dedup_by(|a, b| a.byte_range == b.byte_range);
+    let columns = Utf16Columns::new(source);
+    for injection in &mut injections {
+        columns.convert_range(&mut injection.range);
+    }
+    injections
+}
+
+/// String literals following a `language=<id>` comment. The comment goes on
+/// the line before the statement holding the literal, or right before the
+/// literal itself.
+pub fn annotated_strings(tree: &Tree, source: &str) -> Vec<Injection> {
+    let mut injections = Vec::new();
+    collect_annotated_strings(tree.root_node(), source, &mut injections);
+    injections
+}
+
+fn collect_annotated_strings(node: Node, source: &str, injections: &mut Vec<Injection>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if child.kind().contains("comment") {
+            if let Some(injection) = annotated_string(&child, source) {
+                injections.push(injection);
+            }
+        } else {
+            collect_annotated_strings(child, source, injections);
+        }
+    }
+}
+
+fn annotated_string(comment: &Node, source: &str) -> Option<Injection> {
+    let language = comment_language(source.get(comment.byte_range())?)?;
+    let target = comment.next_named_sibling()?;
+    // A trailing comment annotates its own line, not the next statement
+    let line_start = source[..comment.start_byte()].rfind('\n').map_or(0, |i| i + 1);
+    let own_line = source[line_start..comment.start_byte()].trim().is_empty();
+    if !own_line && target.start_position().row != comment.end_position().row {
+        return None;
+    }
+    let literal = first_string(target)?;
+    string_injection(&literal, language)
+}
+
+/// `x` of a `language=x` comment
+fn comment_language(comment: &str) -> Option<&str> {
+    let text = comment.trim_start_matches(['#', '/', '*', '-', ';']).trim_start();
+    let text = text.strip_prefix("language")?.trim_start().strip_prefix('=')?.trim_start();
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || matches!(c, '_' | '-' | '+' | '#' | '.')))
+        .unwrap_or(text.len());
+    (end > 0).then(|| &text[..end])
+}
+
+/// `node` or its first string literal descendant
+fn first_string(node: Node) -> Option<Node> {
+    if is_string_literal(&node) {
+        return Some(node);
+    }
+    let mut cursor = node.walk();
+    let children: Vec<_> = node.named_children(&mut cursor).collect();
+    children.into_iter().find_map(first_string)
+}
+
+/// The text between the delimiters of a string literal
+fn string_injection(literal: &Node, language: &str) -> Option<Injection> {
+    let count = literal.child_count();
+    if count < 2 {
+        return None;
+    }
+    let open = literal.child(0)?;
+    let close = literal.child(count - 1)?;
+    if open.end_byte() >= close.start_byte() {
+        return None;
+    }
+    let mut holes = Vec::new();
+    let mut cursor = literal.walk();
+    for child in literal.named_children(&mut cursor) {
+        if child.kind().contains("interpolation") || child.kind().contains("substitution") {
+            holes.push(child.byte_range());
+        }
+    }
+    let start = open.end_position();
+    let end = close.start_position();
+    Some(Injection {
+        language: language.to_string(),
+        range: Range::from_coords(start.row as u32, start.column as u32, end.row as u32, end.column as u32),
+        byte_range: open.end_byte()..close.start_byte(),
+        holes,
+    })
+}
+
+/// Tagged template literals of JavaScript and TypeScript whose tag names a
+/// language: `sql`, `css`, `html`, `gql`, and styled-components
+/// (`styled.div`, `styled(Button)`)
+pub fn tagged_templates(tree: &Tree, source: &str) -> Vec<Injection> {
+    let mut injections = Vec::new();
+    collect_tagged_templates(tree.root_node(), source, &mut injections);
+    injections
+}
+
+fn collect_tagged_templates(node: Node, source: &str, injections: &mut Vec<Injection>) {
+    if node.kind() == "call_expression" {
+        let template = node.child_by_field_name("arguments").filter(|a| a.kind() == "template_string");
+        let language = node.child_by_field_name("function").and_then(|tag| tag_language(&tag, source));
+        if let (Some(template), Some(language)) = (template, language) {
+            if let Some(injection) = string_injection(&template, language) {
+                injections.push(injection);
+            }
+        }
+    }
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        collect_tagged_templates(child, source, injections);
+    }
+}
+
+fn tag_language(tag: &Node, source: &str) -> Option<&'static str> {
+    let text = |node: Node| source.get(node.byte_range()).unwrap_or_default();
+    let name = match tag.kind() {
+        "identifier" => text(*tag),
+        // styled.div`...`
+        "member_expression" => text(tag.child_by_field_name("object")?),
+        // styled(Button)`...`
+        "call_expression" => text(tag.child_by_field_name("function")?),
+        _ => return None,
+    };
+    match name {
+        "sql" | "SQL" => Some("sql"),
+        "css" | "keyframes" | "createGlobalStyle" | "injectGlobal" | "styled" => Some("css"),
+        "html" | "svg" => Some("html"),
+        "gql" | "graphql" => Some("graphql"),
+        "md" | "markdown" => Some("markdown"),
+        _ => None,
+    }
+}
+
+fn offset_position(position: &mut Position, origin: Position) {
+    if position.line == 0 {
+        position.column += origin.column;
+    }
+    position.line += origin.line;
+}
+
+/// Move a range of an injected region to document coordinates, given the
+/// position where the region starts
+pub fn offset_range(range: &mut Range, origin: Position) {
+    offset_position(&mut range.start, origin);
+    offset_position(&mut range.end, origin);
+}
+
+/// Move symbols parsed from an injected region to document coordinates,
+/// given the position where the region starts
+pub fn offset_symbols(symbols: &mut [Symbol], origin: Position) {
+    for symbol in symbols {
+        offset_range(&mut symbol.range, origin);
+        offset_range(&mut symbol.selection_range, origin);
+        offset_symbols(&mut symbol.children, origin);
+    }
+}
+
+/// Move diagnostics of an injected region to document coordinates. Related
+/// information is assumed to point into the same region.
+pub fn offset_diagnostics(diagnostics: &mut [Diagnostic], origin: Position) {
+    for diagnostic in diagnostics {
+        offset_range(&mut diagnostic.range, origin);
+        for related in &mut diagnostic.related_information {
+            offset_range(&mut related.range, origin);
+        }
+    }
+}
+
+/// Syntax errors of the injected regions written in one of `languages`, in
+/// document coordinates
+#[cfg(not(target_arch = "wasm32"))]
+pub fn injected_parse_errors(lang: LanguageId, tree: &Tree, source: &str, languages: &[LanguageId]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for injection in injections(lang, tree, source) {
+        let Some(embedded) = injection.language_id().filter(|l| languages.contains(l)) else {
+            continue;
+        };
+        let content = injection.content(source);
+        let Some(injected) = crate::pool::shared().checkout(embedded).ok().and_then(|mut p| p.parse(&content, None).ok())
+        else {
+            continue;
+        };
+        let mut errors = crate::extract_parse_errors(&injected, &content);
+        offset_diagnostics(&mut errors, injection.range.start);
+        diagnostics.extend(errors);
+    }
+    diagnostics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LanguageParser;
+
+    fn parse(lang: LanguageId, source: &str) -> Tree {
+        let mut parser = LanguageParser::new();
+        parser.set_language(lang).unwrap();
+        parser.parse(source, None).unwrap()
+    }
+
+    #[test]
+    fn test_language_comments() {
+        let source = "# language=sql\nquery = f\"SELECT * FROM {table} WHERE id = 1\"\nother = \"x\"  # language=json\nlast = \"[]\"\n";
+        let tree = parse(LanguageId::Python, source);
+        let injections = injections(LanguageId::Python, &tree, source);
+
+        assert_eq!(injections.len(), 1);
+        let injection = &injections[0];
+        assert_eq!(injection.language_id(), Some(LanguageId::Sql));
+        assert_eq!(injection.range.start, Position::new(1, 10));
+        assert_eq!(injection.holes.len(), 1);
+        assert_eq!(injection.content(source), format!("SELECT * FROM {} WHERE id = 1", " ".repeat(7)));
+        assert_eq!(comment_language("/* language=jsx */"), Some("jsx"));
+        assert_eq!(comment_language("// languages: sql"), None);
+    }
+
+    #[test]
+    fn test_tagged_templates() {
+        let source = "const q = sql`SELECT ${cols} FROM t`;\nconst Box = styled.div`\n  color: red;\n`;\nconst s = other`x`;\n";
+        let tree = parse(LanguageId::TypeScript, source);
+        let injections = injections(LanguageId::TypeScript, &tree, source);
+
+        let languages: Vec<_> = injections.iter().map(|i| i.language.as_str()).collect();
+        assert_eq!(languages, vec!["sql", "css"]);
+        assert_eq!(injections[0].content(source), format!("SELECT {} FROM t", " ".repeat(7)));
+        assert_eq!(injections[1].range.start, Position::new(1, 23));
+    }
+
+    #[test]
+    fn test_injected_parse_errors_in_document_coordinates() {
+        let source = "def f():\n    # language=json\n    data = '{\"a\": }'\n";
+        let tree = parse(LanguageId::Python, source);
+
+        let errors = injected_parse_errors(LanguageId::Python, &tree, source, &[LanguageId::Json]);
+        assert!(!errors.is_empty());
+        assert!(errors.iter().all(|e| e.range.start.line == 2 && e.range.start.column >= 12));
+        assert!(injected_parse_errors(LanguageId::Python, &tree, source, &[LanguageId::Sql]).is_empty());
+    }
+}
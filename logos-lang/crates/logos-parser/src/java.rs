@@ -2,7 +2,7 @@
 
 use logos_core::{Symbol, SymbolKind};
 use tree_sitter::{Node, Tree};
-use crate::node_to_range;
+use crate::{node_to_range, signature};
 
 /// Extract symbols from a Java AST
 pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
@@ -98,9 +98,7 @@ fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol
                     node_to_range(&name_node),
                 );
 
-                if let Some(params) = node.child_by_field_name("parameters") {
-                    symbol.detail = Some(get_node_text(&params, source));
-                }
+                symbol.detail = Some(signature(node, source));
 
                 symbols.push(symbol);
             }
@@ -2,7 +2,7 @@
 
 use logos_core::{Symbol, SymbolKind};
 use tree_sitter::{Node, Tree};
-use crate::node_to_range;
+use crate::{node_to_range, signature, signature_between};
 
 /// Extract symbols from a JavaScript AST
 pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
@@ -24,9 +24,7 @@ fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol
                     node_to_range(&name_node),
                 );
 
-                if let Some(params) = node.child_by_field_name("parameters") {
-                    symbol.detail = Some(get_node_text(&params, source));
-                }
+                symbol.detail = Some(signature(node, source));
 
                 symbols.push(symbol);
             }
@@ -59,12 +57,9 @@ fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol
                     SymbolKind::Method
                 };
 
-                symbols.push(Symbol::new(
-                    name,
-                    kind,
-                    node_to_range(node),
-                    node_to_range(&name_node),
-                ));
+                let mut symbol = Symbol::new(name, kind, node_to_range(node), node_to_range(&name_node));
+                symbol.detail = Some(signature(node, source));
+                symbols.push(symbol);
             }
         }
         "variable_declaration" | "lexical_declaration" => {
@@ -89,22 +84,23 @@ fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol
                             };
 
                             // Check if it's a function expression or arrow function
-                            let actual_kind = if let Some(value) = child.child_by_field_name("value") {
-                                match value.kind() {
-                                    "arrow_function" | "function_expression" => SymbolKind::Function,
-                                    "class" => SymbolKind::Class,
-                                    _ => kind,
-                                }
-                            } else {
-                                kind
+                            let value = child.child_by_field_name("value");
+                            let actual_kind = match value.map(|v| v.kind()) {
+                                Some("arrow_function" | "function_expression") => SymbolKind::Function,
+                                Some("class") => SymbolKind::Class,
+                                _ => kind,
                             };
 
-                            symbols.push(Symbol::new(
-                                name,
-                                actual_kind,
-                                node_to_range(node),
-                                node_to_range(&name_node),
-                            ));
+                            let mut symbol =
+                                Symbol::new(name, actual_kind, node_to_range(node), node_to_range(&name_node));
+                            // `const add = (a, b) =>`: the declaration up to the function body
+                            if let Some(body) = value
+                                .filter(|_| actual_kind == SymbolKind::Function)
+                                .and_then(|v| v.child_by_field_name("body"))
+                            {
+                                symbol.detail = Some(signature_between(source, node.start_byte(), body.start_byte()));
+                            }
+                            symbols.push(symbol);
                         }
                     }
                 }
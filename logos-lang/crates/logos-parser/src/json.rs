@@ -0,0 +1,218 @@
+//! JSON parsing: structural outline and parse error messages
+
+use logos_core::{Position, Range, Symbol, SymbolKind};
+use tree_sitter::{Node, Tree};
+use crate::node_to_range;
+
+/// Extract symbols from a JSON AST.
+///
+/// Every object key becomes a symbol whose kind follows its value, with the
+/// keys of nested objects as children. Objects and arrays inside arrays are
+/// listed by index (`[0]`, `[1]`, ...).
+pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
+    let root = tree.root_node();
+    (0..root.named_child_count())
+        .filter_map(|i| root.named_child(i))
+        .flat_map(|value| members(&value, source))
+        .collect()
+}
+
+/// Symbols for the members of a value; scalars have none
+fn members(value: &Node, source: &str) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    match value.kind() {
+        "object" => {
+            for i in 0..value.named_child_count() {
+                let Some(pair) = value.named_child(i).filter(|n| n.kind() == "pair") else {
+                    continue;
+                };
+                let Some(key) = pair.child_by_field_name("key") else {
+                    continue;
+                };
+                let name = unquote(&source[key.byte_range()]);
+                let child = pair.child_by_field_name("value");
+                let kind = child.as_ref().map_or(SymbolKind::Null, value_kind);
+                let mut symbol = Symbol::new(name, kind, node_to_range(&pair), node_to_range(&key));
+                if let Some(child) = child {
+                    symbol.children = members(&child, source);
+                }
+                symbols.push(symbol);
+            }
+        }
+        "array" => {
+            let elements = (0..value.named_child_count()).filter_map(|i| value.named_child(i));
+            for (index, element) in elements.filter(|n| n.kind() != "comment").enumerate() {
+                if !matches!(element.kind(), "object" | "array") {
+                    continue;
+                }
+                let range = node_to_range(&element);
+                let mut symbol = Symbol::new(format!("[{}]", index), value_kind(&element), range, range);
+                symbol.children = members(&element, source);
+                symbols.push(symbol);
+            }
+        }
+        _ => {}
+    }
+    symbols
+}
+
+fn value_kind(value: &Node) -> SymbolKind {
+    match value.kind() {
+        "object" => SymbolKind::Object,
+        "array" => SymbolKind::Array,
+        "string" => SymbolKind::String,
+        "number" => SymbolKind::Number,
+        "true" | "false" => SymbolKind::Boolean,
+        _ => SymbolKind::Null,
+    }
+}
+
+fn unquote(text: &str) -> String {
+    text.strip_prefix('"').and_then(|t| t.strip_suffix('"')).unwrap_or(text).to_string()
+}
+
+/// A specific message for the common JSON mistakes behind an error or
+/// missing node: trailing commas, missing commas and unquoted or
+/// single-quoted strings. `None` leaves the generic message.
+pub fn describe_error(node: &Node, source: &str) -> Option<(Range, String)> {
+    let bytes = source.as_bytes();
+    let next = next_non_whitespace(bytes, node.end_byte());
+    let text = source[node.byte_range()].trim();
+
+    // `[1, 2,]` leaves a missing value after the comma; `{"a": 1, }` an
+    // error node holding the comma
+    if matches!(next.map(|i| bytes[i]), Some(b'}' | b']')) {
+        let comma = if node.is_missing() || text.is_empty() {
+            previous_non_whitespace(bytes, node.start_byte()).filter(|&i| bytes[i] == b',')
+        } else if text == "," {
+            source[node.byte_range()].find(',').map(|i| node.start_byte() + i)
+        } else {
+            None
+        };
+        if let Some(comma) = comma {
+            return Some((byte_range_to_range(source, comma, comma + 1), "Trailing comma".to_string()));
+        }
+    }
+
+    if node.is_missing() {
+        return None;
+    }
+
+    // An error wrapping a complete member that runs into the next one
+    let complete = node.named_child_count() > 0
+        && (0..node.named_child_count())
+            .filter_map(|i| node.named_child(i))
+            .all(|child| !child.is_error() && !child.has_error());
+    if complete && matches!(next.map(|i| bytes[i]), Some(b'"' | b'{' | b'[')) {
+        let end = byte_range_to_range(source, node.end_byte(), node.end_byte());
+        return Some((end, "Missing comma".to_string()));
+    }
+
+    // The first character that isn't structure is the offending token
+    let offset = source[node.byte_range()].find(|c: char| !c.is_whitespace() && !"{[,:".contains(c))?;
+    let start = node.start_byte() + offset;
+    let rest = &source[start..];
+    if let Some(quoted) = rest.strip_prefix('\'') {
+        let len = quoted.find(['\'', '\n']).map_or(rest.len(), |i| i + 2);
+        let message = "Strings must be enclosed in double quotes".to_string();
+        return Some((byte_range_to_range(source, start, start + len), message));
+    }
+    let word_len = rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$')).unwrap_or(rest.len());
+    if word_len == 0 || rest.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let word = &rest[..word_len];
+    let is_key = next_non_whitespace(bytes, start + word_len).is_some_and(|i| bytes[i] == b':');
+    let message = if is_key {
+        format!("Property keys must be double-quoted: \"{}\"", word)
+    } else {
+        format!("Unexpected `{}`; strings must be double-quoted", word)
+    };
+    Some((byte_range_to_range(source, start, start + word_len), message))
+}
+
+fn next_non_whitespace(bytes: &[u8], from: usize) -> Option<usize> {
+    (from..bytes.len()).find(|&i| !bytes[i].is_ascii_whitespace())
+}
+
+fn previous_non_whitespace(bytes: &[u8], before: usize) -> Option<usize> {
+    (0..before).rev().find(|&i| !bytes[i].is_ascii_whitespace())
+}
+
+fn byte_range_to_range(source: &str, start: usize, end: usize) -> Range {
+    Range::new(byte_to_position(source, start), byte_to_position(source, end))
+}
+
+fn byte_to_position(source: &str, offset: usize) -> Position {
+    let before = &source[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position::new(line as u32, (offset - line_start) as u32)
+}
+
+/// Get JSON literals
+pub fn get_keywords() -> &'static [&'static str] {
+    &["true", "false", "null"]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{extract_parse_errors, LanguageId, LanguageParser};
+
+    fn parse(source: &str) -> Tree {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Json).unwrap();
+        parser.parse(source, None).unwrap()
+    }
+
+    #[test]
+    fn test_nested_outline() {
+        let source = r#"{
+  "name": "logos",
+  "private": true,
+  "scripts": { "build": "tsc", "test": null },
+  "files": ["dist", { "glob": "*.d.ts" }]
+}"#;
+        let symbols = extract_symbols(&parse(source), source);
+
+        let names: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("name", SymbolKind::String),
+                ("private", SymbolKind::Boolean),
+                ("scripts", SymbolKind::Object),
+                ("files", SymbolKind::Array),
+            ]
+        );
+        assert_eq!(symbols[0].selection_range, Range::from_coords(1, 2, 1, 8));
+        let scripts: Vec<_> = symbols[2].children.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(scripts, vec![("build", SymbolKind::String), ("test", SymbolKind::Null)]);
+        assert_eq!(symbols[3].children[0].name, "[1]");
+        assert_eq!(symbols[3].children[0].children[0].name, "glob");
+    }
+
+    #[test]
+    fn test_parse_error_messages() {
+        let messages = |source: &str| -> Vec<(String, Range)> {
+            extract_parse_errors(&parse(source), source)
+                .into_iter()
+                .map(|d| (d.message, d.range))
+                .collect()
+        };
+
+        let errors = messages(r#"{"a": [1, 2,], "b": 1, }"#);
+        assert_eq!(errors[0], ("Trailing comma".to_string(), Range::from_coords(0, 11, 0, 12)));
+        assert_eq!(errors[1], ("Trailing comma".to_string(), Range::from_coords(0, 21, 0, 22)));
+
+        let errors = messages(r#"{"a": 1 "b": 2}"#);
+        assert_eq!(errors[0].0, "Missing comma");
+
+        let errors = messages(r#"{name: 1}"#);
+        assert_eq!(errors[0], ("Property keys must be double-quoted: \"name\"".to_string(), Range::from_coords(0, 1, 0, 5)));
+
+        let errors = messages(r#"{'a': 1}"#);
+        assert_eq!(errors[0], ("Strings must be enclosed in double quotes".to_string(), Range::from_coords(0, 1, 0, 4)));
+    }
+}
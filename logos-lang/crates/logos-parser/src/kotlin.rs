@@ -0,0 +1,310 @@
+//! Kotlin-specific parsing and symbol extraction
+
+use logos_core::{Symbol, SymbolKind};
+use tree_sitter::{Node, Tree};
+use crate::node_to_range;
+
+/// Extract symbols from a Kotlin AST
+pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let root = tree.root_node();
+    extract_symbols_from_node(&root, source, &mut symbols, false);
+    symbols
+}
+
+fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol>, in_class: bool) {
+    match node.kind() {
+        "class_declaration" => {
+            if let Some(name_node) = node.child_by_field_name("name") {
+                let name = get_node_text(&name_node, source);
+                let kind = if has_token(node, "interface") {
+                    SymbolKind::Interface
+                } else if has_class_modifier(node, source, "enum") {
+                    SymbolKind::Enum
+                } else {
+                    SymbolKind::Class
+                };
+                let mut symbol = Symbol::new(name, kind, node_to_range(node), node_to_range(&name_node));
+                if has_class_modifier(node, source, "data") {
+                    symbol.detail = Some("data class".to_string());
+                }
+
+                let mut children = Vec::new();
+                for i in 0..node.named_child_count() {
+                    if let Some(child) = node.named_child(i) {
+                        match child.kind() {
+                            "primary_constructor" => extract_constructor_properties(&child, source, &mut children),
+                            "class_body" | "enum_class_body" => extract_class_members(&child, source, &mut children),
+                            _ => {}
+                        }
+                    }
+                }
+                symbol.children = children;
+
+                symbols.push(symbol);
+            }
+        }
+        "object_declaration" | "companion_object" => {
+            let name_node = node.child_by_field_name("name");
+            let name = name_node
+                .map(|n| get_node_text(&n, source))
+                .unwrap_or_else(|| "Companion".to_string());
+            let selection = name_node.map(|n| node_to_range(&n)).unwrap_or_else(|| node_to_range(node));
+            let mut symbol = Symbol::new(name, SymbolKind::Object, node_to_range(node), selection);
+
+            for i in 0..node.named_child_count() {
+                if let Some(child) = node.named_child(i) {
+                    if child.kind() == "class_body" {
+                        let mut children = Vec::new();
+                        extract_class_members(&child, source, &mut children);
+                        symbol.children = children;
+                    }
+                }
+            }
+
+            symbols.push(symbol);
+        }
+        "function_declaration" => {
+            if let Some(name_node) = node.child_by_field_name("name") {
+                let name = get_node_text(&name_node, source);
+                let kind = if in_class { SymbolKind::Method } else { SymbolKind::Function };
+                let mut symbol = Symbol::new(name, kind, node_to_range(node), node_to_range(&name_node));
+
+                if let Some(params) = find_child(node, "function_value_parameters") {
+                    symbol.detail = Some(get_node_text(&params, source));
+                }
+
+                symbols.push(symbol);
+            }
+        }
+        "secondary_constructor" => {
+            let keyword = node.child(0).filter(|c| c.kind() == "constructor");
+            let selection = keyword.map(|k| node_to_range(&k)).unwrap_or_else(|| node_to_range(node));
+            let mut symbol = Symbol::new(
+                "constructor".to_string(),
+                SymbolKind::Constructor,
+                node_to_range(node),
+                selection,
+            );
+
+            if let Some(params) = find_child(node, "function_value_parameters") {
+                symbol.detail = Some(get_node_text(&params, source));
+            }
+
+            symbols.push(symbol);
+        }
+        "property_declaration" => {
+            let kind = if has_modifier_text(node, source, "const") {
+                SymbolKind::Constant
+            } else if in_class {
+                SymbolKind::Property
+            } else {
+                SymbolKind::Variable
+            };
+
+            let mut declarations = Vec::new();
+            for i in 0..node.named_child_count() {
+                if let Some(child) = node.named_child(i) {
+                    match child.kind() {
+                        "variable_declaration" => declarations.push(child),
+                        "multi_variable_declaration" => {
+                            for j in 0..child.named_child_count() {
+                                if let Some(decl) = child.named_child(j) {
+                                    if decl.kind() == "variable_declaration" {
+                                        declarations.push(decl);
+                                    }
+                                }
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            for decl in declarations {
+                if let Some(name_node) = find_child(&decl, "identifier") {
+                    let name = get_node_text(&name_node, source);
+                    symbols.push(Symbol::new(name, kind, node_to_range(node), node_to_range(&name_node)));
+                }
+            }
+        }
+        // Don't descend into function bodies or lambdas for symbols
+        "function_body" | "lambda_literal" | "anonymous_function" => {}
+        _ => {
+            for i in 0..node.named_child_count() {
+                if let Some(child) = node.named_child(i) {
+                    extract_symbols_from_node(&child, source, symbols, in_class);
+                }
+            }
+        }
+    }
+}
+
+fn extract_class_members(node: &Node, source: &str, symbols: &mut Vec<Symbol>) {
+    for i in 0..node.named_child_count() {
+        if let Some(child) = node.named_child(i) {
+            if child.kind() == "enum_entry" {
+                if let Some(name_node) = find_child(&child, "identifier") {
+                    let name = get_node_text(&name_node, source);
+                    symbols.push(Symbol::new(
+                        name,
+                        SymbolKind::EnumMember,
+                        node_to_range(&child),
+                        node_to_range(&name_node),
+                    ));
+                }
+            } else {
+                extract_symbols_from_node(&child, source, symbols, true);
+            }
+        }
+    }
+}
+
+/// `class User(val name: String)` declares a property per `val`/`var` parameter
+fn extract_constructor_properties(node: &Node, source: &str, symbols: &mut Vec<Symbol>) {
+    let Some(params) = find_child(node, "class_parameters") else {
+        return;
+    };
+    for i in 0..params.named_child_count() {
+        if let Some(param) = params.named_child(i) {
+            if param.kind() != "class_parameter" || !(has_token(&param, "val") || has_token(&param, "var")) {
+                continue;
+            }
+            if let Some(name_node) = find_child(&param, "identifier") {
+                let name = get_node_text(&name_node, source);
+                symbols.push(Symbol::new(
+                    name,
+                    SymbolKind::Property,
+                    node_to_range(&param),
+                    node_to_range(&name_node),
+                ));
+            }
+        }
+    }
+}
+
+fn find_child<'a>(node: &Node<'a>, kind: &str) -> Option<Node<'a>> {
+    (0..node.named_child_count())
+        .filter_map(|i| node.named_child(i))
+        .find(|child| child.kind() == kind)
+}
+
+/// Whether an anonymous keyword token (e.g. `interface`, `val`) is a direct child
+fn has_token(node: &Node, kind: &str) -> bool {
+    (0..node.child_count())
+        .filter_map(|i| node.child(i))
+        .any(|child| !child.is_named() && child.kind() == kind)
+}
+
+fn has_class_modifier(node: &Node, source: &str, modifier: &str) -> bool {
+    find_child(node, "modifiers").is_some_and(|modifiers| {
+        (0..modifiers.named_child_count())
+            .filter_map(|i| modifiers.named_child(i))
+            .any(|m| m.kind() == "class_modifier" && get_node_text(&m, source) == modifier)
+    })
+}
+
+fn has_modifier_text(node: &Node, source: &str, modifier: &str) -> bool {
+    find_child(node, "modifiers").is_some_and(|modifiers| {
+        (0..modifiers.named_child_count())
+            .filter_map(|i| modifiers.named_child(i))
+            .any(|m| get_node_text(&m, source) == modifier)
+    })
+}
+
+fn get_node_text(node: &Node, source: &str) -> String {
+    source[node.byte_range()].to_string()
+}
+
+/// Get Kotlin keywords
+pub fn get_keywords() -> &'static [&'static str] {
+    &[
+        "abstract", "annotation", "as", "break", "by", "catch", "class",
+        "companion", "const", "constructor", "continue", "crossinline", "data",
+        "do", "else", "enum", "external", "false", "final", "finally", "for",
+        "fun", "get", "if", "import", "in", "infix", "init", "inline", "inner",
+        "interface", "internal", "is", "lateinit", "noinline", "null", "object",
+        "open", "operator", "out", "override", "package", "private", "protected",
+        "public", "reified", "return", "sealed", "set", "super", "suspend",
+        "this", "throw", "true", "try", "typealias", "val", "var", "vararg",
+        "when", "where", "while",
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LanguageId, LanguageParser};
+
+    fn parse(source: &str) -> Vec<Symbol> {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Kotlin).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        extract_symbols(&tree, source)
+    }
+
+    #[test]
+    fn test_classes_and_members() {
+        let symbols = parse(
+            r#"
+data class User(val name: String, age: Int) {
+    fun greet(times: Int): String = name
+    companion object {
+        fun create(): User = User("a", 1)
+    }
+}
+enum class Color { RED, GREEN }
+interface Shape { fun area(): Double }
+"#,
+        );
+
+        let user = &symbols[0];
+        assert_eq!((user.name.as_str(), user.kind), ("User", SymbolKind::Class));
+        assert_eq!(user.detail.as_deref(), Some("data class"));
+        let members: Vec<_> = user.children.iter().map(|c| (c.name.as_str(), c.kind)).collect();
+        assert_eq!(
+            members,
+            vec![
+                ("name", SymbolKind::Property),
+                ("greet", SymbolKind::Method),
+                ("Companion", SymbolKind::Object),
+            ]
+        );
+        assert_eq!(user.children[1].detail.as_deref(), Some("(times: Int)"));
+        assert_eq!(user.children[2].children[0].name, "create");
+
+        assert_eq!(symbols[1].kind, SymbolKind::Enum);
+        assert_eq!(symbols[1].children.len(), 2);
+        assert_eq!(symbols[1].children[0].kind, SymbolKind::EnumMember);
+        assert_eq!(symbols[2].kind, SymbolKind::Interface);
+    }
+
+    #[test]
+    fn test_objects_functions_properties() {
+        let symbols = parse(
+            r#"
+object Registry {
+    const val MAX = 3
+    val items = mutableListOf<String>()
+}
+fun top(x: Int): Int {
+    val local = x
+    return local
+}
+val answer: Int = 42
+"#,
+        );
+
+        let kinds: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("Registry", SymbolKind::Object),
+                ("top", SymbolKind::Function),
+                ("answer", SymbolKind::Variable),
+            ]
+        );
+        let registry: Vec<_> = symbols[0].children.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(registry, vec![("MAX", SymbolKind::Constant), ("items", SymbolKind::Property)]);
+    }
+}
@@ -8,10 +8,50 @@ pub mod cpp;
 pub mod java;
 pub mod javascript;
 pub mod typescript;
+pub mod kotlin;
+pub mod swift;
+pub mod csharp;
+pub mod bash;
+pub mod html;
+pub mod css;
+pub mod json;
+pub mod yaml;
+pub mod toml;
+pub mod markdown;
+pub mod sql;
+pub mod elixir;
+pub mod dart;
+pub mod dockerfile;
+pub mod protobuf;
+pub mod brackets;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod compat;
+pub mod conditional;
+pub mod detect;
+pub mod doc_comment;
+pub mod dump;
+pub mod folding;
+pub mod injection;
+pub mod suppression;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod query;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod highlight;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod pool;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod grammar;
+pub mod lite;
+pub mod utf16;
 
-use logos_core::{Diagnostic, Position, Range};
+use logos_core::{Diagnostic, Document, Position, Range, Symbol, TextChange};
+
+pub use injection::{injections, offset_diagnostics, offset_range, offset_symbols, Injection};
+pub use utf16::Utf16Columns;
+#[cfg(not(target_arch = "wasm32"))]
+pub use injection::injected_parse_errors;
 use thiserror::Error;
-use tree_sitter::{Parser, Tree, Node, Language};
+use tree_sitter::{InputEdit, Parser, Point, Tree, Node, Language};
 
 /// Parser errors
 #[derive(Debug, Error)]
@@ -22,6 +62,8 @@ pub enum ParseError {
     ParseFailed,
     #[error("Unsupported language: {0}")]
     UnsupportedLanguage(String),
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
 }
 
 /// Supported programming languages
@@ -34,11 +76,109 @@ pub enum LanguageId {
     Cpp,
     Java,
     JavaScript,
+    /// JavaScript with JSX (`.jsx`)
+    JavaScriptReact,
     TypeScript,
+    /// TypeScript with JSX (`.tsx`), where `<T>expr` is an element rather
+    /// than a type assertion
+    TypeScriptReact,
+    Kotlin,
+    Swift,
+    CSharp,
+    Bash,
+    Html,
+    Css,
+    Scss,
+    Json,
+    Yaml,
+    Toml,
+    Markdown,
+    Sql,
+    Elixir,
+    Dart,
+    /// Parsed with the shell grammar; stages and instructions come from
+    /// [`dockerfile`]
+    Dockerfile,
+    /// Parsed with the C grammar, which reads its comments; messages and
+    /// services come from [`protobuf`]
+    Protobuf,
+    /// A grammar loaded at runtime, by its configured id (see
+    /// [`grammar::GrammarRegistry`])
+    Custom(&'static str),
 }
 
 impl LanguageId {
+    /// Tree-sitter grammar of the language
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn grammar(self) -> Result<Language, ParseError> {
+        let language = match self {
+            LanguageId::Python => tree_sitter_python::LANGUAGE.into(),
+            LanguageId::Go => tree_sitter_go::LANGUAGE.into(),
+            LanguageId::Rust => tree_sitter_rust::LANGUAGE.into(),
+            // A `.proto` file's comments read as C
+            LanguageId::C | LanguageId::Protobuf => tree_sitter_c::LANGUAGE.into(),
+            LanguageId::Cpp => tree_sitter_cpp::LANGUAGE.into(),
+            LanguageId::Java => tree_sitter_java::LANGUAGE.into(),
+            // The JavaScript grammar parses JSX
+            LanguageId::JavaScript | LanguageId::JavaScriptReact => tree_sitter_javascript::LANGUAGE.into(),
+            LanguageId::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            LanguageId::TypeScriptReact => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            LanguageId::Kotlin => tree_sitter_kotlin_ng::LANGUAGE.into(),
+            LanguageId::Swift => tree_sitter_swift::LANGUAGE.into(),
+            LanguageId::CSharp => tree_sitter_c_sharp::LANGUAGE.into(),
+            // A Dockerfile's comments and commands read as shell
+            LanguageId::Bash | LanguageId::Dockerfile => tree_sitter_bash::LANGUAGE.into(),
+            LanguageId::Html => tree_sitter_html::LANGUAGE.into(),
+            LanguageId::Css => tree_sitter_css::LANGUAGE.into(),
+            LanguageId::Scss => tree_sitter_scss::language(),
+            LanguageId::Json => tree_sitter_json::LANGUAGE.into(),
+            LanguageId::Yaml => tree_sitter_yaml::LANGUAGE.into(),
+            LanguageId::Toml => tree_sitter_toml_ng::LANGUAGE.into(),
+            LanguageId::Markdown => tree_sitter_md::LANGUAGE.into(),
+            LanguageId::Sql => tree_sitter_sequel::LANGUAGE.into(),
+            LanguageId::Elixir => tree_sitter_elixir::LANGUAGE.into(),
+            LanguageId::Dart => tree_sitter_dart::language(),
+            LanguageId::Custom(id) => {
+                return grammar::registry().language(id).ok_or_else(|| ParseError::UnsupportedLanguage(id.to_string()));
+            }
+        };
+        Ok(language)
+    }
+
+    /// Language for a language id or alias, including loaded grammars
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Option<Self> {
+        Self::builtin_from_str(s).or_else(|| Self::loaded_by_id(s))
+    }
+
+    /// Language of files with extension `ext`, including loaded grammars
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        Self::builtin_from_extension(ext).or_else(|| Self::loaded_by_extension(ext))
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn loaded_by_id(id: &str) -> Option<Self> {
+        grammar::registry().by_id(id)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn loaded_by_extension(ext: &str) -> Option<Self> {
+        grammar::registry().by_extension(ext)
+    }
+
+    /// Grammars can't be loaded from shared libraries in WASM
+    #[cfg(target_arch = "wasm32")]
+    fn loaded_by_id(_id: &str) -> Option<Self> {
+        None
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn loaded_by_extension(_ext: &str) -> Option<Self> {
+        None
+    }
+
+    /// Built-in language for a language id or alias
+    pub fn builtin_from_str(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
             "python" | "py" => Some(Self::Python),
             "go" | "golang" => Some(Self::Go),
@@ -47,12 +187,30 @@ impl LanguageId {
             "cpp" | "c++" | "cxx" => Some(Self::Cpp),
             "java" => Some(Self::Java),
             "javascript" | "js" => Some(Self::JavaScript),
+            "javascriptreact" | "jsx" => Some(Self::JavaScriptReact),
             "typescript" | "ts" => Some(Self::TypeScript),
+            "typescriptreact" | "tsx" => Some(Self::TypeScriptReact),
+            "kotlin" | "kt" => Some(Self::Kotlin),
+            "swift" => Some(Self::Swift),
+            "csharp" | "c#" | "cs" => Some(Self::CSharp),
+            "shellscript" | "bash" | "sh" => Some(Self::Bash),
+            "html" | "htm" => Some(Self::Html),
+            "css" => Some(Self::Css),
+            "scss" => Some(Self::Scss),
+            "json" | "jsonc" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            "markdown" | "md" => Some(Self::Markdown),
+            "sql" => Some(Self::Sql),
+            "elixir" | "ex" => Some(Self::Elixir),
+            "dart" => Some(Self::Dart),
+            "dockerfile" | "containerfile" => Some(Self::Dockerfile),
+            "proto" | "proto3" | "protobuf" => Some(Self::Protobuf),
             _ => None,
         }
     }
 
-    pub fn from_extension(ext: &str) -> Option<Self> {
+    fn builtin_from_extension(ext: &str) -> Option<Self> {
         match ext.to_lowercase().as_str() {
             "py" => Some(Self::Python),
             "go" => Some(Self::Go),
@@ -61,7 +219,25 @@ impl LanguageId {
             "cpp" | "cxx" | "cc" | "hpp" | "hxx" => Some(Self::Cpp),
             "java" => Some(Self::Java),
             "js" | "mjs" | "cjs" => Some(Self::JavaScript),
+            "jsx" => Some(Self::JavaScriptReact),
             "ts" | "mts" | "cts" => Some(Self::TypeScript),
+            "tsx" => Some(Self::TypeScriptReact),
+            "kt" | "kts" => Some(Self::Kotlin),
+            "swift" => Some(Self::Swift),
+            "cs" | "csx" => Some(Self::CSharp),
+            "sh" | "bash" => Some(Self::Bash),
+            "html" | "htm" | "xhtml" => Some(Self::Html),
+            "css" => Some(Self::Css),
+            "scss" => Some(Self::Scss),
+            "json" | "jsonc" => Some(Self::Json),
+            "yaml" | "yml" => Some(Self::Yaml),
+            "toml" => Some(Self::Toml),
+            "md" | "markdown" => Some(Self::Markdown),
+            "sql" => Some(Self::Sql),
+            "ex" | "exs" => Some(Self::Elixir),
+            "dart" => Some(Self::Dart),
+            "dockerfile" | "containerfile" => Some(Self::Dockerfile),
+            "proto" => Some(Self::Protobuf),
             _ => None,
         }
     }
@@ -75,9 +251,44 @@ impl LanguageId {
             Self::Cpp => "cpp",
             Self::Java => "java",
             Self::JavaScript => "javascript",
+            Self::JavaScriptReact => "javascriptreact",
             Self::TypeScript => "typescript",
+            Self::TypeScriptReact => "typescriptreact",
+            Self::Kotlin => "kotlin",
+            Self::Swift => "swift",
+            Self::CSharp => "csharp",
+            Self::Bash => "shellscript",
+            Self::Html => "html",
+            Self::Css => "css",
+            Self::Scss => "scss",
+            Self::Json => "json",
+            Self::Yaml => "yaml",
+            Self::Toml => "toml",
+            Self::Markdown => "markdown",
+            Self::Sql => "sql",
+            Self::Elixir => "elixir",
+            Self::Dart => "dart",
+            Self::Dockerfile => "dockerfile",
+            Self::Protobuf => "proto",
+            Self::Custom(id) => id,
         }
     }
+
+    /// Language a dialect belongs to: JavaScript for JSX, TypeScript for
+    /// TSX, and the language itself otherwise
+    pub fn base(self) -> Self {
+        match self {
+            Self::JavaScriptReact => Self::JavaScript,
+            Self::TypeScriptReact => Self::TypeScript,
+            other => other,
+        }
+    }
+
+    /// Markup, style, data and query languages: nothing to extract into
+    /// variables or functions
+    pub fn is_markup(self) -> bool {
+        matches!(self, Self::Html | Self::Css | Self::Scss | Self::Json | Self::Yaml | Self::Toml | Self::Markdown | Self::Sql | Self::Dockerfile | Self::Protobuf)
+    }
 }
 
 /// Multi-language parser wrapper
@@ -97,19 +308,9 @@ impl LanguageParser {
     /// Set the language for parsing
     #[cfg(not(target_arch = "wasm32"))]
     pub fn set_language(&mut self, lang: LanguageId) -> Result<(), ParseError> {
-        let language = match lang {
-            LanguageId::Python => tree_sitter_python::LANGUAGE.into(),
-            LanguageId::Go => tree_sitter_go::LANGUAGE.into(),
-            LanguageId::Rust => tree_sitter_rust::LANGUAGE.into(),
-            LanguageId::C => tree_sitter_c::LANGUAGE.into(),
-            LanguageId::Cpp => tree_sitter_cpp::LANGUAGE.into(),
-            LanguageId::Java => tree_sitter_java::LANGUAGE.into(),
-            LanguageId::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
-            LanguageId::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
-        };
-
+        compat::check(lang)?;
         self.parser
-            .set_language(&language)
+            .set_language(&lang.grammar()?)
             .map_err(|e| ParseError::LanguageError(e.to_string()))?;
         self.current_language = Some(lang);
         Ok(())
@@ -140,6 +341,24 @@ impl LanguageParser {
             .ok_or(ParseError::ParseFailed)
     }
 
+    /// Apply `changes` to `doc` and reparse it, reusing the unchanged parts
+    /// of `old_tree`, the tree of the document before the changes
+    pub fn parse_incremental(
+        &mut self,
+        doc: &mut Document,
+        old_tree: Option<Tree>,
+        changes: &[TextChange],
+    ) -> Result<Tree, ParseError> {
+        let mut tree = old_tree;
+        apply_changes(doc, &mut tree, changes);
+        self.parse(doc.content(), tree.as_ref())
+    }
+
+    /// Discard the state of an unfinished parse
+    pub fn reset(&mut self) {
+        self.parser.reset();
+    }
+
     /// Get current language
     pub fn current_language(&self) -> Option<LanguageId> {
         self.current_language
@@ -152,29 +371,56 @@ impl Default for LanguageParser {
     }
 }
 
-/// Extract diagnostics from parse errors in the tree
+/// Language-specific message and range for an error or missing node
+type DescribeError = fn(&tree_sitter::Node, &str) -> Option<(Range, String)>;
+
+/// Extract diagnostics from parse errors in the tree. JSON, YAML and TOML
+/// errors get specific messages (trailing commas, unquoted keys, bad
+/// indentation, ...).
 pub fn extract_parse_errors(tree: &Tree, source: &str) -> Vec<Diagnostic> {
-    let mut diagnostics = Vec::new();
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
     let mut cursor = tree.walk();
 
-    extract_errors_recursive(&mut cursor, source, &mut diagnostics);
-    diagnostics
+    let language = tree.language();
+    let describe: Option<DescribeError> = if *language == tree_sitter_json::LANGUAGE.into() {
+        Some(json::describe_error)
+    } else if *language == tree_sitter_yaml::LANGUAGE.into() {
+        Some(yaml::describe_error)
+    } else if *language == tree_sitter_toml_ng::LANGUAGE.into() {
+        Some(toml::describe_error)
+    } else {
+        None
+    };
+    extract_errors_recursive(&mut cursor, source, describe, &mut diagnostics);
+    // Separate error nodes can describe the same token
+    let mut unique: Vec<Diagnostic> = Vec::with_capacity(diagnostics.len());
+    for diagnostic in diagnostics {
+        if !unique.iter().any(|d| d.range == diagnostic.range && d.message == diagnostic.message) {
+            unique.push(diagnostic);
+        }
+    }
+    Utf16Columns::new(source).convert_diagnostics(&mut unique);
+    unique
 }
 
 fn extract_errors_recursive(
     cursor: &mut tree_sitter::TreeCursor,
     source: &str,
+    describe: Option<DescribeError>,
     diagnostics: &mut Vec<Diagnostic>,
 ) {
     let node = cursor.node();
 
     if node.is_error() || node.is_missing() {
-        let range = node_to_range(&node);
-        let message = if node.is_missing() {
-            format!("Missing {}", node.kind())
-        } else {
-            "Syntax error".to_string()
-        };
+        let described = describe.and_then(|describe| describe(&node, source));
+        let (range, message) = described.unwrap_or_else(|| {
+            let message = if node.is_missing() {
+                format!("Missing {}", node.kind())
+            } else {
+                "Syntax error".to_string()
+            };
+            (node_to_range(&node), message)
+        });
         diagnostics.push(
             Diagnostic::error(range, message).with_source("logos-parser".to_string()),
         );
@@ -182,7 +428,7 @@ fn extract_errors_recursive(
 
     if cursor.goto_first_child() {
         loop {
-            extract_errors_recursive(cursor, source, diagnostics);
+            extract_errors_recursive(cursor, source, describe, diagnostics);
             if !cursor.goto_next_sibling() {
                 break;
             }
@@ -191,7 +437,178 @@ fn extract_errors_recursive(
     }
 }
 
-/// Convert tree-sitter node range to logos Range
+/// Reserved words for the given language
+pub fn keywords(lang: LanguageId) -> &'static [&'static str] {
+    match lang {
+        LanguageId::Python => python::get_keywords(),
+        LanguageId::Go => go::get_keywords(),
+        LanguageId::Rust => rust_lang::get_keywords(),
+        LanguageId::C => c::get_keywords(),
+        LanguageId::Cpp => cpp::get_keywords(),
+        LanguageId::Java => java::get_keywords(),
+        LanguageId::JavaScript | LanguageId::JavaScriptReact => javascript::get_keywords(),
+        LanguageId::TypeScript | LanguageId::TypeScriptReact => typescript::get_keywords(),
+        LanguageId::Kotlin => kotlin::get_keywords(),
+        LanguageId::Swift => swift::get_keywords(),
+        LanguageId::CSharp => csharp::get_keywords(),
+        LanguageId::Bash => bash::get_keywords(),
+        LanguageId::Html => html::get_keywords(),
+        LanguageId::Css => css::get_keywords(),
+        LanguageId::Scss => css::get_scss_keywords(),
+        LanguageId::Json => json::get_keywords(),
+        LanguageId::Yaml => yaml::get_keywords(),
+        LanguageId::Toml => toml::get_keywords(),
+        LanguageId::Markdown => markdown::get_keywords(),
+        LanguageId::Sql => sql::get_keywords(),
+        LanguageId::Elixir => elixir::get_keywords(),
+        LanguageId::Dart => dart::get_keywords(),
+        LanguageId::Dockerfile => dockerfile::get_keywords(),
+        LanguageId::Protobuf => protobuf::get_keywords(),
+        LanguageId::Custom(_) => &[],
+    }
+}
+
+/// Extract document symbols with the extractor for the given language,
+/// with their doc comments. Extractors work in tree-sitter points; the
+/// ranges they return are converted to UTF-16 columns here.
+pub fn extract_symbols(lang: LanguageId, tree: &Tree, source: &str) -> Vec<Symbol> {
+    let mut symbols = match lang {
+        LanguageId::Python => python::extract_symbols(tree, source),
+        LanguageId::Go => go::extract_symbols(tree, source),
+        LanguageId::Rust => rust_lang::extract_symbols(tree, source),
+        LanguageId::C => c::extract_symbols(tree, source),
+        LanguageId::Cpp => cpp::extract_symbols(tree, source),
+        LanguageId::Java => java::extract_symbols(tree, source),
+        LanguageId::JavaScript | LanguageId::JavaScriptReact => javascript::extract_symbols(tree, source),
+        LanguageId::TypeScript | LanguageId::TypeScriptReact => typescript::extract_symbols(tree, source),
+        LanguageId::Kotlin => kotlin::extract_symbols(tree, source),
+        LanguageId::Swift => swift::extract_symbols(tree, source),
+        LanguageId::CSharp => csharp::extract_symbols(tree, source),
+        LanguageId::Bash => bash::extract_symbols(tree, source),
+        LanguageId::Html => html::extract_symbols(tree, source),
+        LanguageId::Css | LanguageId::Scss => css::extract_symbols(tree, source),
+        LanguageId::Json => json::extract_symbols(tree, source),
+        LanguageId::Yaml => yaml::extract_symbols(tree, source),
+        LanguageId::Toml => toml::extract_symbols(tree, source),
+        LanguageId::Markdown => markdown::extract_symbols(tree, source),
+        LanguageId::Sql => sql::extract_symbols(tree, source),
+        LanguageId::Elixir => elixir::extract_symbols(tree, source),
+        LanguageId::Dart => dart::extract_symbols(tree, source),
+        LanguageId::Dockerfile => dockerfile::extract_symbols(source),
+        LanguageId::Protobuf => protobuf::extract_symbols(source),
+        // Loaded grammars come without a symbol extractor
+        LanguageId::Custom(_) => Vec::new(),
+    };
+    doc_comment::attach_documentation(lang, tree, source, &mut symbols);
+    Utf16Columns::new(source).convert_symbols(&mut symbols);
+    symbols
+}
+
+/// Apply `changes` to `doc` in order, keeping `tree` in step with
+/// `Tree::edit` so that the next parse can pass it as the old tree. A
+/// full-content change leaves nothing to reuse and drops the tree.
+pub fn apply_changes(doc: &mut Document, tree: &mut Option<Tree>, changes: &[TextChange]) {
+    for change in changes {
+        match (change.range, tree.as_mut()) {
+            (Some(range), Some(old)) => old.edit(&input_edit(doc, range, &change.text)),
+            (None, _) => *tree = None,
+            _ => {}
+        }
+        doc.apply(change);
+    }
+}
+
+/// The edit replacing `range` of `doc` (not yet changed) by `text`. Tree
+/// positions count bytes where document positions count UTF-16 units.
+fn input_edit(doc: &Document, range: Range, text: &str) -> InputEdit {
+    let start_byte = doc.offset_at(range.start).unwrap_or(0);
+    let old_end_byte = doc.offset_at(range.end).unwrap_or(doc.content().len()).max(start_byte);
+    let point_at = |offset: usize| {
+        let line = doc.position_at(offset).line;
+        let line_start = doc.offset_at(Position::new(line, 0)).unwrap_or(0);
+        Point::new(line as usize, offset - line_start)
+    };
+    let start_position = point_at(start_byte);
+    let new_end_position = match text.rfind('\n') {
+        Some(last) => Point::new(start_position.row + text.matches('\n').count(), text.len() - last - 1),
+        None => Point::new(start_position.row, start_position.column + text.len()),
+    };
+    InputEdit {
+        start_byte,
+        old_end_byte,
+        new_end_byte: start_byte + text.len(),
+        start_position,
+        old_end_position: point_at(old_end_byte),
+        new_end_position,
+    }
+}
+
+/// Range of the innermost identifier at `position` of `source`, the text
+/// `tree` was parsed from.
+///
+/// A cursor just past the last character of an identifier (`foo|(`) still
+/// resolves to that identifier.
+pub fn identifier_range_at(tree: &Tree, source: &str, position: Position) -> Option<Range> {
+    let columns = Utf16Columns::new(source);
+    let at = columns.point(position);
+    let before = position.column.checked_sub(1).map(|column| columns.point(Position::new(position.line, column)));
+
+    [Some(at), before].into_iter().flatten().find_map(|point| {
+        let node = tree.root_node().descendant_for_point_range(point, point)?;
+        is_identifier(&node).then(|| Range::new(columns.position(node.start_position()), columns.position(node.end_position())))
+    })
+}
+
+fn is_identifier(node: &Node) -> bool {
+    node.is_named() && node.child_count() == 0 && node.kind().ends_with("identifier")
+}
+
+/// Whether `node` is a string literal, template or raw string of any of
+/// the grammars (its first and last children are the delimiters)
+pub fn is_string_literal(node: &Node) -> bool {
+    matches!(
+        node.kind(),
+        "string" | "template_string" | "string_literal" | "raw_string_literal" | "interpreted_string_literal"
+    )
+}
+
+/// Declaration of a function up to its body, on one line, without leading
+/// annotations: `fn parse(source: &str) -> Tree`,
+/// `func (s *Server) Run(ctx context.Context) error`
+pub fn signature(node: &Node, source: &str) -> String {
+    let end = node.child_by_field_name("body").map_or(node.end_byte(), |body| body.start_byte());
+    signature_between(source, node.start_byte(), end)
+}
+
+/// Text of `source[start..end]` as a signature: whitespace collapsed,
+/// annotations and decorators dropped from the front, and the `{`, `:`,
+/// `=>` or `;` that opens or ends the body dropped from the back
+pub fn signature_between(source: &str, start: usize, end: usize) -> String {
+    let text = source.get(start..end).unwrap_or_default().split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut text = text.as_str();
+    while let Some(rest) = text.strip_prefix('@') {
+        let name_end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '.')).unwrap_or(rest.len());
+        let mut rest = &rest[name_end..];
+        if rest.starts_with('(') {
+            let mut depth = 0;
+            let close = rest.char_indices().find(|&(_, c)| {
+                match c {
+                    '(' => depth += 1,
+                    ')' => depth -= 1,
+                    _ => {}
+                }
+                depth == 0
+            });
+            rest = close.map_or("", |(i, _)| &rest[i + 1..]);
+        }
+        text = rest.trim_start();
+    }
+    let text = text.trim_end().trim_end_matches(['{', ':', ';']).trim_end();
+    text.strip_suffix("=>").unwrap_or(text).trim_end().to_string()
+}
+
+/// Convert tree-sitter node range to logos Range. Columns stay in bytes,
+/// as tree-sitter counts them; [`Utf16Columns`] converts them.
 pub fn node_to_range(node: &Node) -> Range {
     let start = node.start_position();
     let end = node.end_position();
@@ -225,6 +642,84 @@ mod tests {
         assert_eq!(LanguageId::from_extension("py"), Some(LanguageId::Python));
         assert_eq!(LanguageId::from_extension("rs"), Some(LanguageId::Rust));
         assert_eq!(LanguageId::from_extension("ts"), Some(LanguageId::TypeScript));
+        assert_eq!(LanguageId::from_extension("tsx"), Some(LanguageId::TypeScriptReact));
+        assert_eq!(LanguageId::from_extension("jsx"), Some(LanguageId::JavaScriptReact));
+        assert_eq!(LanguageId::from_extension("kts"), Some(LanguageId::Kotlin));
+        assert_eq!(LanguageId::from_extension("swift"), Some(LanguageId::Swift));
+        assert_eq!(LanguageId::from_extension("cs"), Some(LanguageId::CSharp));
+        assert_eq!(LanguageId::from_extension("sh"), Some(LanguageId::Bash));
+        assert_eq!(LanguageId::from_extension("htm"), Some(LanguageId::Html));
+        assert_eq!(LanguageId::from_extension("css"), Some(LanguageId::Css));
+        assert_eq!(LanguageId::from_extension("scss"), Some(LanguageId::Scss));
+        assert_eq!(LanguageId::from_extension("jsonc"), Some(LanguageId::Json));
+        assert_eq!(LanguageId::from_extension("yml"), Some(LanguageId::Yaml));
+        assert_eq!(LanguageId::from_extension("toml"), Some(LanguageId::Toml));
+        assert_eq!(LanguageId::from_extension("md"), Some(LanguageId::Markdown));
+        assert_eq!(LanguageId::from_extension("sql"), Some(LanguageId::Sql));
+        assert_eq!(LanguageId::from_extension("exs"), Some(LanguageId::Elixir));
+        assert_eq!(LanguageId::from_extension("dart"), Some(LanguageId::Dart));
+        assert_eq!(LanguageId::from_extension("proto"), Some(LanguageId::Protobuf));
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_tsx_dialect() {
+        let element = "const el = <Button onClick={() => go()}>Go</Button>;";
+        let assertion = "const n = <number>value;";
+        let has_error = |lang, source| {
+            let mut parser = LanguageParser::new();
+            parser.set_language(lang).unwrap();
+            parser.parse(source, None).unwrap().root_node().has_error()
+        };
+        assert!(!has_error(LanguageId::TypeScriptReact, element));
+        assert!(has_error(LanguageId::TypeScript, element));
+        assert!(!has_error(LanguageId::TypeScript, assertion));
+        assert!(!has_error(LanguageId::JavaScriptReact, element));
+        assert_eq!(LanguageId::TypeScriptReact.base(), LanguageId::TypeScript);
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_utf16_ranges() {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::JavaScript).unwrap();
+        // The crab is a surrogate pair; each CJK character is one unit
+        let source = "const s = \"🦀\"; function 名前(値) { return 値; }\n";
+        let tree = parser.parse(source, None).unwrap();
+
+        let symbols = extract_symbols(LanguageId::JavaScript, &tree, source);
+        let function = symbols.iter().find(|s| s.name == "名前").unwrap();
+        assert_eq!(function.selection_range, Range::from_coords(0, 25, 0, 27));
+        assert_eq!(function.range.end, Position::new(0, 44));
+
+        assert_eq!(identifier_range_at(&tree, source, Position::new(0, 41)), Some(Range::from_coords(0, 40, 0, 41)));
+        assert_eq!(identifier_range_at(&tree, source, Position::new(0, 26)), Some(Range::from_coords(0, 25, 0, 27)));
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_signature_details() {
+        let detail = |lang, source: &str| {
+            let mut parser = LanguageParser::new();
+            parser.set_language(lang).unwrap();
+            let tree = parser.parse(source, None).unwrap();
+            extract_symbols(lang, &tree, source)[0].detail.clone().unwrap_or_default()
+        };
+        assert_eq!(
+            detail(LanguageId::Rust, "pub fn parse(source: &str)\n    -> Tree {\n    todo!()\n}\n"),
+            "pub fn parse(source: &str) -> Tree"
+        );
+        assert_eq!(
+            detail(LanguageId::Go, "package main\n\nfunc (s *Server) Run(ctx context.Context) error {\n\treturn nil\n}\n"),
+            "func (s *Server) Run(ctx context.Context) error"
+        );
+        assert_eq!(
+            detail(LanguageId::TypeScript, "const add = (a: number, b: number): number => a + b;\n"),
+            "const add = (a: number, b: number): number"
+        );
+        assert_eq!(detail(LanguageId::Cpp, "int add(int a, int b) { return a + b; }\n"), "int add(int a, int b)");
+        let java = "@Override @SuppressWarnings(\"x\")\n    public void run() {";
+        assert_eq!(signature_between(java, 0, java.len()), "public void run()");
     }
 
     #[test]
@@ -236,4 +731,59 @@ mod tests {
         let tree = parser.parse("def hello(): pass", None).unwrap();
         assert!(!tree.root_node().has_error());
     }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_parse_incremental_matches_full_parse() {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Python).unwrap();
+        let mut doc = Document::new("a.py".into(), "python".into(), "s = 'héllo'\ndef f():\n    return 1\n".into());
+        let tree = parser.parse(doc.content(), None).unwrap();
+
+        // Columns are UTF-16; the edit after `é` must land on the right byte
+        let changes = [
+            TextChange { range: Some(Range::from_coords(0, 10, 0, 10)), text: "!".into() },
+            TextChange { range: Some(Range::from_coords(2, 11, 2, 12)), text: "2\n\ndef g(x):\n    return x".into() },
+            TextChange { range: Some(Range::from_coords(1, 4, 1, 5)), text: "main".into() },
+        ];
+        let tree = parser.parse_incremental(&mut doc, Some(tree), &changes).unwrap();
+
+        assert_eq!(doc.content(), "s = 'héllo!'\ndef main():\n    return 2\n\ndef g(x):\n    return x\n");
+        let fresh = parser.parse(doc.content(), None).unwrap();
+        assert_eq!(tree.root_node().to_sexp(), fresh.root_node().to_sexp());
+        assert_eq!(tree.root_node().end_byte(), doc.content().len());
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_full_change_drops_old_tree() {
+        let mut doc = Document::new("a.py".into(), "python".into(), "x = 1\n".into());
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Python).unwrap();
+        let mut tree = Some(parser.parse(doc.content(), None).unwrap());
+
+        apply_changes(&mut doc, &mut tree, &[TextChange { range: None, text: "def f(): pass\n".into() }]);
+        assert!(tree.is_none());
+        assert_eq!(doc.content(), "def f(): pass\n");
+    }
+
+    #[test]
+    #[cfg(not(target_arch = "wasm32"))]
+    fn test_identifier_range_at() {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Python).unwrap();
+
+        let source = "class Greeter:\n    def greet(self, name):\n        return name.upper()\n";
+        let tree = parser.parse(source, None).unwrap();
+
+        // Inside a declaration name: just the name, not the whole def
+        assert_eq!(identifier_range_at(&tree, source, Position::new(1, 10)), Some(Range::from_coords(1, 8, 1, 13)));
+        // Attribute access picks the innermost identifier
+        assert_eq!(identifier_range_at(&tree, source, Position::new(2, 22)), Some(Range::from_coords(2, 20, 2, 25)));
+        // Just past the end of an identifier
+        assert_eq!(identifier_range_at(&tree, source, Position::new(2, 19)), Some(Range::from_coords(2, 15, 2, 19)));
+        // Keywords and whitespace have no identifier
+        assert_eq!(identifier_range_at(&tree, source, Position::new(1, 1)), None);
+        assert_eq!(identifier_range_at(&tree, source, Position::new(0, 2)), None);
+    }
 }
@@ -0,0 +1,409 @@
+//! Lightweight tokenizer for files too large to parse
+//!
+//! Enormous generated files (bundles, amalgamations, lookup tables) are not
+//! worth a full tree-sitter parse. This module scans them line by line with a
+//! keyword/regex approach to provide basic highlighting tokens and a flat list
+//! of guessed top-level symbols.
+
+use crate::LanguageId;
+use logos_core::{Position, Range, Symbol, SymbolKind};
+use regex::Regex;
+
+/// Files larger than this (in bytes) skip tree-sitter and use this module
+pub const MAX_PARSE_BYTES: usize = 2 * 1024 * 1024;
+
+/// Whether a source is above the parse-size limit
+pub fn exceeds_parse_limit(source: &str) -> bool {
+    source.len() > MAX_PARSE_BYTES
+}
+
+/// Token categories produced by the lightweight tokenizer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LiteTokenKind {
+    Keyword,
+    String,
+    Comment,
+    Number,
+    Function,
+}
+
+impl LiteTokenKind {
+    /// Token type names in legend order (LSP semantic token types)
+    pub const LEGEND: [&'static str; 5] = ["keyword", "string", "comment", "number", "function"];
+
+    /// Index into [`LiteTokenKind::LEGEND`]
+    pub fn legend_index(&self) -> u32 {
+        match self {
+            LiteTokenKind::Keyword => 0,
+            LiteTokenKind::String => 1,
+            LiteTokenKind::Comment => 2,
+            LiteTokenKind::Number => 3,
+            LiteTokenKind::Function => 4,
+        }
+    }
+}
+
+/// A single-line token; `start` and `length` are in UTF-16 code units
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiteToken {
+    pub line: u32,
+    pub start: u32,
+    pub length: u32,
+    pub kind: LiteTokenKind,
+}
+
+struct CommentSyntax {
+    line: &'static str,
+    block: Option<(&'static str, &'static str)>,
+}
+
+fn comment_syntax(lang: LanguageId) -> CommentSyntax {
+    match lang {
+        LanguageId::Python
+        | LanguageId::Elixir
+        | LanguageId::Bash
+        | LanguageId::Dockerfile
+        | LanguageId::Yaml
+        | LanguageId::Toml => CommentSyntax { line: "#", block: None },
+        LanguageId::Sql => CommentSyntax { line: "--", block: Some(("/*", "*/")) },
+        _ => CommentSyntax { line: "//", block: Some(("/*", "*/")) },
+    }
+}
+
+fn utf16_len(s: &str) -> u32 {
+    s.chars().map(|c| c.len_utf16() as u32).sum()
+}
+
+/// Tokenize a source into keyword/string/comment/number/function tokens
+pub fn tokenize(lang: LanguageId, source: &str) -> Vec<LiteToken> {
+    let keywords = crate::keywords(lang);
+    let syntax = comment_syntax(lang);
+    let mut tokens = Vec::new();
+    let mut in_block_comment = false;
+
+    for (line_no, line) in source.lines().enumerate() {
+        let line_no = line_no as u32;
+        let mut push = |start: usize, end: usize, kind: LiteTokenKind| {
+            if end > start {
+                tokens.push(LiteToken {
+                    line: line_no,
+                    start: utf16_len(&line[..start]),
+                    length: utf16_len(&line[start..end]),
+                    kind,
+                });
+            }
+        };
+
+        let bytes = line.as_bytes();
+        let mut i = 0;
+
+        if in_block_comment {
+            let (_, close) = syntax.block.expect("block comment state without block syntax");
+            match line.find(close) {
+                Some(end) => {
+                    push(0, end + close.len(), LiteTokenKind::Comment);
+                    i = end + close.len();
+                    in_block_comment = false;
+                }
+                None => {
+                    push(0, line.len(), LiteTokenKind::Comment);
+                    continue;
+                }
+            }
+        }
+
+        while i < bytes.len() {
+            let rest = &line[i..];
+            let c = bytes[i];
+
+            if rest.starts_with(syntax.line) {
+                push(i, line.len(), LiteTokenKind::Comment);
+                break;
+            }
+            if let Some((open, close)) = syntax.block {
+                if let Some(body) = rest.strip_prefix(open) {
+                    match body.find(close) {
+                        Some(end) => {
+                            let end = i + open.len() + end + close.len();
+                            push(i, end, LiteTokenKind::Comment);
+                            i = end;
+                            continue;
+                        }
+                        None => {
+                            push(i, line.len(), LiteTokenKind::Comment);
+                            in_block_comment = true;
+                            break;
+                        }
+                    }
+                }
+            }
+
+            if c == b'"' || c == b'\'' || c == b'`' {
+                let mut j = i + 1;
+                while j < bytes.len() && bytes[j] != c {
+                    j += if bytes[j] == b'\\' { 2 } else { 1 };
+                }
+                let end = (j + 1).min(bytes.len());
+                push(i, end, LiteTokenKind::String);
+                i = end;
+                continue;
+            }
+
+            if c.is_ascii_digit() {
+                let mut j = i + 1;
+                while j < bytes.len() && (bytes[j].is_ascii_alphanumeric() || bytes[j] == b'.' || bytes[j] == b'_') {
+                    j += 1;
+                }
+                push(i, j, LiteTokenKind::Number);
+                i = j;
+                continue;
+            }
+
+            if c.is_ascii_alphabetic() || c == b'_' || c >= 0x80 {
+                let mut j = i;
+                for (offset, ch) in rest.char_indices() {
+                    if !(ch.is_alphanumeric() || ch == '_') {
+                        break;
+                    }
+                    j = i + offset + ch.len_utf8();
+                }
+                if j == i {
+                    // Non-identifier multibyte char
+                    i += rest.chars().next().map_or(1, char::len_utf8);
+                    continue;
+                }
+                let word = &line[i..j];
+                // SQL keywords are case-insensitive
+                let is_keyword = if lang == LanguageId::Sql {
+                    keywords.iter().any(|k| k.eq_ignore_ascii_case(word))
+                } else {
+                    keywords.contains(&word)
+                };
+                if is_keyword {
+                    push(i, j, LiteTokenKind::Keyword);
+                } else if line[j..].trim_start().starts_with('(') {
+                    push(i, j, LiteTokenKind::Function);
+                }
+                i = j;
+                continue;
+            }
+
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+fn definition_patterns(lang: LanguageId) -> Vec<(&'static str, SymbolKind)> {
+    match lang {
+        LanguageId::Python => vec![
+            (r"^\s*(?:async\s+)?def\s+(\w+)", SymbolKind::Function),
+            (r"^\s*class\s+(\w+)", SymbolKind::Class),
+        ],
+        LanguageId::Go => vec![
+            (r"^func\s+(?:\([^)]*\)\s*)?(\w+)", SymbolKind::Function),
+            (r"^type\s+(\w+)\s+struct\b", SymbolKind::Struct),
+            (r"^type\s+(\w+)\s+interface\b", SymbolKind::Interface),
+        ],
+        LanguageId::Rust => vec![
+            (r"^\s*(?:pub(?:\([^)]*\))?\s+)?(?:const\s+)?(?:async\s+)?(?:unsafe\s+)?fn\s+(\w+)", SymbolKind::Function),
+            (r"^\s*(?:pub(?:\([^)]*\))?\s+)?struct\s+(\w+)", SymbolKind::Struct),
+            (r"^\s*(?:pub(?:\([^)]*\))?\s+)?enum\s+(\w+)", SymbolKind::Enum),
+            (r"^\s*(?:pub(?:\([^)]*\))?\s+)?trait\s+(\w+)", SymbolKind::Interface),
+        ],
+        LanguageId::JavaScript | LanguageId::JavaScriptReact | LanguageId::TypeScript | LanguageId::TypeScriptReact => vec![
+            (r"^\s*(?:export\s+)?(?:default\s+)?(?:async\s+)?function\s*\*?\s*(\w+)", SymbolKind::Function),
+            (r"^\s*(?:export\s+)?(?:default\s+)?(?:abstract\s+)?class\s+(\w+)", SymbolKind::Class),
+            (r"^\s*(?:export\s+)?interface\s+(\w+)", SymbolKind::Interface),
+        ],
+        LanguageId::Java => vec![
+            (r"^\s*(?:(?:public|protected|private|abstract|final|static)\s+)*class\s+(\w+)", SymbolKind::Class),
+            (r"^\s*(?:(?:public|protected|private)\s+)*interface\s+(\w+)", SymbolKind::Interface),
+            (r"^\s*(?:(?:public|protected|private)\s+)*enum\s+(\w+)", SymbolKind::Enum),
+        ],
+        LanguageId::Kotlin => vec![
+            (r"^\s*(?:(?:public|private|internal|protected|open|abstract|sealed|data|enum|inner)\s+)*class\s+(\w+)", SymbolKind::Class),
+            (r"^\s*(?:(?:public|private|internal|sealed|fun)\s+)*interface\s+(\w+)", SymbolKind::Interface),
+            (r"^\s*(?:(?:public|private|internal)\s+)*object\s+(\w+)", SymbolKind::Object),
+            (r"^\s*(?:(?:public|private|internal|protected|open|override|suspend|inline)\s+)*fun\s+(?:<[^>]*>\s*)?(?:[\w.]+\.)?(\w+)", SymbolKind::Function),
+        ],
+        LanguageId::Swift => vec![
+            (r"^\s*(?:(?:public|private|fileprivate|internal|open|final)\s+)*(?:class|actor)\s+(\w+)", SymbolKind::Class),
+            (r"^\s*(?:(?:public|private|fileprivate|internal)\s+)*struct\s+(\w+)", SymbolKind::Struct),
+            (r"^\s*(?:(?:public|private|fileprivate|internal|indirect)\s+)*enum\s+(\w+)", SymbolKind::Enum),
+            (r"^\s*(?:(?:public|private|fileprivate|internal)\s+)*protocol\s+(\w+)", SymbolKind::Interface),
+            (r"^\s*(?:(?:public|private|fileprivate|internal|open|override|static|class|mutating)\s+)*func\s+(\w+)", SymbolKind::Function),
+        ],
+        LanguageId::CSharp => vec![
+            (r"^\s*namespace\s+([\w.]+)", SymbolKind::Namespace),
+            (r"^\s*(?:(?:public|private|protected|internal|static|abstract|sealed|partial)\s+)*(?:class|record)\s+(\w+)", SymbolKind::Class),
+            (r"^\s*(?:(?:public|private|protected|internal|readonly|partial)\s+)*struct\s+(\w+)", SymbolKind::Struct),
+            (r"^\s*(?:(?:public|private|protected|internal|partial)\s+)*interface\s+(\w+)", SymbolKind::Interface),
+            (r"^\s*(?:(?:public|private|protected|internal)\s+)*enum\s+(\w+)", SymbolKind::Enum),
+        ],
+        LanguageId::Bash => vec![
+            (r"^\s*function\s+([\w-]+)", SymbolKind::Function),
+            (r"^\s*([\w-]+)\s*\(\s*\)", SymbolKind::Function),
+            (r"^(?:export\s+)?([A-Za-z_]\w*)=", SymbolKind::Variable),
+            (r"^readonly\s+([A-Za-z_]\w*)=", SymbolKind::Constant),
+        ],
+        LanguageId::Dockerfile => vec![
+            (r"(?i)^FROM\s+(?:--\S+\s+)*\S+\s+AS\s+(\S+)", SymbolKind::Module),
+            (r"(?i)^(?:ARG|ENV)\s+([A-Za-z_]\w*)", SymbolKind::Variable),
+        ],
+        LanguageId::Protobuf => vec![
+            (r"^\s*message\s+(\w+)", SymbolKind::Struct),
+            (r"^\s*enum\s+(\w+)", SymbolKind::Enum),
+            (r"^\s*service\s+(\w+)", SymbolKind::Interface),
+            (r"^\s*rpc\s+(\w+)", SymbolKind::Method),
+        ],
+        LanguageId::Html => vec![
+            (r#"\bid\s*=\s*["']([\w-]+)["']"#, SymbolKind::Key),
+        ],
+        LanguageId::Json => vec![
+            (r#"^\s*"([^"]+)"\s*:"#, SymbolKind::Key),
+        ],
+        LanguageId::Toml => vec![
+            (r"^\[\[?\s*([^\]]+?)\s*\]\]?", SymbolKind::Object),
+            (r"^([A-Za-z0-9_-][\w.-]*)\s*=", SymbolKind::Key),
+        ],
+        LanguageId::Sql => vec![
+            (r"(?i)^\s*create\s+(?:or\s+replace\s+)?(?:temp(?:orary)?\s+)?table\s+(?:if\s+not\s+exists\s+)?([\w.]+)", SymbolKind::Struct),
+            (r"(?i)^\s*create\s+(?:or\s+replace\s+)?(?:materialized\s+)?view\s+(?:if\s+not\s+exists\s+)?([\w.]+)", SymbolKind::Interface),
+            (r"(?i)^\s*create\s+(?:or\s+replace\s+)?function\s+([\w.]+)", SymbolKind::Function),
+        ],
+        LanguageId::Dart => vec![
+            (r"^\s*(?:(?:abstract|base|final|sealed|interface)\s+)*class\s+(\w+)", SymbolKind::Class),
+            (r"^\s*(?:base\s+)?mixin\s+(\w+)", SymbolKind::Class),
+            (r"^\s*enum\s+(\w+)", SymbolKind::Enum),
+            (r"^\s*extension\s+(\w+)\s+on\b", SymbolKind::Class),
+            (r"^[\w<>?,\s]+?\s+(\w+)\s*\([^;]*$", SymbolKind::Function),
+        ],
+        LanguageId::Elixir => vec![
+            (r"^\s*def(?:module|impl)\s+([\w.]+)", SymbolKind::Module),
+            (r"^\s*defprotocol\s+([\w.]+)", SymbolKind::Interface),
+            (r"^\s*def(?:p|macrop?|guardp?|delegate)?\s+([a-z_]\w*[!?]?)", SymbolKind::Function),
+        ],
+        LanguageId::Markdown => vec![
+            (r"^#{1,6}\s+(.+?)(?:\s+#+)?\s*$", SymbolKind::String),
+        ],
+        LanguageId::Yaml => vec![
+            (r"^([A-Za-z_][\w.-]*)\s*:(?:\s|$)", SymbolKind::Key),
+            (r#"^"([^"]+)"\s*:(?:\s|$)"#, SymbolKind::Key),
+        ],
+        LanguageId::Css | LanguageId::Scss => vec![
+            (r"^\s*(--[\w-]+)\s*:", SymbolKind::Variable),
+            (r"^(\$[\w-]+)\s*:", SymbolKind::Variable),
+            (r"^@mixin\s+([\w-]+)", SymbolKind::Function),
+            (r"^@keyframes\s+([\w-]+)", SymbolKind::Object),
+            (r"^([.#][\w-]+)[^;{]*\{", SymbolKind::Class),
+        ],
+        LanguageId::C | LanguageId::Cpp => vec![
+            (r"^(?:struct|class)\s+(\w+)\s*\{", SymbolKind::Struct),
+            (r"^[A-Za-z_][\w\s\*&:<>,]*?\b(\w+)\s*\([^;]*$", SymbolKind::Function),
+        ],
+        LanguageId::Custom(_) => Vec::new(),
+    }
+}
+
+/// Guess top-level definitions with per-language regexes.
+/// Returns a flat list whose ranges cover only the defining line.
+pub fn guess_symbols(lang: LanguageId, source: &str) -> Vec<Symbol> {
+    let patterns: Vec<(Regex, SymbolKind)> = definition_patterns(lang)
+        .into_iter()
+        .filter_map(|(pattern, kind)| Regex::new(pattern).ok().map(|re| (re, kind)))
+        .collect();
+    let mut symbols = Vec::new();
+
+    for (line_no, line) in source.lines().enumerate() {
+        let line_no = line_no as u32;
+        for (re, kind) in &patterns {
+            let Some(name) = re.captures(line).and_then(|c| c.get(1)) else {
+                continue;
+            };
+            let start = utf16_len(&line[..name.start()]);
+            let end = utf16_len(&line[..name.end()]);
+            symbols.push(Symbol::new(
+                name.as_str().to_string(),
+                *kind,
+                Range::new(Position::new(line_no, 0), Position::new(line_no, utf16_len(line))),
+                Range::new(Position::new(line_no, start), Position::new(line_no, end)),
+            ));
+            break;
+        }
+    }
+
+    symbols
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tokenize_python() {
+        let tokens = tokenize(LanguageId::Python, "def f(x):  # note\n    return 'a' + 42\n");
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                LiteTokenKind::Keyword,
+                LiteTokenKind::Function,
+                LiteTokenKind::Comment,
+                LiteTokenKind::Keyword,
+                LiteTokenKind::String,
+                LiteTokenKind::Number,
+            ]
+        );
+        assert_eq!(tokens[2].start, 11);
+    }
+
+    #[test]
+    fn test_tokenize_block_comment_spans_lines() {
+        let tokens = tokenize(LanguageId::C, "/* a\nb */ int x;\n");
+        assert_eq!(tokens[0].kind, LiteTokenKind::Comment);
+        assert_eq!(tokens[1], LiteToken { line: 1, start: 0, length: 4, kind: LiteTokenKind::Comment });
+        assert_eq!(tokens[2].kind, LiteTokenKind::Keyword);
+    }
+
+    #[test]
+    fn test_tokenize_utf16_columns() {
+        let tokens = tokenize(LanguageId::JavaScript, "const é = \"😀\"; return 1;");
+        let string = tokens.iter().find(|t| t.kind == LiteTokenKind::String).unwrap();
+        assert_eq!((string.start, string.length), (10, 4));
+        let ret = tokens.iter().find(|t| t.start == 16).unwrap();
+        assert_eq!(ret.kind, LiteTokenKind::Keyword);
+    }
+
+    #[test]
+    fn test_tokenize_sql_ignores_keyword_case() {
+        let tokens = tokenize(LanguageId::Sql, "select id FROM t -- all\n");
+        let kinds: Vec<_> = tokens.iter().map(|t| t.kind).collect();
+        assert_eq!(kinds, vec![LiteTokenKind::Keyword, LiteTokenKind::Keyword, LiteTokenKind::Comment]);
+    }
+
+    #[test]
+    fn test_tokenize_yaml_comments() {
+        let tokens = tokenize(LanguageId::Yaml, "url: http://example.com  # mirror\n");
+        let comments: Vec<_> = tokens.iter().filter(|t| t.kind == LiteTokenKind::Comment).collect();
+        assert_eq!(comments, vec![&LiteToken { line: 0, start: 25, length: 8, kind: LiteTokenKind::Comment }]);
+    }
+
+    #[test]
+    fn test_tokenize_toml_comments() {
+        let tokens = tokenize(LanguageId::Toml, "# settings\npath = \"a//b\" # cache\n");
+        let comments: Vec<_> = tokens.iter().filter(|t| t.kind == LiteTokenKind::Comment).map(|t| (t.line, t.start)).collect();
+        assert_eq!(comments, vec![(0, 0), (1, 14)]);
+    }
+
+    #[test]
+    fn test_guess_symbols() {
+        let source = "package main\n\nfunc (s *Server) Run() {}\ntype Config struct {\n}\n";
+        let symbols = guess_symbols(LanguageId::Go, source);
+        assert_eq!(symbols.len(), 2);
+        assert_eq!(symbols[0].name, "Run");
+        assert_eq!(symbols[0].selection_range, Range::from_coords(2, 17, 2, 20));
+        assert_eq!(symbols[1].kind, SymbolKind::Struct);
+    }
+}
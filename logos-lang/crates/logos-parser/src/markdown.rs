@@ -0,0 +1,178 @@
+//! Markdown parsing: heading outline and fenced code regions
+
+use logos_core::{Position, Range, Symbol, SymbolKind};
+use tree_sitter::{Node, Tree};
+use crate::{node_to_range, Injection, LanguageId};
+
+/// A heading found in document order, before nesting
+struct Heading {
+    level: usize,
+    name: String,
+    start: Position,
+    selection_range: Range,
+}
+
+/// Extract symbols from a Markdown AST.
+///
+/// Every ATX (`## Title`) and setext (`Title` over `===`) heading becomes a
+/// symbol nested under the closest preceding heading of a lower level. A
+/// heading's range runs up to the next heading of the same or a higher
+/// level, so it covers its whole section.
+pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
+    let root = tree.root_node();
+    let mut headings = Vec::new();
+    collect_headings(&root, source, &mut headings);
+
+    let end = root.end_position();
+    let document_end = Position::new(end.row as u32, end.column as u32);
+    nest(&headings, document_end)
+}
+
+fn collect_headings(node: &Node, source: &str, headings: &mut Vec<Heading>) {
+    for i in 0..node.named_child_count() {
+        let Some(child) = node.named_child(i) else {
+            continue;
+        };
+        match child.kind() {
+            "atx_heading" | "setext_heading" => headings.extend(heading(&child, source)),
+            "section" | "block_quote" | "list" | "list_item" => collect_headings(&child, source, headings),
+            _ => {}
+        }
+    }
+}
+
+fn heading(node: &Node, source: &str) -> Option<Heading> {
+    let content = node.child_by_field_name("heading_content")?;
+    let level = if node.kind() == "atx_heading" {
+        let marker = node.named_child(0)?.kind();
+        marker.strip_prefix("atx_h")?.strip_suffix("_marker")?.parse().ok()?
+    } else if find_child(node, "setext_h1_underline").is_some() {
+        1
+    } else {
+        2
+    };
+    let text = source[content.byte_range()].trim();
+    // An ATX heading may close with a run of `#`
+    let name = if node.kind() == "atx_heading" {
+        text.trim_end_matches('#').trim_end()
+    } else {
+        text
+    };
+    if name.is_empty() {
+        return None;
+    }
+    Some(Heading {
+        level,
+        name: name.split_whitespace().collect::<Vec<_>>().join(" "),
+        start: node_to_range(node).start,
+        selection_range: node_to_range(&content),
+    })
+}
+
+/// Nest the headings of `headings` by level; `end` is where the last
+/// section stops
+fn nest(headings: &[Heading], end: Position) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let mut i = 0;
+    while i < headings.len() {
+        let heading = &headings[i];
+        let section_len = headings[i + 1..]
+            .iter()
+            .position(|h| h.level <= heading.level)
+            .unwrap_or(headings.len() - i - 1);
+        let next = i + 1 + section_len;
+        let section_end = headings.get(next).map_or(end, |h| h.start);
+
+        let range = Range::new(heading.start, section_end);
+        let mut symbol = Symbol::new(heading.name.clone(), SymbolKind::String, range, heading.selection_range);
+        symbol.children = nest(&headings[i + 1..next], section_end);
+        symbols.push(symbol);
+        i = next;
+    }
+    symbols
+}
+
+/// Fenced code blocks tagged with a language (```` ```rust ````), with the
+/// range of their content. Tags name languages by their id where this
+/// build knows them (`py` gives `"python"`) and are kept lowercase as
+/// written otherwise. Untagged and indented blocks are skipped.
+pub fn injections(tree: &Tree, source: &str) -> Vec<Injection> {
+    let mut injections = Vec::new();
+    collect_injections(&tree.root_node(), source, &mut injections);
+    injections
+}
+
+fn collect_injections(node: &Node, source: &str, injections: &mut Vec<Injection>) {
+    if node.kind() != "fenced_code_block" {
+        for i in 0..node.named_child_count() {
+            if let Some(child) = node.named_child(i) {
+                collect_injections(&child, source, injections);
+            }
+        }
+        return;
+    }
+
+    let Some(tag) = find_child(node, "info_string").and_then(|info| find_child(&info, "language")) else {
+        return;
+    };
+    let Some(content) = find_child(node, "code_fence_content") else {
+        return;
+    };
+    let tag = &source[tag.byte_range()];
+    let language = LanguageId::from_str(tag).map_or_else(|| tag.to_lowercase(), |lang| lang.as_str().to_string());
+    injections.push(Injection {
+        language,
+        range: node_to_range(&content),
+        byte_range: content.byte_range(),
+        holes: Vec::new(),
+    });
+}
+
+fn find_child<'a>(node: &Node<'a>, kind: &str) -> Option<Node<'a>> {
+    (0..node.named_child_count())
+        .filter_map(|i| node.named_child(i))
+        .find(|child| child.kind() == kind)
+}
+
+/// Markdown has no keywords
+pub fn get_keywords() -> &'static [&'static str] {
+    &[]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LanguageParser;
+
+    fn parse(source: &str) -> Tree {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Markdown).unwrap();
+        parser.parse(source, None).unwrap()
+    }
+
+    #[test]
+    fn test_heading_hierarchy() {
+        let source = "# Guide\n\nIntro.\n\n## Install ##\n\n### From   source\n\nUsage\n-----\n\n# Appendix\n";
+        let symbols = extract_symbols(&parse(source), source);
+
+        let names: Vec<_> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["Guide", "Appendix"]);
+        let guide: Vec<_> = symbols[0].children.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(guide, vec!["Install", "Usage"]);
+        assert_eq!(symbols[0].children[0].children[0].name, "From source");
+        assert_eq!(symbols[0].range, Range::from_coords(0, 0, 11, 0));
+        assert_eq!(symbols[0].children[0].selection_range.start, Position::new(4, 3));
+        assert_eq!(symbols[1].range.end, Position::new(12, 0));
+    }
+
+    #[test]
+    fn test_fenced_code_injections() {
+        let source = "# Example\n\n```py\ndef f():\n    pass\n```\n\n~~~Haskell\nmain = pure ()\n~~~\n\n```\nplain\n```\n";
+        let found = injections(&parse(source), source);
+
+        let languages: Vec<_> = found.iter().map(|i| i.language.as_str()).collect();
+        assert_eq!(languages, vec!["python", "haskell"]);
+        assert_eq!(&source[found[0].byte_range.clone()], "def f():\n    pass\n");
+        assert_eq!(found[0].range.start, Position::new(3, 0));
+    }
+}
@@ -0,0 +1,147 @@
+//! Parser pool
+//!
+//! A tree-sitter parser parses one document at a time. Instead of sharing
+//! one parser behind a lock, callers check out a parser for the language
+//! they need and it goes back to the pool when the guard drops, so indexing
+//! threads and interactive requests parse in parallel.
+
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
+use std::sync::{Mutex, OnceLock};
+
+use crate::{LanguageId, LanguageParser, ParseError};
+
+/// Idle parsers kept per language when no limit is given
+const DEFAULT_MAX_IDLE: usize = 8;
+
+/// Idle parsers keyed by language, handed out with `checkout`
+pub struct ParserPool {
+    idle: Mutex<HashMap<LanguageId, Vec<LanguageParser>>>,
+    /// Parsers kept per language; extra ones are dropped on checkin
+    max_idle: usize,
+}
+
+impl ParserPool {
+    pub fn new(max_idle: usize) -> Self {
+        Self {
+            idle: Mutex::new(HashMap::new()),
+            max_idle,
+        }
+    }
+
+    /// A parser set to `lang`, reusing an idle one when there is one
+    pub fn checkout(&self, lang: LanguageId) -> Result<PooledParser<'_>, ParseError> {
+        let idle = self.idle.lock().ok().and_then(|mut idle| idle.get_mut(&lang)?.pop());
+        let parser = match idle {
+            Some(parser) => parser,
+            None => {
+                let mut parser = LanguageParser::new();
+                parser.set_language(lang)?;
+                parser
+            }
+        };
+        Ok(PooledParser { pool: self, parser: Some(parser) })
+    }
+
+    /// Number of idle parsers for `lang`
+    pub fn idle_count(&self, lang: LanguageId) -> usize {
+        self.idle
+            .lock()
+            .map(|idle| idle.get(&lang).map_or(0, Vec::len))
+            .unwrap_or(0)
+    }
+
+    fn checkin(&self, mut parser: LanguageParser) {
+        let Some(lang) = parser.current_language() else {
+            return;
+        };
+        // A parse interrupted by a panic leaves state behind
+        parser.reset();
+        if let Ok(mut idle) = self.idle.lock() {
+            let parsers = idle.entry(lang).or_default();
+            if parsers.len() < self.max_idle {
+                parsers.push(parser);
+            }
+        }
+    }
+}
+
+impl Default for ParserPool {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_IDLE)
+    }
+}
+
+/// Process-wide pool shared by the indexer and the analysis host
+pub fn shared() -> &'static ParserPool {
+    static POOL: OnceLock<ParserPool> = OnceLock::new();
+    POOL.get_or_init(ParserPool::default)
+}
+
+/// A parser checked out of a `ParserPool`, returned to it on drop
+pub struct PooledParser<'a> {
+    pool: &'a ParserPool,
+    parser: Option<LanguageParser>,
+}
+
+impl Deref for PooledParser<'_> {
+    type Target = LanguageParser;
+
+    fn deref(&self) -> &LanguageParser {
+        self.parser.as_ref().expect("parser is present until drop")
+    }
+}
+
+impl DerefMut for PooledParser<'_> {
+    fn deref_mut(&mut self) -> &mut LanguageParser {
+        self.parser.as_mut().expect("parser is present until drop")
+    }
+}
+
+impl Drop for PooledParser<'_> {
+    fn drop(&mut self) {
+        if let Some(parser) = self.parser.take() {
+            self.pool.checkin(parser);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checkout_reuses_checked_in_parsers() {
+        let pool = ParserPool::new(1);
+        {
+            let mut first = pool.checkout(LanguageId::Python).unwrap();
+            let mut second = pool.checkout(LanguageId::Python).unwrap();
+            assert!(!first.parse("x = 1", None).unwrap().root_node().has_error());
+            assert!(!second.parse("y = 2", None).unwrap().root_node().has_error());
+        }
+        // Only one idle parser is kept
+        assert_eq!(pool.idle_count(LanguageId::Python), 1);
+        assert_eq!(pool.idle_count(LanguageId::Rust), 0);
+
+        let mut parser = pool.checkout(LanguageId::Python).unwrap();
+        assert_eq!(pool.idle_count(LanguageId::Python), 0);
+        assert_eq!(parser.current_language(), Some(LanguageId::Python));
+        assert!(!parser.parse("def f(): pass", None).unwrap().root_node().has_error());
+    }
+
+    #[test]
+    fn test_parallel_checkouts() {
+        let pool = ParserPool::default();
+        std::thread::scope(|scope| {
+            for i in 0..4 {
+                let pool = &pool;
+                scope.spawn(move || {
+                    let mut parser = pool.checkout(LanguageId::Rust).unwrap();
+                    let tree = parser.parse(&format!("fn f{}() {{}}", i), None).unwrap();
+                    assert_eq!(tree.root_node().child(0).unwrap().kind(), "function_item");
+                });
+            }
+        });
+        assert!(pool.idle_count(LanguageId::Rust) >= 1);
+    }
+}
@@ -0,0 +1,346 @@
+//! Protocol Buffers symbol extraction
+//!
+//! There is no bundled protobuf grammar: `.proto` files parse with the C
+//! grammar, which reads their comments (so doc comments attach) but not
+//! their declarations. Those come from scanning the tokens instead:
+//! messages, enums and services with their fields, values, `oneof`s and
+//! RPCs, nested as written. Ranges are in byte columns, like those of the
+//! tree-sitter extractors.
+
+use logos_core::{Position, Range, Symbol, SymbolKind};
+
+/// Keywords of proto2 and proto3
+const KEYWORDS: &[&str] = &[
+    "syntax", "edition", "package", "import", "public", "weak", "option", "message", "enum", "service", "rpc",
+    "returns", "stream", "oneof", "map", "repeated", "optional", "required", "reserved", "extensions", "extend",
+    "to", "max", "group", "double", "float", "int32", "int64", "uint32", "uint64", "sint32", "sint64", "fixed32",
+    "fixed64", "sfixed32", "sfixed64", "bool", "string", "bytes", "true", "false",
+];
+
+pub fn get_keywords() -> &'static [&'static str] {
+    KEYWORDS
+}
+
+/// A word, string or punctuation character, comments left out
+#[derive(Debug, Clone, Copy)]
+struct Token<'a> {
+    text: &'a str,
+    range: Range,
+}
+
+/// What a block declares, which decides how its statements read
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Block {
+    File,
+    Message,
+    Enum,
+    Service,
+}
+
+/// Messages, enums, services and their members, nested
+pub fn extract_symbols(source: &str) -> Vec<Symbol> {
+    let tokens = tokenize(source);
+    let mut parser = Parser { tokens: &tokens, next: 0 };
+    parser.block(Block::File)
+}
+
+fn tokenize(source: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    let bytes = source.as_bytes();
+    let (mut i, mut line, mut line_start) = (0, 0u32, 0);
+    let position = |offset: usize, line: u32, line_start: usize| Position::new(line, (offset - line_start) as u32);
+    while i < bytes.len() {
+        let c = bytes[i];
+        if c == b'\n' {
+            i += 1;
+            line += 1;
+            line_start = i;
+        } else if c.is_ascii_whitespace() {
+            i += 1;
+        } else if source[i..].starts_with("//") {
+            i = source[i..].find('\n').map_or(bytes.len(), |end| i + end);
+        } else if source[i..].starts_with("/*") {
+            let end = source[i + 2..].find("*/").map_or(bytes.len(), |end| i + 2 + end + 2);
+            for (offset, _) in source[i..end].match_indices('\n') {
+                line += 1;
+                line_start = i + offset + 1;
+            }
+            i = end;
+        } else if c == b'"' || c == b'\'' {
+            let start = i;
+            i += 1;
+            while i < bytes.len() && bytes[i] != c && bytes[i] != b'\n' {
+                i += if bytes[i] == b'\\' { 2 } else { 1 };
+            }
+            i = (i + 1).min(bytes.len());
+            let range = Range::new(position(start, line, line_start), position(i, line, line_start));
+            tokens.push(Token { text: &source[start..i], range });
+        } else if c.is_ascii_alphanumeric() || c == b'_' || c == b'.' {
+            let start = i;
+            while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'.') {
+                i += 1;
+            }
+            let range = Range::new(position(start, line, line_start), position(i, line, line_start));
+            tokens.push(Token { text: &source[start..i], range });
+        } else {
+            let width = source[i..].chars().next().map_or(1, char::len_utf8);
+            let range = Range::new(position(i, line, line_start), position(i + width, line, line_start));
+            tokens.push(Token { text: &source[i..i + width], range });
+            i += width;
+        }
+    }
+    tokens
+}
+
+struct Parser<'a, 't> {
+    tokens: &'t [Token<'a>],
+    next: usize,
+}
+
+impl<'a> Parser<'a, '_> {
+    fn peek(&self, ahead: usize) -> Option<Token<'a>> {
+        self.tokens.get(self.next + ahead).copied()
+    }
+
+    fn is_name(token: Option<Token>) -> bool {
+        token.is_some_and(|t| t.text.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_'))
+    }
+
+    /// Declarations up to the `}` closing the block, which is consumed
+    fn block(&mut self, block: Block) -> Vec<Symbol> {
+        let mut symbols = Vec::new();
+        while let Some(token) = self.peek(0) {
+            match token.text {
+                "}" => {
+                    self.next += 1;
+                    break;
+                }
+                ";" => self.next += 1,
+                "{" => {
+                    self.next += 1;
+                    self.block(Block::File);
+                }
+                "message" | "enum" | "service" | "oneof" if Self::is_name(self.peek(1)) && self.opens(2) => {
+                    symbols.push(self.declaration(token));
+                }
+                "extend" => {
+                    // Fields added to another message belong to it
+                    self.skip_statement();
+                }
+                "rpc" if block == Block::Service && Self::is_name(self.peek(1)) => symbols.push(self.rpc(token)),
+                "option" | "reserved" | "extensions" | "syntax" | "edition" | "package" | "import" => {
+                    self.skip_statement();
+                }
+                _ => match block {
+                    Block::Message => symbols.extend(self.field(SymbolKind::Field)),
+                    Block::Enum => symbols.extend(self.field(SymbolKind::EnumMember)),
+                    Block::File | Block::Service => self.skip_statement(),
+                },
+            }
+        }
+        symbols
+    }
+
+    /// Whether the token `ahead` opens a block
+    fn opens(&self, ahead: usize) -> bool {
+        self.peek(ahead).is_some_and(|t| t.text == "{")
+    }
+
+    /// `message`, `enum`, `service` or `oneof` with its block
+    fn declaration(&mut self, keyword: Token<'a>) -> Symbol {
+        let name = self.tokens[self.next + 1];
+        self.next += 3;
+        let (kind, block) = match keyword.text {
+            "message" => (SymbolKind::Struct, Block::Message),
+            "enum" => (SymbolKind::Enum, Block::Enum),
+            "service" => (SymbolKind::Interface, Block::Service),
+            // The fields of a oneof are fields of the message
+            _ => (SymbolKind::Field, Block::Message),
+        };
+        let children = self.block(block);
+        let end = self.tokens[..self.next].last().map_or(name.range.end, |t| t.range.end);
+        let mut symbol = Symbol::new(name.text.to_string(), kind, Range::new(keyword.range.start, end), name.range);
+        if keyword.text == "oneof" {
+            symbol.detail = Some(format!("oneof {}", name.text));
+        }
+        symbol.children = children;
+        symbol
+    }
+
+    /// `rpc Name (Request) returns (Response)`, ended by `;` or a block of
+    /// options
+    fn rpc(&mut self, keyword: Token<'a>) -> Symbol {
+        let name = self.tokens[self.next + 1];
+        let start = self.next;
+        let mut end = start;
+        while let Some(token) = self.peek(end - start) {
+            end += 1;
+            if token.text == ";" || token.text == "{" {
+                break;
+            }
+        }
+        let signature = self.tokens[start..end]
+            .iter()
+            .map(|t| t.text)
+            .filter(|text| *text != ";" && *text != "{")
+            .collect::<Vec<_>>();
+        self.next = end;
+        if self.tokens.get(end - 1).is_some_and(|t| t.text == "{") {
+            self.block(Block::File);
+        }
+        let last = self.tokens[self.next - 1];
+        let mut symbol = Symbol::new(name.text.to_string(), SymbolKind::Method, Range::new(keyword.range.start, last.range.end), name.range);
+        symbol.detail = Some(join_signature(&signature));
+        symbol
+    }
+
+    /// A field, `[label] type name = number [options];`, or an enum value,
+    /// `NAME = number [options];`: the name is the word before the `=`
+    fn field(&mut self, kind: SymbolKind) -> Option<Symbol> {
+        let start = self.next;
+        let mut equals = None;
+        while let Some(token) = self.peek(0) {
+            match token.text {
+                ";" => {
+                    self.next += 1;
+                    break;
+                }
+                // A group declares a nested message too
+                "{" | "}" => break,
+                "=" if equals.is_none() => equals = Some(self.next),
+                _ => {}
+            }
+            self.next += 1;
+        }
+        let equals = equals.filter(|&equals| equals > start)?;
+        let name = self.tokens[equals - 1];
+        if !Self::is_name(Some(name)) {
+            return None;
+        }
+        let last = self.tokens[self.next - 1];
+        let mut symbol = Symbol::new(name.text.to_string(), kind, Range::new(self.tokens[start].range.start, last.range.end), name.range);
+        if kind == SymbolKind::Field {
+            let words: Vec<&str> = self.tokens[start..self.next.min(equals + 2)].iter().map(|t| t.text).collect();
+            symbol.detail = Some(join_signature(&words));
+        }
+        Some(symbol)
+    }
+
+    fn skip_statement(&mut self) {
+        while let Some(token) = self.peek(0) {
+            self.next += 1;
+            match token.text {
+                ";" => return,
+                "{" => {
+                    self.block(Block::File);
+                    return;
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Tokens joined as written, without spaces inside brackets and before
+/// commas: `map<string, int32> counts = 1`, `rpc Get(GetRequest) returns (User)`
+fn join_signature(words: &[&str]) -> String {
+    let mut text = String::new();
+    for (i, word) in words.iter().enumerate() {
+        let previous = if i == 0 { "" } else { words[i - 1] };
+        let glued = matches!(*word, ")" | ">" | ",")
+            || matches!(previous, "(" | "<")
+            || (matches!(*word, "(" | "<") && previous != "returns");
+        if i > 0 && !glued {
+            text.push(' ');
+        }
+        text.push_str(word);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outline(symbols: &[Symbol]) -> Vec<String> {
+        symbols
+            .iter()
+            .map(|s| {
+                if s.children.is_empty() {
+                    format!("{:?} {}", s.kind, s.name)
+                } else {
+                    format!("{:?} {}({})", s.kind, s.name, outline(&s.children).join(", "))
+                }
+            })
+            .collect()
+    }
+
+    const USER_PROTO: &str = r#"syntax = "proto3";
+package acme.users.v1;
+
+import "google/protobuf/timestamp.proto";
+option go_package = "acme/users/v1;usersv1";
+
+// A registered user
+message User {
+  string id = 1;
+  repeated string tags = 2 [packed = true];
+  map<string, int32> counts = 3;
+  oneof contact {
+    string email = 4;
+    string phone = 5;
+  }
+  message Address { string city = 1; }
+  reserved 6, 7;
+}
+
+enum Role {
+  ROLE_UNSPECIFIED = 0;
+  ROLE_ADMIN = 1;
+}
+
+service UserService {
+  option (acme.auth) = true;
+  rpc GetUser(GetUserRequest) returns (User);
+  rpc WatchUsers(stream WatchRequest) returns (stream User) {
+    option deprecated = true;
+  }
+}
+"#;
+
+    #[test]
+    fn test_outline() {
+        let symbols = extract_symbols(USER_PROTO);
+        assert_eq!(
+            outline(&symbols),
+            vec![
+                "Struct User(Field id, Field tags, Field counts, Field contact(Field email, Field phone), Struct Address(Field city))",
+                "Enum Role(EnumMember ROLE_UNSPECIFIED, EnumMember ROLE_ADMIN)",
+                "Interface UserService(Method GetUser, Method WatchUsers)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ranges_and_details() {
+        let symbols = extract_symbols(USER_PROTO);
+        let user = &symbols[0];
+        assert_eq!(user.selection_range, Range::from_coords(7, 8, 7, 12));
+        assert_eq!(user.range, Range::from_coords(7, 0, 17, 1));
+        assert_eq!(user.children[2].detail.as_deref(), Some("map<string, int32> counts = 3"));
+        assert_eq!(user.children[1].detail.as_deref(), Some("repeated string tags = 2"));
+
+        let service = &symbols[2];
+        assert_eq!(service.children[0].detail.as_deref(), Some("rpc GetUser(GetUserRequest) returns (User)"));
+        assert_eq!(service.children[1].detail.as_deref(), Some("rpc WatchUsers(stream WatchRequest) returns (stream User)"));
+        assert_eq!(service.children[1].range.end, Position::new(29, 3));
+    }
+
+    #[test]
+    fn test_comments_and_strings_are_skipped() {
+        let source = "/* message Hidden {\n} */\nmessage Shown {\n  // string skipped = 1;\n  string note = 1 [default = \"a { b\"];\n}\n";
+        let symbols = extract_symbols(source);
+        assert_eq!(outline(&symbols), vec!["Struct Shown(Field note)"]);
+        assert_eq!(symbols[0].selection_range, Range::from_coords(2, 8, 2, 13));
+    }
+}
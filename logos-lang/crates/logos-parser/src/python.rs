@@ -2,7 +2,7 @@
 
 use logos_core::{Symbol, SymbolKind};
 use tree_sitter::{Node, Tree};
-use crate::node_to_range;
+use crate::{node_to_range, signature};
 
 /// Extract symbols from a Python AST
 pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
@@ -35,11 +35,7 @@ fn extract_symbols_from_node(
                     node_to_range(&name_node),
                 );
 
-                // Extract parameters for detail
-                if let Some(params) = node.child_by_field_name("parameters") {
-                    let params_text = get_node_text(&params, source);
-                    symbol.detail = Some(params_text);
-                }
+                symbol.detail = Some(signature(node, source));
 
                 // Extract nested symbols
                 if let Some(body) = node.child_by_field_name("body") {
@@ -183,6 +179,7 @@ def hello(name: str) -> str:
         assert_eq!(symbols.len(), 1);
         assert_eq!(symbols[0].name, "hello");
         assert_eq!(symbols[0].kind, SymbolKind::Function);
+        assert_eq!(symbols[0].detail.as_deref(), Some("def hello(name: str) -> str"));
     }
 
     #[test]
@@ -0,0 +1,180 @@
+//! Tree-sitter query execution
+//!
+//! Runs `.scm` queries (highlights, locals, tags or user patterns) against
+//! parse trees. Queries are compiled once per language and query source;
+//! `#eq?`, `#match?` and `#any-of?` predicates are applied while matching.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use logos_core::Range;
+use streaming_iterator::StreamingIterator;
+use tree_sitter::{Query, QueryCursor, Tree};
+
+use crate::{node_to_range, LanguageId, ParseError};
+
+/// A node captured by a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryCapture {
+    /// Capture name without the `@`
+    pub name: String,
+    pub range: Range,
+    pub byte_range: std::ops::Range<usize>,
+    /// Index of the pattern that produced the capture
+    pub pattern_index: usize,
+}
+
+impl QueryCapture {
+    /// Source text of the captured node
+    pub fn text<'s>(&self, source: &'s str) -> &'s str {
+        &source[self.byte_range.clone()]
+    }
+}
+
+/// One match of a query pattern with its captures
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryMatch {
+    /// Index of the matching pattern within the query
+    pub pattern_index: usize,
+    pub captures: Vec<QueryCapture>,
+}
+
+/// Compiles and runs tree-sitter queries, caching compiled queries
+#[derive(Default)]
+pub struct QueryEngine {
+    compiled: HashMap<(LanguageId, String), Arc<Query>>,
+}
+
+impl QueryEngine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compile `source` for `lang`, or return the cached query
+    pub fn compile(&mut self, lang: LanguageId, source: &str) -> Result<Arc<Query>, ParseError> {
+        let key = (lang, source.to_string());
+        if let Some(query) = self.compiled.get(&key) {
+            return Ok(Arc::clone(query));
+        }
+        let query = Query::new(&lang.grammar()?, source).map_err(|e| ParseError::InvalidQuery(e.to_string()))?;
+        let query = Arc::new(query);
+        self.compiled.insert(key, Arc::clone(&query));
+        Ok(query)
+    }
+
+    /// Matches of `query` in `tree`, in the order tree-sitter reports them.
+    /// `range` limits matching to nodes intersecting it.
+    pub fn matches(
+        &mut self,
+        lang: LanguageId,
+        query: &str,
+        tree: &Tree,
+        source: &str,
+        range: Option<Range>,
+    ) -> Result<Vec<QueryMatch>, ParseError> {
+        let query = self.compile(lang, query)?;
+        let names = query.capture_names();
+        let mut cursor = cursor_for(range);
+        let mut matches = Vec::new();
+        let mut found = cursor.matches(&query, tree.root_node(), source.as_bytes());
+        while let Some(m) = found.next() {
+            let captures = m
+                .captures
+                .iter()
+                .map(|capture| QueryCapture {
+                    name: names[capture.index as usize].to_string(),
+                    range: node_to_range(&capture.node),
+                    byte_range: capture.node.byte_range(),
+                    pattern_index: m.pattern_index,
+                })
+                .collect();
+            matches.push(QueryMatch { pattern_index: m.pattern_index, captures });
+        }
+        Ok(matches)
+    }
+
+    /// Captures of `query` in `tree`, in document order. Captures whose
+    /// name starts with `_` are helpers for predicates and left out.
+    pub fn captures(
+        &mut self,
+        lang: LanguageId,
+        query: &str,
+        tree: &Tree,
+        source: &str,
+        range: Option<Range>,
+    ) -> Result<Vec<QueryCapture>, ParseError> {
+        let query = self.compile(lang, query)?;
+        let names = query.capture_names();
+        let mut cursor = cursor_for(range);
+        let mut captures = Vec::new();
+        let mut found = cursor.captures(&query, tree.root_node(), source.as_bytes());
+        while let Some((m, index)) = found.next() {
+            let capture = m.captures[*index];
+            let name = names[capture.index as usize];
+            if name.starts_with('_') {
+                continue;
+            }
+            captures.push(QueryCapture {
+                name: name.to_string(),
+                range: node_to_range(&capture.node),
+                byte_range: capture.node.byte_range(),
+                pattern_index: m.pattern_index,
+            });
+        }
+        Ok(captures)
+    }
+}
+
+fn cursor_for(range: Option<Range>) -> QueryCursor {
+    let mut cursor = QueryCursor::new();
+    if let Some(range) = range {
+        let point = |p: logos_core::Position| tree_sitter::Point::new(p.line as usize, p.column as usize);
+        cursor.set_point_range(point(range.start)..point(range.end));
+    }
+    cursor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LanguageParser;
+
+    fn parse(lang: LanguageId, source: &str) -> Tree {
+        let mut parser = LanguageParser::new();
+        parser.set_language(lang).unwrap();
+        parser.parse(source, None).unwrap()
+    }
+
+    #[test]
+    fn test_captures_with_predicates() {
+        let source = "def main():\n    helper()\n    print(1)\n";
+        let tree = parse(LanguageId::Python, source);
+        let query = r#"(call function: (identifier) @call (#not-eq? @call "print"))
+(function_definition name: (identifier) @definition)"#;
+        let mut engine = QueryEngine::new();
+        let captures = engine.captures(LanguageId::Python, query, &tree, source, None).unwrap();
+        let found: Vec<_> = captures.iter().map(|c| (c.name.as_str(), c.text(source), c.range.start.line)).collect();
+        assert_eq!(found, vec![("definition", "main", 0), ("call", "helper", 1)]);
+
+        let in_range = engine
+            .captures(LanguageId::Python, query, &tree, source, Some(Range::from_coords(1, 0, 1, 12)))
+            .unwrap();
+        assert_eq!(in_range.len(), 1);
+        assert_eq!(engine.compiled.len(), 1);
+    }
+
+    #[test]
+    fn test_matches_and_errors() {
+        let source = "fn a() {}\nstruct B;\n";
+        let tree = parse(LanguageId::Rust, source);
+        let mut engine = QueryEngine::new();
+        let matches = engine
+            .matches(LanguageId::Rust, "(function_item name: (_) @name) (struct_item name: (_) @name)", &tree, source, None)
+            .unwrap();
+        let found: Vec<_> = matches.iter().map(|m| (m.pattern_index, m.captures[0].text(source))).collect();
+        assert_eq!(found, vec![(0, "a"), (1, "B")]);
+
+        let error = engine.matches(LanguageId::Rust, "(no_such_node) @x", &tree, source, None).unwrap_err();
+        assert!(matches!(error, ParseError::InvalidQuery(_)));
+    }
+}
@@ -0,0 +1,245 @@
+//! SQL parsing: outline of statements and the objects they create
+
+use logos_core::{Symbol, SymbolKind};
+use tree_sitter::{Node, Tree};
+use crate::node_to_range;
+
+/// Extract symbols from a SQL AST, one per statement.
+///
+/// `CREATE TABLE` gives a struct with its columns as fields (the column
+/// type as detail), `CREATE [MATERIALIZED] VIEW` an interface and
+/// `CREATE FUNCTION` a function whose detail is its argument list; indexes,
+/// schemas, types, triggers and sequences are listed too. Names keep their
+/// schema (`public.orders`) without identifier quotes. Other statements
+/// are named by their command (`SELECT`, `ALTER TABLE`) with the table they
+/// work on as detail. Common table expressions (`WITH recent AS (...)`) are
+/// children of their statement.
+pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
+    let root = tree.root_node();
+    let mut symbols = Vec::new();
+    for i in 0..root.named_child_count() {
+        let Some(statement) = root.named_child(i) else {
+            continue;
+        };
+        if statement.kind() != "statement" {
+            continue;
+        }
+        let created = (0..statement.named_child_count())
+            .filter_map(|j| statement.named_child(j))
+            .find_map(|node| create_symbol(&node, source));
+        let Some(mut symbol) = created.or_else(|| statement_symbol(&statement, source)) else {
+            continue;
+        };
+        let mut ctes = Vec::new();
+        collect_ctes(&statement, source, &mut ctes);
+        symbol.children.extend(ctes);
+        symbols.push(symbol);
+    }
+    symbols
+}
+
+fn create_symbol(node: &Node, source: &str) -> Option<Symbol> {
+    let kind = match node.kind() {
+        "create_table" => SymbolKind::Struct,
+        "create_view" | "create_materialized_view" => SymbolKind::Interface,
+        "create_function" => SymbolKind::Function,
+        "create_index" => SymbolKind::Key,
+        "create_schema" | "create_database" => SymbolKind::Namespace,
+        "create_type" if find_child(node, "keyword_enum").is_some() => SymbolKind::Enum,
+        "create_type" => SymbolKind::Class,
+        "create_trigger" => SymbolKind::Event,
+        "create_sequence" => SymbolKind::Variable,
+        _ => return None,
+    };
+    // An index is named before the table it's on
+    let name_node = node
+        .child_by_field_name("column")
+        .or_else(|| find_child(node, "object_reference"))
+        .or_else(|| find_child(node, "identifier"))?;
+    let name = unquote(&source[name_node.byte_range()]);
+    let mut symbol = Symbol::new(name, kind, node_to_range(node), node_to_range(&name_node));
+
+    match node.kind() {
+        "create_table" => {
+            if let Some(columns) = find_child(node, "column_definitions") {
+                symbol.children = (0..columns.named_child_count())
+                    .filter_map(|i| columns.named_child(i))
+                    .filter(|c| c.kind() == "column_definition")
+                    .filter_map(|column| column_symbol(&column, source))
+                    .collect();
+            }
+        }
+        "create_function" => {
+            symbol.detail = find_child(node, "function_arguments").map(|args| source[args.byte_range()].to_string());
+        }
+        "create_index" => {
+            symbol.detail = find_child(node, "object_reference").map(|table| unquote(&source[table.byte_range()]));
+        }
+        _ => {}
+    }
+    Some(symbol)
+}
+
+/// A statement that creates nothing, named by its command
+fn statement_symbol(statement: &Node, source: &str) -> Option<Symbol> {
+    let command = (0..statement.named_child_count())
+        .filter_map(|i| statement.named_child(i))
+        .find(|node| !node.kind().starts_with("keyword_") && !matches!(node.kind(), "cte" | "comment"))?;
+    let name = match command.kind() {
+        "set_operation" => "SELECT".to_string(),
+        kind => kind.replace('_', " ").to_uppercase(),
+    };
+    let keyword = command.child(0).unwrap_or(command);
+    let mut symbol = Symbol::new(name, SymbolKind::Operator, node_to_range(statement), node_to_range(&keyword));
+    symbol.detail = target_table(statement).map(|table| unquote(&source[table.byte_range()]));
+    Some(symbol)
+}
+
+/// First table a statement names, outside its common table expressions
+fn target_table<'a>(node: &Node<'a>) -> Option<Node<'a>> {
+    let mut cursor = node.walk();
+    let children: Vec<_> = node.named_children(&mut cursor).collect();
+    children.into_iter().find_map(|child| match child.kind() {
+        "object_reference" => Some(child),
+        "cte" => None,
+        _ => target_table(&child),
+    })
+}
+
+fn collect_ctes(node: &Node, source: &str, ctes: &mut Vec<Symbol>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if child.kind() != "cte" {
+            collect_ctes(&child, source, ctes);
+            continue;
+        }
+        let Some(name) = find_child(&child, "identifier") else {
+            continue;
+        };
+        let mut symbol = Symbol::new(
+            unquote(&source[name.byte_range()]),
+            SymbolKind::Interface,
+            node_to_range(&child),
+            node_to_range(&name),
+        );
+        // CTEs nest in CTEs
+        collect_ctes(&child, source, &mut symbol.children);
+        ctes.push(symbol);
+    }
+}
+
+fn column_symbol(column: &Node, source: &str) -> Option<Symbol> {
+    let name = column.child_by_field_name("name")?;
+    let mut symbol = Symbol::new(
+        unquote(&source[name.byte_range()]),
+        SymbolKind::Field,
+        node_to_range(column),
+        node_to_range(&name),
+    );
+    symbol.detail = column.child_by_field_name("type").map(|t| source[t.byte_range()].to_string());
+    Some(symbol)
+}
+
+/// Drop the quotes of quoted identifiers (`"x"`, `` `x` ``, `[x]`)
+fn unquote(name: &str) -> String {
+    name.chars().filter(|c| !matches!(c, '"' | '`' | '[' | ']')).collect()
+}
+
+fn find_child<'a>(node: &Node<'a>, kind: &str) -> Option<Node<'a>> {
+    (0..node.named_child_count())
+        .filter_map(|i| node.named_child(i))
+        .find(|child| child.kind() == kind)
+}
+
+/// Get SQL keywords. SQL is case-insensitive; completions offer the
+/// conventional upper case.
+pub fn get_keywords() -> &'static [&'static str] {
+    &[
+        "ADD", "ALL", "ALTER", "AND", "AS", "ASC", "BEGIN", "BETWEEN", "BY",
+        "CASCADE", "CASE", "CHECK", "COLUMN", "COMMIT", "CONSTRAINT", "CREATE",
+        "CROSS", "DEFAULT", "DELETE", "DESC", "DISTINCT", "DROP", "ELSE", "END",
+        "EXISTS", "FOREIGN", "FROM", "FULL", "FUNCTION", "GROUP", "HAVING", "IF",
+        "IN", "INDEX", "INNER", "INSERT", "INTO", "IS", "JOIN", "KEY", "LEFT",
+        "LIKE", "LIMIT", "NOT", "NULL", "OFFSET", "ON", "OR", "ORDER", "OUTER",
+        "PRIMARY", "REFERENCES", "REPLACE", "RETURNING", "RETURNS", "RIGHT",
+        "ROLLBACK", "SELECT", "SET", "TABLE", "THEN", "TRANSACTION", "UNION",
+        "UNIQUE", "UPDATE", "USING", "VALUES", "VIEW", "WHEN", "WHERE", "WITH",
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LanguageId, LanguageParser};
+
+    fn parse(source: &str) -> Vec<Symbol> {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Sql).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        extract_symbols(&tree, source)
+    }
+
+    #[test]
+    fn test_migration_outline() {
+        let source = r#"CREATE TABLE users (
+  id INT PRIMARY KEY,
+  "display name" TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS public.orders (id INT);
+CREATE INDEX idx_users ON users (id);
+CREATE MATERIALIZED VIEW recent AS SELECT * FROM orders;
+"#;
+        let symbols = parse(source);
+        let names: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("users", SymbolKind::Struct),
+                ("public.orders", SymbolKind::Struct),
+                ("idx_users", SymbolKind::Key),
+                ("recent", SymbolKind::Interface),
+            ]
+        );
+        assert_eq!(symbols[2].detail.as_deref(), Some("users"));
+        let columns: Vec<_> = symbols[0]
+            .children
+            .iter()
+            .map(|c| (c.name.as_str(), c.detail.as_deref()))
+            .collect();
+        assert_eq!(columns, vec![("id", Some("INT")), ("display name", Some("TEXT"))]);
+    }
+
+    #[test]
+    fn test_function_and_view() {
+        let source = "create view active as select * from users;\ncreate or replace function add(a int, b int) returns int as $$ select a + b $$ language sql;\n";
+        let symbols = parse(source);
+        assert_eq!(symbols[0].name, "active");
+        assert_eq!(symbols[0].kind, SymbolKind::Interface);
+        assert_eq!(symbols[1].name, "add");
+        assert_eq!(symbols[1].kind, SymbolKind::Function);
+        assert_eq!(symbols[1].detail.as_deref(), Some("(a int, b int)"));
+        assert_eq!(symbols[1].selection_range.start.line, 1);
+    }
+
+    #[test]
+    fn test_statements_and_ctes() {
+        let source = r#"WITH recent AS (SELECT * FROM orders WHERE placed > now() - interval '1 day')
+SELECT user_id FROM recent;
+INSERT INTO audit_log (action) VALUES ('export');
+UPDATE "users" SET active = false WHERE id = 1;
+"#;
+        let symbols = parse(source);
+        let outline: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind, s.detail.as_deref())).collect();
+        assert_eq!(
+            outline,
+            vec![
+                ("SELECT", SymbolKind::Operator, Some("recent")),
+                ("INSERT", SymbolKind::Operator, Some("audit_log")),
+                ("UPDATE", SymbolKind::Operator, Some("users")),
+            ]
+        );
+        let ctes: Vec<_> = symbols[0].children.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(ctes, vec![("recent", SymbolKind::Interface)]);
+        assert_eq!(symbols[1].selection_range.start.line, 2);
+    }
+}
@@ -0,0 +1,209 @@
+//! Suppression comments for diagnostics
+//!
+//! Two directives are recognized in any comment style:
+//! - `logos-ignore-next-line [rule, ...]` silences diagnostics starting on
+//!   the following line
+//! - `logos: disable=rule[,rule]` on a line of its own silences the rules
+//!   until `logos: enable=rule` or the end of the file; after code, it only
+//!   covers its own line
+//!
+//! Without rule ids a directive covers every diagnostic.
+
+use std::collections::HashMap;
+
+use logos_core::Position;
+use crate::LanguageId;
+
+const IGNORE_NEXT_LINE: &str = "logos-ignore-next-line";
+const DISABLE: &str = "logos: disable";
+const ENABLE: &str = "logos: enable";
+
+/// Tokens that open a comment in the supported languages
+const COMMENT_OPENERS: &[&str] = &["//", "#", "--", "/*", "<!--", ";", "*"];
+
+/// Lines silenced by the suppression comments of a document
+#[derive(Debug, Clone, Default)]
+pub struct Suppressions {
+    rules: Vec<Suppression>,
+}
+
+#[derive(Debug, Clone)]
+struct Suppression {
+    /// 0-based lines, end exclusive
+    start: u32,
+    end: u32,
+    /// Rule ids covered; empty covers all
+    codes: Vec<String>,
+}
+
+impl Suppressions {
+    /// Collect the suppression comments of a source
+    pub fn scan(source: &str) -> Self {
+        let mut rules = Vec::new();
+        // Open `disable` blocks by rule id; `None` is the all-rules block
+        let mut open: HashMap<Option<String>, u32> = HashMap::new();
+        let mut line_count = 0;
+
+        for (index, text) in source.lines().enumerate() {
+            let line = index as u32;
+            line_count = line + 1;
+            let Some((directive, args, trailing)) = find_directive(text) else {
+                continue;
+            };
+            let codes = parse_codes(args);
+            match directive {
+                IGNORE_NEXT_LINE => rules.push(Suppression { start: line + 1, end: line + 2, codes }),
+                DISABLE if trailing => rules.push(Suppression { start: line, end: line + 1, codes }),
+                DISABLE => {
+                    let keys: Vec<Option<String>> = if codes.is_empty() {
+                        vec![None]
+                    } else {
+                        codes.into_iter().map(Some).collect()
+                    };
+                    for key in keys {
+                        open.entry(key).or_insert(line + 1);
+                    }
+                }
+                _ => {
+                    let closed: Vec<Option<String>> = if codes.is_empty() {
+                        open.keys().cloned().collect()
+                    } else {
+                        codes.into_iter().map(Some).collect()
+                    };
+                    for key in closed {
+                        if let Some(start) = open.remove(&key) {
+                            rules.push(Suppression { start, end: line, codes: key.into_iter().collect() });
+                        }
+                    }
+                }
+            }
+        }
+        for (key, start) in open {
+            rules.push(Suppression { start, end: line_count.max(start), codes: key.into_iter().collect() });
+        }
+        Self { rules }
+    }
+
+    /// Whether a diagnostic starting on `line` with rule id `code` is
+    /// silenced
+    pub fn is_suppressed(&self, line: u32, code: Option<&str>) -> bool {
+        self.rules.iter().any(|rule| {
+            (rule.start..rule.end).contains(&line)
+                && (rule.codes.is_empty() || code.is_some_and(|c| rule.codes.iter().any(|r| r == c)))
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// The directive of a comment on `text`, its arguments, and whether code
+/// precedes the comment
+fn find_directive(text: &str) -> Option<(&'static str, &str, bool)> {
+    for directive in [IGNORE_NEXT_LINE, DISABLE, ENABLE] {
+        let Some(at) = text.find(directive) else {
+            continue;
+        };
+        let before = text[..at].trim_end();
+        let Some(opener) = COMMENT_OPENERS.iter().find(|o| before.ends_with(*o)) else {
+            continue;
+        };
+        let trailing = !before[..before.len() - opener.len()].trim().is_empty();
+        let rest = &text[at + directive.len()..];
+        let args = match directive {
+            IGNORE_NEXT_LINE => rest,
+            _ => match rest.strip_prefix('=') {
+                Some(args) => args,
+                None if rest.trim_start().is_empty() || rest.starts_with(char::is_whitespace) => rest,
+                None => continue,
+            },
+        };
+        return Some((directive, args, trailing));
+    }
+    None
+}
+
+/// Rule ids separated by commas or spaces; comment terminators and prose
+/// are dropped
+fn parse_codes(args: &str) -> Vec<String> {
+    args.split(|c: char| c == ',' || c.is_whitespace())
+        .take_while(|token| !matches!(*token, "--" | "—"))
+        .filter(|token| {
+            !token.is_empty()
+                && token.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '/' | '.' | '@'))
+        })
+        .filter(|token| *token != "all")
+        .map(str::to_string)
+        .collect()
+}
+
+/// Insertion that silences `code` (or everything) on `line`: a
+/// `logos-ignore-next-line` comment above it, indented like it. `None` for
+/// languages without comments.
+pub fn suppression_edit(lang: LanguageId, source: &str, line: u32, code: Option<&str>) -> Option<(Position, String)> {
+    let body = match code {
+        Some(code) => format!("{} {}", IGNORE_NEXT_LINE, code),
+        None => IGNORE_NEXT_LINE.to_string(),
+    };
+    let comment = match lang {
+        LanguageId::Json => return None,
+        LanguageId::Python | LanguageId::Elixir | LanguageId::Bash | LanguageId::Dockerfile | LanguageId::Yaml | LanguageId::Toml => {
+            format!("# {}", body)
+        }
+        LanguageId::Sql => format!("-- {}", body),
+        LanguageId::Html | LanguageId::Markdown => format!("<!-- {} -->", body),
+        LanguageId::Css | LanguageId::Scss => format!("/* {} */", body),
+        _ => format!("// {}", body),
+    };
+    let target = source.lines().nth(line as usize).unwrap_or("");
+    let indent: String = target.chars().take_while(|c| c.is_whitespace()).collect();
+    Some((Position::new(line, 0), format!("{}{}\n", indent, comment)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_directives() {
+        let source = "\
+// logos-ignore-next-line unused-variable
+let a = 1;
+let b = 2; // logos: disable=shadowing
+# logos: disable=long-line, todo
+x
+<!-- logos: enable=todo -->
+y
+-- logos-ignore-next-line
+z
+";
+        let s = Suppressions::scan(source);
+        assert!(s.is_suppressed(1, Some("unused-variable")));
+        assert!(!s.is_suppressed(1, Some("shadowing")));
+        assert!(!s.is_suppressed(1, None));
+        assert!(s.is_suppressed(2, Some("shadowing")));
+        assert!(!s.is_suppressed(3, Some("shadowing")));
+        assert!(s.is_suppressed(4, Some("todo")));
+        assert!(!s.is_suppressed(6, Some("todo")));
+        assert!(s.is_suppressed(6, Some("long-line")));
+        assert!(s.is_suppressed(8, Some("anything")));
+        assert!(s.is_suppressed(8, None));
+        assert!(!s.is_suppressed(7, None));
+    }
+
+    #[test]
+    fn test_directive_needs_a_comment() {
+        let s = Suppressions::scan("let s = \"logos-ignore-next-line\";\nbad();\n");
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn test_suppression_edit() {
+        let source = "def f():\n    x = 1\n";
+        let (position, text) = suppression_edit(LanguageId::Python, source, 1, Some("unused")).unwrap();
+        assert_eq!((position.line, position.column), (1, 0));
+        assert_eq!(text, "    # logos-ignore-next-line unused\n");
+        assert!(suppression_edit(LanguageId::Json, "{}", 0, None).is_none());
+    }
+}
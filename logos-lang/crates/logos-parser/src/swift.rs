@@ -0,0 +1,246 @@
+//! Swift-specific parsing and symbol extraction
+
+use logos_core::{Symbol, SymbolKind};
+use tree_sitter::{Node, Tree};
+use crate::node_to_range;
+
+/// Extract symbols from a Swift AST
+pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    let root = tree.root_node();
+    extract_symbols_from_node(&root, source, &mut symbols, false);
+    symbols
+}
+
+fn extract_symbols_from_node(node: &Node, source: &str, symbols: &mut Vec<Symbol>, in_type: bool) {
+    match node.kind() {
+        // class, struct, enum, actor and extension all share one node kind
+        "class_declaration" => {
+            if let Some(name_node) = node.child_by_field_name("name") {
+                let name = get_node_text(&name_node, source);
+                let declaration_kind = node
+                    .child_by_field_name("declaration_kind")
+                    .map(|k| k.kind())
+                    .unwrap_or("class");
+                let kind = match declaration_kind {
+                    "struct" => SymbolKind::Struct,
+                    "enum" => SymbolKind::Enum,
+                    _ => SymbolKind::Class,
+                };
+                let mut symbol = Symbol::new(name, kind, node_to_range(node), node_to_range(&name_node));
+                if matches!(declaration_kind, "extension" | "actor") {
+                    symbol.detail = Some(declaration_kind.to_string());
+                }
+
+                if let Some(body) = node.child_by_field_name("body") {
+                    let mut children = Vec::new();
+                    extract_type_members(&body, source, &mut children);
+                    symbol.children = children;
+                }
+
+                symbols.push(symbol);
+            }
+        }
+        "protocol_declaration" => {
+            if let Some(name_node) = node.child_by_field_name("name") {
+                let name = get_node_text(&name_node, source);
+                let mut symbol = Symbol::new(name, SymbolKind::Interface, node_to_range(node), node_to_range(&name_node));
+
+                if let Some(body) = node.child_by_field_name("body") {
+                    let mut children = Vec::new();
+                    extract_type_members(&body, source, &mut children);
+                    symbol.children = children;
+                }
+
+                symbols.push(symbol);
+            }
+        }
+        "function_declaration" | "protocol_function_declaration" => {
+            if let Some(name_node) = node.child_by_field_name("name") {
+                let name = get_node_text(&name_node, source);
+                let kind = if in_type { SymbolKind::Method } else { SymbolKind::Function };
+                let mut symbol = Symbol::new(name, kind, node_to_range(node), node_to_range(&name_node));
+                symbol.detail = parameter_list(node, source);
+                symbols.push(symbol);
+            }
+        }
+        "init_declaration" => {
+            let keyword = node.child_by_field_name("name");
+            let selection = keyword.map(|k| node_to_range(&k)).unwrap_or_else(|| node_to_range(node));
+            let mut symbol = Symbol::new("init".to_string(), SymbolKind::Constructor, node_to_range(node), selection);
+            symbol.detail = parameter_list(node, source);
+            symbols.push(symbol);
+        }
+        "property_declaration" | "protocol_property_declaration" => {
+            let kind = if in_type { SymbolKind::Property } else { SymbolKind::Variable };
+            let mut cursor = node.walk();
+            for pattern in node.children_by_field_name("name", &mut cursor) {
+                if let Some(name_node) = pattern.child_by_field_name("bound_identifier") {
+                    let name = get_node_text(&name_node, source);
+                    symbols.push(Symbol::new(name, kind, node_to_range(node), node_to_range(&name_node)));
+                }
+            }
+        }
+        // Don't descend into function bodies or closures for symbols
+        "function_body" | "lambda_literal" | "computed_property" => {}
+        _ => {
+            for i in 0..node.named_child_count() {
+                if let Some(child) = node.named_child(i) {
+                    extract_symbols_from_node(&child, source, symbols, in_type);
+                }
+            }
+        }
+    }
+}
+
+fn extract_type_members(node: &Node, source: &str, symbols: &mut Vec<Symbol>) {
+    for i in 0..node.named_child_count() {
+        if let Some(child) = node.named_child(i) {
+            if child.kind() == "enum_entry" {
+                // `case north, south` declares one member per name
+                let mut cursor = child.walk();
+                for name_node in child.children_by_field_name("name", &mut cursor) {
+                    let name = get_node_text(&name_node, source);
+                    symbols.push(Symbol::new(
+                        name,
+                        SymbolKind::EnumMember,
+                        node_to_range(&child),
+                        node_to_range(&name_node),
+                    ));
+                }
+            } else {
+                extract_symbols_from_node(&child, source, symbols, true);
+            }
+        }
+    }
+}
+
+/// The parenthesized parameter list of a function or initializer, e.g. `(kind k: String)`
+fn parameter_list(node: &Node, source: &str) -> Option<String> {
+    let children: Vec<Node> = (0..node.child_count()).filter_map(|i| node.child(i)).collect();
+    let open = children.iter().position(|c| c.kind() == "(")?;
+    let last_param = children.iter().rposition(|c| c.kind() == "parameter").unwrap_or(open);
+    let close = children[last_param..].iter().find(|c| c.kind() == ")")?;
+    Some(source[children[open].start_byte()..close.end_byte()].to_string())
+}
+
+fn get_node_text(node: &Node, source: &str) -> String {
+    source[node.byte_range()].to_string()
+}
+
+/// Get Swift keywords
+pub fn get_keywords() -> &'static [&'static str] {
+    &[
+        "actor", "any", "as", "associatedtype", "async", "await", "break",
+        "case", "catch", "class", "continue", "default", "defer", "deinit",
+        "do", "else", "enum", "extension", "fallthrough", "false", "fileprivate",
+        "final", "for", "func", "guard", "if", "import", "in", "indirect",
+        "init", "inout", "internal", "is", "lazy", "let", "mutating", "nil",
+        "nonmutating", "open", "operator", "override", "precedencegroup",
+        "private", "protocol", "public", "repeat", "required", "rethrows",
+        "return", "self", "Self", "some", "static", "struct", "subscript",
+        "super", "switch", "throw", "throws", "true", "try", "typealias",
+        "var", "weak", "where", "while",
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{LanguageId, LanguageParser};
+
+    fn parse(source: &str) -> Vec<Symbol> {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Swift).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        extract_symbols(&tree, source)
+    }
+
+    #[test]
+    fn test_types_and_members() {
+        let symbols = parse(
+            r#"
+protocol Shape {
+    func area() -> Double
+    var name: String { get }
+}
+class Animal: Shape {
+    let name: String
+    init(name: String) { self.name = name }
+    func area() -> Double { let local = 1.0; return local }
+    static func make(kind k: String) -> Animal { Animal(name: k) }
+}
+struct Point { var x: Double; var y: Double }
+enum Direction {
+    case north, south
+    case east(Int)
+}
+"#,
+        );
+
+        let kinds: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("Shape", SymbolKind::Interface),
+                ("Animal", SymbolKind::Class),
+                ("Point", SymbolKind::Struct),
+                ("Direction", SymbolKind::Enum),
+            ]
+        );
+
+        let shape: Vec<_> = symbols[0].children.iter().map(|c| (c.name.as_str(), c.kind)).collect();
+        assert_eq!(shape, vec![("area", SymbolKind::Method), ("name", SymbolKind::Property)]);
+
+        let animal: Vec<_> = symbols[1].children.iter().map(|c| (c.name.as_str(), c.kind)).collect();
+        assert_eq!(
+            animal,
+            vec![
+                ("name", SymbolKind::Property),
+                ("init", SymbolKind::Constructor),
+                ("area", SymbolKind::Method),
+                ("make", SymbolKind::Method),
+            ]
+        );
+        assert_eq!(symbols[1].children[1].detail.as_deref(), Some("(name: String)"));
+        assert_eq!(symbols[1].children[3].detail.as_deref(), Some("(kind k: String)"));
+
+        assert_eq!(symbols[2].children.len(), 2);
+        let cases: Vec<_> = symbols[3].children.iter().map(|c| (c.name.as_str(), c.kind)).collect();
+        assert_eq!(
+            cases,
+            vec![
+                ("north", SymbolKind::EnumMember),
+                ("south", SymbolKind::EnumMember),
+                ("east", SymbolKind::EnumMember),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_extensions_and_top_level() {
+        let symbols = parse(
+            r#"
+extension Point: Equatable {
+    func distance(to other: Point) -> Double { 0 }
+}
+func topLevel(a: Int) -> Int { a }
+let answer = 42
+"#,
+        );
+
+        let kinds: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("Point", SymbolKind::Class),
+                ("topLevel", SymbolKind::Function),
+                ("answer", SymbolKind::Variable),
+            ]
+        );
+        assert_eq!(symbols[0].detail.as_deref(), Some("extension"));
+        assert_eq!(symbols[0].children[0].name, "distance");
+        assert_eq!(symbols[0].children[0].kind, SymbolKind::Method);
+        assert_eq!(symbols[1].detail.as_deref(), Some("(a: Int)"));
+    }
+}
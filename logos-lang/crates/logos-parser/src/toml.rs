@@ -0,0 +1,210 @@
+//! TOML parsing: table and key outline, parse error messages
+
+use logos_core::{Position, Range, Symbol, SymbolKind};
+use tree_sitter::{Node, Tree};
+use crate::node_to_range;
+
+/// Extract symbols from a TOML AST.
+///
+/// Keys before the first table header are top-level symbols. Each `[table]`
+/// becomes an object named by its header, with its keys as children, and
+/// each `[[array]]` entry an element named by header and index (`bin[0]`,
+/// `bin[1]`, ...). Key kinds follow their values; inline tables list their
+/// keys as children. Dotted keys keep their full text (`version.workspace`).
+pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
+    let root = tree.root_node();
+    let mut symbols = Vec::new();
+    let mut array_counts: Vec<(String, usize)> = Vec::new();
+
+    for i in 0..root.named_child_count() {
+        let Some(child) = root.named_child(i) else {
+            continue;
+        };
+        match child.kind() {
+            "pair" => symbols.extend(pair_symbol(&child, source)),
+            "table" | "table_array_element" => {
+                let Some(header) = header_key(&child) else {
+                    continue;
+                };
+                let key = source[header.byte_range()].to_string();
+                let (name, kind) = if child.kind() == "table" {
+                    (key, SymbolKind::Object)
+                } else {
+                    let index = match array_counts.iter_mut().find(|(k, _)| *k == key) {
+                        Some((_, count)) => {
+                            *count += 1;
+                            *count - 1
+                        }
+                        None => {
+                            array_counts.push((key.clone(), 1));
+                            0
+                        }
+                    };
+                    (format!("{}[{}]", key, index), SymbolKind::Array)
+                };
+                let mut symbol = Symbol::new(name, kind, node_to_range(&child), node_to_range(&header));
+                symbol.children = pairs(&child, source);
+                symbols.push(symbol);
+            }
+            _ => {}
+        }
+    }
+    symbols
+}
+
+/// The key of a table header: its first named child
+fn header_key<'a>(table: &Node<'a>) -> Option<Node<'a>> {
+    table
+        .named_child(0)
+        .filter(|n| matches!(n.kind(), "bare_key" | "quoted_key" | "dotted_key"))
+}
+
+fn pairs(node: &Node, source: &str) -> Vec<Symbol> {
+    (0..node.named_child_count())
+        .filter_map(|i| node.named_child(i))
+        .filter(|n| n.kind() == "pair")
+        .filter_map(|pair| pair_symbol(&pair, source))
+        .collect()
+}
+
+fn pair_symbol(pair: &Node, source: &str) -> Option<Symbol> {
+    let key = pair.named_child(0)?;
+    let value = pair.named_child(1);
+    let kind = value.as_ref().map_or(SymbolKind::Null, value_kind);
+    let mut symbol = Symbol::new(key_text(&key, source), kind, node_to_range(pair), node_to_range(&key));
+    if let Some(value) = value.filter(|v| v.kind() == "inline_table") {
+        symbol.children = pairs(&value, source);
+    }
+    Some(symbol)
+}
+
+/// Key text with the quotes of a lone quoted key removed
+fn key_text(key: &Node, source: &str) -> String {
+    let text = &source[key.byte_range()];
+    if key.kind() == "quoted_key" {
+        for quote in ['"', '\''] {
+            if let Some(inner) = text.strip_prefix(quote).and_then(|t| t.strip_suffix(quote)) {
+                return inner.to_string();
+            }
+        }
+    }
+    text.to_string()
+}
+
+fn value_kind(value: &Node) -> SymbolKind {
+    match value.kind() {
+        "inline_table" => SymbolKind::Object,
+        "array" => SymbolKind::Array,
+        "integer" | "float" => SymbolKind::Number,
+        "boolean" => SymbolKind::Boolean,
+        _ => SymbolKind::String,
+    }
+}
+
+/// A specific message for common TOML mistakes behind an error node: a key
+/// with nothing after `=`, and commas outside arrays and inline tables.
+/// `None` leaves the generic message.
+pub fn describe_error(node: &Node, source: &str) -> Option<(Range, String)> {
+    if node.is_missing() {
+        return None;
+    }
+    let text = source[node.byte_range()].trim_end();
+    let key = node.named_child(0).filter(|n| matches!(n.kind(), "bare_key" | "quoted_key" | "dotted_key"))?;
+
+    if node.named_child_count() == 1 && text.ends_with('=') {
+        let message = format!("Missing value for `{}`", &source[key.byte_range()]);
+        return Some((node_to_range(&key), message));
+    }
+    if text.ends_with(',') {
+        let comma = node.start_byte() + text.len() - 1;
+        let range = Range::new(position_at(source, comma), position_at(source, comma + 1));
+        return Some((range, "Commas only separate array and inline table items".to_string()));
+    }
+    None
+}
+
+fn position_at(source: &str, offset: usize) -> Position {
+    let before = &source[..offset];
+    let line = before.matches('\n').count();
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    Position::new(line as u32, (offset - line_start) as u32)
+}
+
+/// Get TOML literals
+pub fn get_keywords() -> &'static [&'static str] {
+    &["true", "false", "inf", "nan"]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{extract_parse_errors, LanguageId, LanguageParser};
+
+    fn parse(source: &str) -> Tree {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Toml).unwrap();
+        parser.parse(source, None).unwrap()
+    }
+
+    #[test]
+    fn test_cargo_manifest_outline() {
+        let source = r#"edition = "2021"
+
+[package]
+name = "x"
+version.workspace = true
+"quoted key" = 1
+
+[dependencies]
+serde = { version = "1", features = ["derive"] }
+
+[[bin]]
+name = "a"
+
+[[bin]]
+name = "b"
+
+[target.'cfg(unix)'.dependencies]
+libc = "0.2"
+"#;
+        let symbols = extract_symbols(&parse(source), source);
+
+        let names: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("edition", SymbolKind::String),
+                ("package", SymbolKind::Object),
+                ("dependencies", SymbolKind::Object),
+                ("bin[0]", SymbolKind::Array),
+                ("bin[1]", SymbolKind::Array),
+                ("target.'cfg(unix)'.dependencies", SymbolKind::Object),
+            ]
+        );
+        assert_eq!(symbols[1].selection_range, Range::from_coords(2, 1, 2, 8));
+        let package: Vec<_> = symbols[1].children.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            package,
+            vec![
+                ("name", SymbolKind::String),
+                ("version.workspace", SymbolKind::Boolean),
+                ("quoted key", SymbolKind::Number),
+            ]
+        );
+        let serde = &symbols[2].children[0];
+        assert_eq!(serde.kind, SymbolKind::Object);
+        let serde_keys: Vec<_> = serde.children.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(serde_keys, vec!["version", "features"]);
+    }
+
+    #[test]
+    fn test_parse_error_messages() {
+        let source = "[a]\nx = \ny = 1,\n";
+        let errors: Vec<_> = extract_parse_errors(&parse(source), source)
+            .into_iter()
+            .map(|d| (d.message, d.range))
+            .collect();
+        assert_eq!(errors[0], ("Missing value for `x`".to_string(), Range::from_coords(1, 0, 1, 1)));
+        assert_eq!(errors[1], ("Commas only separate array and inline table items".to_string(), Range::from_coords(2, 5, 2, 6)));
+    }
+}
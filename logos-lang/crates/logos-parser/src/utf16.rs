@@ -0,0 +1,129 @@
+//! Tree-sitter columns to protocol columns
+//!
+//! Tree-sitter points count bytes from the start of the line, while
+//! [`Position`] columns count UTF-16 code units. The two agree on ASCII
+//! only: `é` is two bytes and one unit, `名` three bytes and one unit, and
+//! `🦀`, outside the Basic Multilingual Plane, four bytes and two units.
+//! Extractors build ranges from tree-sitter points, so their output is
+//! converted once per source through [`Utf16Columns`].
+
+use logos_core::{Diagnostic, Position, Range, Symbol};
+use tree_sitter::Point;
+
+/// Converts between byte and UTF-16 columns of one source
+pub struct Utf16Columns<'a> {
+    source: &'a str,
+    /// Byte offset of each line start; empty for ASCII sources, where the
+    /// columns agree
+    line_starts: Vec<usize>,
+}
+
+impl<'a> Utf16Columns<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let line_starts = if source.is_ascii() {
+            Vec::new()
+        } else {
+            std::iter::once(0).chain(source.match_indices('\n').map(|(i, _)| i + 1)).collect()
+        };
+        Self { source, line_starts }
+    }
+
+    /// Text of line `row` without its line break, from the start of the line
+    fn line(&self, row: usize) -> &'a str {
+        let Some(&start) = self.line_starts.get(row) else {
+            return "";
+        };
+        let end = self.line_starts.get(row + 1).map_or(self.source.len(), |next| next - 1);
+        &self.source[start..end]
+    }
+
+    /// Position of a tree-sitter point
+    pub fn position(&self, point: Point) -> Position {
+        if self.line_starts.is_empty() {
+            return Position::new(point.row as u32, point.column as u32);
+        }
+        let column = self
+            .line(point.row)
+            .char_indices()
+            .take_while(|(i, _)| *i < point.column)
+            .map(|(_, c)| c.len_utf16() as u32)
+            .sum();
+        Position::new(point.row as u32, column)
+    }
+
+    /// Tree-sitter point of a position. A column inside a surrogate pair
+    /// resolves to the start of its character.
+    pub fn point(&self, position: Position) -> Point {
+        let row = position.line as usize;
+        if self.line_starts.is_empty() {
+            return Point::new(row, position.column as usize);
+        }
+        let mut units = 0;
+        for (i, c) in self.line(row).char_indices() {
+            units += c.len_utf16() as u32;
+            if units > position.column {
+                return Point::new(row, i);
+            }
+        }
+        Point::new(row, self.line(row).len())
+    }
+
+    /// Rewrite a position holding a byte column
+    pub fn convert_position(&self, position: &mut Position) {
+        if !self.line_starts.is_empty() {
+            *position = self.position(Point::new(position.line as usize, position.column as usize));
+        }
+    }
+
+    pub fn convert_range(&self, range: &mut Range) {
+        self.convert_position(&mut range.start);
+        self.convert_position(&mut range.end);
+    }
+
+    /// Rewrite symbols built from tree-sitter points, children included
+    pub fn convert_symbols(&self, symbols: &mut [Symbol]) {
+        if self.line_starts.is_empty() {
+            return;
+        }
+        for symbol in symbols {
+            self.convert_range(&mut symbol.range);
+            self.convert_range(&mut symbol.selection_range);
+            self.convert_symbols(&mut symbol.children);
+        }
+    }
+
+    pub fn convert_diagnostics(&self, diagnostics: &mut [Diagnostic]) {
+        if self.line_starts.is_empty() {
+            return;
+        }
+        for diagnostic in diagnostics {
+            self.convert_range(&mut diagnostic.range);
+            for related in &mut diagnostic.related_information {
+                self.convert_range(&mut related.range);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_columns_of_wide_characters() {
+        let source = "x = 1\nconst 名前 = \"🦀\"; let café = 2;\n";
+        let columns = Utf16Columns::new(source);
+
+        // `名前` is 6 bytes and 2 units
+        assert_eq!(columns.position(Point::new(1, 12)), Position::new(1, 8));
+        // Past `"🦀"`: 4 bytes and 2 units for the crab
+        assert_eq!(columns.position(Point::new(1, 20)), Position::new(1, 14));
+        assert_eq!(columns.position(Point::new(1, 32)), Position::new(1, 25));
+        assert_eq!(columns.position(Point::new(0, 3)), Position::new(0, 3));
+
+        assert_eq!(columns.point(Position::new(1, 14)), Point::new(1, 20));
+        // Inside the surrogate pair of the crab
+        assert_eq!(columns.point(Position::new(1, 13)), Point::new(1, 16));
+        assert_eq!(columns.point(Position::new(1, 99)), Point::new(1, 37));
+    }
+}
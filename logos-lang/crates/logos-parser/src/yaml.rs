@@ -0,0 +1,335 @@
+//! YAML parsing: key outline, anchors and indentation errors
+
+use logos_core::{Position, Range, Symbol, SymbolKind};
+use tree_sitter::{Node, Tree};
+use crate::node_to_range;
+
+/// Extract symbols from a YAML AST.
+///
+/// Every mapping key becomes a symbol whose kind follows its value, with the
+/// keys of nested mappings as children. Mappings and sequences inside
+/// sequences are listed by index (`[0]`, `[1]`, ...). Anchors (`&name`) are
+/// shown as the detail of the node they label, aliases (`*name`) as the
+/// detail of the key using them. A stream of several documents gets one
+/// namespace per document.
+pub fn extract_symbols(tree: &Tree, source: &str) -> Vec<Symbol> {
+    let root = tree.root_node();
+    let documents: Vec<Node> = (0..root.named_child_count())
+        .filter_map(|i| root.named_child(i))
+        .filter(|n| n.kind() == "document" && n.named_child_count() > 0)
+        .collect();
+
+    if documents.len() == 1 {
+        return document_members(&documents[0], source);
+    }
+    documents
+        .iter()
+        .enumerate()
+        .map(|(i, document)| {
+            let range = node_to_range(document);
+            let mut symbol = Symbol::new(format!("Document {}", i + 1), SymbolKind::Namespace, range, range);
+            symbol.children = document_members(document, source);
+            symbol
+        })
+        .collect()
+}
+
+fn document_members(document: &Node, source: &str) -> Vec<Symbol> {
+    (0..document.named_child_count())
+        .filter_map(|i| document.named_child(i))
+        .flat_map(|node| members(&node, source))
+        .collect()
+}
+
+/// Symbols for the members of a node; scalars have none
+fn members(node: &Node, source: &str) -> Vec<Symbol> {
+    let Some(content) = content(node) else {
+        return Vec::new();
+    };
+    let mut symbols = Vec::new();
+    match content.kind() {
+        "block_mapping" | "flow_mapping" => {
+            for i in 0..content.named_child_count() {
+                let Some(pair) = content
+                    .named_child(i)
+                    .filter(|n| matches!(n.kind(), "block_mapping_pair" | "flow_pair"))
+                else {
+                    continue;
+                };
+                let Some(key) = pair.child_by_field_name("key") else {
+                    continue;
+                };
+                let name = unquote(source[key.byte_range()].trim());
+                // `<<: *base` merges another mapping rather than naming a key
+                if name == "<<" {
+                    continue;
+                }
+                let value = pair.child_by_field_name("value");
+                let kind = value.as_ref().map_or(SymbolKind::Null, value_kind);
+                let mut symbol = Symbol::new(name, kind, node_to_range(&pair), node_to_range(&key));
+                if let Some(value) = value {
+                    symbol.detail = label(&value, source);
+                    symbol.children = members(&value, source);
+                }
+                symbols.push(symbol);
+            }
+        }
+        "block_sequence" | "flow_sequence" => {
+            let items = (0..content.named_child_count()).filter_map(|i| content.named_child(i));
+            for (index, item) in items.filter(|n| n.kind() != "comment").enumerate() {
+                // Block items wrap their node in a `block_sequence_item`
+                let item = if item.kind() == "block_sequence_item" {
+                    match item.named_child(0) {
+                        Some(node) => node,
+                        None => continue,
+                    }
+                } else {
+                    item
+                };
+                let kind = value_kind(&item);
+                if !matches!(kind, SymbolKind::Object | SymbolKind::Array) {
+                    continue;
+                }
+                let range = node_to_range(&item);
+                let mut symbol = Symbol::new(format!("[{}]", index), kind, range, range);
+                symbol.detail = label(&item, source);
+                symbol.children = members(&item, source);
+                symbols.push(symbol);
+            }
+        }
+        _ => {}
+    }
+    symbols
+}
+
+/// The value inside a `block_node`/`flow_node`, past any anchor and tag
+fn content<'a>(node: &Node<'a>) -> Option<Node<'a>> {
+    if !matches!(node.kind(), "block_node" | "flow_node") {
+        return Some(*node);
+    }
+    (0..node.named_child_count())
+        .filter_map(|i| node.named_child(i))
+        .find(|child| !matches!(child.kind(), "anchor" | "tag" | "comment"))
+}
+
+/// `&anchor` for a labelled node, `*alias` for an alias
+fn label(node: &Node, source: &str) -> Option<String> {
+    let children: Vec<Node> = (0..node.named_child_count()).filter_map(|i| node.named_child(i)).collect();
+    children
+        .iter()
+        .find(|child| child.kind() == "anchor")
+        .or_else(|| children.iter().find(|child| child.kind() == "alias"))
+        .map(|child| source[child.byte_range()].to_string())
+}
+
+fn value_kind(node: &Node) -> SymbolKind {
+    let Some(content) = content(node) else {
+        return SymbolKind::Null;
+    };
+    match content.kind() {
+        "block_mapping" | "flow_mapping" => SymbolKind::Object,
+        "block_sequence" | "flow_sequence" => SymbolKind::Array,
+        "alias" => SymbolKind::Key,
+        "plain_scalar" => match content.named_child(0).map(|n| n.kind()) {
+            Some("integer_scalar" | "float_scalar") => SymbolKind::Number,
+            Some("boolean_scalar") => SymbolKind::Boolean,
+            Some("null_scalar") => SymbolKind::Null,
+            _ => SymbolKind::String,
+        },
+        _ => SymbolKind::String,
+    }
+}
+
+fn unquote(text: &str) -> String {
+    for quote in ['"', '\''] {
+        if let Some(inner) = text.strip_prefix(quote).and_then(|t| t.strip_suffix(quote)) {
+            return inner.to_string();
+        }
+    }
+    text.to_string()
+}
+
+/// A specific message for indentation mistakes behind an error node: tabs,
+/// a line indented under a scalar, or a dedent to a column no enclosing
+/// block uses. `None` leaves the generic message.
+pub fn describe_error(node: &Node, source: &str) -> Option<(Range, String)> {
+    if node.is_missing() {
+        return None;
+    }
+    // Error recovery often stops short of the offending line, so look
+    // anywhere from the start of the error onwards
+    let first_row = node.start_position().row;
+
+    // Columns of the enclosing blocks, innermost last
+    let mut levels: Vec<usize> = Vec::new();
+    // Whether the previous line leaves a nested block open (`key:`, `-`)
+    let mut opens_block = true;
+    // Column of the line holding a `|`/`>` scalar while inside its text
+    let mut block_scalar: Option<usize> = None;
+
+    for (row, line) in source.lines().enumerate() {
+        let text = line.trim_start_matches(' ');
+        let indent = line.len() - text.len();
+        if text.trim().is_empty() || text.starts_with('#') {
+            continue;
+        }
+        if let Some(column) = block_scalar {
+            if indent > column {
+                continue;
+            }
+            block_scalar = None;
+        }
+        if text.starts_with("---") || text.starts_with("...") {
+            levels.clear();
+            opens_block = true;
+            continue;
+        }
+
+        let problem = if text.starts_with('\t') {
+            Some("Tabs can't be used for indentation in YAML".to_string())
+        } else if levels.last().is_none_or(|&top| indent > top) {
+            (!opens_block).then(|| "Unexpected indentation; the line above doesn't start a nested block".to_string())
+        } else {
+            while levels.last().is_some_and(|&top| top > indent) {
+                levels.pop();
+            }
+            (levels.last() != Some(&indent)).then(|| {
+                format!("Bad indentation: {} spaces doesn't line up with any enclosing block", indent)
+            })
+        };
+        if let Some(message) = problem {
+            if row >= first_row {
+                let range = Range::new(
+                    Position::new(row as u32, indent as u32),
+                    Position::new(row as u32, line.trim_end().len() as u32),
+                );
+                return Some((range, message));
+            }
+        }
+        if levels.last() != Some(&indent) {
+            levels.push(indent);
+        }
+
+        // A sequence item's content starts a block of its own
+        let mut entry = text;
+        let mut column = indent;
+        while let Some(rest) = entry.strip_prefix('-').filter(|r| r.is_empty() || r.starts_with(' ')) {
+            let content = rest.trim_start_matches(' ');
+            column += entry.len() - content.len();
+            entry = content;
+            if !entry.is_empty() {
+                levels.push(column);
+            }
+        }
+        let value = entry_value(entry);
+        opens_block = value.is_empty();
+        if value.starts_with(['|', '>']) {
+            block_scalar = Some(indent);
+        }
+    }
+    None
+}
+
+/// What follows the key of a `key: value` entry (or the whole entry when
+/// it is a bare value), without its comment, anchor or tag
+fn entry_value(entry: &str) -> &str {
+    let value = match entry.find(": ") {
+        Some(i) if !entry.starts_with(['"', '\'', '{', '[']) => &entry[i + 2..],
+        _ => match entry.strip_suffix(':') {
+            Some(_) => "",
+            None => entry,
+        },
+    };
+    let value = value.split(" #").next().unwrap_or("").trim();
+    value
+        .split_whitespace()
+        .find(|token| !token.starts_with(['&', '!']))
+        .map_or("", |first| &value[value.find(first).unwrap_or(0)..])
+}
+
+/// Get YAML literals and common directives
+pub fn get_keywords() -> &'static [&'static str] {
+    &["true", "false", "null", "yes", "no", "on", "off", "---", "...", "<<"]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{extract_parse_errors, LanguageId, LanguageParser};
+
+    fn parse(source: &str) -> Tree {
+        let mut parser = LanguageParser::new();
+        parser.set_language(LanguageId::Yaml).unwrap();
+        parser.parse(source, None).unwrap()
+    }
+
+    #[test]
+    fn test_outline_with_anchors() {
+        let source = r#"name: CI
+defaults: &defaults
+  runs-on: ubuntu-latest
+jobs:
+  build:
+    <<: *defaults
+    timeout: 10
+    steps:
+      - uses: actions/checkout@v4
+      - name: Test
+        run: |
+          cargo test
+"quoted key": {a: 1}
+"#;
+        let symbols = extract_symbols(&parse(source), source);
+
+        let names: Vec<_> = symbols.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(
+            names,
+            vec![
+                ("name", SymbolKind::String),
+                ("defaults", SymbolKind::Object),
+                ("jobs", SymbolKind::Object),
+                ("quoted key", SymbolKind::Object),
+            ]
+        );
+        assert_eq!(symbols[1].detail.as_deref(), Some("&defaults"));
+        assert_eq!(symbols[1].selection_range, Range::from_coords(1, 0, 1, 8));
+
+        let build = &symbols[2].children[0];
+        let keys: Vec<_> = build.children.iter().map(|s| (s.name.as_str(), s.kind)).collect();
+        assert_eq!(keys, vec![("timeout", SymbolKind::Number), ("steps", SymbolKind::Array)]);
+        let steps = &build.children[1].children;
+        assert_eq!(steps[1].name, "[1]");
+        assert_eq!(steps[1].children[1].name, "run");
+        assert_eq!(symbols[3].children[0].name, "a");
+
+        let stream = "a: 1\n---\nb: 2\n";
+        let documents = extract_symbols(&parse(stream), stream);
+        assert_eq!(documents[1].name, "Document 2");
+        assert_eq!(documents[1].children[0].name, "b");
+    }
+
+    #[test]
+    fn test_indentation_errors() {
+        let messages = |source: &str| -> Vec<(String, Range)> {
+            extract_parse_errors(&parse(source), source)
+                .into_iter()
+                .map(|d| (d.message, d.range))
+                .collect()
+        };
+
+        let errors = messages("jobs:\n  build:\n    steps: 1\n   bad: 2\n  ok: 3\n");
+        assert_eq!(
+            errors[0],
+            (
+                "Bad indentation: 3 spaces doesn't line up with any enclosing block".to_string(),
+                Range::from_coords(3, 3, 3, 9)
+            )
+        );
+
+        let errors = messages("a: 1\n  b: 2\n");
+        assert_eq!(errors[0].0, "Unexpected indentation; the line above doesn't start a nested block");
+        assert_eq!(errors[0].1.start.line, 1);
+
+        assert!(messages("a:\n  - x: 1\n    y: |\n       text\n  - z\nb: 2\n").is_empty());
+    }
+}
@@ -0,0 +1,31 @@
+//! Logos Python - scripting analyses from Python
+//!
+//! Exposes the engine the IDE runs on as the `logos` Python module, for
+//! one-off repository analyses:
+//!
+//! ```python
+//! import logos
+//!
+//! ws = logos.Workspace("path/to/repo")
+//! for cls in ws.symbols(kind="class"):
+//!     print(cls.path, cls.line, cls.name, len(ws.subtypes(cls.name)))
+//!
+//! # Structural queries are tree-sitter patterns
+//! for m in ws.query("python", "(call function: (identifier) @callee)"):
+//!     print(m.path, m.captures["callee"].text)
+//!
+//! # Documents that aren't on disk go through an Analysis
+//! a = logos.Analysis()
+//! a.open_document("untitled:1", "yaml", "a: 1\n  b: 2\n")
+//! print(a.diagnostics("untitled:1"))
+//! ```
+//!
+//! The bindings are behind the `python` feature so the rest of the
+//! workspace builds without a Python toolchain; wheels are built with
+//! maturin (`pyproject.toml`). [`Workspace`] is the same API for Rust.
+
+pub mod workspace;
+#[cfg(feature = "python")]
+mod python;
+
+pub use workspace::{FileLocation, QueryCapture, QueryMatch, SymbolInfo, Workspace};
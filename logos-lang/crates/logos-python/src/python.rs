@@ -0,0 +1,238 @@
+//! PyO3 classes of the `logos` module
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use logos_analysis::AnalysisHost;
+use logos_core::Range;
+use pyo3::exceptions::{PyOSError, PyValueError};
+use pyo3::prelude::*;
+
+use crate::workspace::{FileLocation, QueryMatch, SymbolInfo, Workspace};
+
+/// A symbol; `line`/`column` locate its name, 0-based
+#[pyclass(name = "Symbol", module = "logos", frozen, get_all)]
+#[derive(Clone)]
+struct PySymbol {
+    name: String,
+    kind: String,
+    qualified_name: String,
+    path: PathBuf,
+    line: u32,
+    column: u32,
+    end_line: u32,
+    exported: bool,
+    documentation: Option<String>,
+}
+
+#[pymethods]
+impl PySymbol {
+    fn __repr__(&self) -> String {
+        format!("<Symbol {} {} at {}:{}>", self.kind, self.name, self.path.display(), self.line + 1)
+    }
+}
+
+impl From<SymbolInfo> for PySymbol {
+    fn from(symbol: SymbolInfo) -> Self {
+        Self {
+            name: symbol.name,
+            kind: symbol.kind,
+            qualified_name: symbol.qualified_name,
+            path: symbol.path,
+            line: symbol.selection_range.start.line,
+            column: symbol.selection_range.start.column,
+            end_line: symbol.range.end.line,
+            exported: symbol.exported,
+            documentation: symbol.documentation,
+        }
+    }
+}
+
+/// A location in a file, 0-based
+#[pyclass(name = "Location", module = "logos", frozen, get_all)]
+#[derive(Clone)]
+struct PyLocation {
+    path: PathBuf,
+    line: u32,
+    column: u32,
+    end_line: u32,
+    end_column: u32,
+}
+
+impl PyLocation {
+    fn new(path: PathBuf, range: Range) -> Self {
+        Self {
+            path,
+            line: range.start.line,
+            column: range.start.column,
+            end_line: range.end.line,
+            end_column: range.end.column,
+        }
+    }
+}
+
+#[pymethods]
+impl PyLocation {
+    fn __repr__(&self) -> String {
+        format!("<Location {}:{}:{}>", self.path.display(), self.line + 1, self.column + 1)
+    }
+}
+
+impl From<FileLocation> for PyLocation {
+    fn from(location: FileLocation) -> Self {
+        Self::new(location.path, location.range)
+    }
+}
+
+/// A captured node of a query match
+#[pyclass(name = "Capture", module = "logos", frozen, get_all)]
+#[derive(Clone)]
+struct PyCapture {
+    text: String,
+    location: PyLocation,
+}
+
+/// One match of a structural query; `captures` maps capture names to nodes
+#[pyclass(name = "Match", module = "logos", frozen, get_all)]
+struct PyMatch {
+    path: PathBuf,
+    pattern: usize,
+    captures: HashMap<String, PyCapture>,
+}
+
+impl From<QueryMatch> for PyMatch {
+    fn from(m: QueryMatch) -> Self {
+        let captures = m
+            .captures
+            .into_iter()
+            .map(|capture| {
+                let location = PyLocation::new(m.path.clone(), capture.range);
+                (capture.name, PyCapture { text: capture.text, location })
+            })
+            .collect();
+        Self { path: m.path, pattern: m.pattern, captures }
+    }
+}
+
+/// An indexed project directory
+#[pyclass(name = "Workspace", module = "logos", unsendable)]
+struct PyWorkspace {
+    inner: Workspace,
+}
+
+#[pymethods]
+impl PyWorkspace {
+    #[new]
+    fn new(root: PathBuf) -> PyResult<Self> {
+        Workspace::open(&root).map(|inner| Self { inner }).map_err(PyOSError::new_err)
+    }
+
+    #[getter]
+    fn root(&self) -> PathBuf {
+        self.inner.root().to_path_buf()
+    }
+
+    /// Indexing counters: files, symbols, imports and errors
+    #[getter]
+    fn stats(&self) -> HashMap<&'static str, usize> {
+        let stats = self.inner.stats();
+        HashMap::from([
+            ("files", stats.files_indexed),
+            ("symbols", stats.symbols_found),
+            ("imports", stats.imports_found),
+            ("errors", stats.errors.len()),
+        ])
+    }
+
+    fn files(&self) -> Vec<PathBuf> {
+        self.inner.files()
+    }
+
+    #[pyo3(signature = (kind=None))]
+    fn symbols(&self, kind: Option<&str>) -> Vec<PySymbol> {
+        self.inner.symbols(kind).into_iter().map(PySymbol::from).collect()
+    }
+
+    fn find(&self, query: &str) -> Vec<PySymbol> {
+        self.inner.find(query).into_iter().map(PySymbol::from).collect()
+    }
+
+    fn references(&self, name: &str) -> Vec<PyLocation> {
+        self.inner.references(name).into_iter().map(PyLocation::from).collect()
+    }
+
+    fn subtypes(&self, name: &str) -> Vec<PySymbol> {
+        self.inner.subtypes(name).into_iter().map(PySymbol::from).collect()
+    }
+
+    fn supertypes(&self, name: &str) -> Vec<PySymbol> {
+        self.inner.supertypes(name).into_iter().map(PySymbol::from).collect()
+    }
+
+    /// Run a tree-sitter query over every indexed file of `language`
+    fn query(&self, language: &str, pattern: &str) -> PyResult<Vec<PyMatch>> {
+        let matches = self.inner.query(language, pattern).map_err(PyValueError::new_err)?;
+        Ok(matches.into_iter().map(PyMatch::from).collect())
+    }
+}
+
+/// Documents analyzed in memory, as the editor's open files are
+#[pyclass(name = "Analysis", module = "logos", unsendable)]
+struct PyAnalysis {
+    host: AnalysisHost,
+}
+
+#[pymethods]
+impl PyAnalysis {
+    #[new]
+    fn new() -> Self {
+        Self { host: AnalysisHost::new() }
+    }
+
+    fn open_document(&mut self, uri: &str, language_id: &str, text: &str) {
+        self.host.open_document(uri, language_id, text);
+    }
+
+    fn change_document(&mut self, uri: &str, text: &str) {
+        self.host.change_document(uri, text);
+    }
+
+    fn close_document(&mut self, uri: &str) {
+        self.host.close_document(uri);
+    }
+
+    /// `(name, kind, line, column)` of each symbol in the document
+    fn document_symbols(&self, uri: &str) -> Vec<(String, String, u32, u32)> {
+        self.host
+            .snapshot()
+            .document_symbols(uri)
+            .iter()
+            .map(|s| {
+                let kind = serde_json::to_value(s.kind).ok().and_then(|v| v.as_str().map(str::to_string));
+                (s.name.clone(), kind.unwrap_or_default(), s.selection_range.start.line, s.selection_range.start.column)
+            })
+            .collect()
+    }
+
+    /// `(message, line, column)` of each syntax error in a data file and
+    /// each inactive cfg/ifdef region
+    fn diagnostics(&self, uri: &str) -> Vec<(String, u32, u32)> {
+        self.host
+            .snapshot()
+            .diagnostics(uri)
+            .into_iter()
+            .map(|d| (d.message, d.range.start.line, d.range.start.column))
+            .collect()
+    }
+}
+
+#[pymodule]
+fn logos(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyWorkspace>()?;
+    module.add_class::<PyAnalysis>()?;
+    module.add_class::<PySymbol>()?;
+    module.add_class::<PyLocation>()?;
+    module.add_class::<PyCapture>()?;
+    module.add_class::<PyMatch>()?;
+    Ok(())
+}
@@ -0,0 +1,279 @@
+//! Indexed workspace queries behind the Python `Workspace` class
+
+use std::path::{Path, PathBuf};
+
+use logos_core::uri::uri_to_path;
+use logos_core::{Range, SymbolKind};
+use logos_index::{IndexingStats, ProjectIndexer, SmartSymbol, SymbolLocation};
+use logos_parser::query::QueryEngine;
+use logos_parser::{LanguageId, LanguageParser};
+
+/// A symbol as handed to scripts: plain data, positions 0-based
+#[derive(Debug, Clone, PartialEq)]
+pub struct SymbolInfo {
+    pub name: String,
+    /// `SymbolKind` in camelCase (`function`, `enumMember`, ...)
+    pub kind: String,
+    pub qualified_name: String,
+    pub path: PathBuf,
+    pub range: Range,
+    pub selection_range: Range,
+    pub exported: bool,
+    pub documentation: Option<String>,
+}
+
+impl SymbolInfo {
+    fn from_symbol(symbol: &SmartSymbol) -> Self {
+        Self {
+            name: symbol.name.clone(),
+            kind: kind_name(symbol.kind),
+            qualified_name: symbol.qualified_name.clone(),
+            path: location_path(&symbol.location),
+            range: symbol.location.range,
+            selection_range: symbol.location.selection_range,
+            exported: symbol.exported,
+            documentation: symbol.documentation.clone(),
+        }
+    }
+}
+
+/// A location in a file, positions 0-based
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileLocation {
+    pub path: PathBuf,
+    pub range: Range,
+}
+
+impl From<&SymbolLocation> for FileLocation {
+    fn from(location: &SymbolLocation) -> Self {
+        Self { path: location_path(location), range: location.selection_range }
+    }
+}
+
+/// One match of a structural query
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryMatch {
+    pub path: PathBuf,
+    /// Index of the matching pattern within the query
+    pub pattern: usize,
+    pub captures: Vec<QueryCapture>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryCapture {
+    /// Capture name without the `@`
+    pub name: String,
+    pub text: String,
+    pub range: Range,
+}
+
+/// A project indexed the way Smart mode indexes it
+pub struct Workspace {
+    root: PathBuf,
+    indexer: ProjectIndexer,
+    stats: IndexingStats,
+}
+
+impl Workspace {
+    /// Index every supported file under `root`
+    pub fn open(root: &Path) -> Result<Self, String> {
+        let indexer = ProjectIndexer::new();
+        let stats = indexer.index_directory(root)?;
+        Ok(Self { root: root.to_path_buf(), indexer, stats })
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Stats of the indexing run
+    pub fn stats(&self) -> &IndexingStats {
+        &self.stats
+    }
+
+    /// Indexed files, sorted
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self
+            .indexer
+            .get_index()
+            .symbols
+            .files()
+            .iter()
+            .filter_map(|uri| uri_to_path(uri))
+            .collect();
+        files.sort();
+        files
+    }
+
+    /// Every symbol in location order, optionally only those of one kind
+    pub fn symbols(&self, kind: Option<&str>) -> Vec<SymbolInfo> {
+        let mut symbols = in_location_order(self.indexer.get_index().symbols.all());
+        if let Some(kind) = kind {
+            symbols.retain(|symbol| symbol.kind.eq_ignore_ascii_case(kind));
+        }
+        symbols
+    }
+
+    /// Symbols whose name matches `query`, as workspace symbol search does,
+    /// in location order
+    pub fn find(&self, query: &str) -> Vec<SymbolInfo> {
+        in_location_order(self.indexer.get_index().symbols.search(query))
+    }
+
+    /// References to symbols named `name`, definitions included
+    pub fn references(&self, name: &str) -> Vec<FileLocation> {
+        let index = self.indexer.get_index();
+        let mut locations: Vec<FileLocation> = index
+            .symbols
+            .find_by_name(name)
+            .iter()
+            .flat_map(|symbol| index.symbols.get_references(symbol.id))
+            .map(|reference| FileLocation::from(&reference.location))
+            .collect();
+        sort_locations(&mut locations);
+        locations
+    }
+
+    /// Direct subtypes and implementors of a type named `name`
+    pub fn subtypes(&self, name: &str) -> Vec<SymbolInfo> {
+        let index = self.indexer.get_index();
+        let hierarchy = &index.type_hierarchy;
+        let ids = index
+            .symbols
+            .find_by_name(name)
+            .iter()
+            .flat_map(|s| hierarchy.direct_subtypes(s.id))
+            .collect();
+        self.resolve(ids)
+    }
+
+    /// Direct supertypes and interfaces of a type named `name`
+    pub fn supertypes(&self, name: &str) -> Vec<SymbolInfo> {
+        let index = self.indexer.get_index();
+        let hierarchy = &index.type_hierarchy;
+        let ids = index
+            .symbols
+            .find_by_name(name)
+            .iter()
+            .flat_map(|s| hierarchy.direct_supertypes(s.id))
+            .collect();
+        self.resolve(ids)
+    }
+
+    /// Run a tree-sitter query over every indexed file of `language`
+    pub fn query(&self, language: &str, pattern: &str) -> Result<Vec<QueryMatch>, String> {
+        let lang = LanguageId::from_str(language).ok_or_else(|| format!("Unsupported language: {}", language))?;
+        let mut parser = LanguageParser::new();
+        parser.set_language(lang).map_err(|e| e.to_string())?;
+        // Compile up front so a bad pattern is reported even when no file
+        // matches the language
+        let mut engine = QueryEngine::new();
+        engine.compile(lang, pattern).map_err(|e| e.to_string())?;
+
+        let mut matches = Vec::new();
+        for path in self.files() {
+            if path.extension().and_then(|e| e.to_str()).and_then(LanguageId::from_extension) != Some(lang) {
+                continue;
+            }
+            let Ok(source) = std::fs::read_to_string(&path) else {
+                continue;
+            };
+            let tree = parser.parse(&source, None).map_err(|e| e.to_string())?;
+            for m in engine.matches(lang, pattern, &tree, &source, None).map_err(|e| e.to_string())? {
+                let captures = m
+                    .captures
+                    .iter()
+                    .map(|capture| QueryCapture {
+                        name: capture.name.clone(),
+                        text: capture.text(&source).to_string(),
+                        range: capture.range,
+                    })
+                    .collect();
+                matches.push(QueryMatch { path: path.clone(), pattern: m.pattern_index, captures });
+            }
+        }
+        Ok(matches)
+    }
+
+    fn resolve(&self, mut ids: Vec<logos_index::SymbolId>) -> Vec<SymbolInfo> {
+        ids.sort_by_key(|id| id.0);
+        ids.dedup();
+        let index = self.indexer.get_index();
+        in_location_order(ids.into_iter().filter_map(|id| index.symbols.get(id)).collect())
+    }
+}
+
+fn in_location_order(mut symbols: Vec<SmartSymbol>) -> Vec<SymbolInfo> {
+    symbols.sort_by(|a, b| a.location.cmp(&b.location).then_with(|| a.name.cmp(&b.name)));
+    symbols.iter().map(SymbolInfo::from_symbol).collect()
+}
+
+fn kind_name(kind: SymbolKind) -> String {
+    serde_json::to_value(kind)
+        .ok()
+        .and_then(|value| value.as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+fn location_path(location: &SymbolLocation) -> PathBuf {
+    uri_to_path(&location.uri).unwrap_or_else(|| PathBuf::from(&location.uri))
+}
+
+fn sort_locations(locations: &mut Vec<FileLocation>) {
+    locations.sort_by(|a, b| a.path.cmp(&b.path).then_with(|| a.range.cmp(&b.range)));
+    locations.dedup();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    fn sample() -> (tempfile::TempDir, Workspace) {
+        let dir = tempdir().unwrap();
+        fs::write(
+            dir.path().join("shapes.py"),
+            "class Shape:\n    pass\n\nclass Circle(Shape):\n    def area(self):\n        return helper()\n\ndef helper():\n    return 1\n",
+        )
+        .unwrap();
+        fs::write(dir.path().join("main.py"), "def main():\n    helper()\n").unwrap();
+        let workspace = Workspace::open(dir.path()).unwrap();
+        (dir, workspace)
+    }
+
+    #[test]
+    fn test_symbols_and_relations() {
+        let (_dir, workspace) = sample();
+        assert_eq!(workspace.files().len(), 2);
+
+        let classes: Vec<_> = workspace.symbols(Some("class")).into_iter().map(|s| s.name).collect();
+        assert_eq!(classes, vec!["Shape", "Circle"]);
+        let helper = &workspace.find("helper")[0];
+        assert_eq!(helper.kind, "function");
+        assert_eq!(helper.selection_range.start.line, 7);
+
+        let subtypes: Vec<_> = workspace.subtypes("Shape").into_iter().map(|s| s.name).collect();
+        assert_eq!(subtypes, vec!["Circle"]);
+        let supertypes: Vec<_> = workspace.supertypes("Circle").into_iter().map(|s| s.name).collect();
+        assert_eq!(supertypes, vec!["Shape"]);
+    }
+
+    #[test]
+    fn test_structural_query() {
+        let (_dir, workspace) = sample();
+        let matches = workspace
+            .query("python", "(call function: (identifier) @callee)")
+            .unwrap();
+        let callees: Vec<_> = matches
+            .iter()
+            .map(|m| (m.path.file_name().unwrap().to_str().unwrap(), m.captures[0].text.as_str()))
+            .collect();
+        assert_eq!(callees, vec![("main.py", "helper"), ("shapes.py", "helper")]);
+        assert_eq!(matches[0].captures[0].name, "callee");
+
+        assert!(workspace.query("python", "(no_such_node) @x").is_err());
+        assert!(workspace.query("go", "(function_declaration").is_err());
+        assert!(workspace.query("cobol", "(x)").is_err());
+    }
+}
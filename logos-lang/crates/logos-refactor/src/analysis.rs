@@ -89,7 +89,7 @@ pub fn is_valid_expression(text: &str, language: LanguageId) -> bool {
     }
 
     // Language-specific checks
-    match language {
+    match language.base() {
         LanguageId::Python => is_valid_python_expression(trimmed),
         LanguageId::JavaScript | LanguageId::TypeScript => is_valid_js_expression(trimmed),
         LanguageId::Rust => is_valid_rust_expression(trimmed),
@@ -163,21 +163,9 @@ pub fn has_balanced_delimiters(text: &str) -> bool {
                     string_char = ch;
                 }
                 '(' | '[' | '{' => stack.push(ch),
-                ')' => {
-                    if stack.pop() != Some('(') {
-                        return false;
-                    }
-                }
-                ']' => {
-                    if stack.pop() != Some('[') {
-                        return false;
-                    }
-                }
-                '}' => {
-                    if stack.pop() != Some('{') {
-                        return false;
-                    }
-                }
+                ')' if stack.pop() != Some('(') => return false,
+                ']' if stack.pop() != Some('[') => return false,
+                '}' if stack.pop() != Some('{') => return false,
                 _ => {}
             }
         }
@@ -192,7 +180,7 @@ pub fn find_variable_references(text: &str, language: LanguageId) -> HashSet<Str
     let mut variables = HashSet::new();
 
     // Basic identifier pattern (works for most languages)
-    let pattern = match language {
+    let pattern = match language.base() {
         LanguageId::Python => r"\b([a-zA-Z_][a-zA-Z0-9_]*)\b",
         LanguageId::Rust => r"\b([a-zA-Z_][a-zA-Z0-9_]*)\b",
         LanguageId::Go => r"\b([a-zA-Z_][a-zA-Z0-9_]*)\b",
@@ -216,7 +204,7 @@ pub fn find_variable_references(text: &str, language: LanguageId) -> HashSet<Str
 
 /// Get keywords for a language
 fn get_language_keywords(language: LanguageId) -> HashSet<&'static str> {
-    match language {
+    match language.base() {
         LanguageId::Python => [
             "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class",
             "continue", "def", "del", "elif", "else", "except", "finally", "for", "from",
@@ -253,13 +241,14 @@ fn get_language_keywords(language: LanguageId) -> HashSet<&'static str> {
         .iter()
         .cloned()
         .collect(),
+        LanguageId::Elixir => logos_parser::elixir::get_keywords().iter().cloned().collect(),
         _ => HashSet::new(),
     }
 }
 
 /// Check if a name is a builtin
 fn is_builtin(name: &str, language: LanguageId) -> bool {
-    match language {
+    match language.base() {
         LanguageId::Python => {
             matches!(
                 name,
@@ -334,8 +323,8 @@ pub fn suggest_variable_name(text: &str, language: LanguageId) -> String {
 }
 
 fn to_variable_case(name: &str, language: LanguageId) -> String {
-    match language {
-        LanguageId::Python | LanguageId::Rust => {
+    match language.base() {
+        LanguageId::Python | LanguageId::Rust | LanguageId::Elixir => {
             // snake_case
             let mut result = String::new();
             for (i, ch) in name.chars().enumerate() {
@@ -368,7 +357,7 @@ fn to_variable_case(name: &str, language: LanguageId) -> String {
 }
 
 fn default_name(language: LanguageId) -> String {
-    match language {
+    match language.base() {
         LanguageId::Python | LanguageId::Rust => "extracted".to_string(),
         _ => "extracted".to_string(),
     }
@@ -395,7 +384,7 @@ pub fn find_declaration_insertion_point(
         let line = lines[i].trim();
 
         // Check for statement starts
-        let is_statement_start = match language {
+        let is_statement_start = match language.base() {
             LanguageId::Python => {
                 line.starts_with("if ")
                     || line.starts_with("for ")
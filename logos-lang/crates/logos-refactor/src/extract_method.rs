@@ -55,6 +55,13 @@ pub fn can_extract(ctx: &RefactorContext) -> Result<bool, RefactorError> {
         return Err(RefactorError::NoExpression);
     }
 
+    if ctx.language.is_markup() {
+        return Err(RefactorError::CannotExtract(format!("{} has no functions", ctx.language.as_str())));
+    }
+    if let LanguageId::Custom(id) = ctx.language {
+        return Err(RefactorError::CannotExtract(format!("No refactorings for {}", id)));
+    }
+
     // Check for balanced delimiters
     if !has_balanced_delimiters(selected) {
         return Err(RefactorError::CannotExtract(
@@ -168,10 +175,10 @@ fn find_modified_variables(text: &str, language: LanguageId) -> HashSet<String>
     let mut modified = HashSet::new();
 
     // Pattern for assignments: identifier = something (but not ==, ===, etc.)
-    let pattern = match language {
-        LanguageId::Python => r"(\w+)\s*(?<![=!<>])=(?![=])",
-        LanguageId::Go => r"(\w+)\s*:?=",
-        _ => r"(\w+)\s*(?<![=!<>])=(?![=])",
+    // The regex crate has no look-around, so compound operators are matched explicitly.
+    let pattern = match language.base() {
+        LanguageId::Go => r"(\w+)\s*:?=(?:[^=]|$)",
+        _ => r"(\w+)\s*(?:[-+*/%&|^]|\*\*|//|<<|>>)?=(?:[^=]|$)",
     };
 
     if let Ok(re) = Regex::new(pattern) {
@@ -256,7 +263,7 @@ fn generate_method(
     let body_indent = format!("{}    ", base_indent);
     let indented_body = indent_code(body.trim(), &body_indent);
 
-    match language {
+    match language.base() {
         LanguageId::Python => {
             let mut code = format!("\n{}def {}({}):\n", base_indent, name, param_list);
             code.push_str(&indented_body);
@@ -336,7 +343,7 @@ fn generate_method(
             code.push_str(&format!("\n{}}}\n", base_indent));
             code
         }
-        LanguageId::Java => {
+        LanguageId::Java | LanguageId::CSharp => {
             let mut code = format!(
                 "\n{}private void {}({}) {{\n",
                 base_indent, name, param_list
@@ -345,6 +352,66 @@ fn generate_method(
             code.push_str(&format!("\n{}}}\n", base_indent));
             code
         }
+        LanguageId::Kotlin => {
+            let mut code = format!("\n{}private fun {}({}) {{\n", base_indent, name, param_list);
+            code.push_str(&indented_body);
+            if return_vars.len() == 1 && !has_explicit_return {
+                code.push_str(&format!("\n{}return {}", body_indent, return_vars[0]));
+            }
+            code.push_str(&format!("\n{}}}\n", base_indent));
+            code
+        }
+        LanguageId::Swift => {
+            let mut code = format!("\n{}private func {}({}) {{\n", base_indent, name, param_list);
+            code.push_str(&indented_body);
+            if return_vars.len() == 1 && !has_explicit_return {
+                code.push_str(&format!("\n{}return {}", body_indent, return_vars[0]));
+            }
+            code.push_str(&format!("\n{}}}\n", base_indent));
+            code
+        }
+        LanguageId::Bash => {
+            // Shell functions take positional arguments and return through stdout
+            let mut code = format!("\n{}{}() {{\n", base_indent, name);
+            for (i, param) in params.iter().enumerate() {
+                code.push_str(&format!("{}local {}=\"${}\"\n", body_indent, param, i + 1));
+            }
+            code.push_str(&indented_body);
+            if !return_vars.is_empty() && !has_explicit_return {
+                let values: Vec<String> = return_vars.iter().map(|v| format!("\"${}\"", v)).collect();
+                code.push_str(&format!("\n{}echo {}", body_indent, values.join(" ")));
+            }
+            code.push_str(&format!("\n{}}}\n", base_indent));
+            code
+        }
+        LanguageId::Dart => {
+            // Several results come back as a record
+            let mut code = format!("\n{}dynamic {}({}) {{\n", base_indent, name, param_list);
+            code.push_str(&indented_body);
+            if !return_vars.is_empty() && !has_explicit_return {
+                if return_vars.len() == 1 {
+                    code.push_str(&format!("\n{}return {};", body_indent, return_vars[0]));
+                } else {
+                    code.push_str(&format!("\n{}return ({});", body_indent, return_vars.join(", ")));
+                }
+            }
+            code.push_str(&format!("\n{}}}\n", base_indent));
+            code
+        }
+        LanguageId::Elixir => {
+            // Private to the module; the last expression is the return value
+            let mut code = format!("\n{}defp {}({}) do\n", base_indent, name, param_list);
+            code.push_str(&indented_body);
+            if !return_vars.is_empty() && !has_explicit_return {
+                if return_vars.len() == 1 {
+                    code.push_str(&format!("\n{}{}", body_indent, return_vars[0]));
+                } else {
+                    code.push_str(&format!("\n{}{{{}}}", body_indent, return_vars.join(", ")));
+                }
+            }
+            code.push_str(&format!("\n{}end\n", base_indent));
+            code
+        }
         _ => {
             format!(
                 "\n{}// Extracted method\n{}function {}({}) {{\n{}\n{}}}\n",
@@ -363,16 +430,20 @@ fn generate_call(
     _indent: &str,
 ) -> String {
     let param_list = params.join(", ");
-    let call = format!("{}({})", name, param_list);
+    let call = if language == LanguageId::Bash {
+        let args: Vec<String> = params.iter().map(|p| format!(" \"${}\"", p)).collect();
+        format!("{}{}", name, args.concat())
+    } else {
+        format!("{}({})", name, param_list)
+    };
 
     if return_vars.is_empty() {
-        match language {
-            LanguageId::Python => call,
-            LanguageId::Go => call,
+        match language.base() {
+            LanguageId::Python | LanguageId::Go | LanguageId::Kotlin | LanguageId::Swift | LanguageId::Bash | LanguageId::Elixir => call,
             _ => format!("{};", call),
         }
     } else {
-        match language {
+        match language.base() {
             LanguageId::Python => {
                 if return_vars.len() == 1 {
                     format!("{} = {}", return_vars[0], call)
@@ -383,6 +454,48 @@ fn generate_call(
             LanguageId::Go => {
                 format!("{} = {}", return_vars.join(", "), call)
             }
+            LanguageId::Kotlin => {
+                if return_vars.len() == 1 {
+                    format!("val {} = {}", return_vars[0], call)
+                } else {
+                    format!("val ({}) = {}", return_vars.join(", "), call)
+                }
+            }
+            LanguageId::Swift => {
+                if return_vars.len() == 1 {
+                    format!("let {} = {}", return_vars[0], call)
+                } else {
+                    format!("let ({}) = {}", return_vars.join(", "), call)
+                }
+            }
+            LanguageId::Bash => {
+                if return_vars.len() == 1 {
+                    format!("{}=$({})", return_vars[0], call)
+                } else {
+                    format!("read -r {} <<< \"$({})\"", return_vars.join(" "), call)
+                }
+            }
+            LanguageId::Dart => {
+                if return_vars.len() == 1 {
+                    format!("final {} = {};", return_vars[0], call)
+                } else {
+                    format!("final ({}) = {};", return_vars.join(", "), call)
+                }
+            }
+            LanguageId::Elixir => {
+                if return_vars.len() == 1 {
+                    format!("{} = {}", return_vars[0], call)
+                } else {
+                    format!("{{{}}} = {}", return_vars.join(", "), call)
+                }
+            }
+            LanguageId::CSharp => {
+                if return_vars.len() == 1 {
+                    format!("var {} = {};", return_vars[0], call)
+                } else {
+                    format!("var ({}) = {};", return_vars.join(", "), call)
+                }
+            }
             LanguageId::Rust => {
                 if return_vars.len() == 1 {
                     format!("let {} = {};", return_vars[0], call)
@@ -484,6 +597,16 @@ mod tests {
         assert!(modified.contains("z"));
     }
 
+    #[test]
+    fn test_find_modified_variables_skips_comparisons() {
+        let code = "if a == 1 or b != 2 or c <= 3: d **= 2";
+        let modified = find_modified_variables(code, LanguageId::Python);
+        assert_eq!(modified, HashSet::from(["d".to_string()]));
+
+        let modified = find_modified_variables("n := 1\nif m == n {}", LanguageId::Go);
+        assert_eq!(modified, HashSet::from(["n".to_string()]));
+    }
+
     #[test]
     fn test_analyze_parameters() {
         let source = "let x = 1;\nlet y = x + 2;\nconsole.log(y);";
@@ -22,6 +22,13 @@ pub fn can_extract(ctx: &RefactorContext) -> Result<bool, RefactorError> {
         return Err(RefactorError::NoExpression);
     }
 
+    if ctx.language.is_markup() {
+        return Err(RefactorError::CannotExtract(format!("{} has no variables", ctx.language.as_str())));
+    }
+    if let LanguageId::Custom(id) = ctx.language {
+        return Err(RefactorError::CannotExtract(format!("No refactorings for {}", id)));
+    }
+
     // Check if it's a valid expression
     if !is_valid_expression(selected, ctx.language) {
         return Err(RefactorError::CannotExtract(
@@ -117,7 +124,7 @@ pub fn extract(ctx: &RefactorContext, variable_name: &str) -> Result<RefactorRes
 
     // First, add replacements for all occurrences (in reverse order)
     let mut sorted_occurrences = occurrences.clone();
-    sorted_occurrences.sort_by(|a, b| b.start.cmp(&a.start));
+    sorted_occurrences.sort_by_key(|r| std::cmp::Reverse(r.start));
 
     for occurrence in sorted_occurrences {
         edits.push(TextEdit::replace(occurrence, variable_name.to_string()));
@@ -135,14 +142,14 @@ pub fn extract(ctx: &RefactorContext, variable_name: &str) -> Result<RefactorRes
 
 /// Generate a variable declaration statement
 fn generate_declaration(name: &str, value: &str, language: LanguageId, indent: &str) -> String {
-    match language {
+    match language.base() {
         LanguageId::Python => {
             format!("{}{} = {}\n", indent, name, value)
         }
-        LanguageId::JavaScript => {
+        LanguageId::JavaScript | LanguageId::JavaScriptReact => {
             format!("{}const {} = {};\n", indent, name, value)
         }
-        LanguageId::TypeScript => {
+        LanguageId::TypeScript | LanguageId::TypeScriptReact => {
             format!("{}const {} = {};\n", indent, name, value)
         }
         LanguageId::Rust => {
@@ -151,12 +158,29 @@ fn generate_declaration(name: &str, value: &str, language: LanguageId, indent: &
         LanguageId::Go => {
             format!("{}{} := {}\n", indent, name, value)
         }
-        LanguageId::Java => {
+        LanguageId::Java | LanguageId::CSharp => {
             format!("{}var {} = {};\n", indent, name, value)
         }
         LanguageId::C | LanguageId::Cpp => {
             format!("{}auto {} = {};\n", indent, name, value)
         }
+        LanguageId::Kotlin => {
+            format!("{}val {} = {}\n", indent, name, value)
+        }
+        LanguageId::Swift => {
+            format!("{}let {} = {}\n", indent, name, value)
+        }
+        LanguageId::Bash => {
+            format!("{}{}={}\n", indent, name, value)
+        }
+        LanguageId::Dart => {
+            format!("{}final {} = {};\n", indent, name, value)
+        }
+        LanguageId::Elixir => {
+            format!("{}{} = {}\n", indent, name, value)
+        }
+        LanguageId::Html | LanguageId::Css | LanguageId::Scss | LanguageId::Json | LanguageId::Yaml | LanguageId::Toml | LanguageId::Markdown | LanguageId::Sql | LanguageId::Dockerfile | LanguageId::Protobuf => unreachable!("markup is rejected by can_extract"),
+        LanguageId::Custom(_) => unreachable!("loaded grammars are rejected by can_extract"),
     }
 }
 
@@ -235,5 +259,9 @@ mod tests {
             generate_declaration("x", "1 + 2", LanguageId::Go, "\t"),
             "\tx := 1 + 2\n"
         );
+        assert_eq!(
+            generate_declaration("x", "1 + 2", LanguageId::TypeScriptReact, ""),
+            "const x = 1 + 2;\n"
+        );
     }
 }
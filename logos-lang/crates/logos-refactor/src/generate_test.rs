@@ -0,0 +1,529 @@
+//! Generate Test Skeleton
+//!
+//! Stub tests for a function, or for each public method of a class, in the
+//! test style of the language: pytest functions, Go `testing` functions,
+//! `describe`/`it` blocks, JUnit and `kotlin.test` classes and Rust
+//! `#[cfg(test)]` modules. Each stub quotes the signature it tests.
+
+use std::path::Path;
+
+use logos_core::{Position, Range, Symbol, SymbolKind};
+use logos_parser::LanguageId;
+use regex::Regex;
+use tree_sitter::{Node, Tree};
+
+use crate::{RefactorError, TextEdit};
+
+/// What to generate tests for
+pub struct SkeletonContext<'a> {
+    pub language: LanguageId,
+    pub source: &'a str,
+    pub tree: &'a Tree,
+    /// Symbols of the document, nested
+    pub symbols: &'a [Symbol],
+    /// Function, method or class to test
+    pub target: &'a Symbol,
+    pub source_path: &'a Path,
+    pub test_path: &'a Path,
+}
+
+/// A function or method to write a stub test for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestedMember {
+    pub name: String,
+    /// Class or type the member belongs to
+    pub owner: Option<String>,
+    /// First line of the declaration, without the body opener
+    pub signature: String,
+}
+
+/// Block the stubs are nested in, such as a test class
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Container {
+    open: String,
+    close: String,
+    /// Text identifying the block in an existing test file
+    marker: String,
+}
+
+/// Stub tests and the surrounding code they need
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestSkeleton {
+    /// Package declaration and framework imports of a new test file
+    preamble: String,
+    /// Imports of the code under test, added to existing files as well
+    imports: Vec<String>,
+    container: Option<Container>,
+    /// One stub per member, each starting with its declaration line
+    tests: Vec<String>,
+    /// Blank lines between stubs
+    separator: &'static str,
+}
+
+impl TestSkeleton {
+    /// Number of stub tests
+    pub fn len(&self) -> usize {
+        self.tests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tests.is_empty()
+    }
+
+    /// Content of a new test file
+    pub fn render(&self) -> String {
+        let mut out = self.preamble.clone();
+        if !self.imports.is_empty() {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&self.imports.join("\n"));
+            out.push('\n');
+        }
+        if !out.is_empty() {
+            out.push_str(self.separator);
+        }
+        out.push_str(&self.body(&self.tests));
+        out
+    }
+
+    /// Edits adding the stubs to the test file, given its current content
+    /// (`None` when it doesn't exist yet). Stubs whose declaration is
+    /// already in the file are skipped.
+    pub fn edits(&self, existing: Option<&str>) -> Vec<TextEdit> {
+        let Some(existing) = existing else {
+            return vec![TextEdit::insert(Position::new(0, 0), self.render())];
+        };
+        let tests: Vec<String> = self
+            .tests
+            .iter()
+            .filter(|test| !existing.contains(declaration(test)))
+            .cloned()
+            .collect();
+        if tests.is_empty() {
+            return Vec::new();
+        }
+
+        let mut edits = Vec::new();
+        let missing: Vec<&String> = self.imports.iter().filter(|import| !existing.contains(import.as_str())).collect();
+        if !missing.is_empty() {
+            let line = existing
+                .lines()
+                .enumerate()
+                .filter(|(_, line)| ["import ", "from ", "use ", "package "].iter().any(|k| line.starts_with(k)))
+                .map(|(i, _)| i as u32 + 1)
+                .last()
+                .unwrap_or(0);
+            let text: String = missing.iter().map(|import| format!("{}\n", import)).collect();
+            edits.push(TextEdit::insert(Position::new(line, 0), text));
+        }
+
+        let end = position_at(existing, existing.len());
+        match &self.container {
+            Some(container) if existing.contains(&container.marker) => {
+                let close = existing.rfind(container.close.trim()).unwrap_or(existing.len());
+                let close = existing[..close].rfind('\n').map_or(close, |newline| newline + 1);
+                let text = format!("\n{}", tests.join(self.separator));
+                edits.push(TextEdit::insert(position_at(existing, close), text));
+            }
+            _ => {
+                let lead = if existing.ends_with('\n') || existing.is_empty() { "" } else { "\n" };
+                edits.push(TextEdit::insert(end, format!("{}{}{}", lead, self.separator, self.body(&tests))));
+            }
+        }
+        edits
+    }
+
+    fn body(&self, tests: &[String]) -> String {
+        let tests = tests.join(self.separator);
+        match &self.container {
+            Some(container) => format!("{}{}{}", container.open, tests, container.close),
+            None => tests,
+        }
+    }
+}
+
+/// Stub tests for `ctx.target`: the target itself when it is a function or
+/// method, or its public methods when it is a class
+pub fn skeleton(ctx: &SkeletonContext) -> Result<TestSkeleton, RefactorError> {
+    let members = members(ctx);
+    if members.is_empty() {
+        return Err(RefactorError::CannotExtract(format!("{} has no public methods to test", ctx.target.name)));
+    }
+    let subject = ctx.target.name.as_str();
+    let stem = ctx.source_path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+
+    let skeleton = match ctx.language.base() {
+        LanguageId::Python => TestSkeleton {
+            preamble: String::new(),
+            imports: vec![format!("from {} import {}", stem, subject)],
+            container: None,
+            tests: members
+                .iter()
+                .map(|m| {
+                    format!(
+                        "def test_{}():\n    # {}\n    raise NotImplementedError\n",
+                        test_name(m, "_", snake_case),
+                        m.signature
+                    )
+                })
+                .collect(),
+            separator: "\n\n",
+        },
+        LanguageId::Go => TestSkeleton {
+            preamble: format!("package {}\n\nimport \"testing\"\n", go_package(ctx.source).unwrap_or("main")),
+            imports: Vec::new(),
+            container: None,
+            tests: members
+                .iter()
+                .map(|m| {
+                    format!(
+                        "func Test{}(t *testing.T) {{\n\t// {}\n\tt.Skip(\"not implemented\")\n}}\n",
+                        test_name(m, "_", str::to_string),
+                        m.signature
+                    )
+                })
+                .collect(),
+            separator: "\n",
+        },
+        LanguageId::JavaScript | LanguageId::TypeScript => TestSkeleton {
+            preamble: String::new(),
+            imports: vec![format!("import {{ {} }} from '{}';", subject, import_path(ctx.source_path, ctx.test_path))],
+            container: Some(Container {
+                open: format!("describe('{}', () => {{\n", subject),
+                close: "});\n".to_string(),
+                marker: format!("describe('{}'", subject),
+            }),
+            tests: members
+                .iter()
+                .map(|m| {
+                    format!(
+                        "  it('{}', () => {{\n    // {}\n    throw new Error('not implemented');\n  }});\n",
+                        m.name, m.signature
+                    )
+                })
+                .collect(),
+            separator: "\n",
+        },
+        LanguageId::Java => TestSkeleton {
+            preamble: format!(
+                "{}import org.junit.jupiter.api.Test;\n\nimport static org.junit.jupiter.api.Assertions.fail;\n",
+                package_line(ctx.source, ";")
+            ),
+            imports: Vec::new(),
+            container: Some(test_class(subject, "class {}Test {\n")),
+            tests: members
+                .iter()
+                .map(|m| {
+                    format!(
+                        "    @Test\n    void {}() {{\n        // {}\n        fail(\"not implemented\");\n    }}\n",
+                        m.name, m.signature
+                    )
+                })
+                .collect(),
+            separator: "\n",
+        },
+        LanguageId::Kotlin => TestSkeleton {
+            preamble: format!("{}import kotlin.test.Test\nimport kotlin.test.fail\n", package_line(ctx.source, "")),
+            imports: Vec::new(),
+            container: Some(test_class(subject, "class {}Test {\n")),
+            tests: members
+                .iter()
+                .map(|m| {
+                    format!(
+                        "    @Test\n    fun {}() {{\n        // {}\n        fail(\"not implemented\")\n    }}\n",
+                        m.name, m.signature
+                    )
+                })
+                .collect(),
+            separator: "\n",
+        },
+        LanguageId::Rust => TestSkeleton {
+            preamble: String::new(),
+            imports: Vec::new(),
+            container: Some(Container {
+                open: "#[cfg(test)]\nmod tests {\n    use super::*;\n\n".to_string(),
+                close: "}\n".to_string(),
+                marker: "mod tests".to_string(),
+            }),
+            tests: members
+                .iter()
+                .map(|m| {
+                    format!(
+                        "    #[test]\n    fn {}() {{\n        // {}\n        todo!();\n    }}\n",
+                        test_name(m, "_", snake_case),
+                        m.signature
+                    )
+                })
+                .collect(),
+            separator: "\n",
+        },
+        other => {
+            return Err(RefactorError::CannotExtract(format!(
+                "No test skeleton for {} yet",
+                other.as_str()
+            )));
+        }
+    };
+    Ok(skeleton)
+}
+
+/// Declaration line of a stub, after its attributes or annotations
+fn declaration(test: &str) -> &str {
+    test.lines()
+        .map(str::trim)
+        .find(|line| !line.starts_with('@') && !line.starts_with("#["))
+        .unwrap_or_default()
+}
+
+/// Functions and methods to write stubs for
+pub fn members(ctx: &SkeletonContext) -> Vec<TestedMember> {
+    let target = ctx.target;
+    if matches!(target.kind, SymbolKind::Function | SymbolKind::Method) {
+        return vec![TestedMember {
+            name: target.name.clone(),
+            owner: None,
+            signature: signature(ctx.source, target.range, target.selection_range),
+        }];
+    }
+
+    let owner = Some(target.name.clone());
+    match ctx.language.base() {
+        // Methods live in `impl` blocks, outside the type
+        LanguageId::Rust => {
+            let mut members = Vec::new();
+            collect_impl_methods(ctx.tree.root_node(), ctx.source, &target.name, &mut members);
+            members
+        }
+        // Methods are top-level functions with a receiver
+        LanguageId::Go => {
+            let receiver = Regex::new(&format!(r"^func\s*\(\s*\w*\s*\*?{}\b", regex::escape(&target.name))).ok();
+            ctx.symbols
+                .iter()
+                .filter(|s| matches!(s.kind, SymbolKind::Method | SymbolKind::Function))
+                .map(|s| (s, signature(ctx.source, s.range, s.selection_range)))
+                .filter(|(s, sig)| {
+                    receiver.as_ref().is_some_and(|r| r.is_match(sig)) && is_public(ctx.language, &s.name, sig)
+                })
+                .map(|(s, signature)| TestedMember { name: s.name.clone(), owner: owner.clone(), signature })
+                .collect()
+        }
+        _ => target
+            .children
+            .iter()
+            .filter(|s| matches!(s.kind, SymbolKind::Method | SymbolKind::Function))
+            .map(|s| (s, signature(ctx.source, s.range, s.selection_range)))
+            .filter(|(s, sig)| is_public(ctx.language, &s.name, sig))
+            .map(|(s, signature)| TestedMember { name: s.name.clone(), owner: owner.clone(), signature })
+            .collect(),
+    }
+}
+
+fn collect_impl_methods(node: Node, source: &str, type_name: &str, members: &mut Vec<TestedMember>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        if child.kind() != "impl_item" {
+            collect_impl_methods(child, source, type_name, members);
+            continue;
+        }
+        let Some(ty) = child.child_by_field_name("type") else {
+            continue;
+        };
+        let ty = &source[ty.byte_range()];
+        if ty.split('<').next().map(str::trim) != Some(type_name) {
+            continue;
+        }
+        let Some(body) = child.child_by_field_name("body") else {
+            continue;
+        };
+        // Trait impls implement a public interface even without `pub`
+        let in_trait_impl = child.child_by_field_name("trait").is_some();
+        let mut body_cursor = body.walk();
+        for item in body.named_children(&mut body_cursor) {
+            let (Some(name), Some(block)) = (item.child_by_field_name("name"), item.child_by_field_name("body")) else {
+                continue;
+            };
+            if item.kind() != "function_item" {
+                continue;
+            }
+            let signature = source[item.start_byte()..block.start_byte()].trim().to_string();
+            if in_trait_impl || signature.starts_with("pub") {
+                members.push(TestedMember {
+                    name: source[name.byte_range()].to_string(),
+                    owner: Some(type_name.to_string()),
+                    signature,
+                });
+            }
+        }
+    }
+}
+
+/// Whether a member is part of the public surface of its class
+fn is_public(language: LanguageId, name: &str, signature: &str) -> bool {
+    let has_modifier = |modifier: &str| signature.split_whitespace().any(|word| word == modifier);
+    match language.base() {
+        LanguageId::Python => !name.starts_with('_'),
+        LanguageId::Go => name.chars().next().is_some_and(char::is_uppercase),
+        LanguageId::Rust => signature.starts_with("pub"),
+        LanguageId::JavaScript | LanguageId::TypeScript => {
+            !name.starts_with(['_', '#']) && name != "constructor" && !has_modifier("private") && !has_modifier("protected")
+        }
+        _ => !has_modifier("private") && !has_modifier("protected"),
+    }
+}
+
+/// Declaration of a symbol up to the end of the line with its name, without
+/// a trailing body opener
+fn signature(source: &str, range: Range, selection: Range) -> String {
+    let start = offset_of(source, range.start);
+    let line_end = offset_of(source, selection.end);
+    let end = source[line_end..].find('\n').map_or(source.len(), |i| line_end + i);
+    let text = source.get(start..end).unwrap_or_default().trim();
+    text.trim_end_matches(['{', ':', '=', ' ']).trim_end().to_string()
+}
+
+/// Byte offset of a position whose column counts bytes, as tree ranges do
+fn offset_of(source: &str, position: Position) -> usize {
+    let line_start: usize = source.split_inclusive('\n').take(position.line as usize).map(str::len).sum();
+    (line_start + position.column as usize).min(source.len())
+}
+
+/// Position of a byte offset, with the column in UTF-16 units
+fn position_at(text: &str, offset: usize) -> Position {
+    let before = &text[..offset];
+    let line = before.matches('\n').count() as u32;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let column = before[line_start..].chars().map(char::len_utf16).sum::<usize>() as u32;
+    Position::new(line, column)
+}
+
+fn test_name(member: &TestedMember, separator: &str, case: fn(&str) -> String) -> String {
+    match &member.owner {
+        Some(owner) => format!("{}{}{}", case(owner), separator, case(&member.name)),
+        None => case(&member.name),
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 && !out.ends_with('_') {
+                out.push('_');
+            }
+            out.extend(c.to_lowercase());
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn test_class(subject: &str, open: &str) -> Container {
+    let open = open.replace("{}", subject);
+    Container {
+        marker: open.trim_end_matches(['{', '\n', ' ']).to_string(),
+        open,
+        close: "}\n".to_string(),
+    }
+}
+
+fn go_package(source: &str) -> Option<&str> {
+    source.lines().find_map(|line| line.strip_prefix("package ")).map(str::trim)
+}
+
+/// `package` line of the source followed by a blank line, or nothing
+fn package_line(source: &str, terminator: &str) -> String {
+    source
+        .lines()
+        .find(|line| line.starts_with("package "))
+        .map(|line| format!("{}{}\n\n", line.trim_end().trim_end_matches(';'), terminator))
+        .unwrap_or_default()
+}
+
+/// Module specifier of `source_path` relative to `test_path`, without the
+/// extension
+fn import_path(source_path: &Path, test_path: &Path) -> String {
+    let stem = source_path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+    let (Some(from), Some(to)) = (test_path.parent(), source_path.parent()) else {
+        return format!("./{}", stem);
+    };
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<String> = vec!["..".to_string(); from.len() - common];
+    if parts.is_empty() {
+        parts.push(".".to_string());
+    }
+    parts.extend(to[common..].iter().map(|c| c.as_os_str().to_string_lossy().into_owned()));
+    parts.push(stem.to_string());
+    parts.join("/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logos_parser::LanguageParser;
+
+    fn generate(language: LanguageId, source: &str, target: &str, source_path: &str, test_path: &str) -> TestSkeleton {
+        let mut parser = LanguageParser::new();
+        parser.set_language(language).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let symbols = logos_parser::extract_symbols(language, &tree, source);
+        let target = symbols.iter().find(|s| s.name == target).unwrap();
+        let ctx = SkeletonContext {
+            language,
+            source,
+            tree: &tree,
+            symbols: &symbols,
+            target,
+            source_path: Path::new(source_path),
+            test_path: Path::new(test_path),
+        };
+        skeleton(&ctx).unwrap()
+    }
+
+    #[test]
+    fn test_python_class_new_and_existing_file() {
+        let source = "class Greeter:\n    def greet(self, name):\n        pass\n\n    def _helper(self):\n        pass\n\n    def wave(self):\n        pass\n";
+        let skeleton = generate(LanguageId::Python, source, "Greeter", "/p/greeter.py", "/p/test_greeter.py");
+        assert_eq!(skeleton.len(), 2);
+        assert_eq!(
+            skeleton.render(),
+            "from greeter import Greeter\n\n\ndef test_greeter_greet():\n    # def greet(self, name)\n    raise NotImplementedError\n\n\ndef test_greeter_wave():\n    # def wave(self)\n    raise NotImplementedError\n"
+        );
+
+        let existing = "import pytest\n\ndef test_greeter_greet():\n    pass\n";
+        let edits = skeleton.edits(Some(existing));
+        assert_eq!(edits.len(), 2);
+        assert_eq!(edits[0].range.start, Position::new(1, 0));
+        assert_eq!(edits[0].new_text, "from greeter import Greeter\n");
+        assert_eq!(edits[1].range.start, Position::new(4, 0));
+        assert!(edits[1].new_text.starts_with("\n\ndef test_greeter_wave():"));
+        assert!(!edits[1].new_text.contains("test_greeter_greet"));
+    }
+
+    #[test]
+    fn test_rust_impl_methods_into_existing_module() {
+        let source = "pub struct Stack;\n\nimpl Stack {\n    pub fn push(&mut self, x: i32) {}\n    fn grow(&mut self) {}\n}\n\n#[cfg(test)]\nmod tests {\n    use super::*;\n}\n";
+        let skeleton = generate(LanguageId::Rust, source, "Stack", "/p/stack.rs", "/p/stack.rs");
+        assert_eq!(skeleton.len(), 1);
+        let edits = skeleton.edits(Some(source));
+        assert_eq!(edits.len(), 1);
+        assert_eq!(edits[0].range.start, Position::new(10, 0));
+        assert_eq!(
+            edits[0].new_text,
+            "\n    #[test]\n    fn stack_push() {\n        // pub fn push(&mut self, x: i32)\n        todo!();\n    }\n"
+        );
+    }
+
+    #[test]
+    fn test_typescript_function_import_path() {
+        let source = "export function add(a: number, b: number): number {\n  return a + b;\n}\n";
+        let skeleton = generate(LanguageId::TypeScript, source, "add", "/p/src/math.ts", "/p/src/__tests__/math.test.ts");
+        assert_eq!(
+            skeleton.render(),
+            "import { add } from '../math';\n\ndescribe('add', () => {\n  it('add', () => {\n    // function add(a: number, b: number): number\n    throw new Error('not implemented');\n  });\n});\n"
+        );
+    }
+}
@@ -4,10 +4,12 @@
 //! - Extract Variable: Extract a selected expression into a new variable
 //! - Extract Method: Extract selected code into a new function/method
 //! - Safe Delete: Safely delete symbols that are not used elsewhere
+//! - Generate Test: Stub tests for a function or the public methods of a class
 
 pub mod analysis;
 pub mod extract_method;
 pub mod extract_variable;
+pub mod generate_test;
 pub mod safe_delete;
 
 use logos_core::{Location, Position, Range};
@@ -120,13 +120,18 @@ fn extract_symbol_name(text: &str) -> String {
 
 /// Find all usages of a symbol by name
 fn find_usages(ctx: &RefactorContext, name: &str) -> Vec<Location> {
+    find_usages_in(ctx.uri, ctx.source, name)
+}
+
+/// Find the whole-word occurrences of `name` in the document `uri` with
+/// text `source`, e.g. to look for usages in the other files of a
+/// workspace
+pub fn find_usages_in(uri: &str, source: &str, name: &str) -> Vec<Location> {
     let mut usages = Vec::new();
     let pattern = format!(r"\b{}\b", regex::escape(name));
 
     if let Ok(re) = Regex::new(&pattern) {
-        let lines: Vec<&str> = ctx.source.lines().collect();
-
-        for (line_num, line) in lines.iter().enumerate() {
+        for (line_num, line) in source.lines().enumerate() {
             for m in re.find_iter(line) {
                 let range = Range::from_coords(
                     line_num as u32,
@@ -134,7 +139,7 @@ fn find_usages(ctx: &RefactorContext, name: &str) -> Vec<Location> {
                     line_num as u32,
                     m.end() as u32,
                 );
-                usages.push(Location::new(ctx.uri.to_string(), range));
+                usages.push(Location::new(uri.to_string(), range));
             }
         }
     }
@@ -266,6 +271,19 @@ mod tests {
         assert_eq!(usages.len(), 3); // declaration + 2 uses
     }
 
+    #[test]
+    fn test_find_usages_in_other_file() {
+        let usages = find_usages_in("file:///b.js", "import { foo } from './a';\nfoobar(foo);", "foo");
+        let ranges: Vec<_> = usages.iter().map(|loc| (loc.uri.as_str(), loc.range)).collect();
+        assert_eq!(
+            ranges,
+            vec![
+                ("file:///b.js", Range::from_coords(0, 9, 0, 12)),
+                ("file:///b.js", Range::from_coords(1, 7, 1, 10)),
+            ]
+        );
+    }
+
     #[test]
     fn test_analyze_unused() {
         let source = "function unused() {}\nfunction used() {}\nused();";
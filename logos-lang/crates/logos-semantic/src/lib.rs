@@ -1,11 +1,13 @@
 //! Logos Semantic - Semantic analysis for the language service
 
+pub mod metrics;
 pub mod resolver;
 pub mod scope;
 pub mod type_check;
 pub mod type_infer;
 pub mod unused;
 
+pub use metrics::{function_metrics, FunctionMetrics};
 pub use type_check::{TypeCheckConfig, TypeCheckError, TypeCheckErrorKind, TypeChecker};
 pub use type_infer::{LiteralType, Type, TypeContext, TypeError};
 pub use unused::{UnusedDetector, UnusedItem, UnusedKind};
@@ -45,9 +47,11 @@ impl SemanticAnalyzer {
     }
 
     pub fn analyze(&self, symbols: &[Symbol], source: &str) -> SemanticInfo {
-        let mut info = SemanticInfo::default();
-        info.scope_tree = scope::ScopeTree::from_symbols(symbols);
-        info.symbols = symbols.to_vec();
+        let mut info = SemanticInfo {
+            scope_tree: scope::ScopeTree::from_symbols(symbols),
+            symbols: symbols.to_vec(),
+            ..Default::default()
+        };
         self.check_duplicates(&info.symbols, &mut info.diagnostics);
 
         // Detect unused code
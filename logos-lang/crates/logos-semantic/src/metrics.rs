@@ -0,0 +1,122 @@
+//! Function metrics: cyclomatic complexity and length
+//!
+//! Complexity is counted on the syntax tree with grammar-independent node
+//! kinds, so it approximates McCabe's number for every supported language:
+//! one plus the branches, loops, match arms, catch clauses, ternaries and
+//! short-circuit operators of the function body. Nested functions are
+//! measured on their own and don't add to the enclosing function.
+
+use logos_core::{Range, Symbol, SymbolKind};
+use serde::{Deserialize, Serialize};
+use logos_parser::Utf16Columns;
+use tree_sitter::{Node, Tree};
+
+/// Metrics of one function, method or constructor
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionMetrics {
+    pub name: String,
+    pub kind: SymbolKind,
+    pub range: Range,
+    pub selection_range: Range,
+    pub complexity: u32,
+    /// Lines spanned by the function, declaration included
+    pub lines: u32,
+}
+
+/// Node kinds that open a branch, across the grammars
+const DECISION_KINDS: &[&str] = &[
+    "if_statement", "if_expression", "elif_clause", "else_if_clause", "if_let_expression",
+    "guard_statement", "conditional_expression", "ternary_expression",
+    "for_statement", "for_in_statement", "for_expression", "enhanced_for_statement",
+    "foreach_statement", "range_for_statement", "while_statement", "while_expression",
+    "do_statement", "do_while_statement", "repeat_while_statement",
+    "case_clause", "switch_case", "case_statement", "expression_case", "type_case",
+    "switch_block_statement_group", "switch_rule", "switch_section", "match_arm", "when_entry",
+    "catch_clause", "except_clause", "rescue_clause",
+];
+
+/// Short-circuit operator tokens
+const BOOLEAN_OPERATORS: &[&str] = &["&&", "||", "and", "or", "??"];
+
+/// Node kinds that define a function, whose body is measured separately
+const FUNCTION_KINDS: &[&str] = &[
+    "function_definition", "function_declaration", "function_item", "method_declaration",
+    "method_definition", "constructor_declaration", "func_literal",
+];
+
+/// Metrics of the functions, methods and constructors among `symbols`
+/// (children included) of `source`, in document order
+pub fn function_metrics(tree: &Tree, source: &str, symbols: &[Symbol]) -> Vec<FunctionMetrics> {
+    let mut metrics = Vec::new();
+    collect(tree, &Utf16Columns::new(source), symbols, &mut metrics);
+    metrics
+}
+
+fn collect(tree: &Tree, columns: &Utf16Columns, symbols: &[Symbol], metrics: &mut Vec<FunctionMetrics>) {
+    for symbol in symbols {
+        if matches!(symbol.kind, SymbolKind::Function | SymbolKind::Method | SymbolKind::Constructor) {
+            if let Some(node) = node_for_range(tree, columns, &symbol.range) {
+                metrics.push(FunctionMetrics {
+                    name: symbol.name.clone(),
+                    kind: symbol.kind,
+                    range: symbol.range,
+                    selection_range: symbol.selection_range,
+                    complexity: 1 + decision_points(&node, true),
+                    lines: symbol.range.end.line - symbol.range.start.line + 1,
+                });
+            }
+        }
+        collect(tree, columns, &symbol.children, metrics);
+    }
+}
+
+fn node_for_range<'t>(tree: &'t Tree, columns: &Utf16Columns, range: &Range) -> Option<Node<'t>> {
+    tree.root_node().descendant_for_point_range(columns.point(range.start), columns.point(range.end))
+}
+
+fn decision_points(node: &Node, is_root: bool) -> u32 {
+    if !is_root && FUNCTION_KINDS.contains(&node.kind()) {
+        return 0;
+    }
+    let own = if node.is_named() {
+        DECISION_KINDS.contains(&node.kind()) as u32
+    } else {
+        BOOLEAN_OPERATORS.contains(&node.kind()) as u32
+    };
+    let mut cursor = node.walk();
+    let children: u32 = node.children(&mut cursor).map(|child| decision_points(&child, false)).sum();
+    own + children
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use logos_parser::{LanguageId, LanguageParser};
+
+    fn metrics(lang: LanguageId, source: &str) -> Vec<FunctionMetrics> {
+        let mut parser = LanguageParser::new();
+        parser.set_language(lang).unwrap();
+        let tree = parser.parse(source, None).unwrap();
+        let symbols = logos_parser::extract_symbols(lang, &tree, source);
+        function_metrics(&tree, source, &symbols)
+    }
+
+    #[test]
+    fn test_python_branches() {
+        let source = "def simple():\n    return 1\n\ndef busy(x, items):\n    if x > 0 and x < 10:\n        return 1\n    elif x < 0:\n        return 2\n    for item in items:\n        try:\n            pass\n        except ValueError:\n            pass\n    return 3 if x else 4\n";
+        let result = metrics(LanguageId::Python, source);
+        let summary: Vec<_> = result.iter().map(|m| (m.name.as_str(), m.complexity, m.lines)).collect();
+        // busy: if, and, elif, for, except, conditional
+        assert_eq!(summary, vec![("simple", 1, 2), ("busy", 7, 11)]);
+    }
+
+    #[test]
+    fn test_nested_functions_are_measured_alone() {
+        let source = "fn outer(v: Option<u8>) -> u8 {\n    fn inner(a: bool) -> u8 { if a { 1 } else { 0 } }\n    match v {\n        Some(x) if x > 1 || x == 0 => x,\n        _ => inner(true),\n    }\n}\n";
+        let result = metrics(LanguageId::Rust, source);
+        let summary: Vec<_> = result.iter().map(|m| (m.name.as_str(), m.complexity)).collect();
+        // outer: two match arms and `||`
+        assert_eq!(summary, vec![("outer", 4)]);
+    }
+}
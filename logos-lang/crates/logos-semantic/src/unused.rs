@@ -197,16 +197,17 @@ impl UnusedDetector {
     fn collect_references(&mut self, source: &str) {
         // Simple word-based reference detection
         // A more accurate approach would use the AST
+        let mut counts: HashMap<&str, usize> = HashMap::new();
         for word in source.split(|c: char| !c.is_alphanumeric() && c != '_') {
             if !word.is_empty() && !self.should_ignore(word) {
-                // Check if this word is a defined symbol
-                if self.defined_symbols.contains_key(word) {
-                    // Count occurrences - if more than 1, it's used
-                    let count = source.matches(word).count();
-                    if count > 1 {
-                        self.mark_used(word);
-                    }
-                }
+                *counts.entry(word).or_default() += 1;
+            }
+        }
+        for (word, count) in counts {
+            // Check if this word is a defined symbol
+            // Count whole-word occurrences - if more than 1, it's used
+            if count > 1 && self.defined_symbols.contains_key(word) {
+                self.mark_used(word);
             }
         }
     }
@@ -238,10 +239,11 @@ impl UnusedDetector {
                 });
             }
         }
-        // Sort by range position
+        // Sort by range position, then name
         unused.sort_by(|a, b| {
             a.range.start.line.cmp(&b.range.start.line)
                 .then_with(|| a.range.start.column.cmp(&b.range.start.column))
+                .then_with(|| a.name.cmp(&b.name))
         });
         unused
     }
@@ -273,6 +275,7 @@ mod tests {
                 end: Position { line, column: name.len() as u32 },
             },
             detail: None,
+            documentation: None,
             children: Vec::new(),
         }
     }
@@ -317,4 +320,15 @@ mod tests {
         let unused = detector.analyze(&symbols, source);
         assert!(unused.is_empty());
     }
+
+    #[test]
+    fn test_substring_is_not_a_reference() {
+        let mut detector = UnusedDetector::new();
+        let symbols = vec![make_symbol("count", SymbolKind::Variable, 0)];
+        let source = "let count = 1;\nprint(counter, account);";
+
+        let unused = detector.analyze(&symbols, source);
+        assert_eq!(unused.len(), 1);
+        assert_eq!(unused[0].name, "count");
+    }
 }